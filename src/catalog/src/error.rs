@@ -21,7 +21,7 @@ use datafusion::error::DataFusionError;
 use datatypes::prelude::ConcreteDataType;
 use snafu::Location;
 
-use crate::DeregisterTableRequest;
+use crate::{DeregisterTableRequest, DeregisterViewRequest};
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub))]
@@ -105,6 +105,9 @@ pub enum Error {
     #[snafu(display("Table `{}` already exists", table))]
     TableExists { table: String, location: Location },
 
+    #[snafu(display("View `{}` already exists", view))]
+    ViewExists { view: String, location: Location },
+
     #[snafu(display("Table `{}` not exist", table))]
     TableNotExist { table: String, location: Location },
 
@@ -165,6 +168,17 @@ pub enum Error {
         source: table::error::Error,
     },
 
+    #[snafu(display(
+        "Failed to deregister view, request: {:?}, source: {}",
+        request,
+        source
+    ))]
+    DeregisterView {
+        request: DeregisterViewRequest,
+        #[snafu(backtrace)]
+        source: table::error::Error,
+    },
+
     #[snafu(display("Illegal catalog manager state: {}", msg))]
     IllegalManagerState { location: Location, msg: String },
 
@@ -276,7 +290,7 @@ impl ErrorExt for Error {
                 source.status_code()
             }
 
-            Error::TableExists { .. } => StatusCode::TableAlreadyExists,
+            Error::TableExists { .. } | Error::ViewExists { .. } => StatusCode::TableAlreadyExists,
             Error::TableNotExist { .. } => StatusCode::TableNotFound,
             Error::SchemaExists { .. } | Error::TableEngineNotFound { .. } => {
                 StatusCode::InvalidArguments
@@ -288,6 +302,7 @@ impl ErrorExt for Error {
             | Error::OpenTable { source, .. }
             | Error::CreateTable { source, .. }
             | Error::DeregisterTable { source, .. }
+            | Error::DeregisterView { source, .. }
             | Error::RegionStats { source, .. }
             | Error::TableSchemaMismatch { source } => source.status_code(),
 
@@ -28,6 +28,7 @@ pub const CATALOG_KEY_PREFIX: &str = "__c";
 pub const SCHEMA_KEY_PREFIX: &str = "__s";
 pub const TABLE_GLOBAL_KEY_PREFIX: &str = "__tg";
 pub const TABLE_REGIONAL_KEY_PREFIX: &str = "__tr";
+pub const VIEW_KEY_PREFIX: &str = "__v";
 
 const ALPHANUMERICS_NAME_PATTERN: &str = "[a-zA-Z_][a-zA-Z0-9_]*";
 
@@ -59,6 +60,13 @@ lazy_static! {
     .unwrap();
 }
 
+lazy_static! {
+    static ref VIEW_KEY_PATTERN: Regex = Regex::new(&format!(
+        "^{VIEW_KEY_PREFIX}-({ALPHANUMERICS_NAME_PATTERN})-({ALPHANUMERICS_NAME_PATTERN})-({ALPHANUMERICS_NAME_PATTERN})$"
+    ))
+    .unwrap();
+}
+
 pub fn build_catalog_prefix() -> String {
     format!("{CATALOG_KEY_PREFIX}-")
 }
@@ -67,6 +75,14 @@ pub fn build_schema_prefix(catalog_name: impl AsRef<str>) -> String {
     format!("{SCHEMA_KEY_PREFIX}-{}-", catalog_name.as_ref())
 }
 
+pub fn build_view_prefix(catalog_name: impl AsRef<str>, schema_name: impl AsRef<str>) -> String {
+    format!(
+        "{VIEW_KEY_PREFIX}-{}-{}-",
+        catalog_name.as_ref(),
+        schema_name.as_ref()
+    )
+}
+
 pub fn build_table_global_prefix(
     catalog_name: impl AsRef<str>,
     schema_name: impl AsRef<str>,
@@ -193,6 +209,47 @@ pub struct TableRegionalValue {
     pub regions_ids: Vec<u32>,
 }
 
+/// View has only one key across all frontends and datanodes, its value keeps the definition.
+pub struct ViewKey {
+    pub catalog_name: String,
+    pub schema_name: String,
+    pub view_name: String,
+}
+
+impl Display for ViewKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(VIEW_KEY_PREFIX)?;
+        f.write_str("-")?;
+        f.write_str(&self.catalog_name)?;
+        f.write_str("-")?;
+        f.write_str(&self.schema_name)?;
+        f.write_str("-")?;
+        f.write_str(&self.view_name)
+    }
+}
+
+impl ViewKey {
+    pub fn parse<S: AsRef<str>>(s: S) -> Result<Self, Error> {
+        let key = s.as_ref();
+        let captures = VIEW_KEY_PATTERN
+            .captures(key)
+            .context(InvalidCatalogSnafu { key })?;
+        ensure!(captures.len() == 4, InvalidCatalogSnafu { key });
+
+        Ok(Self {
+            catalog_name: captures[1].to_string(),
+            schema_name: captures[2].to_string(),
+            view_name: captures[3].to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ViewValue {
+    /// The query that defines the view.
+    pub definition: String,
+}
+
 pub struct CatalogKey {
     pub catalog_name: String,
 }
@@ -280,7 +337,8 @@ define_catalog_value!(
     TableRegionalValue,
     TableGlobalValue,
     CatalogValue,
-    SchemaValue
+    SchemaValue,
+    ViewValue
 );
 
 #[cfg(test)]
@@ -318,6 +376,22 @@ mod tests {
         assert_eq!(key, &entry.to_string());
     }
 
+    #[test]
+    fn test_parse_view_key() {
+        let key = "__v-C-S-V";
+        let entry = ViewKey::parse(key).unwrap();
+        assert_eq!("C", entry.catalog_name);
+        assert_eq!("S", entry.schema_name);
+        assert_eq!("V", entry.view_name);
+        assert_eq!(key, &entry.to_string());
+
+        let value = ViewValue {
+            definition: "SELECT * FROM T".to_string(),
+        };
+        let bytes = value.as_bytes().unwrap();
+        assert_eq!(value, ViewValue::from_bytes(bytes).unwrap());
+    }
+
     #[test]
     fn test_build_prefix() {
         assert_eq!("__c-", build_catalog_prefix());
@@ -326,6 +400,10 @@ mod tests {
             "__tg-CATALOG-SCHEMA-",
             build_table_global_prefix("CATALOG", "SCHEMA")
         );
+        assert_eq!(
+            "__v-CATALOG-SCHEMA-",
+            build_view_prefix("CATALOG", "SCHEMA")
+        );
     }
 
     #[test]
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod processlist;
 mod tables;
 
 use std::any::Any;
@@ -24,10 +25,12 @@ use table::table::adapter::TableAdapter;
 use table::TableRef;
 
 use crate::error::{DatafusionSnafu, Result, TableSchemaMismatchSnafu};
+use crate::information_schema::processlist::InformationSchemaProcesslist;
 use crate::information_schema::tables::InformationSchemaTables;
 use crate::{CatalogProviderRef, SchemaProvider};
 
 const TABLES: &str = "tables";
+const PROCESSLIST: &str = "processlist";
 
 pub(crate) struct InformationSchemaProvider {
     catalog_name: String,
@@ -50,21 +53,23 @@ impl SchemaProvider for InformationSchemaProvider {
     }
 
     fn table_names(&self) -> Result<Vec<String>> {
-        Ok(vec![TABLES.to_string()])
+        Ok(vec![TABLES.to_string(), PROCESSLIST.to_string()])
     }
 
     async fn table(&self, name: &str) -> Result<Option<TableRef>> {
-        let table = if name.eq_ignore_ascii_case(TABLES) {
+        let stream: Arc<dyn PartitionStream> = if name.eq_ignore_ascii_case(TABLES) {
             Arc::new(InformationSchemaTables::new(
                 self.catalog_name.clone(),
                 self.catalog_provider.clone(),
             ))
+        } else if name.eq_ignore_ascii_case(PROCESSLIST) {
+            Arc::new(InformationSchemaProcesslist::new(self.catalog_name.clone()))
         } else {
             return Ok(None);
         };
 
         let table = Arc::new(
-            StreamingTable::try_new(table.schema().clone(), vec![table]).with_context(|_| {
+            StreamingTable::try_new(stream.schema().clone(), vec![stream]).with_context(|_| {
                 DatafusionSnafu {
                     msg: format!("Failed to get InformationSchema table '{name}'"),
                 }
@@ -75,6 +80,9 @@ impl SchemaProvider for InformationSchemaProvider {
     }
 
     fn table_exist(&self, name: &str) -> Result<bool> {
-        Ok(matches!(name.to_ascii_lowercase().as_str(), TABLES))
+        Ok(matches!(
+            name.to_ascii_lowercase().as_str(),
+            TABLES | PROCESSLIST
+        ))
     }
 }
@@ -0,0 +1,182 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow_schema::SchemaRef as ArrowSchemaRef;
+use common_query::physical_plan::TaskContext;
+use common_recordbatch::RecordBatch;
+use datafusion::datasource::streaming::PartitionStream as DfPartitionStream;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter as DfRecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream as DfSendableRecordBatchStream;
+use datatypes::prelude::{ConcreteDataType, ScalarVectorBuilder, VectorRef};
+use datatypes::schema::{ColumnSchema, Schema, SchemaRef};
+use datatypes::vectors::{
+    Int64VectorBuilder, StringVectorBuilder, TimestampMillisecondVectorBuilder, UInt32VectorBuilder,
+};
+use session::context::{QueryContext, UserInfo};
+use session::process::{ProcessManager, ProcessManagerRef};
+use snafu::ResultExt;
+
+use crate::error::{CreateRecordBatchSnafu, Result};
+
+pub(super) struct InformationSchemaProcesslist {
+    schema: SchemaRef,
+    catalog_name: String,
+    process_manager: ProcessManagerRef,
+}
+
+impl InformationSchemaProcesslist {
+    pub(super) fn new(catalog_name: String) -> Self {
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new("id", ConcreteDataType::uint32_datatype(), false),
+            ColumnSchema::new("user", ConcreteDataType::string_datatype(), false),
+            ColumnSchema::new("client", ConcreteDataType::string_datatype(), true),
+            ColumnSchema::new("protocol", ConcreteDataType::string_datatype(), true),
+            ColumnSchema::new("db", ConcreteDataType::string_datatype(), false),
+            ColumnSchema::new("query", ConcreteDataType::string_datatype(), false),
+            ColumnSchema::new(
+                "start_time",
+                ConcreteDataType::timestamp_millisecond_datatype(),
+                false,
+            ),
+            ColumnSchema::new("elapsed_ms", ConcreteDataType::int64_datatype(), false),
+        ]));
+        Self {
+            schema,
+            catalog_name,
+            process_manager: ProcessManager::global(),
+        }
+    }
+
+    fn builder(&self, user: Option<Arc<UserInfo>>) -> InformationSchemaProcesslistBuilder {
+        InformationSchemaProcesslistBuilder::new(
+            self.schema.clone(),
+            self.catalog_name.clone(),
+            self.process_manager.clone(),
+            user,
+        )
+    }
+}
+
+/// Builds the `information_schema.processlist` table row by row
+///
+/// Columns are based on <https://dev.mysql.com/doc/refman/8.0/en/information-schema-processlist-table.html>
+struct InformationSchemaProcesslistBuilder {
+    schema: SchemaRef,
+    catalog_name: String,
+    process_manager: ProcessManagerRef,
+    /// The user querying the table, who can only see the statements they can access.
+    user: Option<Arc<UserInfo>>,
+
+    ids: UInt32VectorBuilder,
+    users: StringVectorBuilder,
+    clients: StringVectorBuilder,
+    protocols: StringVectorBuilder,
+    dbs: StringVectorBuilder,
+    queries: StringVectorBuilder,
+    start_times: TimestampMillisecondVectorBuilder,
+    elapsed_times: Int64VectorBuilder,
+}
+
+impl InformationSchemaProcesslistBuilder {
+    fn new(
+        schema: SchemaRef,
+        catalog_name: String,
+        process_manager: ProcessManagerRef,
+        user: Option<Arc<UserInfo>>,
+    ) -> Self {
+        Self {
+            schema,
+            catalog_name,
+            process_manager,
+            user,
+            ids: UInt32VectorBuilder::with_capacity(42),
+            users: StringVectorBuilder::with_capacity(42),
+            clients: StringVectorBuilder::with_capacity(42),
+            protocols: StringVectorBuilder::with_capacity(42),
+            dbs: StringVectorBuilder::with_capacity(42),
+            queries: StringVectorBuilder::with_capacity(42),
+            start_times: TimestampMillisecondVectorBuilder::with_capacity(42),
+            elapsed_times: Int64VectorBuilder::with_capacity(42),
+        }
+    }
+
+    /// Construct the `information_schema.processlist` virtual table
+    fn make_processlist(&mut self) -> Result<RecordBatch> {
+        // The statements are hidden from an unknown user.
+        let processes = match &self.user {
+            Some(user) => self.process_manager.processes_of(user),
+            None => vec![],
+        };
+        for process in processes {
+            if process.catalog() != self.catalog_name {
+                continue;
+            }
+
+            self.ids.push(Some(process.id()));
+            self.users.push(Some(process.user()));
+            self.clients
+                .push(process.client().map(|x| x.to_string()).as_deref());
+            self.protocols
+                .push(process.channel().map(|x| x.to_string()).as_deref());
+            self.dbs.push(Some(process.schema()));
+            self.queries.push(Some(process.query()));
+            self.start_times
+                .push(Some(process.start_timestamp_millis().into()));
+            self.elapsed_times
+                .push(Some(process.elapsed().as_millis() as i64));
+        }
+
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<RecordBatch> {
+        let columns: Vec<VectorRef> = vec![
+            Arc::new(self.ids.finish()),
+            Arc::new(self.users.finish()),
+            Arc::new(self.clients.finish()),
+            Arc::new(self.protocols.finish()),
+            Arc::new(self.dbs.finish()),
+            Arc::new(self.queries.finish()),
+            Arc::new(self.start_times.finish()),
+            Arc::new(self.elapsed_times.finish()),
+        ];
+        RecordBatch::new(self.schema.clone(), columns).context(CreateRecordBatchSnafu)
+    }
+}
+
+impl DfPartitionStream for InformationSchemaProcesslist {
+    fn schema(&self) -> &ArrowSchemaRef {
+        self.schema.arrow_schema()
+    }
+
+    fn execute(&self, ctx: Arc<TaskContext>) -> DfSendableRecordBatchStream {
+        let schema = self.schema().clone();
+        let user = ctx
+            .session_config()
+            .get_extension::<QueryContext>()
+            .map(|query_ctx| query_ctx.current_user());
+        let mut builder = self.builder(user);
+        Box::pin(DfRecordBatchStreamAdapter::new(
+            schema,
+            futures::stream::once(async move {
+                builder
+                    .make_processlist()
+                    .map(|x| x.into_df_record_batch())
+                    .map_err(Into::into)
+            }),
+        ))
+    }
+}
@@ -28,7 +28,7 @@ use snafu::ResultExt;
 use table::metadata::TableType;
 
 use crate::error::{CreateRecordBatchSnafu, Result};
-use crate::information_schema::TABLES;
+use crate::information_schema::{PROCESSLIST, TABLES};
 use crate::CatalogProviderRef;
 
 pub(super) struct InformationSchemaTables {
@@ -102,15 +102,20 @@ impl InformationSchemaTablesBuilder {
                 let Some(table) = schema.table(&table_name).await? else { continue };
                 self.add_table(&catalog_name, &schema_name, &table_name, table.table_type());
             }
+            for view_name in schema.view_names()? {
+                self.add_table(&catalog_name, &schema_name, &view_name, TableType::View);
+            }
         }
 
         // Add a final list for the information schema tables themselves
-        self.add_table(
-            &catalog_name,
-            INFORMATION_SCHEMA_NAME,
-            TABLES,
-            TableType::View,
-        );
+        for table_name in [TABLES, PROCESSLIST] {
+            self.add_table(
+                &catalog_name,
+                INFORMATION_SCHEMA_NAME,
+                table_name,
+                TableType::View,
+            );
+        }
 
         self.finish()
     }
@@ -90,7 +90,7 @@ pub trait CatalogManager: CatalogList {
     async fn start(&self) -> Result<()>;
 
     /// Registers a table within given catalog/schema to catalog manager,
-    /// returns whether the table registered.
+    /// returns whether the table registered. Fails if a view of the same name exists.
     async fn register_table(&self, request: RegisterTableRequest) -> Result<bool>;
 
     /// Deregisters a table within given catalog/schema to catalog manager,
@@ -104,6 +104,17 @@ pub trait CatalogManager: CatalogList {
     /// Rename a table to [RenameTableRequest::new_table_name], returns whether the table is renamed.
     async fn rename_table(&self, request: RenameTableRequest) -> Result<bool>;
 
+    /// Registers a view within given catalog/schema, or replaces the definition of the existing
+    /// view with the same name. Returns whether a new view is registered.
+    ///
+    /// Tables and views share their names, so it fails if a table of the same name exists. The
+    /// check is atomic with the registration, a table registered concurrently either makes the
+    /// view fail or fails itself.
+    async fn register_view(&self, request: RegisterViewRequest) -> Result<bool>;
+
+    /// Deregisters a view within given catalog/schema, returns whether the view existed.
+    async fn deregister_view(&self, request: DeregisterViewRequest) -> Result<bool>;
+
     /// Register a system table, should be called before starting the manager.
     async fn register_system_table(&self, request: RegisterSystemTableRequest)
         -> error::Result<()>;
@@ -117,6 +128,9 @@ pub trait CatalogManager: CatalogList {
         schema: &str,
         table_name: &str,
     ) -> Result<Option<TableRef>>;
+
+    /// Returns the definition of the view by catalog, schema and view name.
+    async fn view(&self, catalog: &str, schema: &str, view_name: &str) -> Result<Option<String>>;
 }
 
 pub type CatalogManagerRef = Arc<dyn CatalogManager>;
@@ -170,6 +184,22 @@ pub struct DeregisterTableRequest {
     pub table_name: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct RegisterViewRequest {
+    pub catalog: String,
+    pub schema: String,
+    pub view_name: String,
+    /// The query that defines the view.
+    pub definition: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeregisterViewRequest {
+    pub catalog: String,
+    pub schema: String,
+    pub view_name: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct RegisterSchemaRequest {
     pub catalog: String,
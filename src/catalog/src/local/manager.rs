@@ -39,7 +39,7 @@ use crate::error::{
     self, CatalogNotFoundSnafu, IllegalManagerStateSnafu, OpenTableSnafu, ReadSystemCatalogSnafu,
     Result, SchemaExistsSnafu, SchemaNotFoundSnafu, SystemCatalogSnafu,
     SystemCatalogTypeMismatchSnafu, TableEngineNotFoundSnafu, TableExistsSnafu, TableNotExistSnafu,
-    TableNotFoundSnafu,
+    TableNotFoundSnafu, ViewExistsSnafu,
 };
 use crate::local::memory::{MemoryCatalogManager, MemoryCatalogProvider, MemorySchemaProvider};
 use crate::system::{
@@ -49,8 +49,9 @@ use crate::system::{
 use crate::tables::SystemCatalog;
 use crate::{
     handle_system_table_request, CatalogList, CatalogManager, CatalogProvider, CatalogProviderRef,
-    DeregisterTableRequest, RegisterSchemaRequest, RegisterSystemTableRequest,
-    RegisterTableRequest, RenameTableRequest, SchemaProvider, SchemaProviderRef,
+    DeregisterTableRequest, DeregisterViewRequest, RegisterSchemaRequest,
+    RegisterSystemTableRequest, RegisterTableRequest, RegisterViewRequest, RenameTableRequest,
+    SchemaProvider, SchemaProviderRef,
 };
 
 /// A `CatalogManager` consists of a system catalog and a bunch of user catalogs.
@@ -230,13 +231,26 @@ impl LocalCatalogManager {
                     info!("Registered table: {:?}", t);
                     max_table_id = max_table_id.max(t.table_id);
                 }
+                Entry::View(v) => {
+                    let schema = self
+                        .catalogs
+                        .schema(&v.catalog_name, &v.schema_name)?
+                        .context(SchemaNotFoundSnafu {
+                            catalog: &v.catalog_name,
+                            schema: &v.schema_name,
+                        })?;
+                    schema
+                        .register_view(v.view_name.clone(), v.definition.clone())
+                        .await?;
+                    info!("Registered view: {:?}", v);
+                }
             }
         }
         Ok(max_table_id)
     }
 
     /// Sort catalog entries to ensure catalog entries comes first, then schema entries,
-    /// and table entries is the last.
+    /// then table entries, and view entries is the last.
     fn sort_entries(mut entries: Vec<Entry>) -> Vec<Entry> {
         entries.sort();
         entries
@@ -377,6 +391,17 @@ impl CatalogManager for LocalCatalogManager {
                 // Try to register table with same table id, just ignore.
                 Ok(false)
             } else {
+                // Tables and views share their names, views are registered under the same lock.
+                ensure!(
+                    schema.view(&request.table_name).await?.is_none(),
+                    ViewExistsSnafu {
+                        view: format_full_table_name(
+                            catalog_name,
+                            schema_name,
+                            &request.table_name
+                        ),
+                    }
+                );
                 let engine = request.table.table_info().meta.engine.to_string();
                 // table does not exist
                 self.system
@@ -507,6 +532,71 @@ impl CatalogManager for LocalCatalogManager {
         }
     }
 
+    async fn register_view(&self, request: RegisterViewRequest) -> Result<bool> {
+        let started = self.init_lock.lock().await;
+        ensure!(
+            *started,
+            IllegalManagerStateSnafu {
+                msg: "Catalog manager not started",
+            }
+        );
+
+        let catalog_name = &request.catalog;
+        let schema_name = &request.schema;
+        let schema = self
+            .catalogs
+            .schema(catalog_name, schema_name)?
+            .with_context(|| SchemaNotFoundSnafu {
+                catalog: catalog_name,
+                schema: schema_name,
+            })?;
+
+        {
+            let _lock = self.register_lock.lock().await;
+            // Tables and views share their names, tables are registered under the same lock.
+            ensure!(
+                schema.table(&request.view_name).await?.is_none(),
+                TableExistsSnafu {
+                    table: format_full_table_name(catalog_name, schema_name, &request.view_name),
+                }
+            );
+            self.system
+                .register_view(
+                    catalog_name.clone(),
+                    schema_name.clone(),
+                    request.view_name.clone(),
+                    request.definition.clone(),
+                )
+                .await?;
+            schema
+                .register_view(request.view_name, request.definition)
+                .await
+                .map(|v| v.is_none())
+        }
+    }
+
+    async fn deregister_view(&self, request: DeregisterViewRequest) -> Result<bool> {
+        {
+            let started = *self.init_lock.lock().await;
+            ensure!(started, IllegalManagerStateSnafu { msg: "not started" });
+        }
+
+        {
+            let _lock = self.register_lock.lock().await;
+            if self
+                .catalogs
+                .view(&request.catalog, &request.schema, &request.view_name)
+                .await?
+                .is_none()
+            {
+                return Ok(false);
+            }
+
+            self.system.deregister_view(&request).await?;
+            self.catalogs.deregister_view(request).await
+        }
+    }
+
     async fn register_system_table(&self, request: RegisterSystemTableRequest) -> Result<()> {
         ensure!(
             !*self.init_lock.lock().await,
@@ -548,6 +638,17 @@ impl CatalogManager for LocalCatalogManager {
             })?;
         schema.table(table_name).await
     }
+
+    async fn view(
+        &self,
+        catalog_name: &str,
+        schema_name: &str,
+        view_name: &str,
+    ) -> Result<Option<String>> {
+        self.catalogs
+            .view(catalog_name, schema_name, view_name)
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -557,11 +658,17 @@ mod tests {
     use mito::engine::MITO_ENGINE;
 
     use super::*;
-    use crate::system::{CatalogEntry, SchemaEntry};
+    use crate::system::{CatalogEntry, SchemaEntry, ViewEntry};
 
     #[test]
     fn test_sort_entry() {
         let vec = vec![
+            Entry::View(ViewEntry {
+                catalog_name: "C1".to_string(),
+                schema_name: "S1".to_string(),
+                view_name: "V1".to_string(),
+                definition: "SELECT * FROM T1".to_string(),
+            }),
             Entry::Table(TableEntry {
                 catalog_name: "C1".to_string(),
                 schema_name: "S1".to_string(),
@@ -598,5 +705,6 @@ mod tests {
         assert_matches!(res[3], Entry::Schema(..));
         assert_matches!(res[4], Entry::Table(..));
         assert_matches!(res[5], Entry::Table(..));
+        assert_matches!(res[6], Entry::View(..));
     }
 }
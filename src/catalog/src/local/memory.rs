@@ -28,12 +28,13 @@ use table::TableRef;
 
 use crate::error::{
     self, CatalogNotFoundSnafu, Result, SchemaNotFoundSnafu, TableExistsSnafu, TableNotFoundSnafu,
+    ViewExistsSnafu,
 };
 use crate::schema::SchemaProvider;
 use crate::{
     CatalogList, CatalogManager, CatalogProvider, CatalogProviderRef, DeregisterTableRequest,
-    RegisterSchemaRequest, RegisterSystemTableRequest, RegisterTableRequest, RenameTableRequest,
-    SchemaProviderRef,
+    DeregisterViewRequest, RegisterSchemaRequest, RegisterSystemTableRequest, RegisterTableRequest,
+    RegisterViewRequest, RenameTableRequest, SchemaProviderRef,
 };
 
 /// Simple in-memory list of catalogs
@@ -142,6 +143,32 @@ impl CatalogManager for MemoryCatalogManager {
         Ok(true)
     }
 
+    async fn register_view(&self, request: RegisterViewRequest) -> Result<bool> {
+        let schema = self
+            .schema(&request.catalog, &request.schema)?
+            .with_context(|| SchemaNotFoundSnafu {
+                catalog: &request.catalog,
+                schema: &request.schema,
+            })?;
+        schema
+            .register_view(request.view_name, request.definition)
+            .await
+            .map(|v| v.is_none())
+    }
+
+    async fn deregister_view(&self, request: DeregisterViewRequest) -> Result<bool> {
+        let schema = self
+            .schema(&request.catalog, &request.schema)?
+            .with_context(|| SchemaNotFoundSnafu {
+                catalog: &request.catalog,
+                schema: &request.schema,
+            })?;
+        schema
+            .deregister_view(&request.view_name)
+            .await
+            .map(|v| v.is_some())
+    }
+
     async fn register_system_table(&self, _request: RegisterSystemTableRequest) -> Result<()> {
         // TODO(ruihang): support register system table request
         Ok(())
@@ -172,6 +199,13 @@ impl CatalogManager for MemoryCatalogManager {
             Some(s) => s.table(table_name).await,
         }
     }
+
+    async fn view(&self, catalog: &str, schema: &str, view_name: &str) -> Result<Option<String>> {
+        match self.schema(catalog, schema)? {
+            None => Ok(None),
+            Some(s) => s.view(view_name).await,
+        }
+    }
 }
 
 impl MemoryCatalogManager {
@@ -271,6 +305,8 @@ impl CatalogProvider for MemoryCatalogProvider {
 /// Simple in-memory implementation of a schema.
 pub struct MemorySchemaProvider {
     tables: RwLock<HashMap<String, TableRef>>,
+    /// Definitions of views, keyed by view name.
+    views: RwLock<HashMap<String, String>>,
 }
 
 impl MemorySchemaProvider {
@@ -278,6 +314,7 @@ impl MemorySchemaProvider {
     pub fn new() -> Self {
         Self {
             tables: RwLock::new(HashMap::new()),
+            views: RwLock::new(HashMap::new()),
         }
     }
 }
@@ -305,7 +342,12 @@ impl SchemaProvider for MemorySchemaProvider {
     }
 
     fn register_table(&self, name: String, table: TableRef) -> Result<Option<TableRef>> {
+        // Tables are locked before views, see `register_view`.
         let mut tables = self.tables.write().unwrap();
+        ensure!(
+            !self.views.read().unwrap().contains_key(&name),
+            ViewExistsSnafu { view: name }
+        );
         if let Some(existing) = tables.get(name.as_str()) {
             // if table with the same name but different table id exists, then it's a fatal bug
             if existing.table_info().ident.table_id != table.table_info().ident.table_id {
@@ -324,6 +366,10 @@ impl SchemaProvider for MemorySchemaProvider {
 
     fn rename_table(&self, name: &str, new_name: String) -> Result<TableRef> {
         let mut tables = self.tables.write().unwrap();
+        ensure!(
+            !self.views.read().unwrap().contains_key(&new_name),
+            ViewExistsSnafu { view: new_name }
+        );
         if tables.get(name).is_some() {
             let table = tables.remove(name).unwrap();
             tables.insert(new_name, table.clone());
@@ -341,6 +387,32 @@ impl SchemaProvider for MemorySchemaProvider {
         Ok(tables.remove(name))
     }
 
+    fn view_names(&self) -> Result<Vec<String>> {
+        let views = self.views.read().unwrap();
+        Ok(views.keys().cloned().collect())
+    }
+
+    async fn view(&self, name: &str) -> Result<Option<String>> {
+        let views = self.views.read().unwrap();
+        Ok(views.get(name).cloned())
+    }
+
+    async fn register_view(&self, name: String, definition: String) -> Result<Option<String>> {
+        // Holds the lock of tables, so that no table of the name is registered meanwhile.
+        let tables = self.tables.read().unwrap();
+        ensure!(
+            !tables.contains_key(&name),
+            TableExistsSnafu { table: name }
+        );
+        let mut views = self.views.write().unwrap();
+        Ok(views.insert(name, definition))
+    }
+
+    async fn deregister_view(&self, name: &str) -> Result<Option<String>> {
+        let mut views = self.views.write().unwrap();
+        Ok(views.remove(name))
+    }
+
     fn table_exist(&self, name: &str) -> Result<bool> {
         let tables = self.tables.read().unwrap();
         Ok(tables.contains_key(name))
@@ -399,6 +471,54 @@ mod tests {
         assert_eq!(StatusCode::TableAlreadyExists, err.status_code());
     }
 
+    #[tokio::test]
+    async fn test_mem_provider_view() {
+        let provider = MemorySchemaProvider::new();
+        assert!(provider.view("v").await.unwrap().is_none());
+        assert!(provider
+            .register_view("v".to_string(), "SELECT 1".to_string())
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            Some("SELECT 1".to_string()),
+            provider
+                .register_view("v".to_string(), "SELECT 2".to_string())
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            Some("SELECT 2".to_string()),
+            provider.view("v").await.unwrap()
+        );
+        assert_eq!(vec!["v".to_string()], provider.view_names().unwrap());
+        // views are not tables
+        assert!(!provider.table_exist("v").unwrap());
+        assert!(provider.table_names().unwrap().is_empty());
+
+        // but they share their names
+        let err = provider
+            .register_table("v".to_string(), Arc::new(NumbersTable::default()))
+            .unwrap_err();
+        assert_eq!(StatusCode::TableAlreadyExists, err.status_code());
+        let _ = provider
+            .register_table("t".to_string(), Arc::new(NumbersTable::default()))
+            .unwrap();
+        let err = provider
+            .register_view("t".to_string(), "SELECT 3".to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(StatusCode::TableAlreadyExists, err.status_code());
+        let err = provider.rename_table("t", "v".to_string()).unwrap_err();
+        assert_eq!(StatusCode::TableAlreadyExists, err.status_code());
+
+        assert_eq!(
+            Some("SELECT 2".to_string()),
+            provider.deregister_view("v").await.unwrap()
+        );
+        assert!(provider.deregister_view("v").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_mem_provider_rename_table() {
         let provider = MemorySchemaProvider::new();
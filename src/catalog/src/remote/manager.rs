@@ -28,7 +28,7 @@ use dashmap::DashMap;
 use futures::Stream;
 use futures_util::StreamExt;
 use parking_lot::RwLock;
-use snafu::{OptionExt, ResultExt};
+use snafu::{ensure, OptionExt, ResultExt};
 use table::engine::manager::TableEngineManagerRef;
 use table::engine::EngineContext;
 use table::metadata::TableId;
@@ -42,15 +42,16 @@ use crate::error::{
     SchemaNotFoundSnafu, TableEngineNotFoundSnafu, TableExistsSnafu, UnimplementedSnafu,
 };
 use crate::helper::{
-    build_catalog_prefix, build_schema_prefix, build_table_global_prefix, CatalogKey, CatalogValue,
-    SchemaKey, SchemaValue, TableGlobalKey, TableGlobalValue, TableRegionalKey, TableRegionalValue,
-    CATALOG_KEY_PREFIX,
+    build_catalog_prefix, build_schema_prefix, build_table_global_prefix, build_view_prefix,
+    CatalogKey, CatalogValue, SchemaKey, SchemaValue, TableGlobalKey, TableGlobalValue,
+    TableRegionalKey, TableRegionalValue, ViewKey, ViewValue, CATALOG_KEY_PREFIX,
 };
 use crate::remote::{Kv, KvBackendRef};
 use crate::{
     handle_system_table_request, CatalogList, CatalogManager, CatalogProvider, CatalogProviderRef,
-    DeregisterTableRequest, RegisterSchemaRequest, RegisterSystemTableRequest,
-    RegisterTableRequest, RenameTableRequest, SchemaProvider, SchemaProviderRef,
+    DeregisterTableRequest, DeregisterViewRequest, RegisterSchemaRequest,
+    RegisterSystemTableRequest, RegisterTableRequest, RegisterViewRequest, RenameTableRequest,
+    SchemaProvider, SchemaProviderRef,
 };
 
 /// Catalog manager based on metasrv.
@@ -490,6 +491,30 @@ impl CatalogManager for RemoteCatalogManager {
         .fail()
     }
 
+    async fn register_view(&self, request: RegisterViewRequest) -> Result<bool> {
+        let schema = self
+            .schema(&request.catalog, &request.schema)?
+            .with_context(|| SchemaNotFoundSnafu {
+                catalog: &request.catalog,
+                schema: &request.schema,
+            })?;
+        let prev = schema
+            .register_view(request.view_name, request.definition)
+            .await?;
+        Ok(prev.is_none())
+    }
+
+    async fn deregister_view(&self, request: DeregisterViewRequest) -> Result<bool> {
+        let schema = self
+            .schema(&request.catalog, &request.schema)?
+            .with_context(|| SchemaNotFoundSnafu {
+                catalog: &request.catalog,
+                schema: &request.schema,
+            })?;
+        let prev = schema.deregister_view(&request.view_name).await?;
+        Ok(prev.is_some())
+    }
+
     async fn register_system_table(&self, request: RegisterSystemTableRequest) -> Result<()> {
         let mut requests = self.system_table_requests.lock().await;
         requests.push(request);
@@ -521,6 +546,24 @@ impl CatalogManager for RemoteCatalogManager {
             })?;
         schema.table(table_name).await
     }
+
+    async fn view(
+        &self,
+        catalog_name: &str,
+        schema_name: &str,
+        view_name: &str,
+    ) -> Result<Option<String>> {
+        let catalog = self
+            .catalog(catalog_name)?
+            .with_context(|| CatalogNotFoundSnafu { catalog_name })?;
+        let schema = catalog
+            .schema(schema_name)?
+            .with_context(|| SchemaNotFoundSnafu {
+                catalog: catalog_name,
+                schema: schema_name,
+            })?;
+        schema.view(view_name).await
+    }
 }
 
 impl CatalogList for RemoteCatalogManager {
@@ -776,6 +819,14 @@ impl RemoteSchemaProvider {
             node_id: self.node_id,
         }
     }
+
+    fn build_view_key(&self, view_name: impl AsRef<str>) -> ViewKey {
+        ViewKey {
+            catalog_name: self.catalog_name.clone(),
+            schema_name: self.schema_name.clone(),
+            view_name: view_name.as_ref().to_string(),
+        }
+    }
 }
 
 #[async_trait]
@@ -866,6 +917,69 @@ impl SchemaProvider for RemoteSchemaProvider {
         prev
     }
 
+    fn view_names(&self) -> Result<Vec<String>> {
+        let backend = self.backend.clone();
+        let view_prefix = build_view_prefix(&self.catalog_name, &self.schema_name);
+        std::thread::spawn(|| {
+            common_runtime::block_on_read(async move {
+                let mut iter = backend.range(view_prefix.as_bytes());
+                let mut view_names = vec![];
+                while let Some(r) = iter.next().await {
+                    let Kv(k, _) = r?;
+                    let view_key = ViewKey::parse(String::from_utf8_lossy(&k))
+                        .context(InvalidCatalogValueSnafu)?;
+                    view_names.push(view_key.view_name);
+                }
+                Ok(view_names)
+            })
+        })
+        .join()
+        .unwrap()
+    }
+
+    async fn view(&self, name: &str) -> Result<Option<String>> {
+        let view_key = self.build_view_key(name).to_string();
+        let Some(kv) = self.backend.get(view_key.as_bytes()).await? else { return Ok(None) };
+        let view_value = ViewValue::from_bytes(kv.1).context(InvalidCatalogValueSnafu)?;
+        Ok(Some(view_value.definition))
+    }
+
+    async fn register_view(&self, name: String, definition: String) -> Result<Option<String>> {
+        // Tables are registered under the same lock.
+        let _guard = self.mutex.lock().await;
+        ensure!(
+            !self.table_exist(&name)?,
+            TableExistsSnafu {
+                table: format!("{}.{}.{}", &self.catalog_name, &self.schema_name, &name),
+            }
+        );
+        let prev = self.view(&name).await?;
+        let view_key = self.build_view_key(&name).to_string();
+        let view_value = ViewValue { definition };
+        self.backend
+            .set(
+                view_key.as_bytes(),
+                &view_value.as_bytes().context(InvalidCatalogValueSnafu)?,
+            )
+            .await?;
+        debug!(
+            "Successfully set catalog view entry, key: {}, view value: {:?}",
+            view_key, view_value
+        );
+        Ok(prev)
+    }
+
+    async fn deregister_view(&self, name: &str) -> Result<Option<String>> {
+        let _guard = self.mutex.lock().await;
+        let prev = self.view(name).await?;
+        if prev.is_some() {
+            let view_key = self.build_view_key(name).to_string();
+            self.backend.delete(view_key.as_bytes()).await?;
+            debug!("Successfully deleted catalog view entry, key: {}", view_key);
+        }
+        Ok(prev)
+    }
+
     /// Checks if table exists in schema provider based on locally opened table map.
     fn table_exist(&self, name: &str) -> Result<bool> {
         Ok(self.tables.load().contains_key(name))
@@ -60,6 +60,35 @@ pub trait SchemaProvider: Sync + Send {
         .fail()
     }
 
+    /// Retrieves the list of available view names in this schema.
+    fn view_names(&self) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    /// Retrieves the definition of a view from the schema by name, provided it exists.
+    async fn view(&self, _name: &str) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// If supported by the implementation, adds a new view to this schema, or replaces the
+    /// definition of the view with the same name and returns the replaced one. Fails if a table
+    /// of the same name exists.
+    async fn register_view(&self, name: String, _definition: String) -> Result<Option<String>> {
+        NotSupportedSnafu {
+            op: format!("register_view({name}, <definition>)"),
+        }
+        .fail()
+    }
+
+    /// If supported by the implementation, removes an existing view from this schema and returns
+    /// its definition. If no view of that name exists, returns Ok(None).
+    async fn deregister_view(&self, name: &str) -> Result<Option<String>> {
+        NotSupportedSnafu {
+            op: format!("deregister_view({name})"),
+        }
+        .fail()
+    }
+
     /// If supported by the implementation, checks the table exist in the schema provider or not.
     /// If no matched table in the schema provider, return false.
     /// Otherwise, return true.
@@ -41,7 +41,7 @@ use crate::error::{
     self, CreateSystemCatalogSnafu, EmptyValueSnafu, Error, InvalidEntryTypeSnafu, InvalidKeySnafu,
     OpenSystemCatalogSnafu, Result, ValueDeserializeSnafu,
 };
-use crate::DeregisterTableRequest;
+use crate::{DeregisterTableRequest, DeregisterViewRequest};
 
 pub const ENTRY_TYPE_INDEX: usize = 0;
 pub const KEY_INDEX: usize = 1;
@@ -217,6 +217,38 @@ pub(crate) fn build_table_deletion_request(
     }
 }
 
+/// Formats key string for view entry in system catalog
+#[inline]
+pub fn format_view_entry_key(catalog: &str, schema: &str, view_name: &str) -> String {
+    format!("{catalog}.{schema}.{view_name}")
+}
+
+pub fn build_view_insert_request(
+    catalog: String,
+    schema: String,
+    view_name: String,
+    definition: String,
+) -> InsertRequest {
+    let entry_key = format_view_entry_key(&catalog, &schema, &view_name);
+    build_insert_request(
+        EntryType::View,
+        entry_key.as_bytes(),
+        serde_json::to_string(&ViewEntryValue {
+            view_name,
+            definition,
+        })
+        .unwrap()
+        .as_bytes(),
+    )
+}
+
+pub(crate) fn build_view_deletion_request(request: &DeregisterViewRequest) -> DeleteRequest {
+    let view_key = format_view_entry_key(&request.catalog, &request.schema, &request.view_name);
+    DeleteRequest {
+        key_column_values: build_primary_key_columns(EntryType::View, view_key.as_bytes()),
+    }
+}
+
 fn build_primary_key_columns(entry_type: EntryType, key: &[u8]) -> HashMap<String, VectorRef> {
     let mut m = HashMap::with_capacity(3);
     m.insert(
@@ -335,6 +367,27 @@ pub fn decode_system_catalog(
                 engine: table_meta.engine,
             }))
         }
+
+        EntryType::View => {
+            // As for view entry, the key is a string with format: `<catalog_name>.<schema_name>.<view_name>`
+            // and the value is a JSON string with format: `{"view_name": <view_name>, "definition": <query>}`
+            let view_parts = key.split('.').collect::<Vec<_>>();
+            ensure!(
+                view_parts.len() >= 3,
+                InvalidKeySnafu {
+                    key: Some(key.to_string())
+                }
+            );
+            let value = value.context(EmptyValueSnafu)?;
+            let view_meta: ViewEntryValue =
+                serde_json::from_slice(value).context(ValueDeserializeSnafu)?;
+            Ok(Entry::View(ViewEntry {
+                catalog_name: view_parts[0].to_string(),
+                schema_name: view_parts[1].to_string(),
+                view_name: view_meta.view_name,
+                definition: view_meta.definition,
+            }))
+        }
     }
 }
 
@@ -343,6 +396,7 @@ pub enum EntryType {
     Catalog = 1,
     Schema = 2,
     Table = 3,
+    View = 4,
 }
 
 impl TryFrom<u8> for EntryType {
@@ -353,6 +407,7 @@ impl TryFrom<u8> for EntryType {
             b if b == Self::Catalog as u8 => Ok(Self::Catalog),
             b if b == Self::Schema as u8 => Ok(Self::Schema),
             b if b == Self::Table as u8 => Ok(Self::Table),
+            b if b == Self::View as u8 => Ok(Self::View),
             b => InvalidEntryTypeSnafu {
                 entry_type: Some(b),
             }
@@ -366,6 +421,7 @@ pub enum Entry {
     Catalog(CatalogEntry),
     Schema(SchemaEntry),
     Table(TableEntry),
+    View(ViewEntry),
 }
 
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd)]
@@ -399,6 +455,20 @@ pub struct TableEntryValue {
     pub engine: String,
 }
 
+#[derive(Debug, PartialEq, Eq, Ord, PartialOrd)]
+pub struct ViewEntry {
+    pub catalog_name: String,
+    pub schema_name: String,
+    pub view_name: String,
+    pub definition: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ViewEntryValue {
+    pub view_name: String,
+    pub definition: String,
+}
+
 fn mito_engine() -> String {
     MITO_ENGINE.to_string()
 }
@@ -487,7 +557,8 @@ mod tests {
         assert_eq!(EntryType::Catalog, EntryType::try_from(1).unwrap());
         assert_eq!(EntryType::Schema, EntryType::try_from(2).unwrap());
         assert_eq!(EntryType::Table, EntryType::try_from(3).unwrap());
-        assert!(EntryType::try_from(4).is_err());
+        assert_eq!(EntryType::View, EntryType::try_from(4).unwrap());
+        assert!(EntryType::try_from(5).is_err());
     }
 
     pub async fn prepare_table_engine() -> (TempDir, TableEngineRef) {
@@ -579,4 +650,48 @@ mod tests {
         let batches = RecordBatches::try_collect(records).await.unwrap().take();
         assert_eq!(batches.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_system_catalog_view_records() {
+        let (_dir, table_engine) = prepare_table_engine().await;
+        let catalog_table = SystemCatalogTable::new(table_engine).await.unwrap();
+
+        for definition in ["SELECT 1", "SELECT 2"] {
+            let view_insertion = build_view_insert_request(
+                DEFAULT_CATALOG_NAME.to_string(),
+                DEFAULT_SCHEMA_NAME.to_string(),
+                "my_view".to_string(),
+                definition.to_string(),
+            );
+            assert_eq!(1, catalog_table.insert(view_insertion).await.unwrap());
+        }
+
+        // Registering the view again replaces its definition.
+        let records = catalog_table.records().await.unwrap();
+        let batches = RecordBatches::try_collect(records).await.unwrap().take();
+        let rows = batches.iter().flat_map(|b| b.rows()).collect::<Vec<_>>();
+        assert_eq!(rows.len(), 1);
+        let Value::UInt8(entry_type) = rows[0][0] else { unreachable!() };
+        let Value::Binary(key) = rows[0][1].clone() else { unreachable!() };
+        let Value::Binary(value) = rows[0][3].clone() else { unreachable!() };
+        let entry = decode_system_catalog(Some(entry_type), Some(&*key), Some(&*value)).unwrap();
+        let expected = Entry::View(ViewEntry {
+            catalog_name: DEFAULT_CATALOG_NAME.to_string(),
+            schema_name: DEFAULT_SCHEMA_NAME.to_string(),
+            view_name: "my_view".to_string(),
+            definition: "SELECT 2".to_string(),
+        });
+        assert_eq!(entry, expected);
+
+        let view_deletion = build_view_deletion_request(&DeregisterViewRequest {
+            catalog: DEFAULT_CATALOG_NAME.to_string(),
+            schema: DEFAULT_SCHEMA_NAME.to_string(),
+            view_name: "my_view".to_string(),
+        });
+        assert_eq!(1, catalog_table.delete(view_deletion).await.unwrap());
+
+        let records = catalog_table.records().await.unwrap();
+        let batches = RecordBatches::try_collect(records).await.unwrap().take();
+        assert_eq!(batches.len(), 0);
+    }
 }
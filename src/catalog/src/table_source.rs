@@ -133,6 +133,23 @@ impl DfTableSourceProvider {
         self.resolved_tables.insert(resolved_name, table.clone());
         Ok(table)
     }
+
+    /// Returns the definition of the view referenced by `table_ref`, or `None` if there is no
+    /// such view.
+    pub async fn resolve_view(&self, table_ref: TableReference<'_>) -> Result<Option<String>> {
+        let table_ref = self.resolve_table_ref(table_ref)?;
+
+        let catalog_name = table_ref.catalog.as_ref();
+        let schema_name = table_ref.schema.as_ref();
+        let view_name = table_ref.table.as_ref();
+        if schema_name == INFORMATION_SCHEMA_NAME {
+            return Ok(None);
+        }
+
+        let Some(catalog) = self.catalog_list.catalog(catalog_name)? else { return Ok(None) };
+        let Some(schema) = catalog.schema(schema_name)? else { return Ok(None) };
+        schema.view(view_name).await
+    }
 }
 
 #[cfg(test)]
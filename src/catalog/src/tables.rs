@@ -26,9 +26,12 @@ use table::{Table, TableRef};
 use crate::error::{self, Error, InsertCatalogRecordSnafu, Result as CatalogResult};
 use crate::system::{
     build_schema_insert_request, build_table_deletion_request, build_table_insert_request,
-    SystemCatalogTable,
+    build_view_deletion_request, build_view_insert_request, SystemCatalogTable,
+};
+use crate::{
+    CatalogProvider, DeregisterTableRequest, DeregisterViewRequest, SchemaProvider,
+    SchemaProviderRef,
 };
-use crate::{CatalogProvider, DeregisterTableRequest, SchemaProvider, SchemaProviderRef};
 
 pub struct InformationSchema {
     pub system: Arc<SystemCatalogTable>,
@@ -102,6 +105,35 @@ impl SystemCatalog {
             })
     }
 
+    pub async fn register_view(
+        &self,
+        catalog: String,
+        schema: String,
+        view_name: String,
+        definition: String,
+    ) -> crate::error::Result<usize> {
+        let request = build_view_insert_request(catalog, schema, view_name, definition);
+        self.information_schema
+            .system
+            .insert(request)
+            .await
+            .context(InsertCatalogRecordSnafu)
+    }
+
+    pub(crate) async fn deregister_view(
+        &self,
+        request: &DeregisterViewRequest,
+    ) -> CatalogResult<bool> {
+        self.information_schema
+            .system
+            .delete(build_view_deletion_request(request))
+            .await
+            .map(|x| x == 1)
+            .with_context(|_| error::DeregisterViewSnafu {
+                request: request.clone(),
+            })
+    }
+
     pub async fn register_schema(
         &self,
         catalog: String,
@@ -17,8 +17,9 @@ mod tests {
     use std::sync::Arc;
 
     use catalog::local::LocalCatalogManager;
-    use catalog::{CatalogManager, RegisterTableRequest, RenameTableRequest};
+    use catalog::{CatalogManager, RegisterTableRequest, RegisterViewRequest, RenameTableRequest};
     use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
+    use common_error::prelude::{ErrorExt, StatusCode};
     use common_telemetry::{error, info};
     use mito::config::EngineConfig;
     use table::engine::manager::MemoryTableEngineManager;
@@ -79,6 +80,62 @@ mod tests {
         assert_eq!(registered_table.table_info().ident.table_id, table_id);
     }
 
+    #[tokio::test]
+    async fn test_register_view() {
+        let catalog_manager = create_local_catalog_manager().await.unwrap();
+        let request = RegisterViewRequest {
+            catalog: DEFAULT_CATALOG_NAME.to_string(),
+            schema: DEFAULT_SCHEMA_NAME.to_string(),
+            view_name: "test_view".to_string(),
+            definition: "SELECT * FROM numbers".to_string(),
+        };
+        assert!(catalog_manager
+            .register_view(request.clone())
+            .await
+            .unwrap());
+        assert!(!catalog_manager
+            .register_view(RegisterViewRequest {
+                definition: "SELECT number FROM numbers".to_string(),
+                ..request
+            })
+            .await
+            .unwrap());
+
+        let definition = catalog_manager
+            .view(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, "test_view")
+            .await
+            .unwrap();
+        assert_eq!(Some("SELECT number FROM numbers".to_string()), definition);
+        assert!(catalog_manager
+            .table(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, "test_view")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Tables and views share their names.
+        let err = catalog_manager
+            .register_table(RegisterTableRequest {
+                catalog: DEFAULT_CATALOG_NAME.to_string(),
+                schema: DEFAULT_SCHEMA_NAME.to_string(),
+                table_name: "test_view".to_string(),
+                table_id: 42,
+                table: Arc::new(NumbersTable::new(42)),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(StatusCode::TableAlreadyExists, err.status_code());
+        let err = catalog_manager
+            .register_view(RegisterViewRequest {
+                catalog: DEFAULT_CATALOG_NAME.to_string(),
+                schema: DEFAULT_SCHEMA_NAME.to_string(),
+                view_name: "numbers".to_string(),
+                definition: "SELECT 1".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(StatusCode::TableAlreadyExists, err.status_code());
+    }
+
     #[tokio::test]
     async fn test_duplicate_register() {
         let catalog_manager = create_local_catalog_manager().await.unwrap();
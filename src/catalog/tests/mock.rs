@@ -112,11 +112,16 @@ impl KvBackend for MockKvBackend {
     }
 
     async fn delete_range(&self, key: &[u8], end: &[u8]) -> Result<(), Error> {
+        let mut map = self.map.write().await;
+        // An empty end means deleting the single key.
+        if end.is_empty() {
+            let _ = map.remove(key);
+            return Ok(());
+        }
+
         let start = key.to_vec();
         let end = end.to_vec();
         let range = start..end;
-
-        let mut map = self.map.write().await;
         map.retain(|k, _| !range.contains(k));
         Ok(())
     }
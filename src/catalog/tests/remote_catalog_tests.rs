@@ -22,11 +22,14 @@ mod tests {
     use std::collections::HashSet;
     use std::sync::Arc;
 
-    use catalog::helper::{CatalogKey, CatalogValue, SchemaKey, SchemaValue};
+    use catalog::helper::{CatalogKey, CatalogValue, SchemaKey, SchemaValue, ViewKey, ViewValue};
     use catalog::remote::{
         KvBackend, KvBackendRef, RemoteCatalogManager, RemoteCatalogProvider, RemoteSchemaProvider,
     };
-    use catalog::{CatalogList, CatalogManager, RegisterTableRequest};
+    use catalog::{
+        CatalogList, CatalogManager, DeregisterViewRequest, RegisterTableRequest,
+        RegisterViewRequest,
+    };
     use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, MITO_ENGINE};
     use datatypes::schema::RawSchema;
     use futures_util::StreamExt;
@@ -219,6 +222,61 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_register_view() {
+        let node_id = 42;
+        let (backend, _, catalog_manager) = prepare_components(node_id).await;
+        let request = RegisterViewRequest {
+            catalog: DEFAULT_CATALOG_NAME.to_string(),
+            schema: DEFAULT_SCHEMA_NAME.to_string(),
+            view_name: "test_view".to_string(),
+            definition: "SELECT * FROM numbers".to_string(),
+        };
+        assert!(catalog_manager
+            .register_view(request.clone())
+            .await
+            .unwrap());
+        // Registering again replaces the definition.
+        assert!(!catalog_manager
+            .register_view(RegisterViewRequest {
+                definition: "SELECT number FROM numbers".to_string(),
+                ..request
+            })
+            .await
+            .unwrap());
+
+        let view_key = ViewKey {
+            catalog_name: DEFAULT_CATALOG_NAME.to_string(),
+            schema_name: DEFAULT_SCHEMA_NAME.to_string(),
+            view_name: "test_view".to_string(),
+        }
+        .to_string();
+        let kv = backend.get(view_key.as_bytes()).await.unwrap().unwrap();
+        assert_eq!(
+            "SELECT number FROM numbers",
+            ViewValue::from_bytes(kv.1).unwrap().definition
+        );
+        assert_eq!(
+            Some("SELECT number FROM numbers".to_string()),
+            catalog_manager
+                .view(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, "test_view")
+                .await
+                .unwrap()
+        );
+
+        let request = DeregisterViewRequest {
+            catalog: DEFAULT_CATALOG_NAME.to_string(),
+            schema: DEFAULT_SCHEMA_NAME.to_string(),
+            view_name: "test_view".to_string(),
+        };
+        assert!(catalog_manager
+            .deregister_view(request.clone())
+            .await
+            .unwrap());
+        assert!(!catalog_manager.deregister_view(request).await.unwrap());
+        assert!(backend.get(view_key.as_bytes()).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_register_catalog_schema_table() {
         let node_id = 42;
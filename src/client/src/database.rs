@@ -12,21 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
 use api::v1::auth_header::AuthScheme;
 use api::v1::ddl_request::Expr as DdlExpr;
 use api::v1::greptime_request::Request;
 use api::v1::query_request::Query;
 use api::v1::{
     greptime_response, AffectedRows, AlterExpr, AuthHeader, CreateTableExpr, DdlRequest,
-    DropTableExpr, FlushTableExpr, GreptimeRequest, InsertRequest, PromRangeQuery, QueryRequest,
-    RequestHeader,
+    DropTableExpr, FlightMetadata, FlushTableExpr, GreptimeRequest, InsertRequest, PromRangeQuery,
+    QueryRequest, RequestHeader,
 };
-use arrow_flight::{FlightData, Ticket};
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::{FlightData, FlightDescriptor, PutResult, Ticket};
 use common_error::prelude::*;
-use common_grpc::flight::{flight_messages_to_recordbatches, FlightDecoder, FlightMessage};
+use common_grpc::flight::{
+    flight_messages_to_recordbatches, FlightDecoder, FlightEncoder, FlightMessage,
+};
 use common_query::Output;
+use common_recordbatch::SendableRecordBatchStream;
 use common_telemetry::logging;
-use futures_util::{TryFutureExt, TryStreamExt};
+use futures_util::{StreamExt, TryFutureExt, TryStreamExt};
+use parking_lot::Mutex;
 use prost::Message;
 use snafu::{ensure, ResultExt};
 
@@ -129,6 +136,107 @@ impl Database {
         Ok(value)
     }
 
+    /// Inserts the record batches into the table by Flight `DoPut`, which creates or alters the
+    /// table like [Database::insert] does, and returns the affected rows.
+    ///
+    /// The columns are tags, fields or the timestamp as the
+    /// [SEMANTIC_TYPE_KEY](common_grpc::flight::SEMANTIC_TYPE_KEY) in their metadata says.
+    pub async fn bulk_insert(
+        &self,
+        table_name: &str,
+        mut recordbatches: SendableRecordBatchStream,
+    ) -> Result<u32> {
+        let request = GreptimeRequest {
+            header: Some(RequestHeader {
+                catalog: self.catalog.clone(),
+                schema: self.schema.clone(),
+                authorization: self.ctx.auth_header.clone(),
+                dbname: self.dbname.clone(),
+            }),
+            request: Some(Request::Insert(InsertRequest {
+                table_name: table_name.to_string(),
+                ..Default::default()
+            })),
+        };
+        let descriptor = FlightDescriptor {
+            r#type: DescriptorType::Cmd as i32,
+            cmd: request.encode_to_vec().into(),
+            path: vec![],
+        };
+
+        // The error of reading the record batches ends the stream, and is returned after the
+        // put is done.
+        let read_error = Arc::new(Mutex::new(None));
+        let stream_read_error = read_error.clone();
+        let flight_data = async_stream::stream! {
+            let mut encoder = FlightEncoder::default();
+            let mut schema = encoder.encode(FlightMessage::Schema(recordbatches.schema()));
+            schema.flight_descriptor = Some(descriptor);
+            yield schema;
+
+            while let Some(recordbatch) = recordbatches.next().await {
+                match recordbatch {
+                    Ok(recordbatch) => {
+                        yield encoder.encode(FlightMessage::Recordbatch(recordbatch));
+                    }
+                    Err(e) => {
+                        *stream_read_error.lock() = Some(e);
+                        break;
+                    }
+                }
+            }
+        };
+
+        let mut client = self.client.make_flight_client()?;
+        let results: Vec<PutResult> = client
+            .mut_inner()
+            .do_put(flight_data)
+            .and_then(|response| response.into_inner().try_collect())
+            .await
+            .map_err(|e| {
+                let tonic_code = e.code();
+                let e: error::Error = e.into();
+                let code = e.status_code();
+                let msg = e.to_string();
+                error::ServerSnafu { code, msg }
+                    .fail::<()>()
+                    .map_err(BoxedError::new)
+                    .context(error::FlightPutSnafu {
+                        tonic_code,
+                        addr: client.addr(),
+                    })
+                    .map_err(|error| {
+                        logging::error!(
+                            "Failed to do Flight put, addr: {}, code: {}, source: {}",
+                            client.addr(),
+                            tonic_code,
+                            error
+                        );
+                        error
+                    })
+                    .unwrap_err()
+            })?;
+
+        if let Some(e) = read_error.lock().take() {
+            return Err(e).context(error::ReadRecordBatchSnafu);
+        }
+
+        let mut affected_rows = 0;
+        for result in results {
+            let metadata = FlightMetadata::decode(result.app_metadata).map_err(|e| {
+                IllegalFlightMessagesSnafu {
+                    reason: format!("Failed to decode FlightMetadata: {e}"),
+                }
+                .build()
+            })?;
+            let affected = metadata.affected_rows.context(IllegalFlightMessagesSnafu {
+                reason: "Expect 'AffectedRows' in the put result!",
+            })?;
+            affected_rows += affected.value;
+        }
+        Ok(affected_rows)
+    }
+
     pub async fn sql(&self, sql: &str) -> Result<Output> {
         self.do_get(Request::Query(QueryRequest {
             query: Some(Query::Sql(sql.to_string())),
@@ -32,6 +32,19 @@ pub enum Error {
         source: BoxedError,
     },
 
+    #[snafu(display("Failed to do Flight put, code: {}, source: {}", tonic_code, source))]
+    FlightPut {
+        addr: String,
+        tonic_code: Code,
+        source: BoxedError,
+    },
+
+    #[snafu(display("Failed to read the record batches to insert, source: {}", source))]
+    ReadRecordBatch {
+        #[snafu(backtrace)]
+        source: common_recordbatch::error::Error,
+    },
+
     #[snafu(display("Failed to convert FlightData, source: {}", source))]
     ConvertFlightData {
         #[snafu(backtrace)]
@@ -80,7 +93,10 @@ impl ErrorExt for Error {
             | Error::IllegalDatabaseResponse { .. } => StatusCode::Internal,
 
             Error::Server { code, .. } => *code,
-            Error::FlightGet { source, .. } => source.status_code(),
+            Error::FlightGet { source, .. } | Error::FlightPut { source, .. } => {
+                source.status_code()
+            }
+            Error::ReadRecordBatch { source } => source.status_code(),
             Error::CreateChannel { source, .. } | Error::ConvertFlightData { source } => {
                 source.status_code()
             }
@@ -108,12 +108,14 @@ pub struct StartCommand {
     #[clap(long)]
     user_provider: Option<String>,
     #[clap(long)]
+    admin_user: Option<String>,
+    #[clap(long)]
     disable_dashboard: bool,
 }
 
 impl StartCommand {
     async fn build(self) -> Result<Instance> {
-        let plugins = Arc::new(load_frontend_plugins(&self.user_provider)?);
+        let plugins = Arc::new(load_frontend_plugins(&self.user_provider, &self.admin_user)?);
         let opts: FrontendOptions = self.try_into()?;
 
         let mut instance = FeInstance::try_new_distributed(&opts, plugins.clone())
@@ -129,11 +131,15 @@ impl StartCommand {
     }
 }
 
-pub fn load_frontend_plugins(user_provider: &Option<String>) -> Result<Plugins> {
+pub fn load_frontend_plugins(
+    user_provider: &Option<String>,
+    admin_user: &Option<String>,
+) -> Result<Plugins> {
     let mut plugins = Plugins::new();
 
     if let Some(provider) = user_provider {
-        let provider = auth::user_provider_from_option(provider).context(IllegalAuthConfigSnafu)?;
+        let provider = auth::user_provider_from_option(provider, admin_user.clone())
+            .context(IllegalAuthConfigSnafu)?;
         plugins.insert::<UserProviderRef>(provider);
     }
     Ok(plugins)
@@ -235,6 +241,7 @@ mod tests {
             tls_cert_path: None,
             tls_key_path: None,
             user_provider: None,
+            admin_user: None,
             disable_dashboard: false,
         };
 
@@ -298,6 +305,7 @@ mod tests {
             tls_cert_path: None,
             tls_key_path: None,
             user_provider: None,
+            admin_user: None,
             disable_dashboard: false,
         };
 
@@ -329,10 +337,11 @@ mod tests {
             tls_cert_path: None,
             tls_key_path: None,
             user_provider: Some("static_user_provider:cmd:test=test".to_string()),
+            admin_user: None,
             disable_dashboard: false,
         };
 
-        let plugins = load_frontend_plugins(&command.user_provider);
+        let plugins = load_frontend_plugins(&command.user_provider, &command.admin_user);
         assert!(plugins.is_ok());
         let plugins = plugins.unwrap();
         let provider = plugins.get::<UserProviderRef>();
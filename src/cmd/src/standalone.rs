@@ -19,15 +19,20 @@ use common_base::Plugins;
 use common_telemetry::info;
 use datanode::datanode::{Datanode, DatanodeOptions, ProcedureConfig, StorageConfig, WalConfig};
 use datanode::instance::InstanceRef;
+use frontend::flight_sql::FlightSqlOptions;
 use frontend::frontend::FrontendOptions;
+use frontend::graphite::GraphiteOptions;
 use frontend::grpc::GrpcOptions;
 use frontend::influxdb::InfluxdbOptions;
 use frontend::instance::{FrontendInstance, Instance as FeInstance};
 use frontend::mysql::MysqlOptions;
 use frontend::opentsdb::OpentsdbOptions;
+use frontend::otlp::OtlpOptions;
 use frontend::postgres::PostgresOptions;
 use frontend::prom::PromOptions;
 use frontend::prometheus::PrometheusOptions;
+use frontend::query_limit::QueryLimitOptions;
+use frontend::rule::RuleOptions;
 use serde::{Deserialize, Serialize};
 use servers::http::HttpOptions;
 use servers::tls::{TlsMode, TlsOption};
@@ -79,6 +84,11 @@ pub struct StandaloneOptions {
     pub influxdb_options: Option<InfluxdbOptions>,
     pub prometheus_options: Option<PrometheusOptions>,
     pub prom_options: Option<PromOptions>,
+    pub otlp_options: Option<OtlpOptions>,
+    pub graphite_options: Option<GraphiteOptions>,
+    pub flight_sql_options: Option<FlightSqlOptions>,
+    pub query_limit_options: QueryLimitOptions,
+    pub rule_options: RuleOptions,
     pub wal: WalConfig,
     pub storage: StorageConfig,
     pub procedure: Option<ProcedureConfig>,
@@ -97,6 +107,11 @@ impl Default for StandaloneOptions {
             influxdb_options: Some(InfluxdbOptions::default()),
             prometheus_options: Some(PrometheusOptions::default()),
             prom_options: Some(PromOptions::default()),
+            otlp_options: Some(OtlpOptions::default()),
+            graphite_options: None,
+            flight_sql_options: None,
+            query_limit_options: QueryLimitOptions::default(),
+            rule_options: RuleOptions::default(),
             wal: WalConfig::default(),
             storage: StorageConfig::default(),
             procedure: None,
@@ -116,7 +131,12 @@ impl StandaloneOptions {
             influxdb_options: self.influxdb_options,
             prometheus_options: self.prometheus_options,
             prom_options: self.prom_options,
+            otlp_options: self.otlp_options,
+            graphite_options: self.graphite_options,
+            flight_sql_options: self.flight_sql_options,
             meta_client_options: None,
+            query_limit_options: self.query_limit_options,
+            rule_options: self.rule_options,
         }
     }
 
@@ -193,13 +213,15 @@ struct StartCommand {
     tls_key_path: Option<String>,
     #[clap(long)]
     user_provider: Option<String>,
+    #[clap(long)]
+    admin_user: Option<String>,
 }
 
 impl StartCommand {
     async fn build(self) -> Result<Instance> {
         let enable_memory_catalog = self.enable_memory_catalog;
         let config_file = self.config_file.clone();
-        let plugins = Arc::new(load_frontend_plugins(&self.user_provider)?);
+        let plugins = Arc::new(load_frontend_plugins(&self.user_provider, &self.admin_user)?);
         let fe_opts = FrontendOptions::try_from(self)?;
         let dn_opts: DatanodeOptions = {
             let mut opts: StandaloneOptions = if let Some(path) = config_file {
@@ -221,6 +243,10 @@ impl StartCommand {
             .context(StartDatanodeSnafu)?;
 
         let mut frontend = build_frontend(plugins.clone(), datanode.get_instance()).await?;
+        frontend.set_default_query_limits((&fe_opts.query_limit_options).into());
+        frontend
+            .load_rules(&fe_opts.rule_options)
+            .context(StartFrontendSnafu)?;
 
         frontend
             .build_servers(&fe_opts, plugins)
@@ -352,6 +378,7 @@ mod tests {
             tls_cert_path: None,
             tls_key_path: None,
             user_provider: None,
+            admin_user: None,
         };
 
         let fe_opts = FrontendOptions::try_from(cmd).unwrap();
@@ -396,9 +423,10 @@ mod tests {
             tls_cert_path: None,
             tls_key_path: None,
             user_provider: Some("static_user_provider:cmd:test=test".to_string()),
+            admin_user: None,
         };
 
-        let plugins = load_frontend_plugins(&command.user_provider);
+        let plugins = load_frontend_plugins(&command.user_provider, &command.admin_user);
         assert!(plugins.is_ok());
         let plugins = plugins.unwrap();
         let provider = plugins.get::<UserProviderRef>();
@@ -25,5 +25,13 @@ pub const MIN_USER_TABLE_ID: u32 = 1024;
 pub const SYSTEM_CATALOG_TABLE_ID: u32 = 0;
 /// scripts table id
 pub const SCRIPTS_TABLE_ID: u32 = 1;
+/// prometheus metric metadata table id
+pub const PROMETHEUS_METRIC_METADATA_TABLE_ID: u32 = 2;
+/// prometheus exemplars table id
+pub const PROMETHEUS_EXEMPLARS_TABLE_ID: u32 = 3;
+/// prometheus metric metadata table name
+pub const PROMETHEUS_METRIC_METADATA_TABLE_NAME: &str = "prometheus_metric_metadata";
+/// prometheus exemplars table name
+pub const PROMETHEUS_EXEMPLARS_TABLE_NAME: &str = "prometheus_exemplars";
 
 pub const MITO_ENGINE: &str = "mito";
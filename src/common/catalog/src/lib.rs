@@ -12,6 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::consts::{
+    DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, PROMETHEUS_EXEMPLARS_TABLE_NAME,
+    PROMETHEUS_METRIC_METADATA_TABLE_NAME,
+};
+
 pub mod consts;
 pub mod error;
 
@@ -20,3 +25,14 @@ pub mod error;
 pub fn format_full_table_name(catalog: &str, schema: &str, table: &str) -> String {
     format!("{catalog}.{schema}.{table}")
 }
+
+/// Returns true if the table is a system table stored in a user schema, which is left out of the
+/// tables listed for the schema.
+pub fn is_hidden_table(catalog: &str, schema: &str, table: &str) -> bool {
+    catalog == DEFAULT_CATALOG_NAME
+        && schema == DEFAULT_SCHEMA_NAME
+        && matches!(
+            table,
+            PROMETHEUS_METRIC_METADATA_TABLE_NAME | PROMETHEUS_EXEMPLARS_TABLE_NAME
+        )
+}
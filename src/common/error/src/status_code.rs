@@ -45,6 +45,12 @@ pub enum StatusCode {
     PlanQuery = 3000,
     /// The query engine fail to execute query.
     EngineExecuteQuery = 3001,
+    /// The query is cancelled, e.g. by `KILL QUERY`.
+    Cancelled = 3002,
+    /// The query runs longer than the statement timeout.
+    QueryTimeout = 3003,
+    /// The query uses more memory than its limit.
+    ExceedMemoryLimit = 3004,
     // ====== End of query related status code =========
 
     // ====== Begin of catalog related status code =====
@@ -101,6 +107,9 @@ impl StatusCode {
             | StatusCode::InvalidSyntax
             | StatusCode::PlanQuery
             | StatusCode::EngineExecuteQuery
+            | StatusCode::Cancelled
+            | StatusCode::QueryTimeout
+            | StatusCode::ExceedMemoryLimit
             | StatusCode::TableAlreadyExists
             | StatusCode::TableNotFound
             | StatusCode::TableColumnNotFound
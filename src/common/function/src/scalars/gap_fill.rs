@@ -0,0 +1,92 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functions to fill the time buckets that have no rows in aggregating queries, e.g.
+//! `SELECT time_bucket_gapfill('1m', ts, '2023-01-01 00:00:00', '2023-01-01 01:00:00') AS minute,
+//! locf(avg(usage)) FROM cpu GROUP BY minute`.
+//!
+//! The functions only mark what to fill. The query engine fills the buckets by rewriting the
+//! aggregating queries that group rows by `time_bucket_gapfill`.
+
+mod interpolate;
+mod locf;
+mod time_bucket_gapfill;
+
+use std::sync::Arc;
+
+use common_time::timestamp::TimeUnit;
+pub use interpolate::InterpolateFunction;
+pub use locf::LocfFunction;
+pub use time_bucket_gapfill::TimeBucketGapfillFunction;
+
+use crate::scalars::function_registry::FunctionRegistry;
+
+pub const TIME_BUCKET_GAPFILL: &str = "time_bucket_gapfill";
+pub const LOCF: &str = "locf";
+pub const INTERPOLATE: &str = "interpolate";
+
+pub(crate) struct GapFillFunction;
+
+impl GapFillFunction {
+    pub fn register(registry: &FunctionRegistry) {
+        registry.register(Arc::new(TimeBucketGapfillFunction::default()));
+        registry.register(Arc::new(LocfFunction::default()));
+        registry.register(Arc::new(InterpolateFunction::default()));
+    }
+}
+
+/// Parses the width of time buckets like `5m` or `5 minutes` into the number of `unit`s.
+///
+/// Returns `None` if it's not a duration or it's less than one `unit`.
+pub fn parse_bucket_width(width: &str, unit: TimeUnit) -> Option<i64> {
+    // humantime doesn't allow spaces between numbers and units, like "5 minutes".
+    let width = width.split_whitespace().collect::<String>();
+    let width = humantime::parse_duration(&width).ok()?;
+    i64::try_from(width.as_nanos() / unit.factor() as u128)
+        .ok()
+        .filter(|x| *x > 0)
+}
+
+/// Returns the start of the bucket that `ts` is in, buckets are aligned to the unix epoch.
+pub fn bucket_start(ts: i64, width: i64) -> i64 {
+    ts - ts.rem_euclid(width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bucket_width() {
+        assert_eq!(
+            Some(300_000),
+            parse_bucket_width("5m", TimeUnit::Millisecond)
+        );
+        assert_eq!(Some(300), parse_bucket_width("5 minutes", TimeUnit::Second));
+        assert_eq!(
+            Some(1_500_000),
+            parse_bucket_width("1s 500ms", TimeUnit::Microsecond)
+        );
+        assert_eq!(None, parse_bucket_width("500ms", TimeUnit::Second));
+        assert_eq!(None, parse_bucket_width("0s", TimeUnit::Nanosecond));
+        assert_eq!(None, parse_bucket_width("five minutes", TimeUnit::Second));
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        assert_eq!(0, bucket_start(0, 60));
+        assert_eq!(60, bucket_start(119, 60));
+        assert_eq!(-60, bucket_start(-1, 60));
+    }
+}
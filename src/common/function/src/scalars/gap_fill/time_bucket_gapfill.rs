@@ -0,0 +1,173 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::sync::Arc;
+
+use common_query::error::{self, Result, UnsupportedInputDataTypeSnafu};
+use common_query::prelude::{Signature, Volatility};
+use datatypes::arrow::array::{ArrayRef, Int64Array};
+use datatypes::arrow::compute::kernels::cast;
+use datatypes::arrow::datatypes::DataType;
+use datatypes::prelude::*;
+use datatypes::vectors::{Helper, VectorRef};
+use snafu::{ensure, OptionExt, ResultExt};
+
+use crate::scalars::function::{Function, FunctionContext};
+use crate::scalars::gap_fill::{bucket_start, parse_bucket_width, TIME_BUCKET_GAPFILL};
+
+/// `time_bucket_gapfill(width, ts, start, end)` returns the start of the bucket that `ts` is in.
+///
+/// `start` and `end` are the time range to fill, they are used by the query engine when the
+/// function is in `GROUP BY`, and ignored here.
+#[derive(Clone, Debug, Default)]
+pub struct TimeBucketGapfillFunction;
+
+impl fmt::Display for TimeBucketGapfillFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TIME_BUCKET_GAPFILL")
+    }
+}
+
+impl Function for TimeBucketGapfillFunction {
+    fn name(&self) -> &str {
+        TIME_BUCKET_GAPFILL
+    }
+
+    fn return_type(&self, input_types: &[ConcreteDataType]) -> Result<ConcreteDataType> {
+        match input_types.get(1) {
+            Some(ts_type @ ConcreteDataType::Timestamp(_)) => Ok(ts_type.clone()),
+            _ => UnsupportedInputDataTypeSnafu {
+                function: TIME_BUCKET_GAPFILL,
+                datatypes: input_types.to_vec(),
+            }
+            .fail(),
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        Signature::any(4, Volatility::Immutable)
+    }
+
+    fn eval(&self, _func_ctx: FunctionContext, columns: &[VectorRef]) -> Result<VectorRef> {
+        ensure!(
+            columns.len() == 4,
+            error::InvalidFuncArgsSnafu {
+                err_msg: format!(
+                    "The length of the args is not correct, expect exactly four, have: {}",
+                    columns.len()
+                ),
+            }
+        );
+        let ts = &columns[1];
+        let ConcreteDataType::Timestamp(ts_type) = ts.data_type() else {
+            return UnsupportedInputDataTypeSnafu {
+                function: TIME_BUCKET_GAPFILL,
+                datatypes: columns.iter().map(|c| c.data_type()).collect::<Vec<_>>(),
+            }
+            .fail();
+        };
+        if ts.is_empty() {
+            return Ok(ts.clone());
+        }
+
+        let width = columns[0].get(0);
+        let width = match &width {
+            Value::String(s) => parse_bucket_width(s.as_utf8(), ts_type.unit()),
+            _ => None,
+        }
+        .with_context(|| error::InvalidFuncArgsSnafu {
+            err_msg: format!(
+                "Invalid bucket width {width:?}, expect a duration like '5m' that is no less than one {:?}",
+                ts_type.unit()
+            ),
+        })?;
+
+        let array = ts.to_arrow_array();
+        let values = cast::cast(&array, &DataType::Int64).context(error::TypeCastSnafu {
+            typ: DataType::Int64,
+        })?;
+        // Safety: it's just casted to Int64.
+        let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+        let buckets: ArrayRef = Arc::new(
+            values
+                .iter()
+                .map(|v| v.map(|v| bucket_start(v, width)))
+                .collect::<Int64Array>(),
+        );
+        let buckets = cast::cast(&buckets, array.data_type()).context(error::TypeCastSnafu {
+            typ: array.data_type().clone(),
+        })?;
+        Helper::try_into_vector(buckets).context(error::FromArrowArraySnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datatypes::vectors::{ConstantVector, StringVector, TimestampSecondVector};
+
+    use super::*;
+
+    #[test]
+    fn test_time_bucket_gapfill() {
+        let f = TimeBucketGapfillFunction::default();
+        assert_eq!(TIME_BUCKET_GAPFILL, f.name());
+        let ts_type = ConcreteDataType::timestamp_second_datatype();
+        let string_type = ConcreteDataType::string_datatype();
+        assert_eq!(
+            ts_type,
+            f.return_type(&[
+                string_type.clone(),
+                ts_type.clone(),
+                string_type.clone(),
+                string_type.clone()
+            ])
+            .unwrap()
+        );
+        assert!(f
+            .return_type(&[
+                string_type.clone(),
+                string_type.clone(),
+                string_type.clone(),
+                string_type
+            ])
+            .is_err());
+
+        let constant = |s: &str| -> VectorRef {
+            Arc::new(ConstantVector::new(
+                Arc::new(StringVector::from(vec![s])),
+                4,
+            ))
+        };
+        let ts: VectorRef = Arc::new(TimestampSecondVector::from(vec![
+            Some(0),
+            Some(59),
+            None,
+            Some(-1),
+        ]));
+        let args = vec![constant("1 minute"), ts.clone(), constant(""), constant("")];
+        let vector = f.eval(FunctionContext::default(), &args).unwrap();
+        let expected: VectorRef = Arc::new(TimestampSecondVector::from(vec![
+            Some(0),
+            Some(0),
+            None,
+            Some(-60),
+        ]));
+        assert_eq!(expected, vector);
+
+        let args = vec![constant("1ms"), ts, constant(""), constant("")];
+        let err = f.eval(FunctionContext::default(), &args).unwrap_err();
+        assert!(err.to_string().contains("Invalid bucket width"), "{err}");
+    }
+}
@@ -15,7 +15,9 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use api::v1::{AffectedRows, FlightMetadata};
+use api::helper::ColumnDataTypeWrapper;
+use api::v1::column::SemanticType;
+use api::v1::{AffectedRows, Column, FlightMetadata};
 use arrow_flight::utils::flight_data_to_arrow_batch;
 use arrow_flight::{FlightData, IpcMessage, SchemaAsIpc};
 use common_base::bytes::Bytes;
@@ -23,15 +25,24 @@ use common_recordbatch::{RecordBatch, RecordBatches};
 use datatypes::arrow;
 use datatypes::arrow::datatypes::Schema as ArrowSchema;
 use datatypes::arrow::ipc::{root_as_message, writer, MessageHeader};
+use datatypes::prelude::ConcreteDataType;
 use datatypes::schema::{Schema, SchemaRef};
 use flatbuffers::FlatBufferBuilder;
 use prost::Message;
 use snafu::{OptionExt, ResultExt};
 
 use crate::error::{
-    ConvertArrowSchemaSnafu, CreateRecordBatchSnafu, DecodeFlightDataSnafu, InvalidFlightDataSnafu,
-    Result,
+    ColumnDataTypeSnafu, ConvertArrowSchemaSnafu, CreateRecordBatchSnafu, DecodeFlightDataSnafu,
+    InvalidFlightDataSnafu, Result,
 };
+use crate::select;
+
+/// The key in the metadata of an Arrow field to mark the semantic type of the column written by
+/// Flight `DoPut`, whose value is one of `TAG`, `FIELD` and `TIMESTAMP`.
+///
+/// Without it, the time index column is the timestamp, or the first timestamp column if there
+/// is no time index, and the others are fields.
+pub const SEMANTIC_TYPE_KEY: &str = "greptime:semantic_type";
 
 #[derive(Debug, Clone)]
 pub enum FlightMessage {
@@ -198,6 +209,65 @@ pub fn flight_messages_to_recordbatches(messages: Vec<FlightMessage>) -> Result<
     }
 }
 
+/// Describes the columns of the record batch as those of an insert request, without their values,
+/// to create or alter the table on insertion.
+pub fn recordbatch_to_column_defs(recordbatch: &RecordBatch) -> Result<Vec<Column>> {
+    let column_schemas = recordbatch.schema.column_schemas();
+    let semantic_types = column_schemas
+        .iter()
+        .map(
+            |column_schema| match column_schema.metadata().get(SEMANTIC_TYPE_KEY) {
+                Some(semantic_type) => match semantic_type.to_ascii_uppercase().as_str() {
+                    "TAG" => Ok(Some(SemanticType::Tag)),
+                    "FIELD" => Ok(Some(SemanticType::Field)),
+                    "TIMESTAMP" => Ok(Some(SemanticType::Timestamp)),
+                    _ => InvalidFlightDataSnafu {
+                        reason: format!(
+                            "Unknown semantic type '{semantic_type}' of column '{}'",
+                            column_schema.name
+                        ),
+                    }
+                    .fail(),
+                },
+                None if column_schema.is_time_index() => Ok(Some(SemanticType::Timestamp)),
+                None => Ok(None),
+            },
+        )
+        .collect::<Result<Vec<_>>>()?;
+    let timestamp_index = if semantic_types.contains(&Some(SemanticType::Timestamp)) {
+        None
+    } else {
+        column_schemas.iter().position(|column_schema| {
+            matches!(column_schema.data_type, ConcreteDataType::Timestamp(_))
+        })
+    };
+
+    let row_count = recordbatch.num_rows();
+    column_schemas
+        .iter()
+        .zip(recordbatch.columns())
+        .zip(semantic_types)
+        .enumerate()
+        .map(|(i, ((column_schema, vector), semantic_type))| {
+            let datatype = ColumnDataTypeWrapper::try_from(column_schema.data_type.clone())
+                .context(ColumnDataTypeSnafu)?
+                .datatype();
+            let semantic_type = match semantic_type {
+                Some(semantic_type) => semantic_type,
+                None if timestamp_index == Some(i) => SemanticType::Timestamp,
+                None => SemanticType::Field,
+            };
+            Ok(Column {
+                column_name: column_schema.name.clone(),
+                semantic_type: semantic_type as i32,
+                values: None,
+                null_mask: select::null_mask(&[vector.clone()], row_count),
+                datatype: datatype as i32,
+            })
+        })
+        .collect()
+}
+
 fn build_none_flight_msg() -> Bytes {
     let mut builder = FlatBufferBuilder::new();
 
@@ -214,11 +284,14 @@ fn build_none_flight_msg() -> Bytes {
 
 #[cfg(test)]
 mod test {
+    use api::v1::ColumnDataType;
     use arrow_flight::utils::batches_to_flight_data;
     use datatypes::arrow::datatypes::{DataType, Field};
     use datatypes::prelude::ConcreteDataType;
     use datatypes::schema::ColumnSchema;
-    use datatypes::vectors::Int32Vector;
+    use datatypes::vectors::{
+        Float64Vector, Int32Vector, StringVector, TimestampMillisecondVector,
+    };
 
     use super::*;
     use crate::Error;
@@ -319,4 +392,55 @@ mod test {
         let actual = flight_messages_to_recordbatches(vec![m1, m2, m3]).unwrap();
         assert_eq!(actual, recordbatches);
     }
+
+    #[test]
+    fn test_recordbatch_to_column_defs() {
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new("host", ConcreteDataType::string_datatype(), true)
+                .with_metadata([(SEMANTIC_TYPE_KEY.to_string(), "tag".to_string())].into()),
+            ColumnSchema::new("cpu", ConcreteDataType::float64_datatype(), true),
+            ColumnSchema::new(
+                "ts",
+                ConcreteDataType::timestamp_millisecond_datatype(),
+                false,
+            ),
+        ]));
+        let recordbatch = RecordBatch::new(
+            schema,
+            vec![
+                Arc::new(StringVector::from(vec!["host1", "host2"])) as _,
+                Arc::new(Float64Vector::from(vec![Some(0.5), None])) as _,
+                Arc::new(TimestampMillisecondVector::from_vec(vec![1000, 2000])) as _,
+            ],
+        )
+        .unwrap();
+
+        let columns = recordbatch_to_column_defs(&recordbatch).unwrap();
+        assert_eq!(
+            vec![
+                Column {
+                    column_name: "host".to_string(),
+                    semantic_type: SemanticType::Tag as i32,
+                    values: None,
+                    null_mask: vec![],
+                    datatype: ColumnDataType::String as i32,
+                },
+                Column {
+                    column_name: "cpu".to_string(),
+                    semantic_type: SemanticType::Field as i32,
+                    values: None,
+                    null_mask: vec![2],
+                    datatype: ColumnDataType::Float64 as i32,
+                },
+                Column {
+                    column_name: "ts".to_string(),
+                    semantic_type: SemanticType::Timestamp as i32,
+                    values: None,
+                    null_mask: vec![],
+                    datatype: ColumnDataType::TimestampMillisecond as i32,
+                },
+            ],
+            columns
+        );
+    }
 }
@@ -17,6 +17,7 @@ use std::any::Any;
 
 use common_error::ext::BoxedError;
 use common_error::prelude::*;
+use datafusion::error::DataFusionError;
 use datatypes::prelude::ConcreteDataType;
 use snafu::Location;
 
@@ -54,7 +55,7 @@ pub enum Error {
 
     #[snafu(display("Failed to poll stream, source: {}", source))]
     PollStream {
-        source: datafusion::error::DataFusionError,
+        source: DataFusionError,
         location: Location,
     },
 
@@ -89,6 +90,15 @@ pub enum Error {
         #[snafu(backtrace)]
         source: datatypes::error::Error,
     },
+
+    #[snafu(display("Stream is cancelled"))]
+    StreamCancelled { location: Location },
+
+    #[snafu(display("Stream timed out after {:?}", timeout))]
+    StreamTimeout {
+        timeout: std::time::Duration,
+        location: Location,
+    },
 }
 
 impl ErrorExt for Error {
@@ -96,6 +106,12 @@ impl ErrorExt for Error {
         match self {
             Error::NewDfRecordBatch { .. } => StatusCode::InvalidArguments,
 
+            // The memory pool of a query with memory limit fails the allocation beyond the limit.
+            Error::PollStream {
+                source: DataFusionError::ResourcesExhausted(_),
+                ..
+            } => StatusCode::ExceedMemoryLimit,
+
             Error::DataTypes { .. }
             | Error::CreateRecordBatches { .. }
             | Error::PollStream { .. }
@@ -105,6 +121,9 @@ impl ErrorExt for Error {
 
             Error::External { source } => source.status_code(),
 
+            Error::StreamCancelled { .. } => StatusCode::Cancelled,
+            Error::StreamTimeout { .. } => StatusCode::QueryTimeout,
+
             Error::SchemaConversion { source, .. } | Error::CastVector { source, .. } => {
                 source.status_code()
             }
@@ -304,6 +304,9 @@ pub enum Error {
     #[snafu(display("Schema {} already exists", name))]
     SchemaExists { name: String, location: Location },
 
+    #[snafu(display("View {} already exists", name))]
+    ViewExists { name: String, location: Location },
+
     #[snafu(display("Failed to convert alter expr to request: {}", source))]
     AlterExprToRequest {
         #[snafu(backtrace)]
@@ -540,6 +543,7 @@ impl ErrorExt for Error {
             Delete { source, .. } => source.status_code(),
             TableEngineNotFound { source, .. } => source.status_code(),
             TableNotFound { .. } => StatusCode::TableNotFound,
+            ViewExists { .. } => StatusCode::TableAlreadyExists,
             ColumnNotFound { .. } => StatusCode::TableColumnNotFound,
 
             ParseSqlValue { source, .. } | ParseSql { source, .. } => source.status_code(),
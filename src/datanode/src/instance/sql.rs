@@ -179,6 +179,16 @@ impl Instance {
             | QueryStatement::Sql(Statement::Tql(_))
             | QueryStatement::Sql(Statement::Delete(_))
             | QueryStatement::Sql(Statement::DescribeTable(_))
+            | QueryStatement::Sql(Statement::ShowProcesslist(_))
+            | QueryStatement::Sql(Statement::Kill(_))
+            | QueryStatement::Sql(Statement::SetVariables(_))
+            | QueryStatement::Sql(Statement::LoadData(_))
+            | QueryStatement::Sql(Statement::CopyStdio(_))
+            | QueryStatement::Sql(Statement::CreateMaterializedView(_))
+            | QueryStatement::Sql(Statement::DropMaterializedView(_))
+            | QueryStatement::Sql(Statement::CreateView(_))
+            | QueryStatement::Sql(Statement::DropView(_))
+            | QueryStatement::Sql(Statement::ShowCreateView(_))
             | QueryStatement::Promql(_) => unreachable!(),
         }
     }
@@ -46,6 +46,10 @@ impl SqlHandler {
             }
         );
         let is_rename = req.is_rename_table();
+        if let AlterKind::RenameTable { new_table_name } = &req.alter_kind {
+            self.ensure_not_view(&req.catalog_name, &req.schema_name, new_table_name)
+                .await?;
+        }
 
         let table = table_engine
             .alter_table(&ctx, req)
@@ -15,6 +15,7 @@
 use std::collections::HashMap;
 
 use catalog::{RegisterSchemaRequest, RegisterTableRequest};
+use common_catalog::format_full_table_name;
 use common_procedure::{watcher, ProcedureManagerRef, ProcedureWithId};
 use common_query::Output;
 use common_telemetry::tracing::{error, info};
@@ -34,7 +35,7 @@ use crate::error::{
     self, CatalogNotFoundSnafu, CatalogSnafu, ConstraintNotSupportedSnafu, CreateTableSnafu,
     IllegalPrimaryKeysDefSnafu, InsertSystemCatalogSnafu, KeyColumnNotFoundSnafu,
     RegisterSchemaSnafu, Result, SchemaExistsSnafu, SchemaNotFoundSnafu, SubmitProcedureSnafu,
-    TableEngineNotFoundSnafu, UnrecognizedTableOptionSnafu, WaitProcedureSnafu,
+    TableEngineNotFoundSnafu, UnrecognizedTableOptionSnafu, ViewExistsSnafu, WaitProcedureSnafu,
 };
 use crate::sql::SqlHandler;
 
@@ -73,6 +74,8 @@ impl SqlHandler {
     }
 
     pub(crate) async fn create_table(&self, req: CreateTableRequest) -> Result<Output> {
+        self.ensure_not_view(&req.catalog_name, &req.schema_name, &req.table_name)
+            .await?;
         if let Some(procedure_manager) = &self.procedure_manager {
             return self.create_table_by_procedure(procedure_manager, req).await;
         }
@@ -138,6 +141,27 @@ impl SqlHandler {
         Ok(Output::AffectedRows(0))
     }
 
+    /// Tables and views share their names, so a table can't take the name of a view.
+    pub(crate) async fn ensure_not_view(
+        &self,
+        catalog: &str,
+        schema: &str,
+        table_name: &str,
+    ) -> Result<()> {
+        let view = self
+            .catalog_manager
+            .view(catalog, schema, table_name)
+            .await
+            .context(CatalogSnafu)?;
+        ensure!(
+            view.is_none(),
+            ViewExistsSnafu {
+                name: format_full_table_name(catalog, schema, table_name),
+            }
+        );
+        Ok(())
+    }
+
     pub(crate) async fn create_table_by_procedure(
         &self,
         procedure_manager: &ProcedureManagerRef,
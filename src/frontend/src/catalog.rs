@@ -20,18 +20,19 @@ use api::v1::CreateTableExpr;
 use async_trait::async_trait;
 use catalog::error::{
     self as catalog_err, InternalSnafu, InvalidCatalogValueSnafu, InvalidSystemTableDefSnafu,
-    Result as CatalogResult, UnimplementedSnafu,
+    Result as CatalogResult, TableExistsSnafu, UnimplementedSnafu, ViewExistsSnafu,
 };
 use catalog::helper::{
-    build_catalog_prefix, build_schema_prefix, build_table_global_prefix, CatalogKey, SchemaKey,
-    TableGlobalKey, TableGlobalValue,
+    build_catalog_prefix, build_schema_prefix, build_table_global_prefix, build_view_prefix,
+    CatalogKey, SchemaKey, TableGlobalKey, TableGlobalValue, ViewKey, ViewValue,
 };
 use catalog::remote::{Kv, KvBackendRef};
 use catalog::{
     CatalogList, CatalogManager, CatalogProvider, CatalogProviderRef, DeregisterTableRequest,
-    RegisterSchemaRequest, RegisterSystemTableRequest, RegisterTableRequest, RenameTableRequest,
-    SchemaProvider, SchemaProviderRef,
+    DeregisterViewRequest, RegisterSchemaRequest, RegisterSystemTableRequest, RegisterTableRequest,
+    RegisterViewRequest, RenameTableRequest, SchemaProvider, SchemaProviderRef,
 };
+use common_catalog::format_full_table_name;
 use common_error::prelude::BoxedError;
 use common_telemetry::error;
 use futures::StreamExt;
@@ -97,8 +98,22 @@ impl CatalogManager for FrontendCatalogManager {
         Ok(())
     }
 
+    /// The table is already created in the meta server, so it's only checked that no view has
+    /// taken its name meanwhile, see [FrontendSchemaProvider::register_view].
     // TODO(LFC): Handle the table caching in (de)register_table.
-    async fn register_table(&self, _request: RegisterTableRequest) -> CatalogResult<bool> {
+    async fn register_table(&self, request: RegisterTableRequest) -> CatalogResult<bool> {
+        ensure!(
+            self.view(&request.catalog, &request.schema, &request.table_name)
+                .await?
+                .is_none(),
+            ViewExistsSnafu {
+                view: format_full_table_name(
+                    &request.catalog,
+                    &request.schema,
+                    &request.table_name
+                ),
+            }
+        );
         Ok(true)
     }
 
@@ -122,6 +137,30 @@ impl CatalogManager for FrontendCatalogManager {
         unimplemented!()
     }
 
+    async fn register_view(&self, request: RegisterViewRequest) -> CatalogResult<bool> {
+        let prev = self
+            .schema(&request.catalog, &request.schema)?
+            .context(catalog::error::SchemaNotFoundSnafu {
+                catalog: &request.catalog,
+                schema: &request.schema,
+            })?
+            .register_view(request.view_name, request.definition)
+            .await?;
+        Ok(prev.is_none())
+    }
+
+    async fn deregister_view(&self, request: DeregisterViewRequest) -> CatalogResult<bool> {
+        let prev = self
+            .schema(&request.catalog, &request.schema)?
+            .context(catalog::error::SchemaNotFoundSnafu {
+                catalog: &request.catalog,
+                schema: &request.schema,
+            })?
+            .deregister_view(&request.view_name)
+            .await?;
+        Ok(prev.is_some())
+    }
+
     async fn register_system_table(
         &self,
         request: RegisterSystemTableRequest,
@@ -237,6 +276,18 @@ impl CatalogManager for FrontendCatalogManager {
             .table(table_name)
             .await
     }
+
+    async fn view(
+        &self,
+        catalog: &str,
+        schema: &str,
+        view_name: &str,
+    ) -> catalog::error::Result<Option<String>> {
+        self.schema(catalog, schema)?
+            .context(catalog::error::SchemaNotFoundSnafu { catalog, schema })?
+            .view(view_name)
+            .await
+    }
 }
 
 impl CatalogList for FrontendCatalogManager {
@@ -413,11 +464,96 @@ impl SchemaProvider for FrontendSchemaProvider {
         Ok(Some(table))
     }
 
+    fn view_names(&self) -> catalog::error::Result<Vec<String>> {
+        let backend = self.backend.clone();
+        let catalog_name = self.catalog_name.clone();
+        let schema_name = self.schema_name.clone();
+
+        std::thread::spawn(|| {
+            common_runtime::block_on_read(async move {
+                let key = build_view_prefix(catalog_name, schema_name);
+                let mut iter = backend.range(key.as_bytes());
+                let mut res = vec![];
+
+                while let Some(r) = iter.next().await {
+                    let Kv(k, _) = r?;
+                    let key = ViewKey::parse(String::from_utf8_lossy(&k))
+                        .context(InvalidCatalogValueSnafu)?;
+                    res.push(key.view_name);
+                }
+                Ok(res)
+            })
+        })
+        .join()
+        .unwrap()
+    }
+
+    async fn view(&self, name: &str) -> catalog::error::Result<Option<String>> {
+        let view_key = self.view_key(name);
+        let Some(kv) = self.backend.get(view_key.to_string().as_bytes()).await? else { return Ok(None) };
+        let v = ViewValue::from_bytes(kv.1).context(InvalidCatalogValueSnafu)?;
+        Ok(Some(v.definition))
+    }
+
+    /// The view is written before checking that no table has its name, while a table is
+    /// checked after it's written, see [FrontendCatalogManager::register_table]. So of a table
+    /// and a view of the same name registered concurrently, at least one sees the other.
+    async fn register_view(
+        &self,
+        name: String,
+        definition: String,
+    ) -> catalog::error::Result<Option<String>> {
+        let prev = self.view(&name).await?;
+        let view_key = self.view_key(&name).to_string();
+        self.set_view(&view_key, definition).await?;
+        if self.table(&name).await?.is_some() {
+            match prev {
+                Some(prev) => self.set_view(&view_key, prev).await?,
+                None => self.backend.delete(view_key.as_bytes()).await?,
+            }
+            return TableExistsSnafu {
+                table: format_full_table_name(&self.catalog_name, &self.schema_name, &name),
+            }
+            .fail();
+        }
+        Ok(prev)
+    }
+
+    async fn deregister_view(&self, name: &str) -> catalog::error::Result<Option<String>> {
+        let prev = self.view(name).await?;
+        if prev.is_some() {
+            self.backend
+                .delete(self.view_key(name).to_string().as_bytes())
+                .await?;
+        }
+        Ok(prev)
+    }
+
     fn table_exist(&self, name: &str) -> catalog::error::Result<bool> {
         Ok(self.table_names()?.contains(&name.to_string()))
     }
 }
 
+impl FrontendSchemaProvider {
+    async fn set_view(&self, view_key: &str, definition: String) -> catalog::error::Result<()> {
+        let view_value = ViewValue { definition };
+        self.backend
+            .set(
+                view_key.as_bytes(),
+                &view_value.as_bytes().context(InvalidCatalogValueSnafu)?,
+            )
+            .await
+    }
+
+    fn view_key(&self, view_name: &str) -> ViewKey {
+        ViewKey {
+            catalog_name: self.catalog_name.clone(),
+            schema_name: self.schema_name.clone(),
+            view_name: view_name.to_string(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, MITO_ENGINE};
@@ -221,6 +221,12 @@ pub enum Error {
         source: table::error::Error,
     },
 
+    #[snafu(display("Failed to describe the columns of record batch, source: {}", source))]
+    DescribeRecordBatchColumns {
+        #[snafu(backtrace)]
+        source: common_grpc::error::Error,
+    },
+
     #[snafu(display("Failed to find region route for table {}", table_name))]
     FindRegionRoute {
         table_name: String,
@@ -281,6 +287,12 @@ pub enum Error {
     #[snafu(display("Table already exists: `{}`", table))]
     TableAlreadyExist { table: String, location: Location },
 
+    #[snafu(display("View already exists: `{}`", view))]
+    ViewAlreadyExist { view: String, location: Location },
+
+    #[snafu(display("View `{}` not exist", view))]
+    ViewNotFound { view: String, location: Location },
+
     #[snafu(display("Failed to encode Substrait logical plan, source: {}", source))]
     EncodeSubstraitLogicalPlan {
         #[snafu(backtrace)]
@@ -370,6 +382,88 @@ pub enum Error {
         #[snafu(backtrace)]
         source: script::error::Error,
     },
+
+    #[snafu(display("Query is cancelled, connection id: {}", id))]
+    QueryCancelled { id: u32, location: Location },
+
+    #[snafu(display("Unknown connection id: {}", id))]
+    UnknownConnectionId { id: u32, location: Location },
+
+    #[snafu(display("Not allowed to kill the query of connection {}", id))]
+    KillDenied { id: u32, location: Location },
+
+    #[snafu(display("Query exceeds the statement timeout {:?}", timeout))]
+    QueryTimeout {
+        timeout: std::time::Duration,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid value {} of variable {}, reason: {}", value, variable, reason))]
+    InvalidVariableValue {
+        variable: String,
+        value: String,
+        reason: String,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid materialized view {}, reason: {}", name, reason))]
+    InvalidMaterializedView {
+        name: String,
+        reason: String,
+        location: Location,
+    },
+
+    #[snafu(display("Materialized view not found: {}", view))]
+    MaterializedViewNotFound { view: String, location: Location },
+
+    #[snafu(display("Failed to read rule file {}, source: {}", path, source))]
+    ReadRuleFile {
+        path: String,
+        source: std::io::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Failed to parse rule file {}, source: {}", path, source))]
+    ParseRuleFile {
+        path: String,
+        source: serde_yaml::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid rule {} in group {}, reason: {}", name, group, reason))]
+    InvalidRule {
+        group: String,
+        name: String,
+        reason: String,
+        location: Location,
+    },
+
+    #[snafu(display("Failed to write the result of rule {}, source: {}", name, source))]
+    WriteRuleResult {
+        name: String,
+        #[snafu(backtrace)]
+        source: servers::error::Error,
+    },
+
+    #[snafu(display("Failed to start repeated task {}, source: {}", name, source))]
+    StartRepeatedTask {
+        name: String,
+        #[snafu(backtrace)]
+        source: common_runtime::error::Error,
+    },
+
+    #[snafu(display("Failed to stop repeated task {}, source: {}", name, source))]
+    StopRepeatedTask {
+        name: String,
+        #[snafu(backtrace)]
+        source: common_runtime::error::Error,
+    },
+
+    #[snafu(display("Failed to collect recordbatch, source: {}", source))]
+    CollectRecordbatch {
+        #[snafu(backtrace)]
+        source: common_recordbatch::error::Error,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -403,6 +497,8 @@ impl ErrorExt for Error {
 
             Error::Table { source } => source.status_code(),
 
+            Error::DescribeRecordBatchColumns { source } => source.status_code(),
+
             Error::ConvertColumnDefaultConstraint { source, .. }
             | Error::CreateTableInfo { source } => source.status_code(),
 
@@ -422,7 +518,9 @@ impl ErrorExt for Error {
             | Error::IncompleteGrpcResult { .. }
             | Error::ContextValueNotFound { .. } => StatusCode::Unexpected,
 
-            Error::TableNotFound { .. } => StatusCode::TableNotFound,
+            Error::TableNotFound { .. }
+            | Error::ViewNotFound { .. }
+            | Error::MaterializedViewNotFound { .. } => StatusCode::TableNotFound,
             Error::ColumnNotFound { .. } => StatusCode::TableColumnNotFound,
 
             Error::JoinTask { .. } => StatusCode::Unexpected,
@@ -444,7 +542,9 @@ impl ErrorExt for Error {
 
             Error::AlterExprToRequest { source, .. } => source.status_code(),
             Error::LeaderNotFound { .. } => StatusCode::StorageUnavailable,
-            Error::TableAlreadyExist { .. } => StatusCode::TableAlreadyExists,
+            Error::TableAlreadyExist { .. } | Error::ViewAlreadyExist { .. } => {
+                StatusCode::TableAlreadyExists
+            }
             Error::EncodeSubstraitLogicalPlan { source } => source.status_code(),
             Error::InvokeDatanode { source } => source.status_code(),
             Error::ColumnDefaultValue { source, .. } => source.status_code(),
@@ -456,6 +556,21 @@ impl ErrorExt for Error {
             Error::UnrecognizedTableOption { .. } => StatusCode::InvalidArguments,
 
             Error::StartScriptManager { source } => source.status_code(),
+
+            Error::QueryCancelled { .. } => StatusCode::Cancelled,
+            Error::KillDenied { .. } => StatusCode::AccessDenied,
+            Error::UnknownConnectionId { .. }
+            | Error::InvalidVariableValue { .. }
+            | Error::InvalidMaterializedView { .. }
+            | Error::ReadRuleFile { .. }
+            | Error::ParseRuleFile { .. }
+            | Error::InvalidRule { .. } => StatusCode::InvalidArguments,
+            Error::WriteRuleResult { source, .. } => source.status_code(),
+            Error::QueryTimeout { .. } => StatusCode::QueryTimeout,
+            Error::CollectRecordbatch { source } => source.status_code(),
+            Error::StartRepeatedTask { source, .. } | Error::StopRepeatedTask { source, .. } => {
+                source.status_code()
+            }
         }
     }
 
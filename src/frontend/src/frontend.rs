@@ -17,13 +17,18 @@ use serde::{Deserialize, Serialize};
 use servers::http::HttpOptions;
 use servers::Mode;
 
+use crate::flight_sql::FlightSqlOptions;
+use crate::graphite::GraphiteOptions;
 use crate::grpc::GrpcOptions;
 use crate::influxdb::InfluxdbOptions;
 use crate::mysql::MysqlOptions;
 use crate::opentsdb::OpentsdbOptions;
+use crate::otlp::OtlpOptions;
 use crate::postgres::PostgresOptions;
 use crate::prom::PromOptions;
 use crate::prometheus::PrometheusOptions;
+use crate::query_limit::QueryLimitOptions;
+use crate::rule::RuleOptions;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
@@ -37,7 +42,12 @@ pub struct FrontendOptions {
     pub influxdb_options: Option<InfluxdbOptions>,
     pub prometheus_options: Option<PrometheusOptions>,
     pub prom_options: Option<PromOptions>,
+    pub otlp_options: Option<OtlpOptions>,
+    pub graphite_options: Option<GraphiteOptions>,
+    pub flight_sql_options: Option<FlightSqlOptions>,
     pub meta_client_options: Option<MetaClientOptions>,
+    pub query_limit_options: QueryLimitOptions,
+    pub rule_options: RuleOptions,
 }
 
 impl Default for FrontendOptions {
@@ -52,7 +62,12 @@ impl Default for FrontendOptions {
             influxdb_options: Some(InfluxdbOptions::default()),
             prometheus_options: Some(PrometheusOptions::default()),
             prom_options: Some(PromOptions::default()),
+            otlp_options: Some(OtlpOptions::default()),
+            graphite_options: None,
+            flight_sql_options: None,
             meta_client_options: None,
+            query_limit_options: QueryLimitOptions::default(),
+            rule_options: RuleOptions::default(),
         }
     }
 }
@@ -0,0 +1,62 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+use servers::graphite::template::DEFAULT_SEPARATOR;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphiteOptions {
+    /// The address of the plaintext protocol server.
+    pub addr: String,
+    /// The address of the pickle protocol server, which is disabled if not set.
+    pub pickle_addr: Option<String>,
+    pub runtime_size: usize,
+    /// The separator to join the parts of the paths mapped into the same table, tag or field.
+    pub separator: String,
+    /// The templates to map the paths of metrics, like `servers.* .host.measurement.field*`.
+    pub templates: Vec<String>,
+}
+
+impl Default for GraphiteOptions {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:2003".to_string(),
+            pickle_addr: None,
+            runtime_size: 2,
+            separator: DEFAULT_SEPARATOR.to_string(),
+            templates: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphiteOptions;
+
+    #[test]
+    fn test_graphite_options() {
+        let opts: GraphiteOptions = toml::from_str(
+            r#"
+            pickle_addr = "127.0.0.1:2004"
+            templates = ["servers.* .host.measurement.field*"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!("127.0.0.1:2003", opts.addr);
+        assert_eq!(Some("127.0.0.1:2004".to_string()), opts.pickle_addr);
+        assert_eq!(".", opts.separator);
+        assert_eq!(1, opts.templates.len());
+    }
+}
@@ -13,17 +13,24 @@
 // limitations under the License.
 
 pub(crate) mod distributed;
+mod graphite;
 mod grpc;
 mod influxdb;
 mod opentsdb;
+mod otlp;
+mod process;
 mod prometheus;
 mod script;
 mod standalone;
+mod variable_value;
+mod view;
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 
+use api::prometheus::remote::{MetricMetadata, Query as RemoteQuery, TimeSeries};
 use api::v1::alter_expr::Kind;
 use api::v1::ddl_request::Expr as DdlExpr;
 use api::v1::greptime_request::Request;
@@ -33,10 +40,12 @@ use catalog::remote::MetaKvBackend;
 use catalog::CatalogManagerRef;
 use common_base::Plugins;
 use common_catalog::consts::MITO_ENGINE;
+use common_catalog::is_hidden_table;
 use common_error::ext::BoxedError;
 use common_grpc::channel_manager::{ChannelConfig, ChannelManager};
+use common_grpc::flight::recordbatch_to_column_defs;
 use common_query::Output;
-use common_recordbatch::RecordBatches;
+use common_recordbatch::{RecordBatch, RecordBatches};
 use common_telemetry::logging::{debug, info};
 use common_telemetry::timer;
 use datafusion::sql::sqlparser::ast::ObjectName;
@@ -55,43 +64,57 @@ use query::{QueryEngineFactory, QueryEngineRef};
 use servers::error as server_error;
 use servers::error::{ExecuteQuerySnafu, ParsePromQLSnafu};
 use servers::interceptor::{SqlQueryInterceptor, SqlQueryInterceptorRef};
-use servers::prom::PromHandler;
-use servers::query_handler::grpc::{GrpcQueryHandler, GrpcQueryHandlerRef};
+use servers::prom::{PromAlert, PromHandler, PromRuleGroup};
+use servers::query_handler::grpc::{BulkInsertHandler, GrpcQueryHandler, GrpcQueryHandlerRef};
 use servers::query_handler::sql::SqlQueryHandler;
 use servers::query_handler::{
-    InfluxdbLineProtocolHandler, OpentsdbProtocolHandler, PrometheusProtocolHandler, ScriptHandler,
+    GraphiteProtocolHandler, InfluxdbLineProtocolHandler, OpenTelemetryProtocolHandler,
+    OpentsdbProtocolHandler, PrometheusProtocolHandler, ScriptHandler,
 };
-use session::context::{QueryContext, QueryContextRef};
+use session::context::{QueryContextRef, QueryLimits};
+use session::process::{ProcessManager, ProcessManagerRef};
 use snafu::prelude::*;
 use sql::dialect::GenericDialect;
 use sql::parser::ParserContext;
-use sql::statements::copy::CopyTable;
+use sql::statements::copy::{CopyStdio, CopyStdioSource, CopyTable};
 use sql::statements::describe::DescribeTable;
+use sql::statements::kill::Kill;
+use sql::statements::set_variables::SetVariables;
 use sql::statements::statement::Statement;
 use sql::statements::tql::Tql;
+use table::requests::InsertRequest as TableInsertRequest;
 
 use crate::catalog::FrontendCatalogManager;
 use crate::datanode::DatanodeClients;
 use crate::error::{
     self, CatalogSnafu, DescribeStatementSnafu, Error, ExecLogicalPlanSnafu, ExecutePromqlSnafu,
-    ExecuteStatementSnafu, ExternalSnafu, InvalidInsertRequestSnafu, MissingMetasrvOptsSnafu,
-    NotSupportedSnafu, ParseQuerySnafu, ParseSqlSnafu, PlanStatementSnafu, Result,
-    SqlExecInterceptedSnafu, TableNotFoundSnafu,
+    ExecuteStatementSnafu, ExternalSnafu, InvalidInsertRequestSnafu, KillDeniedSnafu,
+    MissingMetasrvOptsSnafu, NotSupportedSnafu, ParseQuerySnafu, ParseSqlSnafu, PlanStatementSnafu,
+    Result, SqlExecInterceptedSnafu, TableNotFoundSnafu, UnknownConnectionIdSnafu,
 };
 use crate::expr_factory::{CreateExprFactoryRef, DefaultCreateExprFactory};
 use crate::frontend::FrontendOptions;
+use crate::instance::process::{attach_process, run_cancellable, Deadline};
 use crate::instance::standalone::StandaloneGrpcQueryHandler;
+use crate::materialized_view::{
+    KvViewRegistry, KvWatermarkStore, MaterializedViewManager, MaterializedViewManagerRef,
+    MemoryViewRegistry, MemoryWatermarkStore,
+};
 use crate::metric;
+use crate::rule::{RuleManager, RuleManagerRef, RuleOptions};
 use crate::script::ScriptExecutor;
 use crate::server::{start_server, ServerHandlers, Services};
 
 #[async_trait]
 pub trait FrontendInstance:
     GrpcQueryHandler<Error = Error>
+    + BulkInsertHandler
     + SqlQueryHandler<Error = Error>
     + OpentsdbProtocolHandler
     + InfluxdbLineProtocolHandler
     + PrometheusProtocolHandler
+    + OpenTelemetryProtocolHandler
+    + GraphiteProtocolHandler
     + ScriptHandler
     + PromHandler
     + Send
@@ -110,6 +133,11 @@ pub struct Instance {
     statement_handler: StatementHandlerRef,
     query_engine: QueryEngineRef,
     grpc_query_handler: GrpcQueryHandlerRef<Error>,
+    process_manager: ProcessManagerRef,
+    materialized_view_manager: MaterializedViewManagerRef,
+    rule_manager: RuleManagerRef,
+    /// Limits of statements in sessions that don't set their own.
+    default_query_limits: QueryLimits,
 
     create_expr_factory: CreateExprFactoryRef,
 
@@ -153,6 +181,17 @@ impl Instance {
 
         let script_executor =
             Arc::new(ScriptExecutor::new(catalog_manager.clone(), query_engine.clone()).await?);
+        prometheus::register_prometheus_tables(catalog_manager.as_ref()).await?;
+        let materialized_view_manager = Arc::new(MaterializedViewManager::new(
+            catalog_manager.clone(),
+            query_engine.clone(),
+            dist_instance.clone(),
+            Arc::new(KvWatermarkStore::new(catalog_manager.backend())),
+            Arc::new(KvViewRegistry::new(catalog_manager.backend())),
+        ));
+
+        let rule_manager = Arc::new(RuleManager::default());
+        rule_manager.load(&opts.rule_options)?;
 
         Ok(Instance {
             catalog_manager,
@@ -161,6 +200,10 @@ impl Instance {
             statement_handler: dist_instance.clone(),
             query_engine,
             grpc_query_handler: dist_instance,
+            process_manager: ProcessManager::global(),
+            materialized_view_manager,
+            rule_manager,
+            default_query_limits: (&opts.query_limit_options).into(),
             plugins: plugins.clone(),
             servers: Arc::new(HashMap::new()),
         })
@@ -201,13 +244,26 @@ impl Instance {
         let query_engine = dn_instance.query_engine();
         let script_executor =
             Arc::new(ScriptExecutor::new(catalog_manager.clone(), query_engine.clone()).await?);
+        prometheus::register_prometheus_tables(catalog_manager.as_ref()).await?;
+        let grpc_query_handler = StandaloneGrpcQueryHandler::arc(dn_instance.clone());
+        let materialized_view_manager = Arc::new(MaterializedViewManager::new(
+            catalog_manager.clone(),
+            query_engine.clone(),
+            grpc_query_handler.clone(),
+            Arc::new(MemoryWatermarkStore::default()),
+            Arc::new(MemoryViewRegistry::default()),
+        ));
         Ok(Instance {
             catalog_manager: catalog_manager.clone(),
             script_executor,
             create_expr_factory: Arc::new(DefaultCreateExprFactory),
             statement_handler: dn_instance.clone(),
             query_engine,
-            grpc_query_handler: StandaloneGrpcQueryHandler::arc(dn_instance.clone()),
+            grpc_query_handler,
+            process_manager: ProcessManager::global(),
+            materialized_view_manager,
+            rule_manager: Arc::new(RuleManager::default()),
+            default_query_limits: QueryLimits::default(),
             plugins: Default::default(),
             servers: Arc::new(HashMap::new()),
         })
@@ -226,7 +282,7 @@ impl Instance {
 
     #[cfg(test)]
     pub(crate) async fn new_distributed(
-        catalog_manager: CatalogManagerRef,
+        catalog_manager: Arc<FrontendCatalogManager>,
         dist_instance: Arc<DistInstance>,
     ) -> Self {
         let query_engine = QueryEngineFactory::new(catalog_manager.clone()).query_engine();
@@ -235,6 +291,16 @@ impl Instance {
                 .await
                 .unwrap(),
         );
+        prometheus::register_prometheus_tables(catalog_manager.as_ref())
+            .await
+            .unwrap();
+        let materialized_view_manager = Arc::new(MaterializedViewManager::new(
+            catalog_manager.clone(),
+            query_engine.clone(),
+            dist_instance.clone(),
+            Arc::new(KvWatermarkStore::new(catalog_manager.backend())),
+            Arc::new(KvViewRegistry::new(catalog_manager.backend())),
+        ));
         Instance {
             catalog_manager,
             script_executor,
@@ -242,6 +308,10 @@ impl Instance {
             query_engine,
             create_expr_factory: Arc::new(DefaultCreateExprFactory),
             grpc_query_handler: dist_instance,
+            process_manager: ProcessManager::global(),
+            materialized_view_manager,
+            rule_manager: Arc::new(RuleManager::default()),
+            default_query_limits: QueryLimits::default(),
             plugins: Default::default(),
             servers: Arc::new(HashMap::new()),
         }
@@ -251,6 +321,16 @@ impl Instance {
         &self.catalog_manager
     }
 
+    #[cfg(test)]
+    pub(crate) fn materialized_view_manager(&self) -> &MaterializedViewManagerRef {
+        &self.materialized_view_manager
+    }
+
+    #[cfg(test)]
+    pub(crate) fn rule_manager(&self) -> &RuleManagerRef {
+        &self.rule_manager
+    }
+
     /// Handle batch inserts
     pub async fn handle_inserts(
         &self,
@@ -275,6 +355,58 @@ impl Instance {
         GrpcQueryHandler::do_query(&*self.grpc_query_handler, query, ctx).await
     }
 
+    /// Inserts the record batch into the table as it is, after creating or altering the table on
+    /// demand by the columns of the record batch.
+    pub(crate) async fn handle_recordbatch_insert(
+        &self,
+        table_name: &str,
+        recordbatch: RecordBatch,
+        ctx: QueryContextRef,
+    ) -> Result<usize> {
+        let columns = recordbatch_to_column_defs(&recordbatch)
+            .context(error::DescribeRecordBatchColumnsSnafu)?;
+        let request = InsertRequest {
+            table_name: table_name.to_string(),
+            columns,
+            row_count: recordbatch.num_rows() as u32,
+            ..Default::default()
+        };
+        self.create_or_alter_table_on_demand(ctx.clone(), &request)
+            .await?;
+
+        let catalog_name = ctx.current_catalog();
+        let schema_name = ctx.current_schema();
+        let table = self
+            .catalog_manager
+            .table(&catalog_name, &schema_name, table_name)
+            .await
+            .context(CatalogSnafu)?
+            .with_context(|| TableNotFoundSnafu {
+                table_name: common_catalog::format_full_table_name(
+                    &catalog_name,
+                    &schema_name,
+                    table_name,
+                ),
+            })?;
+        let columns_values = recordbatch
+            .schema
+            .column_schemas()
+            .iter()
+            .map(|column_schema| column_schema.name.clone())
+            .zip(recordbatch.columns().iter().cloned())
+            .collect();
+        table
+            .insert(TableInsertRequest {
+                catalog_name,
+                schema_name,
+                table_name: table_name.to_string(),
+                columns_values,
+                region_number: 0,
+            })
+            .await
+            .context(error::TableSnafu)
+    }
+
     // check if table already exist:
     // - if table does not exist, create table by inferred CreateExpr
     // - if table exist, check if schema matches. If any new column found, alter table by inferred `AlterExpr`
@@ -408,11 +540,22 @@ impl Instance {
         self.plugins = map;
     }
 
+    pub fn set_default_query_limits(&mut self, query_limits: QueryLimits) {
+        self.default_query_limits = query_limits;
+    }
+
     pub fn plugins(&self) -> Arc<Plugins> {
         self.plugins.clone()
     }
 
+    /// Loads the recording and alerting rules, which are evaluated once the instance starts.
+    pub fn load_rules(&self, opts: &RuleOptions) -> Result<()> {
+        self.rule_manager.load(opts)
+    }
+
     pub async fn shutdown(&self) -> Result<()> {
+        self.materialized_view_manager.stop().await?;
+        self.rule_manager.stop().await?;
         futures::future::try_join_all(self.servers.values().map(|server| server.0.shutdown()))
             .await
             .context(error::ShutdownServerSnafu)
@@ -425,6 +568,9 @@ impl FrontendInstance for Instance {
     async fn start(&mut self) -> Result<()> {
         // TODO(hl): Frontend init should move to here
 
+        self.materialized_view_manager.start().await?;
+        self.rule_manager.start(self.clone()).await?;
+
         futures::future::try_join_all(self.servers.values().map(start_server))
             .await
             .context(error::StartServerSnafu)
@@ -498,6 +644,88 @@ impl Instance {
         query::sql::describe_table(table).context(DescribeStatementSnafu)
     }
 
+    fn set_variables(&self, set_var: SetVariables, query_ctx: QueryContextRef) -> Result<Output> {
+        let variable = set_var.variable.to_string().to_lowercase();
+        let mut query_limits = query_ctx.init_query_limits(self.default_query_limits);
+        match variable.as_str() {
+            // MySQL names it "max_execution_time", both are in milliseconds.
+            "statement_timeout" | "max_execution_time" => {
+                query_limits.statement_timeout =
+                    variable_value::parse_timeout(&variable, &set_var.value)?;
+            }
+            "query_memory_limit" => {
+                query_limits.memory_limit =
+                    variable_value::parse_memory_limit(&variable, &set_var.value)?;
+            }
+            _ => {
+                return NotSupportedSnafu {
+                    feat: format!("SET {variable}"),
+                }
+                .fail()
+            }
+        }
+        query_ctx.set_query_limits(query_limits);
+        Ok(Output::AffectedRows(0))
+    }
+
+    /// Runs the statement in the process list, so it can be killed, and fails it once it exceeds
+    /// the statement timeout of the session.
+    async fn run_statement(
+        &self,
+        query: &str,
+        query_ctx: &QueryContextRef,
+        future: impl Future<Output = Result<Output>>,
+    ) -> Result<Output> {
+        let deadline = query_ctx
+            .init_query_limits(self.default_query_limits)
+            .statement_timeout
+            .map(Deadline::after);
+        let guard = self.process_manager.register(query, query_ctx);
+        run_cancellable(guard.process(), deadline, future)
+            .await
+            .map(|output| attach_process(output, guard, deadline))
+    }
+
+    /// Executes the PromQL query under the limits of the session, like the SQL statements.
+    async fn execute_promql(
+        &self,
+        query: &PromQuery,
+        query_ctx: QueryContextRef,
+    ) -> server_error::Result<Output> {
+        let stmt = QueryLanguageParser::parse_promql(query).with_context(|_| ParsePromQLSnafu {
+            query: query.clone(),
+        })?;
+        self.run_statement(
+            &query.query,
+            &query_ctx,
+            self.plan_exec(stmt, query_ctx.clone()),
+        )
+        .await
+        .map_err(BoxedError::new)
+        .with_context(|_| ExecuteQuerySnafu {
+            query: format!("{query:?}"),
+        })
+    }
+
+    /// Kills the statement of a connection in this frontend, whose output stream fails as
+    /// cancelled. Dropping the statement also drops its requests to the datanodes in distributed
+    /// mode, which cancels their Flight streams and aborts the tasks producing their results.
+    ///
+    /// Like `KILL QUERY`, `KILL [CONNECTION]` only cancels the statement of the connection, which
+    /// stays open, since the connections are owned by the protocol servers.
+    fn handle_kill(&self, kill: Kill, query_ctx: QueryContextRef) -> Result<Output> {
+        let process = self
+            .process_manager
+            .get(kill.id)
+            .context(UnknownConnectionIdSnafu { id: kill.id })?;
+        ensure!(
+            process.is_accessible_by(&query_ctx.current_user()),
+            KillDeniedSnafu { id: kill.id }
+        );
+        process.cancel();
+        Ok(Output::AffectedRows(0))
+    }
+
     async fn query_statement(&self, stmt: Statement, query_ctx: QueryContextRef) -> Result<Output> {
         check_permission(self.plugins.clone(), &stmt, &query_ctx)?;
 
@@ -530,10 +758,30 @@ impl Instance {
                 .await
                 .context(ExecuteStatementSnafu),
             Statement::Use(db) => self.handle_use(db, query_ctx),
-            Statement::ShowCreateTable(_) => NotSupportedSnafu {
-                feat: format!("{stmt:?}"),
+            Statement::ShowProcesslist(stmt) => {
+                query::sql::show_processlist(stmt, &self.process_manager, query_ctx)
+                    .context(ExecuteStatementSnafu)
+            }
+            Statement::CreateMaterializedView(stmt) => {
+                self.materialized_view_manager.create(stmt, query_ctx).await
+            }
+            Statement::DropMaterializedView(stmt) => {
+                self.materialized_view_manager.drop(stmt, query_ctx).await
+            }
+            Statement::CreateView(stmt) => self.create_view(stmt, query_ctx).await,
+            Statement::DropView(stmt) => self.drop_view(stmt, query_ctx).await,
+            Statement::ShowCreateView(stmt) => self.show_create_view(stmt, query_ctx).await,
+            Statement::Kill(kill) => self.handle_kill(kill, query_ctx),
+            Statement::SetVariables(set_var) => self.set_variables(set_var, query_ctx),
+            // `LOAD DATA LOCAL INFILE` is handled by the MySQL server, which receives the file
+            // from the client, while loading the files of the server is left to `COPY FROM`.
+            // Likewise, `COPY ... {FROM STDIN | TO STDOUT}` is handled by the PostgreSQL server.
+            Statement::LoadData(_) | Statement::CopyStdio(_) | Statement::ShowCreateTable(_) => {
+                NotSupportedSnafu {
+                    feat: format!("{stmt:?}"),
+                }
+                .fail()
             }
-            .fail(),
         }
     }
 }
@@ -564,7 +812,14 @@ impl SqlQueryHandler for Instance {
                         results.push(Err(e));
                         break;
                     }
-                    match self.query_statement(stmt, query_ctx.clone()).await {
+                    let result = self
+                        .run_statement(
+                            query.as_ref(),
+                            &query_ctx,
+                            self.query_statement(stmt, query_ctx.clone()),
+                        )
+                        .await;
+                    match result {
                         Ok(output) => {
                             let output_result =
                                 query_interceptor.post_execute(output, query_ctx.clone());
@@ -584,13 +839,17 @@ impl SqlQueryHandler for Instance {
         }
     }
 
-    async fn do_promql_query(&self, query: &PromQuery, _: QueryContextRef) -> Vec<Result<Output>> {
-        let result =
-            PromHandler::do_query(self, query)
-                .await
-                .with_context(|_| ExecutePromqlSnafu {
-                    query: format!("{query:?}"),
-                });
+    async fn do_promql_query(
+        &self,
+        query: &PromQuery,
+        query_ctx: QueryContextRef,
+    ) -> Vec<Result<Output>> {
+        let result = self
+            .execute_promql(query, query_ctx)
+            .await
+            .with_context(|_| ExecutePromqlSnafu {
+                query: format!("{query:?}"),
+            });
         vec![result]
     }
 
@@ -626,16 +885,69 @@ impl SqlQueryHandler for Instance {
 
 #[async_trait]
 impl PromHandler for Instance {
-    async fn do_query(&self, query: &PromQuery) -> server_error::Result<Output> {
-        let stmt = QueryLanguageParser::parse_promql(query).with_context(|_| ParsePromQLSnafu {
-            query: query.clone(),
-        })?;
-        self.plan_exec(stmt, QueryContext::arc())
-            .await
-            .map_err(BoxedError::new)
-            .with_context(|_| ExecuteQuerySnafu {
-                query: format!("{query:?}"),
-            })
+    async fn do_query(
+        &self,
+        query: &PromQuery,
+        query_ctx: QueryContextRef,
+    ) -> server_error::Result<Output> {
+        self.execute_promql(query, query_ctx).await
+    }
+
+    async fn metric_names(&self, query_ctx: QueryContextRef) -> server_error::Result<Vec<String>> {
+        let catalog = query_ctx.current_catalog();
+        let schema = query_ctx.current_schema();
+        let Some(schema_provider) = self
+            .catalog_manager
+            .schema(&catalog, &schema)
+            .context(server_error::CatalogSnafu)? else {
+            return Ok(vec![]);
+        };
+        let mut tables = schema_provider
+            .table_names()
+            .context(server_error::CatalogSnafu)?;
+        tables.retain(|table| !is_hidden_table(&catalog, &schema, table));
+        Ok(tables)
+    }
+
+    async fn label_names(
+        &self,
+        metric: &str,
+        query_ctx: QueryContextRef,
+    ) -> server_error::Result<Option<Vec<String>>> {
+        self.query_label_names(metric, query_ctx).await
+    }
+
+    async fn label_values(
+        &self,
+        query: &RemoteQuery,
+        label_name: &str,
+        query_ctx: QueryContextRef,
+    ) -> server_error::Result<Vec<String>> {
+        self.query_label_values(query, label_name, query_ctx).await
+    }
+
+    async fn rule_groups(&self) -> server_error::Result<Vec<PromRuleGroup>> {
+        Ok(self.rule_manager.rule_groups())
+    }
+
+    async fn alerts(&self) -> server_error::Result<Vec<PromAlert>> {
+        Ok(self.rule_manager.alerts())
+    }
+
+    async fn metric_metadata(
+        &self,
+        metric: Option<&str>,
+        query_ctx: QueryContextRef,
+    ) -> server_error::Result<Vec<MetricMetadata>> {
+        self.query_metric_metadata(metric, query_ctx).await
+    }
+
+    async fn query_exemplars(
+        &self,
+        query: &RemoteQuery,
+        query_ctx: QueryContextRef,
+    ) -> server_error::Result<Vec<TimeSeries>> {
+        self.query_series_exemplars(query, query_ctx).await
     }
 }
 
@@ -658,6 +970,9 @@ pub fn check_permission(
         Statement::Query(_) | Statement::Explain(_) | Statement::Tql(_) | Statement::Delete(_) => {}
         // database ops won't be checked
         Statement::CreateDatabase(_) | Statement::ShowDatabases(_) | Statement::Use(_) => {}
+        // process list and session ops are not bound to any schema, the processes of other users
+        // are checked against the current user when executed
+        Statement::ShowProcesslist(_) | Statement::Kill(_) | Statement::SetVariables(_) => {}
         // show create table and alter are not supported yet
         Statement::ShowCreateTable(_) | Statement::CreateExternalTable(_) | Statement::Alter(_) => {
         }
@@ -668,9 +983,24 @@ pub fn check_permission(
         Statement::CreateTable(stmt) => {
             validate_param(&stmt.name, query_ctx)?;
         }
+        Statement::CreateMaterializedView(stmt) => {
+            validate_param(&stmt.name, query_ctx)?;
+        }
+        Statement::CreateView(stmt) => {
+            validate_param(&stmt.name, query_ctx)?;
+        }
         Statement::DropTable(drop_stmt) => {
             validate_param(drop_stmt.table_name(), query_ctx)?;
         }
+        Statement::DropMaterializedView(drop_stmt) => {
+            validate_param(drop_stmt.view_name(), query_ctx)?;
+        }
+        Statement::DropView(drop_stmt) => {
+            validate_param(drop_stmt.view_name(), query_ctx)?;
+        }
+        Statement::ShowCreateView(stmt) => {
+            validate_param(&stmt.view_name, query_ctx)?;
+        }
         Statement::ShowTables(stmt) => {
             if let Some(database) = &stmt.database {
                 validate_catalog_and_schema(&query_ctx.current_catalog(), database, query_ctx)
@@ -681,6 +1011,20 @@ pub fn check_permission(
         Statement::DescribeTable(stmt) => {
             validate_param(stmt.name(), query_ctx)?;
         }
+        Statement::LoadData(stmt) => {
+            validate_param(&stmt.table_name, query_ctx)?;
+        }
+        Statement::CopyStdio(CopyStdio::From { table_name, .. })
+        | Statement::CopyStdio(CopyStdio::To {
+            source: CopyStdioSource::Table { table_name, .. },
+            ..
+        }) => {
+            validate_param(table_name, query_ctx)?;
+        }
+        Statement::CopyStdio(CopyStdio::To {
+            source: CopyStdioSource::Query(_),
+            ..
+        }) => {}
         Statement::Copy(stmd) => match stmd {
             CopyTable::To(copy_table_to) => validate_param(&copy_table_to.table_name, query_ctx)?,
             CopyTable::From(copy_table_from) => {
@@ -31,7 +31,7 @@ use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
 use common_catalog::format_full_table_name;
 use common_error::prelude::BoxedError;
 use common_query::Output;
-use common_telemetry::debug;
+use common_telemetry::{debug, error};
 use datanode::instance::sql::table_idents_to_full_name;
 use datanode::sql::SqlHandler;
 use datatypes::prelude::ConcreteDataType;
@@ -65,7 +65,7 @@ use crate::error::{
     DeserializePartitionSnafu, InvokeDatanodeSnafu, NotSupportedSnafu, ParseSqlSnafu,
     PrimaryKeyNotFoundSnafu, RequestDatanodeSnafu, RequestMetaSnafu, Result, SchemaExistsSnafu,
     StartMetaClientSnafu, TableAlreadyExistSnafu, TableNotFoundSnafu, TableSnafu,
-    ToTableInsertRequestSnafu, UnrecognizedTableOptionSnafu,
+    ToTableInsertRequestSnafu, UnrecognizedTableOptionSnafu, ViewAlreadyExistSnafu,
 };
 use crate::expr_factory;
 use crate::table::DistTable;
@@ -120,6 +120,21 @@ impl DistInstance {
                 .fail()
             };
         }
+        // tables and views share their names
+        ensure!(
+            self.catalog_manager
+                .view(
+                    &table_name.catalog_name,
+                    &table_name.schema_name,
+                    &table_name.table_name,
+                )
+                .await
+                .context(CatalogSnafu)?
+                .is_none(),
+            ViewAlreadyExistSnafu {
+                view: table_name.to_string(),
+            }
+        );
 
         let mut table_info = create_table_info(create_table)?;
 
@@ -168,11 +183,21 @@ impl DistInstance {
             table_id,
             table: table.clone(),
         };
-        ensure!(
-            self.catalog_manager
-                .register_table(request)
+        let registered = self.catalog_manager.register_table(request).await;
+        if !matches!(registered, Ok(true)) {
+            // Gives the name back, e.g. a view of the same name is registered meanwhile.
+            if let Err(e) = self
+                .meta_client
+                .delete_route(MetaDeleteRequest {
+                    table_name: table_name.clone(),
+                })
                 .await
-                .context(CatalogSnafu)?,
+            {
+                error!(e; "Failed to delete the route of unregistered table {table_name}");
+            }
+        }
+        ensure!(
+            registered.context(CatalogSnafu)?,
             TableAlreadyExistSnafu {
                 table: table_name.to_string()
             }
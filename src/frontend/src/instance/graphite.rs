@@ -0,0 +1,126 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use async_trait::async_trait;
+use common_error::prelude::BoxedError;
+use servers::graphite::codec::GraphiteRequest;
+use servers::query_handler::GraphiteProtocolHandler;
+use session::context::QueryContextRef;
+use snafu::ResultExt;
+
+use crate::instance::Instance;
+
+#[async_trait]
+impl GraphiteProtocolHandler for Instance {
+    async fn exec(
+        &self,
+        request: &GraphiteRequest,
+        ctx: QueryContextRef,
+    ) -> servers::error::Result<()> {
+        let requests = request.try_into()?;
+        self.handle_inserts(requests, ctx)
+            .await
+            .map_err(BoxedError::new)
+            .context(servers::error::ExecuteGrpcQuerySnafu)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use common_query::Output;
+    use common_recordbatch::RecordBatches;
+    use servers::graphite::codec;
+    use servers::graphite::template::Templates;
+    use servers::query_handler::sql::SqlQueryHandler;
+    use session::context::QueryContext;
+
+    use super::*;
+    use crate::tests;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_standalone_graphite_exec() {
+        let standalone = tests::create_standalone_instance("test_standalone_graphite_exec").await;
+        let instance = &standalone.instance;
+
+        test_graphite_exec(instance).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_distributed_graphite_exec() {
+        let instance = tests::create_distributed_instance("test_distributed_graphite_exec").await;
+        let instance = &instance.frontend;
+
+        test_graphite_exec(instance).await;
+    }
+
+    async fn test_graphite_exec(instance: &Arc<Instance>) {
+        let templates =
+            Templates::try_new(&["servers.* .host.measurement.field".to_string()], ".").unwrap();
+        let points = [
+            "servers.host1.cpu.idle 66.6 1663840496",
+            "servers.host1.cpu.busy 20.1 1663840496",
+            "servers.host2.cpu.idle 80.0 1663840497",
+            "requests;region=us 10 1663840496",
+        ]
+        .into_iter()
+        .map(|line| codec::parse_line(line, &templates, 0).unwrap().unwrap())
+        .collect();
+        let request = GraphiteRequest { points };
+        instance.exec(&request, QueryContext::arc()).await.unwrap();
+
+        let output = instance
+            .do_query(
+                "SELECT host, greptime_timestamp, idle, busy FROM cpu ORDER BY host, greptime_timestamp",
+                QueryContext::arc(),
+            )
+            .await
+            .remove(0)
+            .unwrap();
+        let Output::Stream(stream) = output else { unreachable!() };
+        let recordbatches = RecordBatches::try_collect(stream).await.unwrap();
+        assert_eq!(
+            recordbatches.pretty_print().unwrap(),
+            "\
++-------+---------------------+------+------+
+| host  | greptime_timestamp  | idle | busy |
++-------+---------------------+------+------+
+| host1 | 2022-09-22T09:54:56 | 66.6 | 20.1 |
+| host2 | 2022-09-22T09:54:57 | 80.0 |      |
++-------+---------------------+------+------+"
+        );
+
+        let output = instance
+            .do_query(
+                "SELECT region, greptime_value FROM requests",
+                QueryContext::arc(),
+            )
+            .await
+            .remove(0)
+            .unwrap();
+        let Output::Stream(stream) = output else { unreachable!() };
+        let recordbatches = RecordBatches::try_collect(stream).await.unwrap();
+        assert_eq!(
+            recordbatches.pretty_print().unwrap(),
+            "\
++--------+----------------+
+| region | greptime_value |
++--------+----------------+
+| us     | 10.0           |
++--------+----------------+"
+        );
+    }
+}
@@ -15,12 +15,14 @@
 use api::v1::greptime_request::Request;
 use api::v1::query_request::Query;
 use async_trait::async_trait;
+use common_error::prelude::BoxedError;
 use common_query::Output;
+use common_recordbatch::RecordBatch;
 use query::parser::PromQuery;
-use servers::query_handler::grpc::GrpcQueryHandler;
+use servers::query_handler::grpc::{BulkInsertHandler, GrpcQueryHandler};
 use servers::query_handler::sql::SqlQueryHandler;
 use session::context::QueryContextRef;
-use snafu::{ensure, OptionExt};
+use snafu::{ensure, OptionExt, ResultExt};
 
 use crate::error::{self, Result};
 use crate::instance::Instance;
@@ -83,6 +85,21 @@ impl GrpcQueryHandler for Instance {
     }
 }
 
+#[async_trait]
+impl BulkInsertHandler for Instance {
+    async fn insert_recordbatch(
+        &self,
+        table_name: &str,
+        recordbatch: RecordBatch,
+        ctx: QueryContextRef,
+    ) -> servers::error::Result<usize> {
+        self.handle_recordbatch_insert(table_name, recordbatch, ctx)
+            .await
+            .map_err(BoxedError::new)
+            .context(servers::error::ExecuteGrpcQuerySnafu)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::HashMap;
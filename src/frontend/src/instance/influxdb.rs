@@ -35,16 +35,31 @@ impl InfluxdbLineProtocolHandler for Instance {
             .context(servers::error::ExecuteGrpcQuerySnafu)?;
         Ok(())
     }
+
+    async fn database_names(&self, ctx: QueryContextRef) -> servers::error::Result<Vec<String>> {
+        let Some(catalog) = self
+            .catalog_manager
+            .catalog(&ctx.current_catalog())
+            .context(servers::error::CatalogSnafu)? else {
+            return Ok(vec![]);
+        };
+        catalog.schema_names().context(servers::error::CatalogSnafu)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
 
+    use common_grpc::writer::Precision;
     use common_query::Output;
     use common_recordbatch::RecordBatches;
-    use servers::query_handler::sql::SqlQueryHandler;
-    use session::context::QueryContext;
+    use serde_json::json;
+    use servers::influxdb::influxql;
+    use servers::query_handler::sql::{
+        ServerSqlQueryHandlerAdaptor, ServerSqlQueryHandlerRef, SqlQueryHandler,
+    };
+    use session::context::{QueryContext, UserInfo};
 
     use super::*;
     use crate::tests;
@@ -97,4 +112,104 @@ monitor1,host=host2 memory=1027 1663840496400340001";
 +-------------------------+-------+------+--------+"
         );
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_standalone_influxql_query() {
+        let standalone = tests::create_standalone_instance("test_standalone_influxql_query").await;
+        let instance = &standalone.instance;
+
+        test_influxql_query(instance).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_distributed_influxql_query() {
+        let instance = tests::create_distributed_instance("test_distributed_influxql_query").await;
+        let instance = &instance.frontend;
+
+        test_influxql_query(instance).await;
+    }
+
+    async fn test_influxql_query(instance: &Arc<Instance>) {
+        let lines = r"
+monitor1,host=host1 cpu=66.6,memory=1024 1663840496100023100
+monitor1,host=host2 memory=1027 1663840496400340001";
+        let request = InfluxdbRequest {
+            precision: None,
+            lines: lines.to_string(),
+        };
+        instance.exec(&request, QueryContext::arc()).await.unwrap();
+
+        let handler: ServerSqlQueryHandlerRef = ServerSqlQueryHandlerAdaptor::arc(instance.clone());
+        let query = |q: &str| {
+            let handler = handler.clone();
+            let q = q.to_string();
+            async move {
+                let response = influxql::execute(
+                    &handler,
+                    &q,
+                    "public",
+                    Some(Precision::Millisecond),
+                    UserInfo::default(),
+                )
+                .await
+                .unwrap();
+                serde_json::to_value(response).unwrap()
+            }
+        };
+
+        let response = query(
+            "SELECT mean(memory) FROM monitor1 WHERE time >= 1663840496000ms AND time < 1663840497000ms GROUP BY time(200ms), host fill(none)",
+        )
+        .await;
+        assert_eq!(
+            json!({"results": [{"statement_id": 0, "series": [
+                {"name": "monitor1", "tags": {"host": "host1"}, "columns": ["time", "mean"], "values": [[1663840496000i64, 1024.0]]},
+                {"name": "monitor1", "tags": {"host": "host2"}, "columns": ["time", "mean"], "values": [[1663840496400i64, 1027.0]]},
+            ]}]}),
+            response
+        );
+
+        let response = query(
+            "SELECT max(cpu) FROM monitor1 WHERE time >= 1663840496000ms AND time < 1663840496600ms GROUP BY time(200ms)",
+        )
+        .await;
+        assert_eq!(
+            json!({"results": [{"statement_id": 0, "series": [
+                {"name": "monitor1", "columns": ["time", "max"], "values": [
+                    [1663840496000i64, 66.6],
+                    [1663840496200i64, null],
+                    [1663840496400i64, null],
+                ]},
+            ]}]}),
+            response
+        );
+
+        let response =
+            query("SELECT * FROM monitor1 WHERE host = 'host2'; SELECT last(cpu) FROM monitor1")
+                .await;
+        assert_eq!(
+            json!({"results": [
+                {"statement_id": 0, "series": [
+                    {"name": "monitor1", "columns": ["time", "cpu", "host", "memory"], "values": [[1663840496400i64, null, "host2", 1027.0]]},
+                ]},
+                {"statement_id": 1, "error": "Invalid InfluxQL: unsupported function: last()"},
+            ]}),
+            response
+        );
+
+        let response = query(
+            "SHOW MEASUREMENTS WITH MEASUREMENT =~ /monitor/; SHOW TAG KEYS FROM monitor1; SHOW TAG VALUES FROM monitor1 WITH KEY = \"host\"; SHOW FIELD KEYS FROM monitor1; SELECT mean(cpu) FROM not_exist",
+        )
+        .await;
+        assert_eq!(
+            json!({"results": [
+                {"statement_id": 0, "series": [{"name": "measurements", "columns": ["name"], "values": [["monitor1"]]}]},
+                {"statement_id": 1, "series": [{"name": "monitor1", "columns": ["tagKey"], "values": [["host"]]}]},
+                {"statement_id": 2, "series": [{"name": "monitor1", "columns": ["key", "value"], "values": [["host", "host1"], ["host", "host2"]]}]},
+                {"statement_id": 3, "series": [{"name": "monitor1", "columns": ["fieldKey", "fieldType"], "values": [["cpu", "float"], ["memory", "float"]]}]},
+                {"statement_id": 4},
+            ]}),
+            response
+        );
+    }
 }
@@ -43,7 +43,11 @@ mod tests {
     use common_query::Output;
     use common_recordbatch::RecordBatches;
     use itertools::Itertools;
-    use servers::query_handler::sql::SqlQueryHandler;
+    use serde_json::json;
+    use servers::opentsdb::query::{self, LookupRequest, QueryRequest, SubQuery, TimeSpec};
+    use servers::query_handler::sql::{
+        ServerSqlQueryHandlerAdaptor, ServerSqlQueryHandlerRef, SqlQueryHandler,
+    };
     use session::context::QueryContext;
 
     use super::*;
@@ -126,4 +130,93 @@ mod tests {
             _ => unreachable!(),
         };
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_standalone_opentsdb_query() {
+        let standalone = tests::create_standalone_instance("test_standalone_opentsdb_query").await;
+        let instance = &standalone.instance;
+
+        test_opentsdb_query(instance).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_distributed_opentsdb_query() {
+        let distributed =
+            tests::create_distributed_instance("test_distributed_opentsdb_query").await;
+        let instance = &distributed.frontend;
+
+        test_opentsdb_query(instance).await;
+    }
+
+    async fn test_opentsdb_query(instance: &Arc<Instance>) {
+        let ctx = QueryContext::arc();
+        for (host, ts, value) in [
+            ("web01", 1000, 1.0),
+            ("web01", 2000, 2.0),
+            ("web02", 1000, 10.0),
+            ("web02", 2000, 20.0),
+        ] {
+            let tags = vec![
+                ("host".to_string(), host.to_string()),
+                ("dc".to_string(), "lga".to_string()),
+            ];
+            let data_point = DataPoint::new("sys_cpu".to_string(), ts, value, tags);
+            instance.exec(&data_point, ctx.clone()).await.unwrap();
+        }
+
+        let handler: ServerSqlQueryHandlerRef = ServerSqlQueryHandlerAdaptor::arc(instance.clone());
+        let query = |m: &[&str]| {
+            let handler = handler.clone();
+            let request = QueryRequest {
+                start: TimeSpec::Number(0),
+                end: Some(TimeSpec::Number(10)),
+                queries: m.iter().map(|m| SubQuery::parse(m).unwrap()).collect(),
+                ms_resolution: false,
+            };
+            async move {
+                let results = query::execute_query(&handler, &request, QueryContext::arc())
+                    .await
+                    .unwrap();
+                serde_json::to_value(results).unwrap()
+            }
+        };
+
+        assert_eq!(
+            json!([{"metric": "sys_cpu", "tags": {"dc": "lga"}, "aggregateTags": ["host"], "dps": {"1": 11.0, "2": 22.0}}]),
+            query(&["sum:sys_cpu"]).await
+        );
+        assert_eq!(
+            json!([
+                {"metric": "sys_cpu", "tags": {"dc": "lga", "host": "web01"}, "aggregateTags": [], "dps": {"1": 1.0, "2": 2.0}},
+                {"metric": "sys_cpu", "tags": {"dc": "lga", "host": "web02"}, "aggregateTags": [], "dps": {"1": 10.0, "2": 20.0}},
+            ]),
+            query(&["max:sys_cpu{host=*}"]).await
+        );
+        assert_eq!(
+            json!([{"metric": "sys_cpu", "tags": {"dc": "lga"}, "aggregateTags": ["host"], "dps": {"0": 33.0}}]),
+            query(&["sum:0all-sum:sys_cpu{dc=lga}{host=web01|web02}"]).await
+        );
+        assert_eq!(
+            json!([{"metric": "sys_cpu", "tags": {"dc": "lga", "host": "web02"}, "aggregateTags": [], "dps": {"2": 10.0}}]),
+            query(&["sum:rate:sys_cpu{host=web02}"]).await
+        );
+        assert_eq!(
+            json!([]),
+            query(&["sum:sys_cpu{rack=*}", "sum:unknown"]).await
+        );
+
+        let lookup = LookupRequest::parse("sys_cpu{host=*}", None, 0).unwrap();
+        let response = query::execute_lookup(&handler, lookup, QueryContext::arc())
+            .await
+            .unwrap();
+        assert_eq!(2, response.total_results);
+        assert_eq!(
+            vec!["web01", "web02"],
+            response
+                .results
+                .iter()
+                .map(|result| result.tags["host"].as_str())
+                .collect::<Vec<_>>()
+        );
+    }
 }
@@ -0,0 +1,208 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use async_trait::async_trait;
+use common_error::prelude::BoxedError;
+use servers::otlp::OtlpMetricsRequest;
+use servers::query_handler::OpenTelemetryProtocolHandler;
+use session::context::QueryContextRef;
+use snafu::ResultExt;
+
+use crate::instance::Instance;
+
+#[async_trait]
+impl OpenTelemetryProtocolHandler for Instance {
+    async fn metrics(
+        &self,
+        request: &OtlpMetricsRequest,
+        ctx: QueryContextRef,
+    ) -> servers::error::Result<()> {
+        let requests = request.try_into()?;
+        self.handle_inserts(requests, ctx)
+            .await
+            .map_err(BoxedError::new)
+            .context(servers::error::ExecuteGrpcQuerySnafu)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use common_query::Output;
+    use common_recordbatch::RecordBatches;
+    use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+    use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::metrics::v1::{
+        metric, number_data_point, AggregationTemporality, Gauge, Histogram, HistogramDataPoint,
+        Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum,
+    };
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+    use servers::otlp::MetricNaming;
+    use servers::query_handler::sql::SqlQueryHandler;
+    use session::context::QueryContext;
+
+    use super::*;
+    use crate::tests;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_standalone_otlp_metrics() {
+        let standalone = tests::create_standalone_instance("test_standalone_otlp_metrics").await;
+        let instance = &standalone.instance;
+
+        test_otlp_metrics(instance).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_distributed_otlp_metrics() {
+        let instance = tests::create_distributed_instance("test_distributed_otlp_metrics").await;
+        let instance = &instance.frontend;
+
+        test_otlp_metrics(instance).await;
+    }
+
+    fn key_value(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    fn number_point(time_unix_nano: u64, value: f64, host: &str) -> NumberDataPoint {
+        NumberDataPoint {
+            attributes: vec![key_value("host", host)],
+            time_unix_nano,
+            value: Some(number_data_point::Value::AsDouble(value)),
+            ..Default::default()
+        }
+    }
+
+    async fn query(instance: &Arc<Instance>, sql: &str) -> String {
+        let output = instance
+            .do_query(sql, QueryContext::arc())
+            .await
+            .remove(0)
+            .unwrap();
+        let Output::Stream(stream) = output else { unreachable!() };
+        let recordbatches = RecordBatches::try_collect(stream).await.unwrap();
+        recordbatches.pretty_print().unwrap()
+    }
+
+    async fn test_otlp_metrics(instance: &Arc<Instance>) {
+        let metrics = vec![
+            Metric {
+                name: "system.memory.usage".to_string(),
+                unit: "By".to_string(),
+                data: Some(metric::Data::Gauge(Gauge {
+                    data_points: vec![
+                        number_point(1663840496100023100, 1024.0, "host1"),
+                        number_point(1663840496400340001, 1027.0, "host2"),
+                    ],
+                })),
+                ..Default::default()
+            },
+            Metric {
+                name: "http.server.requests".to_string(),
+                data: Some(metric::Data::Sum(Sum {
+                    data_points: vec![number_point(1663840496100023100, 10.0, "host1")],
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                    is_monotonic: true,
+                })),
+                ..Default::default()
+            },
+            Metric {
+                name: "http.server.duration".to_string(),
+                unit: "ms".to_string(),
+                data: Some(metric::Data::Histogram(Histogram {
+                    data_points: vec![HistogramDataPoint {
+                        attributes: vec![key_value("host", "host1")],
+                        time_unix_nano: 1663840496100023100,
+                        count: 3,
+                        sum: Some(12.0),
+                        bucket_counts: vec![1, 2],
+                        explicit_bounds: vec![5.0],
+                        ..Default::default()
+                    }],
+                    aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                })),
+                ..Default::default()
+            },
+        ];
+        let request = OtlpMetricsRequest {
+            naming: MetricNaming::Prometheus,
+            request: ExportMetricsServiceRequest {
+                resource_metrics: vec![ResourceMetrics {
+                    resource: Some(Resource {
+                        attributes: vec![key_value("service.name", "api")],
+                        ..Default::default()
+                    }),
+                    scope_metrics: vec![ScopeMetrics {
+                        metrics,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+            },
+        };
+        instance
+            .metrics(&request, QueryContext::arc())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            query(
+                instance,
+                "SELECT greptime_timestamp, host, service_name, greptime_value FROM system_memory_usage_bytes ORDER BY greptime_timestamp",
+            )
+            .await,
+            "\
++-------------------------+-------+--------------+----------------+
+| greptime_timestamp      | host  | service_name | greptime_value |
++-------------------------+-------+--------------+----------------+
+| 2022-09-22T09:54:56.100 | host1 | api          | 1024.0         |
+| 2022-09-22T09:54:56.400 | host2 | api          | 1027.0         |
++-------------------------+-------+--------------+----------------+"
+        );
+        assert_eq!(
+            query(
+                instance,
+                "SELECT host, greptime_value FROM http_server_requests_total",
+            )
+            .await,
+            "\
++-------+----------------+
+| host  | greptime_value |
++-------+----------------+
+| host1 | 10.0           |
++-------+----------------+"
+        );
+        assert_eq!(
+            query(
+                instance,
+                "SELECT le, greptime_value FROM http_server_duration_milliseconds_bucket ORDER BY greptime_value",
+            )
+            .await,
+            "\
++------+----------------+
+| le   | greptime_value |
++------+----------------+
+| 5    | 1.0            |
+| +Inf | 3.0            |
++------+----------------+"
+        );
+    }
+}
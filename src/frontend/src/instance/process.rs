@@ -0,0 +1,218 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use common_query::Output;
+use common_recordbatch::error::{
+    Result as RecordBatchResult, StreamCancelledSnafu, StreamTimeoutSnafu,
+};
+use common_recordbatch::{RecordBatch, RecordBatchStream, SendableRecordBatchStream};
+use datatypes::schema::SchemaRef;
+use futures::{Stream, StreamExt};
+use session::process::{Process, ProcessGuard};
+use tokio::time::{Instant, Sleep};
+
+use crate::error::{QueryCancelledSnafu, QueryTimeoutSnafu, Result};
+
+/// The time by which a statement must finish, including consuming its output.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Deadline {
+    timeout: Duration,
+    instant: Instant,
+}
+
+impl Deadline {
+    pub(crate) fn after(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            instant: Instant::now() + timeout,
+        }
+    }
+}
+
+/// Runs `future` until it completes, or fails it as soon as `process` is killed or the
+/// `deadline` is reached.
+pub(crate) async fn run_cancellable<T>(
+    process: &Process,
+    deadline: Option<Deadline>,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let cancelled = futures::future::poll_fn(|cx| process.poll_cancelled(cx));
+    let timed_out = async {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline.instant).await,
+            None => futures::future::pending().await,
+        }
+    };
+    tokio::select! {
+        biased;
+        _ = cancelled => QueryCancelledSnafu { id: process.id() }.fail(),
+        _ = timed_out => QueryTimeoutSnafu {
+            timeout: deadline.map(|x| x.timeout).unwrap_or_default(),
+        }
+        .fail(),
+        result = future => result,
+    }
+}
+
+/// Keeps the statement in the process list until its output is fully consumed.
+pub(crate) fn attach_process(
+    output: Output,
+    guard: ProcessGuard,
+    deadline: Option<Deadline>,
+) -> Output {
+    match output {
+        Output::Stream(stream) => Output::Stream(Box::pin(CancellableRecordBatchStream {
+            schema: stream.schema(),
+            stream: Some(stream),
+            guard,
+            deadline: deadline.map(|x| (x.timeout, Box::pin(tokio::time::sleep_until(x.instant)))),
+        })),
+        output => output,
+    }
+}
+
+/// A stream that stops yielding record batches once its statement is killed or times out.
+struct CancellableRecordBatchStream {
+    schema: SchemaRef,
+    stream: Option<SendableRecordBatchStream>,
+    guard: ProcessGuard,
+    deadline: Option<(Duration, Pin<Box<Sleep>>)>,
+}
+
+impl RecordBatchStream for CancellableRecordBatchStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Stream for CancellableRecordBatchStream {
+    type Item = RecordBatchResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.stream.is_some() && this.guard.process().poll_cancelled(cx).is_ready() {
+            // Dropping the inner stream stops the execution, in distributed mode this also
+            // cancels the streams from datanodes.
+            this.stream = None;
+            return Poll::Ready(Some(StreamCancelledSnafu.fail()));
+        }
+        if let Some((timeout, sleep)) = this.deadline.as_mut() {
+            if this.stream.is_some() && sleep.as_mut().poll(cx).is_ready() {
+                this.stream = None;
+                return Poll::Ready(Some(StreamTimeoutSnafu { timeout: *timeout }.fail()));
+            }
+        }
+
+        match this.stream.as_mut() {
+            Some(stream) => stream.poll_next_unpin(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common_error::prelude::{ErrorExt, StatusCode};
+    use common_recordbatch::RecordBatches;
+    use datatypes::prelude::{ConcreteDataType, VectorRef};
+    use datatypes::schema::{ColumnSchema, Schema};
+    use datatypes::vectors::UInt32Vector;
+    use session::context::QueryContext;
+    use session::process::ProcessManager;
+
+    use super::*;
+
+    fn new_output() -> Output {
+        let schema = Arc::new(Schema::new(vec![ColumnSchema::new(
+            "n",
+            ConcreteDataType::uint32_datatype(),
+            false,
+        )]));
+        let columns: Vec<VectorRef> = vec![Arc::new(UInt32Vector::from_slice([1, 2, 3]))];
+        let batch = RecordBatch::new(schema.clone(), columns).unwrap();
+        let batches = RecordBatches::try_new(schema, vec![batch.clone(), batch]).unwrap();
+        Output::Stream(batches.as_stream())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stream() {
+        let manager = Arc::new(ProcessManager::default());
+        let query_ctx = QueryContext::arc();
+        let guard = manager.register("SELECT n FROM t", &query_ctx);
+
+        let Output::Stream(mut stream) = attach_process(new_output(), guard, None) else { unreachable!() };
+        assert!(stream.next().await.unwrap().is_ok());
+        assert_eq!(manager.processes().len(), 1);
+
+        assert!(manager.kill(query_ctx.conn_id()));
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::Cancelled);
+        assert!(stream.next().await.is_none());
+
+        drop(stream);
+        assert!(manager.processes().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_future() {
+        let manager = Arc::new(ProcessManager::default());
+        let query_ctx = QueryContext::arc();
+        let guard = manager.register("SELECT sleep()", &query_ctx);
+        let process = guard.process().clone();
+
+        let killer = {
+            let manager = manager.clone();
+            let id = query_ctx.conn_id();
+            tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                manager.kill(id)
+            })
+        };
+        let result =
+            run_cancellable(&process, None, futures::future::pending::<Result<()>>()).await;
+        assert!(killer.await.unwrap());
+        assert_eq!(result.unwrap_err().status_code(), StatusCode::Cancelled);
+
+        let result = run_cancellable(&process, None, async { Ok(1) }).await;
+        assert_eq!(result.unwrap_err().status_code(), StatusCode::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_statement_timeout() {
+        let manager = Arc::new(ProcessManager::default());
+        let query_ctx = QueryContext::arc();
+        let guard = manager.register("SELECT sleep()", &query_ctx);
+
+        let deadline = Some(Deadline::after(Duration::from_millis(10)));
+        let pending = futures::future::pending::<Result<()>>();
+        let result = run_cancellable(guard.process(), deadline, pending).await;
+        assert_eq!(result.unwrap_err().status_code(), StatusCode::QueryTimeout);
+
+        let deadline = Some(Deadline::after(Duration::from_millis(10)));
+        let output = attach_process(new_output(), guard, deadline);
+        let Output::Stream(mut stream) = output else { unreachable!() };
+        assert!(stream.next().await.unwrap().is_ok());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::QueryTimeout);
+        assert!(stream.next().await.is_none());
+    }
+}
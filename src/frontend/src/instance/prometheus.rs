@@ -13,30 +13,37 @@
 // limitations under the License.
 
 use api::prometheus::remote::read_request::ResponseType;
-use api::prometheus::remote::{Query, QueryResult, ReadRequest, ReadResponse, WriteRequest};
+use api::prometheus::remote::{
+    MetricMetadata, Query, QueryResult, ReadRequest, ReadResponse, TimeSeries, WriteRequest,
+};
 use api::v1::greptime_request::Request;
 use api::v1::{query_request, QueryRequest};
 use async_trait::async_trait;
+use catalog::{CatalogManager, RegisterSystemTableRequest};
+use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
 use common_error::prelude::BoxedError;
 use common_query::Output;
 use common_recordbatch::RecordBatches;
 use common_telemetry::logging;
+use futures::stream::BoxStream;
 use prost::Message;
 use servers::error::{self, Result as ServerResult};
+use servers::prometheus::chunked::CHUNKED_CONTENT_TYPE;
 use servers::prometheus::{self, Metrics};
 use servers::query_handler::grpc::GrpcQueryHandler;
-use servers::query_handler::{PrometheusProtocolHandler, PrometheusResponse};
-use session::context::QueryContextRef;
+use servers::query_handler::{
+    PrometheusProtocolHandler, PrometheusResponse, PrometheusResponseBody,
+};
+use session::context::{QueryContext, QueryContextRef};
 use snafu::{OptionExt, ResultExt};
+use table::TableRef;
 
+use crate::error::{CatalogSnafu, Result, TableNotFoundSnafu};
 use crate::instance::Instance;
 
-const SAMPLES_RESPONSE_TYPE: i32 = ResponseType::Samples as i32;
-
 #[inline]
 fn is_supported(response_type: i32) -> bool {
-    // Only supports samples response right now
-    response_type == SAMPLES_RESPONSE_TYPE
+    ResponseType::from_i32(response_type).is_some()
 }
 
 /// Negotiating the content type of the remote read response.
@@ -58,21 +65,37 @@ fn negotiate_response_type(accepted_response_types: &[i32]) -> ServerResult<Resp
             ),
         })?;
 
-    // It's safe to unwrap here, we known that it should be SAMPLES_RESPONSE_TYPE
+    // It's safe to unwrap here, we known that it's a supported response type
     Ok(ResponseType::from_i32(*response_type).unwrap())
 }
 
-async fn to_query_result(table_name: &str, output: Output) -> ServerResult<QueryResult> {
+async fn collect_recordbatches(output: Output) -> ServerResult<RecordBatches> {
     let Output::Stream(stream) = output else { unreachable!() };
-    let recordbatches = RecordBatches::try_collect(stream)
+    RecordBatches::try_collect(stream)
         .await
-        .context(error::CollectRecordbatchSnafu)?;
+        .context(error::CollectRecordbatchSnafu)
+}
+
+async fn to_query_result(table_name: &str, output: Output) -> ServerResult<QueryResult> {
+    let recordbatches = collect_recordbatches(output).await?;
     Ok(QueryResult {
         timeseries: prometheus::recordbatches_to_timeseries(table_name, recordbatches)?,
     })
 }
 
+fn table_tag_columns(table: &TableRef) -> Vec<String> {
+    let table_info = table.table_info();
+    let column_schemas = table_info.meta.schema.column_schemas();
+    table_info
+        .meta
+        .primary_key_indices
+        .iter()
+        .map(|i| column_schemas[*i].name.clone())
+        .collect()
+}
+
 impl Instance {
+    /// Executes the remote read queries.
     async fn handle_remote_queries(
         &self,
         ctx: QueryContextRef,
@@ -88,29 +111,217 @@ impl Instance {
                 sql
             );
 
-            let query = Request::Query(QueryRequest {
-                query: Some(query_request::Query::Sql(sql.to_string())),
-            });
-            let output = self
-                .do_query(query, ctx.clone())
-                .await
-                .map_err(BoxedError::new)
-                .context(error::ExecuteGrpcQuerySnafu)?;
-
+            let output = self.execute_sql(sql, ctx.clone()).await?;
             results.push((table_name, output));
         }
         Ok(results)
     }
+
+    /// Executes the remote read queries for the streamed response. Each query selects its
+    /// samples sorted by their series, see [prometheus::series_ordered_query_sql], and is
+    /// executed as the response is streamed.
+    async fn stream_remote_queries(
+        &self,
+        ctx: QueryContextRef,
+        queries: Vec<Query>,
+    ) -> ServerResult<BoxStream<'static, ServerResult<(usize, String, Output)>>> {
+        let mut sqls = Vec::with_capacity(queries.len());
+        for query in &queries {
+            sqls.push(self.series_ordered_query_sql(query, &ctx).await?);
+        }
+
+        let instance = self.clone();
+        Ok(Box::pin(async_stream::try_stream! {
+            for (query_index, (table_name, sql)) in sqls.into_iter().enumerate() {
+                logging::debug!(
+                    "prometheus remote read, table: {}, sql: {}",
+                    table_name,
+                    sql
+                );
+
+                let output = instance.execute_sql(sql, ctx.clone()).await?;
+                yield (query_index, table_name, output);
+            }
+        }))
+    }
+
+    /// Generates the sql of a remote read query, ordered by the tag columns of the queried
+    /// table, see [prometheus::series_ordered_query_sql].
+    async fn series_ordered_query_sql(
+        &self,
+        query: &Query,
+        ctx: &QueryContextRef,
+    ) -> ServerResult<(String, String)> {
+        let table_name = prometheus::query_table_name(query)?;
+        let table = self
+            .find_table(ctx, &table_name)
+            .await?
+            .context(TableNotFoundSnafu {
+                table_name: &table_name,
+            })
+            .map_err(BoxedError::new)
+            .context(error::ExecuteGrpcQuerySnafu)?;
+        prometheus::series_ordered_query_sql(query, &table_tag_columns(&table))
+    }
+
+    async fn execute_sql(&self, sql: String, ctx: QueryContextRef) -> ServerResult<Output> {
+        let query = Request::Query(QueryRequest {
+            query: Some(query_request::Query::Sql(sql)),
+        });
+        self.do_query(query, ctx)
+            .await
+            .map_err(BoxedError::new)
+            .context(error::ExecuteGrpcQuerySnafu)
+    }
+
+    async fn find_table(
+        &self,
+        ctx: &QueryContextRef,
+        table_name: &str,
+    ) -> ServerResult<Option<TableRef>> {
+        self.catalog_manager
+            .table(&ctx.current_catalog(), &ctx.current_schema(), table_name)
+            .await
+            .context(error::CatalogSnafu)
+    }
+
+    /// Returns the tag columns of the metric's table, see [PromHandler::label_names].
+    ///
+    /// [PromHandler::label_names]: servers::prom::PromHandler::label_names
+    pub(crate) async fn query_label_names(
+        &self,
+        metric: &str,
+        ctx: QueryContextRef,
+    ) -> ServerResult<Option<Vec<String>>> {
+        Ok(self
+            .find_table(&ctx, metric)
+            .await?
+            .map(|table| table_tag_columns(&table)))
+    }
+
+    /// Queries the distinct values of a label, see [PromHandler::label_values].
+    ///
+    /// [PromHandler::label_values]: servers::prom::PromHandler::label_values
+    pub(crate) async fn query_label_values(
+        &self,
+        query: &Query,
+        label_name: &str,
+        ctx: QueryContextRef,
+    ) -> ServerResult<Vec<String>> {
+        let metric = prometheus::query_table_name(query)?;
+        let Some(table) = self.find_table(&ctx, &metric).await? else {
+            return Ok(vec![]);
+        };
+        let schema = table.schema();
+        let Some(timestamp_column) = schema.timestamp_column() else {
+            return Ok(vec![]);
+        };
+        let (table_name, sql) =
+            prometheus::label_values_sql(query, label_name, &timestamp_column.name)?;
+        logging::debug!(
+            "prometheus label values query, table: {}, sql: {}",
+            table_name,
+            sql
+        );
+        let output = self.execute_sql(sql, ctx).await?;
+        Ok(prometheus::recordbatches_to_label_values(
+            collect_recordbatches(output).await?,
+        ))
+    }
+
+    /// Queries the metadata written by remote write requests to the schema of `ctx`, see
+    /// [PromHandler::metric_metadata].
+    ///
+    /// [PromHandler::metric_metadata]: servers::prom::PromHandler::metric_metadata
+    pub(crate) async fn query_metric_metadata(
+        &self,
+        metric: Option<&str>,
+        ctx: QueryContextRef,
+    ) -> ServerResult<Vec<MetricMetadata>> {
+        if !self
+            .has_system_table(prometheus::METRIC_METADATA_TABLE_NAME)
+            .await?
+        {
+            return Ok(vec![]);
+        }
+        let sql =
+            prometheus::metadata_query_sql(&ctx.current_catalog(), &ctx.current_schema(), metric);
+        // The system table is in the default catalog and schema, not in the schema of `ctx`.
+        let output = self.execute_sql(sql, QueryContext::arc()).await?;
+        prometheus::recordbatches_to_metadata(collect_recordbatches(output).await?)
+    }
+
+    /// Queries the exemplars written by remote write requests to the schema of `ctx`, see
+    /// [PromHandler::query_exemplars].
+    ///
+    /// [PromHandler::query_exemplars]: servers::prom::PromHandler::query_exemplars
+    pub(crate) async fn query_series_exemplars(
+        &self,
+        query: &Query,
+        ctx: QueryContextRef,
+    ) -> ServerResult<Vec<TimeSeries>> {
+        if !self
+            .has_system_table(prometheus::EXEMPLAR_TABLE_NAME)
+            .await?
+        {
+            return Ok(vec![]);
+        }
+        let sql =
+            prometheus::exemplars_query_sql(query, &ctx.current_catalog(), &ctx.current_schema())?;
+        logging::debug!("prometheus exemplars query, sql: {}", sql);
+        let output = self.execute_sql(sql, QueryContext::arc()).await?;
+        prometheus::recordbatches_to_exemplars(query, collect_recordbatches(output).await?)
+    }
+
+    /// Returns whether the system table is registered, it's not by the memory catalog manager.
+    async fn has_system_table(&self, table_name: &str) -> ServerResult<bool> {
+        Ok(self
+            .catalog_manager
+            .table(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, table_name)
+            .await
+            .context(error::CatalogSnafu)?
+            .is_some())
+    }
+}
+
+/// Registers the system tables of the metadata and the exemplars written by remote write
+/// requests.
+pub(crate) async fn register_prometheus_tables(catalog_manager: &dyn CatalogManager) -> Result<()> {
+    for request in [
+        prometheus::metric_metadata_table_request(),
+        prometheus::exemplar_table_request(),
+    ] {
+        catalog_manager
+            .register_system_table(RegisterSystemTableRequest {
+                create_table_request: request,
+                open_hook: None,
+            })
+            .await
+            .context(CatalogSnafu)?;
+    }
+    Ok(())
 }
 
 #[async_trait]
 impl PrometheusProtocolHandler for Instance {
     async fn write(&self, request: WriteRequest, ctx: QueryContextRef) -> ServerResult<()> {
-        let requests = prometheus::to_grpc_insert_requests(request.clone())?;
+        let system_requests = prometheus::to_system_insert_requests(
+            &request,
+            &ctx.current_catalog(),
+            &ctx.current_schema(),
+        )?;
+        let requests = prometheus::to_grpc_insert_requests(request)?;
         self.handle_inserts(requests, ctx)
             .await
             .map_err(BoxedError::new)
             .context(error::ExecuteGrpcQuerySnafu)?;
+        if !system_requests.is_empty() {
+            // The system tables are in the default catalog and schema.
+            self.handle_inserts(system_requests, QueryContext::arc())
+                .await
+                .map_err(BoxedError::new)
+                .context(error::ExecuteGrpcQuerySnafu)?;
+        }
         Ok(())
     }
 
@@ -122,10 +333,9 @@ impl PrometheusProtocolHandler for Instance {
         let response_type = negotiate_response_type(&request.accepted_response_types)?;
 
         // TODO(dennis): use read_hints to speedup query if possible
-        let results = self.handle_remote_queries(ctx, &request.queries).await?;
-
         match response_type {
             ResponseType::Samples => {
+                let results = self.handle_remote_queries(ctx, &request.queries).await?;
                 let mut query_results = Vec::with_capacity(results.len());
                 for (table_name, output) in results {
                     query_results.push(to_query_result(&table_name, output).await?);
@@ -139,13 +349,22 @@ impl PrometheusProtocolHandler for Instance {
                 Ok(PrometheusResponse {
                     content_type: "application/x-protobuf".to_string(),
                     content_encoding: "snappy".to_string(),
-                    body: prometheus::snappy_compress(&response.encode_to_vec())?,
+                    body: PrometheusResponseBody::Full(prometheus::snappy_compress(
+                        &response.encode_to_vec(),
+                    )?),
                 })
             }
-            ResponseType::StreamedXorChunks => error::NotSupportedSnafu {
-                feat: "streamed remote read",
+            ResponseType::StreamedXorChunks => {
+                let results = self.stream_remote_queries(ctx, request.queries).await?;
+                Ok(PrometheusResponse {
+                    content_type: CHUNKED_CONTENT_TYPE.to_string(),
+                    // Frames are not compressed.
+                    content_encoding: String::new(),
+                    body: PrometheusResponseBody::Stream(prometheus::chunked_read_response_stream(
+                        results,
+                    )),
+                })
             }
-            .fail(),
         }
     }
 
@@ -159,10 +378,11 @@ mod tests {
     use std::sync::Arc;
 
     use api::prometheus::remote::label_matcher::Type as MatcherType;
-    use api::prometheus::remote::{Label, LabelMatcher, Sample};
-    use common_catalog::consts::DEFAULT_CATALOG_NAME;
+    use api::prometheus::remote::metric_metadata::MetricType;
+    use api::prometheus::remote::{Exemplar, Label, LabelMatcher, Sample};
+    use futures::TryStreamExt;
+    use servers::prometheus::chunked;
     use servers::query_handler::sql::SqlQueryHandler;
-    use session::context::QueryContext;
 
     use super::*;
     use crate::tests;
@@ -239,10 +459,16 @@ mod tests {
             ..Default::default()
         };
 
-        let resp = instance.read(read_request, ctx).await.unwrap();
+        let resp = instance
+            .read(read_request.clone(), ctx.clone())
+            .await
+            .unwrap();
         assert_eq!(resp.content_type, "application/x-protobuf");
         assert_eq!(resp.content_encoding, "snappy");
-        let body = prometheus::snappy_decompress(&resp.body).unwrap();
+        let PrometheusResponseBody::Full(body) = resp.body else {
+            unreachable!()
+        };
+        let body = prometheus::snappy_decompress(&body).unwrap();
         let read_response = ReadResponse::decode(&body[..]).unwrap();
         let query_results = read_response.results;
         assert_eq!(2, query_results.len());
@@ -316,5 +542,330 @@ mod tests {
                 }
             ]
         );
+
+        test_prometheus_streamed_remote_read(instance, read_request, ctx.clone()).await;
+        test_prometheus_metadata_and_exemplars(instance, ctx).await;
+    }
+
+    async fn test_prometheus_metadata_and_exemplars(
+        instance: &Arc<Instance>,
+        ctx: QueryContextRef,
+    ) {
+        assert!(instance
+            .query_metric_metadata(None, ctx.clone())
+            .await
+            .unwrap()
+            .is_empty());
+
+        let write_request = WriteRequest {
+            timeseries: vec![TimeSeries {
+                labels: vec![
+                    Label {
+                        name: prometheus::METRIC_NAME_LABEL.to_string(),
+                        value: "metric1".to_string(),
+                    },
+                    Label {
+                        name: "job".to_string(),
+                        value: "spark".to_string(),
+                    },
+                ],
+                exemplars: vec![
+                    Exemplar {
+                        labels: vec![Label {
+                            name: "trace_id".to_string(),
+                            value: "abc".to_string(),
+                        }],
+                        value: 1.5,
+                        timestamp: 1500,
+                    },
+                    Exemplar {
+                        labels: vec![],
+                        value: 2.5,
+                        timestamp: 5000,
+                    },
+                ],
+                ..Default::default()
+            }],
+            metadata: vec![
+                MetricMetadata {
+                    r#type: MetricType::Gauge as i32,
+                    metric_family_name: "metric1".to_string(),
+                    help: "The first metric.".to_string(),
+                    unit: "".to_string(),
+                },
+                MetricMetadata {
+                    r#type: MetricType::Counter as i32,
+                    metric_family_name: "metric3".to_string(),
+                    help: "The third metric.".to_string(),
+                    unit: "seconds".to_string(),
+                },
+            ],
+        };
+        instance.write(write_request, ctx.clone()).await.unwrap();
+
+        let metadata = instance
+            .query_metric_metadata(None, ctx.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![
+                MetricMetadata {
+                    r#type: MetricType::Gauge as i32,
+                    metric_family_name: "metric1".to_string(),
+                    help: "The first metric.".to_string(),
+                    unit: "".to_string(),
+                },
+                MetricMetadata {
+                    r#type: MetricType::Counter as i32,
+                    metric_family_name: "metric3".to_string(),
+                    help: "The third metric.".to_string(),
+                    unit: "seconds".to_string(),
+                },
+            ],
+            metadata
+        );
+        let metadata = instance
+            .query_metric_metadata(Some("metric3"), ctx.clone())
+            .await
+            .unwrap();
+        assert_eq!(1, metadata.len());
+        assert_eq!("metric3", metadata[0].metric_family_name);
+        // The metadata is scoped to the schema it's written to.
+        assert!(instance
+            .query_metric_metadata(None, QueryContext::arc())
+            .await
+            .unwrap()
+            .is_empty());
+
+        let query = Query {
+            start_timestamp_ms: 1000,
+            end_timestamp_ms: 2000,
+            matchers: vec![
+                LabelMatcher {
+                    name: prometheus::METRIC_NAME_LABEL.to_string(),
+                    value: "metric1".to_string(),
+                    r#type: MatcherType::Eq as i32,
+                },
+                LabelMatcher {
+                    name: "job".to_string(),
+                    value: "spark".to_string(),
+                    r#type: MatcherType::Eq as i32,
+                },
+            ],
+            ..Default::default()
+        };
+        let series = instance
+            .query_series_exemplars(&query, ctx.clone())
+            .await
+            .unwrap();
+        assert_eq!(1, series.len());
+        assert_eq!(
+            vec![
+                Label {
+                    name: prometheus::METRIC_NAME_LABEL.to_string(),
+                    value: "metric1".to_string(),
+                },
+                Label {
+                    name: "job".to_string(),
+                    value: "spark".to_string(),
+                },
+            ],
+            series[0].labels
+        );
+        assert_eq!(
+            vec![Exemplar {
+                labels: vec![Label {
+                    name: "trace_id".to_string(),
+                    value: "abc".to_string(),
+                }],
+                value: 1.5,
+                timestamp: 1500,
+            }],
+            series[0].exemplars
+        );
+
+        // The series matching the labels has no exemplars.
+        let mut query = query;
+        query.matchers[1].value = "hadoop".to_string();
+        assert!(instance
+            .query_series_exemplars(&query, ctx.clone())
+            .await
+            .unwrap()
+            .is_empty());
+        // The metric has no exemplars.
+        query.matchers[0].value = "metric2".to_string();
+        query.matchers[1].r#type = MatcherType::Neq as i32;
+        assert!(instance
+            .query_series_exemplars(&query, ctx)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Reads the series in the streamed response of the read request. The chunks of a series
+    /// may be split into several frames, they are merged back into one series.
+    async fn streamed_remote_read(
+        instance: &Arc<Instance>,
+        mut read_request: ReadRequest,
+        ctx: QueryContextRef,
+    ) -> Vec<(i64, Vec<(String, String)>, Vec<(i64, f64)>)> {
+        read_request.accepted_response_types = vec![ResponseType::StreamedXorChunks as i32];
+        let resp = instance.read(read_request, ctx).await.unwrap();
+        assert_eq!(resp.content_type, CHUNKED_CONTENT_TYPE);
+        assert!(resp.content_encoding.is_empty());
+        let PrometheusResponseBody::Stream(stream) = resp.body else {
+            unreachable!()
+        };
+        let body = stream.try_concat().await.unwrap();
+
+        let mut series: Vec<(i64, Vec<(String, String)>, Vec<(i64, f64)>)> = Vec::new();
+        let mut rest = &body[..];
+        while !rest.is_empty() {
+            let (response, remaining) = chunked::decode_frame(rest).unwrap();
+            for chunked_series in response.chunked_series {
+                let labels = chunked_series
+                    .labels
+                    .into_iter()
+                    .map(|label| (label.name, label.value))
+                    .collect::<Vec<_>>();
+                let samples = chunked_series
+                    .chunks
+                    .iter()
+                    .flat_map(|chunk| chunked::decode_xor_chunk(chunk).unwrap());
+                match series.last_mut() {
+                    Some((query_index, last_labels, last_samples))
+                        if *query_index == response.query_index && *last_labels == labels =>
+                    {
+                        last_samples.extend(samples)
+                    }
+                    _ => series.push((response.query_index, labels, samples.collect())),
+                }
+            }
+            rest = remaining;
+        }
+        series
+    }
+
+    async fn test_prometheus_streamed_remote_read(
+        instance: &Arc<Instance>,
+        read_request: ReadRequest,
+        ctx: QueryContextRef,
+    ) {
+        let series = streamed_remote_read(instance, read_request, ctx).await;
+
+        let label = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            vec![
+                (
+                    0,
+                    vec![label("__name__", "metric1"), label("job", "spark")],
+                    vec![(1000, 1.0), (2000, 2.0)],
+                ),
+                (
+                    1,
+                    vec![
+                        label("__name__", "metric3"),
+                        label("app", "biz"),
+                        label("idc", "z002"),
+                    ],
+                    vec![(1000, 5.0), (2000, 6.0), (3000, 7.0)],
+                ),
+            ],
+            series
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_standalone_prometheus_streamed_remote_read_many_samples() {
+        let standalone = tests::create_standalone_instance(
+            "test_standalone_prometheus_streamed_remote_read_many_samples",
+        )
+        .await;
+        test_prometheus_streamed_remote_read_many_samples(&standalone.instance).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_distributed_prometheus_streamed_remote_read_many_samples() {
+        let distributed = tests::create_distributed_instance(
+            "test_distributed_prometheus_streamed_remote_read_many_samples",
+        )
+        .await;
+        test_prometheus_streamed_remote_read_many_samples(&distributed.frontend).await;
+    }
+
+    async fn test_prometheus_streamed_remote_read_many_samples(instance: &Arc<Instance>) {
+        // More samples in each series than in one record batch.
+        let num_samples = 10_000;
+        let samples = (0..num_samples)
+            .map(|i| Sample {
+                value: i as f64,
+                timestamp: i * 1000,
+            })
+            .collect::<Vec<_>>();
+        let label = |name: &str, value: &str| Label {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        let series_labels = [
+            vec![label("__name__", "many"), label("job", "b")],
+            vec![label("__name__", "many"), label("job", "a")],
+            vec![label("__name__", "many"), label("instance", "i")],
+        ];
+        let write_request = WriteRequest {
+            timeseries: series_labels
+                .iter()
+                .map(|labels| TimeSeries {
+                    labels: labels.clone(),
+                    samples: samples.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let ctx = QueryContext::arc();
+        instance.write(write_request, ctx.clone()).await.unwrap();
+
+        let read_request = ReadRequest {
+            queries: vec![Query {
+                start_timestamp_ms: 0,
+                end_timestamp_ms: num_samples * 1000,
+                matchers: vec![LabelMatcher {
+                    name: prometheus::METRIC_NAME_LABEL.to_string(),
+                    value: "many".to_string(),
+                    r#type: MatcherType::Eq as i32,
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let series = streamed_remote_read(instance, read_request, ctx).await;
+
+        // Series are sorted by their labels, then the samples of each series by timestamp.
+        let label = |name: &str, value: &str| (name.to_string(), value.to_string());
+        let samples = samples
+            .iter()
+            .map(|sample| (sample.timestamp, sample.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (
+                    0,
+                    vec![label("__name__", "many"), label("instance", "i")],
+                    samples.clone(),
+                ),
+                (
+                    0,
+                    vec![label("__name__", "many"), label("job", "a")],
+                    samples.clone(),
+                ),
+                (
+                    0,
+                    vec![label("__name__", "many"), label("job", "b")],
+                    samples,
+                ),
+            ],
+            series
+        );
     }
 }
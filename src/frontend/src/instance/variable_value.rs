@@ -0,0 +1,142 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parses the values of session variables set by `SET variable = value`.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use common_base::readable_size::ReadableSize;
+use snafu::ensure;
+use sql::ast::{Expr, Value};
+
+use crate::error::{InvalidVariableValueSnafu, Result};
+
+/// Parses a timeout, either in milliseconds like `1000` or a duration like `'30s'`. A zero
+/// timeout disables the timeout.
+pub(crate) fn parse_timeout(variable: &str, value: &[Expr]) -> Result<Option<Duration>> {
+    let timeout = match single_value(variable, value)? {
+        Value::Number(n, _) => n
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|e| e.to_string()),
+        Value::SingleQuotedString(s) | Value::DoubleQuotedString(s) => {
+            humantime::parse_duration(s).map_err(|e| e.to_string())
+        }
+        v => Err(format!("unexpected value {v}")),
+    }
+    .map_err(|reason| invalid_value(variable, value, reason))?;
+
+    Ok((!timeout.is_zero()).then_some(timeout))
+}
+
+/// Parses a memory size, either in bytes like `1048576` or a readable size like `'1GB'`.
+/// A zero size disables the limit.
+pub(crate) fn parse_memory_limit(variable: &str, value: &[Expr]) -> Result<Option<usize>> {
+    let size = match single_value(variable, value)? {
+        Value::Number(n, _) => n.parse::<u64>().map_err(|e| e.to_string()),
+        Value::SingleQuotedString(s) | Value::DoubleQuotedString(s) => {
+            ReadableSize::from_str(s).map(|x| x.as_bytes())
+        }
+        v => Err(format!("unexpected value {v}")),
+    }
+    .map_err(|reason| invalid_value(variable, value, reason))?;
+
+    Ok((size > 0).then_some(size as usize))
+}
+
+fn single_value<'a>(variable: &str, value: &'a [Expr]) -> Result<&'a Value> {
+    ensure!(
+        value.len() == 1,
+        InvalidVariableValueSnafu {
+            variable,
+            value: format_value(value),
+            reason: "expect exactly one value",
+        }
+    );
+    match &value[0] {
+        Expr::Value(v) => Ok(v),
+        _ => Err(invalid_value(
+            variable,
+            value,
+            "expect a literal value".to_string(),
+        )),
+    }
+}
+
+fn invalid_value(variable: &str, value: &[Expr], reason: String) -> crate::error::Error {
+    InvalidVariableValueSnafu {
+        variable,
+        value: format_value(value),
+        reason,
+    }
+    .build()
+}
+
+fn format_value(value: &[Expr]) -> String {
+    value
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(n: &str) -> Vec<Expr> {
+        vec![Expr::Value(Value::Number(n.to_string(), false))]
+    }
+
+    fn string(s: &str) -> Vec<Expr> {
+        vec![Expr::Value(Value::SingleQuotedString(s.to_string()))]
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(
+            parse_timeout("statement_timeout", &number("1500")).unwrap(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_timeout("statement_timeout", &string("30s")).unwrap(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_timeout("statement_timeout", &number("0")).unwrap(),
+            None
+        );
+        assert!(parse_timeout("statement_timeout", &string("forever")).is_err());
+        assert!(parse_timeout("statement_timeout", &number("-1")).is_err());
+        assert!(parse_timeout("statement_timeout", &[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_memory_limit() {
+        assert_eq!(
+            parse_memory_limit("query_memory_limit", &number("1024")).unwrap(),
+            Some(1024)
+        );
+        assert_eq!(
+            parse_memory_limit("query_memory_limit", &string("1MB")).unwrap(),
+            Some(1024 * 1024)
+        );
+        assert_eq!(
+            parse_memory_limit("query_memory_limit", &number("0")).unwrap(),
+            None
+        );
+        assert!(parse_memory_limit("query_memory_limit", &string("lots")).is_err());
+    }
+}
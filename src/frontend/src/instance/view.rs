@@ -0,0 +1,137 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use catalog::{DeregisterViewRequest, RegisterViewRequest};
+use common_error::prelude::BoxedError;
+use common_query::Output;
+use datanode::instance::sql::table_idents_to_full_name;
+use query::parser::QueryStatement;
+use session::context::{QueryContext, QueryContextRef};
+use snafu::{ensure, OptionExt, ResultExt};
+use sql::statements::create::CreateView;
+use sql::statements::drop::DropView;
+use sql::statements::show::ShowCreateView;
+use sql::statements::statement::Statement;
+
+use crate::error::{
+    CatalogSnafu, ExecuteStatementSnafu, ExternalSnafu, PlanStatementSnafu, Result,
+    ViewAlreadyExistSnafu, ViewNotFoundSnafu,
+};
+use crate::instance::Instance;
+
+impl Instance {
+    pub(crate) async fn create_view(
+        &self,
+        stmt: CreateView,
+        query_ctx: QueryContextRef,
+    ) -> Result<Output> {
+        let (catalog, schema, view) = table_idents_to_full_name(&stmt.name, query_ctx)
+            .map_err(BoxedError::new)
+            .context(ExternalSnafu)?;
+
+        if !stmt.or_replace
+            && self
+                .catalog_manager
+                .view(&catalog, &schema, &view)
+                .await
+                .context(CatalogSnafu)?
+                .is_some()
+        {
+            return if stmt.if_not_exists {
+                Ok(Output::AffectedRows(0))
+            } else {
+                ViewAlreadyExistSnafu {
+                    view: stmt.name.to_string(),
+                }
+                .fail()
+            };
+        }
+
+        // Tables in the definition are resolved in the view's schema when the view is expanded,
+        // so the definition is validated in that schema too.
+        let definition = stmt.query.inner.to_string();
+        let _ = self
+            .query_engine
+            .planner()
+            .plan(
+                QueryStatement::Sql(Statement::Query(stmt.query)),
+                Arc::new(QueryContext::with(&catalog, &schema)),
+            )
+            .await
+            .context(PlanStatementSnafu)?;
+
+        // Fails if a table of the name exists, tables and views share their names.
+        let _ = self
+            .catalog_manager
+            .register_view(RegisterViewRequest {
+                catalog,
+                schema,
+                view_name: view,
+                definition,
+            })
+            .await
+            .context(CatalogSnafu)?;
+        Ok(Output::AffectedRows(0))
+    }
+
+    pub(crate) async fn drop_view(
+        &self,
+        stmt: DropView,
+        query_ctx: QueryContextRef,
+    ) -> Result<Output> {
+        let (catalog, schema, view) = table_idents_to_full_name(stmt.view_name(), query_ctx)
+            .map_err(BoxedError::new)
+            .context(ExternalSnafu)?;
+
+        let dropped = self
+            .catalog_manager
+            .deregister_view(DeregisterViewRequest {
+                catalog,
+                schema,
+                view_name: view,
+            })
+            .await
+            .context(CatalogSnafu)?;
+        ensure!(
+            dropped || stmt.if_exists(),
+            ViewNotFoundSnafu {
+                view: stmt.view_name().to_string(),
+            }
+        );
+        Ok(Output::AffectedRows(0))
+    }
+
+    pub(crate) async fn show_create_view(
+        &self,
+        stmt: ShowCreateView,
+        query_ctx: QueryContextRef,
+    ) -> Result<Output> {
+        let (catalog, schema, view) = table_idents_to_full_name(&stmt.view_name, query_ctx)
+            .map_err(BoxedError::new)
+            .context(ExternalSnafu)?;
+
+        let definition = self
+            .catalog_manager
+            .view(&catalog, &schema, &view)
+            .await
+            .context(CatalogSnafu)?
+            .with_context(|| ViewNotFoundSnafu {
+                view: stmt.view_name.to_string(),
+            })?;
+        query::sql::show_create_view(&stmt.view_name.to_string(), &definition)
+            .context(ExecuteStatementSnafu)
+    }
+}
@@ -19,16 +19,22 @@ pub mod catalog;
 pub mod datanode;
 pub mod error;
 mod expr_factory;
+pub mod flight_sql;
 pub mod frontend;
+pub mod graphite;
 pub mod grpc;
 pub mod influxdb;
 pub mod instance;
+mod materialized_view;
 pub(crate) mod metric;
 pub mod mysql;
 pub mod opentsdb;
+pub mod otlp;
 pub mod postgres;
 pub mod prom;
 pub mod prometheus;
+pub mod query_limit;
+pub mod rule;
 mod script;
 mod server;
 mod table;
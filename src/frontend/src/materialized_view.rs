@@ -0,0 +1,581 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Materialized views are regular tables holding the results of aggregating queries over time
+//! buckets. They are refreshed periodically in the background from the newly written data.
+//!
+//! The defining query of a view is kept in the options of its table, so a view is found, listed
+//! and dropped along with its table, and its name never collides with another table. The views
+//! to refresh are kept in a registry, see [registry::ViewRegistry].
+
+mod definition;
+mod registry;
+mod watermark;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use api::v1::ddl_request::Expr as DdlExpr;
+use api::v1::greptime_request::Request;
+use api::v1::{CreateTableExpr, DdlRequest, DropTableExpr};
+use catalog::CatalogManagerRef;
+use common_catalog::consts::MITO_ENGINE;
+use common_error::prelude::BoxedError;
+use common_query::Output;
+use common_recordbatch::util as record_util;
+use common_runtime::{RepeatedTask, TaskFunction};
+use common_telemetry::logging::{error, info};
+use common_time::timestamp::TimeUnit;
+use datanode::instance::sql::table_idents_to_full_name;
+use datatypes::prelude::{ConcreteDataType, Value};
+use datatypes::schema::ColumnSchema;
+use meta_client::rpc::TableName;
+use query::parser::{QueryLanguageParser, QueryStatement};
+use query::QueryEngineRef;
+pub(crate) use registry::{KvViewRegistry, MemoryViewRegistry, ViewRegistryRef};
+use servers::query_handler::grpc::GrpcQueryHandlerRef;
+use session::context::{QueryContext, QueryContextRef};
+use snafu::{ensure, OptionExt, ResultExt};
+use sql::ast::Ident;
+use sql::statements::create::CreateMaterializedView;
+use sql::statements::drop::DropMaterializedView;
+use sql::statements::statement::Statement;
+use sql::util::parse_option_string;
+use table::TableRef;
+pub(crate) use watermark::{KvWatermarkStore, MemoryWatermarkStore, WatermarkStoreRef};
+
+use crate::error::{
+    CatalogSnafu, CollectRecordbatchSnafu, DescribeStatementSnafu, Error, ExecLogicalPlanSnafu,
+    ExternalSnafu, IllegalFrontendStateSnafu, InvalidMaterializedViewSnafu,
+    MaterializedViewNotFoundSnafu, ParseQuerySnafu, PlanStatementSnafu, Result,
+    StartRepeatedTaskSnafu, StopRepeatedTaskSnafu, TableAlreadyExistSnafu,
+};
+use crate::expr_factory::column_schemas_to_defs;
+use crate::materialized_view::definition::ViewDefinition;
+
+/// How often the materialized views are refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Max number of buckets aggregated by one refresh of a view. A view over a long history is
+/// filled by several refreshes, instead of a single query over the whole history.
+const MAX_REFRESH_BUCKETS: i64 = 1_000;
+
+/// Option of `CREATE MATERIALIZED VIEW ... WITH (...)`, how late the source rows may arrive and
+/// still be aggregated into the view, e.g. `allowed_lateness = '1h'`.
+const ALLOWED_LATENESS_KEY: &str = "allowed_lateness";
+
+/// Options of the view's table, the defining query and the allowed lateness in milliseconds.
+const QUERY_TABLE_OPTION: &str = "materialized_view_query";
+const ALLOWED_LATENESS_TABLE_OPTION: &str = "materialized_view_allowed_lateness";
+
+pub(crate) type MaterializedViewManagerRef = Arc<MaterializedViewManager>;
+
+pub(crate) struct MaterializedViewManager {
+    refresher: Arc<ViewRefresher>,
+    grpc_query_handler: GrpcQueryHandlerRef<Error>,
+    refresh_task: RepeatedTask<Error>,
+}
+
+impl MaterializedViewManager {
+    pub(crate) fn new(
+        catalog_manager: CatalogManagerRef,
+        query_engine: QueryEngineRef,
+        grpc_query_handler: GrpcQueryHandlerRef<Error>,
+        watermarks: WatermarkStoreRef,
+        registry: ViewRegistryRef,
+    ) -> Self {
+        let refresher = Arc::new(ViewRefresher {
+            catalog_manager,
+            query_engine,
+            watermarks,
+            registry,
+        });
+        Self {
+            refresher: refresher.clone(),
+            grpc_query_handler,
+            refresh_task: RepeatedTask::new(REFRESH_INTERVAL, refresher),
+        }
+    }
+
+    /// Starts refreshing the views periodically in background.
+    ///
+    /// Every frontend tries to refresh all the views, but each window of a view is refreshed by
+    /// the frontend advancing the view's watermark over it.
+    ///
+    /// The views in the catalog are registered first, e.g. the ones kept in memory before a
+    /// restart.
+    pub(crate) async fn start(&self) -> Result<()> {
+        for view in self.refresher.find_views().await? {
+            self.refresher.registry.register(&view).await?;
+        }
+        self.refresh_task
+            .start(common_runtime::bg_runtime())
+            .await
+            .context(StartRepeatedTaskSnafu {
+                name: self.refresher.name(),
+            })
+    }
+
+    pub(crate) async fn stop(&self) -> Result<()> {
+        if !self.refresh_task.started() {
+            return Ok(());
+        }
+        self.refresh_task
+            .stop()
+            .await
+            .context(StopRepeatedTaskSnafu {
+                name: self.refresher.name(),
+            })
+    }
+
+    /// Creates the table of the view. The table is filled by the following refreshes.
+    pub(crate) async fn create(
+        &self,
+        stmt: CreateMaterializedView,
+        query_ctx: QueryContextRef,
+    ) -> Result<Output> {
+        let (catalog, schema, name) = table_idents_to_full_name(&stmt.name, query_ctx.clone())
+            .map_err(BoxedError::new)
+            .context(ExternalSnafu)?;
+        let definition = ViewDefinition::try_new(&name, &stmt.query.inner)?;
+        let allowed_lateness = parse_allowed_lateness(&name, &stmt)?;
+
+        if self
+            .refresher
+            .catalog_manager
+            .table(&catalog, &schema, &name)
+            .await
+            .context(CatalogSnafu)?
+            .is_some()
+        {
+            return if stmt.if_not_exists {
+                Ok(Output::AffectedRows(0))
+            } else {
+                TableAlreadyExistSnafu {
+                    table: stmt.name.to_string(),
+                }
+                .fail()
+            };
+        }
+
+        let query = stmt.query.inner.to_string();
+        let plan = self
+            .refresher
+            .query_engine
+            .planner()
+            .plan(
+                QueryStatement::Sql(Statement::Query(stmt.query)),
+                query_ctx.clone(),
+            )
+            .await
+            .context(PlanStatementSnafu)?;
+        let output_schema = self
+            .refresher
+            .query_engine
+            .describe(plan)
+            .await
+            .context(DescribeStatementSnafu)?;
+
+        let column_schemas = output_schema
+            .column_schemas()
+            .iter()
+            .map(|column| {
+                let is_time_index = column.name == definition.time_index();
+                ensure!(
+                    !is_time_index || matches!(column.data_type, ConcreteDataType::Timestamp(_)),
+                    InvalidMaterializedViewSnafu {
+                        name: &name,
+                        reason: format!("{} must be a timestamp", column.name),
+                    }
+                );
+                Ok(ColumnSchema::new(
+                    &column.name,
+                    column.data_type.clone(),
+                    !is_time_index && column.is_nullable(),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut table_options = HashMap::from([(QUERY_TABLE_OPTION.to_string(), query)]);
+        if let Some(allowed_lateness) = allowed_lateness {
+            let _ = table_options.insert(
+                ALLOWED_LATENESS_TABLE_OPTION.to_string(),
+                allowed_lateness.to_string(),
+            );
+        }
+        // The watermark may be left by a dropped view of the same name.
+        self.refresher
+            .watermarks
+            .remove(&TableName::new(&catalog, &schema, &name))
+            .await?;
+
+        let view = TableName::new(&catalog, &schema, &name);
+        let expr = CreateTableExpr {
+            catalog_name: catalog,
+            schema_name: schema,
+            table_name: name,
+            desc: "".to_string(),
+            column_defs: column_schemas_to_defs(column_schemas)?,
+            time_index: definition.time_index().to_string(),
+            primary_keys: definition.primary_keys().to_vec(),
+            create_if_not_exists: stmt.if_not_exists,
+            table_options,
+            table_id: None,
+            region_ids: vec![],
+            engine: MITO_ENGINE.to_string(),
+        };
+        let _ = self
+            .grpc_query_handler
+            .do_query(
+                Request::Ddl(DdlRequest {
+                    expr: Some(DdlExpr::CreateTable(expr)),
+                }),
+                query_ctx,
+            )
+            .await?;
+        self.refresher.registry.register(&view).await?;
+        Ok(Output::AffectedRows(0))
+    }
+
+    /// Drops the view and its table.
+    pub(crate) async fn drop(
+        &self,
+        stmt: DropMaterializedView,
+        query_ctx: QueryContextRef,
+    ) -> Result<Output> {
+        let (catalog, schema, name) =
+            table_idents_to_full_name(stmt.view_name(), query_ctx.clone())
+                .map_err(BoxedError::new)
+                .context(ExternalSnafu)?;
+
+        let Some(view) = self.refresher.view(&catalog, &schema, &name).await? else {
+            ensure!(
+                stmt.if_exists(),
+                MaterializedViewNotFoundSnafu {
+                    view: stmt.view_name().to_string(),
+                }
+            );
+            return Ok(Output::AffectedRows(0));
+        };
+
+        let expr = DropTableExpr {
+            catalog_name: catalog,
+            schema_name: schema,
+            table_name: name,
+        };
+        let _ = self
+            .grpc_query_handler
+            .do_query(
+                Request::Ddl(DdlRequest {
+                    expr: Some(DdlExpr::DropTable(expr)),
+                }),
+                query_ctx,
+            )
+            .await?;
+        self.refresher.registry.deregister(&view.name).await?;
+        self.refresher.watermarks.remove(&view.name).await?;
+        Ok(Output::AffectedRows(0))
+    }
+
+    /// Refreshes all the views once, as if the current time is `now` in milliseconds.
+    #[cfg(test)]
+    pub(crate) async fn refresh_at(&self, now: i64) -> Result<()> {
+        self.refresher.refresh_all(now).await
+    }
+}
+
+/// A materialized view found in the catalog.
+struct MaterializedView {
+    name: TableName,
+    /// The `SELECT` statement that defines the view.
+    query: String,
+    /// How late (in milliseconds) the source rows may arrive and still be aggregated into the
+    /// view. `None` to use the width of the view's time buckets.
+    allowed_lateness: Option<i64>,
+}
+
+impl MaterializedView {
+    /// Returns the view of the table, or `None` if the table is not a materialized view.
+    fn from_table(name: TableName, table: &TableRef) -> Option<Self> {
+        let table_info = table.table_info();
+        let options = &table_info.meta.options.extra_options;
+        let query = options.get(QUERY_TABLE_OPTION)?.clone();
+        let allowed_lateness = options
+            .get(ALLOWED_LATENESS_TABLE_OPTION)
+            .and_then(|x| x.parse().ok());
+        Some(Self {
+            name,
+            query,
+            allowed_lateness,
+        })
+    }
+}
+
+struct ViewRefresher {
+    catalog_manager: CatalogManagerRef,
+    query_engine: QueryEngineRef,
+    watermarks: WatermarkStoreRef,
+    registry: ViewRegistryRef,
+}
+
+#[async_trait::async_trait]
+impl TaskFunction<Error> for ViewRefresher {
+    fn name(&self) -> &str {
+        "materialized-view-refresh-task"
+    }
+
+    async fn call(&self) -> Result<()> {
+        self.refresh_all(chrono::Utc::now().timestamp_millis())
+            .await
+    }
+}
+
+impl ViewRefresher {
+    /// Returns the materialized view of the given name.
+    async fn view(
+        &self,
+        catalog: &str,
+        schema: &str,
+        name: &str,
+    ) -> Result<Option<MaterializedView>> {
+        let table = self
+            .catalog_manager
+            .table(catalog, schema, name)
+            .await
+            .context(CatalogSnafu)?;
+        Ok(table.and_then(|table| {
+            MaterializedView::from_table(TableName::new(catalog, schema, name), &table)
+        }))
+    }
+
+    /// Returns the registered materialized views. The views whose tables are gone, e.g. dropped
+    /// by `DROP TABLE`, are deregistered.
+    async fn views(&self) -> Result<Vec<MaterializedView>> {
+        let mut views = vec![];
+        for name in self.registry.views().await? {
+            match self
+                .view(&name.catalog_name, &name.schema_name, &name.table_name)
+                .await?
+            {
+                Some(view) => views.push(view),
+                None => self.registry.deregister(&name).await?,
+            }
+        }
+        Ok(views)
+    }
+
+    /// Finds the materialized views through all the tables of the catalog.
+    async fn find_views(&self) -> Result<Vec<TableName>> {
+        let mut views = vec![];
+        for catalog_name in self.catalog_manager.catalog_names().context(CatalogSnafu)? {
+            let catalog = self
+                .catalog_manager
+                .catalog(&catalog_name)
+                .context(CatalogSnafu)?;
+            let Some(catalog) = catalog else { continue };
+            for schema_name in catalog.schema_names().context(CatalogSnafu)? {
+                let schema = catalog.schema(&schema_name).context(CatalogSnafu)?;
+                let Some(schema) = schema else { continue };
+                for table_name in schema.table_names().context(CatalogSnafu)? {
+                    let table = schema.table(&table_name).await.context(CatalogSnafu)?;
+                    let Some(table) = table else { continue };
+                    let name = TableName::new(&catalog_name, &schema_name, table_name);
+                    views.extend(MaterializedView::from_table(name, &table).map(|view| view.name));
+                }
+            }
+        }
+        Ok(views)
+    }
+
+    async fn refresh_all(&self, now: i64) -> Result<()> {
+        for view in self.views().await? {
+            let name = view.name.clone();
+            if let Err(e) = self.refresh_view(view, now).await {
+                error!(e; "Failed to refresh materialized view {name}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Aggregates the source rows written since the last refresh into the view.
+    ///
+    /// Only the buckets that have ended by `now` are refreshed, at most [MAX_REFRESH_BUCKETS] of
+    /// them at a time. The buckets within the allowed lateness before the watermark are
+    /// recomputed too, to include the rows that arrive late. Rows arriving later than that are
+    /// not aggregated. Since a bucket is always computed from all of its source rows,
+    /// recomputing overwrites the previous result instead of adding to it.
+    async fn refresh_view(&self, view: MaterializedView, now: i64) -> Result<()> {
+        let stmt = QueryLanguageParser::parse_sql(&view.query).context(ParseQuerySnafu)?;
+        let QueryStatement::Sql(Statement::Query(query)) = stmt else {
+            return InvalidMaterializedViewSnafu {
+                name: &view.name.table_name,
+                reason: "the definition is not a query",
+            }
+            .fail();
+        };
+        let definition = ViewDefinition::try_new(&view.name.table_name, &query.inner)?;
+
+        let bucket = definition.bucket_millis();
+        let watermark = self.watermarks.get(&view.name).await?;
+        let from = match watermark {
+            Some(watermark) => watermark,
+            None => match self.initial_watermark(&view, &definition).await? {
+                Some(watermark) => watermark,
+                // Both the view and its source table are empty.
+                None => return Ok(()),
+            },
+        };
+        let end = (now - now.rem_euclid(bucket))
+            .min(from.saturating_add(bucket.saturating_mul(MAX_REFRESH_BUCKETS)));
+        if from >= end {
+            return Ok(());
+        }
+        // Claims the window, it's being refreshed by another frontend if it fails.
+        if !self
+            .watermarks
+            .compare_and_set(&view.name, watermark, end)
+            .await?
+        {
+            return Ok(());
+        }
+
+        let allowed_lateness = view.allowed_lateness.unwrap_or(bucket);
+        let start = from.saturating_sub(allowed_lateness);
+        let start = start - start.rem_euclid(bucket);
+        let sql = format!(
+            "INSERT INTO {} {}",
+            Ident::with_quote('"', &view.name.table_name),
+            definition.window_query(Some(start), end)?
+        );
+        match self.execute(&view.name, &sql).await {
+            Ok(Output::AffectedRows(rows)) => {
+                info!(
+                    "Refreshed materialized view {} up to {}, {} rows written",
+                    view.name, end, rows
+                );
+                Ok(())
+            }
+            Ok(_) => Ok(()),
+            Err(e) => {
+                // Gives the window back, so that it's refreshed again.
+                let result = match watermark {
+                    Some(watermark) => self
+                        .watermarks
+                        .compare_and_set(&view.name, Some(end), watermark)
+                        .await
+                        .map(|_| ()),
+                    None => self.watermarks.remove(&view.name).await,
+                };
+                if let Err(reset_err) = result {
+                    error!(
+                        reset_err; "Failed to reset the watermark of materialized view {}",
+                        view.name
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Finds where the view without a watermark is refreshed from. It's the last bucket in the
+    /// view's table if the table has rows, e.g. the watermarks kept in memory are lost on
+    /// restart, otherwise it's the bucket of the earliest source row. Returns `None` if both
+    /// are empty.
+    async fn initial_watermark(
+        &self,
+        view: &MaterializedView,
+        definition: &ViewDefinition,
+    ) -> Result<Option<i64>> {
+        let bucket = definition.bucket_millis();
+        let sql = format!(
+            "SELECT max({}) FROM {}",
+            Ident::with_quote('"', definition.time_index()),
+            Ident::with_quote('"', &view.name.table_name)
+        );
+        if let Some(last) = self.query_timestamp(&view.name, &sql).await? {
+            return Ok(Some(last.saturating_add(bucket)));
+        }
+        let first = self
+            .query_timestamp(&view.name, &definition.min_time_query()?)
+            .await?;
+        Ok(first.map(|first| first - first.rem_euclid(bucket)))
+    }
+
+    /// Executes the query of a single timestamp, returns it in milliseconds.
+    async fn query_timestamp(&self, view: &TableName, sql: &str) -> Result<Option<i64>> {
+        let batches = match self.execute(view, sql).await? {
+            Output::Stream(stream) => record_util::collect(stream)
+                .await
+                .context(CollectRecordbatchSnafu)?,
+            Output::RecordBatches(batches) => batches.take(),
+            Output::AffectedRows(_) => {
+                return IllegalFrontendStateSnafu {
+                    err_msg: format!("Query {sql} returns affected rows"),
+                }
+                .fail();
+            }
+        };
+        let value = batches
+            .iter()
+            .flat_map(|batch| batch.rows())
+            .next()
+            .and_then(|row| row.into_iter().next());
+        Ok(match value {
+            Some(Value::Timestamp(ts)) => ts.convert_to(TimeUnit::Millisecond).map(|x| x.value()),
+            _ => None,
+        })
+    }
+
+    /// Executes the SQL in the schema of the view.
+    async fn execute(&self, view: &TableName, sql: &str) -> Result<Output> {
+        let query_ctx = Arc::new(QueryContext::with(&view.catalog_name, &view.schema_name));
+        let stmt = QueryLanguageParser::parse_sql(sql).context(ParseQuerySnafu)?;
+        let plan = self
+            .query_engine
+            .planner()
+            .plan(stmt, query_ctx.clone())
+            .await
+            .context(PlanStatementSnafu)?;
+        self.query_engine
+            .execute(plan, query_ctx)
+            .await
+            .context(ExecLogicalPlanSnafu)
+    }
+}
+
+fn parse_allowed_lateness(name: &str, stmt: &CreateMaterializedView) -> Result<Option<i64>> {
+    let mut allowed_lateness = None;
+    for option in &stmt.options {
+        let key = option.name.value.to_lowercase();
+        ensure!(
+            key == ALLOWED_LATENESS_KEY,
+            InvalidMaterializedViewSnafu {
+                name,
+                reason: format!("unknown option {}", option.name),
+            }
+        );
+        let lateness = parse_option_string(option.value.clone())
+            .and_then(|value| humantime::parse_duration(&value).ok())
+            .with_context(|| InvalidMaterializedViewSnafu {
+                name,
+                reason: format!(
+                    "{} must be a duration like '1h', found {}",
+                    ALLOWED_LATENESS_KEY, option.value
+                ),
+            })?;
+        allowed_lateness = Some(lateness.as_millis() as i64);
+    }
+    Ok(allowed_lateness)
+}
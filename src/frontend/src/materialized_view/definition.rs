@@ -0,0 +1,355 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use datafusion::sql::sqlparser::ast::{Query, SelectItem, SetExpr, TableFactor};
+use snafu::{ensure, OptionExt};
+use sql::ast::{BinaryOperator, Expr, FunctionArg, FunctionArgExpr, Value};
+
+use crate::error::{InvalidMaterializedViewSnafu, Result};
+
+/// How a materialized view aggregates its source table, extracted from the defining query.
+///
+/// The query must group the rows of a single table into time buckets by `date_bin`, e.g.
+/// `SELECT host, date_bin(INTERVAL '5 minutes', ts) AS time_window, avg(usage) AS usage FROM cpu
+/// GROUP BY host, time_window`. A bucket only depends on the source rows inside it, so the view
+/// is refreshed by recomputing whole buckets and overwriting their previous results.
+#[derive(Debug, Clone)]
+pub(crate) struct ViewDefinition {
+    name: String,
+    query: Query,
+    /// Source table column that rows are bucketed by.
+    source_time_column: Expr,
+    /// Width of the time buckets in milliseconds.
+    bucket_millis: i64,
+    /// Output column of the buckets, it's the time index of the view's table.
+    time_index: String,
+    /// The other output columns in `GROUP BY`, they are the primary keys of the view's table.
+    primary_keys: Vec<String>,
+}
+
+impl ViewDefinition {
+    pub(crate) fn try_new(name: &str, query: &Query) -> Result<Self> {
+        let invalid = |reason: &str| InvalidMaterializedViewSnafu {
+            name,
+            reason: reason.to_string(),
+        };
+
+        ensure!(query.with.is_none(), invalid("WITH is not supported"));
+        ensure!(
+            query.limit.is_none() && query.offset.is_none() && query.fetch.is_none(),
+            invalid("LIMIT, OFFSET and FETCH are not supported")
+        );
+        let SetExpr::Select(select) = query.body.as_ref() else {
+            return invalid("the query must be a single SELECT").fail();
+        };
+        ensure!(
+            select.from.len() == 1
+                && select.from[0].joins.is_empty()
+                && matches!(select.from[0].relation, TableFactor::Table { .. }),
+            invalid("the query must select from exactly one table")
+        );
+
+        let outputs = select
+            .projection
+            .iter()
+            .map(|item| match item {
+                SelectItem::UnnamedExpr(expr @ Expr::Identifier(ident)) => {
+                    Ok((ident.value.clone(), expr))
+                }
+                SelectItem::UnnamedExpr(expr @ Expr::CompoundIdentifier(idents)) => {
+                    // Parser never produces empty compound identifiers.
+                    Ok((idents.last().unwrap().value.clone(), expr))
+                }
+                SelectItem::ExprWithAlias { expr, alias } => Ok((alias.value.clone(), expr)),
+                SelectItem::UnnamedExpr(expr) => {
+                    invalid(&format!("expression {expr} must have an alias")).fail()
+                }
+                SelectItem::QualifiedWildcard(..) | SelectItem::Wildcard(..) => {
+                    invalid("wildcard is not supported").fail()
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut bucketing = None;
+        let mut primary_keys = Vec::with_capacity(select.group_by.len());
+        for group_expr in &select.group_by {
+            let (output, expr) = outputs
+                .iter()
+                .find(|(_, expr)| *expr == group_expr)
+                .or_else(|| match group_expr {
+                    Expr::Identifier(ident) => {
+                        outputs.iter().find(|(output, _)| *output == ident.value)
+                    }
+                    _ => None,
+                })
+                .with_context(|| {
+                    invalid(&format!(
+                        "GROUP BY expression {group_expr} must be selected"
+                    ))
+                })?;
+
+            match parse_date_bin(expr) {
+                Some(_) if bucketing.is_some() => {
+                    return invalid("the query must GROUP BY exactly one date_bin").fail();
+                }
+                Some(date_bin) => {
+                    let (bucket_millis, source_time_column) = date_bin.with_context(|| {
+                        invalid(&format!(
+                            "{expr} must be like date_bin(INTERVAL '5 minutes', ts)"
+                        ))
+                    })?;
+                    bucketing = Some((bucket_millis, source_time_column, output.clone()));
+                }
+                None => primary_keys.push(output.clone()),
+            }
+        }
+        let (bucket_millis, source_time_column, time_index) =
+            bucketing.with_context(|| invalid("the query must GROUP BY exactly one date_bin"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            query: query.clone(),
+            source_time_column,
+            bucket_millis,
+            time_index,
+            primary_keys,
+        })
+    }
+
+    pub(crate) fn bucket_millis(&self) -> i64 {
+        self.bucket_millis
+    }
+
+    pub(crate) fn time_index(&self) -> &str {
+        &self.time_index
+    }
+
+    pub(crate) fn primary_keys(&self) -> &[String] {
+        &self.primary_keys
+    }
+
+    /// Returns the defining query restricted to the source rows in `[start, end)`, both are
+    /// timestamps in milliseconds.
+    pub(crate) fn window_query(&self, start: Option<i64>, end: i64) -> Result<Query> {
+        let mut predicate = self.compare_time(BinaryOperator::Lt, end)?;
+        if let Some(start) = start {
+            predicate = and(self.compare_time(BinaryOperator::GtEq, start)?, predicate);
+        }
+
+        let mut query = self.query.clone();
+        let SetExpr::Select(select) = query.body.as_mut() else {
+            return InvalidMaterializedViewSnafu {
+                name: &self.name,
+                reason: "the query must be a single SELECT",
+            }
+            .fail();
+        };
+        select.selection = Some(match select.selection.take() {
+            Some(selection) => and(Expr::Nested(Box::new(selection)), predicate),
+            None => predicate,
+        });
+        Ok(query)
+    }
+
+    /// Returns the query of the earliest source row, which is where the view starts.
+    pub(crate) fn min_time_query(&self) -> Result<String> {
+        let SetExpr::Select(select) = self.query.body.as_ref() else {
+            return InvalidMaterializedViewSnafu {
+                name: &self.name,
+                reason: "the query must be a single SELECT",
+            }
+            .fail();
+        };
+        let mut sql = format!(
+            "SELECT min({}) FROM {}",
+            self.source_time_column, select.from[0]
+        );
+        if let Some(selection) = &select.selection {
+            sql.push_str(&format!(" WHERE {selection}"));
+        }
+        Ok(sql)
+    }
+
+    fn compare_time(&self, op: BinaryOperator, millis: i64) -> Result<Expr> {
+        // Using string literal because the optimizer casts it to the type of the time column.
+        let timestamp = Utc
+            .timestamp_millis_opt(millis)
+            .single()
+            .with_context(|| InvalidMaterializedViewSnafu {
+                name: &self.name,
+                reason: format!("timestamp {millis} is out of range"),
+            })?
+            .to_rfc3339_opts(SecondsFormat::Millis, true);
+        Ok(Expr::BinaryOp {
+            left: Box::new(self.source_time_column.clone()),
+            op,
+            right: Box::new(Expr::Value(Value::SingleQuotedString(timestamp))),
+        })
+    }
+}
+
+fn and(left: Expr, right: Expr) -> Expr {
+    Expr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOperator::And,
+        right: Box::new(right),
+    }
+}
+
+/// Returns `None` if `expr` is not a `date_bin` call, or `Some(None)` if it's a `date_bin` call
+/// that can't be maintained incrementally, e.g. the one with an origin.
+fn parse_date_bin(expr: &Expr) -> Option<Option<(i64, Expr)>> {
+    let Expr::Function(function) = expr else { return None };
+    if !function.name.to_string().eq_ignore_ascii_case("date_bin") {
+        return None;
+    }
+
+    let args = function
+        .args
+        .iter()
+        .map(|arg| match arg {
+            FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => Some(expr),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    let parsed = match args.as_deref() {
+        Some([stride, column @ (Expr::Identifier(_) | Expr::CompoundIdentifier(_))]) => {
+            parse_stride_millis(stride).map(|stride| (stride, (*column).clone()))
+        }
+        _ => None,
+    };
+    Some(parsed)
+}
+
+/// Parses the stride of `date_bin`, either an interval like `INTERVAL '5 minutes'` or a string
+/// like `'5m'`.
+fn parse_stride_millis(expr: &Expr) -> Option<i64> {
+    let stride = match expr {
+        Expr::Value(Value::SingleQuotedString(s)) => s.clone(),
+        Expr::Interval {
+            value,
+            leading_field,
+            ..
+        } => match (value.as_ref(), leading_field) {
+            (Expr::Value(Value::SingleQuotedString(s)), None) => s.clone(),
+            (Expr::Value(Value::SingleQuotedString(s)), Some(unit)) => {
+                format!("{s}{}", unit.to_string().to_lowercase())
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    // humantime doesn't allow spaces between numbers and units, like "5 minutes".
+    let stride = stride.split_whitespace().collect::<String>();
+    humantime::parse_duration(&stride)
+        .ok()
+        .map(|x| x.as_millis() as i64)
+        .filter(|x| *x > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use sql::dialect::GenericDialect;
+    use sql::parser::ParserContext;
+    use sql::statements::statement::Statement;
+
+    use super::*;
+
+    fn parse_query(sql: &str) -> Query {
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        let Statement::Query(query) = stmts.remove(0) else { unreachable!() };
+        query.inner
+    }
+
+    #[test]
+    fn test_view_definition() {
+        let query = parse_query(
+            "SELECT host, date_bin(INTERVAL '5 minutes', ts) AS time_window, avg(usage) AS usage \
+            FROM cpu WHERE dc = 'a' GROUP BY host, time_window",
+        );
+        let definition = ViewDefinition::try_new("cpu_5m", &query).unwrap();
+        assert_eq!(definition.bucket_millis(), 300_000);
+        assert_eq!(definition.time_index(), "time_window");
+        assert_eq!(definition.primary_keys(), ["host"]);
+
+        let window = definition.window_query(Some(0), 300_000).unwrap();
+        assert_eq!(
+            window.to_string(),
+            "SELECT host, date_bin(INTERVAL '5 minutes', ts) AS time_window, avg(usage) AS usage \
+            FROM cpu WHERE (dc = 'a') AND ts >= '1970-01-01T00:00:00.000Z' AND ts < '1970-01-01T00:05:00.000Z' \
+            GROUP BY host, time_window"
+        );
+        assert_eq!(
+            definition.min_time_query().unwrap(),
+            "SELECT min(ts) FROM cpu WHERE dc = 'a'"
+        );
+
+        let window = definition.window_query(None, 300_000).unwrap();
+        assert!(window
+            .to_string()
+            .contains("WHERE (dc = 'a') AND ts < '1970-01-01T00:05:00.000Z'"));
+
+        let query = parse_query(
+            "SELECT date_bin('1h', cpu.ts) AS hour, count(*) AS c FROM cpu GROUP BY date_bin('1h', cpu.ts)",
+        );
+        let definition = ViewDefinition::try_new("cpu_1h", &query).unwrap();
+        assert_eq!(definition.bucket_millis(), 3_600_000);
+        assert_eq!(definition.time_index(), "hour");
+        assert!(definition.primary_keys().is_empty());
+        assert!(definition
+            .window_query(None, 0)
+            .unwrap()
+            .to_string()
+            .contains("WHERE cpu.ts < '1970-01-01T00:00:00.000Z'"));
+
+        let err = definition.window_query(Some(i64::MIN), 0).unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{err}");
+    }
+
+    #[test]
+    fn test_invalid_view_definition() {
+        let cases = [
+            (
+                "SELECT host, avg(usage) AS u FROM cpu GROUP BY host",
+                "exactly one date_bin",
+            ),
+            (
+                "SELECT date_bin('1m', ts) AS t, avg(usage) FROM cpu GROUP BY t",
+                "must have an alias",
+            ),
+            (
+                "SELECT date_bin('1m', ts) AS t FROM cpu GROUP BY t, host",
+                "must be selected",
+            ),
+            (
+                "SELECT date_bin('1m', ts, '1970-01-01T00:00:30Z') AS t FROM cpu GROUP BY t",
+                "must be like date_bin",
+            ),
+            (
+                "SELECT date_bin('1m', ts) AS t FROM cpu GROUP BY t LIMIT 1",
+                "LIMIT",
+            ),
+            (
+                "SELECT date_bin('1m', a.ts) AS t FROM a JOIN b ON a.id = b.id GROUP BY t",
+                "exactly one table",
+            ),
+            ("SELECT * FROM cpu", "wildcard"),
+        ];
+        for (sql, reason) in cases {
+            let err = ViewDefinition::try_new("v", &parse_query(sql)).unwrap_err();
+            assert!(err.to_string().contains(reason), "{sql}: {err}");
+        }
+    }
+}
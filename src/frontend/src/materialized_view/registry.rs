@@ -0,0 +1,148 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registries of materialized views, the views refreshed in background.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use catalog::remote::KvBackendRef;
+use futures::TryStreamExt;
+use meta_client::rpc::TableName;
+use snafu::ResultExt;
+
+use crate::error::{CatalogSnafu, Result};
+
+const REGISTRY_KEY_PREFIX: &str = "__mvr";
+
+pub(crate) type ViewRegistryRef = Arc<dyn ViewRegistry>;
+
+/// Keeps the names of the materialized views, so that they are refreshed without looking for
+/// them through all the tables of the catalog.
+///
+/// A view is registered when it's created and deregistered when it's dropped. The views found
+/// in the catalog are registered again on startup.
+#[async_trait::async_trait]
+pub(crate) trait ViewRegistry: Send + Sync {
+    async fn register(&self, view: &TableName) -> Result<()>;
+
+    async fn deregister(&self, view: &TableName) -> Result<()>;
+
+    async fn views(&self) -> Result<Vec<TableName>>;
+}
+
+/// Views kept in memory, for the standalone mode that has only one frontend.
+#[derive(Default)]
+pub(crate) struct MemoryViewRegistry {
+    views: Mutex<HashSet<TableName>>,
+}
+
+#[async_trait::async_trait]
+impl ViewRegistry for MemoryViewRegistry {
+    async fn register(&self, view: &TableName) -> Result<()> {
+        let _ = self.views.lock().unwrap().insert(view.clone());
+        Ok(())
+    }
+
+    async fn deregister(&self, view: &TableName) -> Result<()> {
+        let _ = self.views.lock().unwrap().remove(view);
+        Ok(())
+    }
+
+    async fn views(&self) -> Result<Vec<TableName>> {
+        Ok(self.views.lock().unwrap().iter().cloned().collect())
+    }
+}
+
+/// Views kept in the meta KV store, shared by all frontends in the distributed mode, so that
+/// the views created by a frontend are also refreshed by the others.
+pub(crate) struct KvViewRegistry {
+    backend: KvBackendRef,
+}
+
+impl KvViewRegistry {
+    pub(crate) fn new(backend: KvBackendRef) -> Self {
+        Self { backend }
+    }
+}
+
+#[async_trait::async_trait]
+impl ViewRegistry for KvViewRegistry {
+    async fn register(&self, view: &TableName) -> Result<()> {
+        self.backend
+            .set(registry_key(view).as_bytes(), &encode_view(view))
+            .await
+            .context(CatalogSnafu)
+    }
+
+    async fn deregister(&self, view: &TableName) -> Result<()> {
+        self.backend
+            .delete(registry_key(view).as_bytes())
+            .await
+            .context(CatalogSnafu)
+    }
+
+    async fn views(&self) -> Result<Vec<TableName>> {
+        let kvs = self
+            .backend
+            .range(format!("{REGISTRY_KEY_PREFIX}-").as_bytes())
+            .try_collect::<Vec<_>>()
+            .await
+            .context(CatalogSnafu)?;
+        Ok(kvs.iter().filter_map(|kv| decode_view(&kv.1)).collect())
+    }
+}
+
+fn registry_key(view: &TableName) -> String {
+    format!(
+        "{REGISTRY_KEY_PREFIX}-{}-{}-{}",
+        view.catalog_name, view.schema_name, view.table_name
+    )
+}
+
+/// The names are kept in the value, as they may contain the separator of the key.
+fn encode_view(view: &TableName) -> Vec<u8> {
+    serde_json::to_vec(view).unwrap_or_default()
+}
+
+fn decode_view(value: &[u8]) -> Option<TableName> {
+    serde_json::from_slice(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_view_registry() {
+        let registry = MemoryViewRegistry::default();
+        let view = TableName::new("greptime", "public", "cpu_1m");
+        assert!(registry.views().await.unwrap().is_empty());
+
+        registry.register(&view).await.unwrap();
+        registry.register(&view).await.unwrap();
+        assert_eq!(vec![view.clone()], registry.views().await.unwrap());
+
+        registry.deregister(&view).await.unwrap();
+        assert!(registry.views().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_view_encoding() {
+        let view = TableName::new("greptime", "public", "cpu-1m");
+        assert_eq!("__mvr-greptime-public-cpu-1m", registry_key(&view));
+        assert_eq!(Some(view.clone()), decode_view(&encode_view(&view)));
+        assert_eq!(None, decode_view(b"x"));
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Watermarks of materialized views, how far each view has been refreshed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use catalog::remote::KvBackendRef;
+use meta_client::rpc::TableName;
+use snafu::ResultExt;
+
+use crate::error::{CatalogSnafu, Result};
+
+const WATERMARK_KEY_PREFIX: &str = "__mvw";
+
+pub(crate) type WatermarkStoreRef = Arc<dyn WatermarkStore>;
+
+/// Keeps the watermarks of materialized views. Rows of the source table before the watermark
+/// (in milliseconds) have been aggregated into the view.
+#[async_trait::async_trait]
+pub(crate) trait WatermarkStore: Send + Sync {
+    async fn get(&self, view: &TableName) -> Result<Option<i64>>;
+
+    /// Sets the watermark of the view to `new` if it's still `current`, returns whether it's
+    /// set. The refresh of a window is claimed by advancing the watermark over it, so that only
+    /// one frontend refreshes it.
+    async fn compare_and_set(
+        &self,
+        view: &TableName,
+        current: Option<i64>,
+        new: i64,
+    ) -> Result<bool>;
+
+    async fn remove(&self, view: &TableName) -> Result<()>;
+}
+
+/// Watermarks kept in memory, for the standalone mode that has only one frontend.
+///
+/// They are lost on restart, and recovered from the tables of the views then.
+#[derive(Default)]
+pub(crate) struct MemoryWatermarkStore {
+    watermarks: Mutex<HashMap<TableName, i64>>,
+}
+
+#[async_trait::async_trait]
+impl WatermarkStore for MemoryWatermarkStore {
+    async fn get(&self, view: &TableName) -> Result<Option<i64>> {
+        Ok(self.watermarks.lock().unwrap().get(view).copied())
+    }
+
+    async fn compare_and_set(
+        &self,
+        view: &TableName,
+        current: Option<i64>,
+        new: i64,
+    ) -> Result<bool> {
+        let mut watermarks = self.watermarks.lock().unwrap();
+        if watermarks.get(view).copied() != current {
+            return Ok(false);
+        }
+        let _ = watermarks.insert(view.clone(), new);
+        Ok(true)
+    }
+
+    async fn remove(&self, view: &TableName) -> Result<()> {
+        let _ = self.watermarks.lock().unwrap().remove(view);
+        Ok(())
+    }
+}
+
+/// Watermarks kept in the meta KV store, shared by all frontends in the distributed mode.
+pub(crate) struct KvWatermarkStore {
+    backend: KvBackendRef,
+}
+
+impl KvWatermarkStore {
+    pub(crate) fn new(backend: KvBackendRef) -> Self {
+        Self { backend }
+    }
+}
+
+#[async_trait::async_trait]
+impl WatermarkStore for KvWatermarkStore {
+    async fn get(&self, view: &TableName) -> Result<Option<i64>> {
+        let value = self
+            .backend
+            .get(watermark_key(view).as_bytes())
+            .await
+            .context(CatalogSnafu)?;
+        Ok(value.and_then(|kv| decode_watermark(&kv.1)))
+    }
+
+    async fn compare_and_set(
+        &self,
+        view: &TableName,
+        current: Option<i64>,
+        new: i64,
+    ) -> Result<bool> {
+        // An empty expected value means the key doesn't exist.
+        let expect = current.map(encode_watermark).unwrap_or_default();
+        let result = self
+            .backend
+            .compare_and_set(
+                watermark_key(view).as_bytes(),
+                &expect,
+                &encode_watermark(new),
+            )
+            .await
+            .context(CatalogSnafu)?;
+        Ok(result.is_ok())
+    }
+
+    async fn remove(&self, view: &TableName) -> Result<()> {
+        self.backend
+            .delete(watermark_key(view).as_bytes())
+            .await
+            .context(CatalogSnafu)
+    }
+}
+
+fn watermark_key(view: &TableName) -> String {
+    format!(
+        "{WATERMARK_KEY_PREFIX}-{}-{}-{}",
+        view.catalog_name, view.schema_name, view.table_name
+    )
+}
+
+fn encode_watermark(watermark: i64) -> Vec<u8> {
+    watermark.to_string().into_bytes()
+}
+
+fn decode_watermark(value: &[u8]) -> Option<i64> {
+    std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_watermark_store() {
+        let store = MemoryWatermarkStore::default();
+        let view = TableName::new("greptime", "public", "cpu_1m");
+        assert_eq!(None, store.get(&view).await.unwrap());
+
+        assert!(store.compare_and_set(&view, None, 60).await.unwrap());
+        assert!(!store.compare_and_set(&view, None, 120).await.unwrap());
+        assert!(store.compare_and_set(&view, Some(60), 120).await.unwrap());
+        assert_eq!(Some(120), store.get(&view).await.unwrap());
+
+        store.remove(&view).await.unwrap();
+        assert_eq!(None, store.get(&view).await.unwrap());
+    }
+
+    #[test]
+    fn test_watermark_encoding() {
+        let view = TableName::new("greptime", "public", "cpu_1m");
+        assert_eq!("__mvw-greptime-public-cpu_1m", watermark_key(&view));
+        assert_eq!(Some(-60), decode_watermark(&encode_watermark(-60)));
+        assert_eq!(None, decode_watermark(b"x"));
+    }
+}
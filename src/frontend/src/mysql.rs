@@ -22,6 +22,10 @@ pub struct MysqlOptions {
     #[serde(default = "Default::default")]
     pub tls: TlsOption,
     pub reject_no_database: Option<bool>,
+    /// Whether to serve `LOAD DATA LOCAL INFILE`, which relays the packets of every connection
+    /// in front of the MySQL protocol handler. Connections upgraded to TLS are not supported.
+    #[serde(default)]
+    pub local_infile: bool,
 }
 
 impl Default for MysqlOptions {
@@ -31,6 +35,7 @@ impl Default for MysqlOptions {
             runtime_size: 2,
             tls: TlsOption::default(),
             reject_no_database: None,
+            local_infile: false,
         }
     }
 }
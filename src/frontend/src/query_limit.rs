@@ -0,0 +1,62 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use common_base::readable_size::ReadableSize;
+use serde::{Deserialize, Serialize};
+use session::context::QueryLimits;
+
+/// The default limits of statements, which can be overridden in a session by
+/// `SET statement_timeout` and `SET query_memory_limit`. Unlimited if not set.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryLimitOptions {
+    #[serde(with = "humantime_serde")]
+    pub statement_timeout: Option<Duration>,
+    /// Only limits the operators executed in the frontend in distributed mode, the datanodes
+    /// don't limit the memory of their parts of the statement.
+    pub memory_limit: Option<ReadableSize>,
+}
+
+impl From<&QueryLimitOptions> for QueryLimits {
+    fn from(opts: &QueryLimitOptions) -> Self {
+        QueryLimits {
+            statement_timeout: opts.statement_timeout,
+            memory_limit: opts.memory_limit.map(|x| x.as_bytes() as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_limit_options() {
+        let opts: QueryLimitOptions = toml::from_str(
+            r#"
+            statement_timeout = "30s"
+            memory_limit = "1GB"
+            "#,
+        )
+        .unwrap();
+        let limits = QueryLimits::from(&opts);
+        assert_eq!(limits.statement_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(limits.memory_limit, Some(1024 * 1024 * 1024));
+
+        let opts: QueryLimitOptions = toml::from_str("").unwrap();
+        assert_eq!(QueryLimits::from(&opts), QueryLimits::default());
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evaluates Prometheus recording and alerting rules periodically in background.
+//!
+//! The results of recording rules are written to the metrics named by the rules, and the pending
+//! and firing alerts are written to the `ALERTS` metric, both through the Prometheus remote write
+//! path. Alerts are notified through the [AlertSink] in the plugins, if there is one.
+//!
+//! The time when each alert became active is also written to the `ALERTS_FOR_STATE` metric, and
+//! restored from it on startup, so a restart doesn't reset the `for` duration of the alerts.
+
+mod alert;
+mod definition;
+mod group;
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use common_runtime::{RepeatedTask, TaskFunction};
+use common_telemetry::logging::info;
+use serde::{Deserialize, Serialize};
+use servers::prom::{PromAlert, PromRuleGroup};
+use snafu::ResultExt;
+
+use crate::error::{Error, Result, StartRepeatedTaskSnafu, StopRepeatedTaskSnafu};
+use crate::instance::Instance;
+pub use crate::rule::alert::{
+    Alert, AlertState, ALERTS_FOR_STATE_METRIC_NAME, ALERTS_METRIC_NAME, ALERT_NAME_LABEL,
+    ALERT_STATE_LABEL,
+};
+use crate::rule::definition::RuleFile;
+use crate::rule::group::RuleGroup;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RuleOptions {
+    /// Paths of the rule files in the Prometheus format.
+    pub rule_files: Vec<String>,
+    /// How often the rule groups without their own interval are evaluated.
+    #[serde(with = "humantime_serde")]
+    pub evaluation_interval: Duration,
+}
+
+impl Default for RuleOptions {
+    fn default() -> Self {
+        Self {
+            rule_files: vec![],
+            evaluation_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Receives the alerts to notify, e.g. to send them to an Alertmanager.
+#[async_trait::async_trait]
+pub trait AlertSink: Send + Sync {
+    /// Sends the firing alerts and the alerts resolved in an evaluation of an alerting rule.
+    /// Firing alerts are sent again in every evaluation until they are resolved.
+    async fn send(&self, alerts: &[Alert]) -> Result<()>;
+}
+
+pub type AlertSinkRef = Arc<dyn AlertSink>;
+
+pub(crate) type RuleManagerRef = Arc<RuleManager>;
+
+#[derive(Default)]
+pub(crate) struct RuleManager {
+    groups: RwLock<Vec<Arc<RuleGroup>>>,
+    /// Names and tasks evaluating the groups.
+    tasks: tokio::sync::Mutex<Vec<(String, RepeatedTask<Error>)>>,
+}
+
+impl RuleManager {
+    /// Loads the rule groups in the rule files, replacing the groups loaded before.
+    pub(crate) fn load(&self, opts: &RuleOptions) -> Result<()> {
+        let mut groups = Vec::new();
+        for path in &opts.rule_files {
+            let file = RuleFile::load(path)?;
+            groups.extend(
+                file.groups
+                    .into_iter()
+                    .map(|group| Arc::new(RuleGroup::new(path, group, opts.evaluation_interval))),
+            );
+        }
+        info!(
+            "Loaded {} rule groups from {} rule files",
+            groups.len(),
+            opts.rule_files.len()
+        );
+        *self.groups.write().unwrap() = groups;
+        Ok(())
+    }
+
+    /// Starts evaluating each group periodically in background.
+    ///
+    /// Every frontend evaluates the groups loaded by itself, so the rule files should be given to
+    /// only one frontend to avoid writing the same results repeatedly.
+    pub(crate) async fn start(&self, instance: Instance) -> Result<()> {
+        let sink = instance.plugins().get::<AlertSinkRef>().cloned();
+        let groups = self.groups.read().unwrap().clone();
+        let mut tasks = self.tasks.lock().await;
+        for group in groups {
+            let evaluator = Arc::new(GroupEvaluator {
+                name: format!("rule-group-{}-evaluate-task", group.name),
+                group: group.clone(),
+                instance: instance.clone(),
+                sink: sink.clone(),
+            });
+            let name = evaluator.name.clone();
+            let task = RepeatedTask::new(group.interval, evaluator);
+            task.start(common_runtime::bg_runtime())
+                .await
+                .context(StartRepeatedTaskSnafu { name: &name })?;
+            tasks.push((name, task));
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn stop(&self) -> Result<()> {
+        let mut tasks = self.tasks.lock().await;
+        for (name, task) in tasks.drain(..) {
+            task.stop().await.context(StopRepeatedTaskSnafu { name })?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn rule_groups(&self) -> Vec<PromRuleGroup> {
+        self.groups
+            .read()
+            .unwrap()
+            .iter()
+            .map(|group| group.to_prom_rule_group())
+            .collect()
+    }
+
+    pub(crate) fn alerts(&self) -> Vec<PromAlert> {
+        self.groups
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|group| group.alerts())
+            .map(|alert| alert.to_prom_alert())
+            .collect()
+    }
+
+    /// Evaluates all the groups once at `now`, in unix milliseconds.
+    #[cfg(test)]
+    pub(crate) async fn evaluate(
+        &self,
+        instance: &Instance,
+        sink: Option<&AlertSinkRef>,
+        now: i64,
+    ) {
+        let groups = self.groups.read().unwrap().clone();
+        for group in groups {
+            group.evaluate(instance, sink, now).await;
+        }
+    }
+}
+
+struct GroupEvaluator {
+    name: String,
+    group: Arc<RuleGroup>,
+    instance: Instance,
+    sink: Option<AlertSinkRef>,
+}
+
+#[async_trait::async_trait]
+impl TaskFunction<Error> for GroupEvaluator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn call(&self) -> Result<()> {
+        let now = Utc::now().timestamp_millis();
+        self.group
+            .evaluate(&self.instance, self.sink.as_ref(), now)
+            .await;
+        Ok(())
+    }
+}
+
+/// Formats unix milliseconds in RFC3339 format.
+pub(crate) fn format_millis(millis: i64) -> String {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rule_options() {
+        let toml_string = r#"
+            rule_files = ["/etc/greptimedb/rules.yml"]
+            evaluation_interval = "30s"
+        "#;
+        let opts: RuleOptions = toml::from_str(toml_string).unwrap();
+        assert_eq!(
+            RuleOptions {
+                rule_files: vec!["/etc/greptimedb/rules.yml".to_string()],
+                evaluation_interval: Duration::from_secs(30),
+            },
+            opts
+        );
+
+        let opts: RuleOptions = toml::from_str("").unwrap();
+        assert_eq!(RuleOptions::default(), opts);
+    }
+
+    #[test]
+    fn test_format_millis() {
+        assert_eq!("1970-01-01T00:01:00.500Z", format_millis(60_500));
+    }
+}
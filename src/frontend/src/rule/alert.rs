@@ -0,0 +1,476 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use api::prometheus::remote::{Label, Sample, TimeSeries};
+use servers::prom::{format_value, PromAlert, PromLabels};
+use servers::prometheus::METRIC_NAME_LABEL;
+
+use crate::rule::definition::RuleDefinition;
+use crate::rule::format_millis;
+
+/// Name of the metric holding the pending and firing alerts.
+pub const ALERTS_METRIC_NAME: &str = "ALERTS";
+/// Name of the metric holding the time when each pending or firing alert became active, in unix
+/// seconds, to restore the alerts after a restart.
+pub const ALERTS_FOR_STATE_METRIC_NAME: &str = "ALERTS_FOR_STATE";
+pub const ALERT_NAME_LABEL: &str = "alertname";
+pub const ALERT_STATE_LABEL: &str = "alertstate";
+
+/// How far back the `ALERTS_FOR_STATE` series are looked for when restoring the alerts, so the
+/// alerts are restored if the frontend was down for less than this.
+const RESTORE_LOOKBACK: &str = "1h";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AlertState {
+    /// The alert is no longer active, i.e. resolved.
+    Inactive,
+    /// The alert is active, but not for long enough to fire.
+    Pending,
+    Firing,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Inactive => "inactive",
+            AlertState::Pending => "pending",
+            AlertState::Firing => "firing",
+        }
+    }
+}
+
+/// An alert created for a series returned by the expression of an alerting rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Labels of the series, the labels of the rule and the name of the rule.
+    pub labels: PromLabels,
+    pub annotations: PromLabels,
+    pub state: AlertState,
+    /// Value of the series in the latest evaluation.
+    pub value: f64,
+    /// Time when the alert became pending, in unix milliseconds.
+    pub active_at: i64,
+    pub fired_at: Option<i64>,
+    pub resolved_at: Option<i64>,
+}
+
+impl Alert {
+    pub(crate) fn to_prom_alert(&self) -> PromAlert {
+        PromAlert {
+            labels: self.labels.clone(),
+            annotations: self.annotations.clone(),
+            state: self.state.as_str().to_string(),
+            active_at: format_millis(self.active_at),
+            value: format_value(self.value),
+        }
+    }
+}
+
+/// A rule alerting on the series returned by its expression, following the semantics of
+/// Prometheus:
+/// - An alert is pending once its series is returned.
+/// - A pending alert fires once its series has been returned for the `for` duration.
+/// - A pending alert is dropped once its series is no longer returned.
+/// - A firing alert is resolved once its series is no longer returned.
+///
+/// The time when each alert became active is restored from `ALERTS_FOR_STATE` before the first
+/// evaluation, so restarting doesn't reset the `for` duration of the alerts.
+pub(crate) struct AlertingRule {
+    pub(crate) name: String,
+    pub(crate) expr: String,
+    pub(crate) hold: Duration,
+    pub(crate) labels: PromLabels,
+    pub(crate) annotations: PromLabels,
+    /// Pending and firing alerts, keyed by their labels.
+    active: RwLock<BTreeMap<PromLabels, Alert>>,
+    restored: AtomicBool,
+}
+
+impl AlertingRule {
+    pub(crate) fn new(definition: &RuleDefinition) -> Self {
+        Self {
+            name: definition.name().to_string(),
+            expr: definition.expr.clone(),
+            hold: definition.hold.unwrap_or_default(),
+            labels: definition.labels.clone(),
+            annotations: definition.annotations.clone(),
+            active: RwLock::new(BTreeMap::new()),
+            restored: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the query of the latest `ALERTS_FOR_STATE` series of the rule, if its alerts
+    /// haven't been restored yet. Alerts firing immediately don't need to be restored.
+    pub(crate) fn restore_query(&self) -> Option<String> {
+        if self.hold.is_zero() || self.restored.load(Ordering::Relaxed) {
+            return None;
+        }
+        let name = self.name.replace('\\', "\\\\").replace('"', "\\\"");
+        Some(format!(
+            "last_over_time({ALERTS_FOR_STATE_METRIC_NAME}{{{ALERT_NAME_LABEL}=\"{name}\"}}[{RESTORE_LOOKBACK}])"
+        ))
+    }
+
+    /// Restores the alerts from the series returned by the [restore query](Self::restore_query),
+    /// as pending alerts active since the value of their series. The next [update](Self::update)
+    /// fires them if they have been active for long enough, or drops them if their series are no
+    /// longer returned by the expression.
+    pub(crate) fn restore(&self, states: Vec<(PromLabels, f64)>) {
+        let mut active = self.active.write().unwrap();
+        for (mut labels, active_at) in states {
+            let _ = labels.remove(METRIC_NAME_LABEL);
+            let _ = active.entry(labels.clone()).or_insert_with(|| Alert {
+                labels,
+                annotations: BTreeMap::new(),
+                state: AlertState::Pending,
+                value: 0.0,
+                active_at: (active_at * 1000.0).round() as i64,
+                fired_at: None,
+                resolved_at: None,
+            });
+        }
+        self.restored.store(true, Ordering::Relaxed);
+    }
+
+    /// Updates the alerts with the series returned by the expression at `now`, in unix
+    /// milliseconds. Returns the alerts to notify, which are the firing alerts and the alerts
+    /// resolved in this evaluation.
+    pub(crate) fn update(&self, samples: Vec<(PromLabels, f64)>, now: i64) -> Vec<Alert> {
+        let mut active = self.active.write().unwrap();
+        let mut returned = HashSet::with_capacity(samples.len());
+        for (mut series_labels, value) in samples {
+            let _ = series_labels.remove(METRIC_NAME_LABEL);
+            let expand = |template: &String| expand_template(template, &series_labels, value);
+
+            let mut labels = series_labels.clone();
+            labels.extend(self.labels.iter().map(|(k, v)| (k.clone(), expand(v))));
+            let _ = labels.insert(ALERT_NAME_LABEL.to_string(), self.name.clone());
+            let annotations = self
+                .annotations
+                .iter()
+                .map(|(k, v)| (k.clone(), expand(v)))
+                .collect();
+
+            let alert = active.entry(labels.clone()).or_insert_with(|| Alert {
+                labels: labels.clone(),
+                annotations: BTreeMap::new(),
+                state: AlertState::Pending,
+                value,
+                active_at: now,
+                fired_at: None,
+                resolved_at: None,
+            });
+            alert.value = value;
+            alert.annotations = annotations;
+            let _ = returned.insert(labels);
+        }
+
+        let hold = self.hold.as_millis() as i64;
+        let mut notified = Vec::new();
+        active.retain(|labels, alert| {
+            if !returned.contains(labels) {
+                if alert.state == AlertState::Firing {
+                    alert.state = AlertState::Inactive;
+                    alert.resolved_at = Some(now);
+                    notified.push(alert.clone());
+                }
+                return false;
+            }
+            if alert.state == AlertState::Pending && now - alert.active_at >= hold {
+                alert.state = AlertState::Firing;
+                alert.fired_at = Some(now);
+            }
+            if alert.state == AlertState::Firing {
+                notified.push(alert.clone());
+            }
+            true
+        });
+        notified
+    }
+
+    /// Returns the pending and firing alerts.
+    pub(crate) fn alerts(&self) -> Vec<Alert> {
+        self.active.read().unwrap().values().cloned().collect()
+    }
+
+    /// Returns the most severe state of the alerts.
+    pub(crate) fn state(&self) -> AlertState {
+        self.active
+            .read()
+            .unwrap()
+            .values()
+            .map(|alert| alert.state)
+            .max()
+            .unwrap_or(AlertState::Inactive)
+    }
+
+    /// Returns the series of the `ALERTS` and `ALERTS_FOR_STATE` metrics at `now`, one of each
+    /// for every pending or firing alert.
+    pub(crate) fn alerts_series(&self, now: i64) -> Vec<TimeSeries> {
+        self.active
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|alert| {
+                [
+                    alert_series(
+                        alert,
+                        &[
+                            (METRIC_NAME_LABEL, ALERTS_METRIC_NAME),
+                            (ALERT_STATE_LABEL, alert.state.as_str()),
+                        ],
+                        1.0,
+                        now,
+                    ),
+                    alert_series(
+                        alert,
+                        &[(METRIC_NAME_LABEL, ALERTS_FOR_STATE_METRIC_NAME)],
+                        alert.active_at as f64 / 1000.0,
+                        now,
+                    ),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Returns a series with the labels of `alert` and `extra_labels`.
+fn alert_series(alert: &Alert, extra_labels: &[(&str, &str)], value: f64, now: i64) -> TimeSeries {
+    let labels = alert
+        .labels
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .chain(extra_labels.iter().copied())
+        .map(|(name, value)| Label {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+        .collect();
+    TimeSeries {
+        labels,
+        samples: vec![Sample {
+            value,
+            timestamp: now,
+        }],
+        ..Default::default()
+    }
+}
+
+/// Expands the references to the labels of the series, like `{{ $labels.job }}`, and to its
+/// value, `{{ $value }}`, in a label or annotation template. Other actions are kept as is.
+fn expand_template(template: &str, labels: &PromLabels, value: f64) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let action = &rest[start..start + len + 2];
+        let variable = action[2..action.len() - 2].trim();
+        expanded.push_str(&rest[..start]);
+        if variable == "$value" {
+            expanded.push_str(&format_value(value));
+        } else if let Some(name) = variable.strip_prefix("$labels.") {
+            // A missing label expands to an empty string.
+            expanded.push_str(labels.get(name).map(String::as_str).unwrap_or_default());
+        } else {
+            expanded.push_str(action);
+        }
+        rest = &rest[start + len + 2..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> PromLabels {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn new_rule(hold: Duration) -> AlertingRule {
+        AlertingRule::new(&RuleDefinition {
+            record: None,
+            alert: Some("HighLoad".to_string()),
+            expr: "load > 1".to_string(),
+            hold: Some(hold),
+            labels: BTreeMap::from([("severity".to_string(), "page".to_string())]),
+            annotations: BTreeMap::from([(
+                "summary".to_string(),
+                "{{ $labels.host }} load is {{$value}}".to_string(),
+            )]),
+        })
+    }
+
+    fn sorted_labels(series: &TimeSeries) -> Vec<(&str, &str)> {
+        let mut labels = series
+            .labels
+            .iter()
+            .map(|label| (label.name.as_str(), label.value.as_str()))
+            .collect::<Vec<_>>();
+        labels.sort();
+        labels
+    }
+
+    #[test]
+    fn test_expand_template() {
+        let series = labels(&[("host", "a")]);
+        let cases = [
+            ("plain", "plain"),
+            ("{{ $labels.host }}", "a"),
+            ("{{$labels.host}}: {{ $value }}", "a: 2.5"),
+            ("[{{ $labels.missing }}]", "[]"),
+            ("{{ humanize $value }}", "{{ humanize $value }}"),
+            ("unclosed {{ $value", "unclosed {{ $value"),
+        ];
+        for (template, expected) in cases {
+            assert_eq!(
+                expected,
+                expand_template(template, &series, 2.5),
+                "{template}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_alert_lifecycle() {
+        let rule = new_rule(Duration::from_secs(60));
+        let series = vec![(labels(&[("__name__", "load"), ("host", "a")]), 2.0)];
+        let alert_labels = labels(&[
+            ("alertname", "HighLoad"),
+            ("host", "a"),
+            ("severity", "page"),
+        ]);
+
+        // Pending until the series has been returned for a minute.
+        assert!(rule.update(series.clone(), 0).is_empty());
+        assert_eq!(AlertState::Pending, rule.state());
+        let alerts = rule.alerts();
+        assert_eq!(1, alerts.len());
+        assert_eq!(alert_labels, alerts[0].labels);
+        assert_eq!(labels(&[("summary", "a load is 2")]), alerts[0].annotations);
+        assert!(rule.update(series.clone(), 30_000).is_empty());
+        assert_eq!(AlertState::Pending, rule.state());
+
+        let notified = rule.update(series.clone(), 60_000);
+        assert_eq!(1, notified.len());
+        assert_eq!(AlertState::Firing, notified[0].state);
+        assert_eq!(0, notified[0].active_at);
+        assert_eq!(Some(60_000), notified[0].fired_at);
+
+        let series_of_alerts = rule.alerts_series(60_000);
+        assert_eq!(2, series_of_alerts.len());
+        assert_eq!(
+            vec![
+                ("__name__", "ALERTS"),
+                ("alertname", "HighLoad"),
+                ("alertstate", "firing"),
+                ("host", "a"),
+                ("severity", "page"),
+            ],
+            sorted_labels(&series_of_alerts[0])
+        );
+        assert_eq!(1.0, series_of_alerts[0].samples[0].value);
+        assert_eq!(
+            vec![
+                ("__name__", "ALERTS_FOR_STATE"),
+                ("alertname", "HighLoad"),
+                ("host", "a"),
+                ("severity", "page"),
+            ],
+            sorted_labels(&series_of_alerts[1])
+        );
+        assert_eq!(0.0, series_of_alerts[1].samples[0].value);
+
+        // Resolved once the series is no longer returned, and notified only once.
+        let notified = rule.update(vec![], 90_000);
+        assert_eq!(1, notified.len());
+        assert_eq!(AlertState::Inactive, notified[0].state);
+        assert_eq!(Some(90_000), notified[0].resolved_at);
+        assert!(rule.alerts().is_empty());
+        assert!(rule.update(vec![], 120_000).is_empty());
+        assert_eq!(AlertState::Inactive, rule.state());
+    }
+
+    #[test]
+    fn test_pending_alert_dropped() {
+        let rule = new_rule(Duration::from_secs(60));
+        let series = vec![(labels(&[("host", "a")]), 2.0)];
+        assert!(rule.update(series.clone(), 0).is_empty());
+        assert!(rule.update(vec![], 30_000).is_empty());
+        assert!(rule.alerts().is_empty());
+
+        // Becomes pending again from the start.
+        assert!(rule.update(series.clone(), 60_000).is_empty());
+        assert_eq!(60_000, rule.alerts()[0].active_at);
+    }
+
+    #[test]
+    fn test_fire_immediately() {
+        let rule = new_rule(Duration::ZERO);
+        let notified = rule.update(vec![(labels(&[("host", "a")]), 2.0)], 0);
+        assert_eq!(1, notified.len());
+        assert_eq!(AlertState::Firing, notified[0].state);
+    }
+
+    #[test]
+    fn test_restore_alerts() {
+        let rule = new_rule(Duration::from_secs(60));
+        assert_eq!(
+            Some(r#"last_over_time(ALERTS_FOR_STATE{alertname="HighLoad"}[1h])"#.to_string()),
+            rule.restore_query()
+        );
+        let restored = labels(&[
+            ("alertname", "HighLoad"),
+            ("host", "a"),
+            ("severity", "page"),
+        ]);
+        let dropped = labels(&[
+            ("alertname", "HighLoad"),
+            ("host", "b"),
+            ("severity", "page"),
+        ]);
+        rule.restore(vec![(restored.clone(), 30.0), (dropped, 30.0)]);
+        assert_eq!(None, rule.restore_query());
+
+        // Keeps the time when the alert became active, instead of being pending from now.
+        let series = vec![(labels(&[("host", "a")]), 2.0)];
+        assert!(rule.update(series.clone(), 60_000).is_empty());
+        let alerts = rule.alerts();
+        assert_eq!(1, alerts.len());
+        assert_eq!(restored, alerts[0].labels);
+        assert_eq!(30_000, alerts[0].active_at);
+
+        let notified = rule.update(series, 90_000);
+        assert_eq!(1, notified.len());
+        assert_eq!(AlertState::Firing, notified[0].state);
+        assert_eq!(30_000, notified[0].active_at);
+    }
+
+    #[test]
+    fn test_no_restore_for_alerts_firing_immediately() {
+        let rule = new_rule(Duration::ZERO);
+        assert_eq!(None, rule.restore_query());
+    }
+}
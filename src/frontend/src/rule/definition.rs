@@ -0,0 +1,268 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Definitions of rules in the
+//! [Prometheus rule file format](https://prometheus.io/docs/prometheus/latest/configuration/recording_rules/).
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use serde::Deserialize;
+use servers::prometheus::METRIC_NAME_LABEL;
+use snafu::{ensure, ResultExt};
+
+use crate::error::{InvalidRuleSnafu, ParseRuleFileSnafu, ReadRuleFileSnafu, Result};
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RuleFile {
+    #[serde(default)]
+    pub(crate) groups: Vec<GroupDefinition>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct GroupDefinition {
+    pub(crate) name: String,
+    /// How often the rules are evaluated, the global evaluation interval if absent.
+    #[serde(default, with = "humantime_serde")]
+    pub(crate) interval: Option<Duration>,
+    #[serde(default)]
+    pub(crate) rules: Vec<RuleDefinition>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RuleDefinition {
+    /// Name of the metric the results of a recording rule are written to.
+    pub(crate) record: Option<String>,
+    /// Name of an alerting rule.
+    pub(crate) alert: Option<String>,
+    pub(crate) expr: String,
+    /// How long the expression must keep returning a series before its alert fires.
+    #[serde(rename = "for", default, with = "humantime_serde")]
+    pub(crate) hold: Option<Duration>,
+    #[serde(default)]
+    pub(crate) labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub(crate) annotations: BTreeMap<String, String>,
+}
+
+impl RuleFile {
+    /// Reads and validates the rule file at `path`.
+    pub(crate) fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path).context(ReadRuleFileSnafu { path })?;
+        Self::parse(path, &content)
+    }
+
+    fn parse(path: &str, content: &str) -> Result<Self> {
+        let file: RuleFile = serde_yaml::from_str(content).context(ParseRuleFileSnafu { path })?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut names = HashSet::with_capacity(self.groups.len());
+        for group in &self.groups {
+            ensure!(
+                !group.name.is_empty(),
+                InvalidRuleSnafu {
+                    group: "",
+                    name: "",
+                    reason: "group name must not be empty",
+                }
+            );
+            ensure!(
+                names.insert(&group.name),
+                InvalidRuleSnafu {
+                    group: &group.name,
+                    name: "",
+                    reason: "group name is repeated in the same file",
+                }
+            );
+            for rule in &group.rules {
+                rule.validate(&group.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RuleDefinition {
+    pub(crate) fn name(&self) -> &str {
+        self.record
+            .as_deref()
+            .or(self.alert.as_deref())
+            .unwrap_or_default()
+    }
+
+    fn validate(&self, group: &str) -> Result<()> {
+        let invalid = |reason: &str| {
+            InvalidRuleSnafu {
+                group,
+                name: self.name(),
+                reason,
+            }
+            .fail()
+        };
+
+        match (&self.record, &self.alert) {
+            (Some(_), Some(_)) => return invalid("only one of 'record' and 'alert' can be set"),
+            (None, None) => return invalid("one of 'record' and 'alert' must be set"),
+            (Some(record), None) => {
+                if !is_valid_metric_name(record) {
+                    return invalid("'record' must be a valid metric name");
+                }
+                if self.hold.is_some() {
+                    return invalid("'for' is only valid for alerting rules");
+                }
+                if !self.annotations.is_empty() {
+                    return invalid("'annotations' is only valid for alerting rules");
+                }
+            }
+            (None, Some(alert)) => {
+                if alert.is_empty() {
+                    return invalid("'alert' must not be empty");
+                }
+            }
+        }
+
+        if self.expr.is_empty() {
+            return invalid("'expr' must not be empty");
+        }
+        if let Err(e) = promql_parser::parser::parse(&self.expr) {
+            return invalid(&format!("failed to parse 'expr': {e}"));
+        }
+        if let Some(name) = self
+            .labels
+            .keys()
+            .chain(self.annotations.keys())
+            .find(|name| !is_valid_label_name(name) || *name == METRIC_NAME_LABEL)
+        {
+            return invalid(&format!("invalid label name {name:?}"));
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule_file() {
+        let content = r#"
+groups:
+  - name: example
+    interval: 30s
+    rules:
+      - record: job:http_requests:sum
+        expr: sum by (job) (http_requests)
+        labels:
+          team: infra
+      - alert: HighErrorRate
+        expr: job:http_errors:sum / job:http_requests:sum > 0.5
+        for: 10m
+        labels:
+          severity: page
+        annotations:
+          summary: "High error rate of {{ $labels.job }}"
+"#;
+        let file = RuleFile::parse("rules.yml", content).unwrap();
+        assert_eq!(
+            RuleFile {
+                groups: vec![GroupDefinition {
+                    name: "example".to_string(),
+                    interval: Some(Duration::from_secs(30)),
+                    rules: vec![
+                        RuleDefinition {
+                            record: Some("job:http_requests:sum".to_string()),
+                            alert: None,
+                            expr: "sum by (job) (http_requests)".to_string(),
+                            hold: None,
+                            labels: BTreeMap::from([("team".to_string(), "infra".to_string())]),
+                            annotations: BTreeMap::new(),
+                        },
+                        RuleDefinition {
+                            record: None,
+                            alert: Some("HighErrorRate".to_string()),
+                            expr: "job:http_errors:sum / job:http_requests:sum > 0.5".to_string(),
+                            hold: Some(Duration::from_secs(600)),
+                            labels: BTreeMap::from([("severity".to_string(), "page".to_string())]),
+                            annotations: BTreeMap::from([(
+                                "summary".to_string(),
+                                "High error rate of {{ $labels.job }}".to_string()
+                            )]),
+                        },
+                    ],
+                }],
+            },
+            file
+        );
+    }
+
+    #[test]
+    fn test_invalid_rule_file() {
+        let cases = [
+            (
+                "groups: [{name: g, rules: [{record: a, alert: b, expr: up}]}]",
+                "only one of 'record' and 'alert' can be set",
+            ),
+            (
+                "groups: [{name: g, rules: [{expr: up}]}]",
+                "one of 'record' and 'alert' must be set",
+            ),
+            (
+                "groups: [{name: g, rules: [{record: 'a-b', expr: up}]}]",
+                "'record' must be a valid metric name",
+            ),
+            (
+                "groups: [{name: g, rules: [{record: a, expr: up, for: 1m}]}]",
+                "'for' is only valid for alerting rules",
+            ),
+            (
+                "groups: [{name: g, rules: [{alert: a, expr: 'sum(up'}]}]",
+                "failed to parse 'expr'",
+            ),
+            (
+                "groups: [{name: g, rules: [{alert: a, expr: up, labels: {__name__: b}}]}]",
+                "invalid label name \"__name__\"",
+            ),
+            (
+                "groups: [{name: g, rules: []}, {name: g, rules: []}]",
+                "group name is repeated",
+            ),
+            (
+                "groups: [{name: g, rules: [{alert: a, expr: up, keep: 1m}]}]",
+                "unknown field `keep`",
+            ),
+        ];
+        for (content, expected) in cases {
+            let err = RuleFile::parse("rules.yml", content).unwrap_err();
+            assert!(err.to_string().contains(expected), "{content}: {err}");
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use api::prometheus::remote::{Label, Sample, TimeSeries, WriteRequest};
+use common_telemetry::logging::{debug, error};
+use query::parser::PromQuery;
+use servers::prom::{PromHandler, PromLabels, PromRule, PromRuleGroup, PromRuleHealth};
+use servers::prometheus::METRIC_NAME_LABEL;
+use servers::query_handler::PrometheusProtocolHandler;
+use session::context::QueryContext;
+use snafu::ResultExt;
+
+use crate::error::{ExecutePromqlSnafu, Result, WriteRuleResultSnafu};
+use crate::instance::Instance;
+use crate::rule::alert::{Alert, AlertingRule};
+use crate::rule::definition::{GroupDefinition, RuleDefinition};
+use crate::rule::{format_millis, AlertSinkRef};
+
+/// Step of the instant queries evaluating the rules.
+const EVALUATION_STEP: &str = "1s";
+
+/// A group of rules evaluated sequentially at the same interval, so a rule can use the results
+/// of the rules before it.
+pub(crate) struct RuleGroup {
+    pub(crate) name: String,
+    file: String,
+    pub(crate) interval: Duration,
+    rules: Vec<Rule>,
+    health: RwLock<RuleHealth>,
+}
+
+struct Rule {
+    kind: RuleKind,
+    health: RwLock<RuleHealth>,
+}
+
+enum RuleKind {
+    Recording(RecordingRule),
+    Alerting(AlertingRule),
+}
+
+/// A rule writing the series returned by its expression to the metric named `name`.
+struct RecordingRule {
+    name: String,
+    expr: String,
+    labels: PromLabels,
+}
+
+#[derive(Debug, Default, Clone)]
+struct RuleHealth {
+    last_error: Option<String>,
+    /// Time of the last evaluation in unix milliseconds.
+    last_evaluation: Option<i64>,
+    evaluation_time: Duration,
+}
+
+impl RuleHealth {
+    fn to_prom_health(&self) -> PromRuleHealth {
+        let health = match (&self.last_evaluation, &self.last_error) {
+            (None, _) => "unknown",
+            (Some(_), None) => "ok",
+            (Some(_), Some(_)) => "err",
+        };
+        PromRuleHealth {
+            health: health.to_string(),
+            last_error: self.last_error.clone(),
+            last_evaluation: self.last_evaluation.map(format_millis),
+            evaluation_time: self.evaluation_time.as_secs_f64(),
+        }
+    }
+}
+
+impl RuleGroup {
+    pub(crate) fn new(file: &str, definition: GroupDefinition, default_interval: Duration) -> Self {
+        let rules = definition
+            .rules
+            .iter()
+            .map(|rule| Rule {
+                kind: RuleKind::new(rule),
+                health: RwLock::default(),
+            })
+            .collect();
+        Self {
+            name: definition.name,
+            file: file.to_string(),
+            interval: definition.interval.unwrap_or(default_interval),
+            rules,
+            health: RwLock::default(),
+        }
+    }
+
+    /// Evaluates the rules at `now`, in unix milliseconds. A failed rule doesn't stop evaluating
+    /// the rules after it, its error is recorded in its health instead.
+    pub(crate) async fn evaluate(
+        &self,
+        instance: &Instance,
+        sink: Option<&AlertSinkRef>,
+        now: i64,
+    ) {
+        let group_start = Instant::now();
+        for rule in &self.rules {
+            let start = Instant::now();
+            let result = rule.kind.evaluate(instance, sink, now).await;
+            if let Err(e) = &result {
+                error!(e; "Failed to evaluate rule {} in group {}", rule.kind.name(), self.name);
+            }
+            *rule.health.write().unwrap() = RuleHealth {
+                last_error: result.err().map(|e| e.to_string()),
+                last_evaluation: Some(now),
+                evaluation_time: start.elapsed(),
+            };
+        }
+        *self.health.write().unwrap() = RuleHealth {
+            last_error: None,
+            last_evaluation: Some(now),
+            evaluation_time: group_start.elapsed(),
+        };
+        debug!("Evaluated rule group {} at {}", self.name, now);
+    }
+
+    pub(crate) fn to_prom_rule_group(&self) -> PromRuleGroup {
+        let health = self.health.read().unwrap().to_prom_health();
+        PromRuleGroup {
+            name: self.name.clone(),
+            file: self.file.clone(),
+            rules: self.rules.iter().map(Rule::to_prom_rule).collect(),
+            interval: self.interval.as_secs_f64(),
+            last_evaluation: health.last_evaluation,
+            evaluation_time: health.evaluation_time,
+        }
+    }
+
+    /// Returns the pending and firing alerts of the alerting rules.
+    pub(crate) fn alerts(&self) -> Vec<Alert> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match &rule.kind {
+                RuleKind::Alerting(rule) => Some(rule.alerts()),
+                RuleKind::Recording(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+impl Rule {
+    fn to_prom_rule(&self) -> PromRule {
+        let health = self.health.read().unwrap().to_prom_health();
+        match &self.kind {
+            RuleKind::Recording(rule) => PromRule::Recording {
+                name: rule.name.clone(),
+                query: rule.expr.clone(),
+                labels: rule.labels.clone(),
+                health,
+            },
+            RuleKind::Alerting(rule) => PromRule::Alerting {
+                name: rule.name.clone(),
+                query: rule.expr.clone(),
+                duration: rule.hold.as_secs_f64(),
+                labels: rule.labels.clone(),
+                annotations: rule.annotations.clone(),
+                alerts: rule.alerts().iter().map(Alert::to_prom_alert).collect(),
+                state: rule.state().as_str().to_string(),
+                health,
+            },
+        }
+    }
+}
+
+impl RuleKind {
+    fn new(definition: &RuleDefinition) -> Self {
+        match &definition.record {
+            Some(name) => RuleKind::Recording(RecordingRule {
+                name: name.clone(),
+                expr: definition.expr.clone(),
+                labels: definition.labels.clone(),
+            }),
+            None => RuleKind::Alerting(AlertingRule::new(definition)),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            RuleKind::Recording(rule) => &rule.name,
+            RuleKind::Alerting(rule) => &rule.name,
+        }
+    }
+
+    async fn evaluate(
+        &self,
+        instance: &Instance,
+        sink: Option<&AlertSinkRef>,
+        now: i64,
+    ) -> Result<()> {
+        match self {
+            RuleKind::Recording(rule) => {
+                let samples = query_instant(instance, &rule.expr, now).await?;
+                let timeseries = samples
+                    .into_iter()
+                    .map(|(mut labels, value)| {
+                        labels.extend(rule.labels.clone());
+                        let _ = labels.insert(METRIC_NAME_LABEL.to_string(), rule.name.clone());
+                        TimeSeries {
+                            labels: labels
+                                .into_iter()
+                                .map(|(name, value)| Label { name, value })
+                                .collect(),
+                            samples: vec![Sample {
+                                value,
+                                timestamp: now,
+                            }],
+                            ..Default::default()
+                        }
+                    })
+                    .collect();
+                write(instance, &rule.name, timeseries).await
+            }
+            RuleKind::Alerting(rule) => {
+                if let Some(query) = rule.restore_query() {
+                    // Failing to restore doesn't fail the rule, the alerts become pending from
+                    // now instead.
+                    let states = query_instant(instance, &query, now)
+                        .await
+                        .unwrap_or_else(|e| {
+                            error!(e; "Failed to restore the alerts of rule {}", rule.name);
+                            vec![]
+                        });
+                    rule.restore(states);
+                }
+                let samples = query_instant(instance, &rule.expr, now).await?;
+                let notified = rule.update(samples, now);
+                write(instance, &rule.name, rule.alerts_series(now)).await?;
+                if let Some(sink) = sink.filter(|_| !notified.is_empty()) {
+                    // Failing to notify doesn't fail the rule, the alerts are sent again in
+                    // the next evaluation.
+                    if let Err(e) = sink.send(&notified).await {
+                        error!(e; "Failed to send {} alerts of rule {}", notified.len(), rule.name);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Evaluates `expr` at `now`, returns the value of each series. The label sets of the series
+/// don't contain the metric name.
+async fn query_instant(
+    instance: &Instance,
+    expr: &str,
+    now: i64,
+) -> Result<Vec<(PromLabels, f64)>> {
+    let time = (now as f64 / 1000.0).to_string();
+    let query = PromQuery {
+        query: expr.to_string(),
+        start: time.clone(),
+        end: time,
+        step: EVALUATION_STEP.to_string(),
+    };
+    let result = PromHandler::do_query(instance, &query, QueryContext::arc()).await;
+    let series = servers::prom::query_samples(result, "")
+        .await
+        .context(ExecutePromqlSnafu { query: expr })?;
+    Ok(series
+        .into_iter()
+        .filter_map(|(mut labels, mut samples)| {
+            let _ = labels.remove(METRIC_NAME_LABEL);
+            samples.pop().map(|(_, value)| (labels, value))
+        })
+        .collect())
+}
+
+async fn write(instance: &Instance, rule: &str, timeseries: Vec<TimeSeries>) -> Result<()> {
+    if timeseries.is_empty() {
+        return Ok(());
+    }
+    let request = WriteRequest {
+        timeseries,
+        ..Default::default()
+    };
+    PrometheusProtocolHandler::write(instance, request, QueryContext::arc())
+        .await
+        .context(WriteRuleResultSnafu { name: rule })
+}
@@ -21,6 +21,9 @@ use common_runtime::Builder as RuntimeBuilder;
 use common_telemetry::info;
 use servers::auth::UserProviderRef;
 use servers::error::Error::InternalIo;
+use servers::flight_sql::FlightSqlServer;
+use servers::graphite::template::Templates;
+use servers::graphite::{GraphiteProtocol, GraphiteServer};
 use servers::grpc::GrpcServer;
 use servers::http::HttpServerBuilder;
 use servers::metrics_handler::MetricsHandler;
@@ -38,6 +41,7 @@ use crate::error::{self, Result};
 use crate::frontend::FrontendOptions;
 use crate::influxdb::InfluxdbOptions;
 use crate::instance::FrontendInstance;
+use crate::otlp::OtlpOptions;
 use crate::prometheus::PrometheusOptions;
 
 pub(crate) struct Services;
@@ -73,7 +77,8 @@ impl Services {
                 ServerGrpcQueryHandlerAdaptor::arc(instance.clone()),
                 user_provider.clone(),
                 grpc_runtime,
-            );
+            )
+            .with_bulk_insert_handler(instance.clone());
 
             result.push((Box::new(grpc_server), grpc_addr));
         };
@@ -90,10 +95,13 @@ impl Services {
             );
             let mysql_server = MysqlServer::create_server(
                 mysql_io_runtime,
-                Arc::new(MysqlSpawnRef::new(
-                    ServerSqlQueryHandlerAdaptor::arc(instance.clone()),
-                    user_provider.clone(),
-                )),
+                Arc::new(
+                    MysqlSpawnRef::new(
+                        ServerSqlQueryHandlerAdaptor::arc(instance.clone()),
+                        user_provider.clone(),
+                    )
+                    .with_bulk_insert_handler(instance.clone()),
+                ),
                 Arc::new(MysqlSpawnConfig::new(
                     opts.tls.should_force_tls(),
                     opts.tls
@@ -103,6 +111,7 @@ impl Services {
                         })?
                         .map(Arc::new),
                     opts.reject_no_database.unwrap_or(false),
+                    opts.local_infile,
                 )),
             );
             result.push((mysql_server, mysql_addr));
@@ -119,12 +128,15 @@ impl Services {
                     .context(error::RuntimeResourceSnafu)?,
             );
 
-            let pg_server = Box::new(PostgresServer::new(
-                ServerSqlQueryHandlerAdaptor::arc(instance.clone()),
-                opts.tls.clone(),
-                pg_io_runtime,
-                user_provider.clone(),
-            )) as Box<dyn Server>;
+            let pg_server = Box::new(
+                PostgresServer::new(
+                    ServerSqlQueryHandlerAdaptor::arc(instance.clone()),
+                    opts.tls.clone(),
+                    pg_io_runtime,
+                    user_provider.clone(),
+                )
+                .with_bulk_insert_handler(instance.clone()),
+            ) as Box<dyn Server>;
 
             result.push((pg_server, pg_addr));
         }
@@ -148,6 +160,60 @@ impl Services {
             set_opentsdb_handler = true;
         }
 
+        if let Some(opts) = &opts.graphite_options {
+            let templates = Arc::new(
+                Templates::try_new(&opts.templates, &opts.separator)
+                    .context(error::StartServerSnafu)?,
+            );
+
+            let io_runtime = Arc::new(
+                RuntimeBuilder::default()
+                    .worker_threads(opts.runtime_size)
+                    .thread_name("graphite-io-handlers")
+                    .build()
+                    .context(error::RuntimeResourceSnafu)?,
+            );
+
+            let addr = parse_addr(&opts.addr)?;
+            let server = GraphiteServer::create_server(
+                instance.clone(),
+                templates.clone(),
+                GraphiteProtocol::Plaintext,
+                io_runtime.clone(),
+            );
+            result.push((server, addr));
+
+            if let Some(pickle_addr) = &opts.pickle_addr {
+                let addr = parse_addr(pickle_addr)?;
+                let server = GraphiteServer::create_server(
+                    instance.clone(),
+                    templates,
+                    GraphiteProtocol::Pickle,
+                    io_runtime,
+                );
+                result.push((server, addr));
+            }
+        }
+
+        if let Some(opts) = &opts.flight_sql_options {
+            let addr = parse_addr(&opts.addr)?;
+
+            let runtime = Arc::new(
+                RuntimeBuilder::default()
+                    .worker_threads(opts.runtime_size)
+                    .thread_name("flight-sql-handlers")
+                    .build()
+                    .context(error::RuntimeResourceSnafu)?,
+            );
+
+            let server = FlightSqlServer::new(
+                ServerSqlQueryHandlerAdaptor::arc(instance.clone()),
+                user_provider.clone(),
+                runtime,
+            );
+            result.push((Box::new(server), addr));
+        }
+
         if let Some(http_options) = &opts.http_options {
             let http_addr = parse_addr(&http_options.addr)?;
 
@@ -174,7 +240,12 @@ impl Services {
                 opts.prometheus_options,
                 Some(PrometheusOptions { enable: true })
             ) {
-                http_server_builder.with_prom_handler(instance.clone());
+                http_server_builder
+                    .with_prom_handler(instance.clone())
+                    .with_prom_query_handler(instance.clone());
+            }
+            if matches!(opts.otlp_options, Some(OtlpOptions { enable: true })) {
+                http_server_builder.with_otlp_handler(instance.clone());
             }
             http_server_builder.with_metrics_handler(MetricsHandler);
             http_server_builder.with_script_handler(instance.clone());
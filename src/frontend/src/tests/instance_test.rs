@@ -13,20 +13,26 @@
 // limitations under the License.
 
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use common_catalog::consts::DEFAULT_CATALOG_NAME;
+use common_error::prelude::{ErrorExt, StatusCode};
 use common_query::Output;
 use common_recordbatch::util;
 use common_telemetry::logging;
+use common_test_util::temp_dir::create_temp_dir;
+use datatypes::prelude::Value;
 use datatypes::vectors::{Int64Vector, StringVector, UInt64Vector, VectorRef};
+use query::parser::PromQuery;
 use rstest::rstest;
 use rstest_reuse::apply;
+use servers::prom::{PromHandler, PromRule};
 use servers::query_handler::sql::SqlQueryHandler;
-use session::context::{QueryContext, QueryContextRef};
+use session::context::{QueryContext, QueryContextRef, UserInfo};
 
 use crate::error::{Error, Result};
 use crate::instance::Instance;
+use crate::rule::{Alert, AlertSink, AlertSinkRef, AlertState, RuleOptions};
 use crate::tests::test_util::{
     both_instances_cases, check_output_stream, check_unordered_output_stream, distributed,
     standalone, standalone_instance_case, MockInstance,
@@ -944,21 +950,27 @@ async fn test_information_schema(instance: Arc<dyn MockInstance>) {
     let output = execute_sql(&instance, sql).await;
     let expected = if is_distributed_mode {
         "\
-+---------------+--------------------+------------+------------+
-| table_catalog | table_schema       | table_name | table_type |
-+---------------+--------------------+------------+------------+
-| greptime      | public             | scripts    | BASE TABLE |
-| greptime      | information_schema | tables     | VIEW       |
-+---------------+--------------------+------------+------------+"
++---------------+--------------------+----------------------------+------------+
+| table_catalog | table_schema       | table_name                 | table_type |
++---------------+--------------------+----------------------------+------------+
+| greptime      | information_schema | processlist                | VIEW       |
+| greptime      | public             | prometheus_exemplars       | BASE TABLE |
+| greptime      | public             | prometheus_metric_metadata | BASE TABLE |
+| greptime      | public             | scripts                    | BASE TABLE |
+| greptime      | information_schema | tables                     | VIEW       |
++---------------+--------------------+----------------------------+------------+"
     } else {
         "\
-+---------------+--------------------+------------+------------+
-| table_catalog | table_schema       | table_name | table_type |
-+---------------+--------------------+------------+------------+
-| greptime      | public             | numbers    | BASE TABLE |
-| greptime      | public             | scripts    | BASE TABLE |
-| greptime      | information_schema | tables     | VIEW       |
-+---------------+--------------------+------------+------------+"
++---------------+--------------------+----------------------------+------------+
+| table_catalog | table_schema       | table_name                 | table_type |
++---------------+--------------------+----------------------------+------------+
+| greptime      | public             | numbers                    | BASE TABLE |
+| greptime      | information_schema | processlist                | VIEW       |
+| greptime      | public             | prometheus_exemplars       | BASE TABLE |
+| greptime      | public             | prometheus_metric_metadata | BASE TABLE |
+| greptime      | public             | scripts                    | BASE TABLE |
+| greptime      | information_schema | tables                     | VIEW       |
++---------------+--------------------+----------------------------+------------+"
     };
     check_output_stream(output, expected).await;
 
@@ -968,11 +980,614 @@ async fn test_information_schema(instance: Arc<dyn MockInstance>) {
 | table_catalog   | table_schema       | table_name    | table_type |
 +-----------------+--------------------+---------------+------------+
 | another_catalog | another_schema     | another_table | BASE TABLE |
+| another_catalog | information_schema | processlist   | VIEW       |
 | another_catalog | information_schema | tables        | VIEW       |
 +-----------------+--------------------+---------------+------------+";
     check_output_stream(output, expected).await;
 }
 
+#[apply(standalone_instance_case)]
+async fn test_show_processlist_and_kill_query(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    // Processes are listed per catalog, so other tests running in parallel can't interfere.
+    let query_ctx = Arc::new(QueryContext::with("processlist_catalog", "public"));
+    let output = execute_sql_with(&instance, "show processlist", query_ctx.clone()).await;
+    let Output::RecordBatches(batches) = output else { unreachable!() };
+    let batches = batches.take();
+    assert_eq!(batches[0].num_rows(), 1);
+    assert_eq!(
+        batches[0].column(0).get(0),
+        Value::UInt32(query_ctx.conn_id())
+    );
+    assert_eq!(batches[0].column(6).get(0), Value::from("show processlist"));
+
+    let sql = "select * from numbers";
+    let output = execute_sql_with(&instance, sql, query_ctx.clone()).await;
+    let Output::Stream(stream) = output else { unreachable!() };
+
+    let sql = format!("kill query {}", query_ctx.conn_id());
+    let output = execute_sql(&instance, &sql).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let err = util::collect(stream).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::Cancelled);
+
+    // `KILL <id>` of MySQL clients also cancels the statement.
+    let output = execute_sql_with(&instance, "select * from numbers", query_ctx.clone()).await;
+    let Output::Stream(stream) = output else { unreachable!() };
+    let output = execute_sql(&instance, &format!("kill {}", query_ctx.conn_id())).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let err = util::collect(stream).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::Cancelled);
+
+    // The killed statement is gone from the list.
+    let err = try_execute_sql(&instance, &sql).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::InvalidArguments);
+}
+
+#[apply(standalone_instance_case)]
+async fn test_processlist_of_users(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    let new_query_ctx = |user_info| {
+        let query_ctx = QueryContext::arc();
+        query_ctx.set_current_user(user_info);
+        query_ctx
+    };
+    let alice = new_query_ctx(UserInfo::new("alice"));
+    let bob = new_query_ctx(UserInfo::new("bob"));
+    let admin = new_query_ctx(UserInfo::new_admin("greptime"));
+
+    let Output::Stream(alice_stream) =
+        execute_sql_with(&instance, "select * from numbers", alice.clone()).await
+    else {
+        unreachable!()
+    };
+
+    // Lists by both `SHOW PROCESSLIST` and `information_schema.processlist`, each of them lists
+    // itself as the statement of the connection.
+    let list_ids = |query_ctx: QueryContextRef| {
+        let instance = instance.clone();
+        async move {
+            let output = execute_sql_with(&instance, "show processlist", query_ctx.clone()).await;
+            let shown_ids = collect_ids(output).await;
+            let sql = "select id from information_schema.processlist";
+            let output = execute_sql_with(&instance, sql, query_ctx).await;
+            (shown_ids, collect_ids(output).await)
+        }
+    };
+
+    // Bob only sees his own statement, but not the query of Alice.
+    let expected = vec![bob.conn_id()];
+    assert_eq!((expected.clone(), expected), list_ids(bob.clone()).await);
+    // The administrator sees all statements, including the ones of other tests.
+    let (shown_ids, ids) = list_ids(admin.clone()).await;
+    for ids in [shown_ids, ids] {
+        assert!(ids.contains(&alice.conn_id()));
+        assert!(ids.contains(&admin.conn_id()));
+    }
+
+    // Bob can't kill the query of Alice.
+    let sql = format!("kill query {}", alice.conn_id());
+    let err = try_execute_sql_with(&instance, &sql, bob.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::AccessDenied);
+
+    // Alice can kill her own query from another connection.
+    let output = execute_sql_with(&instance, &sql, new_query_ctx(UserInfo::new("alice"))).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let err = util::collect(alice_stream).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::Cancelled);
+}
+
+async fn collect_ids(output: Output) -> Vec<u32> {
+    let batches = match output {
+        Output::RecordBatches(batches) => batches.take(),
+        Output::Stream(stream) => util::collect(stream).await.unwrap(),
+        _ => unreachable!(),
+    };
+    batches
+        .iter()
+        .flat_map(|batch| {
+            (0..batch.num_rows()).map(|i| match batch.column(0).get(i) {
+                Value::UInt32(id) => id,
+                _ => unreachable!(),
+            })
+        })
+        .collect()
+}
+
+#[apply(standalone_instance_case)]
+async fn test_query_limits(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    let query_ctx = QueryContext::arc();
+    let sql = "select number, count(*) from numbers group by number";
+
+    let output = execute_sql_with(&instance, "set query_memory_limit = 1", query_ctx.clone()).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let Output::Stream(stream) = execute_sql_with(&instance, sql, query_ctx.clone()).await else {
+        unreachable!()
+    };
+    let err = util::collect(stream).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::ExceedMemoryLimit);
+
+    let output = execute_sql_with(&instance, "set query_memory_limit = 0", query_ctx.clone()).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let Output::Stream(stream) = execute_sql_with(&instance, sql, query_ctx.clone()).await else {
+        unreachable!()
+    };
+    let batches = util::collect(stream).await.unwrap();
+    assert_eq!(batches.iter().map(|x| x.num_rows()).sum::<usize>(), 100);
+
+    let output = execute_sql_with(&instance, "set statement_timeout = 1", query_ctx.clone()).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    // The statement times out either while it's executed or while its output is consumed.
+    let status_code = match try_execute_sql_with(&instance, sql, query_ctx.clone()).await {
+        Ok(Output::Stream(stream)) => {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            util::collect(stream).await.unwrap_err().status_code()
+        }
+        Ok(_) => unreachable!(),
+        Err(e) => e.status_code(),
+    };
+    assert_eq!(status_code, StatusCode::QueryTimeout);
+
+    // PromQL queries are limited by the session as well.
+    let query_ctx = QueryContext::arc();
+    let sql = "create table prom_limits(ts timestamp time index, val double)";
+    let output = execute_sql_with(&instance, sql, query_ctx.clone()).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let sql = "insert into prom_limits values (1000, 1.0), (2000, 2.0), (3000, 3.0)";
+    let output = execute_sql_with(&instance, sql, query_ctx.clone()).await;
+    assert!(matches!(output, Output::AffectedRows(3)));
+    let output = execute_sql_with(&instance, "set statement_timeout = 1", query_ctx.clone()).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let query = PromQuery {
+        query: "max_over_time(prom_limits[1s])".to_string(),
+        start: "0".to_string(),
+        end: "3".to_string(),
+        step: "1s".to_string(),
+    };
+    let status_code = match instance
+        .do_promql_query(&query, query_ctx.clone())
+        .await
+        .remove(0)
+    {
+        Ok(Output::Stream(stream)) => {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            util::collect(stream).await.unwrap_err().status_code()
+        }
+        Ok(_) => unreachable!(),
+        Err(e) => e.status_code(),
+    };
+    assert_eq!(status_code, StatusCode::QueryTimeout);
+
+    let err = try_execute_sql_with(
+        &instance,
+        "set statement_timeout = 'forever'",
+        query_ctx.clone(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::InvalidArguments);
+    let err = try_execute_sql_with(&instance, "set no_such_variable = 1", query_ctx)
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::Unsupported);
+}
+
+#[apply(both_instances_cases)]
+async fn test_materialized_view(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    execute_sql(
+        &instance,
+        "create table cpu(host string, usage double, ts timestamp time index, primary key (host))",
+    )
+    .await;
+    let sql = "insert into cpu values \
+        ('host1', 1.0, 0), ('host1', 3.0, 30000), ('host2', 5.0, 50000), \
+        ('host1', 10.0, 60000), ('host2', 20.0, 90000)";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(5)));
+
+    let sql = "create materialized view cpu_1m as \
+        select host, date_bin(INTERVAL '1 minute', ts) as time_window, avg(usage) as usage \
+        from cpu group by host, time_window";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let err = try_execute_sql(&instance, sql).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableAlreadyExists);
+    let sql =
+        "create materialized view cpu_bad as select host, avg(usage) as u from cpu group by host";
+    let err = try_execute_sql(&instance, sql).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::InvalidArguments);
+
+    let sql = "create materialized view cpu_1m_late with (allowed_lateness = '5m') as \
+        select host, date_bin(INTERVAL '1 minute', ts) as time_window, avg(usage) as usage \
+        from cpu group by host, time_window";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let sql = "create materialized view cpu_bad with (lateness = '5m') as \
+        select date_bin('1m', ts) as t, avg(usage) as u from cpu group by t";
+    let err = try_execute_sql(&instance, sql).await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::InvalidArguments);
+
+    let manager = instance.materialized_view_manager();
+    manager.refresh_at(120_000).await.unwrap();
+    let expected = "\
++-------+---------------------+-------+
+| host  | time_window         | usage |
++-------+---------------------+-------+
+| host1 | 1970-01-01T00:00:00 | 2.0   |
+| host1 | 1970-01-01T00:01:00 | 10.0  |
+| host2 | 1970-01-01T00:00:00 | 5.0   |
+| host2 | 1970-01-01T00:01:00 | 20.0  |
++-------+---------------------+-------+";
+    for view in ["cpu_1m", "cpu_1m_late"] {
+        let sql = format!("select * from {view} order by host, time_window");
+        check_output_stream(execute_sql(&instance, &sql).await, expected).await;
+    }
+
+    // Refreshing again changes nothing, the buckets are not aggregated twice.
+    manager.refresh_at(120_000).await.unwrap();
+    let output = execute_sql(&instance, "select * from cpu_1m order by host, time_window").await;
+    check_output_stream(output, expected).await;
+
+    // The late row is only aggregated into the view allowing it.
+    let output = execute_sql(&instance, "insert into cpu values ('host1', 5.0, 10000)").await;
+    assert!(matches!(output, Output::AffectedRows(1)));
+    manager.refresh_at(180_000).await.unwrap();
+    let output = execute_sql(&instance, "select * from cpu_1m order by host, time_window").await;
+    check_output_stream(output, expected).await;
+    let expected_late = expected.replace("| 2.0   |", "| 3.0   |");
+    let sql = "select * from cpu_1m_late order by host, time_window";
+    check_output_stream(execute_sql(&instance, sql).await, &expected_late).await;
+
+    let output = execute_sql(&instance, "drop materialized view cpu_1m").await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    assert!(try_execute_sql(&instance, "select * from cpu_1m")
+        .await
+        .is_err());
+    let err = try_execute_sql(&instance, "drop materialized view cpu_1m")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableNotFound);
+    let output = execute_sql(&instance, "drop materialized view if exists cpu_1m").await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+
+    // The view is dropped along with its table.
+    execute_sql(&instance, "drop table cpu_1m_late").await;
+    manager.refresh_at(240_000).await.unwrap();
+    let err = try_execute_sql(&instance, "drop materialized view cpu_1m_late")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableNotFound);
+    // Tables are not materialized views.
+    let err = try_execute_sql(&instance, "drop materialized view cpu")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableNotFound);
+
+    // A refresh aggregates a limited number of buckets, the history is filled gradually.
+    let sql = "create materialized view cpu_1ms as \
+        select host, date_bin('1ms', ts) as t, max(usage) as usage from cpu group by host, t";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    manager.refresh_at(240_000).await.unwrap();
+    let expected = "\
++-----------------+
+| COUNT(UInt8(1)) |
++-----------------+
+| 1               |
++-----------------+";
+    let output = execute_sql(&instance, "select count(*) from cpu_1ms").await;
+    check_output_stream(output, expected).await;
+}
+
+/// Keeps the notified alerts, standing in for an Alertmanager.
+#[derive(Default)]
+struct LocalAlertSink {
+    alerts: Mutex<Vec<Alert>>,
+}
+
+#[async_trait::async_trait]
+impl AlertSink for LocalAlertSink {
+    async fn send(&self, alerts: &[Alert]) -> Result<()> {
+        self.alerts.lock().unwrap().extend_from_slice(alerts);
+        Ok(())
+    }
+}
+
+#[apply(standalone_instance_case)]
+async fn test_rules(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    execute_sql(
+        &instance,
+        "create table host_load(host string, val double, ts timestamp time index, primary key (host))",
+    )
+    .await;
+    let sql = "insert into host_load values ('a', 2.0, 30000), ('b', 0.5, 30000)";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(2)));
+
+    let dir = create_temp_dir("test_rules");
+    let path = dir.path().join("rules.yml");
+    std::fs::write(
+        &path,
+        r#"
+groups:
+  - name: load
+    rules:
+      - record: host_load_doubled
+        expr: host_load * 2
+        labels:
+          source: rule
+      - alert: HighLoad
+        expr: host_load > 1
+        labels:
+          severity: page
+        annotations:
+          summary: "{{ $labels.host }} load is {{ $value }}"
+"#,
+    )
+    .unwrap();
+    let rule_manager = instance.rule_manager();
+    rule_manager
+        .load(&RuleOptions {
+            rule_files: vec![path.to_string_lossy().to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+
+    let concrete_sink = Arc::new(LocalAlertSink::default());
+    let sink: AlertSinkRef = concrete_sink.clone();
+    rule_manager.evaluate(&instance, Some(&sink), 60000).await;
+
+    let expected = "\
++------+--------+----------------+---------------------+
+| host | source | greptime_value | greptime_timestamp  |
++------+--------+----------------+---------------------+
+| a    | rule   | 4.0            | 1970-01-01T00:01:00 |
+| b    | rule   | 1.0            | 1970-01-01T00:01:00 |
++------+--------+----------------+---------------------+";
+    let sql = "select host, source, greptime_value, greptime_timestamp from host_load_doubled order by host";
+    let output = execute_sql(&instance, sql).await;
+    check_output_stream(output, expected).await;
+
+    // The alert fires immediately without `for`.
+    let expected = "\
++-----------+------------+------+----------+----------------+
+| alertname | alertstate | host | severity | greptime_value |
++-----------+------------+------+----------+----------------+
+| HighLoad  | firing     | a    | page     | 1.0            |
++-----------+------------+------+----------+----------------+";
+    let sql = r#"select alertname, alertstate, host, severity, greptime_value from "ALERTS""#;
+    let output = execute_sql(&instance, sql).await;
+    check_output_stream(output, expected).await;
+
+    let alerts = instance.alerts().await.unwrap();
+    assert_eq!(1, alerts.len());
+    assert_eq!("firing", alerts[0].state);
+    assert_eq!("a load is 2", alerts[0].annotations["summary"]);
+    let groups = instance.rule_groups().await.unwrap();
+    assert_eq!(1, groups.len());
+    assert_eq!(2, groups[0].rules.len());
+    for rule in &groups[0].rules {
+        let (PromRule::Recording { health, .. } | PromRule::Alerting { health, .. }) = rule;
+        assert_eq!("ok", health.health, "{rule:?}");
+    }
+    {
+        let notified = concrete_sink.alerts.lock().unwrap();
+        assert_eq!(1, notified.len());
+        assert_eq!(AlertState::Firing, notified[0].state);
+        assert_eq!("a", notified[0].labels["host"]);
+    }
+
+    // The alert is resolved once the sample is out of the lookback window.
+    rule_manager.evaluate(&instance, Some(&sink), 600000).await;
+    assert!(instance.alerts().await.unwrap().is_empty());
+    let notified = concrete_sink.alerts.lock().unwrap();
+    assert_eq!(2, notified.len());
+    assert_eq!(AlertState::Inactive, notified[1].state);
+    assert_eq!(Some(600000), notified[1].resolved_at);
+}
+
+#[apply(standalone_instance_case)]
+async fn test_restore_alerts(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    execute_sql(
+        &instance,
+        "create table host_load(host string, val double, ts timestamp time index, primary key (host))",
+    )
+    .await;
+    let sql = "insert into host_load values ('a', 2.0, 30000)";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(1)));
+
+    let dir = create_temp_dir("test_restore_alerts");
+    let path = dir.path().join("rules.yml");
+    std::fs::write(
+        &path,
+        r#"
+groups:
+  - name: load
+    rules:
+      - alert: HighLoad
+        expr: host_load > 1
+        for: 2m
+"#,
+    )
+    .unwrap();
+    let opts = RuleOptions {
+        rule_files: vec![path.to_string_lossy().to_string()],
+        ..Default::default()
+    };
+    let rule_manager = instance.rule_manager();
+    rule_manager.load(&opts).unwrap();
+    rule_manager.evaluate(&instance, None, 60000).await;
+
+    let expected = "\
++-----------+------+----------------+
+| alertname | host | greptime_value |
++-----------+------+----------------+
+| HighLoad  | a    | 60.0           |
++-----------+------+----------------+";
+    let sql = r#"select alertname, host, greptime_value from "ALERTS_FOR_STATE""#;
+    let output = execute_sql(&instance, sql).await;
+    check_output_stream(output, expected).await;
+
+    // Loading the rules again drops their state, as a restart does. The alert is still active
+    // since it was first evaluated, instead of becoming pending from now.
+    rule_manager.load(&opts).unwrap();
+    rule_manager.evaluate(&instance, None, 150000).await;
+    let alerts = instance.alerts().await.unwrap();
+    assert_eq!(1, alerts.len());
+    assert_eq!("pending", alerts[0].state);
+    assert_eq!("1970-01-01T00:01:00.000Z", alerts[0].active_at);
+
+    rule_manager.evaluate(&instance, None, 180000).await;
+    let alerts = instance.alerts().await.unwrap();
+    assert_eq!(1, alerts.len());
+    assert_eq!("firing", alerts[0].state);
+}
+
+#[apply(both_instances_cases)]
+async fn test_view(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    execute_sql(
+        &instance,
+        "create table cpu(host string, usage double, ts timestamp time index, primary key (host))",
+    )
+    .await;
+    let sql =
+        "insert into cpu values ('host1', 1.0, 0), ('host2', 3.0, 1000), ('host3', 5.0, 2000)";
+    let output = execute_sql(&instance, sql).await;
+    assert!(matches!(output, Output::AffectedRows(3)));
+
+    let output = execute_sql(
+        &instance,
+        "create view busy_hosts as select host, usage from cpu where usage > 2",
+    )
+    .await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let err = try_execute_sql(&instance, "create view busy_hosts as select host from cpu")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableAlreadyExists);
+    let err = try_execute_sql(&instance, "create view cpu as select host from cpu")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableAlreadyExists);
+    let err = try_execute_sql(
+        &instance,
+        "create or replace view cpu as select host from cpu",
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableAlreadyExists);
+    let err = try_execute_sql(&instance, "create view bad as select host from not_exist")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableNotFound);
+    let output = execute_sql(
+        &instance,
+        "create view if not exists busy_hosts as select host from cpu",
+    )
+    .await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+
+    // Tables can't take the names of views either.
+    let err = try_execute_sql(
+        &instance,
+        "create table busy_hosts(host string, ts timestamp time index)",
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableAlreadyExists);
+
+    // Views are listed along with tables.
+    let output = execute_sql(&instance, "show tables like 'busy%'").await;
+    let expected = "\
++------------+
+| Tables     |
++------------+
+| busy_hosts |
++------------+";
+    check_output_stream(output, expected).await;
+    let sql = "select table_name, table_type from information_schema.tables \
+        where table_schema = 'public' and table_name in ('cpu', 'busy_hosts') order by table_name";
+    let output = execute_sql(&instance, sql).await;
+    let expected = "\
++------------+------------+
+| table_name | table_type |
++------------+------------+
+| busy_hosts | VIEW       |
+| cpu        | BASE TABLE |
++------------+------------+";
+    check_output_stream(output, expected).await;
+
+    let output = execute_sql(&instance, "select * from busy_hosts order by host").await;
+    let expected = "\
++-------+-------+
+| host  | usage |
++-------+-------+
+| host2 | 3.0   |
+| host3 | 5.0   |
++-------+-------+";
+    check_output_stream(output, expected).await;
+
+    let output = execute_sql(&instance, "show create view busy_hosts").await;
+    let expected = "\
++------------+-----------------------------------------------------------------------+
+| View       | Create View                                                           |
++------------+-----------------------------------------------------------------------+
+| busy_hosts | CREATE VIEW busy_hosts AS SELECT host, usage FROM cpu WHERE usage > 2 |
++------------+-----------------------------------------------------------------------+";
+    check_output_stream(output, expected).await;
+
+    // Views are expanded in their own schema, and can be built upon other views.
+    execute_sql(&instance, "create database db2").await;
+    let output = execute_sql(
+        &instance,
+        "create view db2.hottest as select host from public.busy_hosts order by usage desc limit 1",
+    )
+    .await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let output = execute_sql(
+        &instance,
+        "create or replace view busy_hosts as select host, usage from cpu where usage < 2",
+    )
+    .await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let output = execute_sql(&instance, "select * from db2.hottest").await;
+    let expected = "\
++-------+
+| host  |
++-------+
+| host1 |
++-------+";
+    check_output_stream(output, expected).await;
+
+    let output = execute_sql(&instance, "drop view busy_hosts").await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+    let err = try_execute_sql(&instance, "select * from busy_hosts")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableNotFound);
+    let err = try_execute_sql(&instance, "drop view busy_hosts")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::TableNotFound);
+    let output = execute_sql(&instance, "drop view if exists busy_hosts").await;
+    assert!(matches!(output, Output::AffectedRows(0)));
+}
+
 async fn execute_sql(instance: &Arc<Instance>, sql: &str) -> Output {
     execute_sql_with(instance, sql, QueryContext::arc()).await
 }
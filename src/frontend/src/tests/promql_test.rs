@@ -508,3 +508,686 @@ async fn binary_op_plain_columns(instance: Arc<dyn MockInstance>) {
     )
     .await;
 }
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn binary_op_ignoring(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"http_requests{group="canary"} / ignoring(group) http_requests{group="production"}"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+------------+----------+---------------------+-------------------------------------------+\
+        \n| job        | instance | ts                  | http_requests.value / http_requests.value |\
+        \n+------------+----------+---------------------+-------------------------------------------+\
+        \n| api-server | 0        | 1970-01-01T00:00:00 | 3.0                                       |\
+        \n| api-server | 1        | 1970-01-01T00:00:00 | 2.0                                       |\
+        \n| app-server | 0        | 1970-01-01T00:00:00 | 1.4                                       |\
+        \n| app-server | 1        | 1970-01-01T00:00:00 | 1.3333333333333333                        |\
+        \n+------------+----------+---------------------+-------------------------------------------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn binary_op_group_left(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"http_requests{job="api-server"} / on(group) group_left sum by (group) (http_requests{job="api-server"})"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+------------+----------+------------+---------------------+------------------------------------------------+\
+        \n| job        | instance | group      | ts                  | http_requests.value / SUM(http_requests.value) |\
+        \n+------------+----------+------------+---------------------+------------------------------------------------+\
+        \n| api-server | 0        | canary     | 1970-01-01T00:00:00 | 0.42857142857142855                            |\
+        \n| api-server | 0        | production | 1970-01-01T00:00:00 | 0.3333333333333333                             |\
+        \n| api-server | 1        | canary     | 1970-01-01T00:00:00 | 0.5714285714285714                             |\
+        \n| api-server | 1        | production | 1970-01-01T00:00:00 | 0.6666666666666666                             |\
+        \n+------------+----------+------------+---------------------+------------------------------------------------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn binary_op_unless_on(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"http_requests{job="api-server"} unless on(group) http_requests{group="canary"}"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+------------+----------+------------+---------------------+-------+\
+        \n| job        | instance | group      | ts                  | value |\
+        \n+------------+----------+------------+---------------------+-------+\
+        \n| api-server | 0        | production | 1970-01-01T00:00:00 | 100.0 |\
+        \n| api-server | 1        | production | 1970-01-01T00:00:00 | 200.0 |\
+        \n+------------+----------+------------+---------------------+-------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn aggregators_topk(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"topk by (group) (1, http_requests{job="api-server"})"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+------------+----------+------------+---------------------+-------+\
+        \n| job        | instance | group      | ts                  | value |\
+        \n+------------+----------+------------+---------------------+-------+\
+        \n| api-server | 1        | canary     | 1970-01-01T00:00:00 | 400.0 |\
+        \n| api-server | 1        | production | 1970-01-01T00:00:00 | 200.0 |\
+        \n+------------+----------+------------+---------------------+-------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn aggregators_quantile(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"quantile(0.5, http_requests{job="api-server"})"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+------------------------------------+\
+        \n| ts                  | quantile(0.5, http_requests.value) |\
+        \n+---------------------+------------------------------------+\
+        \n| 1970-01-01T00:00:00 | 250.0                              |\
+        \n+---------------------+------------------------------------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn aggregators_count_values(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"count_values("v", http_requests{group="canary"})"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+-----+---------------------+--------------------------------------+\
+        \n| v   | ts                  | count_values(v, http_requests.value) |\
+        \n+-----+---------------------+--------------------------------------+\
+        \n| 300 | 1970-01-01T00:00:00 | 1.0                                  |\
+        \n| 400 | 1970-01-01T00:00:00 | 1.0                                  |\
+        \n| 700 | 1970-01-01T00:00:00 | 1.0                                  |\
+        \n| 800 | 1970-01-01T00:00:00 | 1.0                                  |\
+        \n+-----+---------------------+--------------------------------------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn label_replace_new_label(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"label_replace(http_requests{group="canary"}, "kind", "$1", "job", "(.*)-server")"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+------------+----------+--------+------+-------+\
+        \n| ts                  | job        | instance | group  | kind | value |\
+        \n+---------------------+------------+----------+--------+------+-------+\
+        \n| 1970-01-01T00:00:00 | api-server | 0        | canary | api  | 300.0 |\
+        \n| 1970-01-01T00:00:00 | api-server | 1        | canary | api  | 400.0 |\
+        \n| 1970-01-01T00:00:00 | app-server | 0        | canary | app  | 700.0 |\
+        \n| 1970-01-01T00:00:00 | app-server | 1        | canary | app  | 800.0 |\
+        \n+---------------------+------------+----------+--------+------+-------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn absent_series(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        r#"absent(http_requests{job="db-server", group!="canary"})"#,
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+-----------+-------+\
+        \n| ts                  | job       | value |\
+        \n+---------------------+-----------+-------+\
+        \n| 1970-01-01T00:00:00 | db-server | 1.0   |\
+        \n| 1970-01-01T00:01:00 | db-server | 1.0   |\
+        \n+---------------------+-----------+-------+",
+    )
+    .await;
+}
+
+// # Tests for holt_winters
+// # positive trends
+// load 10s
+//   http_requests{job="api-server", instance="0", group="production"}  0+10x1000 100+30x1000
+//   http_requests{job="api-server", instance="1", group="production"}  0+20x1000 200+30x1000
+//   http_requests{job="api-server", instance="0", group="canary"}      0+30x1000 300+80x1000
+//   http_requests{job="api-server", instance="1", group="canary"}      0+40x2000
+//
+// eval instant at 8000s holt_winters(http_requests[1m], 0.01, 0.1)
+//   {job="api-server", instance="0", group="production"} 8000
+//   {job="api-server", instance="1", group="production"} 16000
+//   {job="api-server", instance="0", group="canary"} 24000
+//   {job="api-server", instance="1", group="canary"} 32000
+//
+// Only the samples in the range of [1m] are loaded.
+#[apply(standalone_instance_case)]
+async fn holt_winters_positive_trends(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        r#"insert into http_requests(job, instance, group, value, ts) values
+    ('api-server', '0', 'production', 7940, 7940000),
+    ('api-server', '0', 'production', 7950, 7950000),
+    ('api-server', '0', 'production', 7960, 7960000),
+    ('api-server', '0', 'production', 7970, 7970000),
+    ('api-server', '0', 'production', 7980, 7980000),
+    ('api-server', '0', 'production', 7990, 7990000),
+    ('api-server', '0', 'production', 8000, 8000000),
+    ('api-server', '1', 'production', 15880, 7940000),
+    ('api-server', '1', 'production', 15900, 7950000),
+    ('api-server', '1', 'production', 15920, 7960000),
+    ('api-server', '1', 'production', 15940, 7970000),
+    ('api-server', '1', 'production', 15960, 7980000),
+    ('api-server', '1', 'production', 15980, 7990000),
+    ('api-server', '1', 'production', 16000, 8000000),
+    ('api-server', '0', 'canary', 23820, 7940000),
+    ('api-server', '0', 'canary', 23850, 7950000),
+    ('api-server', '0', 'canary', 23880, 7960000),
+    ('api-server', '0', 'canary', 23910, 7970000),
+    ('api-server', '0', 'canary', 23940, 7980000),
+    ('api-server', '0', 'canary', 23970, 7990000),
+    ('api-server', '0', 'canary', 24000, 8000000),
+    ('api-server', '1', 'canary', 31760, 7940000),
+    ('api-server', '1', 'canary', 31800, 7950000),
+    ('api-server', '1', 'canary', 31840, 7960000),
+    ('api-server', '1', 'canary', 31880, 7970000),
+    ('api-server', '1', 'canary', 31920, 7980000),
+    ('api-server', '1', 'canary', 31960, 7990000),
+    ('api-server', '1', 'canary', 32000, 8000000);"#,
+        "holt_winters(http_requests[1m], 0.01, 0.1)",
+        UNIX_EPOCH.checked_add(Duration::from_secs(8000)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(8000)).unwrap(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+--------------------------------------------------------------+------------+----------+------------+\
+        \n| ts                  | prom_holt_winters(ts_range,value,Float64(0.01),Float64(0.1)) | job        | instance | group      |\
+        \n+---------------------+--------------------------------------------------------------+------------+----------+------------+\
+        \n| 1970-01-01T02:13:20 | 8000.0                                                       | api-server | 0        | production |\
+        \n| 1970-01-01T02:13:20 | 16000.0                                                      | api-server | 1        | production |\
+        \n| 1970-01-01T02:13:20 | 24000.0                                                      | api-server | 0        | canary     |\
+        \n| 1970-01-01T02:13:20 | 32000.0                                                      | api-server | 1        | canary     |\
+        \n+---------------------+--------------------------------------------------------------+------------+----------+------------+",
+    )
+    .await;
+}
+
+// # negative trends
+// load 10s
+//   http_requests{job="api-server", instance="0", group="production"}  8000-10x1000
+//   http_requests{job="api-server", instance="1", group="production"}  0-20x1000
+//   http_requests{job="api-server", instance="0", group="canary"}      0+30x1000 300-80x1000
+//   http_requests{job="api-server", instance="1", group="canary"}      0-40x1000 0+40x1000
+//
+// eval instant at 8000s holt_winters(http_requests[1m], 0.01, 0.1)
+//   {job="api-server", instance="0", group="production"} 0
+//   {job="api-server", instance="1", group="production"} -16000
+//   {job="api-server", instance="0", group="canary"} 24000
+//   {job="api-server", instance="1", group="canary"} -32000
+//
+// Only the samples in the range of [1m] are loaded.
+#[apply(standalone_instance_case)]
+async fn holt_winters_negative_trends(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        r#"insert into http_requests(job, instance, group, value, ts) values
+    ('api-server', '0', 'production', 60, 7940000),
+    ('api-server', '0', 'production', 50, 7950000),
+    ('api-server', '0', 'production', 40, 7960000),
+    ('api-server', '0', 'production', 30, 7970000),
+    ('api-server', '0', 'production', 20, 7980000),
+    ('api-server', '0', 'production', 10, 7990000),
+    ('api-server', '0', 'production', 0, 8000000),
+    ('api-server', '1', 'production', -15880, 7940000),
+    ('api-server', '1', 'production', -15900, 7950000),
+    ('api-server', '1', 'production', -15920, 7960000),
+    ('api-server', '1', 'production', -15940, 7970000),
+    ('api-server', '1', 'production', -15960, 7980000),
+    ('api-server', '1', 'production', -15980, 7990000),
+    ('api-server', '1', 'production', -16000, 8000000),
+    ('api-server', '0', 'canary', 23820, 7940000),
+    ('api-server', '0', 'canary', 23850, 7950000),
+    ('api-server', '0', 'canary', 23880, 7960000),
+    ('api-server', '0', 'canary', 23910, 7970000),
+    ('api-server', '0', 'canary', 23940, 7980000),
+    ('api-server', '0', 'canary', 23970, 7990000),
+    ('api-server', '0', 'canary', 24000, 8000000),
+    ('api-server', '1', 'canary', -31760, 7940000),
+    ('api-server', '1', 'canary', -31800, 7950000),
+    ('api-server', '1', 'canary', -31840, 7960000),
+    ('api-server', '1', 'canary', -31880, 7970000),
+    ('api-server', '1', 'canary', -31920, 7980000),
+    ('api-server', '1', 'canary', -31960, 7990000),
+    ('api-server', '1', 'canary', -32000, 8000000);"#,
+        "holt_winters(http_requests[1m], 0.01, 0.1)",
+        UNIX_EPOCH.checked_add(Duration::from_secs(8000)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(8000)).unwrap(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+--------------------------------------------------------------+------------+----------+------------+\
+        \n| ts                  | prom_holt_winters(ts_range,value,Float64(0.01),Float64(0.1)) | job        | instance | group      |\
+        \n+---------------------+--------------------------------------------------------------+------------+----------+------------+\
+        \n| 1970-01-01T02:13:20 | 0.0                                                          | api-server | 0        | production |\
+        \n| 1970-01-01T02:13:20 | -16000.0                                                     | api-server | 1        | production |\
+        \n| 1970-01-01T02:13:20 | 24000.0                                                      | api-server | 0        | canary     |\
+        \n| 1970-01-01T02:13:20 | -32000.0                                                     | api-server | 1        | canary     |\
+        \n+---------------------+--------------------------------------------------------------+------------+----------+------------+",
+    )
+    .await;
+}
+
+// load 5m
+//   testcounter_reset_middle  0+10x4 0+10x5
+//
+// # Tests for predict_linear().
+// eval instant at 50m predict_linear(testcounter_reset_middle[50m], 3600)
+//   {} 76.81818181818181
+#[apply(standalone_instance_case)]
+async fn predict_linear_counter_reset(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        r#"create table testcounter_reset_middle (
+            "value" double,
+            ts timestamp TIME INDEX,
+        );"#,
+        r#"insert into testcounter_reset_middle(value, ts) values
+            (0, 0),
+            (10, 300000),
+            (20, 600000),
+            (30, 900000),
+            (40, 1200000),
+            (0, 1500000),
+            (10, 1800000),
+            (20, 2100000),
+            (30, 2400000),
+            (40, 2700000),
+            (50, 3000000);"#,
+        "predict_linear(testcounter_reset_middle[50m], 3600)",
+        UNIX_EPOCH.checked_add(Duration::from_secs(3000)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(3000)).unwrap(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+------------------------------------------------------+\
+        \n| ts                  | prom_predict_linear(ts_range,value,ts,Float64(3600)) |\
+        \n+---------------------+------------------------------------------------------+\
+        \n| 1970-01-01T00:50:00 | 76.81818181818181                                    |\
+        \n+---------------------+------------------------------------------------------+",
+    )
+    .await;
+}
+
+// load 5m
+//   http_requests{job="app-server", instance="1", group="canary"}  0+80x10
+//
+// # With http_requests, there is a sample value exactly at the end of
+// # the range, and it has exactly the predicted value, so predict_linear
+// # can be emulated with deriv.
+// eval instant at 50m predict_linear(http_requests[50m], 3600) - (http_requests + deriv(http_requests[50m]) * 3600)
+//   {group="canary", instance="1", job="app-server"} 0
+#[apply(standalone_instance_case)]
+async fn predict_linear_emulated_with_deriv(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        r#"insert into http_requests(job, instance, group, value, ts) values
+            ('app-server', '1', 'canary', 0, 0),
+            ('app-server', '1', 'canary', 80, 300000),
+            ('app-server', '1', 'canary', 160, 600000),
+            ('app-server', '1', 'canary', 240, 900000),
+            ('app-server', '1', 'canary', 320, 1200000),
+            ('app-server', '1', 'canary', 400, 1500000),
+            ('app-server', '1', 'canary', 480, 1800000),
+            ('app-server', '1', 'canary', 560, 2100000),
+            ('app-server', '1', 'canary', 640, 2400000),
+            ('app-server', '1', 'canary', 720, 2700000),
+            ('app-server', '1', 'canary', 800, 3000000);"#,
+        "predict_linear(http_requests[50m], 3600) - (http_requests + deriv(http_requests[50m]) * 3600)",
+        UNIX_EPOCH.checked_add(Duration::from_secs(3000)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(3000)).unwrap(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+------------+----------+--------+---------------------+-------------------------------------------------------------------------------------------------------------------------+\
+        \n| job        | instance | group  | ts                  | prom_predict_linear(ts_range,value,ts,Float64(3600)) - http_requests.value + prom_deriv(ts_range,value) * Float64(3600) |\
+        \n+------------+----------+--------+---------------------+-------------------------------------------------------------------------------------------------------------------------+\
+        \n| app-server | 1        | canary | 1970-01-01T00:50:00 | 0.0                                                                                                                     |\
+        \n+------------+----------+--------+---------------------+-------------------------------------------------------------------------------------------------------------------------+",
+    )
+    .await;
+}
+
+// # Tests for absent().
+// eval instant at 50m absent(nonexistent)
+//   {} 1
+//
+// eval instant at 50m absent(nonexistent{job="testjob", instance="testinstance", method=~".x"})
+//   {instance="testinstance", job="testjob"} 1
+//
+// eval instant at 50m absent(nonexistent{job="testjob",job="testjob2",foo="bar"})
+//   {foo="bar"} 1
+//
+// eval instant at 50m absent(nonexistent{job="testjob",job=~"testjob2",foo="bar"})
+//   {foo="bar"} 1
+//
+// eval instant at 50m absent(http_requests)
+//
+// eval instant at 50m absent(sum(http_requests))
+//
+// eval instant at 50m absent(sum(nonexistent{job="testjob", instance="testinstance"}))
+//   {} 1
+//
+// eval instant at 50m absent(max(nonexistant))
+//   {} 1
+//
+// eval instant at 50m absent(nonexistant > 1)
+//   {} 1
+//
+// eval instant at 50m absent(a + b)
+//   {} 1
+//
+// eval instant at 50m absent(a and b)
+//   {} 1
+//
+// eval instant at 50m absent(rate(nonexistant[5m]))
+//   {} 1
+#[apply(standalone_instance_case)]
+async fn absent_missing_series(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    // the sample of `absent()` without labels
+    const SAMPLE: &str = "+---------------------+-------+\
+            \n| time                | value |\
+            \n+---------------------+-------+\
+            \n| 1970-01-01T00:00:00 | 1.0   |\
+            \n+---------------------+-------+";
+    // `absent()` of present series produces no sample
+    const EMPTY: &str = "+----+-------+\
+            \n| ts | value |\
+            \n+----+-------+\
+            \n+----+-------+";
+
+    let cases = [
+        ("absent(nonexistent)", SAMPLE),
+        (
+            r#"absent(nonexistent{job="testjob", instance="testinstance", method=~".x"})"#,
+            "+---------------------+--------------+---------+-------+\
+        \n| time                | instance     | job     | value |\
+        \n+---------------------+--------------+---------+-------+\
+        \n| 1970-01-01T00:00:00 | testinstance | testjob | 1.0   |\
+        \n+---------------------+--------------+---------+-------+",
+        ),
+        (
+            r#"absent(nonexistent{job="testjob",job="testjob2",foo="bar"})"#,
+            "+---------------------+-----+-------+\
+        \n| time                | foo | value |\
+        \n+---------------------+-----+-------+\
+        \n| 1970-01-01T00:00:00 | bar | 1.0   |\
+        \n+---------------------+-----+-------+",
+        ),
+        (
+            r#"absent(nonexistent{job="testjob",job=~"testjob2",foo="bar"})"#,
+            "+---------------------+-----+-------+\
+        \n| time                | foo | value |\
+        \n+---------------------+-----+-------+\
+        \n| 1970-01-01T00:00:00 | bar | 1.0   |\
+        \n+---------------------+-----+-------+",
+        ),
+        ("absent(http_requests)", EMPTY),
+        ("absent(sum(http_requests))", EMPTY),
+        (
+            r#"absent(sum(nonexistent{job="testjob", instance="testinstance"}))"#,
+            SAMPLE,
+        ),
+        ("absent(max(nonexistant))", SAMPLE),
+        ("absent(nonexistant > 1)", SAMPLE),
+        ("absent(a + b)", SAMPLE),
+        ("absent(a and b)", SAMPLE),
+        ("absent(rate(nonexistant[5m]))", SAMPLE),
+    ];
+    for (promql, expected) in cases {
+        create_insert_query_assert(
+            instance.clone(),
+            AGGREGATORS_CREATE_TABLE,
+            AGGREGATORS_INSERT_DATA,
+            promql,
+            UNIX_EPOCH,
+            UNIX_EPOCH,
+            Duration::from_secs(60),
+            Duration::from_secs(0),
+            expected,
+        )
+        .await;
+    }
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn literal_expression(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        AGGREGATORS_CREATE_TABLE,
+        AGGREGATORS_INSERT_DATA,
+        "1 + 1",
+        UNIX_EPOCH,
+        unix_epoch_plus_100s(),
+        Duration::from_secs(60),
+        Duration::from_secs(0),
+        "+---------------------+-------+\
+        \n| time                | value |\
+        \n+---------------------+-------+\
+        \n| 1970-01-01T00:00:00 | 2.0   |\
+        \n| 1970-01-01T00:01:00 | 2.0   |\
+        \n+---------------------+-------+",
+    )
+    .await;
+}
+
+const SUBQUERY_CREATE_TABLE: &str = r#"create table metric (
+    host string,
+    val double,
+    ts timestamp TIME INDEX,
+    PRIMARY KEY (host),
+);"#;
+
+// A counter increasing by 1 per second in the first 10 minutes, then by 2 per second.
+const SUBQUERY_INSERT_DATA: &str = r#"insert into metric(host, val, ts) values
+    ('host1', 1000.0, 0),
+    ('host1', 1060.0, 60000),
+    ('host1', 1120.0, 120000),
+    ('host1', 1180.0, 180000),
+    ('host1', 1240.0, 240000),
+    ('host1', 1300.0, 300000),
+    ('host1', 1360.0, 360000),
+    ('host1', 1420.0, 420000),
+    ('host1', 1480.0, 480000),
+    ('host1', 1540.0, 540000),
+    ('host1', 1600.0, 600000),
+    ('host1', 1720.0, 660000),
+    ('host1', 1840.0, 720000),
+    ('host1', 1960.0, 780000),
+    ('host1', 2080.0, 840000),
+    ('host1', 2200.0, 900000),
+    ('host1', 2320.0, 960000),
+    ('host1', 2440.0, 1020000),
+    ('host1', 2560.0, 1080000),
+    ('host1', 2680.0, 1140000),
+    ('host1', 2800.0, 1200000);"#;
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn subquery_max_over_rate(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    create_insert_query_assert(
+        instance,
+        SUBQUERY_CREATE_TABLE,
+        SUBQUERY_INSERT_DATA,
+        "max_over_time(rate(metric[5m])[10m:1m])",
+        UNIX_EPOCH.checked_add(Duration::from_secs(630)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(1230)).unwrap(),
+        Duration::from_secs(300),
+        Duration::from_secs(1),
+        "+---------------------+---------------------------------------------------------+-------+\
+        \n| ts                  | prom_max_over_time(ts_range,prom_rate(ts_range,val,ts)) | host  |\
+        \n+---------------------+---------------------------------------------------------+-------+\
+        \n| 1970-01-01T00:10:30 | 1.0                                                     | host1 |\
+        \n| 1970-01-01T00:15:30 | 2.0                                                     | host1 |\
+        \n| 1970-01-01T00:20:30 | 2.0                                                     | host1 |\
+        \n+---------------------+---------------------------------------------------------+-------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn subquery_max_over_rate_offset(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    // each range ends 5m earlier, the one at 00:15:30 no longer covers the faster rate
+    create_insert_query_assert(
+        instance,
+        SUBQUERY_CREATE_TABLE,
+        SUBQUERY_INSERT_DATA,
+        "max_over_time(rate(metric[5m])[10m:1m] offset 5m)",
+        UNIX_EPOCH.checked_add(Duration::from_secs(630)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(1230)).unwrap(),
+        Duration::from_secs(300),
+        Duration::from_secs(1),
+        "+---------------------+---------------------------------------------------------+-------+\
+        \n| ts                  | prom_max_over_time(ts_range,prom_rate(ts_range,val,ts)) | host  |\
+        \n+---------------------+---------------------------------------------------------+-------+\
+        \n| 1970-01-01T00:10:30 | 1.0                                                     | host1 |\
+        \n| 1970-01-01T00:15:30 | 1.0                                                     | host1 |\
+        \n| 1970-01-01T00:20:30 | 2.0                                                     | host1 |\
+        \n+---------------------+---------------------------------------------------------+-------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn subquery_aligned_steps(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    // the inner selector is evaluated at whole minutes, not at 30s past them like the query,
+    // so each range of 2m sums up exactly two samples
+    create_insert_query_assert(
+        instance,
+        SUBQUERY_CREATE_TABLE,
+        SUBQUERY_INSERT_DATA,
+        "sum_over_time(metric[2m:1m])",
+        UNIX_EPOCH.checked_add(Duration::from_secs(90)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(270)).unwrap(),
+        Duration::from_secs(60),
+        Duration::from_secs(1),
+        "+---------------------+----------------------------------+-------+\
+        \n| ts                  | prom_sum_over_time(ts_range,val) | host  |\
+        \n+---------------------+----------------------------------+-------+\
+        \n| 1970-01-01T00:01:30 | 2060.0                           | host1 |\
+        \n| 1970-01-01T00:02:30 | 2180.0                           | host1 |\
+        \n| 1970-01-01T00:03:30 | 2300.0                           | host1 |\
+        \n| 1970-01-01T00:04:30 | 2420.0                           | host1 |\
+        \n+---------------------+----------------------------------+-------+",
+    )
+    .await;
+}
+
+// This is not derived from prometheus
+#[apply(standalone_instance_case)]
+async fn subquery_aligned_steps_offset(instance: Arc<dyn MockInstance>) {
+    let instance = instance.frontend();
+
+    // the steps are aligned before being shifted by the offset, so the samples at whole
+    // minutes move to 30s past them, and each range of 2m but the first sums up three samples
+    create_insert_query_assert(
+        instance,
+        SUBQUERY_CREATE_TABLE,
+        SUBQUERY_INSERT_DATA,
+        "sum_over_time(metric[2m:1m] offset 30s)",
+        UNIX_EPOCH.checked_add(Duration::from_secs(90)).unwrap(),
+        UNIX_EPOCH.checked_add(Duration::from_secs(270)).unwrap(),
+        Duration::from_secs(60),
+        Duration::from_secs(1),
+        "+---------------------+----------------------------------+-------+\
+        \n| ts                  | prom_sum_over_time(ts_range,val) | host  |\
+        \n+---------------------+----------------------------------+-------+\
+        \n| 1970-01-01T00:01:30 | 2060.0                           | host1 |\
+        \n| 1970-01-01T00:02:30 | 3180.0                           | host1 |\
+        \n| 1970-01-01T00:03:30 | 3360.0                           | host1 |\
+        \n| 1970-01-01T00:04:30 | 3540.0                           | host1 |\
+        \n+---------------------+----------------------------------+-------+",
+    )
+    .await;
+}
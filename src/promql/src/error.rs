@@ -25,6 +25,9 @@ pub enum Error {
     #[snafu(display("Unsupported expr type: {}", name))]
     UnsupportedExpr { name: String, location: Location },
 
+    #[snafu(display("Unsupported feature: {}", feat))]
+    NotSupported { feat: String, location: Location },
+
     #[snafu(display("Unexpected token: {:?}", token))]
     UnexpectedToken {
         token: TokenType,
@@ -98,6 +101,20 @@ pub enum Error {
 
     #[snafu(display("Cannot find column {col}"))]
     ColumnNotFound { col: String, location: Location },
+
+    #[snafu(display("Invalid argument of function {fn_name}: {desc}"))]
+    InvalidFunctionArgument {
+        fn_name: String,
+        desc: String,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid regular expression {regex}: {source}"))]
+    InvalidRegex {
+        regex: String,
+        source: regex::Error,
+        location: Location,
+    },
 }
 
 impl ErrorExt for Error {
@@ -112,7 +129,11 @@ impl ErrorExt for Error {
             | ExpectExpr { .. }
             | ExpectRangeSelector { .. }
             | ZeroRangeSelector { .. }
-            | ColumnNotFound { .. } => StatusCode::InvalidArguments,
+            | ColumnNotFound { .. }
+            | InvalidFunctionArgument { .. }
+            | InvalidRegex { .. } => StatusCode::InvalidArguments,
+
+            NotSupported { .. } => StatusCode::Unsupported,
 
             UnknownTable { .. }
             | DataFusionPlanning { .. }
@@ -12,19 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod absent;
 mod empty_metric;
 mod instant_manipulate;
 mod normalize;
 mod planner;
 mod range_manipulate;
+mod scalar_calculate;
 mod series_divide;
+mod vector_aggregate;
+mod vector_match;
 
+pub use absent::{Absent, AbsentExec, ABSENT_VALUE_COLUMN};
 use datafusion::arrow::datatypes::{ArrowPrimitiveType, TimestampMillisecondType};
 pub use empty_metric::{EmptyMetric, EmptyMetricExec, EmptyMetricStream};
 pub use instant_manipulate::{InstantManipulate, InstantManipulateExec, InstantManipulateStream};
 pub use normalize::{SeriesNormalize, SeriesNormalizeExec, SeriesNormalizeStream};
 pub use planner::PromExtensionPlanner;
 pub use range_manipulate::{RangeManipulate, RangeManipulateExec, RangeManipulateStream};
+pub use scalar_calculate::{ScalarCalculate, ScalarCalculateExec};
 pub use series_divide::{SeriesDivide, SeriesDivideExec, SeriesDivideStream};
+pub use vector_aggregate::{VectorAggregate, VectorAggregateExec, VectorAggregateOp, LE_LABEL};
+pub use vector_match::{
+    MatchCardinality, MatchingLabels, OperandColumns, VectorMatch, VectorMatchExec,
+    VectorMatchModifier, VectorMatchOp,
+};
 
 pub(crate) type Millisecond = <TimestampMillisecondType as ArrowPrimitiveType>::Native;
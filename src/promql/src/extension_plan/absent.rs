@@ -0,0 +1,351 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, Float64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::{DFField, DFSchema, DFSchemaRef, Result as DataFusionResult, Statistics};
+use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datatypes::arrow::array::TimestampMillisecondArray;
+use datatypes::arrow::datatypes::SchemaRef;
+use datatypes::arrow::record_batch::RecordBatch;
+use futures::stream;
+
+use crate::extension_plan::vector_match::cast_column;
+use crate::extension_plan::Millisecond;
+
+/// Name of the value column generated by [Absent].
+pub const ABSENT_VALUE_COLUMN: &str = "value";
+
+/// Implements `absent()` in PromQL.
+///
+/// Generates a sample with value 1 at each timestamp in the range where the input has
+/// no sample. The generated samples carry the given constant labels, which are derived
+/// from the equality matchers of the input selector.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Absent {
+    start: Millisecond,
+    end: Millisecond,
+    interval: Millisecond,
+    time_index: String,
+    labels: Vec<(String, String)>,
+    input: LogicalPlan,
+    output_schema: DFSchemaRef,
+}
+
+impl Absent {
+    pub fn try_new(
+        start: Millisecond,
+        end: Millisecond,
+        interval: Millisecond,
+        time_index: String,
+        labels: Vec<(String, String)>,
+        input: LogicalPlan,
+    ) -> DataFusionResult<Self> {
+        let time_field = input.schema().field_with_unqualified_name(&time_index)?;
+        let mut fields = vec![DFField::new(
+            time_field.qualifier().cloned(),
+            &time_index,
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        )];
+        fields.extend(labels.iter().map(|(name, _)| {
+            DFField::new(time_field.qualifier().cloned(), name, DataType::Utf8, false)
+        }));
+        fields.push(DFField::new(
+            time_field.qualifier().cloned(),
+            ABSENT_VALUE_COLUMN,
+            DataType::Float64,
+            true,
+        ));
+        let output_schema = Arc::new(DFSchema::new_with_metadata(fields, HashMap::new())?);
+
+        Ok(Self {
+            start,
+            end,
+            interval,
+            time_index,
+            labels,
+            input,
+            output_schema,
+        })
+    }
+
+    pub fn to_execution_plan(&self, exec_input: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        Arc::new(AbsentExec {
+            start: self.start,
+            end: self.end,
+            interval: self.interval,
+            time_index: self.time_index.clone(),
+            labels: self.labels.clone(),
+            input: exec_input,
+            output_schema: Arc::new(self.output_schema.as_ref().into()),
+            metric: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl UserDefinedLogicalNodeCore for Absent {
+    fn name(&self) -> &str {
+        "Absent"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.output_schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "PromAbsent: range=[{}..{}], interval=[{}], time index=[{}], labels={:?}",
+            self.start, self.end, self.interval, self.time_index, self.labels
+        )
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        assert!(!inputs.is_empty());
+
+        Self {
+            start: self.start,
+            end: self.end,
+            interval: self.interval,
+            time_index: self.time_index.clone(),
+            labels: self.labels.clone(),
+            input: inputs[0].clone(),
+            output_schema: self.output_schema.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AbsentExec {
+    start: Millisecond,
+    end: Millisecond,
+    interval: Millisecond,
+    time_index: String,
+    labels: Vec<(String, String)>,
+    input: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    metric: ExecutionPlanMetricsSet,
+}
+
+impl ExecutionPlan for AbsentExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.output_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        assert!(!children.is_empty());
+        Ok(Arc::new(Self {
+            start: self.start,
+            end: self.end,
+            interval: self.interval,
+            time_index: self.time_index.clone(),
+            labels: self.labels.clone(),
+            input: children[0].clone(),
+            output_schema: self.output_schema.clone(),
+            metric: self.metric.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        let output = absent(
+            self.start,
+            self.end,
+            self.interval,
+            self.time_index.clone(),
+            self.labels.clone(),
+            self.input.clone(),
+            self.output_schema.clone(),
+            context,
+            BaselineMetrics::new(&self.metric, partition),
+        );
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.output_schema.clone(),
+            stream::once(output),
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "PromAbsentExec: range=[{}..{}], interval=[{}], time index=[{}], labels={:?}",
+                self.start, self.end, self.interval, self.time_index, self.labels
+            ),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metric.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn absent(
+    start: Millisecond,
+    end: Millisecond,
+    interval: Millisecond,
+    time_index: String,
+    labels: Vec<(String, String)>,
+    input: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    context: Arc<TaskContext>,
+    metric: BaselineMetrics,
+) -> DataFusionResult<RecordBatch> {
+    let batches = datafusion::physical_plan::collect(input, context).await?;
+
+    let _timer = metric.elapsed_compute().timer();
+    let mut present = HashSet::new();
+    for batch in &batches {
+        let timestamps = cast_column::<TimestampMillisecondArray>(
+            batch,
+            &time_index,
+            &DataType::Timestamp(TimeUnit::Millisecond, None),
+        )?;
+        present.extend(timestamps.iter().flatten());
+    }
+
+    let output_timestamps = (start..=end)
+        .step_by(interval.max(1) as usize)
+        .filter(|ts| !present.contains(ts))
+        .collect::<Vec<_>>();
+    let num_rows = output_timestamps.len();
+    let mut columns: Vec<ArrayRef> =
+        vec![Arc::new(TimestampMillisecondArray::from(output_timestamps))];
+    for (_, value) in &labels {
+        columns.push(Arc::new(StringArray::from(vec![value.as_str(); num_rows])));
+    }
+    columns.push(Arc::new(Float64Array::from(vec![1.0; num_rows])));
+
+    let output = RecordBatch::try_new(output_schema, columns)?;
+    metric.record_output(output.num_rows());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use datafusion::logical_expr::EmptyRelation;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn absent_samples() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("value", DataType::Float64, true),
+        ]));
+        // samples at 0s and 2s
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "a"])),
+                Arc::new(TimestampMillisecondArray::from(vec![0, 0, 2000])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])),
+            ],
+        )
+        .unwrap();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap());
+        let input_plan = LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(DFSchema::try_from_qualified_schema("m", &input.schema()).unwrap()),
+        });
+        let absent = Absent::try_new(
+            0,
+            3000,
+            1000,
+            "timestamp".to_string(),
+            vec![("job".to_string(), "api".to_string())],
+            input_plan,
+        )
+        .unwrap();
+
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(
+            absent.to_execution_plan(input),
+            session_context.task_ctx(),
+        )
+        .await
+        .unwrap();
+        let result = datatypes::arrow::util::pretty::pretty_format_batches(&result)
+            .unwrap()
+            .to_string();
+        let expected = String::from(
+            "+---------------------+-----+-------+\
+            \n| timestamp           | job | value |\
+            \n+---------------------+-----+-------+\
+            \n| 1970-01-01T00:00:01 | api | 1.0   |\
+            \n| 1970-01-01T00:00:03 | api | 1.0   |\
+            \n+---------------------+-----+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+}
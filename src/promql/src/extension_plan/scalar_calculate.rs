@@ -0,0 +1,374 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::arrow::array::Float64Array;
+use datafusion::arrow::compute;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::{
+    DFField, DFSchema, DFSchemaRef, OwnedTableReference, Result as DataFusionResult, Statistics,
+};
+use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datatypes::arrow::array::{Array, TimestampMillisecondArray};
+use datatypes::arrow::datatypes::SchemaRef;
+use datatypes::arrow::record_batch::RecordBatch;
+use futures::stream;
+
+use crate::extension_plan::vector_match::cast_column;
+use crate::extension_plan::Millisecond;
+
+/// Converts a vector to a scalar like `scalar()` in PromQL.
+///
+/// Generates one sample at each timestamp in the range, whose value is the only sample of
+/// the input at that timestamp, or NaN if there are no or multiple samples.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ScalarCalculate {
+    start: Millisecond,
+    end: Millisecond,
+    interval: Millisecond,
+    time_index: String,
+    field_column: String,
+    input: LogicalPlan,
+    output_schema: DFSchemaRef,
+}
+
+impl ScalarCalculate {
+    pub fn try_new(
+        start: Millisecond,
+        end: Millisecond,
+        interval: Millisecond,
+        time_index: String,
+        field_column: String,
+        input: LogicalPlan,
+    ) -> DataFusionResult<Self> {
+        let input_schema = input.schema();
+        let time_field = input_schema.field_with_unqualified_name(&time_index)?;
+        let field = input_schema.field_with_unqualified_name(&field_column)?;
+        let output_schema = Arc::new(DFSchema::new_with_metadata(
+            vec![
+                DFField::new(
+                    time_field.qualifier().cloned(),
+                    &time_index,
+                    DataType::Timestamp(TimeUnit::Millisecond, None),
+                    false,
+                ),
+                DFField::new(
+                    None::<OwnedTableReference>,
+                    &format!("scalar({})", field.qualified_name()),
+                    DataType::Float64,
+                    true,
+                ),
+            ],
+            HashMap::new(),
+        )?);
+
+        Ok(Self {
+            start,
+            end,
+            interval,
+            time_index,
+            field_column,
+            input,
+            output_schema,
+        })
+    }
+
+    /// Name of the generated value column.
+    pub fn field_column(&self) -> String {
+        self.output_schema.field(1).name().clone()
+    }
+
+    pub fn to_execution_plan(&self, exec_input: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        Arc::new(ScalarCalculateExec {
+            start: self.start,
+            end: self.end,
+            interval: self.interval,
+            time_index: self.time_index.clone(),
+            field_column: self.field_column.clone(),
+            input: exec_input,
+            output_schema: Arc::new(self.output_schema.as_ref().into()),
+            metric: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl UserDefinedLogicalNodeCore for ScalarCalculate {
+    fn name(&self) -> &str {
+        "ScalarCalculate"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.output_schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "PromScalarCalculate: range=[{}..{}], interval=[{}], time index=[{}], value=[{}]",
+            self.start, self.end, self.interval, self.time_index, self.field_column
+        )
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        assert!(!inputs.is_empty());
+
+        Self {
+            start: self.start,
+            end: self.end,
+            interval: self.interval,
+            time_index: self.time_index.clone(),
+            field_column: self.field_column.clone(),
+            input: inputs[0].clone(),
+            output_schema: self.output_schema.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScalarCalculateExec {
+    start: Millisecond,
+    end: Millisecond,
+    interval: Millisecond,
+    time_index: String,
+    field_column: String,
+    input: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    metric: ExecutionPlanMetricsSet,
+}
+
+impl ExecutionPlan for ScalarCalculateExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.output_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        assert!(!children.is_empty());
+        Ok(Arc::new(Self {
+            start: self.start,
+            end: self.end,
+            interval: self.interval,
+            time_index: self.time_index.clone(),
+            field_column: self.field_column.clone(),
+            input: children[0].clone(),
+            output_schema: self.output_schema.clone(),
+            metric: self.metric.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        let output = calculate(
+            self.start,
+            self.end,
+            self.interval,
+            self.time_index.clone(),
+            self.field_column.clone(),
+            self.input.clone(),
+            self.output_schema.clone(),
+            context,
+            BaselineMetrics::new(&self.metric, partition),
+        );
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.output_schema.clone(),
+            stream::once(output),
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "PromScalarCalculateExec: range=[{}..{}], interval=[{}], time index=[{}], value=[{}]",
+                self.start, self.end, self.interval, self.time_index, self.field_column
+            ),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metric.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn calculate(
+    start: Millisecond,
+    end: Millisecond,
+    interval: Millisecond,
+    time_index: String,
+    field_column: String,
+    input: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    context: Arc<TaskContext>,
+    metric: BaselineMetrics,
+) -> DataFusionResult<RecordBatch> {
+    let batches = datafusion::physical_plan::collect(input.clone(), context).await?;
+
+    let _timer = metric.elapsed_compute().timer();
+    let batch = compute::concat_batches(&input.schema(), &batches)?;
+    let timestamps = cast_column::<TimestampMillisecondArray>(
+        &batch,
+        &time_index,
+        &DataType::Timestamp(TimeUnit::Millisecond, None),
+    )?;
+    let values = cast_column::<Float64Array>(&batch, &field_column, &DataType::Float64)?;
+
+    // the number of samples and the last sample of each timestamp
+    let mut samples: HashMap<Millisecond, (usize, f64)> = HashMap::new();
+    for row in 0..batch.num_rows() {
+        if values.is_null(row) {
+            continue;
+        }
+        let sample = samples.entry(timestamps.value(row)).or_default();
+        sample.0 += 1;
+        sample.1 = values.value(row);
+    }
+
+    let output_timestamps = (start..=end)
+        .step_by(interval.max(1) as usize)
+        .collect::<Vec<_>>();
+    let output_values = output_timestamps
+        .iter()
+        .map(|ts| match samples.get(ts) {
+            Some((1, value)) => *value,
+            _ => f64::NAN,
+        })
+        .collect::<Vec<_>>();
+    let output = RecordBatch::try_new(
+        output_schema,
+        vec![
+            Arc::new(TimestampMillisecondArray::from(output_timestamps)),
+            Arc::new(Float64Array::from(output_values)),
+        ],
+    )?;
+    metric.record_output(output.num_rows());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::array::StringArray;
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use datafusion::logical_expr::EmptyRelation;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn scalar_calculate() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("value", DataType::Float64, true),
+        ]));
+        // one sample at 0s, two samples at 1s and no sample at 2s
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "a", "b"])),
+                Arc::new(TimestampMillisecondArray::from(vec![0, 1000, 1000])),
+                Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])),
+            ],
+        )
+        .unwrap();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap());
+        let input_plan = LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(DFSchema::try_from_qualified_schema("m", &input.schema()).unwrap()),
+        });
+        let scalar = ScalarCalculate::try_new(
+            0,
+            2000,
+            1000,
+            "timestamp".to_string(),
+            "value".to_string(),
+            input_plan,
+        )
+        .unwrap();
+        assert_eq!(scalar.field_column(), "scalar(m.value)");
+
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(
+            scalar.to_execution_plan(input),
+            session_context.task_ctx(),
+        )
+        .await
+        .unwrap();
+        let result = datatypes::arrow::util::pretty::pretty_format_batches(&result)
+            .unwrap()
+            .to_string();
+        let expected = String::from(
+            "+---------------------+-----------------+\
+            \n| timestamp           | scalar(m.value) |\
+            \n+---------------------+-----------------+\
+            \n| 1970-01-01T00:00:00 | 1.0             |\
+            \n| 1970-01-01T00:00:01 | NaN             |\
+            \n| 1970-01-01T00:00:02 | NaN             |\
+            \n+---------------------+-----------------+",
+        );
+        assert_eq!(result, expected);
+    }
+}
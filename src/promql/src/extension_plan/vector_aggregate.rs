@@ -0,0 +1,922 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, Float64Array, StringArray, UInt32Array};
+use datafusion::arrow::compute;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::{
+    DFField, DFSchema, DFSchemaRef, OwnedTableReference, Result as DataFusionResult, Statistics,
+};
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datatypes::arrow::array::{Array, TimestampMillisecondArray};
+use datatypes::arrow::datatypes::SchemaRef;
+use datatypes::arrow::error::Result as ArrowResult;
+use datatypes::arrow::record_batch::RecordBatch;
+use datatypes::value::OrderedF64;
+use futures::stream;
+
+use crate::extension_plan::vector_match::cast_column;
+use crate::extension_plan::Millisecond;
+
+/// PromQL aggregations that select series or generate new series, which can't be expressed
+/// by DataFusion's aggregate functions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VectorAggregateOp {
+    /// `topk(k, ...)`, the series with the largest `k` values.
+    TopK(i64),
+    /// `bottomk(k, ...)`, the series with the smallest `k` values.
+    BottomK(i64),
+    /// `quantile(φ, ...)`, the φ-quantile of values, `0 <= φ <= 1`.
+    Quantile(OrderedF64),
+    /// `count_values("label", ...)`, the number of series of each value, the values are
+    /// put into the given label.
+    CountValues(String),
+    /// `histogram_quantile(φ, ...)`, the φ-quantile of the histogram made up of the bucket
+    /// series in each group, whose upper bounds are in the [LE_LABEL] label.
+    HistogramQuantile(OrderedF64),
+}
+
+impl VectorAggregateOp {
+    /// Name of the value column generated from the input value column `field`.
+    fn value_column_name(&self, field: &str) -> String {
+        match self {
+            Self::TopK(_) | Self::BottomK(_) => field.to_string(),
+            Self::Quantile(q) => format!("quantile({q}, {field})"),
+            Self::CountValues(label) => format!("count_values({label}, {field})"),
+            Self::HistogramQuantile(q) => format!("histogram_quantile({q}, {field})"),
+        }
+    }
+}
+
+/// The label of bucket series which holds the upper bound of the bucket.
+pub const LE_LABEL: &str = "le";
+
+impl Display for VectorAggregateOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TopK(k) => write!(f, "topk({k})"),
+            Self::BottomK(k) => write!(f, "bottomk({k})"),
+            Self::Quantile(q) => write!(f, "quantile({q})"),
+            Self::CountValues(label) => write!(f, "count_values({label})"),
+            Self::HistogramQuantile(q) => write!(f, "histogram_quantile({q})"),
+        }
+    }
+}
+
+/// Aggregates the samples of each group at each timestamp with a [VectorAggregateOp].
+///
+/// Samples are grouped by the timestamp and `group_tags`. `topk` and `bottomk` keep the
+/// selected samples as they are, `quantile` generates one sample for each group, and
+/// `count_values` generates one sample for each distinct value in each group.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct VectorAggregate {
+    op: VectorAggregateOp,
+    tag_columns: Vec<String>,
+    group_tags: Vec<String>,
+    time_index: String,
+    field_columns: Vec<String>,
+    input: LogicalPlan,
+    output_schema: DFSchemaRef,
+}
+
+impl VectorAggregate {
+    pub fn try_new(
+        op: VectorAggregateOp,
+        tag_columns: Vec<String>,
+        group_tags: Vec<String>,
+        time_index: String,
+        field_columns: Vec<String>,
+        input: LogicalPlan,
+    ) -> DataFusionResult<Self> {
+        if matches!(
+            op,
+            VectorAggregateOp::TopK(_)
+                | VectorAggregateOp::BottomK(_)
+                | VectorAggregateOp::CountValues(_)
+        ) && field_columns.len() != 1
+        {
+            return Err(DataFusionError::Plan(format!(
+                "{op} requires exactly one value column, found {field_columns:?}"
+            )));
+        }
+
+        let input_schema = input.schema();
+        let output_schema = match &op {
+            VectorAggregateOp::TopK(_) | VectorAggregateOp::BottomK(_) => input_schema.clone(),
+            VectorAggregateOp::Quantile(_) | VectorAggregateOp::HistogramQuantile(_) => {
+                let mut fields = group_tags
+                    .iter()
+                    .chain(Some(&time_index))
+                    .map(|name| Ok(input_schema.field_with_unqualified_name(name)?.clone()))
+                    .collect::<DataFusionResult<Vec<_>>>()?;
+                for field in &field_columns {
+                    let field = input_schema.field_with_unqualified_name(field)?;
+                    fields.push(DFField::new(
+                        None::<OwnedTableReference>,
+                        &op.value_column_name(&field.qualified_name()),
+                        DataType::Float64,
+                        true,
+                    ));
+                }
+                Arc::new(DFSchema::new_with_metadata(fields, HashMap::new())?)
+            }
+            VectorAggregateOp::CountValues(label) => {
+                let mut fields = group_tags
+                    .iter()
+                    .filter(|tag| *tag != label)
+                    .map(|name| Ok(input_schema.field_with_unqualified_name(name)?.clone()))
+                    .collect::<DataFusionResult<Vec<_>>>()?;
+                let time_field = input_schema.field_with_unqualified_name(&time_index)?;
+                fields.push(DFField::new(
+                    time_field.qualifier().cloned(),
+                    label,
+                    DataType::Utf8,
+                    true,
+                ));
+                fields.push(time_field.clone());
+                let field = input_schema.field_with_unqualified_name(&field_columns[0])?;
+                fields.push(DFField::new(
+                    None::<OwnedTableReference>,
+                    &op.value_column_name(&field.qualified_name()),
+                    DataType::Float64,
+                    true,
+                ));
+                Arc::new(DFSchema::new_with_metadata(fields, HashMap::new())?)
+            }
+        };
+
+        Ok(Self {
+            op,
+            tag_columns,
+            group_tags,
+            time_index,
+            field_columns,
+            input,
+            output_schema,
+        })
+    }
+
+    /// Tag columns of the output.
+    pub fn tag_columns(&self) -> Vec<String> {
+        match &self.op {
+            VectorAggregateOp::TopK(_) | VectorAggregateOp::BottomK(_) => self.tag_columns.clone(),
+            VectorAggregateOp::Quantile(_) | VectorAggregateOp::HistogramQuantile(_) => {
+                self.group_tags.clone()
+            }
+            VectorAggregateOp::CountValues(label) => self
+                .group_tags
+                .iter()
+                .filter(|tag| *tag != label)
+                .chain(Some(label))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Field columns of the output.
+    pub fn field_columns(&self) -> Vec<String> {
+        match &self.op {
+            VectorAggregateOp::TopK(_) | VectorAggregateOp::BottomK(_) => {
+                self.field_columns.clone()
+            }
+            _ => {
+                // generated value columns are the last ones
+                let fields = self.output_schema.fields();
+                fields[fields.len() - self.field_columns.len()..]
+                    .iter()
+                    .map(|field| field.name().clone())
+                    .collect()
+            }
+        }
+    }
+
+    pub fn to_execution_plan(&self, exec_input: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        Arc::new(VectorAggregateExec {
+            op: self.op.clone(),
+            group_tags: self.group_tags.clone(),
+            time_index: self.time_index.clone(),
+            field_columns: self.field_columns.clone(),
+            input: exec_input,
+            output_schema: Arc::new(self.output_schema.as_ref().into()),
+            metric: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl UserDefinedLogicalNodeCore for VectorAggregate {
+    fn name(&self) -> &str {
+        "VectorAggregate"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.output_schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PromVectorAggregate: op=[{}], group by={:?}, time index=[{}], values={:?}",
+            self.op, self.group_tags, self.time_index, self.field_columns
+        )
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        assert!(!inputs.is_empty());
+
+        Self {
+            op: self.op.clone(),
+            tag_columns: self.tag_columns.clone(),
+            group_tags: self.group_tags.clone(),
+            time_index: self.time_index.clone(),
+            field_columns: self.field_columns.clone(),
+            input: inputs[0].clone(),
+            output_schema: self.output_schema.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorAggregateExec {
+    op: VectorAggregateOp,
+    group_tags: Vec<String>,
+    time_index: String,
+    field_columns: Vec<String>,
+    input: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    metric: ExecutionPlanMetricsSet,
+}
+
+impl ExecutionPlan for VectorAggregateExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.output_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        assert!(!children.is_empty());
+        Ok(Arc::new(Self {
+            op: self.op.clone(),
+            group_tags: self.group_tags.clone(),
+            time_index: self.time_index.clone(),
+            field_columns: self.field_columns.clone(),
+            input: children[0].clone(),
+            output_schema: self.output_schema.clone(),
+            metric: self.metric.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        let aggregator = Aggregator {
+            op: self.op.clone(),
+            group_tags: self.group_tags.clone(),
+            time_index: self.time_index.clone(),
+            field_columns: self.field_columns.clone(),
+            output_schema: self.output_schema.clone(),
+        };
+        let output = aggregator.aggregate(
+            self.input.clone(),
+            context,
+            BaselineMetrics::new(&self.metric, partition),
+        );
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.output_schema.clone(),
+            stream::once(output),
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "PromVectorAggregateExec: op=[{}], group by={:?}, time index=[{}], values={:?}",
+                self.op, self.group_tags, self.time_index, self.field_columns
+            ),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metric.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+struct Aggregator {
+    op: VectorAggregateOp,
+    group_tags: Vec<String>,
+    time_index: String,
+    field_columns: Vec<String>,
+    output_schema: SchemaRef,
+}
+
+impl Aggregator {
+    /// Samples of a group may be in any batch, so the input is collected before aggregating.
+    async fn aggregate(
+        self,
+        input: Arc<dyn ExecutionPlan>,
+        context: Arc<TaskContext>,
+        metric: BaselineMetrics,
+    ) -> DataFusionResult<RecordBatch> {
+        let batches = datafusion::physical_plan::collect(input.clone(), context).await?;
+
+        let _timer = metric.elapsed_compute().timer();
+        let batch = compute::concat_batches(&input.schema(), &batches)?;
+        let output = self.aggregate_batch(&batch)?;
+        metric.record_output(output.num_rows());
+        Ok(output)
+    }
+
+    fn aggregate_batch(&self, batch: &RecordBatch) -> DataFusionResult<RecordBatch> {
+        let groups = self.group_rows(batch)?;
+        let fields = self
+            .field_columns
+            .iter()
+            .map(|field| cast_column::<Float64Array>(batch, field, &DataType::Float64))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+
+        match &self.op {
+            VectorAggregateOp::TopK(k) | VectorAggregateOp::BottomK(k) => {
+                let values = &fields[0];
+                let is_top = matches!(self.op, VectorAggregateOp::TopK(_));
+                let k = usize::try_from(*k).unwrap_or(0);
+                let mut indices = Vec::new();
+                for rows in groups {
+                    let mut rows = rows
+                        .into_iter()
+                        .filter(|row| values.is_valid(*row))
+                        .collect::<Vec<_>>();
+                    // NaN is regarded as the least value by topk, and the largest by bottomk
+                    rows.sort_by(|a, b| {
+                        let (a, b) = (values.value(*a), values.value(*b));
+                        let ordering = if is_top {
+                            b.partial_cmp(&a)
+                        } else {
+                            a.partial_cmp(&b)
+                        };
+                        ordering.unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+                    });
+                    indices.extend(rows.into_iter().take(k).map(|row| row as u32));
+                }
+                let output = take_columns(batch.columns(), indices)?;
+                Ok(RecordBatch::try_new(self.output_schema.clone(), output)?)
+            }
+            VectorAggregateOp::Quantile(q) => {
+                let mut indices = Vec::with_capacity(groups.len());
+                let mut quantiles = vec![Vec::with_capacity(groups.len()); fields.len()];
+                for rows in groups {
+                    indices.push(rows[0] as u32);
+                    for (values, quantiles) in fields.iter().zip(quantiles.iter_mut()) {
+                        let mut samples = rows
+                            .iter()
+                            .filter(|row| values.is_valid(**row))
+                            .map(|row| values.value(*row))
+                            .collect::<Vec<_>>();
+                        quantiles.push(quantile(q.0, &mut samples));
+                    }
+                }
+                let mut output = take_columns(&self.group_columns(batch)?, indices)?;
+                output.extend(
+                    quantiles
+                        .into_iter()
+                        .map(|values| Arc::new(Float64Array::from(values)) as ArrayRef),
+                );
+                Ok(RecordBatch::try_new(self.output_schema.clone(), output)?)
+            }
+            VectorAggregateOp::HistogramQuantile(q) => {
+                let upper_bounds = cast_column::<StringArray>(batch, LE_LABEL, &DataType::Utf8)?;
+                let upper_bounds = (0..batch.num_rows())
+                    .map(|row| {
+                        upper_bounds
+                            .is_valid(row)
+                            .then(|| upper_bounds.value(row).parse::<f64>().ok())
+                            .flatten()
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut indices = Vec::with_capacity(groups.len());
+                let mut quantiles = vec![Vec::with_capacity(groups.len()); fields.len()];
+                for rows in groups {
+                    // series without a valid upper bound are ignored
+                    let rows = rows
+                        .into_iter()
+                        .filter(|row| upper_bounds[*row].is_some())
+                        .collect::<Vec<_>>();
+                    if rows.is_empty() {
+                        continue;
+                    }
+                    indices.push(rows[0] as u32);
+                    for (values, quantiles) in fields.iter().zip(quantiles.iter_mut()) {
+                        let mut buckets = rows
+                            .iter()
+                            .filter(|row| values.is_valid(**row))
+                            .filter_map(|row| Some((upper_bounds[*row]?, values.value(*row))))
+                            .collect::<Vec<_>>();
+                        quantiles.push(bucket_quantile(q.0, &mut buckets));
+                    }
+                }
+                let mut output = take_columns(&self.group_columns(batch)?, indices)?;
+                output.extend(
+                    quantiles
+                        .into_iter()
+                        .map(|values| Arc::new(Float64Array::from(values)) as ArrayRef),
+                );
+                Ok(RecordBatch::try_new(self.output_schema.clone(), output)?)
+            }
+            VectorAggregateOp::CountValues(label) => {
+                let values = &fields[0];
+                let mut indices = Vec::new();
+                let mut labels = Vec::new();
+                let mut counts = Vec::new();
+                for rows in groups {
+                    let mut value_counts: Vec<(String, f64)> = Vec::new();
+                    for row in rows.iter().filter(|row| values.is_valid(**row)) {
+                        let value = format_value(values.value(*row));
+                        match value_counts.iter_mut().find(|(v, _)| *v == value) {
+                            Some((_, count)) => *count += 1.0,
+                            None => value_counts.push((value, 1.0)),
+                        }
+                    }
+                    for (value, count) in value_counts {
+                        indices.push(rows[0] as u32);
+                        labels.push(value);
+                        counts.push(count);
+                    }
+                }
+                let schema = batch.schema();
+                let columns = self
+                    .group_tags
+                    .iter()
+                    .filter(|tag| *tag != label)
+                    .chain(Some(&self.time_index))
+                    .map(|name| Ok(batch.column(schema.index_of(name)?).clone()))
+                    .collect::<DataFusionResult<Vec<_>>>()?;
+                let mut output = take_columns(&columns, indices)?;
+                // the generated label goes right before the time index
+                output.insert(
+                    output.len() - 1,
+                    Arc::new(StringArray::from(labels)) as ArrayRef,
+                );
+                output.push(Arc::new(Float64Array::from(counts)));
+                Ok(RecordBatch::try_new(self.output_schema.clone(), output)?)
+            }
+        }
+    }
+
+    /// Groups the rows by the timestamp and group tags, in the order of their first rows.
+    fn group_rows(&self, batch: &RecordBatch) -> DataFusionResult<Vec<Vec<usize>>> {
+        let tags = self
+            .group_tags
+            .iter()
+            .map(|tag| cast_column::<StringArray>(batch, tag, &DataType::Utf8))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        let timestamps = cast_column::<TimestampMillisecondArray>(
+            batch,
+            &self.time_index,
+            &DataType::Timestamp(TimeUnit::Millisecond, None),
+        )?;
+
+        let mut group_indices: HashMap<(Millisecond, Vec<Option<&str>>), usize> = HashMap::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for row in 0..batch.num_rows() {
+            let key = (
+                timestamps.value(row),
+                tags.iter()
+                    .map(|tag| tag.is_valid(row).then(|| tag.value(row)))
+                    .collect(),
+            );
+            let index = *group_indices.entry(key).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[index].push(row);
+        }
+        Ok(groups)
+    }
+
+    /// Group tag columns and the time index column.
+    fn group_columns(&self, batch: &RecordBatch) -> DataFusionResult<Vec<ArrayRef>> {
+        let schema = batch.schema();
+        self.group_tags
+            .iter()
+            .chain(Some(&self.time_index))
+            .map(|name| Ok(batch.column(schema.index_of(name)?).clone()))
+            .collect()
+    }
+}
+
+/// Takes the rows at `indices` from each of `columns`.
+fn take_columns(columns: &[ArrayRef], indices: Vec<u32>) -> DataFusionResult<Vec<ArrayRef>> {
+    let indices = UInt32Array::from(indices);
+    Ok(columns
+        .iter()
+        .map(|column| compute::take(column, &indices, None))
+        .collect::<ArrowResult<Vec<_>>>()?)
+}
+
+/// Calculates the φ-quantile of `values` like Prometheus, which interpolates linearly
+/// between the two nearest ranks.
+fn quantile(q: f64, values: &mut [f64]) -> f64 {
+    if values.is_empty() || q.is_nan() {
+        return f64::NAN;
+    }
+    if q < 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if q > 1.0 {
+        return f64::INFINITY;
+    }
+    // NaN is regarded as the least value
+    values.sort_by(|a, b| {
+        a.partial_cmp(b)
+            .unwrap_or_else(|| b.is_nan().cmp(&a.is_nan()))
+    });
+
+    let n = values.len() as f64;
+    let rank = q * (n - 1.0);
+    let lower = rank.floor().max(0.0);
+    let upper = (lower + 1.0).min(n - 1.0);
+    let weight = rank - rank.floor();
+    values[lower as usize] * (1.0 - weight) + values[upper as usize] * weight
+}
+
+/// Calculates the φ-quantile from the `(upper bound, cumulative count)` of buckets like
+/// Prometheus, which assumes a linear distribution within the bucket containing the quantile.
+///
+/// The buckets must contain one with `+Inf` upper bound, otherwise NaN is returned. If the
+/// quantile falls into the highest bucket, the upper bound of the second highest bucket is
+/// returned.
+fn bucket_quantile(q: f64, buckets: &mut Vec<(f64, f64)>) -> f64 {
+    if q.is_nan() {
+        return f64::NAN;
+    }
+    if q < 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if q > 1.0 {
+        return f64::INFINITY;
+    }
+    buckets.sort_by(|a, b| {
+        a.0.partial_cmp(&b.0)
+            .unwrap_or_else(|| a.0.is_nan().cmp(&b.0.is_nan()))
+    });
+    match buckets.last() {
+        Some((upper_bound, _)) if *upper_bound == f64::INFINITY => {}
+        _ => return f64::NAN,
+    }
+
+    // merge buckets with the same upper bound
+    buckets.dedup_by(|bucket, prev| {
+        if bucket.0 == prev.0 {
+            prev.1 += bucket.1;
+            true
+        } else {
+            false
+        }
+    });
+    // counts may be decreasing due to precision issues or scraping inconsistency
+    let mut max = f64::NEG_INFINITY;
+    for (_, count) in buckets.iter_mut() {
+        max = max.max(*count);
+        *count = max;
+    }
+
+    if buckets.len() < 2 {
+        return f64::NAN;
+    }
+    let observations = buckets[buckets.len() - 1].1;
+    if observations == 0.0 {
+        return f64::NAN;
+    }
+    let mut rank = q * observations;
+    let b = buckets[..buckets.len() - 1]
+        .iter()
+        .position(|(_, count)| *count >= rank)
+        .unwrap_or(buckets.len() - 1);
+
+    if b == buckets.len() - 1 {
+        return buckets[buckets.len() - 2].0;
+    }
+    if b == 0 && buckets[0].0 <= 0.0 {
+        return buckets[0].0;
+    }
+    let (mut bucket_start, bucket_end, mut count) = (0.0, buckets[b].0, buckets[b].1);
+    if b > 0 {
+        bucket_start = buckets[b - 1].0;
+        count -= buckets[b - 1].1;
+        rank -= buckets[b - 1].1;
+    }
+    bucket_start + (bucket_end - bucket_start) * (rank / count)
+}
+
+/// Formats the value as a label like Prometheus.
+fn format_value(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use datafusion::logical_expr::EmptyRelation;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    fn prepare_input() -> Arc<MemoryExec> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new("job", DataType::Utf8, true),
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("value", DataType::Float64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d", "a", "b"])),
+                Arc::new(StringArray::from(vec!["x", "x", "y", "x", "x", "x"])),
+                Arc::new(TimestampMillisecondArray::from(vec![
+                    0, 0, 0, 0, 1000, 1000,
+                ])),
+                Arc::new(Float64Array::from(vec![1.0, 3.0, 2.0, f64::NAN, 5.0, 4.0])),
+            ],
+        )
+        .unwrap();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    async fn do_vector_aggregate_test(op: VectorAggregateOp, group_tags: &[&str]) -> String {
+        let input = prepare_input();
+        let input_plan = LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(DFSchema::try_from_qualified_schema("m", &input.schema()).unwrap()),
+        });
+        let aggregate = VectorAggregate::try_new(
+            op,
+            vec!["host".to_string(), "job".to_string()],
+            group_tags.iter().map(|tag| tag.to_string()).collect(),
+            "timestamp".to_string(),
+            vec!["value".to_string()],
+            input_plan,
+        )
+        .unwrap();
+        let exec = aggregate.to_execution_plan(input);
+
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(exec, session_context.task_ctx())
+            .await
+            .unwrap();
+        datatypes::arrow::util::pretty::pretty_format_batches(&result)
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn topk() {
+        let result = do_vector_aggregate_test(VectorAggregateOp::TopK(1), &["job"]).await;
+        let expected = String::from(
+            "+------+-----+---------------------+-------+\
+            \n| host | job | timestamp           | value |\
+            \n+------+-----+---------------------+-------+\
+            \n| b    | x   | 1970-01-01T00:00:00 | 3.0   |\
+            \n| c    | y   | 1970-01-01T00:00:00 | 2.0   |\
+            \n| a    | x   | 1970-01-01T00:00:01 | 5.0   |\
+            \n+------+-----+---------------------+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn bottomk() {
+        let result = do_vector_aggregate_test(VectorAggregateOp::BottomK(2), &["job"]).await;
+        let expected = String::from(
+            "+------+-----+---------------------+-------+\
+            \n| host | job | timestamp           | value |\
+            \n+------+-----+---------------------+-------+\
+            \n| a    | x   | 1970-01-01T00:00:00 | 1.0   |\
+            \n| b    | x   | 1970-01-01T00:00:00 | 3.0   |\
+            \n| c    | y   | 1970-01-01T00:00:00 | 2.0   |\
+            \n| b    | x   | 1970-01-01T00:00:01 | 4.0   |\
+            \n| a    | x   | 1970-01-01T00:00:01 | 5.0   |\
+            \n+------+-----+---------------------+-------+",
+        );
+        assert_eq!(result, expected);
+
+        // k less than 1 selects nothing
+        let result = do_vector_aggregate_test(VectorAggregateOp::BottomK(0), &["job"]).await;
+        let expected = String::from(
+            "+------+-----+-----------+-------+\
+            \n| host | job | timestamp | value |\
+            \n+------+-----+-----------+-------+\
+            \n+------+-----+-----------+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn quantile() {
+        let result = do_vector_aggregate_test(VectorAggregateOp::Quantile(0.5.into()), &[]).await;
+        let expected = String::from(
+            "+---------------------+------------------------+\
+            \n| timestamp           | quantile(0.5, m.value) |\
+            \n+---------------------+------------------------+\
+            \n| 1970-01-01T00:00:00 | 1.5                    |\
+            \n| 1970-01-01T00:00:01 | 4.5                    |\
+            \n+---------------------+------------------------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn quantile_edge_cases() {
+        assert!(super::quantile(0.5, &mut []).is_nan());
+        assert!(super::quantile(f64::NAN, &mut [1.0]).is_nan());
+        assert_eq!(super::quantile(-0.1, &mut [1.0]), f64::NEG_INFINITY);
+        assert_eq!(super::quantile(1.1, &mut [1.0]), f64::INFINITY);
+        assert_eq!(super::quantile(0.9, &mut [1.0]), 1.0);
+        assert_eq!(super::quantile(1.0, &mut [3.0, 1.0, 2.0]), 3.0);
+    }
+
+    #[tokio::test]
+    async fn count_values() {
+        let result =
+            do_vector_aggregate_test(VectorAggregateOp::CountValues("v".to_string()), &["job"])
+                .await;
+        let expected = String::from(
+            "+-----+-----+---------------------+--------------------------+\
+            \n| job | v   | timestamp           | count_values(v, m.value) |\
+            \n+-----+-----+---------------------+--------------------------+\
+            \n| x   | 1   | 1970-01-01T00:00:00 | 1.0                      |\
+            \n| x   | 3   | 1970-01-01T00:00:00 | 1.0                      |\
+            \n| x   | NaN | 1970-01-01T00:00:00 | 1.0                      |\
+            \n| y   | 2   | 1970-01-01T00:00:00 | 1.0                      |\
+            \n| x   | 5   | 1970-01-01T00:00:01 | 1.0                      |\
+            \n| x   | 4   | 1970-01-01T00:00:01 | 1.0                      |\
+            \n+-----+-----+---------------------+--------------------------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn histogram_quantile() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("job", DataType::Utf8, true),
+            Field::new("le", DataType::Utf8, true),
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            ),
+            Field::new("value", DataType::Float64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec![
+                    "a", "a", "a", "a", "b", "c", "a", "a", "a",
+                ])),
+                Arc::new(StringArray::from(vec![
+                    "0.1", "0.5", "1", "+Inf", "1", "abc", "0.1", "0.5", "+Inf",
+                ])),
+                Arc::new(TimestampMillisecondArray::from(vec![
+                    0, 0, 0, 0, 0, 0, 1000, 1000, 1000,
+                ])),
+                Arc::new(Float64Array::from(vec![
+                    10.0, 20.0, 40.0, 40.0, 5.0, 1.0, 10.0, 8.0, 20.0,
+                ])),
+            ],
+        )
+        .unwrap();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap());
+        let input_plan = LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(DFSchema::try_from_qualified_schema("m", &input.schema()).unwrap()),
+        });
+        let aggregate = VectorAggregate::try_new(
+            VectorAggregateOp::HistogramQuantile(0.75.into()),
+            vec!["job".to_string(), "le".to_string()],
+            vec!["job".to_string()],
+            "timestamp".to_string(),
+            vec!["value".to_string()],
+            input_plan,
+        )
+        .unwrap();
+
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(
+            aggregate.to_execution_plan(input),
+            session_context.task_ctx(),
+        )
+        .await
+        .unwrap();
+        let result = datatypes::arrow::util::pretty::pretty_format_batches(&result)
+            .unwrap()
+            .to_string();
+        let expected = String::from(
+            "+-----+---------------------+-----------------------------------+\
+            \n| job | timestamp           | histogram_quantile(0.75, m.value) |\
+            \n+-----+---------------------+-----------------------------------+\
+            \n| a   | 1970-01-01T00:00:00 | 0.75                              |\
+            \n| b   | 1970-01-01T00:00:00 | NaN                               |\
+            \n| a   | 1970-01-01T00:00:01 | 0.5                               |\
+            \n+-----+---------------------+-----------------------------------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn bucket_quantile_edge_cases() {
+        let mut buckets = vec![(1.0, 1.0), (f64::INFINITY, 2.0)];
+        assert!(super::bucket_quantile(f64::NAN, &mut buckets.clone()).is_nan());
+        assert_eq!(
+            super::bucket_quantile(-1.0, &mut buckets.clone()),
+            f64::NEG_INFINITY
+        );
+        assert_eq!(
+            super::bucket_quantile(2.0, &mut buckets.clone()),
+            f64::INFINITY
+        );
+        // the quantile falls into the lowest bucket
+        assert_eq!(super::bucket_quantile(0.25, &mut buckets.clone()), 0.5);
+        // the quantile falls into the highest bucket
+        assert_eq!(super::bucket_quantile(0.9, &mut buckets), 1.0);
+        // only the +Inf bucket
+        assert!(super::bucket_quantile(0.5, &mut vec![(f64::INFINITY, 2.0)]).is_nan());
+        // no observation
+        assert!(super::bucket_quantile(0.5, &mut vec![(1.0, 0.0), (f64::INFINITY, 0.0)]).is_nan());
+        // buckets with the same upper bound are merged
+        assert_eq!(
+            super::bucket_quantile(0.5, &mut vec![(f64::INFINITY, 4.0), (2.0, 1.0), (2.0, 1.0)]),
+            2.0
+        );
+    }
+}
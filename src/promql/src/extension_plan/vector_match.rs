@@ -0,0 +1,1157 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array, StringArray, UInt32Array};
+use datafusion::arrow::compute;
+use datafusion::arrow::datatypes::{DataType, TimeUnit};
+use datafusion::common::{
+    DFField, DFSchema, DFSchemaRef, OwnedTableReference, Result as DataFusionResult, Statistics,
+};
+use datafusion::error::DataFusionError;
+use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+};
+use datatypes::arrow::array::TimestampMillisecondArray;
+use datatypes::arrow::datatypes::SchemaRef;
+use datatypes::arrow::error::Result as ArrowResult;
+use datatypes::arrow::record_batch::RecordBatch;
+use futures::stream;
+
+use crate::extension_plan::Millisecond;
+
+/// Operators of binary expressions whose operands are both instant vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VectorMatchOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Atan2,
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    And,
+    Or,
+    Unless,
+}
+
+impl VectorMatchOp {
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Self::Eq | Self::Ne | Self::Gt | Self::Lt | Self::Ge | Self::Le
+        )
+    }
+
+    pub fn is_set(&self) -> bool {
+        matches!(self, Self::And | Self::Or | Self::Unless)
+    }
+
+    /// Applies an arithmetic or comparison operator on two sample values. Returns `None`
+    /// if the sample is filtered out by a comparison without the `bool` modifier.
+    fn apply(&self, lhs: f64, rhs: f64, return_bool: bool) -> Option<f64> {
+        let matched = match self {
+            Self::Add => return Some(lhs + rhs),
+            Self::Sub => return Some(lhs - rhs),
+            Self::Mul => return Some(lhs * rhs),
+            Self::Div => return Some(lhs / rhs),
+            Self::Mod => return Some(lhs % rhs),
+            Self::Pow => return Some(lhs.powf(rhs)),
+            Self::Atan2 => return Some(lhs.atan2(rhs)),
+            Self::Eq => lhs == rhs,
+            Self::Ne => lhs != rhs,
+            Self::Gt => lhs > rhs,
+            Self::Lt => lhs < rhs,
+            Self::Ge => lhs >= rhs,
+            Self::Le => lhs <= rhs,
+            Self::And | Self::Or | Self::Unless => {
+                unreachable!("set operators are not applied on sample values")
+            }
+        };
+        if return_bool {
+            Some(if matched { 1.0 } else { 0.0 })
+        } else {
+            matched.then_some(lhs)
+        }
+    }
+}
+
+impl Display for VectorMatchOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Pow => "^",
+            Self::Atan2 => "atan2",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Gt => ">",
+            Self::Lt => "<",
+            Self::Ge => ">=",
+            Self::Le => "<=",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Unless => "unless",
+        };
+        write!(f, "{op}")
+    }
+}
+
+/// Labels that decide which series of both sides match, from `on(...)` or `ignoring(...)`.
+/// All labels are used if it's absent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MatchingLabels {
+    On(Vec<String>),
+    Ignoring(Vec<String>),
+}
+
+impl MatchingLabels {
+    fn contains(&self, label: &str) -> bool {
+        match self {
+            Self::On(labels) => labels.iter().any(|l| l == label),
+            Self::Ignoring(labels) => !labels.iter().any(|l| l == label),
+        }
+    }
+}
+
+impl Display for MatchingLabels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::On(labels) => write!(f, "on({})", labels.join(", ")),
+            Self::Ignoring(labels) => write!(f, "ignoring({})", labels.join(", ")),
+        }
+    }
+}
+
+/// How many series of each side can match each other. Labels of `group_left(...)` and
+/// `group_right(...)` are copied from the "one" side to the result.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MatchCardinality {
+    OneToOne,
+    ManyToOne(Vec<String>),
+    OneToMany(Vec<String>),
+    /// Only for set operators.
+    ManyToMany,
+}
+
+impl Display for MatchCardinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OneToOne => write!(f, "one-to-one"),
+            Self::ManyToOne(labels) => write!(f, "many-to-one({})", labels.join(", ")),
+            Self::OneToMany(labels) => write!(f, "one-to-many({})", labels.join(", ")),
+            Self::ManyToMany => write!(f, "many-to-many"),
+        }
+    }
+}
+
+/// Modifiers of a binary expression between two vectors.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VectorMatchModifier {
+    pub return_bool: bool,
+    pub matching: Option<MatchingLabels>,
+    pub cardinality: MatchCardinality,
+}
+
+/// Columns of an operand of [VectorMatch].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OperandColumns {
+    pub tag_columns: Vec<String>,
+    pub time_index: String,
+    pub field_columns: Vec<String>,
+}
+
+/// Binary operation between two instant vectors with Prometheus' vector matching semantics.
+///
+/// Samples of both sides are matched if they are at the same timestamp and their labels
+/// are equal, on the labels selected by [MatchingLabels]. Empty labels are regarded as
+/// absent. Field columns of both sides are paired by their positions.
+///
+/// - Arithmetic and comparison operators generate one sample for each matched pair, the
+///   result keeps the labels of the "many" side, or only the matching labels for one-to-one
+///   matching. Comparisons keep the left hand-side values unless `bool` is specified.
+/// - `and` and `unless` keep the left hand-side samples that have (or don't have) a match.
+/// - `or` keeps all left hand-side samples, plus the right hand-side ones without a match.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct VectorMatch {
+    spec: MatchSpec,
+    left: LogicalPlan,
+    right: LogicalPlan,
+    output_schema: DFSchemaRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MatchSpec {
+    op: VectorMatchOp,
+    modifier: VectorMatchModifier,
+    left: OperandColumns,
+    right: OperandColumns,
+    output_tags: Vec<String>,
+}
+
+impl VectorMatch {
+    pub fn try_new(
+        op: VectorMatchOp,
+        modifier: VectorMatchModifier,
+        left: LogicalPlan,
+        left_columns: OperandColumns,
+        right: LogicalPlan,
+        right_columns: OperandColumns,
+    ) -> DataFusionResult<Self> {
+        let spec = MatchSpec::try_new(op, modifier, left_columns, right_columns)?;
+        let output_schema = spec.output_schema(left.schema(), right.schema())?;
+        Ok(Self {
+            spec,
+            left,
+            right,
+            output_schema,
+        })
+    }
+
+    pub fn tag_columns(&self) -> &[String] {
+        &self.spec.output_tags
+    }
+
+    pub fn field_columns(&self) -> Vec<String> {
+        let fields = self.output_schema.fields();
+        fields[fields.len() - self.spec.left.field_columns.len()..]
+            .iter()
+            .map(|field| field.name().clone())
+            .collect()
+    }
+
+    pub fn to_execution_plan(
+        &self,
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+    ) -> Arc<dyn ExecutionPlan> {
+        Arc::new(VectorMatchExec {
+            spec: Arc::new(self.spec.clone()),
+            left,
+            right,
+            output_schema: Arc::new(self.output_schema.as_ref().into()),
+            metric: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+/// Appends the tags of the other side accepted by `include` to `tags`.
+fn merge_tags(tags: &[String], other: &[String], include: impl Fn(&String) -> bool) -> Vec<String> {
+    let mut result = tags.to_vec();
+    for tag in other {
+        if include(tag) && !result.contains(tag) {
+            result.push(tag.clone());
+        }
+    }
+    result
+}
+
+impl MatchSpec {
+    fn try_new(
+        op: VectorMatchOp,
+        modifier: VectorMatchModifier,
+        left_columns: OperandColumns,
+        right_columns: OperandColumns,
+    ) -> DataFusionResult<Self> {
+        if left_columns.field_columns.len() != right_columns.field_columns.len() {
+            return Err(DataFusionError::Plan(format!(
+                "Operands of \"{op}\" have different numbers of value columns: {:?} and {:?}",
+                left_columns.field_columns, right_columns.field_columns
+            )));
+        }
+        match &modifier.cardinality {
+            MatchCardinality::ManyToOne(_) | MatchCardinality::OneToMany(_) if op.is_set() => {
+                return Err(DataFusionError::Plan(format!(
+                    "No grouping allowed for \"{op}\" operation"
+                )));
+            }
+            MatchCardinality::ManyToMany if !op.is_set() => {
+                return Err(DataFusionError::Plan(format!(
+                    "Many-to-many matching is only allowed for set operators, found \"{op}\""
+                )));
+            }
+            _ => {}
+        }
+        if modifier.return_bool && !op.is_comparison() {
+            return Err(DataFusionError::Plan(format!(
+                "Bool modifier can only be used on comparison operators, found \"{op}\""
+            )));
+        }
+
+        let output_tags = match (&modifier.cardinality, op) {
+            (_, VectorMatchOp::And | VectorMatchOp::Unless) => left_columns.tag_columns.clone(),
+            (_, VectorMatchOp::Or) => merge_tags(
+                &left_columns.tag_columns,
+                &right_columns.tag_columns,
+                |_| true,
+            ),
+            (MatchCardinality::ManyToOne(include), _) => merge_tags(
+                &left_columns.tag_columns,
+                &right_columns.tag_columns,
+                |tag| include.contains(tag),
+            ),
+            (MatchCardinality::OneToMany(include), _) => merge_tags(
+                &right_columns.tag_columns,
+                &left_columns.tag_columns,
+                |tag| include.contains(tag),
+            ),
+            _ => left_columns
+                .tag_columns
+                .iter()
+                .filter(|tag| {
+                    modifier
+                        .matching
+                        .as_ref()
+                        .map_or(true, |matching| matching.contains(tag))
+                })
+                .cloned()
+                .collect(),
+        };
+
+        Ok(Self {
+            op,
+            modifier,
+            left: left_columns,
+            right: right_columns,
+            output_tags,
+        })
+    }
+
+    fn output_schema(
+        &self,
+        left_schema: &DFSchemaRef,
+        right_schema: &DFSchemaRef,
+    ) -> DataFusionResult<DFSchemaRef> {
+        if matches!(self.op, VectorMatchOp::And | VectorMatchOp::Unless) {
+            return Ok(left_schema.clone());
+        }
+
+        let qualifier = left_schema
+            .field_with_unqualified_name(&self.left.time_index)?
+            .qualifier()
+            .cloned();
+        let mut fields = self
+            .output_tags
+            .iter()
+            .map(|tag| DFField::new(qualifier.clone(), tag, DataType::Utf8, true))
+            .collect::<Vec<_>>();
+        fields.push(DFField::new(
+            qualifier,
+            &self.left.time_index,
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ));
+        for (left_field, right_field) in self
+            .left
+            .field_columns
+            .iter()
+            .zip(self.right.field_columns.iter())
+        {
+            // filtering comparisons and `or` keep the samples of the left hand-side
+            let name = if self.op == VectorMatchOp::Or
+                || (self.op.is_comparison() && !self.modifier.return_bool)
+            {
+                left_field.clone()
+            } else {
+                format!(
+                    "{} {} {}",
+                    left_schema
+                        .field_with_unqualified_name(left_field)?
+                        .qualified_name(),
+                    self.op,
+                    right_schema
+                        .field_with_unqualified_name(right_field)?
+                        .qualified_name()
+                )
+            };
+            fields.push(DFField::new(
+                None::<OwnedTableReference>,
+                &name,
+                DataType::Float64,
+                true,
+            ));
+        }
+        Ok(Arc::new(DFSchema::new_with_metadata(
+            fields,
+            HashMap::new(),
+        )?))
+    }
+}
+
+impl UserDefinedLogicalNodeCore for VectorMatch {
+    fn name(&self) -> &str {
+        "VectorMatch"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.left, &self.right]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.output_schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PromVectorMatch: {}", self.spec)
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        assert_eq!(inputs.len(), 2);
+
+        Self {
+            spec: self.spec.clone(),
+            left: inputs[0].clone(),
+            right: inputs[1].clone(),
+            output_schema: self.output_schema.clone(),
+        }
+    }
+}
+
+impl Display for MatchSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "op=[{}", self.op)?;
+        if self.modifier.return_bool {
+            write!(f, " bool")?;
+        }
+        write!(f, "]")?;
+        if let Some(matching) = &self.modifier.matching {
+            write!(f, ", matching=[{matching}]")?;
+        }
+        write!(f, ", cardinality=[{}]", self.modifier.cardinality)
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorMatchExec {
+    spec: Arc<MatchSpec>,
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    metric: ExecutionPlanMetricsSet,
+}
+
+impl ExecutionPlan for VectorMatchExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.output_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false, false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 2);
+        Ok(Arc::new(Self {
+            spec: self.spec.clone(),
+            left: children[0].clone(),
+            right: children[1].clone(),
+            output_schema: self.output_schema.clone(),
+            metric: self.metric.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        let output = match_vectors(
+            self.spec.clone(),
+            self.left.clone(),
+            self.right.clone(),
+            self.output_schema.clone(),
+            context,
+            BaselineMetrics::new(&self.metric, partition),
+        );
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.output_schema.clone(),
+            stream::once(output),
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(f, "PromVectorMatchExec: {}", self.spec),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metric.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Samples may match the ones in any batch of the other side, so both sides are collected
+/// before matching.
+async fn match_vectors(
+    spec: Arc<MatchSpec>,
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+    schema: SchemaRef,
+    context: Arc<TaskContext>,
+    metric: BaselineMetrics,
+) -> DataFusionResult<RecordBatch> {
+    let left_batches = datafusion::physical_plan::collect(left.clone(), context.clone()).await?;
+    let right_batches = datafusion::physical_plan::collect(right.clone(), context).await?;
+
+    let _timer = metric.elapsed_compute().timer();
+    let left = Operand::try_new(&spec.left, &left.schema(), &left_batches)?;
+    let right = Operand::try_new(&spec.right, &right.schema(), &right_batches)?;
+    let batch = spec.evaluate(&left, &right, &schema)?;
+    metric.record_output(batch.num_rows());
+    Ok(batch)
+}
+
+/// An operand of the matching, concatenated from all of its batches.
+struct Operand {
+    batch: RecordBatch,
+    /// Tag values casted to strings, sorted by tag names.
+    tags: Vec<(String, StringArray)>,
+    timestamps: TimestampMillisecondArray,
+    fields: Vec<Float64Array>,
+}
+
+impl Operand {
+    fn try_new(
+        columns: &OperandColumns,
+        schema: &SchemaRef,
+        batches: &[RecordBatch],
+    ) -> DataFusionResult<Self> {
+        let batch = compute::concat_batches(schema, batches)?;
+        let mut tags = columns
+            .tag_columns
+            .iter()
+            .map(|tag| Ok((tag.clone(), cast_column(&batch, tag, &DataType::Utf8)?)))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+        tags.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let timestamps = cast_column(
+            &batch,
+            &columns.time_index,
+            &DataType::Timestamp(TimeUnit::Millisecond, None),
+        )?;
+        let fields = columns
+            .field_columns
+            .iter()
+            .map(|field| cast_column(&batch, field, &DataType::Float64))
+            .collect::<DataFusionResult<Vec<_>>>()?;
+
+        Ok(Self {
+            batch,
+            tags,
+            timestamps,
+            fields,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.batch.num_rows()
+    }
+
+    /// Returns the timestamp and the matching labels of a sample.
+    fn key(&self, row: usize, matching: &Option<MatchingLabels>) -> (Millisecond, Vec<&str>) {
+        let mut labels = Vec::new();
+        for (name, values) in &self.tags {
+            if !matching.as_ref().map_or(true, |m| m.contains(name)) {
+                continue;
+            }
+            if let Some(value) = tag_value(values, row) {
+                labels.push(name.as_str());
+                labels.push(value);
+            }
+        }
+        (self.timestamps.value(row), labels)
+    }
+
+    fn tag(&self, name: &str, row: usize) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .and_then(|(_, values)| tag_value(values, row))
+    }
+
+    fn value(&self, field: usize, row: usize) -> Option<f64> {
+        let values = &self.fields[field];
+        values.is_valid(row).then(|| values.value(row))
+    }
+}
+
+fn tag_value(values: &StringArray, row: usize) -> Option<&str> {
+    if values.is_valid(row) && !values.value(row).is_empty() {
+        Some(values.value(row))
+    } else {
+        None
+    }
+}
+
+pub(super) fn cast_column<T: Array + Clone + 'static>(
+    batch: &RecordBatch,
+    name: &str,
+    data_type: &DataType,
+) -> DataFusionResult<T> {
+    let index = batch.schema().index_of(name)?;
+    let array = compute::cast(batch.column(index), data_type)?;
+    Ok(array
+        .as_any()
+        .downcast_ref::<T>()
+        .expect("array should be casted to the expected type")
+        .clone())
+}
+
+/// Builds the result of arithmetic, comparison and `or` operations.
+struct OutputBuilder<'a> {
+    tags: Vec<Vec<Option<&'a str>>>,
+    timestamps: Vec<Millisecond>,
+    fields: Vec<Vec<Option<f64>>>,
+}
+
+impl<'a> OutputBuilder<'a> {
+    fn new(num_tags: usize, num_fields: usize) -> Self {
+        Self {
+            tags: vec![Vec::new(); num_tags],
+            timestamps: Vec::new(),
+            fields: vec![Vec::new(); num_fields],
+        }
+    }
+
+    fn push(
+        &mut self,
+        tags: impl Iterator<Item = Option<&'a str>>,
+        timestamp: Millisecond,
+        values: impl Iterator<Item = Option<f64>>,
+    ) {
+        for (column, tag) in self.tags.iter_mut().zip(tags) {
+            column.push(tag);
+        }
+        self.timestamps.push(timestamp);
+        for (column, value) in self.fields.iter_mut().zip(values) {
+            column.push(value);
+        }
+    }
+
+    fn finish(self, schema: &SchemaRef) -> DataFusionResult<RecordBatch> {
+        let mut columns = self
+            .tags
+            .into_iter()
+            .map(|tags| Arc::new(StringArray::from(tags)) as ArrayRef)
+            .collect::<Vec<_>>();
+        columns.push(Arc::new(TimestampMillisecondArray::from(self.timestamps)));
+        columns.extend(
+            self.fields
+                .into_iter()
+                .map(|values| Arc::new(Float64Array::from(values)) as ArrayRef),
+        );
+        Ok(RecordBatch::try_new(schema.clone(), columns)?)
+    }
+}
+
+impl MatchSpec {
+    fn evaluate(
+        &self,
+        left: &Operand,
+        right: &Operand,
+        schema: &SchemaRef,
+    ) -> DataFusionResult<RecordBatch> {
+        let matching = &self.modifier.matching;
+        match self.op {
+            VectorMatchOp::And | VectorMatchOp::Unless => {
+                let right_keys = (0..right.len())
+                    .map(|row| right.key(row, matching))
+                    .collect::<HashSet<_>>();
+                let keep_matched = self.op == VectorMatchOp::And;
+                let indices = (0..left.len())
+                    .filter(|row| right_keys.contains(&left.key(*row, matching)) == keep_matched)
+                    .map(|row| row as u32)
+                    .collect::<Vec<_>>();
+                let indices = UInt32Array::from(indices);
+                let columns = left
+                    .batch
+                    .columns()
+                    .iter()
+                    .map(|column| compute::take(column, &indices, None))
+                    .collect::<ArrowResult<Vec<_>>>()?;
+                Ok(RecordBatch::try_new(schema.clone(), columns)?)
+            }
+            VectorMatchOp::Or => {
+                let left_keys = (0..left.len())
+                    .map(|row| left.key(row, matching))
+                    .collect::<HashSet<_>>();
+                let mut output = OutputBuilder::new(self.output_tags.len(), left.fields.len());
+                for row in 0..left.len() {
+                    output.push(
+                        self.output_tags.iter().map(|tag| left.tag(tag, row)),
+                        left.timestamps.value(row),
+                        (0..left.fields.len()).map(|field| left.value(field, row)),
+                    );
+                }
+                for row in 0..right.len() {
+                    if !left_keys.contains(&right.key(row, matching)) {
+                        output.push(
+                            self.output_tags.iter().map(|tag| right.tag(tag, row)),
+                            right.timestamps.value(row),
+                            (0..right.fields.len()).map(|field| right.value(field, row)),
+                        );
+                    }
+                }
+                output.finish(schema)
+            }
+            _ => self.evaluate_binary(left, right, schema),
+        }
+    }
+
+    /// Evaluates arithmetic and comparison operators.
+    fn evaluate_binary(
+        &self,
+        left: &Operand,
+        right: &Operand,
+        schema: &SchemaRef,
+    ) -> DataFusionResult<RecordBatch> {
+        let matching = &self.modifier.matching;
+        // every sample of the "many" side looks for its match on the "one" side
+        let (many, one, include) = match &self.modifier.cardinality {
+            MatchCardinality::OneToMany(include) => (right, left, Some(include)),
+            MatchCardinality::ManyToOne(include) => (left, right, Some(include)),
+            _ => (left, right, None),
+        };
+        let swapped = matches!(self.modifier.cardinality, MatchCardinality::OneToMany(_));
+
+        let mut one_rows = HashMap::with_capacity(one.len());
+        for row in 0..one.len() {
+            if one_rows.insert(one.key(row, matching), row).is_some() {
+                return Err(DataFusionError::Execution(format!(
+                    "Found duplicate series for the match group on the {} hand-side of the operation, many-to-many matching is not allowed",
+                    if swapped { "left" } else { "right" }
+                )));
+            }
+        }
+
+        let mut matched = HashSet::new();
+        let mut output = OutputBuilder::new(self.output_tags.len(), left.fields.len());
+        for row in 0..many.len() {
+            let key = many.key(row, matching);
+            let Some(&one_row) = one_rows.get(&key) else {
+                continue;
+            };
+            if include.is_none() && !matched.insert(key) {
+                return Err(DataFusionError::Execution(
+                    "Multiple matches for labels, many-to-one matching must be explicit (group_left/group_right)".to_string(),
+                ));
+            }
+
+            let values = (0..left.fields.len())
+                .map(|field| {
+                    let (lhs, rhs) = if swapped {
+                        (one.value(field, one_row), many.value(field, row))
+                    } else {
+                        (many.value(field, row), one.value(field, one_row))
+                    };
+                    self.op.apply(lhs?, rhs?, self.modifier.return_bool)
+                })
+                .collect::<Vec<_>>();
+            if values.iter().all(Option::is_none) {
+                continue;
+            }
+            let tags = self.output_tags.iter().map(|tag| match include {
+                Some(include) if include.contains(tag) => one.tag(tag, one_row),
+                _ => many.tag(tag, row),
+            });
+            output.push(tags, many.timestamps.value(row), values.into_iter());
+        }
+        output.finish(schema)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    type Sample<'a> = (Vec<&'a str>, Millisecond, f64);
+
+    fn prepare_input(tags: &[&str], samples: Vec<Sample>) -> Arc<MemoryExec> {
+        let mut fields = tags
+            .iter()
+            .map(|tag| Field::new(*tag, DataType::Utf8, true))
+            .collect::<Vec<_>>();
+        fields.push(Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ));
+        fields.push(Field::new("value", DataType::Float64, true));
+        let schema = Arc::new(Schema::new(fields));
+
+        let mut columns = (0..tags.len())
+            .map(|i| {
+                Arc::new(StringArray::from(
+                    samples
+                        .iter()
+                        .map(|(tags, _, _)| tags[i])
+                        .collect::<Vec<_>>(),
+                )) as ArrayRef
+            })
+            .collect::<Vec<_>>();
+        columns.push(Arc::new(TimestampMillisecondArray::from(
+            samples.iter().map(|(_, ts, _)| *ts).collect::<Vec<_>>(),
+        )));
+        columns.push(Arc::new(Float64Array::from(
+            samples.iter().map(|(_, _, v)| *v).collect::<Vec<_>>(),
+        )));
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    fn operand_columns(tags: &[&str]) -> OperandColumns {
+        OperandColumns {
+            tag_columns: tags.iter().map(|tag| tag.to_string()).collect(),
+            time_index: "timestamp".to_string(),
+            field_columns: vec!["value".to_string()],
+        }
+    }
+
+    async fn do_vector_match_test(
+        op: VectorMatchOp,
+        modifier: VectorMatchModifier,
+        left: (&[&str], Vec<Sample<'_>>),
+        right: (&[&str], Vec<Sample<'_>>),
+    ) -> DataFusionResult<String> {
+        let left_input = prepare_input(left.0, left.1);
+        let right_input = prepare_input(right.0, right.1);
+        let left_schema =
+            Arc::new(DFSchema::try_from_qualified_schema("lhs", &left_input.schema()).unwrap());
+        let right_schema =
+            Arc::new(DFSchema::try_from_qualified_schema("rhs", &right_input.schema()).unwrap());
+        let spec = MatchSpec::try_new(
+            op,
+            modifier,
+            operand_columns(left.0),
+            operand_columns(right.0),
+        )
+        .unwrap();
+        let output_schema = spec.output_schema(&left_schema, &right_schema).unwrap();
+        let exec = Arc::new(VectorMatchExec {
+            spec: Arc::new(spec),
+            left: left_input,
+            right: right_input,
+            output_schema: Arc::new(output_schema.as_ref().into()),
+            metric: ExecutionPlanMetricsSet::new(),
+        });
+
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(exec, session_context.task_ctx()).await?;
+        Ok(
+            datatypes::arrow::util::pretty::pretty_format_batches(&result)
+                .unwrap()
+                .to_string(),
+        )
+    }
+
+    fn modifier(
+        matching: Option<MatchingLabels>,
+        cardinality: MatchCardinality,
+    ) -> VectorMatchModifier {
+        VectorMatchModifier {
+            return_bool: false,
+            matching,
+            cardinality,
+        }
+    }
+
+    #[tokio::test]
+    async fn one_to_one_ignoring() {
+        let result = do_vector_match_test(
+            VectorMatchOp::Div,
+            modifier(
+                Some(MatchingLabels::Ignoring(vec!["path".to_string()])),
+                MatchCardinality::OneToOne,
+            ),
+            (
+                &["host", "path"],
+                vec![
+                    (vec!["a", "x"], 0, 10.0),
+                    (vec!["b", "x"], 0, 20.0),
+                    (vec!["c", "x"], 0, 30.0),
+                ],
+            ),
+            (
+                &["host"],
+                vec![
+                    (vec!["a"], 0, 2.0),
+                    (vec!["b"], 0, 4.0),
+                    (vec!["a"], 1000, 5.0),
+                ],
+            ),
+        )
+        .await
+        .unwrap();
+        let expected = String::from(
+            "+------+---------------------+-----------------------+\
+            \n| host | timestamp           | lhs.value / rhs.value |\
+            \n+------+---------------------+-----------------------+\
+            \n| a    | 1970-01-01T00:00:00 | 5.0                   |\
+            \n| b    | 1970-01-01T00:00:00 | 5.0                   |\
+            \n+------+---------------------+-----------------------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn many_to_one_group_left() {
+        let result = do_vector_match_test(
+            VectorMatchOp::Mul,
+            modifier(
+                Some(MatchingLabels::On(vec!["host".to_string()])),
+                MatchCardinality::ManyToOne(vec!["dc".to_string()]),
+            ),
+            (
+                &["host", "path"],
+                vec![
+                    (vec!["a", "x"], 0, 10.0),
+                    (vec!["a", "y"], 0, 20.0),
+                    (vec!["b", "x"], 0, 30.0),
+                ],
+            ),
+            (
+                &["host", "dc"],
+                vec![(vec!["a", "dc1"], 0, 10.0), (vec!["b", "dc2"], 0, 10.0)],
+            ),
+        )
+        .await
+        .unwrap();
+        let expected = String::from(
+            "+------+------+-----+---------------------+-----------------------+\
+            \n| host | path | dc  | timestamp           | lhs.value * rhs.value |\
+            \n+------+------+-----+---------------------+-----------------------+\
+            \n| a    | x    | dc1 | 1970-01-01T00:00:00 | 100.0                 |\
+            \n| a    | y    | dc1 | 1970-01-01T00:00:00 | 200.0                 |\
+            \n| b    | x    | dc2 | 1970-01-01T00:00:00 | 300.0                 |\
+            \n+------+------+-----+---------------------+-----------------------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn one_to_one_multiple_matches() {
+        let err = do_vector_match_test(
+            VectorMatchOp::Add,
+            modifier(
+                Some(MatchingLabels::On(vec!["host".to_string()])),
+                MatchCardinality::OneToOne,
+            ),
+            (
+                &["host", "path"],
+                vec![(vec!["a", "x"], 0, 10.0), (vec!["a", "y"], 0, 20.0)],
+            ),
+            (&["host"], vec![(vec!["a"], 0, 1.0)]),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("Multiple matches for labels"),
+            "{err}"
+        );
+
+        let err = do_vector_match_test(
+            VectorMatchOp::Add,
+            modifier(
+                Some(MatchingLabels::On(vec!["host".to_string()])),
+                MatchCardinality::ManyToOne(vec![]),
+            ),
+            (&["host"], vec![(vec!["a"], 0, 1.0)]),
+            (
+                &["host", "path"],
+                vec![(vec!["a", "x"], 0, 10.0), (vec!["a", "y"], 0, 20.0)],
+            ),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("duplicate series for the match group on the right hand-side"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn comparison() {
+        let left: (&[&str], _) = (
+            &["host", "path"],
+            vec![(vec!["a", "x"], 0, 1.0), (vec!["b", "x"], 0, 5.0)],
+        );
+        let right: (&[&str], _) = (&["host"], vec![(vec!["a"], 0, 3.0), (vec!["b"], 0, 3.0)]);
+        let matching = Some(MatchingLabels::Ignoring(vec!["path".to_string()]));
+
+        let result = do_vector_match_test(
+            VectorMatchOp::Gt,
+            modifier(matching.clone(), MatchCardinality::OneToOne),
+            left.clone(),
+            right.clone(),
+        )
+        .await
+        .unwrap();
+        let expected = String::from(
+            "+------+---------------------+-------+\
+            \n| host | timestamp           | value |\
+            \n+------+---------------------+-------+\
+            \n| b    | 1970-01-01T00:00:00 | 5.0   |\
+            \n+------+---------------------+-------+",
+        );
+        assert_eq!(result, expected);
+
+        let result = do_vector_match_test(
+            VectorMatchOp::Gt,
+            VectorMatchModifier {
+                return_bool: true,
+                matching,
+                cardinality: MatchCardinality::OneToOne,
+            },
+            left,
+            right,
+        )
+        .await
+        .unwrap();
+        let expected = String::from(
+            "+------+---------------------+-----------------------+\
+            \n| host | timestamp           | lhs.value > rhs.value |\
+            \n+------+---------------------+-----------------------+\
+            \n| a    | 1970-01-01T00:00:00 | 0.0                   |\
+            \n| b    | 1970-01-01T00:00:00 | 1.0                   |\
+            \n+------+---------------------+-----------------------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn set_operators() {
+        let left: (&[&str], _) = (
+            &["host", "path"],
+            vec![(vec!["a", "x"], 0, 1.0), (vec!["b", "x"], 0, 2.0)],
+        );
+        let right: (&[&str], _) = (&["host"], vec![(vec!["a"], 0, 10.0), (vec!["c"], 0, 30.0)]);
+        let on_host = modifier(
+            Some(MatchingLabels::On(vec!["host".to_string()])),
+            MatchCardinality::ManyToMany,
+        );
+
+        let cases = [
+            (
+                VectorMatchOp::And,
+                "+------+------+---------------------+-------+\
+                \n| host | path | timestamp           | value |\
+                \n+------+------+---------------------+-------+\
+                \n| a    | x    | 1970-01-01T00:00:00 | 1.0   |\
+                \n+------+------+---------------------+-------+",
+            ),
+            (
+                VectorMatchOp::Unless,
+                "+------+------+---------------------+-------+\
+                \n| host | path | timestamp           | value |\
+                \n+------+------+---------------------+-------+\
+                \n| b    | x    | 1970-01-01T00:00:00 | 2.0   |\
+                \n+------+------+---------------------+-------+",
+            ),
+            (
+                VectorMatchOp::Or,
+                "+------+------+---------------------+-------+\
+                \n| host | path | timestamp           | value |\
+                \n+------+------+---------------------+-------+\
+                \n| a    | x    | 1970-01-01T00:00:00 | 1.0   |\
+                \n| b    | x    | 1970-01-01T00:00:00 | 2.0   |\
+                \n| c    |      | 1970-01-01T00:00:00 | 30.0  |\
+                \n+------+------+---------------------+-------+",
+            ),
+        ];
+        for (op, expected) in cases {
+            let result = do_vector_match_test(op, on_host.clone(), left.clone(), right.clone())
+                .await
+                .unwrap();
+            assert_eq!(result, expected, "{op}");
+        }
+    }
+
+    #[test]
+    fn invalid_modifiers() {
+        let columns = operand_columns(&["host"]);
+        assert!(MatchSpec::try_new(
+            VectorMatchOp::And,
+            modifier(None, MatchCardinality::ManyToOne(vec![])),
+            columns.clone(),
+            columns.clone(),
+        )
+        .is_err());
+        assert!(MatchSpec::try_new(
+            VectorMatchOp::Add,
+            modifier(None, MatchCardinality::ManyToMany),
+            columns.clone(),
+            columns.clone(),
+        )
+        .is_err());
+
+        let mut two_fields = columns.clone();
+        two_fields.field_columns.push("other".to_string());
+        assert!(MatchSpec::try_new(
+            VectorMatchOp::Add,
+            modifier(None, MatchCardinality::OneToOne),
+            columns,
+            two_fields,
+        )
+        .is_err());
+    }
+}
@@ -14,9 +14,13 @@
 
 mod aggr_over_time;
 mod changes;
+mod date;
 mod deriv;
 mod extrapolate_rate;
+mod holt_winters;
 mod idelta;
+mod label_replace;
+mod predict_linear;
 mod quantile;
 mod resets;
 #[cfg(test)]
@@ -30,9 +34,13 @@ pub use changes::Changes;
 use datafusion::arrow::array::ArrayRef;
 use datafusion::error::DataFusionError;
 use datafusion::physical_plan::ColumnarValue;
+pub use date::DateFunction;
 pub use deriv::Deriv;
 pub use extrapolate_rate::{Delta, Increase, Rate};
+pub use holt_winters::HoltWinters;
 pub use idelta::IDelta;
+pub use label_replace::LabelReplace;
+pub use predict_linear::PredictLinear;
 pub use quantile::QuantileOverTime;
 pub use resets::Resets;
 
@@ -0,0 +1,188 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the date functions like [`hour`](https://prometheus.io/docs/prometheus/latest/querying/functions/#hour)
+//! in PromQL. Refer to the [original
+//! implementation](https://github.com/prometheus/prometheus/blob/90b2f7a540b8a70d8d81372e6692dcbb67ccbaaa/promql/functions.go#L1167-L1240).
+
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use datafusion::arrow::array::Float64Array;
+use datafusion::common::DataFusionError;
+use datafusion::logical_expr::{ScalarUDF, Signature, TypeSignature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use datatypes::arrow::datatypes::DataType;
+
+use crate::functions::extract_array;
+
+/// Functions that extract a part of the date from values, which are regarded as unix
+/// timestamps in seconds, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateFunction {
+    DayOfMonth,
+    DayOfWeek,
+    DayOfYear,
+    DaysInMonth,
+    Hour,
+    Minute,
+    Month,
+    Year,
+}
+
+impl DateFunction {
+    /// Gets the date function by its name in PromQL.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "day_of_month" => Some(Self::DayOfMonth),
+            "day_of_week" => Some(Self::DayOfWeek),
+            "day_of_year" => Some(Self::DayOfYear),
+            "days_in_month" => Some(Self::DaysInMonth),
+            "hour" => Some(Self::Hour),
+            "minute" => Some(Self::Minute),
+            "month" => Some(Self::Month),
+            "year" => Some(Self::Year),
+            _ => None,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::DayOfMonth => "prom_day_of_month",
+            Self::DayOfWeek => "prom_day_of_week",
+            Self::DayOfYear => "prom_day_of_year",
+            Self::DaysInMonth => "prom_days_in_month",
+            Self::Hour => "prom_hour",
+            Self::Minute => "prom_minute",
+            Self::Month => "prom_month",
+            Self::Year => "prom_year",
+        }
+    }
+
+    pub fn scalar_udf(self) -> ScalarUDF {
+        ScalarUDF {
+            name: self.name().to_string(),
+            signature: Signature::new(
+                TypeSignature::Exact(vec![DataType::Float64]),
+                Volatility::Immutable,
+            ),
+            return_type: Arc::new(|_| Ok(Arc::new(DataType::Float64))),
+            fun: Arc::new(move |input| self.calc(input)),
+        }
+    }
+
+    fn calc(&self, input: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        assert_eq!(input.len(), 1);
+        let array = extract_array(&input[0])?;
+        let values = array
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "{}: expect Float64 as value array's type, found {}",
+                    self.name(),
+                    array.data_type()
+                ))
+            })?;
+
+        let result = values
+            .iter()
+            .map(|value| value.map(|value| self.evaluate(value)))
+            .collect::<Float64Array>();
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    }
+
+    fn evaluate(&self, seconds: f64) -> f64 {
+        if !seconds.is_finite() {
+            return f64::NAN;
+        }
+        let Some(datetime) = NaiveDateTime::from_timestamp_opt(seconds as i64, 0) else {
+            return f64::NAN;
+        };
+        let value = match self {
+            Self::DayOfMonth => datetime.day(),
+            Self::DayOfWeek => datetime.weekday().num_days_from_sunday(),
+            Self::DayOfYear => datetime.ordinal(),
+            Self::DaysInMonth => days_in_month(datetime.date()),
+            Self::Hour => datetime.hour(),
+            Self::Minute => datetime.minute(),
+            Self::Month => datetime.month(),
+            Self::Year => return datetime.year() as f64,
+        };
+        value as f64
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first_day| first_day.pred_opt())
+        .map_or(31, |last_day| last_day.day())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn do_date_function_test(function: DateFunction, input: Vec<Option<f64>>, expected: Vec<f64>) {
+        let udf = function.scalar_udf();
+        let input = vec![ColumnarValue::Array(Arc::new(Float64Array::from(input)))];
+        let result = (udf.fun)(&input).unwrap();
+        let ColumnarValue::Array(result) = result else { unreachable!() };
+        let result = result.as_any().downcast_ref::<Float64Array>().unwrap();
+        let result = result.iter().map(|v| v.unwrap()).collect::<Vec<_>>();
+        assert_eq!(result.len(), expected.len());
+        for (result, expected) in result.into_iter().zip(expected) {
+            if expected.is_nan() {
+                assert!(result.is_nan());
+            } else {
+                assert_eq!(result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn date_functions() {
+        // 2024-02-29T13:45:30Z, a Thursday
+        let leap_day = 1709214330.0;
+        let input = vec![Some(0.0), Some(leap_day)];
+
+        do_date_function_test(DateFunction::Year, input.clone(), vec![1970.0, 2024.0]);
+        do_date_function_test(DateFunction::Month, input.clone(), vec![1.0, 2.0]);
+        do_date_function_test(DateFunction::DayOfMonth, input.clone(), vec![1.0, 29.0]);
+        do_date_function_test(DateFunction::DayOfWeek, input.clone(), vec![4.0, 4.0]);
+        do_date_function_test(DateFunction::DayOfYear, input.clone(), vec![1.0, 60.0]);
+        do_date_function_test(DateFunction::DaysInMonth, input.clone(), vec![31.0, 29.0]);
+        do_date_function_test(DateFunction::Hour, input.clone(), vec![0.0, 13.0]);
+        do_date_function_test(DateFunction::Minute, input, vec![0.0, 45.0]);
+    }
+
+    #[test]
+    fn date_functions_with_special_values() {
+        do_date_function_test(
+            DateFunction::Hour,
+            vec![Some(f64::NAN), Some(f64::INFINITY), Some(-1.0)],
+            vec![f64::NAN, f64::NAN, 23.0],
+        );
+        do_date_function_test(
+            DateFunction::DaysInMonth,
+            vec![Some(1701388800.0)], // 2023-12-01
+            vec![31.0],
+        );
+    }
+}
@@ -29,8 +29,8 @@ use datatypes::arrow::datatypes::DataType;
 use crate::functions::{compensated_sum_inc, extract_array};
 use crate::range_array::RangeArray;
 
-#[range_fn(name = "Deriv", ret = "Float64Array", display_name = "prom_drive")]
-pub fn drive(times: &TimestampMillisecondArray, values: &Float64Array) -> Option<f64> {
+#[range_fn(name = "Deriv", ret = "Float64Array", display_name = "prom_deriv")]
+pub fn deriv(times: &TimestampMillisecondArray, values: &Float64Array) -> Option<f64> {
     if values.len() < 2 {
         None
     } else {
@@ -42,8 +42,9 @@ pub fn drive(times: &TimestampMillisecondArray, values: &Float64Array) -> Option
 
 /// linear_regression performs a least-square linear regression analysis on the
 /// times and values. It return the slope and intercept based on times and values.
+/// The slope is in units per second, and the intercept is the value at `intercept_time`.
 /// Prometheus's implementation: https://github.com/prometheus/prometheus/blob/90b2f7a540b8a70d8d81372e6692dcbb67ccbaaa/promql/functions.go#L793-L837
-fn linear_regression(
+pub(super) fn linear_regression(
     times: &TimestampMillisecondArray,
     values: &Float64Array,
     intercept_time: i64,
@@ -62,13 +63,12 @@ fn linear_regression(
     let init_y: f64 = values.value(0);
 
     for (i, value) in values.iter().enumerate() {
-        let time = times.value(i) as f64;
         let value = value.unwrap();
         if const_y && i > 0 && value != init_y {
             const_y = false;
         }
         count += 1.0;
-        let x = time - intercept_time as f64 / 1e3;
+        let x = (times.value(i) - intercept_time) as f64 / 1e3;
         (sum_x, comp_x) = compensated_sum_inc(x, sum_x, comp_x);
         (sum_y, comp_y) = compensated_sum_inc(value, sum_y, comp_y);
         (sum_xy, comp_xy) = compensated_sum_inc(x * value, sum_xy, comp_xy);
@@ -76,8 +76,8 @@ fn linear_regression(
     }
 
     if const_y {
-        if init_y.is_finite() {
-            return (None, None);
+        if init_y.is_infinite() {
+            return (Some(f64::NAN), Some(f64::NAN));
         }
         return (Some(0.0), Some(init_y));
     }
@@ -129,7 +129,7 @@ mod test {
             Deriv::scalar_udf(),
             ts_array,
             value_array,
-            vec![Some(0.010606060606060607), None],
+            vec![Some(10.606060606060607), None],
         );
     }
 }
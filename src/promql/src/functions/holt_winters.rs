@@ -0,0 +1,204 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of [`holt_winters`](https://prometheus.io/docs/prometheus/latest/querying/functions/#holt_winters) in PromQL. Refer to the [original
+//! implementation](https://github.com/prometheus/prometheus/blob/90b2f7a540b8a70d8d81372e6692dcbb67ccbaaa/promql/functions.go#L306-L373).
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::Float64Array;
+use datafusion::arrow::datatypes::TimeUnit;
+use datafusion::common::DataFusionError;
+use datafusion::logical_expr::{ScalarUDF, Signature, TypeSignature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use datatypes::arrow::array::Array;
+use datatypes::arrow::datatypes::DataType;
+
+use crate::error;
+use crate::functions::extract_array;
+use crate::range_array::RangeArray;
+
+/// Produces a smoothed value by double exponential smoothing. Both the smoothing factor
+/// and the trend factor should be in the range (0, 1), which is checked by the planner.
+#[derive(Debug)]
+pub struct HoltWinters {
+    smoothing_factor: f64,
+    trend_factor: f64,
+}
+
+impl HoltWinters {
+    fn new(smoothing_factor: f64, trend_factor: f64) -> Self {
+        Self {
+            smoothing_factor,
+            trend_factor,
+        }
+    }
+
+    pub const fn name() -> &'static str {
+        "prom_holt_winters"
+    }
+
+    pub fn scalar_udf(smoothing_factor: f64, trend_factor: f64) -> ScalarUDF {
+        ScalarUDF {
+            name: Self::name().to_string(),
+            signature: Signature::new(
+                TypeSignature::Exact(Self::input_type()),
+                Volatility::Immutable,
+            ),
+            return_type: Arc::new(|_| Ok(Arc::new(Self::return_type()))),
+            fun: Arc::new(move |input| Self::new(smoothing_factor, trend_factor).calc(input)),
+        }
+    }
+
+    // time index column, value column, smoothing factor and trend factor
+    fn input_type() -> Vec<DataType> {
+        vec![
+            RangeArray::convert_data_type(DataType::Timestamp(TimeUnit::Millisecond, None)),
+            RangeArray::convert_data_type(DataType::Float64),
+            DataType::Float64,
+            DataType::Float64,
+        ]
+    }
+
+    fn return_type() -> DataType {
+        DataType::Float64
+    }
+
+    fn calc(&self, input: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // The third and fourth ones are the factors, which are included in fields.
+        assert_eq!(input.len(), 4);
+        let ts_array = extract_array(&input[0])?;
+        let value_array = extract_array(&input[1])?;
+
+        let ts_range: RangeArray = RangeArray::try_new(ts_array.data().clone().into())?;
+        let value_range: RangeArray = RangeArray::try_new(value_array.data().clone().into())?;
+        error::ensure(
+            ts_range.len() == value_range.len(),
+            DataFusionError::Execution(format!(
+                "{}: input arrays should have the same length, found {} and {}",
+                Self::name(),
+                ts_range.len(),
+                value_range.len()
+            )),
+        )?;
+        error::ensure(
+            value_range.value_type() == DataType::Float64,
+            DataFusionError::Execution(format!(
+                "{}: expect Float64 as value array's type, found {}",
+                Self::name(),
+                value_range.value_type()
+            )),
+        )?;
+
+        // calculation
+        let mut result_array = Vec::with_capacity(ts_range.len());
+        for index in 0..ts_range.len() {
+            let values = value_range.get(index).unwrap();
+            let values = values
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .unwrap()
+                .values();
+            result_array.push(self.smooth(values));
+        }
+
+        let result = ColumnarValue::Array(Arc::new(Float64Array::from_iter(result_array)));
+        Ok(result)
+    }
+
+    fn smooth(&self, values: &[f64]) -> Option<f64> {
+        // can't do the smoothing operation with less than two points
+        if values.len() < 2 {
+            return None;
+        }
+
+        let (sf, tf) = (self.smoothing_factor, self.trend_factor);
+        let mut s0 = 0.0;
+        let mut s1 = values[0];
+        let mut trend = values[1] - values[0];
+        for (i, value) in values.iter().enumerate().skip(1) {
+            // scale the last smoothed value with the trend at this point
+            if i > 1 {
+                trend = tf * (s1 - s0) + (1.0 - tf) * trend;
+            }
+            let smoothed = sf * value + (1.0 - sf) * (s1 + trend);
+            (s0, s1) = (s1, smoothed);
+        }
+        Some(s1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::arrow::array::TimestampMillisecondArray;
+    use datafusion::scalar::ScalarValue;
+
+    use super::*;
+
+    fn do_holt_winters_test(
+        smoothing_factor: f64,
+        trend_factor: f64,
+        values: Vec<f64>,
+        ranges: Vec<(u32, u32)>,
+        expected: Vec<Option<f64>>,
+    ) {
+        let ts_array = Arc::new(TimestampMillisecondArray::from_iter(
+            (0..values.len() as i64).map(|i| Some(i * 1000)),
+        ));
+        let values_array = Arc::new(Float64Array::from(values));
+        let ts_range = RangeArray::from_ranges(ts_array, ranges.clone()).unwrap();
+        let value_range = RangeArray::from_ranges(values_array, ranges).unwrap();
+
+        let input = vec![
+            ColumnarValue::Array(Arc::new(ts_range.into_dict())),
+            ColumnarValue::Array(Arc::new(value_range.into_dict())),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(smoothing_factor))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(trend_factor))),
+        ];
+        let result = (HoltWinters::scalar_udf(smoothing_factor, trend_factor).fun)(&input).unwrap();
+        let result: Vec<Option<f64>> = extract_array(&result)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap()
+            .iter()
+            .collect();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn holt_winters_linear_series() {
+        // like the cases in prometheus' testdata/functions.test, the smoothed value of a
+        // linear series is its last value
+        let values = (0..11).map(|i| (i * 10) as f64).collect::<Vec<_>>();
+        do_holt_winters_test(
+            0.1,
+            0.5,
+            values,
+            vec![(0, 11), (0, 2), (3, 1)],
+            vec![Some(100.0), Some(10.0), None],
+        );
+    }
+
+    #[test]
+    fn holt_winters_fluctuating_series() {
+        do_holt_winters_test(
+            0.5,
+            0.5,
+            vec![1.0, 3.0, 2.0, 4.0],
+            vec![(0, 4), (0, 3)],
+            vec![Some(4.375), Some(3.5)],
+        );
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of [`label_replace`](https://prometheus.io/docs/prometheus/latest/querying/functions/#label_replace)
+//! in PromQL. Refer to the [original
+//! implementation](https://github.com/prometheus/prometheus/blob/90b2f7a540b8a70d8d81372e6692dcbb67ccbaaa/promql/functions.go#L1063-L1109).
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::StringArray;
+use datafusion::common::DataFusionError;
+use datafusion::logical_expr::{ScalarUDF, Signature, TypeSignature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use datafusion::scalar::ScalarValue;
+use datatypes::arrow::array::Array;
+use datatypes::arrow::datatypes::DataType;
+use regex::Regex;
+
+/// Computes the new value of the destination label from the source label.
+///
+/// The arguments are the destination label, the source label, the replacement and the
+/// regular expression. The destination label keeps its value if the regular expression
+/// doesn't match the source label.
+pub struct LabelReplace;
+
+impl LabelReplace {
+    pub const fn name() -> &'static str {
+        "prom_label_replace"
+    }
+
+    pub fn scalar_udf() -> ScalarUDF {
+        ScalarUDF {
+            name: Self::name().to_string(),
+            signature: Signature::new(
+                TypeSignature::Exact(vec![DataType::Utf8; 4]),
+                Volatility::Immutable,
+            ),
+            return_type: Arc::new(|_| Ok(Arc::new(DataType::Utf8))),
+            fun: Arc::new(Self::calc),
+        }
+    }
+
+    /// Anchors the regular expression like Prometheus, which requires it to match the
+    /// entire label value.
+    pub fn compile_regex(regex: &str) -> Result<Regex, regex::Error> {
+        Regex::new(&format!("^(?:{regex})$"))
+    }
+
+    fn calc(input: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        assert_eq!(input.len(), 4);
+        let replacement = Self::extract_literal(&input[2])?;
+        let regex = Self::extract_literal(&input[3])?;
+        let regex = Self::compile_regex(&regex).map_err(|e| {
+            DataFusionError::Execution(format!("{}: invalid regex: {e}", Self::name()))
+        })?;
+
+        let num_rows = input[..2].iter().find_map(|value| match value {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        });
+        let Some(num_rows) = num_rows else {
+            // both labels are constants
+            let dst = Self::extract_literal(&input[0])?;
+            let src = Self::extract_literal(&input[1])?;
+            let result = Self::replace(&regex, &replacement, &dst, &src);
+            return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(Some(result))));
+        };
+
+        let dst = input[0].clone().into_array(num_rows);
+        let src = input[1].clone().into_array(num_rows);
+        let (Some(dst), Some(src)) = (
+            dst.as_any().downcast_ref::<StringArray>(),
+            src.as_any().downcast_ref::<StringArray>(),
+        ) else {
+            return Err(DataFusionError::Execution(format!(
+                "{}: expect Utf8 as label array's type, found {} and {}",
+                Self::name(),
+                dst.data_type(),
+                src.data_type()
+            )));
+        };
+
+        let result = dst
+            .iter()
+            .zip(src.iter())
+            .map(|(dst, src)| {
+                Some(Self::replace(
+                    &regex,
+                    &replacement,
+                    dst.unwrap_or_default(),
+                    src.unwrap_or_default(),
+                ))
+            })
+            .collect::<StringArray>();
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    }
+
+    fn replace(regex: &Regex, replacement: &str, dst: &str, src: &str) -> String {
+        match regex.captures(src) {
+            Some(captures) => {
+                let mut result = String::new();
+                captures.expand(replacement, &mut result);
+                result
+            }
+            None => dst.to_string(),
+        }
+    }
+
+    fn extract_literal(value: &ColumnarValue) -> Result<String, DataFusionError> {
+        match value {
+            ColumnarValue::Scalar(ScalarValue::Utf8(value)) => {
+                Ok(value.clone().unwrap_or_default())
+            }
+            _ => Err(DataFusionError::Execution(format!(
+                "{}: expect a string literal, found {value:?}",
+                Self::name()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn do_label_replace_test(
+        dst: ColumnarValue,
+        src: ColumnarValue,
+        replacement: &str,
+        regex: &str,
+    ) -> ColumnarValue {
+        let input = vec![
+            dst,
+            src,
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(replacement.to_string()))),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(regex.to_string()))),
+        ];
+        (LabelReplace::scalar_udf().fun)(&input).unwrap()
+    }
+
+    fn string_array(values: Vec<&str>) -> ColumnarValue {
+        ColumnarValue::Array(Arc::new(StringArray::from(values)))
+    }
+
+    fn to_strings(value: ColumnarValue) -> Vec<String> {
+        let ColumnarValue::Array(array) = value else { unreachable!() };
+        array
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .iter()
+            .map(|v| v.unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn replace_matched_labels() {
+        let result = do_label_replace_test(
+            string_array(vec!["old", "old", "old"]),
+            string_array(vec!["a:c", "b:d", "no-colon"]),
+            "$1-${2}x",
+            "(.*):(.*)",
+        );
+        assert_eq!(to_strings(result), vec!["a-cx", "b-dx", "old"]);
+    }
+
+    #[test]
+    fn replace_is_anchored() {
+        let result = do_label_replace_test(
+            string_array(vec!["", ""]),
+            string_array(vec!["api-server", "server"]),
+            "matched",
+            "server",
+        );
+        assert_eq!(to_strings(result), vec!["", "matched"]);
+    }
+
+    #[test]
+    fn replace_with_constant_labels() {
+        // a new destination label
+        let result = do_label_replace_test(
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(String::new()))),
+            string_array(vec!["a", "b"]),
+            "$1$1",
+            "(a)",
+        );
+        assert_eq!(to_strings(result), vec!["aa", ""]);
+
+        let result = do_label_replace_test(
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(String::new()))),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(String::new()))),
+            "empty",
+            ".*",
+        );
+        let ColumnarValue::Scalar(ScalarValue::Utf8(Some(result))) = result else { unreachable!() };
+        assert_eq!(result, "empty");
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of [`predict_linear`](https://prometheus.io/docs/prometheus/latest/querying/functions/#predict_linear) in PromQL. Refer to the [original
+//! implementation](https://github.com/prometheus/prometheus/blob/90b2f7a540b8a70d8d81372e6692dcbb67ccbaaa/promql/functions.go#L858-L873).
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Float64Array, TimestampMillisecondArray};
+use datafusion::arrow::datatypes::TimeUnit;
+use datafusion::common::DataFusionError;
+use datafusion::logical_expr::{ScalarUDF, Signature, TypeSignature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use datatypes::arrow::array::Array;
+use datatypes::arrow::datatypes::DataType;
+
+use crate::error;
+use crate::functions::deriv::linear_regression;
+use crate::functions::extract_array;
+use crate::range_array::RangeArray;
+
+/// Predicts the value `duration` seconds after the evaluation timestamp, using simple
+/// linear regression.
+#[derive(Debug)]
+pub struct PredictLinear {
+    /// Duration to predict in second
+    duration: f64,
+}
+
+impl PredictLinear {
+    fn new(duration: f64) -> Self {
+        Self { duration }
+    }
+
+    pub const fn name() -> &'static str {
+        "prom_predict_linear"
+    }
+
+    pub fn scalar_udf(duration: f64) -> ScalarUDF {
+        ScalarUDF {
+            name: Self::name().to_string(),
+            signature: Signature::new(
+                TypeSignature::Exact(Self::input_type()),
+                Volatility::Immutable,
+            ),
+            return_type: Arc::new(|_| Ok(Arc::new(Self::return_type()))),
+            fun: Arc::new(move |input| Self::new(duration).calc(input)),
+        }
+    }
+
+    fn input_type() -> Vec<DataType> {
+        vec![
+            // timestamp range vector
+            RangeArray::convert_data_type(DataType::Timestamp(TimeUnit::Millisecond, None)),
+            // value range vector
+            RangeArray::convert_data_type(DataType::Float64),
+            // timestamp vector
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            // duration
+            DataType::Float64,
+        ]
+    }
+
+    fn return_type() -> DataType {
+        DataType::Float64
+    }
+
+    fn calc(&self, input: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+        // The fourth one is the duration param, which is included in fields.
+        assert_eq!(input.len(), 4);
+
+        // construct matrix from input
+        let ts_array = extract_array(&input[0])?;
+        let ts_range = RangeArray::try_new(ts_array.data().clone().into())?;
+        let value_array = extract_array(&input[1])?;
+        let value_range = RangeArray::try_new(value_array.data().clone().into())?;
+        let ts = extract_array(&input[2])?;
+        let ts = ts
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "{}: expect TimestampMillisecond as time index array's type, found {}",
+                    Self::name(),
+                    ts.data_type()
+                ))
+            })?;
+        error::ensure(
+            ts_range.len() == value_range.len() && ts_range.len() == ts.len(),
+            DataFusionError::Execution(format!(
+                "{}: input arrays should have the same length, found {}, {} and {}",
+                Self::name(),
+                ts_range.len(),
+                value_range.len(),
+                ts.len()
+            )),
+        )?;
+        error::ensure(
+            value_range.value_type() == DataType::Float64,
+            DataFusionError::Execution(format!(
+                "{}: expect Float64 as value array's type, found {}",
+                Self::name(),
+                value_range.value_type()
+            )),
+        )?;
+
+        // calculation
+        let mut result_array = Vec::with_capacity(ts_range.len());
+        for index in 0..ts_range.len() {
+            let timestamps = ts_range.get(index).unwrap();
+            let timestamps = timestamps
+                .as_any()
+                .downcast_ref::<TimestampMillisecondArray>()
+                .unwrap();
+            let values = value_range.get(index).unwrap();
+            let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+
+            if values.len() < 2 {
+                result_array.push(None);
+                continue;
+            }
+
+            let (slope, intercept) = linear_regression(timestamps, values, ts.value(index));
+            result_array.push(
+                slope
+                    .zip(intercept)
+                    .map(|(slope, intercept)| slope * self.duration + intercept),
+            );
+        }
+
+        let result = ColumnarValue::Array(Arc::new(Float64Array::from_iter(result_array)));
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use datafusion::scalar::ScalarValue;
+
+    use super::*;
+
+    fn do_predict_linear_test(duration: f64, eval_ts: Vec<i64>, expected: Vec<Option<f64>>) {
+        // a counter increases 10 per 300ms, and a constant
+        let ts_array = Arc::new(TimestampMillisecondArray::from_iter(
+            [0i64, 300, 600, 900, 1200, 0, 300, 600]
+                .into_iter()
+                .map(Some),
+        ));
+        let values_array = Arc::new(Float64Array::from_iter([
+            0.0, 10.0, 20.0, 30.0, 40.0, 5.0, 5.0, 5.0,
+        ]));
+        let ranges = [(0, 5), (5, 3), (0, 1)];
+        let ts_range = RangeArray::from_ranges(ts_array, ranges).unwrap();
+        let value_range = RangeArray::from_ranges(values_array, ranges).unwrap();
+
+        let input = vec![
+            ColumnarValue::Array(Arc::new(ts_range.into_dict())),
+            ColumnarValue::Array(Arc::new(value_range.into_dict())),
+            ColumnarValue::Array(Arc::new(TimestampMillisecondArray::from(eval_ts))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(duration))),
+        ];
+        let result = (PredictLinear::scalar_udf(duration).fun)(&input).unwrap();
+        let result: Vec<Option<f64>> = extract_array(&result)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap()
+            .iter()
+            .collect();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn predict_linear_at_eval_timestamp() {
+        do_predict_linear_test(
+            0.0,
+            vec![1200, 600, 1200],
+            vec![Some(40.0), Some(5.0), None],
+        );
+    }
+
+    #[test]
+    fn predict_linear_in_future() {
+        // slope is 33.33.. per second
+        do_predict_linear_test(
+            3.0,
+            vec![1200, 600, 1200],
+            vec![Some(140.0), Some(5.0), None],
+        );
+    }
+}
@@ -12,58 +12,64 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 
 use async_recursion::async_recursion;
 use catalog::table_source::DfTableSourceProvider;
-use datafusion::common::{DFSchemaRef, OwnedTableReference, Result as DfResult};
+use datafusion::common::{DFField, DFSchema, DFSchemaRef, OwnedTableReference, Result as DfResult};
 use datafusion::datasource::DefaultTableSource;
 use datafusion::logical_expr::expr::AggregateFunction;
 use datafusion::logical_expr::expr_rewriter::normalize_cols;
 use datafusion::logical_expr::{
-    AggregateFunction as AggregateFunctionEnum, BinaryExpr, BuiltinScalarFunction, Cast, Extension,
-    LogicalPlan, LogicalPlanBuilder, Operator, ScalarUDF,
+    AggregateFunction as AggregateFunctionEnum, BinaryExpr, BuiltinScalarFunction, Cast,
+    EmptyRelation, Extension, LogicalPlan, LogicalPlanBuilder, Operator, ScalarUDF,
 };
 use datafusion::optimizer::utils;
-use datafusion::prelude::{Column, Expr as DfExpr, JoinType};
+use datafusion::prelude::{Column, Expr as DfExpr};
 use datafusion::scalar::ScalarValue;
 use datafusion::sql::TableReference;
-use datatypes::arrow::datatypes::DataType as ArrowDataType;
+use datatypes::arrow::datatypes::{DataType as ArrowDataType, TimeUnit as ArrowTimeUnit};
 use promql_parser::label::{MatchOp, Matcher, Matchers, METRIC_NAME};
 use promql_parser::parser::{
-    token, AggModifier, AggregateExpr, BinaryExpr as PromBinaryExpr, Call, EvalStmt,
-    Expr as PromExpr, Function, MatrixSelector, NumberLiteral, Offset, ParenExpr, StringLiteral,
-    SubqueryExpr, TokenType, UnaryExpr, VectorSelector,
+    token, AggModifier, AggregateExpr, BinModifier, BinaryExpr as PromBinaryExpr, Call, EvalStmt,
+    Expr as PromExpr, Function, LabelModifier, MatrixSelector, NumberLiteral, Offset, ParenExpr,
+    StringLiteral, SubqueryExpr, TokenType, UnaryExpr, VectorMatchCardinality, VectorSelector,
 };
 use snafu::{ensure, OptionExt, ResultExt};
 use table::table::adapter::DfTableProviderAdapter;
 
 use crate::error::{
     CatalogSnafu, ColumnNotFoundSnafu, DataFusionPlanningSnafu, ExpectExprSnafu,
-    ExpectRangeSelectorSnafu, MultipleVectorSnafu, Result, TableNameNotFoundSnafu,
-    TimeIndexNotFoundSnafu, UnexpectedPlanExprSnafu, UnexpectedTokenSnafu, UnknownTableSnafu,
-    UnsupportedExprSnafu, ValueNotFoundSnafu, ZeroRangeSelectorSnafu,
+    ExpectRangeSelectorSnafu, InvalidFunctionArgumentSnafu, InvalidRegexSnafu, MultipleVectorSnafu,
+    NotSupportedSnafu, Result, TableNameNotFoundSnafu, TimeIndexNotFoundSnafu,
+    UnexpectedPlanExprSnafu, UnexpectedTokenSnafu, UnknownTableSnafu, UnsupportedExprSnafu,
+    ValueNotFoundSnafu, ZeroRangeSelectorSnafu,
 };
 use crate::extension_plan::{
-    EmptyMetric, InstantManipulate, Millisecond, RangeManipulate, SeriesDivide, SeriesNormalize,
+    Absent, EmptyMetric, InstantManipulate, MatchCardinality, MatchingLabels, Millisecond,
+    OperandColumns, RangeManipulate, ScalarCalculate, SeriesDivide, SeriesNormalize,
+    VectorAggregate, VectorAggregateOp, VectorMatch, VectorMatchModifier, VectorMatchOp,
+    ABSENT_VALUE_COLUMN, LE_LABEL,
 };
 use crate::functions::{
-    AbsentOverTime, AvgOverTime, Changes, CountOverTime, Delta, IDelta, Increase, LastOverTime,
-    MaxOverTime, MinOverTime, PresentOverTime, QuantileOverTime, Rate, Resets, StddevOverTime,
-    StdvarOverTime, SumOverTime,
+    AbsentOverTime, AvgOverTime, Changes, CountOverTime, DateFunction, Delta, Deriv, HoltWinters,
+    IDelta, Increase, LabelReplace, LastOverTime, MaxOverTime, MinOverTime, PredictLinear,
+    PresentOverTime, QuantileOverTime, Rate, Resets, StddevOverTime, StdvarOverTime, SumOverTime,
 };
 
-const LEFT_PLAN_JOIN_ALIAS: &str = "lhs";
-
 /// `time()` function in PromQL.
 const SPECIAL_TIME_FUNCTION: &str = "time";
 
 /// default value column name for empty metric
 const DEFAULT_FIELD_COLUMN: &str = "value";
 
+/// Default resolution of subqueries that don't specify a step, like `rate(foo[5m])[1h:]`.
+/// Prometheus uses the global evaluation interval, whose default value is one minute.
+const DEFAULT_SUBQUERY_STEP: Millisecond = 60_000;
+
 /// Special modifier to project field columns under multi-field mode
 const FIELD_COLUMN_MATCHER: &str = "__field__";
 
@@ -83,6 +89,11 @@ struct PromPlannerContext {
     field_column_matcher: Option<Vec<Matcher>>,
     /// The range in millisecond of range selector. None if there is no range selector.
     range: Option<Millisecond>,
+    /// Whether selectors of missing tables select nothing instead of failing, which is the case
+    /// in the argument of `absent()`.
+    allow_missing_table: bool,
+    /// Whether the table of the current selector is missing.
+    table_missing: bool,
 }
 
 impl PromPlannerContext {
@@ -120,12 +131,19 @@ impl PromPlanner {
             PromExpr::Aggregate(AggregateExpr {
                 op,
                 expr,
-                // TODO(ruihang): support param
-                param: _param,
+                param,
                 modifier,
             }) => {
                 let input = self.prom_expr_to_plan(*expr.clone()).await?;
 
+                // these aggregations select or generate series, and are planned separately
+                if matches!(
+                    op.id(),
+                    token::T_TOPK | token::T_BOTTOMK | token::T_QUANTILE | token::T_COUNT_VALUES
+                ) {
+                    return self.vector_aggregate_to_plan(*op, param, modifier, input);
+                }
+
                 // calculate columns to group by
                 // Need to append time index column into group by columns
                 let group_exprs = modifier
@@ -181,11 +199,15 @@ impl PromPlanner {
                     Self::try_build_literal_expr(lhs),
                     Self::try_build_literal_expr(rhs),
                 ) {
-                    // TODO(ruihang): handle literal-only expressions
-                    (Some(_lhs), Some(_rhs)) => UnsupportedExprSnafu {
-                        name: "Literal-only expression",
+                    // both sides are literals, evaluates the whole expression at each timestamp
+                    (Some(_), Some(_)) => {
+                        let expr = Self::try_build_literal_expr(&prom_expr).with_context(|| {
+                            UnsupportedExprSnafu {
+                                name: format!("{prom_expr:?}"),
+                            }
+                        })?;
+                        self.literal_to_plan(expr)?
                     }
-                    .fail()?,
                     // lhs is a literal, rhs is a column
                     (Some(expr), None) => {
                         let input = self.prom_expr_to_plan(*rhs.clone()).await?;
@@ -232,65 +254,44 @@ impl PromPlanner {
                             self.projection_for_each_field_column(input, bin_expr_builder)?
                         }
                     }
-                    // both are columns. join them on time index
+                    // both are vectors, match their samples by labels
                     (None, None) => {
                         let left_input = self.prom_expr_to_plan(*lhs.clone()).await?;
-                        let left_field_columns = self.ctx.field_columns.clone();
-                        let left_schema = left_input.schema().clone();
+                        let left_columns = self.operand_columns(&left_input)?;
+                        let left_table_name = self.ctx.table_name.clone();
 
                         let right_input = self.prom_expr_to_plan(*rhs.clone()).await?;
-                        let right_field_columns = self.ctx.field_columns.clone();
-                        let right_schema = right_input.schema().clone();
-
-                        let mut field_columns =
-                            left_field_columns.iter().zip(right_field_columns.iter());
-                        // the new ctx.field_columns for the generated join plan
-                        let join_plan = self.join_on_non_field_columns(left_input, right_input)?;
-                        let bin_expr_builder = |_: &String| {
-                            let (left_col_name, right_col_name) = field_columns.next().unwrap();
-                            let left_col = left_schema
-                                .field_with_name(None, left_col_name)
-                                .context(DataFusionPlanningSnafu)?
-                                .qualified_column();
-                            let right_col = right_schema
-                                .field_with_name(None, right_col_name)
-                                .context(DataFusionPlanningSnafu)?
-                                .qualified_column();
-
-                            let mut binary_expr = DfExpr::BinaryExpr(BinaryExpr {
-                                left: Box::new(DfExpr::Column(left_col)),
-                                op: Self::prom_token_to_binary_op(*op)?,
-                                right: Box::new(DfExpr::Column(right_col)),
-                            });
-                            if is_comparison_op && should_return_bool {
-                                binary_expr = DfExpr::Cast(Cast {
-                                    expr: Box::new(binary_expr),
-                                    data_type: ArrowDataType::Float64,
-                                });
-                            }
-                            Ok(binary_expr)
-                        };
-                        if is_comparison_op && !should_return_bool {
-                            self.filter_on_field_column(join_plan, bin_expr_builder)?
-                        } else {
-                            self.projection_for_each_field_column(join_plan, bin_expr_builder)?
-                        }
+                        let right_columns = self.operand_columns(&right_input)?;
+
+                        let vector_match = VectorMatch::try_new(
+                            Self::prom_token_to_vector_match_op(*op)?,
+                            Self::bin_modifier_to_vector_match(*op, modifier),
+                            left_input,
+                            left_columns.clone(),
+                            right_input,
+                            right_columns,
+                        )
+                        .context(DataFusionPlanningSnafu)?;
+
+                        // the result is described in the terms of the left hand-side
+                        self.ctx.table_name = left_table_name;
+                        self.ctx.time_index_column = Some(left_columns.time_index);
+                        self.ctx.tag_columns = vector_match.tag_columns().to_vec();
+                        self.ctx.field_columns = vector_match.field_columns();
+                        LogicalPlan::Extension(Extension {
+                            node: Arc::new(vector_match),
+                        })
                     }
                 }
             }
             PromExpr::Paren(ParenExpr { expr }) => self.prom_expr_to_plan(*expr.clone()).await?,
-            PromExpr::Subquery(SubqueryExpr { .. }) => UnsupportedExprSnafu {
-                name: "Prom Subquery",
-            }
-            .fail()?,
-            PromExpr::NumberLiteral(NumberLiteral { .. }) => UnsupportedExprSnafu {
-                name: "Prom Number Literal",
+            PromExpr::Subquery(subquery) => self.subquery_to_plan(subquery).await?,
+            PromExpr::NumberLiteral(NumberLiteral { val }) => {
+                self.literal_to_plan(DfExpr::Literal(ScalarValue::Float64(Some(*val))))?
             }
-            .fail()?,
-            PromExpr::StringLiteral(StringLiteral { .. }) => UnsupportedExprSnafu {
-                name: "Prom String Literal",
+            PromExpr::StringLiteral(StringLiteral { val }) => {
+                self.literal_to_plan(DfExpr::Literal(ScalarValue::Utf8(Some(val.clone()))))?
             }
-            .fail()?,
             PromExpr::VectorSelector(VectorSelector {
                 name: _,
                 offset,
@@ -354,32 +355,31 @@ impl PromPlanner {
                 })
             }
             PromExpr::Call(Call { func, args }) => {
-                // TODO(ruihang): refactor this, transform the AST in advance to include an empty metric table.
-                if func.name == SPECIAL_TIME_FUNCTION {
-                    self.ctx.time_index_column = Some(SPECIAL_TIME_FUNCTION.to_string());
-                    self.ctx.field_columns = vec![DEFAULT_FIELD_COLUMN.to_string()];
-                    self.ctx.table_name = Some(String::new());
-
-                    return Ok(LogicalPlan::Extension(Extension {
-                        node: Arc::new(
-                            EmptyMetric::new(
-                                self.ctx.start,
-                                self.ctx.end,
-                                self.ctx.interval,
-                                SPECIAL_TIME_FUNCTION.to_string(),
-                                DEFAULT_FIELD_COLUMN.to_string(),
-                            )
-                            .context(DataFusionPlanningSnafu)?,
-                        ),
-                    }));
+                // functions that can't be expressed as projections of their input
+                match func.name {
+                    SPECIAL_TIME_FUNCTION => return self.time_function_plan(),
+                    "vector" => return self.vector_function_to_plan(&args.args).await,
+                    "scalar" | "timestamp" | "absent" | "histogram_quantile" | "label_replace"
+                    | "label_join" | "sort_by_label" | "sort_by_label_desc" => {
+                        return self
+                            .special_function_to_plan(&prom_expr, func, &args.args)
+                            .await
+                    }
+                    _ => {}
                 }
 
                 let args = self.create_function_args(&args.args)?;
-                let input = self
-                    .prom_expr_to_plan(args.input.with_context(|| ExpectExprSnafu {
+                let input = match args.input {
+                    Some(input) => self.prom_expr_to_plan(input).await?,
+                    // date functions default to `vector(time())`
+                    None if DateFunction::from_name(func.name).is_some() => {
+                        self.time_function_plan()?
+                    }
+                    None => ExpectExprSnafu {
                         expr: prom_expr.clone(),
-                    })?)
-                    .await?;
+                    }
+                    .fail()?,
+                };
                 let mut func_exprs = self.create_function_expr(func, args.literals)?;
                 func_exprs.insert(0, self.create_time_index_column_expr()?);
                 func_exprs.extend_from_slice(&self.create_tag_column_exprs()?);
@@ -429,10 +429,19 @@ impl PromPlanner {
             Some(Offset::Neg(duration)) => -(duration.as_millis() as Millisecond),
             None => 0,
         };
-        let mut filters = self.matchers_to_expr(label_matchers)?;
+        // range selectors look back as far as their range
+        let lookback = self.ctx.range.map_or(self.ctx.lookback_delta, |range| {
+            range.max(self.ctx.lookback_delta)
+        });
+        // a missing table has no label to filter on
+        let mut filters = if self.ctx.table_missing {
+            vec![]
+        } else {
+            self.matchers_to_expr(label_matchers)?
+        };
         filters.push(self.create_time_index_column_expr()?.gt_eq(DfExpr::Literal(
             ScalarValue::TimestampMillisecond(
-                Some(self.ctx.start - offset_duration - self.ctx.lookback_delta),
+                Some(self.ctx.start - offset_duration - lookback),
                 None,
             ),
         )));
@@ -543,6 +552,648 @@ impl PromPlanner {
         Ok(logical_plan)
     }
 
+    /// Plans a subquery like `rate(foo[5m])[1h:1m] offset 5m`.
+    ///
+    /// The inner expression is evaluated at steps aligned to multiples of the subquery step,
+    /// covering the range before each evaluation timestamp, like Prometheus does. Its results
+    /// are then shifted by the offset and grouped into ranges just like a range selector. The
+    /// `@` modifier is not supported.
+    async fn subquery_to_plan(&mut self, subquery: &SubqueryExpr) -> Result<LogicalPlan> {
+        let SubqueryExpr {
+            expr,
+            offset,
+            at,
+            range,
+            step,
+            ..
+        } = subquery;
+        ensure!(
+            at.is_none(),
+            NotSupportedSnafu {
+                feat: "@ modifier of subqueries",
+            }
+        );
+        ensure!(!range.is_zero(), ZeroRangeSelectorSnafu);
+        let range_ms = range.as_millis() as Millisecond;
+        let step_ms = step
+            .map(|step| step.as_millis() as Millisecond)
+            .filter(|step| *step > 0)
+            .unwrap_or(DEFAULT_SUBQUERY_STEP);
+        let offset_ms = match offset {
+            Some(Offset::Pos(duration)) => duration.as_millis() as Millisecond,
+            Some(Offset::Neg(duration)) => -(duration.as_millis() as Millisecond),
+            None => 0,
+        };
+
+        // evaluate the inner expression from the first aligned step after `start - offset - range`
+        let outer = (self.ctx.start, self.ctx.end, self.ctx.interval);
+        let inner_start = self.ctx.start - offset_ms - range_ms;
+        let mut aligned_start = inner_start.div_euclid(step_ms) * step_ms;
+        if aligned_start < inner_start {
+            aligned_start += step_ms;
+        }
+        self.ctx.start = aligned_start;
+        self.ctx.end -= offset_ms;
+        self.ctx.interval = step_ms;
+        let input = self.prom_expr_to_plan(*expr.clone()).await;
+        (self.ctx.start, self.ctx.end, self.ctx.interval) = outer;
+        let input = input?;
+
+        let time_index = self.sync_context_with_plan(&input)?;
+
+        // divide the inner results into series, then shift and sort each of them
+        let sort_plan = LogicalPlanBuilder::from(input)
+            .sort(self.create_tag_and_time_index_column_sort_exprs()?)
+            .context(DataFusionPlanningSnafu)?
+            .build()
+            .context(DataFusionPlanningSnafu)?;
+        let divide_plan = LogicalPlan::Extension(Extension {
+            node: Arc::new(SeriesDivide::new(self.ctx.tag_columns.clone(), sort_plan)),
+        });
+        let normalize_plan = LogicalPlan::Extension(Extension {
+            node: Arc::new(SeriesNormalize::new(
+                -offset_ms,
+                time_index.clone(),
+                divide_plan,
+            )),
+        });
+
+        self.ctx.range = Some(range_ms);
+        let manipulate = RangeManipulate::new(
+            self.ctx.start,
+            self.ctx.end,
+            self.ctx.interval,
+            range_ms,
+            time_index,
+            self.ctx.field_columns.clone(),
+            normalize_plan,
+        )
+        .context(DataFusionPlanningSnafu)?;
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(manipulate),
+        }))
+    }
+
+    /// Plans `topk`, `bottomk`, `quantile` and `count_values`, which aggregate the samples of
+    /// each group at each timestamp with a [VectorAggregate] node.
+    fn vector_aggregate_to_plan(
+        &mut self,
+        op: TokenType,
+        param: &Option<Box<PromExpr>>,
+        modifier: &Option<AggModifier>,
+        input: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        let time_index = self.sync_context_with_plan(&input)?;
+
+        let param = param
+            .as_deref()
+            .and_then(Self::try_build_literal_expr)
+            .with_context(|| UnsupportedExprSnafu {
+                name: format!("{op:?} without a literal parameter"),
+            })?;
+        let op = match (op.id(), param) {
+            (token::T_TOPK, DfExpr::Literal(ScalarValue::Float64(Some(k)))) => {
+                VectorAggregateOp::TopK(k as i64)
+            }
+            (token::T_BOTTOMK, DfExpr::Literal(ScalarValue::Float64(Some(k)))) => {
+                VectorAggregateOp::BottomK(k as i64)
+            }
+            (token::T_QUANTILE, DfExpr::Literal(ScalarValue::Float64(Some(q)))) => {
+                VectorAggregateOp::Quantile(q.into())
+            }
+            (token::T_COUNT_VALUES, DfExpr::Literal(ScalarValue::Utf8(Some(label)))) => {
+                VectorAggregateOp::CountValues(label)
+            }
+            (_, param) => UnsupportedExprSnafu {
+                name: format!("{op:?} with parameter {param}"),
+            }
+            .fail()?,
+        };
+
+        // nonexistence labels are ignored
+        let group_tags = match modifier {
+            Some(AggModifier::By(labels)) => self
+                .ctx
+                .tag_columns
+                .iter()
+                .filter(|tag| labels.contains(*tag))
+                .cloned()
+                .collect(),
+            Some(AggModifier::Without(labels)) => self
+                .ctx
+                .tag_columns
+                .iter()
+                .filter(|tag| !labels.contains(*tag))
+                .cloned()
+                .collect(),
+            None => vec![],
+        };
+
+        self.build_vector_aggregate_plan(op, group_tags, time_index, input)
+    }
+
+    /// Builds a [VectorAggregate] over `input` whose output is sorted by tags and time index.
+    ///
+    /// # Side effect
+    ///
+    /// This method will update the tag and value columns in context.
+    fn build_vector_aggregate_plan(
+        &mut self,
+        op: VectorAggregateOp,
+        group_tags: Vec<String>,
+        time_index: String,
+        input: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        let aggregate = VectorAggregate::try_new(
+            op,
+            self.ctx.tag_columns.clone(),
+            group_tags,
+            time_index.clone(),
+            self.ctx.field_columns.clone(),
+            input,
+        )
+        .context(DataFusionPlanningSnafu)?;
+        self.ctx.tag_columns = aggregate.tag_columns();
+        self.ctx.field_columns = aggregate.field_columns();
+
+        let sort_exprs = self
+            .ctx
+            .tag_columns
+            .iter()
+            .chain(Some(&time_index))
+            .map(|col| DfExpr::Column(Column::from_name(col)).sort(true, false))
+            .collect::<Vec<_>>();
+        LogicalPlanBuilder::from(LogicalPlan::Extension(Extension {
+            node: Arc::new(aggregate),
+        }))
+        .sort(sort_exprs)
+        .context(DataFusionPlanningSnafu)?
+        .build()
+        .context(DataFusionPlanningSnafu)
+    }
+
+    /// Plans `time()` as an [EmptyMetric], whose values are the timestamps in seconds.
+    fn time_function_plan(&mut self) -> Result<LogicalPlan> {
+        // TODO(ruihang): refactor this, transform the AST in advance to include an empty metric table.
+        self.ctx.time_index_column = Some(SPECIAL_TIME_FUNCTION.to_string());
+        self.ctx.field_columns = vec![DEFAULT_FIELD_COLUMN.to_string()];
+        self.ctx.tag_columns = vec![];
+        self.ctx.table_name = Some(String::new());
+
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(
+                EmptyMetric::new(
+                    self.ctx.start,
+                    self.ctx.end,
+                    self.ctx.interval,
+                    SPECIAL_TIME_FUNCTION.to_string(),
+                    DEFAULT_FIELD_COLUMN.to_string(),
+                )
+                .context(DataFusionPlanningSnafu)?,
+            ),
+        }))
+    }
+
+    /// Plans a literal expression, whose value is generated at each timestamp like `time()`.
+    fn literal_to_plan(&mut self, expr: DfExpr) -> Result<LogicalPlan> {
+        let input = self.time_function_plan()?;
+        LogicalPlanBuilder::from(input)
+            .project(vec![
+                self.create_time_index_column_expr()?,
+                expr.alias(DEFAULT_FIELD_COLUMN),
+            ])
+            .context(DataFusionPlanningSnafu)?
+            .build()
+            .context(DataFusionPlanningSnafu)
+    }
+
+    /// Plans `vector(s)`, which returns the scalar as a vector without labels.
+    async fn vector_function_to_plan(&mut self, args: &[Box<PromExpr>]) -> Result<LogicalPlan> {
+        let arg = args.first().with_context(|| InvalidFunctionArgumentSnafu {
+            fn_name: "vector",
+            desc: "expect a scalar argument",
+        })?;
+        match Self::try_build_literal_expr(arg) {
+            Some(expr) => self.literal_to_plan(expr),
+            // scalars computed from vectors, like `time()` and `scalar(v)`, are already
+            // vectors without labels
+            None => self.prom_expr_to_plan(*arg.clone()).await,
+        }
+    }
+
+    /// Plans the functions that transform the whole input vector, rather than each sample.
+    async fn special_function_to_plan(
+        &mut self,
+        prom_expr: &PromExpr,
+        func: &Function,
+        args: &[Box<PromExpr>],
+    ) -> Result<LogicalPlan> {
+        let FunctionArgs { input, literals } = self.create_function_args(args)?;
+        let input = input.with_context(|| ExpectExprSnafu {
+            expr: prom_expr.clone(),
+        })?;
+
+        // `timestamp()` of a selector returns the timestamps of the selected samples
+        if func.name == "timestamp" {
+            if let PromExpr::VectorSelector(selector) = Self::strip_parens(&input) {
+                return self.selector_timestamp_to_plan(selector).await;
+            }
+        }
+
+        // `absent()` keeps the labels that the selector requires to be equal to some values
+        let absent_labels = match Self::strip_parens(&input) {
+            PromExpr::VectorSelector(selector) if func.name == "absent" => {
+                Self::absent_labels(&selector.matchers)
+            }
+            _ => vec![],
+        };
+
+        let allow_missing_table = self.ctx.allow_missing_table;
+        self.ctx.allow_missing_table |= func.name == "absent";
+        let input = self.prom_expr_to_plan(input).await;
+        self.ctx.allow_missing_table = allow_missing_table;
+        let input = input?;
+        let time_index = self.sync_context_with_plan(&input)?;
+        match func.name {
+            "scalar" => {
+                let [field_column] = self.ctx.field_columns.as_slice() else {
+                    return InvalidFunctionArgumentSnafu {
+                        fn_name: func.name,
+                        desc: "expect an input with exactly one value column",
+                    }
+                    .fail();
+                };
+                let scalar = ScalarCalculate::try_new(
+                    self.ctx.start,
+                    self.ctx.end,
+                    self.ctx.interval,
+                    time_index,
+                    field_column.clone(),
+                    input,
+                )
+                .context(DataFusionPlanningSnafu)?;
+                self.ctx.tag_columns = vec![];
+                self.ctx.field_columns = vec![scalar.field_column()];
+                Ok(LogicalPlan::Extension(Extension {
+                    node: Arc::new(scalar),
+                }))
+            }
+            "absent" => {
+                let absent = Absent::try_new(
+                    self.ctx.start,
+                    self.ctx.end,
+                    self.ctx.interval,
+                    time_index,
+                    absent_labels.clone(),
+                    input,
+                )
+                .context(DataFusionPlanningSnafu)?;
+                self.ctx.tag_columns = absent_labels.into_iter().map(|(name, _)| name).collect();
+                self.ctx.field_columns = vec![ABSENT_VALUE_COLUMN.to_string()];
+                Ok(LogicalPlan::Extension(Extension {
+                    node: Arc::new(absent),
+                }))
+            }
+            // the values are the evaluation timestamps if the input isn't a selector
+            "timestamp" => self.project_timestamp_as_value(input, 0),
+            "histogram_quantile" => {
+                let quantile = match literals.first() {
+                    Some(DfExpr::Literal(ScalarValue::Float64(Some(quantile)))) => *quantile,
+                    _ => InvalidFunctionArgumentSnafu {
+                        fn_name: func.name,
+                        desc: "expect a number literal as quantile",
+                    }
+                    .fail()?,
+                };
+                ensure!(
+                    self.ctx.tag_columns.iter().any(|tag| tag == LE_LABEL),
+                    ColumnNotFoundSnafu { col: LE_LABEL }
+                );
+                let group_tags = self
+                    .ctx
+                    .tag_columns
+                    .iter()
+                    .filter(|tag| *tag != LE_LABEL)
+                    .cloned()
+                    .collect();
+                self.build_vector_aggregate_plan(
+                    VectorAggregateOp::HistogramQuantile(quantile.into()),
+                    group_tags,
+                    time_index,
+                    input,
+                )
+            }
+            "label_replace" => {
+                let [dst, replacement, src, regex] =
+                    Self::string_literal_args(func.name, literals)?
+                        .try_into()
+                        .map_err(|_| {
+                            InvalidFunctionArgumentSnafu {
+                                fn_name: func.name,
+                                desc: "expect 4 string arguments",
+                            }
+                            .build()
+                        })?;
+                LabelReplace::compile_regex(&regex).context(InvalidRegexSnafu { regex: &regex })?;
+                let expr = DfExpr::ScalarUDF {
+                    fun: Arc::new(LabelReplace::scalar_udf()),
+                    args: vec![
+                        self.create_label_expr(&dst),
+                        self.create_label_expr(&src),
+                        DfExpr::Literal(ScalarValue::Utf8(Some(replacement))),
+                        DfExpr::Literal(ScalarValue::Utf8(Some(regex))),
+                    ],
+                };
+                self.project_label(func.name, input, dst, expr)
+            }
+            "label_join" => {
+                let mut args = Self::string_literal_args(func.name, literals)?.into_iter();
+                let (Some(dst), Some(separator)) = (args.next(), args.next()) else {
+                    return InvalidFunctionArgumentSnafu {
+                        fn_name: func.name,
+                        desc: "expect the destination label and separator",
+                    }
+                    .fail();
+                };
+                let src_exprs = args
+                    .map(|src| self.create_label_expr(&src))
+                    .collect::<Vec<_>>();
+                let expr = if src_exprs.is_empty() {
+                    DfExpr::Literal(ScalarValue::Utf8(Some(String::new())))
+                } else {
+                    DfExpr::ScalarFunction {
+                        fun: BuiltinScalarFunction::ConcatWithSeparator,
+                        args: Some(DfExpr::Literal(ScalarValue::Utf8(Some(separator))))
+                            .into_iter()
+                            .chain(src_exprs)
+                            .collect(),
+                    }
+                };
+                self.project_label(func.name, input, dst, expr)
+            }
+            "sort_by_label" | "sort_by_label_desc" => {
+                let asc = func.name == "sort_by_label";
+                // nonexistence labels are ignored
+                let mut labels = vec![];
+                for label in Self::string_literal_args(func.name, literals)? {
+                    if self.ctx.tag_columns.contains(&label) && !labels.contains(&label) {
+                        labels.push(label);
+                    }
+                }
+                // break ties by other labels
+                for tag in &self.ctx.tag_columns {
+                    if !labels.contains(tag) {
+                        labels.push(tag.clone());
+                    }
+                }
+                let sort_exprs = labels
+                    .into_iter()
+                    .chain(Some(time_index))
+                    .map(|col| DfExpr::Column(Column::from_name(col)).sort(asc, false))
+                    .collect::<Vec<_>>();
+                LogicalPlanBuilder::from(input)
+                    .sort(sort_exprs)
+                    .context(DataFusionPlanningSnafu)?
+                    .build()
+                    .context(DataFusionPlanningSnafu)
+            }
+            _ => UnsupportedExprSnafu {
+                name: func.name.to_string(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Plans `timestamp()` of a vector selector, whose values are the timestamps of selected
+    /// samples rather than the evaluation timestamps.
+    async fn selector_timestamp_to_plan(
+        &mut self,
+        selector: &VectorSelector,
+    ) -> Result<LogicalPlan> {
+        let VectorSelector {
+            offset, matchers, ..
+        } = selector;
+        let matchers = self.preprocess_label_matchers(matchers)?;
+        self.setup_context().await?;
+        let normalize = self
+            .selector_to_series_normalize_plan(offset, matchers)
+            .await?;
+        // the normalized timestamps are biased by the offset
+        let offset_ms = match offset {
+            Some(Offset::Pos(duration)) => duration.as_millis() as Millisecond,
+            Some(Offset::Neg(duration)) => -(duration.as_millis() as Millisecond),
+            None => 0,
+        };
+        let timestamps = self.project_timestamp_as_value(normalize, offset_ms)?;
+        let manipulate = InstantManipulate::new(
+            self.ctx.start,
+            self.ctx.end,
+            self.ctx.lookback_delta,
+            self.ctx.interval,
+            self.ctx
+                .time_index_column
+                .clone()
+                .expect("time index should be set in `setup_context`"),
+            timestamps,
+        );
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(manipulate),
+        }))
+    }
+
+    /// Replaces each value column with the timestamps in seconds, which are the timestamps
+    /// in the time index column minus `offset`.
+    fn project_timestamp_as_value(
+        &mut self,
+        input: LogicalPlan,
+        offset: Millisecond,
+    ) -> Result<LogicalPlan> {
+        let mut millis = DfExpr::Cast(Cast {
+            expr: Box::new(DfExpr::Cast(Cast {
+                expr: Box::new(self.create_time_index_column_expr()?),
+                data_type: ArrowDataType::Int64,
+            })),
+            data_type: ArrowDataType::Float64,
+        });
+        if offset != 0 {
+            millis = DfExpr::BinaryExpr(BinaryExpr {
+                left: Box::new(millis),
+                op: Operator::Minus,
+                right: Box::new(DfExpr::Literal(ScalarValue::Float64(Some(offset as f64)))),
+            });
+        }
+        let seconds = DfExpr::BinaryExpr(BinaryExpr {
+            left: Box::new(millis),
+            op: Operator::Divide,
+            right: Box::new(DfExpr::Literal(ScalarValue::Float64(Some(1000.0)))),
+        });
+
+        self.ctx.field_columns = self
+            .ctx
+            .field_columns
+            .iter()
+            .map(|field| format!("timestamp({field})"))
+            .collect();
+        let exprs = Some(self.create_time_index_column_expr()?)
+            .into_iter()
+            .chain(self.create_tag_column_exprs()?)
+            .chain(
+                self.ctx
+                    .field_columns
+                    .iter()
+                    .map(|field| seconds.clone().alias(field)),
+            )
+            .collect::<Vec<_>>();
+        LogicalPlanBuilder::from(input)
+            .project(exprs)
+            .context(DataFusionPlanningSnafu)?
+            .build()
+            .context(DataFusionPlanningSnafu)
+    }
+
+    /// Projects `expr` as the label `dst`, other columns are kept as they are.
+    ///
+    /// # Side effect
+    ///
+    /// This method will add `dst` into the tag columns in context if it doesn't exist.
+    fn project_label(
+        &mut self,
+        fn_name: &str,
+        input: LogicalPlan,
+        dst: String,
+        expr: DfExpr,
+    ) -> Result<LogicalPlan> {
+        ensure!(
+            is_valid_label_name(&dst),
+            InvalidFunctionArgumentSnafu {
+                fn_name,
+                desc: format!("invalid destination label name {dst:?}"),
+            }
+        );
+
+        let mut exprs = vec![self.create_time_index_column_expr()?];
+        for tag in &self.ctx.tag_columns {
+            if *tag != dst {
+                exprs.push(DfExpr::Column(Column::from_name(tag)));
+            }
+        }
+        exprs.push(expr.alias(&dst));
+        exprs.extend(
+            self.ctx
+                .field_columns
+                .iter()
+                .map(|field| DfExpr::Column(Column::from_name(field))),
+        );
+        self.ctx.tag_columns.retain(|tag| *tag != dst);
+        self.ctx.tag_columns.push(dst);
+
+        // the alias requalifies the generated label like other columns
+        let table_name = self.ctx.table_name.clone().unwrap_or_default();
+        LogicalPlanBuilder::from(input)
+            .project(exprs)
+            .context(DataFusionPlanningSnafu)?
+            .alias(table_name)
+            .context(DataFusionPlanningSnafu)?
+            .build()
+            .context(DataFusionPlanningSnafu)
+    }
+
+    /// Expression of the label's values, the values of nonexistence labels are empty strings.
+    fn create_label_expr(&self, label: &str) -> DfExpr {
+        if self.ctx.tag_columns.iter().any(|tag| tag == label) {
+            DfExpr::Column(Column::from_name(label))
+        } else {
+            DfExpr::Literal(ScalarValue::Utf8(Some(String::new())))
+        }
+    }
+
+    fn string_literal_args(fn_name: &str, literals: Vec<DfExpr>) -> Result<Vec<String>> {
+        literals
+            .into_iter()
+            .map(|literal| match literal {
+                DfExpr::Literal(ScalarValue::Utf8(Some(value))) => Ok(value),
+                other => InvalidFunctionArgumentSnafu {
+                    fn_name,
+                    desc: format!("expect a string literal, found {other}"),
+                }
+                .fail(),
+            })
+            .collect()
+    }
+
+    fn number_literal_args<const N: usize>(fn_name: &str, literals: &[DfExpr]) -> Result<[f64; N]> {
+        let mut args = [0.0; N];
+        ensure!(
+            literals.len() == N,
+            InvalidFunctionArgumentSnafu {
+                fn_name,
+                desc: format!("expect {N} number literals, found {}", literals.len()),
+            }
+        );
+        for (arg, literal) in args.iter_mut().zip(literals) {
+            *arg = match literal {
+                DfExpr::Literal(ScalarValue::Float64(Some(value))) => *value,
+                other => InvalidFunctionArgumentSnafu {
+                    fn_name,
+                    desc: format!("expect a number literal, found {other}"),
+                }
+                .fail()?,
+            };
+        }
+        Ok(args)
+    }
+
+    /// Labels of the sample generated by `absent()`. They are the labels, except the metric
+    /// name, whose only matcher is an equality matcher.
+    fn absent_labels(matchers: &Matchers) -> Vec<(String, String)> {
+        let mut labels: BTreeMap<&str, Option<&str>> = BTreeMap::new();
+        for matcher in &matchers.matchers {
+            if matcher.name == METRIC_NAME {
+                continue;
+            }
+            let value = matches!(matcher.op, MatchOp::Equal).then_some(matcher.value.as_str());
+            labels
+                .entry(&matcher.name)
+                .and_modify(|existing| *existing = None)
+                .or_insert(value);
+        }
+        labels
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?.to_string())))
+            .collect()
+    }
+
+    fn strip_parens(expr: &PromExpr) -> &PromExpr {
+        match expr {
+            PromExpr::Paren(ParenExpr { expr }) => Self::strip_parens(expr),
+            _ => expr,
+        }
+    }
+
+    /// Makes the time index and tag columns in the context consistent with the output of `plan`,
+    /// and returns the time index.
+    fn sync_context_with_plan(&mut self, plan: &LogicalPlan) -> Result<String> {
+        // aggregations remove the time index from the context, find it in the output instead
+        let time_index = match &self.ctx.time_index_column {
+            Some(time_index) => time_index.clone(),
+            None => plan
+                .schema()
+                .fields()
+                .iter()
+                .find(|field| matches!(field.data_type(), ArrowDataType::Timestamp(_, _)))
+                .map(|field| field.name().clone())
+                .with_context(|| TimeIndexNotFoundSnafu {
+                    table: self.ctx.table_name.clone().unwrap_or_default(),
+                })?,
+        };
+        self.ctx.time_index_column = Some(time_index.clone());
+        // aggregations may also drop some tag columns
+        self.ctx
+            .tag_columns
+            .retain(|tag| plan.schema().field_with_unqualified_name(tag).is_ok());
+        Ok(time_index)
+    }
+
     /// Convert [AggModifier] to [Column] exprs for aggregation.
     /// Timestamp column and tag columns will be included.
     ///
@@ -614,6 +1265,20 @@ impl PromPlanner {
     fn matchers_to_expr(&self, label_matchers: Matchers) -> Result<Vec<DfExpr>> {
         let mut exprs = Vec::with_capacity(label_matchers.matchers.len());
         for matcher in label_matchers.matchers {
+            // A label the metric doesn't have is regarded as an empty label, like Prometheus does.
+            if !self.ctx.tag_columns.contains(&matcher.name)
+                && !self.ctx.field_columns.contains(&matcher.name)
+                && self.ctx.time_index_column.as_ref() != Some(&matcher.name)
+            {
+                let is_match = match &matcher.op {
+                    MatchOp::Equal => matcher.value.is_empty(),
+                    MatchOp::NotEqual => !matcher.value.is_empty(),
+                    MatchOp::Re(regex) => regex.is_match(""),
+                    MatchOp::NotRe(regex) => !regex.is_match(""),
+                };
+                exprs.push(DfExpr::Literal(ScalarValue::Boolean(Some(is_match))));
+                continue;
+            }
             let col = DfExpr::Column(Column::from_name(matcher.name));
             let lit = DfExpr::Literal(ScalarValue::Utf8(Some(matcher.value)));
             let expr = match matcher.op {
@@ -642,6 +1307,9 @@ impl PromPlanner {
         filter: Vec<DfExpr>,
     ) -> Result<LogicalPlan> {
         let table_ref = OwnedTableReference::bare(table_name.to_string());
+        if self.ctx.table_missing {
+            return Self::missing_table_plan(table_ref);
+        }
         let provider = self
             .table_provider
             .resolve_table(table_ref.clone())
@@ -654,6 +1322,32 @@ impl PromPlanner {
         Ok(result)
     }
 
+    /// Plans a missing table as an empty relation with a time index and a value column.
+    fn missing_table_plan(table_ref: OwnedTableReference) -> Result<LogicalPlan> {
+        let schema = DFSchema::new_with_metadata(
+            vec![
+                DFField::new(
+                    Some(table_ref.clone()),
+                    SPECIAL_TIME_FUNCTION,
+                    ArrowDataType::Timestamp(ArrowTimeUnit::Millisecond, None),
+                    false,
+                ),
+                DFField::new(
+                    Some(table_ref),
+                    DEFAULT_FIELD_COLUMN,
+                    ArrowDataType::Float64,
+                    true,
+                ),
+            ],
+            HashMap::new(),
+        )
+        .context(DataFusionPlanningSnafu)?;
+        Ok(LogicalPlan::EmptyRelation(EmptyRelation {
+            produce_one_row: false,
+            schema: Arc::new(schema),
+        }))
+    }
+
     /// Setup [PromPlannerContext]'s state fields.
     async fn setup_context(&mut self) -> Result<()> {
         let table_name = self
@@ -661,11 +1355,23 @@ impl PromPlanner {
             .table_name
             .clone()
             .context(TableNameNotFoundSnafu)?;
-        let table = self
+        let table = match self
             .table_provider
             .resolve_table(TableReference::bare(&table_name))
             .await
-            .context(CatalogSnafu)?
+        {
+            Ok(table) => table,
+            Err(catalog::error::Error::TableNotExist { .. }) if self.ctx.allow_missing_table => {
+                self.ctx.table_missing = true;
+                self.ctx.time_index_column = Some(SPECIAL_TIME_FUNCTION.to_string());
+                self.ctx.field_columns = vec![DEFAULT_FIELD_COLUMN.to_string()];
+                self.ctx.tag_columns = vec![];
+                return Ok(());
+            }
+            Err(e) => return Err(e).context(CatalogSnafu),
+        };
+        self.ctx.table_missing = false;
+        let table = table
             .as_any()
             .downcast_ref::<DefaultTableSource>()
             .context(UnknownTableSnafu)?
@@ -764,6 +1470,7 @@ impl PromPlanner {
             "irate" => ScalarFunc::Udf(IDelta::<true>::scalar_udf()),
             "resets" => ScalarFunc::Udf(Resets::scalar_udf()),
             "changes" => ScalarFunc::Udf(Changes::scalar_udf()),
+            "deriv" => ScalarFunc::Udf(Deriv::scalar_udf()),
             "avg_over_time" => ScalarFunc::Udf(AvgOverTime::scalar_udf()),
             "min_over_time" => ScalarFunc::Udf(MinOverTime::scalar_udf()),
             "max_over_time" => ScalarFunc::Udf(MaxOverTime::scalar_udf()),
@@ -784,14 +1491,37 @@ impl PromPlanner {
                 };
                 ScalarFunc::Udf(QuantileOverTime::scalar_udf(quantile_expr))
             }
-            _ => ScalarFunc::DataFusionBuiltin(
-                BuiltinScalarFunction::from_str(func.name).map_err(|_| {
-                    UnsupportedExprSnafu {
-                        name: func.name.to_string(),
-                    }
-                    .build()
-                })?,
-            ),
+            "predict_linear" => {
+                let [duration] = Self::number_literal_args(func.name, &other_input_exprs)?;
+                ScalarFunc::ExtrapolateUdf(PredictLinear::scalar_udf(duration))
+            }
+            "holt_winters" => {
+                let [smoothing_factor, trend_factor] =
+                    Self::number_literal_args(func.name, &other_input_exprs)?;
+                for (name, factor) in [("smoothing", smoothing_factor), ("trend", trend_factor)] {
+                    ensure!(
+                        factor > 0.0 && factor < 1.0,
+                        InvalidFunctionArgumentSnafu {
+                            fn_name: func.name,
+                            desc: format!(
+                                "invalid {name} factor, expect 0 < factor < 1, found {factor}"
+                            ),
+                        }
+                    );
+                }
+                ScalarFunc::Udf(HoltWinters::scalar_udf(smoothing_factor, trend_factor))
+            }
+            _ => match DateFunction::from_name(func.name) {
+                Some(date_function) => ScalarFunc::InstantUdf(date_function.scalar_udf()),
+                None => ScalarFunc::DataFusionBuiltin(
+                    BuiltinScalarFunction::from_str(func.name).map_err(|_| {
+                        UnsupportedExprSnafu {
+                            name: func.name.to_string(),
+                        }
+                        .build()
+                    })?,
+                ),
+            },
         };
 
         // TODO(ruihang): handle those functions doesn't require input
@@ -809,6 +1539,15 @@ impl PromPlanner {
                     exprs.push(fn_expr);
                     other_input_exprs.remove(field_column_pos);
                 }
+                ScalarFunc::InstantUdf(fun) => {
+                    other_input_exprs.insert(field_column_pos, col_expr);
+                    let fn_expr = DfExpr::ScalarUDF {
+                        fun: Arc::new(fun),
+                        args: other_input_exprs.clone(),
+                    };
+                    exprs.push(fn_expr);
+                    other_input_exprs.remove(field_column_pos);
+                }
                 ScalarFunc::Udf(fun) => {
                     let ts_range_expr = DfExpr::Column(Column::from_name(
                         RangeManipulate::build_timestamp_range_name(
@@ -924,12 +1663,6 @@ impl PromPlanner {
             token::T_GROUP => AggregateFunctionEnum::Grouping,
             token::T_STDDEV => AggregateFunctionEnum::StddevPop,
             token::T_STDVAR => AggregateFunctionEnum::VariancePop,
-            token::T_TOPK | token::T_BOTTOMK | token::T_COUNT_VALUES | token::T_QUANTILE => {
-                UnsupportedExprSnafu {
-                    name: format!("{op:?}"),
-                }
-                .fail()?
-            }
             _ => UnexpectedTokenSnafu { token: op }.fail()?,
         };
 
@@ -978,17 +1711,26 @@ impl PromPlanner {
             | PromExpr::Aggregate(_)
             | PromExpr::Subquery(_) => None,
             PromExpr::Paren(ParenExpr { expr }) => Self::try_build_literal_expr(expr),
-            // TODO(ruihang): support Unary operator
-            PromExpr::Unary(UnaryExpr { expr, .. }) => Self::try_build_literal_expr(expr),
+            PromExpr::Unary(UnaryExpr { expr }) => Some(DfExpr::Negative(Box::new(
+                Self::try_build_literal_expr(expr)?,
+            ))),
             PromExpr::Binary(PromBinaryExpr { lhs, rhs, op, .. }) => {
                 let lhs = Self::try_build_literal_expr(lhs)?;
                 let rhs = Self::try_build_literal_expr(rhs)?;
-                let op = Self::prom_token_to_binary_op(*op).ok()?;
-                Some(DfExpr::BinaryExpr(BinaryExpr {
+                let binary_expr = DfExpr::BinaryExpr(BinaryExpr {
                     left: Box::new(lhs),
-                    op,
+                    op: Self::prom_token_to_binary_op(*op).ok()?,
                     right: Box::new(rhs),
-                }))
+                });
+                // comparisons between scalars always return 0/1
+                if Self::is_token_a_comparison_op(*op) {
+                    Some(DfExpr::Cast(Cast {
+                        expr: Box::new(binary_expr),
+                        data_type: ArrowDataType::Float64,
+                    }))
+                } else {
+                    Some(binary_expr)
+                }
             }
         }
     }
@@ -1026,39 +1768,84 @@ impl PromPlanner {
         )
     }
 
-    /// Build a inner join on time index column and tag columns to concat two logical plans.
-    /// The left plan will be alised as [`LEFT_PLAN_JOIN_ALIAS`].
-    fn join_on_non_field_columns(
-        &self,
-        left: LogicalPlan,
-        right: LogicalPlan,
-    ) -> Result<LogicalPlan> {
-        let mut tag_columns = self
-            .ctx
-            .tag_columns
-            .iter()
-            .map(Column::from_name)
-            .collect::<Vec<_>>();
+    fn prom_token_to_vector_match_op(token: TokenType) -> Result<VectorMatchOp> {
+        match token.id() {
+            token::T_ADD => Ok(VectorMatchOp::Add),
+            token::T_SUB => Ok(VectorMatchOp::Sub),
+            token::T_MUL => Ok(VectorMatchOp::Mul),
+            token::T_DIV => Ok(VectorMatchOp::Div),
+            token::T_MOD => Ok(VectorMatchOp::Mod),
+            token::T_POW => Ok(VectorMatchOp::Pow),
+            token::T_ATAN2 => Ok(VectorMatchOp::Atan2),
+            token::T_EQLC => Ok(VectorMatchOp::Eq),
+            token::T_NEQ => Ok(VectorMatchOp::Ne),
+            token::T_GTR => Ok(VectorMatchOp::Gt),
+            token::T_LSS => Ok(VectorMatchOp::Lt),
+            token::T_GTE => Ok(VectorMatchOp::Ge),
+            token::T_LTE => Ok(VectorMatchOp::Le),
+            token::T_LAND => Ok(VectorMatchOp::And),
+            token::T_LOR => Ok(VectorMatchOp::Or),
+            token::T_LUNLESS => Ok(VectorMatchOp::Unless),
+            _ => UnexpectedTokenSnafu { token }.fail(),
+        }
+    }
+
+    /// Converts the `bool`, `on`/`ignoring` and `group_left`/`group_right` modifiers of a binary
+    /// expression between two vectors.
+    fn bin_modifier_to_vector_match(
+        op: TokenType,
+        modifier: &Option<BinModifier>,
+    ) -> VectorMatchModifier {
+        // set operators always match many-to-many
+        let default_cardinality =
+            if matches!(op.id(), token::T_LAND | token::T_LOR | token::T_LUNLESS) {
+                MatchCardinality::ManyToMany
+            } else {
+                MatchCardinality::OneToOne
+            };
+        let Some(modifier) = modifier else {
+            return VectorMatchModifier {
+                return_bool: false,
+                matching: None,
+                cardinality: default_cardinality,
+            };
+        };
 
-        // push time index column if it exist
-        if let Some(time_index_column) = &self.ctx.time_index_column {
-            tag_columns.push(Column::from_name(time_index_column));
+        let matching = modifier.matching.as_ref().map(|matching| match matching {
+            LabelModifier::Include(labels) => MatchingLabels::On(Self::sorted_labels(labels)),
+            LabelModifier::Exclude(labels) => MatchingLabels::Ignoring(Self::sorted_labels(labels)),
+        });
+        let cardinality = match &modifier.card {
+            VectorMatchCardinality::OneToOne => default_cardinality,
+            VectorMatchCardinality::ManyToOne(labels) => {
+                MatchCardinality::ManyToOne(Self::sorted_labels(labels))
+            }
+            VectorMatchCardinality::OneToMany(labels) => {
+                MatchCardinality::OneToMany(Self::sorted_labels(labels))
+            }
+            VectorMatchCardinality::ManyToMany => MatchCardinality::ManyToMany,
+        };
+        VectorMatchModifier {
+            return_bool: modifier.return_bool,
+            matching,
+            cardinality,
         }
+    }
 
-        // Inner Join on time index column to concat two operator
-        LogicalPlanBuilder::from(left)
-            .alias(LEFT_PLAN_JOIN_ALIAS)
-            .context(DataFusionPlanningSnafu)?
-            .join(
-                right,
-                JoinType::Inner,
-                // (vec![time_index_column.clone()], vec![time_index_column]),
-                (tag_columns.clone(), tag_columns),
-                None,
-            )
-            .context(DataFusionPlanningSnafu)?
-            .build()
-            .context(DataFusionPlanningSnafu)
+    fn sorted_labels<'a>(labels: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+        let mut labels = labels.into_iter().cloned().collect::<Vec<_>>();
+        labels.sort();
+        labels
+    }
+
+    /// Columns of an operand of a binary expression between two vectors.
+    fn operand_columns(&mut self, plan: &LogicalPlan) -> Result<OperandColumns> {
+        let time_index = self.sync_context_with_plan(plan)?;
+        Ok(OperandColumns {
+            tag_columns: self.ctx.tag_columns.clone(),
+            time_index,
+            field_columns: self.ctx.field_columns.clone(),
+        })
     }
 
     /// Build a projection that project and perform operation expr for every value columns.
@@ -1146,6 +1933,15 @@ impl PromPlanner {
     }
 }
 
+/// Whether the name is a valid label name in Prometheus, which matches `[a-zA-Z_][a-zA-Z0-9_]*`.
+fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 #[derive(Default, Debug)]
 struct FunctionArgs {
     input: Option<PromExpr>,
@@ -1155,6 +1951,8 @@ struct FunctionArgs {
 #[derive(Debug, Clone)]
 enum ScalarFunc {
     DataFusionBuiltin(BuiltinScalarFunction),
+    /// UDF that only takes the value column, like [DateFunction].
+    InstantUdf(ScalarUDF),
     Udf(ScalarUDF),
     // todo(ruihang): maybe merge with Udf later
     /// UDF that require extra information like range length to be evaluated.
@@ -1168,6 +1966,7 @@ mod test {
     use catalog::local::MemoryCatalogManager;
     use catalog::{CatalogManager, RegisterTableRequest};
     use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
+    use common_error::prelude::{ErrorExt, StatusCode};
     use datatypes::prelude::ConcreteDataType;
     use datatypes::schema::{ColumnSchema, Schema};
     use promql_parser::parser;
@@ -1285,12 +2084,6 @@ mod test {
         do_single_instant_function_call("abs", "abs").await;
     }
 
-    #[tokio::test]
-    #[should_panic]
-    async fn single_absent() {
-        do_single_instant_function_call("absent", "").await;
-    }
-
     #[tokio::test]
     async fn single_ceil() {
         do_single_instant_function_call("ceil", "ceil").await;
@@ -1550,28 +2343,94 @@ mod test {
         do_aggregate_expr_plan("stdvar", "VARIANCEPOP").await;
     }
 
+    async fn do_vector_aggregate_expr_plan(query: &str, expected: &str) {
+        let prom_expr = parser::parse(query).unwrap();
+        let eval_stmt = EvalStmt {
+            expr: prom_expr,
+            start: UNIX_EPOCH,
+            end: UNIX_EPOCH
+                .checked_add(Duration::from_secs(100_000))
+                .unwrap(),
+            interval: Duration::from_secs(5),
+            lookback_delta: Duration::from_secs(1),
+        };
+
+        let table_provider = build_test_table_provider("some_metric".to_string(), 2, 1).await;
+        let plan = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+            .await
+            .unwrap();
+        let plan = plan.display_indent_schema().to_string();
+        let expected = format!(
+            "{expected}\
+            \n    PromInstantManipulate: range=[0..100000000], lookback=[1000], interval=[5000], time index=[timestamp] [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
+        );
+        assert!(
+            plan.starts_with(&expected),
+            "expected:\n{expected}\nactual:\n{plan}"
+        );
+    }
+
     #[tokio::test]
-    #[should_panic]
     async fn aggregate_top_k() {
-        do_aggregate_expr_plan("topk", "").await;
+        do_vector_aggregate_expr_plan(
+            "topk by (tag_1) (2, some_metric)",
+            "Sort: some_metric.tag_0 ASC NULLS LAST, some_metric.tag_1 ASC NULLS LAST, some_metric.timestamp ASC NULLS LAST [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n  PromVectorAggregate: op=[topk(2)], group by=[\"tag_1\"], time index=[timestamp], values=[\"field_0\"] [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]",
+        )
+        .await;
     }
 
     #[tokio::test]
-    #[should_panic]
     async fn aggregate_bottom_k() {
-        do_aggregate_expr_plan("bottomk", "").await;
+        do_vector_aggregate_expr_plan(
+            "bottomk without (tag_1) (3, some_metric)",
+            "Sort: some_metric.tag_0 ASC NULLS LAST, some_metric.tag_1 ASC NULLS LAST, some_metric.timestamp ASC NULLS LAST [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n  PromVectorAggregate: op=[bottomk(3)], group by=[\"tag_0\"], time index=[timestamp], values=[\"field_0\"] [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]",
+        )
+        .await;
     }
 
     #[tokio::test]
-    #[should_panic]
     async fn aggregate_count_values() {
-        do_aggregate_expr_plan("count_values", "").await;
+        do_vector_aggregate_expr_plan(
+            "count_values by (tag_1) (\"value\", some_metric)",
+            "Sort: some_metric.tag_1 ASC NULLS LAST, some_metric.value ASC NULLS LAST, some_metric.timestamp ASC NULLS LAST [tag_1:Utf8, value:Utf8;N, timestamp:Timestamp(Millisecond, None), count_values(value, some_metric.field_0):Float64;N]\
+            \n  PromVectorAggregate: op=[count_values(value)], group by=[\"tag_1\"], time index=[timestamp], values=[\"field_0\"] [tag_1:Utf8, value:Utf8;N, timestamp:Timestamp(Millisecond, None), count_values(value, some_metric.field_0):Float64;N]",
+        )
+        .await;
     }
 
     #[tokio::test]
-    #[should_panic]
     async fn aggregate_quantile() {
-        do_aggregate_expr_plan("quantile", "").await;
+        do_vector_aggregate_expr_plan(
+            "quantile(0.3, some_metric)",
+            "Sort: some_metric.timestamp ASC NULLS LAST [timestamp:Timestamp(Millisecond, None), quantile(0.3, some_metric.field_0):Float64;N]\
+            \n  PromVectorAggregate: op=[quantile(0.3)], group by=[], time index=[timestamp], values=[\"field_0\"] [timestamp:Timestamp(Millisecond, None), quantile(0.3, some_metric.field_0):Float64;N]",
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn aggregate_vector_ops_with_invalid_param() {
+        let prom_expr = parser::parse("topk(1 + 1, some_metric)").unwrap();
+        let eval_stmt = EvalStmt {
+            expr: prom_expr,
+            start: UNIX_EPOCH,
+            end: UNIX_EPOCH
+                .checked_add(Duration::from_secs(100_000))
+                .unwrap(),
+            interval: Duration::from_secs(5),
+            lookback_delta: Duration::from_secs(1),
+        };
+
+        let table_provider = build_test_table_provider("some_metric".to_string(), 2, 1).await;
+        let err = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, crate::error::Error::UnsupportedExpr { .. }),
+            "{err:?}"
+        );
     }
 
     // TODO(ruihang): add range fn tests once exprs are ready.
@@ -1617,21 +2476,19 @@ mod test {
             .unwrap();
 
         let  expected = String::from(
-            "Projection: some_metric.tag_0, some_metric.timestamp, some_metric.field_0 + some_metric.field_0 AS some_metric.field_0 + some_metric.field_0 [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), some_metric.field_0 + some_metric.field_0:Float64;N]\
-            \n  Inner Join: lhs.tag_0 = some_metric.tag_0, lhs.timestamp = some_metric.timestamp [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N, tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n    SubqueryAlias: lhs [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n      PromInstantManipulate: range=[0..100000000], lookback=[1000], interval=[5000], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n        PromSeriesNormalize: offset=[0], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n          PromSeriesDivide: tags=[\"tag_0\"] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n            Sort: some_metric.tag_0 DESC NULLS LAST, some_metric.timestamp DESC NULLS LAST [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n              Filter: some_metric.tag_0 = Utf8(\"foo\") AND some_metric.timestamp >= TimestampMillisecond(-1000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n                TableScan: some_metric, unsupported_filters=[tag_0 = Utf8(\"foo\"), timestamp >= TimestampMillisecond(-1000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n    PromInstantManipulate: range=[0..100000000], lookback=[1000], interval=[5000], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n      PromSeriesNormalize: offset=[0], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n        PromSeriesDivide: tags=[\"tag_0\"] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n          Sort: some_metric.tag_0 DESC NULLS LAST, some_metric.timestamp DESC NULLS LAST [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n            Filter: some_metric.tag_0 = Utf8(\"bar\") AND some_metric.timestamp >= TimestampMillisecond(-1000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n              TableScan: some_metric, unsupported_filters=[tag_0 = Utf8(\"bar\"), timestamp >= TimestampMillisecond(-1000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
+            "PromVectorMatch: op=[+], cardinality=[one-to-one] [tag_0:Utf8;N, timestamp:Timestamp(Millisecond, None), some_metric.field_0 + some_metric.field_0:Float64;N]\
+            \n  PromInstantManipulate: range=[0..100000000], lookback=[1000], interval=[5000], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n    PromSeriesNormalize: offset=[0], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n      PromSeriesDivide: tags=[\"tag_0\"] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n        Sort: some_metric.tag_0 DESC NULLS LAST, some_metric.timestamp DESC NULLS LAST [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n          Filter: some_metric.tag_0 = Utf8(\"foo\") AND some_metric.timestamp >= TimestampMillisecond(-1000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n            TableScan: some_metric, unsupported_filters=[tag_0 = Utf8(\"foo\"), timestamp >= TimestampMillisecond(-1000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n  PromInstantManipulate: range=[0..100000000], lookback=[1000], interval=[5000], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n    PromSeriesNormalize: offset=[0], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n      PromSeriesDivide: tags=[\"tag_0\"] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n        Sort: some_metric.tag_0 DESC NULLS LAST, some_metric.timestamp DESC NULLS LAST [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n          Filter: some_metric.tag_0 = Utf8(\"bar\") AND some_metric.timestamp >= TimestampMillisecond(-1000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n            TableScan: some_metric, unsupported_filters=[tag_0 = Utf8(\"bar\"), timestamp >= TimestampMillisecond(-1000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
         );
 
         assert_eq!(plan.display_indent_schema().to_string(), expected);
@@ -1657,6 +2514,138 @@ mod test {
         assert_eq!(plan.display_indent_schema().to_string(), expected);
     }
 
+    #[tokio::test]
+    async fn vector_matching_modifiers() {
+        let cases = [
+            (
+                "some_metric / ignoring(tag_1) some_metric",
+                "PromVectorMatch: op=[/], matching=[ignoring(tag_1)], cardinality=[one-to-one] [tag_0:Utf8;N, timestamp:Timestamp(Millisecond, None), some_metric.field_0 / some_metric.field_0:Float64;N]",
+            ),
+            (
+                "some_metric > bool on(tag_0) group_left(tag_1) some_metric",
+                "PromVectorMatch: op=[> bool], matching=[on(tag_0)], cardinality=[many-to-one(tag_1)] [tag_0:Utf8;N, tag_1:Utf8;N, timestamp:Timestamp(Millisecond, None), some_metric.field_0 > some_metric.field_0:Float64;N]",
+            ),
+            (
+                "some_metric and on(tag_0) some_metric",
+                "PromVectorMatch: op=[and], matching=[on(tag_0)], cardinality=[many-to-many] [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]",
+            ),
+            (
+                "some_metric unless some_metric",
+                "PromVectorMatch: op=[unless], cardinality=[many-to-many] [tag_0:Utf8, tag_1:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]",
+            ),
+        ];
+        for (query, expected) in cases {
+            let eval_stmt = EvalStmt {
+                expr: parser::parse(query).unwrap(),
+                start: UNIX_EPOCH,
+                end: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(100_000))
+                    .unwrap(),
+                interval: Duration::from_secs(5),
+                lookback_delta: Duration::from_secs(1),
+            };
+            let table_provider = build_test_table_provider("some_metric".to_string(), 2, 1).await;
+            let plan = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+                .await
+                .unwrap();
+            let plan = plan.display_indent_schema().to_string();
+            assert!(plan.starts_with(expected), "{query}: {plan}");
+        }
+    }
+
+    #[tokio::test]
+    async fn special_functions() {
+        let cases = [
+            (
+                r#"label_replace(some_metric, "tag_2", "$1", "tag_0", "(.*)")"#,
+                "SubqueryAlias: some_metric [timestamp:Timestamp(Millisecond, None), tag_0:Utf8, tag_1:Utf8, tag_2:Utf8;N, field_0:Float64;N]",
+            ),
+            (
+                r#"label_join(some_metric, "tag_0", "-", "tag_0", "tag_1")"#,
+                "SubqueryAlias: some_metric [timestamp:Timestamp(Millisecond, None), tag_1:Utf8, tag_0:Utf8;N, field_0:Float64;N]",
+            ),
+            (
+                "scalar(some_metric)",
+                "PromScalarCalculate: range=[0..100000000], interval=[5000], time index=[timestamp], value=[field_0] [timestamp:Timestamp(Millisecond, None), scalar(some_metric.field_0):Float64;N]",
+            ),
+            (
+                "timestamp(some_metric offset 1s)",
+                "PromInstantManipulate: range=[0..100000000], lookback=[1000], interval=[5000], time index=[timestamp] [timestamp:Timestamp(Millisecond, None), tag_0:Utf8, tag_1:Utf8, timestamp(field_0):Float64]",
+            ),
+            (
+                "hour(some_metric)",
+                "Filter: prom_hour(field_0) IS NOT NULL [timestamp:Timestamp(Millisecond, None), prom_hour(field_0):Float64;N, tag_0:Utf8, tag_1:Utf8]",
+            ),
+            (
+                "day_of_week()",
+                "Filter: prom_day_of_week(value) IS NOT NULL [time:Timestamp(Millisecond, None), prom_day_of_week(value):Float64;N]",
+            ),
+            ("vector(1)", "Float64(1) AS value"),
+            (
+                r#"absent(some_metric{tag_0="a", tag_1="b", tag_1!="c"})"#,
+                "PromAbsent: range=[0..100000000], interval=[5000], time index=[timestamp], labels=[(\"tag_0\", \"a\")] [timestamp:Timestamp(Millisecond, None), tag_0:Utf8, value:Float64;N]",
+            ),
+            (
+                "deriv(some_metric[5m])",
+                "prom_deriv(timestamp_range,field_0):Float64;N",
+            ),
+            (
+                "predict_linear(some_metric[5m], 3600)",
+                "prom_predict_linear(timestamp_range,field_0,timestamp,",
+            ),
+            (
+                "holt_winters(some_metric[5m], 0.5, 0.1)",
+                "prom_holt_winters(timestamp_range,field_0,",
+            ),
+        ];
+        for (query, expected) in cases {
+            let eval_stmt = EvalStmt {
+                expr: parser::parse(query).unwrap(),
+                start: UNIX_EPOCH,
+                end: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(100_000))
+                    .unwrap(),
+                interval: Duration::from_secs(5),
+                lookback_delta: Duration::from_secs(1),
+            };
+            let table_provider = build_test_table_provider("some_metric".to_string(), 2, 1).await;
+            let plan = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+                .await
+                .unwrap();
+            let plan = plan.display_indent_schema().to_string();
+            assert!(plan.contains(expected), "{query}: {plan}");
+        }
+    }
+
+    #[tokio::test]
+    async fn special_functions_with_invalid_arguments() {
+        let cases = [
+            // no `le` label
+            "histogram_quantile(0.9, some_metric)",
+            r#"label_replace(some_metric, "tag_2", "$1", "tag_0", "(")"#,
+            r#"label_replace(some_metric, "2tag", "$1", "tag_0", "(.*)")"#,
+            r#"label_join(some_metric, "tag-2", ",", "tag_0")"#,
+            "holt_winters(some_metric[5m], 1, 0.1)",
+            "holt_winters(some_metric[5m], 0.5, 0)",
+        ];
+        for query in cases {
+            let eval_stmt = EvalStmt {
+                expr: parser::parse(query).unwrap(),
+                start: UNIX_EPOCH,
+                end: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(100_000))
+                    .unwrap(),
+                interval: Duration::from_secs(5),
+                lookback_delta: Duration::from_secs(1),
+            };
+            let table_provider = build_test_table_provider("some_metric".to_string(), 2, 1).await;
+            let err = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+                .await
+                .unwrap_err();
+            assert_eq!(err.status_code(), StatusCode::InvalidArguments, "{query}");
+        }
+    }
+
     #[tokio::test]
     async fn binary_op_literal_column() {
         let query = r#"1 + some_metric{tag_0="bar"}"#;
@@ -1674,12 +2663,47 @@ mod test {
     }
 
     #[tokio::test]
-    #[ignore = "pure literal arithmetic is not supported yet"]
     async fn binary_op_literal_literal() {
-        let query = r#"1 + 1"#;
-        let expected = String::from("");
-
-        indie_query_plan_compare(query, expected).await;
+        let cases = [
+            (
+                "1",
+                "Float64(1) AS value [time:Timestamp(Millisecond, None), value:Float64]",
+            ),
+            (
+                r#""foo""#,
+                "Utf8(\"foo\") AS value [time:Timestamp(Millisecond, None), value:Utf8]",
+            ),
+            ("1 + 1", "Float64(1) + Float64(1) AS value"),
+            (
+                "(2 * 3) - 1",
+                "Float64(2) * Float64(3) - Float64(1) AS value",
+            ),
+            (
+                "1 > bool 2",
+                "CAST(Float64(1) > Float64(2) AS Float64) AS value",
+            ),
+        ];
+        for (query, expected) in cases {
+            let eval_stmt = EvalStmt {
+                expr: parser::parse(query).unwrap(),
+                start: UNIX_EPOCH,
+                end: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(100_000))
+                    .unwrap(),
+                interval: Duration::from_secs(5),
+                lookback_delta: Duration::from_secs(1),
+            };
+            let table_provider = build_test_table_provider("some_metric".to_string(), 1, 1).await;
+            let plan = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+                .await
+                .unwrap();
+            let plan = plan.display_indent_schema().to_string();
+            assert!(plan.contains(expected), "{query}: {plan}");
+            assert!(
+                plan.contains("EmptyMetric: range=[0..100000000], interval=[5000]"),
+                "{query}: {plan}"
+            );
+        }
     }
 
     #[tokio::test]
@@ -1733,13 +2757,43 @@ mod test {
             \n      PromSeriesNormalize: offset=[0], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
             \n        PromSeriesDivide: tags=[\"tag_0\"] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
             \n          Sort: some_metric.tag_0 DESC NULLS LAST, some_metric.timestamp DESC NULLS LAST [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n            Filter: some_metric.timestamp >= TimestampMillisecond(-1000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n              TableScan: some_metric, unsupported_filters=[timestamp >= TimestampMillisecond(-1000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
+            \n            Filter: some_metric.timestamp >= TimestampMillisecond(-300000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n              TableScan: some_metric, unsupported_filters=[timestamp >= TimestampMillisecond(-300000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
         );
 
         indie_query_plan_compare(query, expected).await;
     }
 
+    #[tokio::test]
+    async fn absent_label_matcher() {
+        let cases = [
+            (r#"some_metric{foo="bar"}"#, "Boolean(false)"),
+            (r#"some_metric{foo!="bar"}"#, "Boolean(true)"),
+            (r#"some_metric{foo=~"bar|"}"#, "Boolean(true)"),
+            (r#"some_metric{foo!~".+"}"#, "Boolean(true)"),
+        ];
+        for (query, filter) in cases {
+            let eval_stmt = EvalStmt {
+                expr: parser::parse(query).unwrap(),
+                start: UNIX_EPOCH,
+                end: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(100_000))
+                    .unwrap(),
+                interval: Duration::from_secs(5),
+                lookback_delta: Duration::from_secs(1),
+            };
+            let table_provider = build_test_table_provider("some_metric".to_string(), 1, 1).await;
+            let plan = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+                .await
+                .unwrap();
+            let plan = plan.display_indent_schema().to_string();
+            assert!(
+                plan.contains(&format!("Filter: {filter} AND some_metric.timestamp")),
+                "{query}: {plan}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn less_filter_on_value() {
         let query = "some_metric < 1.2345";
@@ -1766,13 +2820,81 @@ mod test {
             \n      PromSeriesNormalize: offset=[0], time index=[timestamp] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
             \n        PromSeriesDivide: tags=[\"tag_0\"] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
             \n          Sort: some_metric.tag_0 DESC NULLS LAST, some_metric.timestamp DESC NULLS LAST [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n            Filter: some_metric.timestamp >= TimestampMillisecond(-1000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
-            \n              TableScan: some_metric, unsupported_filters=[timestamp >= TimestampMillisecond(-1000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
+            \n            Filter: some_metric.timestamp >= TimestampMillisecond(-300000, None) AND some_metric.timestamp <= TimestampMillisecond(100000000, None) [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]\
+            \n              TableScan: some_metric, unsupported_filters=[timestamp >= TimestampMillisecond(-300000, None), timestamp <= TimestampMillisecond(100000000, None)] [tag_0:Utf8, timestamp:Timestamp(Millisecond, None), field_0:Float64;N]"
         );
 
         indie_query_plan_compare(query, expected).await;
     }
 
+    #[tokio::test]
+    async fn subquery() {
+        let cases = [
+            (
+                "max_over_time(rate(some_metric[5m])[1h:1m] offset 10m)",
+                vec![
+                    "PromRangeManipulate: req range=[0..100000000], interval=[5000], eval range=[3600000], time index=[timestamp]",
+                    "PromSeriesNormalize: offset=[-600000], time index=[timestamp]",
+                    "PromRangeManipulate: req range=[-4200000..99400000], interval=[60000], eval range=[300000], time index=[timestamp]",
+                ],
+            ),
+            (
+                // the inner steps are aligned to multiples of the step
+                "avg_over_time(some_metric[1h:7m])",
+                vec![
+                    "PromRangeManipulate: req range=[0..100000000], interval=[5000], eval range=[3600000], time index=[timestamp]",
+                    "PromInstantManipulate: range=[-3360000..100000000], lookback=[1000], interval=[420000], time index=[timestamp]",
+                ],
+            ),
+            (
+                // aggregation inside a subquery using the default step
+                "max_over_time(sum by (tag_0) (some_metric)[30m:])",
+                vec![
+                    "PromRangeManipulate: req range=[0..100000000], interval=[5000], eval range=[1800000], time index=[timestamp]",
+                    "PromSeriesDivide: tags=[\"tag_0\"]",
+                    "PromInstantManipulate: range=[-1800000..100000000], lookback=[1000], interval=[60000], time index=[timestamp]",
+                ],
+            ),
+        ];
+        for (query, expected) in cases {
+            let eval_stmt = EvalStmt {
+                expr: parser::parse(query).unwrap(),
+                start: UNIX_EPOCH,
+                end: UNIX_EPOCH
+                    .checked_add(Duration::from_secs(100_000))
+                    .unwrap(),
+                interval: Duration::from_secs(5),
+                lookback_delta: Duration::from_secs(1),
+            };
+            let table_provider = build_test_table_provider("some_metric".to_string(), 1, 1).await;
+            let plan = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+                .await
+                .unwrap();
+            let plan = plan.display_indent_schema().to_string();
+            for node in expected {
+                assert!(plan.contains(node), "{query}: {plan}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn subquery_with_at_modifier() {
+        let eval_stmt = EvalStmt {
+            expr: parser::parse("max_over_time(some_metric[5m:1m] @ 123)").unwrap(),
+            start: UNIX_EPOCH,
+            end: UNIX_EPOCH
+                .checked_add(Duration::from_secs(100_000))
+                .unwrap(),
+            interval: Duration::from_secs(5),
+            lookback_delta: Duration::from_secs(1),
+        };
+        let table_provider = build_test_table_provider("some_metric".to_string(), 1, 1).await;
+        let err = PromPlanner::stmt_to_plan(table_provider, eval_stmt)
+            .await
+            .unwrap_err();
+        assert_eq!(StatusCode::Unsupported, err.status_code());
+    }
+
     #[tokio::test]
     async fn value_matcher() {
         // template
@@ -68,8 +68,12 @@ impl DatafusionQueryEngine {
         Self { state }
     }
 
-    async fn exec_query_plan(&self, plan: LogicalPlan) -> Result<Output> {
-        let mut ctx = QueryEngineContext::new(self.state.session_state());
+    async fn exec_query_plan(
+        &self,
+        plan: LogicalPlan,
+        query_ctx: QueryContextRef,
+    ) -> Result<Output> {
+        let mut ctx = QueryEngineContext::new(self.state.session_state(), query_ctx);
 
         // `create_physical_plan` will optimize logical plan internally
         let physical_plan = self.create_physical_plan(&mut ctx, &plan).await?;
@@ -96,7 +100,7 @@ impl DatafusionQueryEngine {
         let table = self.find_table(&table_name).await?;
 
         let output = self
-            .exec_query_plan(LogicalPlan::DfPlan((*dml.input).clone()))
+            .exec_query_plan(LogicalPlan::DfPlan((*dml.input).clone()), query_ctx)
             .await?;
         let mut stream = match output {
             Output::RecordBatches(batches) => batches.as_stream(),
@@ -227,7 +231,7 @@ impl QueryEngine for DatafusionQueryEngine {
             LogicalPlan::DfPlan(DfLogicalPlan::Dml(dml)) => {
                 self.exec_dml_statement(dml, query_ctx).await
             }
-            _ => self.exec_query_plan(plan).await,
+            _ => self.exec_query_plan(plan, query_ctx).await,
         }
     }
 
@@ -345,7 +349,7 @@ impl QueryExecutor for DatafusionQueryEngine {
         match plan.output_partitioning().partition_count() {
             0 => Ok(Box::pin(EmptyRecordBatchStream::new(plan.schema()))),
             1 => Ok(plan
-                .execute(0, ctx.state().task_ctx())
+                .execute(0, ctx.task_ctx())
                 .context(error::ExecutePhysicalPlanSnafu)
                 .map_err(BoxedError::new)
                 .context(QueryExecutionSnafu))?,
@@ -356,7 +360,7 @@ impl QueryExecutor for DatafusionQueryEngine {
                 // CoalescePartitionsExec must produce a single partition
                 assert_eq!(1, plan.output_partitioning().partition_count());
                 let df_stream = plan
-                    .execute(0, ctx.state().task_ctx())
+                    .execute(0, ctx.task_ctx())
                     .context(error::DatafusionSnafu {
                         msg: "Failed to execute DataFusion merge exec",
                     })
@@ -379,12 +383,13 @@ mod tests {
     use catalog::local::{MemoryCatalogProvider, MemorySchemaProvider};
     use catalog::{CatalogList, CatalogProvider, SchemaProvider};
     use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
+    use common_error::prelude::{ErrorExt, StatusCode};
     use common_query::Output;
     use common_recordbatch::util;
     use datatypes::prelude::ConcreteDataType;
     use datatypes::schema::ColumnSchema;
     use datatypes::vectors::{UInt64Vector, VectorRef};
-    use session::context::QueryContext;
+    use session::context::{QueryContext, QueryLimits};
     use table::table::numbers::NumbersTable;
 
     use crate::parser::QueryLanguageParser;
@@ -468,6 +473,30 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_execute_with_memory_limit() {
+        let engine = create_test_engine();
+        let sql = "select number, count(*) from numbers group by number";
+
+        let query_ctx = QueryContext::arc();
+        query_ctx.set_query_limits(QueryLimits {
+            statement_timeout: None,
+            memory_limit: Some(1),
+        });
+        let stmt = QueryLanguageParser::parse_sql(sql).unwrap();
+        let plan = engine
+            .planner()
+            .plan(stmt, query_ctx.clone())
+            .await
+            .unwrap();
+
+        let Output::Stream(stream) = engine.execute(plan, query_ctx).await.unwrap() else {
+            unreachable!()
+        };
+        let err = util::collect(stream).await.unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::ExceedMemoryLimit);
+    }
+
     #[tokio::test]
     async fn test_describe() {
         let engine = create_test_engine();
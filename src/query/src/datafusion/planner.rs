@@ -20,20 +20,29 @@ use arrow_schema::DataType;
 use catalog::table_source::DfTableSourceProvider;
 use common_query::logical_plan::create_aggregate_function;
 use datafusion::catalog::TableReference;
+use datafusion::datasource::provider_as_source;
+use datafusion::datasource::view::ViewTable;
 use datafusion::error::Result as DfResult;
 use datafusion::execution::context::SessionState;
 use datafusion::physical_plan::udaf::AggregateUDF;
 use datafusion::physical_plan::udf::ScalarUDF;
-use datafusion::sql::planner::ContextProvider;
+use datafusion::sql::planner::{ContextProvider, ParserOptions, SqlToRel};
 use datafusion_common::config::ConfigOptions;
 use datafusion_common::{DataFusionError, OwnedTableReference};
 use datafusion_expr::TableSource;
 use datafusion_physical_expr::var_provider::{is_system_variables, VarType};
 use datafusion_sql::parser::Statement as DfStatement;
-use session::context::QueryContextRef;
-use snafu::ResultExt;
-
-use crate::error::{CatalogSnafu, DataFusionSnafu, Result};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use session::context::{QueryContext, QueryContextRef};
+use snafu::{ensure, ResultExt};
+use sql::statements::statement::Statement;
+
+use crate::error::{
+    CatalogSnafu, DataFusionSnafu, InvalidViewDefinitionSnafu, PlanSqlSnafu, RecursiveViewSnafu,
+    Result, SqlSnafu,
+};
+use crate::parser::{QueryLanguageParser, QueryStatement};
 use crate::query_engine::QueryEngineState;
 
 pub struct DfContextProviderAdapter {
@@ -50,30 +59,55 @@ impl DfContextProviderAdapter {
         df_stmt: &DfStatement,
         query_ctx: QueryContextRef,
     ) -> Result<Self> {
-        let table_names = session_state
-            .resolve_table_references(df_stmt)
-            .context(DataFusionSnafu)?;
-
-        let mut table_provider = DfTableSourceProvider::new(
-            engine_state.catalog_list().clone(),
-            engine_state.disallow_cross_schema_query(),
-            query_ctx.as_ref(),
-        );
-
-        let tables = resolve_tables(table_names, &mut table_provider).await?;
-
-        Ok(Self {
-            engine_state,
-            session_state,
-            tables,
-            table_provider,
-        })
+        Self::try_new_with_views(engine_state, session_state, df_stmt, query_ctx, Vec::new()).await
+    }
+
+    /// Creates the adapter for planning `df_stmt`, which is the definition of the last view in
+    /// `expanding_views` if it's not empty.
+    fn try_new_with_views(
+        engine_state: Arc<QueryEngineState>,
+        session_state: SessionState,
+        df_stmt: &DfStatement,
+        query_ctx: QueryContextRef,
+        expanding_views: Vec<String>,
+    ) -> BoxFuture<'_, Result<Self>> {
+        async move {
+            let table_names = session_state
+                .resolve_table_references(df_stmt)
+                .context(DataFusionSnafu)?;
+
+            let mut table_provider = DfTableSourceProvider::new(
+                engine_state.catalog_list().clone(),
+                engine_state.disallow_cross_schema_query(),
+                query_ctx.as_ref(),
+            );
+
+            let tables = resolve_tables(
+                table_names,
+                &mut table_provider,
+                &engine_state,
+                &session_state,
+                &expanding_views,
+            )
+            .await?;
+
+            Ok(Self {
+                engine_state,
+                session_state,
+                tables,
+                table_provider,
+            })
+        }
+        .boxed()
     }
 }
 
 async fn resolve_tables(
     table_names: Vec<OwnedTableReference>,
     table_provider: &mut DfTableSourceProvider,
+    engine_state: &Arc<QueryEngineState>,
+    session_state: &SessionState,
+    expanding_views: &[String],
 ) -> Result<HashMap<String, Arc<dyn TableSource>>> {
     let mut tables = HashMap::with_capacity(table_names.len());
 
@@ -81,12 +115,35 @@ async fn resolve_tables(
         let resolved_name = table_provider
             .resolve_table_ref(table_name.clone())
             .context(CatalogSnafu)?;
-
-        if let Entry::Vacant(v) = tables.entry(resolved_name.to_string()) {
-            let table = table_provider
-                .resolve_table(table_name)
-                .await
-                .context(CatalogSnafu)?;
+        let view_ctx = Arc::new(QueryContext::with(
+            resolved_name.catalog.as_ref(),
+            resolved_name.schema.as_ref(),
+        ));
+        let resolved_name = resolved_name.to_string();
+
+        if let Entry::Vacant(v) = tables.entry(resolved_name.clone()) {
+            let table = match table_provider.resolve_table(table_name.clone()).await {
+                Ok(table) => table,
+                // Tables shadow views with the same name.
+                Err(e @ catalog::error::Error::TableNotExist { .. }) => {
+                    let Some(definition) = table_provider
+                        .resolve_view(table_name)
+                        .await
+                        .context(CatalogSnafu)? else {
+                        return Err(e).context(CatalogSnafu);
+                    };
+                    expand_view(
+                        engine_state,
+                        session_state,
+                        resolved_name,
+                        definition,
+                        view_ctx,
+                        expanding_views,
+                    )
+                    .await?
+                }
+                Err(e) => return Err(e).context(CatalogSnafu),
+            };
 
             v.insert(table);
         }
@@ -94,6 +151,58 @@ async fn resolve_tables(
     Ok(tables)
 }
 
+/// Plans the definition of the view within the view's catalog and schema.
+async fn expand_view(
+    engine_state: &Arc<QueryEngineState>,
+    session_state: &SessionState,
+    view_name: String,
+    definition: String,
+    view_ctx: QueryContextRef,
+    expanding_views: &[String],
+) -> Result<Arc<dyn TableSource>> {
+    ensure!(
+        !expanding_views.contains(&view_name),
+        RecursiveViewSnafu { view: view_name }
+    );
+
+    let stmt = match QueryLanguageParser::parse_sql(&definition)? {
+        QueryStatement::Sql(stmt @ Statement::Query(_)) => stmt,
+        _ => {
+            return InvalidViewDefinitionSnafu {
+                view: view_name,
+                definition,
+            }
+            .fail()
+        }
+    };
+    let df_stmt = (&stmt).try_into().context(SqlSnafu)?;
+
+    let mut expanding_views = expanding_views.to_vec();
+    expanding_views.push(view_name);
+    let context_provider = DfContextProviderAdapter::try_new_with_views(
+        engine_state.clone(),
+        session_state.clone(),
+        &df_stmt,
+        view_ctx,
+        expanding_views,
+    )
+    .await?;
+
+    let config_options = session_state.config().options();
+    let parser_options = ParserOptions {
+        enable_ident_normalization: config_options.sql_parser.enable_ident_normalization,
+        parse_float_as_decimal: config_options.sql_parser.parse_float_as_decimal,
+    };
+    let plan = SqlToRel::new_with_options(&context_provider, parser_options)
+        .statement_to_plan(df_stmt)
+        .context(PlanSqlSnafu {
+            sql: definition.clone(),
+        })?;
+
+    let view = ViewTable::try_new(plan, Some(definition)).context(DataFusionSnafu)?;
+    Ok(provider_as_source(Arc::new(view)))
+}
+
 impl ContextProvider for DfContextProviderAdapter {
     fn get_table_provider(&self, name: TableReference) -> DfResult<Arc<dyn TableSource>> {
         let table_ref = self.table_provider.resolve_table_ref(name)?;
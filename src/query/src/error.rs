@@ -105,6 +105,16 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("View {} refers to itself", view))]
+    RecursiveView { view: String, location: Location },
+
+    #[snafu(display("Definition of view {} is not a query: {}", view, definition))]
+    InvalidViewDefinition {
+        view: String,
+        definition: String,
+        location: Location,
+    },
+
     #[snafu(display("Timestamp column for table '{table_name}' is missing!"))]
     MissingTimestampColumn {
         table_name: String,
@@ -133,7 +143,8 @@ impl ErrorExt for Error {
             QueryExecution { source } | QueryPlan { source } => source.status_code(),
             DataFusion { .. } | MissingTimestampColumn { .. } => StatusCode::Internal,
             Sql { source } => source.status_code(),
-            PlanSql { .. } => StatusCode::PlanQuery,
+            PlanSql { .. } | RecursiveView { .. } => StatusCode::PlanQuery,
+            InvalidViewDefinition { .. } => StatusCode::Unexpected,
         }
     }
 
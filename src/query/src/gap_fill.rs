@@ -0,0 +1,54 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Gap filling of aggregating queries that group rows into time buckets by
+//! `time_bucket_gapfill`. The buckets without any rows are added to the output, and their
+//! values are filled according to `locf` and `interpolate` in the projection.
+
+mod plan;
+mod rule;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::error::Result as DfResult;
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{LogicalPlan, UserDefinedLogicalNode};
+use datafusion::physical_plan::planner::ExtensionPlanner;
+use datafusion::physical_plan::{ExecutionPlan, PhysicalPlanner};
+pub use plan::{FillStrategy, GapFill, GapFillExec, GapFillStream};
+pub use rule::GapFillRule;
+
+/// Max number of buckets gap filling fills, for all groups in total. It bounds the rows the gap
+/// filling produces, as they are all held in memory.
+const MAX_GAP_FILL_BUCKETS: i64 = 100_000;
+
+pub struct GapFillExtensionPlanner;
+
+#[async_trait]
+impl ExtensionPlanner for GapFillExtensionPlanner {
+    async fn plan_extension(
+        &self,
+        _planner: &dyn PhysicalPlanner,
+        node: &dyn UserDefinedLogicalNode,
+        _logical_inputs: &[&LogicalPlan],
+        physical_inputs: &[Arc<dyn ExecutionPlan>],
+        _session_state: &SessionState,
+    ) -> DfResult<Option<Arc<dyn ExecutionPlan>>> {
+        Ok(node
+            .as_any()
+            .downcast_ref::<GapFill>()
+            .map(|node| node.to_execution_plan(physical_inputs[0].clone())))
+    }
+}
@@ -0,0 +1,742 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use common_function::scalars::gap_fill::bucket_start;
+use datafusion::arrow::array::{Array, ArrayRef, Float64Array, Int64Array, UInt64Array};
+use datafusion::arrow::compute;
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::common::{DFField, DFSchema, DFSchemaRef, ScalarValue};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::TaskContext;
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion::physical_expr::PhysicalSortExpr;
+use datafusion::physical_plan::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use datafusion::physical_plan::{
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use futures::{ready, Stream, StreamExt};
+
+use crate::gap_fill::MAX_GAP_FILL_BUCKETS;
+
+/// How to fill the values of the time buckets without rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillStrategy {
+    /// Fills with nulls.
+    Null,
+    /// Fills with the value of the last bucket before, by `locf`.
+    Prev,
+    /// Fills by linear interpolation between the non-null buckets around, by `interpolate`.
+    Linear,
+}
+
+/// Fills the gaps in the output of an aggregation that groups rows into time buckets.
+///
+/// For each group, a row is added for every bucket in `[start, end)` without one. The time
+/// bucket and group columns of added rows are set accordingly, and the other columns are filled
+/// by their [FillStrategy]. Rows out of `[start, end)` are kept as is.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct GapFill {
+    /// Index of the time bucket column.
+    time_index: usize,
+    /// Indices of the other columns in `GROUP BY`.
+    group_indices: Vec<usize>,
+    /// Columns not filled with nulls, and how they are filled.
+    fills: Vec<(usize, FillStrategy)>,
+    /// Width of buckets, in the unit of the time bucket column, same as `start` and `end`.
+    width: i64,
+    start: i64,
+    end: i64,
+
+    input: LogicalPlan,
+    output_schema: DFSchemaRef,
+}
+
+impl GapFill {
+    pub fn try_new(
+        time_index: usize,
+        group_indices: Vec<usize>,
+        fills: Vec<(usize, FillStrategy)>,
+        width: i64,
+        start: i64,
+        end: i64,
+        input: LogicalPlan,
+    ) -> DataFusionResult<Self> {
+        let input_schema = input.schema();
+        // The filled columns are nullable, as gaps may not be filled.
+        let fields = input_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                if index == time_index || group_indices.contains(&index) {
+                    field.clone()
+                } else {
+                    DFField::new(
+                        field.qualifier().cloned(),
+                        field.name(),
+                        field.data_type().clone(),
+                        true,
+                    )
+                }
+            })
+            .collect();
+        let output_schema = Arc::new(DFSchema::new_with_metadata(
+            fields,
+            input_schema.metadata().clone(),
+        )?);
+
+        Ok(Self {
+            time_index,
+            group_indices,
+            fills,
+            width,
+            start,
+            end,
+            input,
+            output_schema,
+        })
+    }
+
+    pub fn to_execution_plan(&self, exec_input: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        Arc::new(GapFillExec {
+            time_index: self.time_index,
+            group_indices: self.group_indices.clone(),
+            fills: self.fills.clone(),
+            width: self.width,
+            start: self.start,
+            end: self.end,
+            input: exec_input,
+            output_schema: SchemaRef::new(self.output_schema.as_ref().into()),
+            metric: ExecutionPlanMetricsSet::new(),
+        })
+    }
+}
+
+impl UserDefinedLogicalNodeCore for GapFill {
+    fn name(&self) -> &str {
+        "GapFill"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.output_schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fields = self.output_schema.fields();
+        let fills = self
+            .fills
+            .iter()
+            .map(|(index, strategy)| format!("{}: {strategy:?}", fields[*index].name()))
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "GapFill: time index=[{}], width=[{}], range=[{}..{}], fills={:?}",
+            fields[self.time_index].name(),
+            self.width,
+            self.start,
+            self.end,
+            fills
+        )
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        assert!(!inputs.is_empty());
+
+        Self {
+            time_index: self.time_index,
+            group_indices: self.group_indices.clone(),
+            fills: self.fills.clone(),
+            width: self.width,
+            start: self.start,
+            end: self.end,
+            input: inputs[0].clone(),
+            output_schema: self.output_schema.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GapFillExec {
+    time_index: usize,
+    group_indices: Vec<usize>,
+    fills: Vec<(usize, FillStrategy)>,
+    width: i64,
+    start: i64,
+    end: i64,
+
+    input: Arc<dyn ExecutionPlan>,
+    output_schema: SchemaRef,
+    metric: ExecutionPlanMetricsSet,
+}
+
+impl ExecutionPlan for GapFillExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.output_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        // All rows of a group are required to find its gaps.
+        vec![Distribution::SinglePartition]
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        assert!(!children.is_empty());
+        Ok(Arc::new(Self {
+            time_index: self.time_index,
+            group_indices: self.group_indices.clone(),
+            fills: self.fills.clone(),
+            width: self.width,
+            start: self.start,
+            end: self.end,
+            input: children[0].clone(),
+            output_schema: self.output_schema.clone(),
+            metric: self.metric.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        let baseline_metric = BaselineMetrics::new(&self.metric, partition);
+
+        let input = self.input.execute(partition, context)?;
+        Ok(Box::pin(GapFillStream {
+            time_index: self.time_index,
+            group_indices: self.group_indices.clone(),
+            fills: self.fills.clone(),
+            width: self.width,
+            start: self.start,
+            end: self.end,
+            output_schema: self.output_schema.clone(),
+            input,
+            buffer: vec![],
+            finished: false,
+            metric: baseline_metric,
+        }))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(
+                    f,
+                    "GapFillExec: time index=[{}], width=[{}], range=[{}..{}]",
+                    self.output_schema.field(self.time_index).name(),
+                    self.width,
+                    self.start,
+                    self.end
+                )
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metric.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// A row of the output, either from the input or added for a gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Slot {
+    time: Option<i64>,
+    /// Index of the input row, `None` for gaps.
+    row: Option<usize>,
+}
+
+/// Slots of a group.
+struct GroupSlots {
+    range: Range<usize>,
+    /// Index of any input row in the group, it's `None` only if the input is empty.
+    row: Option<usize>,
+}
+
+pub struct GapFillStream {
+    time_index: usize,
+    group_indices: Vec<usize>,
+    fills: Vec<(usize, FillStrategy)>,
+    width: i64,
+    start: i64,
+    end: i64,
+
+    output_schema: SchemaRef,
+    input: SendableRecordBatchStream,
+    /// Input batches collected so far, gaps are filled after all of them are collected.
+    buffer: Vec<RecordBatch>,
+    finished: bool,
+    metric: BaselineMetrics,
+}
+
+impl RecordBatchStream for GapFillStream {
+    fn schema(&self) -> SchemaRef {
+        self.output_schema.clone()
+    }
+}
+
+impl Stream for GapFillStream {
+    type Item = DataFusionResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+        loop {
+            match ready!(self.input.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => self.buffer.push(batch),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    self.finished = true;
+                    let batches = std::mem::take(&mut self.buffer);
+                    let result = {
+                        let _timer = self.metric.elapsed_compute().timer();
+                        self.fill(&batches)
+                    };
+                    return self.metric.record_poll(Poll::Ready(Some(result)));
+                }
+            }
+        }
+    }
+}
+
+impl GapFillStream {
+    pub fn fill(&self, batches: &[RecordBatch]) -> DataFusionResult<RecordBatch> {
+        let input = compute::concat_batches(&self.input.schema(), batches)?;
+        let times = compute::cast(input.column(self.time_index), &DataType::Int64)?;
+        // Safety: it's just casted to Int64.
+        let times = times.as_any().downcast_ref::<Int64Array>().unwrap();
+
+        let (slots, groups) = self.build_slots(&input, times)?;
+
+        let mut columns = Vec::with_capacity(input.num_columns());
+        for (index, field) in self.output_schema.fields().iter().enumerate() {
+            let column = input.column(index);
+            let column = if index == self.time_index {
+                let times: ArrayRef =
+                    Arc::new(slots.iter().map(|slot| slot.time).collect::<Int64Array>());
+                compute::cast(&times, field.data_type())?
+            } else if self.group_indices.contains(&index) {
+                let indices = groups
+                    .iter()
+                    .flat_map(|group| group.range.clone().map(|_| group.row.map(|x| x as u64)))
+                    .collect::<UInt64Array>();
+                compute::take(column, &indices, None)?
+            } else {
+                match self.fill_strategy(index) {
+                    FillStrategy::Null => {
+                        let indices = slots
+                            .iter()
+                            .map(|slot| slot.row.map(|x| x as u64))
+                            .collect::<UInt64Array>();
+                        compute::take(column, &indices, None)?
+                    }
+                    FillStrategy::Prev => {
+                        let indices = groups
+                            .iter()
+                            .flat_map(|group| {
+                                slots[group.range.clone()].iter().scan(None, |prev, slot| {
+                                    *prev = slot.row.or(*prev);
+                                    Some(prev.map(|x| x as u64))
+                                })
+                            })
+                            .collect::<UInt64Array>();
+                        compute::take(column, &indices, None)?
+                    }
+                    FillStrategy::Linear => interpolate(column, &slots, &groups)?,
+                }
+            };
+            columns.push(column);
+        }
+
+        RecordBatch::try_new(self.output_schema.clone(), columns)
+            .map_err(DataFusionError::ArrowError)
+    }
+
+    fn fill_strategy(&self, index: usize) -> FillStrategy {
+        self.fills
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, strategy)| *strategy)
+            .unwrap_or(FillStrategy::Null)
+    }
+
+    /// Groups the input rows, and lays out the output rows of each group in time order.
+    fn build_slots(
+        &self,
+        input: &RecordBatch,
+        times: &Int64Array,
+    ) -> DataFusionResult<(Vec<Slot>, Vec<GroupSlots>)> {
+        let mut group_rows: Vec<Vec<usize>> = vec![];
+        let mut group_ids: HashMap<Vec<ScalarValue>, usize> = HashMap::new();
+        for row in 0..input.num_rows() {
+            let key = self
+                .group_indices
+                .iter()
+                .map(|index| ScalarValue::try_from_array(input.column(*index), row))
+                .collect::<DataFusionResult<Vec<_>>>()?;
+            let id = *group_ids.entry(key).or_insert_with(|| {
+                group_rows.push(vec![]);
+                group_rows.len() - 1
+            });
+            group_rows[id].push(row);
+        }
+        // Without other columns in `GROUP BY`, all buckets are filled even if there is no row.
+        if group_rows.is_empty() && self.group_indices.is_empty() {
+            group_rows.push(vec![]);
+        }
+
+        let mut slots = Vec::with_capacity(input.num_rows());
+        let mut groups = Vec::with_capacity(group_rows.len());
+        // Number of buckets filled for all groups so far.
+        let mut filled = 0;
+        for rows in group_rows {
+            let begin = slots.len();
+            let (mut timed_rows, untimed_rows): (Vec<_>, Vec<_>) =
+                rows.iter().partition(|row| times.is_valid(**row));
+            timed_rows.sort_by_key(|row| times.value(*row));
+
+            let mut bucket = bucket_start(self.start, self.width);
+            for row in timed_rows {
+                let time = times.value(row);
+                while bucket < self.end && bucket < time {
+                    slots.push(Slot {
+                        time: Some(bucket),
+                        row: None,
+                    });
+                    bucket += self.width;
+                }
+                if bucket == time {
+                    bucket += self.width;
+                }
+                slots.push(Slot {
+                    time: Some(time),
+                    row: Some(row),
+                });
+            }
+            while bucket < self.end {
+                slots.push(Slot {
+                    time: Some(bucket),
+                    row: None,
+                });
+                bucket += self.width;
+            }
+            slots.extend(untimed_rows.into_iter().map(|row| Slot {
+                time: None,
+                row: Some(row),
+            }));
+
+            // Each group fills a bounded number of buckets, so it's enough to check the total
+            // after filling a group.
+            filled += slots.len() - begin - rows.len();
+            if filled > MAX_GAP_FILL_BUCKETS as usize {
+                return Err(DataFusionError::Execution(format!(
+                    "Gap filling fills more than {MAX_GAP_FILL_BUCKETS} buckets for all groups, use a wider bucket, a shorter time range or fewer groups"
+                )));
+            }
+
+            groups.push(GroupSlots {
+                range: begin..slots.len(),
+                row: rows.first().copied(),
+            });
+        }
+        Ok((slots, groups))
+    }
+}
+
+/// Fills the gaps by linear interpolation between the closest non-null values before and after
+/// them in the same group. Gaps at the beginning or the end of the group are left null.
+fn interpolate(
+    column: &ArrayRef,
+    slots: &[Slot],
+    groups: &[GroupSlots],
+) -> DataFusionResult<ArrayRef> {
+    let values = compute::cast(column, &DataType::Float64)?;
+    // Safety: it's just casted to Float64.
+    let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+    let point = |slot: &Slot| {
+        slot.row
+            .filter(|row| values.is_valid(*row))
+            .zip(slot.time)
+            .map(|(row, time)| (time, values.value(row)))
+    };
+
+    let mut result = Vec::with_capacity(slots.len());
+    for group in groups {
+        let slots = &slots[group.range.clone()];
+        let mut next_points = vec![None; slots.len()];
+        let mut next = None;
+        for (i, slot) in slots.iter().enumerate().rev() {
+            next_points[i] = next;
+            next = point(slot).or(next);
+        }
+
+        let mut prev = None;
+        for (slot, next) in slots.iter().zip(next_points) {
+            let value = match (slot.row, slot.time, prev, next) {
+                (Some(row), ..) => values.is_valid(row).then(|| values.value(row)),
+                (None, Some(t), Some((t0, v0)), Some((t1, v1))) => {
+                    Some(v0 + (v1 - v0) * (t - t0) as f64 / (t1 - t0) as f64)
+                }
+                _ => None,
+            };
+            prev = point(slot).or(prev);
+            result.push(value);
+        }
+    }
+    let result: ArrayRef = Arc::new(Float64Array::from(result));
+    compute::cast(&result, column.data_type()).map_err(DataFusionError::ArrowError)
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::{Float64Array, StringArray, TimestampMillisecondArray};
+    use datafusion::arrow::datatypes::{Field, Schema, TimeUnit};
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    fn prepare_input() -> (SchemaRef, Vec<RecordBatch>) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, false),
+            Field::new(
+                "bucket",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                true,
+            ),
+            Field::new("value", DataType::Float64, false),
+        ]));
+        let batches = vec![
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec!["a", "b", "a"])),
+                    Arc::new(TimestampMillisecondArray::from(vec![0, 10, 30])),
+                    Arc::new(Float64Array::from(vec![1.0, 5.0, 4.0])),
+                ],
+            )
+            .unwrap(),
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringArray::from(vec!["b", "a"])),
+                    Arc::new(TimestampMillisecondArray::from(vec![Some(50), None])),
+                    Arc::new(Float64Array::from(vec![7.0, 9.0])),
+                ],
+            )
+            .unwrap(),
+        ];
+        (schema, batches)
+    }
+
+    async fn do_fill(
+        group_indices: Vec<usize>,
+        strategy: FillStrategy,
+        batches: Vec<RecordBatch>,
+        schema: SchemaRef,
+    ) -> String {
+        let memory_exec = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None).unwrap());
+        let output_schema = Arc::new(Schema::new(
+            schema
+                .fields()
+                .iter()
+                .map(|f| {
+                    if f.name() == "value" {
+                        f.clone().with_nullable(true)
+                    } else {
+                        f.clone()
+                    }
+                })
+                .collect(),
+        ));
+        let gap_fill_exec = Arc::new(GapFillExec {
+            time_index: 1,
+            group_indices,
+            fills: vec![(2, strategy)],
+            width: 10,
+            start: 5,
+            end: 50,
+            input: memory_exec,
+            output_schema,
+            metric: ExecutionPlanMetricsSet::new(),
+        });
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(gap_fill_exec, session_context.task_ctx())
+            .await
+            .unwrap();
+        datafusion::arrow::util::pretty::pretty_format_batches(&result)
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn fill_with_nulls() {
+        let (schema, batches) = prepare_input();
+        let result = do_fill(vec![0], FillStrategy::Null, batches, schema).await;
+        let expected = String::from(
+            "+------+-------------------------+-------+\
+            \n| host | bucket                  | value |\
+            \n+------+-------------------------+-------+\
+            \n| a    | 1970-01-01T00:00:00     | 1.0   |\
+            \n| a    | 1970-01-01T00:00:00.010 |       |\
+            \n| a    | 1970-01-01T00:00:00.020 |       |\
+            \n| a    | 1970-01-01T00:00:00.030 | 4.0   |\
+            \n| a    | 1970-01-01T00:00:00.040 |       |\
+            \n| a    |                         | 9.0   |\
+            \n| b    | 1970-01-01T00:00:00     |       |\
+            \n| b    | 1970-01-01T00:00:00.010 | 5.0   |\
+            \n| b    | 1970-01-01T00:00:00.020 |       |\
+            \n| b    | 1970-01-01T00:00:00.030 |       |\
+            \n| b    | 1970-01-01T00:00:00.040 |       |\
+            \n| b    | 1970-01-01T00:00:00.050 | 7.0   |\
+            \n+------+-------------------------+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn fill_with_prev() {
+        let (schema, batches) = prepare_input();
+        let result = do_fill(vec![0], FillStrategy::Prev, batches, schema).await;
+        let expected = String::from(
+            "+------+-------------------------+-------+\
+            \n| host | bucket                  | value |\
+            \n+------+-------------------------+-------+\
+            \n| a    | 1970-01-01T00:00:00     | 1.0   |\
+            \n| a    | 1970-01-01T00:00:00.010 | 1.0   |\
+            \n| a    | 1970-01-01T00:00:00.020 | 1.0   |\
+            \n| a    | 1970-01-01T00:00:00.030 | 4.0   |\
+            \n| a    | 1970-01-01T00:00:00.040 | 4.0   |\
+            \n| a    |                         | 9.0   |\
+            \n| b    | 1970-01-01T00:00:00     |       |\
+            \n| b    | 1970-01-01T00:00:00.010 | 5.0   |\
+            \n| b    | 1970-01-01T00:00:00.020 | 5.0   |\
+            \n| b    | 1970-01-01T00:00:00.030 | 5.0   |\
+            \n| b    | 1970-01-01T00:00:00.040 | 5.0   |\
+            \n| b    | 1970-01-01T00:00:00.050 | 7.0   |\
+            \n+------+-------------------------+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn fill_with_linear() {
+        let (schema, batches) = prepare_input();
+        let result = do_fill(vec![0], FillStrategy::Linear, batches, schema).await;
+        let expected = String::from(
+            "+------+-------------------------+-------+\
+            \n| host | bucket                  | value |\
+            \n+------+-------------------------+-------+\
+            \n| a    | 1970-01-01T00:00:00     | 1.0   |\
+            \n| a    | 1970-01-01T00:00:00.010 | 2.0   |\
+            \n| a    | 1970-01-01T00:00:00.020 | 3.0   |\
+            \n| a    | 1970-01-01T00:00:00.030 | 4.0   |\
+            \n| a    | 1970-01-01T00:00:00.040 |       |\
+            \n| a    |                         | 9.0   |\
+            \n| b    | 1970-01-01T00:00:00     |       |\
+            \n| b    | 1970-01-01T00:00:00.010 | 5.0   |\
+            \n| b    | 1970-01-01T00:00:00.020 | 5.5   |\
+            \n| b    | 1970-01-01T00:00:00.030 | 6.0   |\
+            \n| b    | 1970-01-01T00:00:00.040 | 6.5   |\
+            \n| b    | 1970-01-01T00:00:00.050 | 7.0   |\
+            \n+------+-------------------------+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn fill_empty_input_without_groups() {
+        let (schema, _) = prepare_input();
+        let schema = Arc::new(Schema::new(schema.fields()[1..].to_vec()));
+        let result = do_fill_without_groups(schema).await;
+        let expected = String::from(
+            "+-------------------------+-------+\
+            \n| bucket                  | value |\
+            \n+-------------------------+-------+\
+            \n| 1970-01-01T00:00:00     |       |\
+            \n| 1970-01-01T00:00:00.010 |       |\
+            \n| 1970-01-01T00:00:00.020 |       |\
+            \n| 1970-01-01T00:00:00.030 |       |\
+            \n| 1970-01-01T00:00:00.040 |       |\
+            \n+-------------------------+-------+",
+        );
+        assert_eq!(result, expected);
+    }
+
+    async fn do_fill_without_groups(schema: SchemaRef) -> String {
+        let memory_exec = Arc::new(MemoryExec::try_new(&[vec![]], schema.clone(), None).unwrap());
+        let output_schema = Arc::new(Schema::new(vec![
+            schema.field(0).clone(),
+            schema.field(1).clone().with_nullable(true),
+        ]));
+        let gap_fill_exec = Arc::new(GapFillExec {
+            time_index: 0,
+            group_indices: vec![],
+            fills: vec![(1, FillStrategy::Prev)],
+            width: 10,
+            start: 5,
+            end: 50,
+            input: memory_exec,
+            output_schema,
+            metric: ExecutionPlanMetricsSet::new(),
+        });
+        let session_context = SessionContext::default();
+        let result = datafusion::physical_plan::collect(gap_fill_exec, session_context.task_ctx())
+            .await
+            .unwrap();
+        datafusion::arrow::util::pretty::pretty_format_batches(&result)
+            .unwrap()
+            .to_string()
+    }
+}
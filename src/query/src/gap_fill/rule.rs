@@ -0,0 +1,301 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use common_function::scalars::gap_fill::{
+    bucket_start, parse_bucket_width, INTERPOLATE, LOCF, TIME_BUCKET_GAPFILL,
+};
+use common_time::timestamp::TimeUnit;
+use common_time::Timestamp;
+use datafusion::optimizer::optimizer::OptimizerRule;
+use datafusion::optimizer::OptimizerConfig;
+use datafusion_common::tree_node::{Transformed, TreeNode, VisitRecursion};
+use datafusion_common::{DataFusionError, Result, ScalarValue};
+use datafusion_expr::expr::Cast;
+use datafusion_expr::{Aggregate, Expr, Extension, Filter, LogicalPlan, Projection};
+use datatypes::arrow::datatypes::DataType;
+use datatypes::prelude::ConcreteDataType;
+
+use crate::gap_fill::{FillStrategy, GapFill, MAX_GAP_FILL_BUCKETS};
+
+/// GapFillRule puts a [GapFill] on the aggregation grouping rows by `time_bucket_gapfill`.
+///
+/// The aggregation must be followed by a projection, maybe with a filter from `HAVING`
+/// between them. The values of a column are filled by `locf` or `interpolate` if the projection
+/// applies them to the column, otherwise by nulls.
+pub struct GapFillRule;
+
+impl OptimizerRule for GapFillRule {
+    fn try_optimize(
+        &self,
+        plan: &LogicalPlan,
+        _config: &dyn OptimizerConfig,
+    ) -> Result<Option<LogicalPlan>> {
+        fill_gaps(plan).map(Some)
+    }
+
+    fn name(&self) -> &str {
+        "GapFillRule"
+    }
+}
+
+impl GapFillRule {
+    /// Checks the arguments of `time_bucket_gapfill`, `locf` and `interpolate` in the plan.
+    ///
+    /// It's called by the planner, as the optimizer skips the rules that fail, and the invalid
+    /// arguments would be ignored silently by [GapFillRule].
+    pub fn check(plan: &LogicalPlan) -> Result<()> {
+        fill_gaps(plan).map(|_| ())
+    }
+}
+
+fn fill_gaps(plan: &LogicalPlan) -> Result<LogicalPlan> {
+    plan.clone()
+        .transform_up(&|plan| {
+            let LogicalPlan::Projection(projection) = &plan else {
+                return Ok(Transformed::No(plan));
+            };
+            let input = match projection.input.as_ref() {
+                LogicalPlan::Filter(filter) => {
+                    match fill_aggregate(&filter.input, &projection.expr)? {
+                        Some(filled) => Some(LogicalPlan::Filter(Filter::try_new(
+                            filter.predicate.clone(),
+                            Arc::new(filled),
+                        )?)),
+                        None => None,
+                    }
+                }
+                input => fill_aggregate(input, &projection.expr)?,
+            };
+            match input {
+                Some(input) => Ok(Transformed::Yes(LogicalPlan::Projection(
+                    Projection::try_new(projection.expr.clone(), Arc::new(input))?,
+                ))),
+                None => Ok(Transformed::No(plan)),
+            }
+        })
+}
+
+/// Returns the aggregation with its gaps filled, or `None` if it doesn't need gap filling.
+fn fill_aggregate(plan: &LogicalPlan, projection: &[Expr]) -> Result<Option<LogicalPlan>> {
+    let LogicalPlan::Aggregate(aggregate) = plan else {
+        return Ok(None);
+    };
+    let mut buckets =
+        aggregate
+            .group_expr
+            .iter()
+            .enumerate()
+            .filter_map(|(index, expr)| match expr {
+                Expr::ScalarUDF { fun, args } if fun.name == TIME_BUCKET_GAPFILL => {
+                    Some((index, args))
+                }
+                _ => None,
+            });
+    let Some((time_index, args)) = buckets.next() else {
+        return Ok(None);
+    };
+    if buckets.next().is_some() {
+        return plan_err(format!(
+            "Only one {TIME_BUCKET_GAPFILL} is allowed in GROUP BY"
+        ));
+    }
+    if aggregate
+        .group_expr
+        .iter()
+        .any(|expr| matches!(expr, Expr::GroupingSet(_)))
+    {
+        return plan_err(format!(
+            "{TIME_BUCKET_GAPFILL} is not supported with grouping sets"
+        ));
+    }
+
+    // The group expressions are the first columns of the aggregation's output.
+    let unit = match aggregate.schema.field(time_index).data_type() {
+        DataType::Timestamp(unit, _) => match ConcreteDataType::from_arrow_time_unit(unit) {
+            ConcreteDataType::Timestamp(ts_type) => ts_type.unit(),
+            _ => unreachable!(),
+        },
+        data_type => {
+            return plan_err(format!(
+                "{TIME_BUCKET_GAPFILL} requires a timestamp, found {data_type:?}"
+            ))
+        }
+    };
+    let [width, _, start, end] = args.as_slice() else {
+        return plan_err(format!("{TIME_BUCKET_GAPFILL} requires 4 arguments"));
+    };
+    let width = match literal(width) {
+        Some(ScalarValue::Utf8(Some(width))) => parse_bucket_width(width, unit),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        DataFusionError::Plan(format!(
+            "Invalid bucket width {width} of {TIME_BUCKET_GAPFILL}, expect a duration like '5m' that is no less than one {unit:?}"
+        ))
+    })?;
+    let start = timestamp(start, unit)?;
+    let end = timestamp(end, unit)?;
+    if start >= end {
+        return plan_err(format!(
+            "The start of {TIME_BUCKET_GAPFILL} must be before the end"
+        ));
+    }
+    let buckets = bucket_count(width, start, end);
+    if buckets > MAX_GAP_FILL_BUCKETS as i128 {
+        return plan_err(format!(
+            "{TIME_BUCKET_GAPFILL} fills {buckets} buckets between the start and the end, more than the limit {MAX_GAP_FILL_BUCKETS}, use a wider bucket or a shorter time range"
+        ));
+    }
+
+    let group_indices = (0..aggregate.group_expr.len())
+        .filter(|index| *index != time_index)
+        .collect();
+    let fills = fill_strategies(aggregate, projection)?;
+
+    let gap_fill = GapFill::try_new(
+        time_index,
+        group_indices,
+        fills,
+        width,
+        start,
+        end,
+        plan.clone(),
+    )?;
+    Ok(Some(LogicalPlan::Extension(Extension {
+        node: Arc::new(gap_fill),
+    })))
+}
+
+/// Finds the aggregated columns that `locf` or `interpolate` is applied to in the projection.
+fn fill_strategies(
+    aggregate: &Aggregate,
+    projection: &[Expr],
+) -> Result<Vec<(usize, FillStrategy)>> {
+    let mut fills: Vec<(usize, FillStrategy)> = vec![];
+    for expr in projection {
+        expr.apply(&mut |expr| {
+            let Expr::ScalarUDF { fun, args } = expr else {
+                return Ok(VisitRecursion::Continue);
+            };
+            let strategy = match fun.name.as_str() {
+                LOCF => FillStrategy::Prev,
+                INTERPOLATE => FillStrategy::Linear,
+                _ => return Ok(VisitRecursion::Continue),
+            };
+            let [Expr::Column(column)] = args.as_slice() else {
+                return plan_err(format!(
+                    "{} only accepts an aggregate function, found {}",
+                    fun.name,
+                    args.iter()
+                        .map(|arg| arg.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            };
+            let index = aggregate.schema.index_of_column(column)?;
+            // Columns in `GROUP BY` are never filled.
+            if index < aggregate.group_expr.len() {
+                return Ok(VisitRecursion::Continue);
+            }
+            match fills.iter().find(|(i, _)| *i == index) {
+                Some((_, existing)) if *existing != strategy => {
+                    return plan_err(format!(
+                        "{column} is filled by both {LOCF} and {INTERPOLATE}"
+                    ))
+                }
+                Some(_) => {}
+                None => fills.push((index, strategy)),
+            }
+            Ok(VisitRecursion::Continue)
+        })?;
+    }
+    Ok(fills)
+}
+
+fn literal(expr: &Expr) -> Option<&ScalarValue> {
+    match expr {
+        Expr::Literal(value) => Some(value),
+        Expr::Cast(Cast { expr, .. }) => literal(expr),
+        _ => None,
+    }
+}
+
+/// Parses the start or end argument of `time_bucket_gapfill` into a timestamp in `unit`.
+fn timestamp(expr: &Expr, unit: TimeUnit) -> Result<i64> {
+    let ts = match literal(expr) {
+        Some(ScalarValue::Utf8(Some(s))) => Timestamp::from_str(s).ok(),
+        Some(ScalarValue::Int64(Some(v))) => Some(Timestamp::new(*v, unit)),
+        Some(ScalarValue::TimestampSecond(Some(v), _)) => Some(Timestamp::new_second(*v)),
+        Some(ScalarValue::TimestampMillisecond(Some(v), _)) => Some(Timestamp::new_millisecond(*v)),
+        Some(ScalarValue::TimestampMicrosecond(Some(v), _)) => Some(Timestamp::new_microsecond(*v)),
+        Some(ScalarValue::TimestampNanosecond(Some(v), _)) => Some(Timestamp::new_nanosecond(*v)),
+        _ => None,
+    };
+    ts.and_then(|ts| ts.convert_to(unit))
+        .map(|ts| ts.value())
+        .ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "Invalid time range {expr} of {TIME_BUCKET_GAPFILL}, expect a timestamp"
+            ))
+        })
+}
+
+/// Returns the number of buckets of `width` overlapping `[start, end)`.
+fn bucket_count(width: i64, start: i64, end: i64) -> i128 {
+    let first = bucket_start(start, width) as i128;
+    let (end, width) = (end as i128, width as i128);
+    (end - first + width - 1) / width
+}
+
+fn plan_err<T>(msg: String) -> Result<T> {
+    Err(DataFusionError::Plan(msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_argument() {
+        let arg = Expr::Literal(ScalarValue::Utf8(Some("1970-01-01T00:01:00Z".to_string())));
+        assert_eq!(60, timestamp(&arg, TimeUnit::Second).unwrap());
+        assert_eq!(60_000, timestamp(&arg, TimeUnit::Millisecond).unwrap());
+
+        let arg = Expr::Cast(Cast::new(
+            Box::new(Expr::Literal(ScalarValue::TimestampMillisecond(
+                Some(1500),
+                None,
+            ))),
+            DataType::Timestamp(datatypes::arrow::datatypes::TimeUnit::Second, None),
+        ));
+        assert_eq!(1, timestamp(&arg, TimeUnit::Second).unwrap());
+
+        let arg = Expr::Literal(ScalarValue::Int64(Some(42)));
+        assert_eq!(42, timestamp(&arg, TimeUnit::Millisecond).unwrap());
+
+        let arg = Expr::Literal(ScalarValue::Utf8(Some("yesterday".to_string())));
+        assert!(timestamp(&arg, TimeUnit::Millisecond).is_err());
+    }
+
+    #[test]
+    fn test_bucket_count() {
+        assert_eq!(4, bucket_count(60, 0, 240));
+        assert_eq!(5, bucket_count(60, 30, 250));
+        assert_eq!(1, bucket_count(60, -1, 0));
+        assert_eq!(u64::MAX as i128, bucket_count(1, i64::MIN, i64::MAX));
+    }
+}
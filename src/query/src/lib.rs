@@ -15,6 +15,7 @@
 pub mod datafusion;
 pub mod error;
 pub mod executor;
+pub mod gap_fill;
 pub mod logical_optimizer;
 mod metrics;
 mod optimizer;
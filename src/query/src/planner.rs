@@ -26,6 +26,7 @@ use snafu::ResultExt;
 use sql::statements::statement::Statement;
 
 use crate::error::{PlanSqlSnafu, QueryPlanSnafu, Result, SqlSnafu};
+use crate::gap_fill::GapFillRule;
 use crate::parser::QueryStatement;
 use crate::plan::LogicalPlan;
 use crate::query_engine::QueryEngineState;
@@ -69,14 +70,17 @@ impl DfLogicalPlanner {
 
         let sql_to_rel = SqlToRel::new_with_options(&context_provider, parser_options);
 
-        let result = sql_to_rel.statement_to_plan(df_stmt).with_context(|_| {
-            let sql = if let Statement::Query(query) = stmt {
-                query.inner.to_string()
-            } else {
-                format!("{stmt:?}")
-            };
-            PlanSqlSnafu { sql }
-        })?;
+        let result = sql_to_rel
+            .statement_to_plan(df_stmt)
+            .and_then(|plan| GapFillRule::check(&plan).map(|_| plan))
+            .with_context(|_| {
+                let sql = if let Statement::Query(query) = stmt {
+                    query.inner.to_string()
+                } else {
+                    format!("{stmt:?}")
+                };
+                PlanSqlSnafu { sql }
+            })?;
         Ok(LogicalPlan::DfPlan(result))
     }
 
@@ -12,20 +12,65 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use datafusion::execution::context::SessionState;
+use std::sync::Arc;
+
+use datafusion::execution::context::{SessionState, TaskContext};
+use datafusion::execution::memory_pool::GreedyMemoryPool;
+use datafusion::execution::runtime_env::RuntimeEnv;
+use session::context::QueryContextRef;
 
-#[derive(Debug)]
 pub struct QueryEngineContext {
     state: SessionState,
+    query_ctx: QueryContextRef,
+    /// The runtime env to execute the plans in, which limits the memory of the query.
+    runtime_env: Arc<RuntimeEnv>,
 }
 
 impl QueryEngineContext {
-    pub fn new(state: SessionState) -> Self {
-        Self { state }
+    pub fn new(state: SessionState, query_ctx: QueryContextRef) -> Self {
+        let runtime_env = match query_ctx.query_limits().memory_limit {
+            Some(memory_limit) => with_memory_limit(state.runtime_env(), memory_limit),
+            None => state.runtime_env().clone(),
+        };
+        Self {
+            state,
+            query_ctx,
+            runtime_env,
+        }
     }
 
     #[inline]
     pub fn state(&self) -> &SessionState {
         &self.state
     }
+
+    /// Returns the context to execute the physical plans in, which carries the query context
+    /// as an extension of the session config, e.g. for the tables depending on the current
+    /// user. Its memory pool is limited by the query limits of the session.
+    pub fn task_ctx(&self) -> Arc<TaskContext> {
+        let session_config = self
+            .state
+            .config()
+            .clone()
+            .with_extension(self.query_ctx.clone());
+        Arc::new(TaskContext::new(
+            None,
+            self.state.session_id().to_string(),
+            session_config,
+            self.state.scalar_functions().clone(),
+            self.state.aggregate_functions().clone(),
+            self.runtime_env.clone(),
+        ))
+    }
+}
+
+/// Derives a runtime env whose memory pool only grants `memory_limit` bytes, so a query
+/// executed in it fails once it uses more memory than that. The memory pool is not shared
+/// with other queries.
+fn with_memory_limit(base: &RuntimeEnv, memory_limit: usize) -> Arc<RuntimeEnv> {
+    Arc::new(RuntimeEnv {
+        memory_pool: Arc::new(GreedyMemoryPool::new(memory_limit)),
+        disk_manager: base.disk_manager.clone(),
+        object_store_registry: base.object_store_registry.clone(),
+    })
 }
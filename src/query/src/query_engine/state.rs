@@ -32,6 +32,7 @@ use datafusion_optimizer::optimizer::Optimizer;
 use promql::extension_plan::PromExtensionPlanner;
 
 use crate::datafusion::DfCatalogListAdapter;
+use crate::gap_fill::{GapFillExtensionPlanner, GapFillRule};
 use crate::optimizer::TypeConversionRule;
 use crate::query_engine::options::QueryOptions;
 
@@ -61,6 +62,8 @@ impl QueryEngineState {
         let mut optimizer = Optimizer::new();
         // Apply the type conversion rule first.
         optimizer.rules.insert(0, Arc::new(TypeConversionRule {}));
+        // Fill gaps before other rules changing the aggregations and the projections above them.
+        optimizer.rules.insert(1, Arc::new(GapFillRule));
 
         let session_state = SessionState::with_config_rt_and_catalog_list(
             session_config,
@@ -140,9 +143,10 @@ impl QueryPlanner for DfQueryPlanner {
 impl DfQueryPlanner {
     fn new() -> Self {
         Self {
-            physical_planner: DefaultPhysicalPlanner::with_extension_planners(vec![Arc::new(
-                PromExtensionPlanner {},
-            )]),
+            physical_planner: DefaultPhysicalPlanner::with_extension_planners(vec![
+                Arc::new(PromExtensionPlanner {}),
+                Arc::new(GapFillExtensionPlanner),
+            ]),
         }
     }
 }
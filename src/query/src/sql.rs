@@ -16,21 +16,25 @@ use std::sync::Arc;
 
 use catalog::CatalogManagerRef;
 use common_catalog::consts::DEFAULT_CATALOG_NAME;
+use common_catalog::is_hidden_table;
 use common_query::Output;
 use common_recordbatch::RecordBatches;
 use datatypes::prelude::*;
 use datatypes::schema::{ColumnSchema, Schema};
-use datatypes::vectors::{Helper, StringVector};
+use datatypes::vectors::{Helper, Int64Vector, StringVector, UInt32Vector};
 use once_cell::sync::Lazy;
 use session::context::QueryContextRef;
+use session::process::ProcessManagerRef;
 use snafu::{ensure, OptionExt, ResultExt};
-use sql::statements::show::{ShowDatabases, ShowKind, ShowTables};
+use sql::statements::show::{ShowDatabases, ShowKind, ShowProcesslist, ShowTables};
 use table::TableRef;
 
 use crate::error::{self, Result};
 
 const SCHEMAS_COLUMN: &str = "Schemas";
 const TABLES_COLUMN: &str = "Tables";
+const VIEW_COLUMN: &str = "View";
+const CREATE_VIEW_COLUMN: &str = "Create View";
 const COLUMN_NAME_COLUMN: &str = "Field";
 const COLUMN_TYPE_COLUMN: &str = "Type";
 const COLUMN_NULLABLE_COLUMN: &str = "Null";
@@ -44,6 +48,21 @@ const SEMANTIC_TYPE_TIME_INDEX: &str = "TIME INDEX";
 const NULLABLE_YES: &str = "YES";
 const NULLABLE_NO: &str = "NO";
 
+/// Without `FULL`, `SHOW PROCESSLIST` only shows this many leading characters of a statement.
+const PROCESSLIST_INFO_LENGTH: usize = 100;
+
+static SHOW_PROCESSLIST_OUTPUT_SCHEMA: Lazy<Arc<Schema>> = Lazy::new(|| {
+    Arc::new(Schema::new(vec![
+        ColumnSchema::new("Id", ConcreteDataType::uint32_datatype(), false),
+        ColumnSchema::new("User", ConcreteDataType::string_datatype(), false),
+        ColumnSchema::new("Host", ConcreteDataType::string_datatype(), true),
+        ColumnSchema::new("Protocol", ConcreteDataType::string_datatype(), true),
+        ColumnSchema::new("Db", ConcreteDataType::string_datatype(), false),
+        ColumnSchema::new("Time", ConcreteDataType::int64_datatype(), false),
+        ColumnSchema::new("Info", ConcreteDataType::string_datatype(), false),
+    ]))
+});
+
 static DESCRIBE_TABLE_OUTPUT_SCHEMA: Lazy<Arc<Schema>> = Lazy::new(|| {
     Arc::new(Schema::new(vec![
         ColumnSchema::new(
@@ -127,12 +146,15 @@ pub fn show_tables(
     } else {
         query_ctx.current_schema()
     };
+    let catalog = query_ctx.current_catalog();
     // TODO(sunng87): move this function into query_ctx
-    let schema = catalog_manager
-        .schema(&query_ctx.current_catalog(), &schema)
+    let schema_provider = catalog_manager
+        .schema(&catalog, &schema)
         .context(error::CatalogSnafu)?
-        .context(error::SchemaNotFoundSnafu { schema })?;
-    let mut tables = schema.table_names().context(error::CatalogSnafu)?;
+        .context(error::SchemaNotFoundSnafu { schema: &schema })?;
+    let mut tables = schema_provider.table_names().context(error::CatalogSnafu)?;
+    tables.retain(|table| !is_hidden_table(&catalog, &schema, table));
+    tables.extend(schema_provider.view_names().context(error::CatalogSnafu)?);
     // TODO(dennis): Specify the order of the results in schema provider API
     tables.sort();
 
@@ -152,6 +174,82 @@ pub fn show_tables(
     Ok(Output::RecordBatches(records))
 }
 
+pub fn show_processlist(
+    stmt: ShowProcesslist,
+    process_manager: &ProcessManagerRef,
+    query_ctx: QueryContextRef,
+) -> Result<Output> {
+    let catalog = query_ctx.current_catalog();
+    let processes = process_manager
+        .processes_of(&query_ctx.current_user())
+        .into_iter()
+        .filter(|x| x.catalog() == catalog)
+        .collect::<Vec<_>>();
+
+    let ids = UInt32Vector::from_iterator(processes.iter().map(|x| x.id()));
+    let users = StringVector::from_iterator(processes.iter().map(|x| x.user()));
+    let hosts = StringVector::from(
+        processes
+            .iter()
+            .map(|x| x.client().map(|c| c.to_string()))
+            .collect::<Vec<_>>(),
+    );
+    let protocols = StringVector::from(
+        processes
+            .iter()
+            .map(|x| x.channel().map(|c| c.to_string()))
+            .collect::<Vec<_>>(),
+    );
+    let dbs = StringVector::from_iterator(processes.iter().map(|x| x.schema()));
+    let times = Int64Vector::from_iterator(processes.iter().map(|x| x.elapsed().as_secs() as i64));
+    let infos = StringVector::from_iterator(processes.iter().map(|x| {
+        let query = x.query();
+        if stmt.full {
+            query
+        } else {
+            // Truncate at a char boundary.
+            query
+                .char_indices()
+                .nth(PROCESSLIST_INFO_LENGTH)
+                .map_or(query, |(i, _)| &query[..i])
+        }
+    }));
+
+    let columns: Vec<VectorRef> = vec![
+        Arc::new(ids),
+        Arc::new(users),
+        Arc::new(hosts),
+        Arc::new(protocols),
+        Arc::new(dbs),
+        Arc::new(times),
+        Arc::new(infos),
+    ];
+    let records = RecordBatches::try_from_columns(SHOW_PROCESSLIST_OUTPUT_SCHEMA.clone(), columns)
+        .context(error::CreateRecordBatchSnafu)?;
+    Ok(Output::RecordBatches(records))
+}
+
+/// Shows the statement to create the view, `view_name` is expected to be quoted if necessary.
+pub fn show_create_view(view_name: &str, definition: &str) -> Result<Output> {
+    let schema = Arc::new(Schema::new(vec![
+        ColumnSchema::new(VIEW_COLUMN, ConcreteDataType::string_datatype(), false),
+        ColumnSchema::new(
+            CREATE_VIEW_COLUMN,
+            ConcreteDataType::string_datatype(),
+            false,
+        ),
+    ]));
+    let columns: Vec<VectorRef> = vec![
+        Arc::new(StringVector::from(vec![view_name])),
+        Arc::new(StringVector::from(vec![format!(
+            "CREATE VIEW {view_name} AS {definition}"
+        )])),
+    ];
+    let records =
+        RecordBatches::try_from_columns(schema, columns).context(error::CreateRecordBatchSnafu)?;
+    Ok(Output::RecordBatches(records))
+}
+
 pub fn describe_table(table: TableRef) -> Result<Output> {
     let table_info = table.table_info();
     let columns_schemas = table_info.meta.schema.column_schemas();
@@ -231,20 +329,65 @@ mod test {
     use common_query::Output;
     use common_recordbatch::{RecordBatch, RecordBatches};
     use common_time::timestamp::TimeUnit;
-    use datatypes::prelude::ConcreteDataType;
+    use datatypes::prelude::{ConcreteDataType, Value};
     use datatypes::schema::{ColumnDefaultConstraint, ColumnSchema, Schema, SchemaRef};
     use datatypes::vectors::{StringVector, TimestampMillisecondVector, UInt32Vector, VectorRef};
+    use session::context::QueryContext;
+    use session::process::ProcessManager;
     use snafu::ResultExt;
+    use sql::statements::show::ShowProcesslist;
     use table::test_util::MemTable;
     use table::TableRef;
 
     use crate::error;
     use crate::error::Result;
     use crate::sql::{
-        describe_table, DESCRIBE_TABLE_OUTPUT_SCHEMA, NULLABLE_NO, NULLABLE_YES,
-        SEMANTIC_TYPE_FIELD, SEMANTIC_TYPE_TIME_INDEX,
+        describe_table, show_create_view, show_processlist, DESCRIBE_TABLE_OUTPUT_SCHEMA,
+        NULLABLE_NO, NULLABLE_YES, SEMANTIC_TYPE_FIELD, SEMANTIC_TYPE_TIME_INDEX,
     };
 
+    #[test]
+    fn test_show_processlist() {
+        let process_manager = Arc::new(ProcessManager::default());
+        let query_ctx = QueryContext::arc();
+        let long_query = format!("SELECT '{}'", "a".repeat(200));
+        let _guard = process_manager.register(&long_query, &query_ctx);
+
+        let show = |full| {
+            let output = show_processlist(
+                ShowProcesslist { full },
+                &process_manager,
+                query_ctx.clone(),
+            )
+            .unwrap();
+            let Output::RecordBatches(batches) = output else { unreachable!() };
+            let batches = batches.take();
+            assert_eq!(batches.len(), 1);
+            let batch = &batches[0];
+            assert_eq!(batch.num_rows(), 1);
+            let id = batch.column(0).get(0);
+            assert_eq!(id, Value::UInt32(query_ctx.conn_id()));
+            let Value::String(info) = batch.column(6).get(0) else { unreachable!() };
+            info.as_utf8().to_string()
+        };
+
+        assert_eq!(show(false).len(), 100);
+        assert_eq!(show(true), long_query);
+    }
+
+    #[test]
+    fn test_show_create_view() {
+        let output = show_create_view("busy_hosts", "SELECT host FROM cpu").unwrap();
+        let Output::RecordBatches(batches) = output else { unreachable!() };
+        let expected = "\
++------------+------------------------------------------------+
+| View       | Create View                                    |
++------------+------------------------------------------------+
+| busy_hosts | CREATE VIEW busy_hosts AS SELECT host FROM cpu |
++------------+------------------------------------------------+";
+        assert_eq!(batches.pretty_print().unwrap(), expected);
+    }
+
     #[test]
     fn test_describe_table_multiple_columns() -> Result<()> {
         let table_name = "test_table";
@@ -21,6 +21,7 @@ use crate::QueryEngineRef;
 
 mod argmax_test;
 mod argmin_test;
+mod gap_fill_test;
 mod mean_test;
 mod my_sum_udaf_example;
 mod percentile_test;
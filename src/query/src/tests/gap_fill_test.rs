@@ -0,0 +1,159 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use catalog::local::{MemoryCatalogManager, MemoryCatalogProvider, MemorySchemaProvider};
+use catalog::{CatalogList, CatalogProvider, SchemaProvider};
+use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
+use common_query::Output;
+use common_recordbatch::{util, RecordBatch, RecordBatches};
+use datatypes::prelude::*;
+use datatypes::schema::{ColumnSchema, Schema};
+use datatypes::vectors::{Float64Vector, StringVector, TimestampMillisecondVector};
+use session::context::QueryContext;
+use table::test_util::MemTable;
+
+use crate::parser::QueryLanguageParser;
+use crate::tests::exec_selection;
+use crate::{QueryEngineFactory, QueryEngineRef};
+
+fn create_test_engine() -> QueryEngineRef {
+    let schema = Arc::new(Schema::new(vec![
+        ColumnSchema::new("host", ConcreteDataType::string_datatype(), false),
+        ColumnSchema::new("usage", ConcreteDataType::float64_datatype(), true),
+        ColumnSchema::new(
+            "ts",
+            ConcreteDataType::timestamp_millisecond_datatype(),
+            false,
+        )
+        .with_time_index(true),
+    ]));
+    let recordbatch = RecordBatch::new(
+        schema,
+        vec![
+            Arc::new(StringVector::from(vec!["host1", "host1", "host2"])) as _,
+            Arc::new(Float64Vector::from_slice([1.0, 4.0, 2.0])) as _,
+            Arc::new(TimestampMillisecondVector::from_slice([0, 180_000, 60_000])) as _,
+        ],
+    )
+    .unwrap();
+    let table = Arc::new(MemTable::new("cpu", recordbatch));
+
+    let schema_provider = Arc::new(MemorySchemaProvider::new());
+    let catalog_provider = Arc::new(MemoryCatalogProvider::new());
+    let catalog_list = Arc::new(MemoryCatalogManager::default());
+    schema_provider
+        .register_table("cpu".to_string(), table)
+        .unwrap();
+    catalog_provider
+        .register_schema(DEFAULT_SCHEMA_NAME.to_string(), schema_provider)
+        .unwrap();
+    catalog_list
+        .register_catalog(DEFAULT_CATALOG_NAME.to_string(), catalog_provider)
+        .unwrap();
+
+    QueryEngineFactory::new(catalog_list).query_engine()
+}
+
+async fn query(engine: QueryEngineRef, sql: &str) -> String {
+    let batches = exec_selection(engine, sql).await;
+    let batches = RecordBatches::try_new(batches.first().unwrap().schema.clone(), batches).unwrap();
+    batches.pretty_print().unwrap()
+}
+
+#[tokio::test]
+async fn test_gap_fill() {
+    let engine = create_test_engine();
+
+    let sql = "SELECT host, \
+        time_bucket_gapfill('1 minute', ts, '1970-01-01T00:00:00Z', '1970-01-01T00:04:00Z') AS minute, \
+        locf(max(usage)) AS prev, interpolate(avg(usage)) AS linear, min(usage) AS nothing \
+        FROM cpu GROUP BY host, minute ORDER BY host, minute";
+    let expected = "\
++-------+---------------------+------+--------+---------+
+| host  | minute              | prev | linear | nothing |
++-------+---------------------+------+--------+---------+
+| host1 | 1970-01-01T00:00:00 | 1.0  | 1.0    | 1.0     |
+| host1 | 1970-01-01T00:01:00 | 1.0  | 2.0    |         |
+| host1 | 1970-01-01T00:02:00 | 1.0  | 3.0    |         |
+| host1 | 1970-01-01T00:03:00 | 4.0  | 4.0    | 4.0     |
+| host2 | 1970-01-01T00:00:00 |      |        |         |
+| host2 | 1970-01-01T00:01:00 | 2.0  | 2.0    | 2.0     |
+| host2 | 1970-01-01T00:02:00 | 2.0  |        |         |
+| host2 | 1970-01-01T00:03:00 | 2.0  |        |         |
++-------+---------------------+------+--------+---------+";
+    assert_eq!(expected, query(engine.clone(), sql).await);
+
+    // Without other columns in GROUP BY, the gaps are filled across all hosts.
+    let sql = "SELECT time_bucket_gapfill('2m', ts, '1970-01-01T00:00:00Z', '1970-01-01T00:08:00Z') AS t, \
+        count(usage) AS c FROM cpu GROUP BY t ORDER BY t";
+    let expected = "\
++---------------------+---+
+| t                   | c |
++---------------------+---+
+| 1970-01-01T00:00:00 | 2 |
+| 1970-01-01T00:02:00 | 1 |
+| 1970-01-01T00:04:00 |   |
+| 1970-01-01T00:06:00 |   |
++---------------------+---+";
+    assert_eq!(expected, query(engine.clone(), sql).await);
+
+    // Too many buckets to fill.
+    let sql = "SELECT time_bucket_gapfill('1ms', ts, '1970-01-01T00:00:00Z', '1970-01-02T00:00:00Z') AS t, \
+        count(usage) AS c FROM cpu GROUP BY t";
+    let stmt = QueryLanguageParser::parse_sql(sql).unwrap();
+    let err = engine
+        .planner()
+        .plan(stmt, QueryContext::arc())
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string()
+            .contains("fills 86400000 buckets between the start and the end"),
+        "{err}"
+    );
+
+    // Invalid arguments are reported by the planner.
+    let sql = "SELECT time_bucket_gapfill('1m', ts, '1970-01-01T00:04:00Z', '1970-01-01T00:00:00Z') AS t, \
+        count(usage) AS c FROM cpu GROUP BY t";
+    let stmt = QueryLanguageParser::parse_sql(sql).unwrap();
+    let err = engine
+        .planner()
+        .plan(stmt, QueryContext::arc())
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("must be before the end"), "{err}");
+
+    // Too many buckets to fill for all groups, though not for each group.
+    let sql = "SELECT host, \
+        time_bucket_gapfill('1ms', ts, '1970-01-01T00:00:00Z', '1970-01-01T00:01:00Z') AS t, \
+        count(usage) AS c FROM cpu GROUP BY host, t";
+    let stmt = QueryLanguageParser::parse_sql(sql).unwrap();
+    let plan = engine
+        .planner()
+        .plan(stmt, QueryContext::arc())
+        .await
+        .unwrap();
+    let output = engine.execute(plan, QueryContext::arc()).await.unwrap();
+    let Output::Stream(stream) = output else { unreachable!() };
+    let err = util::collect(stream).await.err().unwrap();
+    assert!(
+        err.to_string()
+            .contains("fills more than 100000 buckets for all groups"),
+        "{err}"
+    );
+}
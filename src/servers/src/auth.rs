@@ -57,16 +57,20 @@ pub enum Password<'a> {
     PgMD5(HashedPassword<'a>, Salt<'a>),
 }
 
-pub fn user_provider_from_option(opt: &String) -> Result<UserProviderRef> {
+/// Creates the user provider from the option, where `admin_user` names the user who can see and
+/// kill the statements of the other users.
+pub fn user_provider_from_option(
+    opt: &String,
+    admin_user: Option<String>,
+) -> Result<UserProviderRef> {
     let (name, content) = opt.split_once(':').context(InvalidConfigSnafu {
         value: opt.to_string(),
         msg: "UserProviderOption must be in format `<option>:<value>`",
     })?;
     match name {
         user_provider::STATIC_USER_PROVIDER => {
-            let provider =
-                StaticUserProvider::try_from(content).map(|p| Arc::new(p) as UserProviderRef)?;
-            Ok(provider)
+            let provider = StaticUserProvider::try_from(content)?.with_admin_user(admin_user);
+            Ok(Arc::new(provider))
         }
         _ => InvalidConfigSnafu {
             value: name.to_string(),
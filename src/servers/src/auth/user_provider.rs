@@ -68,7 +68,7 @@ impl TryFrom<&str> for StaticUserProvider {
                     msg: "StaticUserProviderOption file must contains at least one valid credential",
                 });
 
-                Ok(StaticUserProvider { users: credential, })
+                Ok(StaticUserProvider { users: credential, admin_user: None })
             }
             "cmd" => content
                 .split(',')
@@ -80,7 +80,7 @@ impl TryFrom<&str> for StaticUserProvider {
                     Ok((k.to_string(), v.as_bytes().to_vec()))
                 })
                 .collect::<Result<HashMap<String, Vec<u8>>>>()
-                .map(|users| StaticUserProvider { users }),
+                .map(|users| StaticUserProvider { users, admin_user: None }),
             _ => InvalidConfigSnafu {
                 value: mode.to_string(),
                 msg: "StaticUserProviderOption must be in format `file:<path>` or `cmd:<values>`",
@@ -92,6 +92,23 @@ impl TryFrom<&str> for StaticUserProvider {
 
 pub struct StaticUserProvider {
     users: HashMap<String, Vec<u8>>,
+    /// The user who can see and kill the statements of the other users.
+    admin_user: Option<String>,
+}
+
+impl StaticUserProvider {
+    pub fn with_admin_user(mut self, admin_user: Option<String>) -> Self {
+        self.admin_user = admin_user;
+        self
+    }
+
+    fn new_user_info(&self, username: &str) -> UserInfo {
+        if self.admin_user.as_deref() == Some(username) {
+            UserInfo::new_admin(username)
+        } else {
+            UserInfo::new(username)
+        }
+    }
 }
 
 #[async_trait]
@@ -126,7 +143,7 @@ impl UserProvider for StaticUserProvider {
                             }
                         );
                         return if save_pwd == pwd.as_bytes() {
-                            Ok(UserInfo::new(username))
+                            Ok(self.new_user_info(username))
                         } else {
                             UserPasswordMismatchSnafu {
                                 username: username.to_string(),
@@ -142,7 +159,7 @@ impl UserProvider for StaticUserProvider {
                             }
                         );
                         auth_mysql(auth_data, salt, username, save_pwd)
-                            .map(|_| UserInfo::new(username))
+                            .map(|_| self.new_user_info(username))
                     }
                     Password::PgMD5(_, _) => UnsupportedPasswordTypeSnafu {
                         password_type: "pg_md5",
@@ -255,6 +272,21 @@ pub mod test {
         assert!(re.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_admin_user() {
+        let provider = StaticUserProvider::try_from("cmd:root=123456,admin=654321")
+            .unwrap()
+            .with_admin_user(Some("admin".to_string()));
+        let authenticate = |username, password| {
+            provider.authenticate(
+                Identity::UserId(username, None),
+                Password::PlainText(password),
+            )
+        };
+        assert!(!authenticate("root", "123456").await.unwrap().is_admin());
+        assert!(authenticate("admin", "654321").await.unwrap().is_admin());
+    }
+
     #[tokio::test]
     async fn test_inline_provider() {
         let provider = StaticUserProvider::try_from("cmd:root=123456,admin=654321").unwrap();
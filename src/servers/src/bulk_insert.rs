@@ -0,0 +1,329 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Inserts the rows loaded in bulk through the protocols, like PostgreSQL `COPY FROM STDIN` and
+//! MySQL `LOAD DATA LOCAL INFILE`, in record batches through the `BulkInsertHandler`.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use common_recordbatch::RecordBatch;
+use common_time::date::Date;
+use common_time::datetime::DateTime;
+use common_time::timestamp::Timestamp;
+use datatypes::prelude::{ConcreteDataType, Value};
+use datatypes::schema::SchemaRef;
+use session::context::QueryContextRef;
+use snafu::{ensure, OptionExt, ResultExt};
+use sql::ast::{Ident, ObjectName};
+use sql::dialect::GenericDialect;
+use sql::parser::ParserContext;
+
+use crate::error::{self, Result};
+use crate::query_handler::grpc::BulkInsertHandlerRef;
+use crate::query_handler::sql::ServerSqlQueryHandlerRef;
+
+/// The number of rows inserted at a time.
+const BATCH_SIZE: usize = 1024;
+
+pub(crate) type Row = Vec<Value>;
+
+pub(crate) struct BulkInserter {
+    handler: BulkInsertHandlerRef,
+    query_ctx: QueryContextRef,
+    /// The name of the table in the current schema.
+    table: String,
+    /// The schema of the columns to insert.
+    schema: SchemaRef,
+    column_types: Vec<ConcreteDataType>,
+    rows: Vec<Row>,
+    affected_rows: usize,
+}
+
+impl BulkInserter {
+    /// Resolves the types of the `columns` of the table, or all columns if `columns` is empty.
+    /// The table must be in the current schema, like the tables of `INSERT`.
+    pub(crate) async fn try_new(
+        query_handler: ServerSqlQueryHandlerRef,
+        handler: BulkInsertHandlerRef,
+        query_ctx: QueryContextRef,
+        table_name: &ObjectName,
+        columns: &[Ident],
+    ) -> Result<Self> {
+        let table = match &table_name.0[..] {
+            [table] => table,
+            [schema, table] if schema.value == query_ctx.current_schema() => table,
+            [catalog, schema, table]
+                if catalog.value == query_ctx.current_catalog()
+                    && schema.value == query_ctx.current_schema() =>
+            {
+                table
+            }
+            _ => {
+                return error::InvalidBulkInsertSnafu {
+                    reason: format!("Table {table_name} is not in the current schema"),
+                }
+                .fail()
+            }
+        };
+
+        let projection = if columns.is_empty() {
+            "*".to_string()
+        } else {
+            columns
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let sql = format!("SELECT {projection} FROM {table_name}");
+        let mut statements =
+            ParserContext::create_with_dialect(&sql, &GenericDialect {}).map_err(|e| {
+                error::InvalidBulkInsertSnafu {
+                    reason: e.to_string(),
+                }
+                .build()
+            })?;
+        ensure!(
+            statements.len() == 1,
+            error::InvalidBulkInsertSnafu {
+                reason: format!("Invalid table name: {table_name}"),
+            }
+        );
+        let schema = query_handler
+            .do_describe(statements.remove(0), query_ctx.clone())
+            .await?
+            .with_context(|| error::InvalidBulkInsertSnafu {
+                reason: format!("Failed to describe the columns of table {table_name}"),
+            })?;
+
+        for column_schema in schema.column_schemas() {
+            ensure!(
+                !matches!(
+                    column_schema.data_type,
+                    ConcreteDataType::Null(_)
+                        | ConcreteDataType::Binary(_)
+                        | ConcreteDataType::List(_)
+                        | ConcreteDataType::Dictionary(_)
+                ),
+                error::InvalidBulkInsertSnafu {
+                    reason: format!(
+                        "Column {} of type {:?} is not supported",
+                        column_schema.name, column_schema.data_type
+                    ),
+                }
+            );
+        }
+        let column_types = schema
+            .column_schemas()
+            .iter()
+            .map(|c| c.data_type.clone())
+            .collect();
+
+        Ok(BulkInserter {
+            handler,
+            query_ctx,
+            table: table.value.clone(),
+            schema: Arc::new(schema),
+            column_types,
+            rows: vec![],
+            affected_rows: 0,
+        })
+    }
+
+    pub(crate) fn column_types(&self) -> &[ConcreteDataType] {
+        &self.column_types
+    }
+
+    /// Buffers the rows, and inserts them once there are enough.
+    pub(crate) async fn push(&mut self, rows: Vec<Row>) -> Result<()> {
+        self.rows.extend(rows);
+        if self.rows.len() >= BATCH_SIZE {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Inserts the rest of the rows, returns the number of inserted rows.
+    pub(crate) async fn finish(mut self) -> Result<usize> {
+        self.flush().await?;
+        Ok(self.affected_rows)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let recordbatch = to_recordbatch(self.schema.clone(), &self.rows)?;
+        self.rows.clear();
+
+        self.affected_rows += self
+            .handler
+            .insert_recordbatch(&self.table, recordbatch, self.query_ctx.clone())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Builds the record batch of the rows, whose values are of the types of the columns.
+fn to_recordbatch(schema: SchemaRef, rows: &[Row]) -> Result<RecordBatch> {
+    let mut columns = schema
+        .column_schemas()
+        .iter()
+        .map(|c| c.data_type.create_mutable_vector(rows.len()))
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (column, value) in columns.iter_mut().zip(row) {
+            column
+                .try_push_value_ref(value.as_value_ref())
+                .map_err(|e| {
+                    error::InvalidBulkInsertSnafu {
+                        reason: e.to_string(),
+                    }
+                    .build()
+                })?;
+        }
+    }
+    RecordBatch::new(schema, columns.iter_mut().map(|c| c.to_vector()))
+        .context(error::BuildBulkInsertRecordBatchSnafu)
+}
+
+/// Parses the value of the column from the text of a field.
+pub(crate) fn parse_text_value(s: &str, data_type: &ConcreteDataType) -> Result<Value> {
+    let invalid = || {
+        error::InvalidBulkInsertSnafu {
+            reason: format!("Invalid input of type {data_type:?}: \"{s}\""),
+        }
+        .build()
+    };
+    macro_rules! parse {
+        ($variant: ident) => {
+            Value::$variant(s.parse().map_err(|_| invalid())?)
+        };
+    }
+
+    let value = match data_type {
+        ConcreteDataType::Boolean(_) => match s.to_ascii_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => Value::Boolean(true),
+            "f" | "false" | "n" | "no" | "off" | "0" => Value::Boolean(false),
+            _ => return Err(invalid()),
+        },
+        ConcreteDataType::Int8(_) => parse!(Int8),
+        ConcreteDataType::Int16(_) => parse!(Int16),
+        ConcreteDataType::Int32(_) => parse!(Int32),
+        ConcreteDataType::Int64(_) => parse!(Int64),
+        ConcreteDataType::UInt8(_) => parse!(UInt8),
+        ConcreteDataType::UInt16(_) => parse!(UInt16),
+        ConcreteDataType::UInt32(_) => parse!(UInt32),
+        ConcreteDataType::UInt64(_) => parse!(UInt64),
+        ConcreteDataType::Float32(_) => {
+            Value::Float32(s.parse::<f32>().map_err(|_| invalid())?.into())
+        }
+        ConcreteDataType::Float64(_) => {
+            Value::Float64(s.parse::<f64>().map_err(|_| invalid())?.into())
+        }
+        ConcreteDataType::String(_) => Value::String(s.into()),
+        ConcreteDataType::Date(_) => Value::Date(Date::from_str(s).map_err(|_| invalid())?),
+        ConcreteDataType::DateTime(_) => {
+            Value::DateTime(DateTime::from_str(s).map_err(|_| invalid())?)
+        }
+        ConcreteDataType::Timestamp(t) => {
+            let ts = Timestamp::from_str(s).map_err(|_| invalid())?;
+            Value::Timestamp(ts.convert_to(t.unit()).with_context(|| {
+                error::InvalidBulkInsertSnafu {
+                    reason: format!("Timestamp overflow: {s}"),
+                }
+            })?)
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use datatypes::schema::{ColumnSchema, Schema};
+    use datatypes::vectors::{Float64Vector, StringVector, VectorRef};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_text_value() {
+        let cases = [
+            (
+                "t",
+                ConcreteDataType::boolean_datatype(),
+                Value::Boolean(true),
+            ),
+            (
+                "0",
+                ConcreteDataType::boolean_datatype(),
+                Value::Boolean(false),
+            ),
+            ("-1", ConcreteDataType::int32_datatype(), Value::Int32(-1)),
+            (
+                "1.5",
+                ConcreteDataType::float64_datatype(),
+                Value::Float64(1.5.into()),
+            ),
+            (
+                "host",
+                ConcreteDataType::string_datatype(),
+                Value::String("host".into()),
+            ),
+            (
+                "1970-01-02",
+                ConcreteDataType::date_datatype(),
+                Value::Date(Date::new(1)),
+            ),
+            (
+                "1970-01-01T00:00:01Z",
+                ConcreteDataType::timestamp_millisecond_datatype(),
+                Value::Timestamp(Timestamp::new_millisecond(1000)),
+            ),
+        ];
+        for (s, data_type, expected) in cases {
+            assert_eq!(expected, parse_text_value(s, &data_type).unwrap());
+        }
+
+        assert!(parse_text_value("-1", &ConcreteDataType::uint32_datatype()).is_err());
+        assert!(parse_text_value("yes?", &ConcreteDataType::boolean_datatype()).is_err());
+        assert!(parse_text_value("1970", &ConcreteDataType::date_datatype()).is_err());
+    }
+
+    #[test]
+    fn test_to_recordbatch() {
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new("host", ConcreteDataType::string_datatype(), true),
+            ColumnSchema::new("cpu", ConcreteDataType::float64_datatype(), true),
+        ]));
+        let rows = vec![
+            vec![Value::String("host1".into()), Value::Float64(1.5.into())],
+            vec![Value::String("host2".into()), Value::Null],
+        ];
+        let recordbatch = to_recordbatch(schema.clone(), &rows).unwrap();
+        assert_eq!(2, recordbatch.num_rows());
+        assert_eq!(
+            Arc::new(StringVector::from(vec!["host1", "host2"])) as VectorRef,
+            recordbatch.column(0).clone()
+        );
+        assert_eq!(
+            Arc::new(Float64Vector::from(vec![Some(1.5), None])) as VectorRef,
+            recordbatch.column(1).clone()
+        );
+
+        let rows = vec![vec![Value::Int32(1), Value::Null]];
+        assert!(to_recordbatch(schema, &rows).is_err());
+    }
+}
@@ -15,6 +15,7 @@
 use std::any::Any;
 use std::net::SocketAddr;
 use std::string::FromUtf8Error;
+use std::time::Duration;
 
 use axum::http::StatusCode as HttpStatusCode;
 use axum::response::{IntoResponse, Response};
@@ -122,6 +123,15 @@ pub enum Error {
     #[snafu(display("Invalid query: {}", reason))]
     InvalidQuery { reason: String, location: Location },
 
+    #[snafu(display("Query timed out after {:?}", timeout))]
+    QueryTimeout {
+        timeout: Duration,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid InfluxQL: {}", reason))]
+    InvalidInfluxql { reason: String, location: Location },
+
     #[snafu(display("Failed to parse InfluxDB line protocol, source: {}", source))]
     InfluxdbLineProtocol {
         #[snafu(backtrace)]
@@ -149,12 +159,63 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Invalid InfluxDB lines, source: {}", source))]
+    InvalidInfluxdbLines {
+        source: FromUtf8Error,
+        location: Location,
+    },
+
+    #[snafu(display("Failed to decompress InfluxDB write request, source: {}", source))]
+    DecompressInfluxdbRequest {
+        source: std::io::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Failed to decode OTLP request, source: {}", source))]
+    DecodeOtlpRequest {
+        source: prost::DecodeError,
+        location: Location,
+    },
+
+    #[snafu(display("Failed to decompress OTLP request, source: {}", source))]
+    DecompressOtlpRequest {
+        source: std::io::Error,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid OTLP request: {}", reason))]
+    InvalidOtlpRequest { reason: String, location: Location },
+
+    #[snafu(display("Failed to write OTLP metrics, source: {}", source))]
+    OtlpMetricsWrite {
+        #[snafu(backtrace)]
+        source: common_grpc::error::Error,
+    },
+
+    #[snafu(display("Invalid Graphite line: {}", reason))]
+    InvalidGraphiteLine { reason: String, location: Location },
+
+    #[snafu(display("Invalid Graphite pickle payload: {}", reason))]
+    InvalidGraphitePickle { reason: String, location: Location },
+
+    #[snafu(display("Invalid Graphite template: {}", reason))]
+    InvalidGraphiteTemplate { reason: String, location: Location },
+
+    #[snafu(display("Failed to write Graphite metrics, source: {}", source))]
+    GraphiteLinesWrite {
+        #[snafu(backtrace)]
+        source: common_grpc::error::Error,
+    },
+
     #[snafu(display("Invalid OpenTSDB Json request, source: {}", source))]
     InvalidOpentsdbJsonRequest {
         source: serde_json::error::Error,
         location: Location,
     },
 
+    #[snafu(display("Invalid OpenTSDB query: {}", reason))]
+    InvalidOpentsdbQuery { reason: String, location: Location },
+
     #[snafu(display(
         "Failed to put OpenTSDB data point: {:?}, source: {}",
         data_point,
@@ -190,6 +251,33 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Invalid Flight descriptor, source: {}", source))]
+    InvalidFlightDescriptor {
+        source: api::DecodeError,
+        location: Location,
+    },
+
+    #[snafu(display("Invalid Flight put request, reason: {}", reason))]
+    InvalidFlightPut { reason: String, location: Location },
+
+    #[snafu(display("Invalid Flight SQL command, reason: {}", reason))]
+    InvalidFlightSqlCommand { reason: String, location: Location },
+
+    #[snafu(display("Invalid PostgreSQL COPY, reason: {}", reason))]
+    InvalidPostgresCopy { reason: String, location: Location },
+
+    #[snafu(display("Invalid bulk insert, reason: {}", reason))]
+    InvalidBulkInsert { reason: String, location: Location },
+
+    #[snafu(display("Failed to build the record batch of bulk insert, source: {}", source))]
+    BuildBulkInsertRecordBatch {
+        #[snafu(backtrace)]
+        source: common_recordbatch::error::Error,
+    },
+
+    #[snafu(display("Invalid MySQL LOAD DATA, reason: {}", reason))]
+    InvalidMysqlLoadData { reason: String, location: Location },
+
     #[snafu(display("Failed to start frontend service, source: {}", source))]
     StartFrontend {
         #[snafu(backtrace)]
@@ -269,6 +357,13 @@ pub enum Error {
         location: Location,
     },
 
+    #[snafu(display("Failed to encode the query output in {}, source: {}", format, source))]
+    EncodeHttpOutput {
+        format: String,
+        source: datatypes::arrow::error::ArrowError,
+        location: Location,
+    },
+
     #[snafu(display("Failed to build HTTP response, source: {source}"))]
     BuildHttpResponse {
         source: http::Error,
@@ -292,7 +387,6 @@ impl ErrorExt for Error {
             Internal { .. }
             | InternalIo { .. }
             | TokioIo { .. }
-            | CollectRecordbatch { .. }
             | StartHttp { .. }
             | StartGrpc { .. }
             | AlreadyStarted { .. }
@@ -301,6 +395,7 @@ impl ErrorExt for Error {
             | CatalogError { .. }
             | GrpcReflectionService { .. }
             | BuildingContext { .. }
+            | EncodeHttpOutput { .. }
             | BuildHttpResponse { .. } => StatusCode::Internal,
 
             InsertScript { source, .. }
@@ -308,26 +403,47 @@ impl ErrorExt for Error {
             | ExecuteQuery { source, .. }
             | ExecuteGrpcQuery { source, .. }
             | ExecuteStatement { source, .. }
+            | CollectRecordbatch { source }
+            | BuildBulkInsertRecordBatch { source }
             | CheckDatabaseValidity { source, .. }
             | ExecuteAlter { source, .. }
             | PutOpentsdbDataPoint { source, .. } => source.status_code(),
 
             NotSupported { .. }
             | InvalidQuery { .. }
+            | InvalidInfluxql { .. }
             | InfluxdbLineProtocol { .. }
+            | InvalidInfluxdbLines { .. }
+            | DecompressInfluxdbRequest { .. }
             | ConnResetByPeer { .. }
             | InvalidOpentsdbLine { .. }
             | InvalidOpentsdbJsonRequest { .. }
+            | InvalidOpentsdbQuery { .. }
+            | DecodeOtlpRequest { .. }
+            | DecompressOtlpRequest { .. }
+            | InvalidOtlpRequest { .. }
+            | InvalidGraphiteLine { .. }
+            | InvalidGraphitePickle { .. }
+            | InvalidGraphiteTemplate { .. }
             | DecodePromRemoteRequest { .. }
             | DecompressPromRemoteRequest { .. }
             | InvalidPromRemoteRequest { .. }
             | InvalidFlightTicket { .. }
+            | InvalidFlightDescriptor { .. }
+            | InvalidFlightPut { .. }
+            | InvalidFlightSqlCommand { .. }
+            | InvalidPostgresCopy { .. }
+            | InvalidBulkInsert { .. }
+            | InvalidMysqlLoadData { .. }
             | InvalidPrepareStatement { .. }
             | TimePrecision { .. } => StatusCode::InvalidArguments,
 
-            InfluxdbLinesWrite { source, .. } | ConvertFlightMessage { source } => {
-                source.status_code()
-            }
+            InfluxdbLinesWrite { source, .. }
+            | OtlpMetricsWrite { source, .. }
+            | GraphiteLinesWrite { source, .. }
+            | ConvertFlightMessage { source } => source.status_code(),
+
+            QueryTimeout { .. } => StatusCode::QueryTimeout,
 
             Hyper { .. } => StatusCode::Unknown,
             TlsRequired { .. } => StatusCode::Unknown,
@@ -371,7 +487,13 @@ impl From<Error> for tonic::Status {
         }
 
         let metadata = MetadataMap::from_headers(headers);
-        tonic::Status::with_metadata(Code::Internal, err.to_string(), metadata)
+        let code = match err.status_code() {
+            StatusCode::Cancelled => Code::Cancelled,
+            StatusCode::QueryTimeout => Code::DeadlineExceeded,
+            StatusCode::ExceedMemoryLimit => Code::ResourceExhausted,
+            _ => Code::Internal,
+        };
+        tonic::Status::with_metadata(code, err.to_string(), metadata)
     }
 }
 
@@ -392,14 +514,32 @@ impl IntoResponse for Error {
         let (status, error_message) = match self {
             Error::InfluxdbLineProtocol { .. }
             | Error::InfluxdbLinesWrite { .. }
+            | Error::InvalidInfluxdbLines { .. }
+            | Error::DecompressInfluxdbRequest { .. }
             | Error::InvalidOpentsdbLine { .. }
             | Error::InvalidOpentsdbJsonRequest { .. }
+            | Error::InvalidOpentsdbQuery { .. }
+            | Error::DecodeOtlpRequest { .. }
+            | Error::DecompressOtlpRequest { .. }
+            | Error::InvalidOtlpRequest { .. }
+            | Error::OtlpMetricsWrite { .. }
             | Error::DecodePromRemoteRequest { .. }
             | Error::DecompressPromRemoteRequest { .. }
             | Error::InvalidPromRemoteRequest { .. }
             | Error::InvalidQuery { .. }
+            | Error::InvalidInfluxql { .. }
             | Error::TimePrecision { .. } => (HttpStatusCode::BAD_REQUEST, self.to_string()),
-            _ => (HttpStatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+            _ => {
+                // The statements stopped by their limits, like the other protocols tell them
+                // apart from the internal errors.
+                let status = match self.status_code() {
+                    StatusCode::QueryTimeout => HttpStatusCode::GATEWAY_TIMEOUT,
+                    StatusCode::Cancelled => HttpStatusCode::SERVICE_UNAVAILABLE,
+                    StatusCode::ExceedMemoryLimit => HttpStatusCode::UNPROCESSABLE_ENTITY,
+                    _ => HttpStatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, self.to_string())
+            }
         };
         let body = Json(json!({
             "error": error_message,
@@ -407,3 +547,30 @@ impl IntoResponse for Error {
         (status, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common_recordbatch::error::StreamCancelledSnafu;
+
+    use super::*;
+
+    #[test]
+    fn test_http_status_of_stopped_statements() {
+        let err = QueryTimeoutSnafu {
+            timeout: Duration::from_secs(1),
+        }
+        .build();
+        assert_eq!(
+            HttpStatusCode::GATEWAY_TIMEOUT,
+            err.into_response().status()
+        );
+
+        let err = Error::CollectRecordbatch {
+            source: StreamCancelledSnafu.build(),
+        };
+        assert_eq!(
+            HttpStatusCode::SERVICE_UNAVAILABLE,
+            err.into_response().status()
+        );
+    }
+}
@@ -0,0 +1,1190 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A server of [Arrow Flight SQL](https://arrow.apache.org/docs/format/FlightSql.html), which
+//! serves the SQL queries by the [SqlQueryHandler](crate::query_handler::sql::SqlQueryHandler)
+//! for the JDBC/ADBC drivers and the BI tools built on them.
+//!
+//! Flight SQL shares the service name with the Flight service of the [GrpcServer], so it's
+//! served on its own address.
+//!
+//! [GrpcServer]: crate::grpc::GrpcServer
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::sql::{
+    ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest,
+    ActionCreatePreparedStatementResult, Any, CommandGetCatalogs, CommandGetDbSchemas,
+    CommandGetTableTypes, CommandGetTables, CommandPreparedStatementQuery,
+    CommandPreparedStatementUpdate, CommandStatementQuery, CommandStatementUpdate,
+    DoPutUpdateResult, ProstMessageExt, TicketStatementQuery,
+};
+use arrow_flight::utils::batches_to_flight_data;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use common_grpc::flight::{FlightDecoder, FlightMessage};
+use common_query::Output;
+use common_recordbatch::RecordBatches;
+use common_runtime::Runtime;
+use common_telemetry::logging::info;
+use datatypes::arrow::array::{ArrayRef, BinaryArray, StringArray};
+use datatypes::arrow::datatypes::{DataType, Field, Schema as ArrowSchema, SchemaRef};
+use datatypes::arrow::ipc::writer::IpcWriteOptions;
+use datatypes::arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use datatypes::value::Value;
+use futures::FutureExt;
+use parking_lot::RwLock;
+use prost::Message;
+use regex::Regex;
+use session::context::{QueryContext, QueryContextRef, UserInfo};
+use snafu::{ensure, OptionExt, ResultExt};
+use sql::dialect::GenericDialect;
+use sql::parser::ParserContext;
+use sql::statements::statement::Statement;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot::{self, Sender};
+use tokio::sync::Mutex;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::metadata::{AsciiMetadataValue, MetadataMap};
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::auth::{Identity, Password, UserProviderRef};
+use crate::error::{self, AlreadyStartedSnafu, Result, StartGrpcSnafu, TcpBindSnafu};
+use crate::grpc::flight::stream::FlightRecordBatchStream;
+use crate::grpc::flight::TonicStream;
+use crate::grpc::TonicResult;
+use crate::query_handler::sql::{
+    query_strings, quote_ident, quote_string, value_to_string, ServerSqlQueryHandlerRef,
+};
+use crate::server::Server;
+
+pub const FLIGHT_SQL_SERVER: &str = "FLIGHT_SQL_SERVER";
+
+const CREATE_PREPARED_STATEMENT: &str = "CreatePreparedStatement";
+const CLOSE_PREPARED_STATEMENT: &str = "ClosePreparedStatement";
+
+const AUTHORIZATION_HEADER: &str = "authorization";
+/// The header to select the database, in the form of `[<catalog>-]<schema>`.
+const DATABASE_HEADER: &str = "database";
+
+/// The only table type of GreptimeDB.
+const TABLE_TYPE: &str = "TABLE";
+
+/// How long the bearer tokens issued by the handshakes are valid.
+const TOKEN_TTL: Duration = Duration::from_secs(60 * 60);
+/// How long the parameters bound to the prepared statements are kept, if the statements are
+/// never closed.
+const PARAMETERS_TTL: Duration = Duration::from_secs(60 * 60);
+
+pub struct FlightSqlServer {
+    shutdown_tx: Mutex<Option<Sender<()>>>,
+    handler: Arc<FlightSqlHandler>,
+}
+
+impl FlightSqlServer {
+    pub fn new(
+        query_handler: ServerSqlQueryHandlerRef,
+        user_provider: Option<UserProviderRef>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            shutdown_tx: Mutex::new(None),
+            handler: Arc::new(FlightSqlHandler::new(query_handler, user_provider, runtime)),
+        }
+    }
+
+    pub fn create_flight_sql_service(&self) -> FlightServiceServer<impl FlightService> {
+        FlightServiceServer::from_arc(self.handler.clone())
+    }
+}
+
+#[async_trait]
+impl Server for FlightSqlServer {
+    async fn shutdown(&self) -> Result<()> {
+        let mut shutdown_tx = self.shutdown_tx.lock().await;
+        if let Some(tx) = shutdown_tx.take() {
+            if tx.send(()).is_err() {
+                info!("Receiver dropped, the Flight SQL server has already existed");
+            }
+        }
+        info!("Shutdown Flight SQL server");
+
+        Ok(())
+    }
+
+    async fn start(&self, addr: SocketAddr) -> Result<SocketAddr> {
+        let (tx, rx) = oneshot::channel();
+        let (listener, addr) = {
+            let mut shutdown_tx = self.shutdown_tx.lock().await;
+            ensure!(
+                shutdown_tx.is_none(),
+                AlreadyStartedSnafu {
+                    server: "Flight SQL"
+                }
+            );
+
+            let listener = TcpListener::bind(addr)
+                .await
+                .context(TcpBindSnafu { addr })?;
+            let addr = listener.local_addr().context(TcpBindSnafu { addr })?;
+            info!("Flight SQL server is bound to {}", addr);
+
+            *shutdown_tx = Some(tx);
+
+            (listener, addr)
+        };
+
+        // Would block to serve requests.
+        tonic::transport::Server::builder()
+            .add_service(self.create_flight_sql_service())
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), rx.map(drop))
+            .await
+            .context(StartGrpcSnafu)?;
+
+        Ok(addr)
+    }
+
+    fn name(&self) -> &str {
+        FLIGHT_SQL_SERVER
+    }
+}
+
+/// The commands of Flight SQL that are supported, which are packed in [Any] as the `cmd` of
+/// [FlightDescriptor]s or the [Ticket]s.
+#[derive(Debug)]
+enum FlightSqlCommand {
+    StatementQuery(CommandStatementQuery),
+    TicketStatementQuery(TicketStatementQuery),
+    PreparedStatementQuery(CommandPreparedStatementQuery),
+    StatementUpdate(CommandStatementUpdate),
+    PreparedStatementUpdate(CommandPreparedStatementUpdate),
+    GetCatalogs(CommandGetCatalogs),
+    GetDbSchemas(CommandGetDbSchemas),
+    GetTables(CommandGetTables),
+    GetTableTypes(CommandGetTableTypes),
+}
+
+impl FlightSqlCommand {
+    fn try_from_any(any: Any) -> Result<Self> {
+        macro_rules! try_unpack {
+            ($($variant:ident($message:ty)),*) => {
+                $(
+                    if any.is::<$message>() {
+                        return unpack::<$message>(&any).map(Self::$variant);
+                    }
+                )*
+            };
+        }
+
+        try_unpack!(
+            StatementQuery(CommandStatementQuery),
+            TicketStatementQuery(TicketStatementQuery),
+            PreparedStatementQuery(CommandPreparedStatementQuery),
+            StatementUpdate(CommandStatementUpdate),
+            PreparedStatementUpdate(CommandPreparedStatementUpdate),
+            GetCatalogs(CommandGetCatalogs),
+            GetDbSchemas(CommandGetDbSchemas),
+            GetTables(CommandGetTables),
+            GetTableTypes(CommandGetTableTypes)
+        );
+
+        error::NotSupportedSnafu {
+            feat: format!("Flight SQL command {}", any.type_url),
+        }
+        .fail()
+    }
+}
+
+fn unpack<M: ProstMessageExt>(any: &Any) -> Result<M> {
+    any.unpack::<M>()
+        .ok()
+        .flatten()
+        .with_context(|| error::InvalidFlightSqlCommandSnafu {
+            reason: format!("failed to unpack {}", any.type_url),
+        })
+}
+
+fn decode_any(bytes: &[u8]) -> Result<Any> {
+    Any::decode(bytes).map_err(|e| {
+        error::InvalidFlightSqlCommandSnafu {
+            reason: e.to_string(),
+        }
+        .build()
+    })
+}
+
+/// The statement handles of the tickets are the SQL themselves.
+fn handle_to_sql(handle: &[u8]) -> Result<String> {
+    String::from_utf8(handle.to_vec()).map_err(|_| {
+        error::InvalidFlightSqlCommandSnafu {
+            reason: "invalid statement handle",
+        }
+        .build()
+    })
+}
+
+/// The length of the random prefix of the prepared statement handles.
+const PREPARED_STATEMENT_ID_LEN: usize = 32;
+
+/// The handles of the prepared statements are the SQL prefixed by a random id, so the SQL
+/// survives the restarts, while the parameters bound to a prepared statement are kept apart
+/// from the others of the same SQL.
+fn prepared_statement_handle(sql: String) -> Bytes {
+    format!("{:032x}{sql}", rand::random::<u128>()).into()
+}
+
+fn prepared_handle_to_sql(handle: &[u8]) -> Result<String> {
+    handle
+        .get(PREPARED_STATEMENT_ID_LEN..)
+        .and_then(|sql| String::from_utf8(sql.to_vec()).ok())
+        .context(error::InvalidFlightSqlCommandSnafu {
+            reason: "invalid prepared statement handle",
+        })
+}
+
+/// Finds the byte offsets of the `?` placeholders, which are out of the quoted strings and
+/// identifiers.
+fn find_placeholders(sql: &str) -> Vec<usize> {
+    let mut quote = None;
+    sql.char_indices()
+        .filter(|(_, c)| match quote {
+            None if *c == '\'' || *c == '"' => {
+                quote = Some(*c);
+                false
+            }
+            None => *c == '?',
+            Some(q) => {
+                if *c == q {
+                    quote = None;
+                }
+                false
+            }
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Replaces the placeholders in the SQL with the parameters, which are SQL literals, in order.
+fn bind_parameters(sql: &str, parameters: &[String]) -> Result<String> {
+    let placeholders = find_placeholders(sql);
+    ensure!(
+        placeholders.len() == parameters.len(),
+        error::InvalidQuerySnafu {
+            reason: format!(
+                "Expecting {} parameters, but got {}.",
+                placeholders.len(),
+                parameters.len()
+            ),
+        }
+    );
+
+    let mut bound = String::with_capacity(sql.len());
+    let mut start = 0;
+    for (placeholder, parameter) in placeholders.into_iter().zip(parameters) {
+        bound.push_str(&sql[start..placeholder]);
+        bound.push_str(parameter);
+        start = placeholder + 1;
+    }
+    bound.push_str(&sql[start..]);
+    Ok(bound)
+}
+
+/// Converts the parameter into a SQL literal.
+fn to_literal(value: Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Boolean(_)
+        | Value::UInt8(_)
+        | Value::UInt16(_)
+        | Value::UInt32(_)
+        | Value::UInt64(_)
+        | Value::Int8(_)
+        | Value::Int16(_)
+        | Value::Int32(_)
+        | Value::Int64(_)
+        | Value::Float32(_)
+        | Value::Float64(_) => value.to_string(),
+        value => quote_string(&value_to_string(value).unwrap_or_default()),
+    }
+}
+
+/// Reads the parameter sets bound by DoPut, which are the rows of the record batches following
+/// the schema in the first message, as SQL literals.
+async fn read_parameter_sets(
+    first: FlightData,
+    stream: &mut Streaming<FlightData>,
+) -> TonicResult<Vec<Vec<String>>> {
+    // No parameters are bound if there's no schema.
+    if first.data_header.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut decoder = FlightDecoder::default();
+    let _ = decoder
+        .try_decode(first)
+        .context(error::ConvertFlightMessageSnafu)?;
+    let mut parameter_sets = vec![];
+    while let Some(flight_data) = stream.message().await? {
+        let message = decoder
+            .try_decode(flight_data)
+            .context(error::ConvertFlightMessageSnafu)?;
+        let FlightMessage::Recordbatch(recordbatch) = message else {
+            return Err(error::InvalidFlightPutSnafu {
+                reason: "Expecting the parameters are all record batches.",
+            }
+            .build()
+            .into());
+        };
+        for row in 0..recordbatch.num_rows() {
+            let parameters = recordbatch
+                .columns()
+                .iter()
+                .map(|column| to_literal(column.get(row)))
+                .collect();
+            parameter_sets.push(parameters);
+        }
+    }
+    Ok(parameter_sets)
+}
+
+/// A map whose entries expire in the time to live since they are inserted, so the entries that
+/// the clients never remove don't pile up.
+struct ExpiringMap<K, V> {
+    ttl: Duration,
+    entries: RwLock<HashMap<K, (V, Instant)>>,
+}
+
+impl<K: Eq + Hash, V: Clone> ExpiringMap<K, V> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn insert(&self, key: K, value: V) {
+        let mut entries = self.entries.write();
+        entries.retain(|_, (_, inserted)| inserted.elapsed() < self.ttl);
+        let _ = entries.insert(key, (value, Instant::now()));
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries
+            .read()
+            .get(key)
+            .filter(|(_, inserted)| inserted.elapsed() < self.ttl)
+            .map(|(value, _)| value.clone())
+    }
+
+    fn remove<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let _ = self.entries.write().remove(key);
+    }
+}
+
+struct FlightSqlHandler {
+    query_handler: ServerSqlQueryHandlerRef,
+    user_provider: Option<UserProviderRef>,
+    runtime: Arc<Runtime>,
+    /// The users of the bearer tokens issued by the handshakes.
+    tokens: ExpiringMap<String, UserInfo>,
+    /// The parameters bound to the prepared queries, by their handles.
+    parameters: ExpiringMap<Bytes, Vec<String>>,
+}
+
+impl FlightSqlHandler {
+    fn new(
+        query_handler: ServerSqlQueryHandlerRef,
+        user_provider: Option<UserProviderRef>,
+        runtime: Arc<Runtime>,
+    ) -> Self {
+        Self {
+            query_handler,
+            user_provider,
+            runtime,
+            tokens: ExpiringMap::new(TOKEN_TTL),
+            parameters: ExpiringMap::new(PARAMETERS_TTL),
+        }
+    }
+
+    /// Creates the query context of the database in the request header, and checks the
+    /// authorization of the request, which is either a bearer token issued by the handshake or
+    /// the basic credential.
+    async fn query_context<T>(&self, request: &Request<T>) -> TonicResult<QueryContextRef> {
+        let ctx = QueryContext::arc();
+        if let Some(database) = request.metadata().get(DATABASE_HEADER) {
+            let database = database
+                .to_str()
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            let (catalog, schema) =
+                crate::parse_catalog_and_schema_from_client_database_name(database);
+            ctx.set_current_catalog(catalog);
+            ctx.set_current_schema(schema);
+        }
+
+        let Some(user_provider) = self.user_provider.as_ref() else { return Ok(ctx) };
+        let metadata = request.metadata();
+        let user_info = match authorization(metadata)?.strip_prefix("Bearer ") {
+            Some(token) => self
+                .tokens
+                .get(token)
+                .ok_or_else(|| Status::unauthenticated("Invalid or expired bearer token"))?,
+            None => self.authenticate(user_provider, metadata).await?,
+        };
+        user_provider
+            .authorize(&ctx.current_catalog(), &ctx.current_schema(), &user_info)
+            .await
+            .map_err(|e| Status::permission_denied(e.to_string()))?;
+        ctx.set_current_user(user_info);
+        Ok(ctx)
+    }
+
+    async fn authenticate(
+        &self,
+        user_provider: &UserProviderRef,
+        metadata: &MetadataMap,
+    ) -> TonicResult<UserInfo> {
+        let credential = authorization(metadata)?
+            .strip_prefix("Basic ")
+            .context(error::UnsupportedAuthSchemeSnafu {
+                name: "non-basic Flight SQL authorization",
+            })
+            .map_err(|e| Status::unauthenticated(e.to_string()))?;
+        let (username, password) = decode_basic(credential)?;
+        user_provider
+            .authenticate(
+                Identity::UserId(&username, None),
+                Password::PlainText(&password),
+            )
+            .await
+            .map_err(|e| Status::unauthenticated(e.to_string()))
+    }
+
+    /// Executes the SQL, which must be a single statement in Flight SQL, in the runtime.
+    async fn execute(&self, sql: String, ctx: QueryContextRef) -> TonicResult<Output> {
+        let _ = parse_statement(&sql)?;
+
+        let handler = self.query_handler.clone();
+        // Executes the statement in another runtime to prevent the execution from being
+        // cancelled by Tonic, the same as the `GreptimeRequestHandler`.
+        let handle = self
+            .runtime
+            .spawn(async move { handler.do_query(&sql, ctx).await });
+        let outputs = handle.await.map_err(|e| {
+            if e.is_cancelled() {
+                Status::cancelled(e.to_string())
+            } else if e.is_panic() {
+                Status::internal(format!("{:?}", e.into_panic()))
+            } else {
+                Status::unknown(e.to_string())
+            }
+        })?;
+        let mut outputs = outputs.into_iter();
+        match (outputs.next(), outputs.next()) {
+            (Some(output), None) => Ok(output?),
+            _ => Err(error::InvalidQuerySnafu {
+                reason: "Flight SQL expects exactly one statement.",
+            }
+            .build()
+            .into()),
+        }
+    }
+
+    /// Executes the update, and returns the affected rows, which are unknown for the queries.
+    async fn execute_update(&self, sql: String, ctx: QueryContextRef) -> TonicResult<i64> {
+        match self.execute(sql, ctx).await? {
+            Output::AffectedRows(rows) => Ok(rows as i64),
+            _ => Ok(-1),
+        }
+    }
+
+    /// Describes the result schema of the SQL, which is empty for the non-query statements.
+    async fn describe(&self, sql: &str, ctx: QueryContextRef) -> Result<SchemaRef> {
+        let statement = parse_statement(sql)?;
+        let schema = self.query_handler.do_describe(statement, ctx).await?;
+        Ok(schema.map_or_else(
+            || Arc::new(ArrowSchema::empty()),
+            |schema| schema.arrow_schema().clone(),
+        ))
+    }
+
+    /// Resolves the command of [FlightDescriptor] to the schema of its result, and the command
+    /// of the [Ticket] to get the result.
+    async fn plan(
+        &self,
+        command: FlightSqlCommand,
+        ctx: QueryContextRef,
+    ) -> TonicResult<(SchemaRef, Any)> {
+        let planned = match command {
+            FlightSqlCommand::StatementQuery(command) => {
+                let schema = self.describe(&command.query, ctx).await?;
+                let ticket = TicketStatementQuery {
+                    statement_handle: command.query.into_bytes().into(),
+                };
+                (schema, ticket.as_any())
+            }
+            FlightSqlCommand::PreparedStatementQuery(command) => {
+                let sql = self.prepared_query(&command.prepared_statement_handle)?;
+                (self.describe(&sql, ctx).await?, command.as_any())
+            }
+            FlightSqlCommand::GetCatalogs(command) => (catalogs_schema(), command.as_any()),
+            FlightSqlCommand::GetDbSchemas(command) => (db_schemas_schema(), command.as_any()),
+            FlightSqlCommand::GetTables(command) => {
+                (tables_schema(command.include_schema), command.as_any())
+            }
+            FlightSqlCommand::GetTableTypes(command) => (table_types_schema(), command.as_any()),
+            command => {
+                return Err(error::InvalidFlightSqlCommandSnafu {
+                    reason: format!("unexpected command in FlightDescriptor: {command:?}"),
+                }
+                .build()
+                .into())
+            }
+        };
+        Ok(planned)
+    }
+
+    /// Returns the SQL of the prepared query, with the parameters bound by DoPut.
+    fn prepared_query(&self, handle: &Bytes) -> Result<String> {
+        let sql = prepared_handle_to_sql(handle)?;
+        let parameters = self.parameters.get(handle).unwrap_or_default();
+        bind_parameters(&sql, &parameters)
+    }
+
+    async fn get_catalogs(&self, ctx: QueryContextRef) -> TonicResult<Vec<FlightData>> {
+        let catalogs = StringArray::from(vec![ctx.current_catalog()]);
+        to_flight_data(catalogs_schema(), vec![Arc::new(catalogs)])
+    }
+
+    async fn get_db_schemas(
+        &self,
+        command: CommandGetDbSchemas,
+        ctx: QueryContextRef,
+    ) -> TonicResult<Vec<FlightData>> {
+        let (catalogs, schemas): (Vec<_>, Vec<_>) = self
+            .list_db_schemas(
+                command.catalog.as_deref(),
+                command.db_schema_filter_pattern.as_deref(),
+                &ctx,
+            )
+            .await?
+            .into_iter()
+            .unzip();
+        to_flight_data(
+            db_schemas_schema(),
+            vec![
+                Arc::new(StringArray::from(catalogs)),
+                Arc::new(StringArray::from(schemas)),
+            ],
+        )
+    }
+
+    async fn get_tables(
+        &self,
+        command: CommandGetTables,
+        ctx: QueryContextRef,
+    ) -> TonicResult<Vec<FlightData>> {
+        let include_schema = command.include_schema;
+        let mut columns: [Vec<String>; 4] = Default::default();
+        let mut table_schemas = vec![];
+        if command.table_types.is_empty() || command.table_types.iter().any(|t| t == TABLE_TYPE) {
+            let pattern = command
+                .table_name_filter_pattern
+                .as_deref()
+                .map(like_to_regex)
+                .transpose()?;
+            let schemas = self
+                .list_db_schemas(
+                    command.catalog.as_deref(),
+                    command.db_schema_filter_pattern.as_deref(),
+                    &ctx,
+                )
+                .await?;
+            for (catalog, schema) in schemas {
+                let ctx = Arc::new(QueryContext::with(&catalog, &schema));
+                let tables = query_strings(&self.query_handler, "SHOW TABLES", ctx.clone()).await?;
+                for table in tables {
+                    if !pattern.as_ref().map_or(true, |p| p.is_match(&table)) {
+                        continue;
+                    }
+                    if include_schema {
+                        let sql = format!("SELECT * FROM {}", quote_ident(&table));
+                        let schema = self.describe(&sql, ctx.clone()).await?;
+                        table_schemas.push(encode_schema(&schema)?.to_vec());
+                    }
+                    let row = [
+                        catalog.clone(),
+                        schema.clone(),
+                        table,
+                        TABLE_TYPE.to_string(),
+                    ];
+                    for (column, value) in columns.iter_mut().zip(row) {
+                        column.push(value);
+                    }
+                }
+            }
+        }
+
+        let mut arrays = columns
+            .into_iter()
+            .map(|column| Arc::new(StringArray::from(column)) as ArrayRef)
+            .collect::<Vec<_>>();
+        if include_schema {
+            arrays.push(Arc::new(BinaryArray::from_iter_values(table_schemas)));
+        }
+        to_flight_data(tables_schema(include_schema), arrays)
+    }
+
+    /// Lists the schemas of the catalog, which is the current one if not specified, filtered by
+    /// the `LIKE` pattern.
+    async fn list_db_schemas(
+        &self,
+        catalog: Option<&str>,
+        pattern: Option<&str>,
+        ctx: &QueryContextRef,
+    ) -> TonicResult<Vec<(String, String)>> {
+        let catalog = catalog.map_or_else(|| ctx.current_catalog(), ToString::to_string);
+        // An empty catalog is for the schemas without catalog, which don't exist.
+        if catalog.is_empty() {
+            return Ok(vec![]);
+        }
+        let pattern = pattern.map(like_to_regex).transpose()?;
+
+        let ctx = Arc::new(QueryContext::with(&catalog, &ctx.current_schema()));
+        let schemas = query_strings(&self.query_handler, "SHOW DATABASES", ctx).await?;
+        Ok(schemas
+            .into_iter()
+            .filter(|schema| pattern.as_ref().map_or(true, |p| p.is_match(schema)))
+            .map(|schema| (catalog.clone(), schema))
+            .collect())
+    }
+
+    async fn get_table_types(&self) -> TonicResult<Vec<FlightData>> {
+        let table_types = StringArray::from(vec![TABLE_TYPE]);
+        to_flight_data(table_types_schema(), vec![Arc::new(table_types)])
+    }
+}
+
+fn parse_statement(sql: &str) -> Result<Statement> {
+    let mut statements =
+        ParserContext::create_with_dialect(sql, &GenericDialect {}).map_err(|e| {
+            error::InvalidQuerySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        })?;
+    ensure!(
+        statements.len() == 1,
+        error::InvalidQuerySnafu {
+            reason: "Flight SQL expects exactly one statement.",
+        }
+    );
+    Ok(statements.remove(0))
+}
+
+fn authorization(metadata: &MetadataMap) -> TonicResult<&str> {
+    metadata
+        .get(AUTHORIZATION_HEADER)
+        .context(error::NotFoundAuthHeaderSnafu)
+        .map_err(|e| Status::unauthenticated(e.to_string()))?
+        .to_str()
+        .map_err(|e| Status::unauthenticated(e.to_string()))
+}
+
+fn decode_basic(credential: &str) -> TonicResult<(String, String)> {
+    let decoded = base64::decode(credential)
+        .context(error::InvalidBase64ValueSnafu)
+        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    let decoded = String::from_utf8(decoded)
+        .context(error::InvalidUtf8ValueSnafu)
+        .map_err(|e| Status::unauthenticated(e.to_string()))?;
+    decoded
+        .split_once(':')
+        .map(|(username, password)| (username.to_string(), password.to_string()))
+        .ok_or_else(|| Status::unauthenticated("Invalid basic credential"))
+}
+
+/// Converts the SQL `LIKE` pattern, where `%` and `_` match any characters and any single
+/// character, into a [Regex].
+fn like_to_regex(pattern: &str) -> Result<Regex> {
+    let mut regex = String::with_capacity(pattern.len() + 2);
+    regex.push('^');
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    regex.push_str(&regex::escape(&c.to_string()));
+                }
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|e| {
+        error::InvalidFlightSqlCommandSnafu {
+            reason: format!("invalid pattern '{pattern}': {e}"),
+        }
+        .build()
+    })
+}
+
+fn catalogs_schema() -> SchemaRef {
+    Arc::new(ArrowSchema::new(vec![Field::new(
+        "catalog_name",
+        DataType::Utf8,
+        false,
+    )]))
+}
+
+fn db_schemas_schema() -> SchemaRef {
+    Arc::new(ArrowSchema::new(vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, false),
+    ]))
+}
+
+fn tables_schema(include_schema: bool) -> SchemaRef {
+    let mut fields = vec![
+        Field::new("catalog_name", DataType::Utf8, true),
+        Field::new("db_schema_name", DataType::Utf8, true),
+        Field::new("table_name", DataType::Utf8, false),
+        Field::new("table_type", DataType::Utf8, false),
+    ];
+    if include_schema {
+        fields.push(Field::new("table_schema", DataType::Binary, false));
+    }
+    Arc::new(ArrowSchema::new(fields))
+}
+
+fn table_types_schema() -> SchemaRef {
+    Arc::new(ArrowSchema::new(vec![Field::new(
+        "table_type",
+        DataType::Utf8,
+        false,
+    )]))
+}
+
+/// The types of the parameters are not inferred, so they are all strings, while the values of
+/// any type are bound as SQL literals.
+fn parameters_schema(placeholders: usize) -> ArrowSchema {
+    let fields = (1..=placeholders)
+        .map(|i| Field::new(format!("${i}"), DataType::Utf8, true))
+        .collect::<Vec<_>>();
+    ArrowSchema::new(fields)
+}
+
+fn encode_schema(schema: &ArrowSchema) -> TonicResult<bytes::Bytes> {
+    let message = IpcMessage::try_from(SchemaAsIpc::new(schema, &IpcWriteOptions::default()))
+        .map_err(|e| Status::internal(e.to_string()))?;
+    Ok(message.0)
+}
+
+fn to_flight_data(schema: SchemaRef, columns: Vec<ArrayRef>) -> TonicResult<Vec<FlightData>> {
+    let batch = ArrowRecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| Status::internal(e.to_string()))?;
+    batches_to_flight_data(schema.as_ref().clone(), vec![batch])
+        .map_err(|e| Status::internal(e.to_string()))
+}
+
+fn to_flight_data_stream(output: Output) -> TonicStream<FlightData> {
+    let stream = match output {
+        Output::Stream(stream) => stream,
+        Output::RecordBatches(recordbatches) => recordbatches.as_stream(),
+        // The non-query statements have no result sets.
+        Output::AffectedRows(_) => RecordBatches::empty().as_stream(),
+    };
+    Box::pin(FlightRecordBatchStream::new(stream))
+}
+
+fn iter_stream<T: Send + Sync + 'static>(items: Vec<T>) -> TonicStream<T> {
+    Box::pin(futures::stream::iter(items.into_iter().map(Ok)))
+}
+
+#[async_trait]
+impl FlightService for FlightSqlHandler {
+    type HandshakeStream = TonicStream<HandshakeResponse>;
+
+    /// Authenticates the basic credential in the `authorization` header, and replies a bearer
+    /// token in the header for the following requests, which expires in [TOKEN_TTL].
+    async fn handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> TonicResult<Response<Self::HandshakeStream>> {
+        let token = format!("{:032x}", rand::random::<u128>());
+        if let Some(user_provider) = self.user_provider.as_ref() {
+            let user_info = self.authenticate(user_provider, request.metadata()).await?;
+            self.tokens.insert(token.clone(), user_info);
+        }
+
+        let handshake = HandshakeResponse {
+            protocol_version: 0,
+            payload: token.clone().into_bytes().into(),
+        };
+        let mut response = Response::new(iter_stream(vec![handshake]));
+        let value = format!("Bearer {token}")
+            .parse::<AsciiMetadataValue>()
+            .map_err(|_| Status::internal("Invalid bearer token"))?;
+        let _ = response.metadata_mut().insert(AUTHORIZATION_HEADER, value);
+        Ok(response)
+    }
+
+    type ListFlightsStream = TonicStream<FlightInfo>;
+
+    async fn list_flights(
+        &self,
+        _: Request<Criteria>,
+    ) -> TonicResult<Response<Self::ListFlightsStream>> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> TonicResult<Response<FlightInfo>> {
+        let ctx = self.query_context(&request).await?;
+        let descriptor = request.into_inner();
+        let command = FlightSqlCommand::try_from_any(decode_any(&descriptor.cmd)?)?;
+
+        let (schema, ticket) = self.plan(command, ctx).await?;
+        let endpoint = FlightEndpoint {
+            ticket: Some(Ticket {
+                ticket: ticket.encode_to_vec().into(),
+            }),
+            location: vec![],
+        };
+        let message = IpcMessage(encode_schema(&schema)?);
+        let info = FlightInfo::new(message, Some(descriptor), vec![endpoint], -1, -1);
+        Ok(Response::new(info))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> TonicResult<Response<SchemaResult>> {
+        let ctx = self.query_context(&request).await?;
+        let command = FlightSqlCommand::try_from_any(decode_any(&request.get_ref().cmd)?)?;
+
+        let (schema, _) = self.plan(command, ctx).await?;
+        Ok(Response::new(SchemaResult {
+            schema: encode_schema(&schema)?,
+        }))
+    }
+
+    type DoGetStream = TonicStream<FlightData>;
+
+    async fn do_get(&self, request: Request<Ticket>) -> TonicResult<Response<Self::DoGetStream>> {
+        let ctx = self.query_context(&request).await?;
+        let command = FlightSqlCommand::try_from_any(decode_any(&request.get_ref().ticket)?)?;
+
+        let stream = match command {
+            FlightSqlCommand::TicketStatementQuery(ticket) => {
+                let sql = handle_to_sql(&ticket.statement_handle)?;
+                to_flight_data_stream(self.execute(sql, ctx).await?)
+            }
+            FlightSqlCommand::PreparedStatementQuery(command) => {
+                let sql = self.prepared_query(&command.prepared_statement_handle)?;
+                to_flight_data_stream(self.execute(sql, ctx).await?)
+            }
+            FlightSqlCommand::GetCatalogs(_) => iter_stream(self.get_catalogs(ctx).await?),
+            FlightSqlCommand::GetDbSchemas(command) => {
+                iter_stream(self.get_db_schemas(command, ctx).await?)
+            }
+            FlightSqlCommand::GetTables(command) => {
+                iter_stream(self.get_tables(command, ctx).await?)
+            }
+            FlightSqlCommand::GetTableTypes(_) => iter_stream(self.get_table_types().await?),
+            command => {
+                return Err(error::InvalidFlightSqlCommandSnafu {
+                    reason: format!("unexpected command in Ticket: {command:?}"),
+                }
+                .build()
+                .into())
+            }
+        };
+        Ok(Response::new(stream))
+    }
+
+    type DoPutStream = TonicStream<PutResult>;
+
+    /// Executes the updates, which are the statements without result sets, and replies the
+    /// affected rows in [DoPutUpdateResult]. A prepared update is executed once for every
+    /// parameter set in the record batches following the first message.
+    ///
+    /// Binds the parameters to a prepared query, which are a single parameter set, for the
+    /// following executions of it.
+    async fn do_put(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> TonicResult<Response<Self::DoPutStream>> {
+        let ctx = self.query_context(&request).await?;
+        let mut stream = request.into_inner();
+        let flight_data = stream
+            .message()
+            .await?
+            .context(error::InvalidFlightPutSnafu {
+                reason: "Expecting a FlightDescriptor in the first message.",
+            })?;
+        let descriptor =
+            flight_data
+                .flight_descriptor
+                .as_ref()
+                .context(error::InvalidFlightPutSnafu {
+                    reason: "Expecting a FlightDescriptor in the first message.",
+                })?;
+
+        let record_count = match FlightSqlCommand::try_from_any(decode_any(&descriptor.cmd)?)? {
+            FlightSqlCommand::StatementUpdate(command) => {
+                self.execute_update(command.query, ctx).await?
+            }
+            FlightSqlCommand::PreparedStatementUpdate(command) => {
+                let sql = prepared_handle_to_sql(&command.prepared_statement_handle)?;
+                let parameter_sets = read_parameter_sets(flight_data, &mut stream).await?;
+                if parameter_sets.is_empty() {
+                    let sql = bind_parameters(&sql, &[])?;
+                    self.execute_update(sql, ctx).await?
+                } else {
+                    let mut record_count = 0;
+                    for parameters in parameter_sets {
+                        let sql = bind_parameters(&sql, &parameters)?;
+                        record_count += self.execute_update(sql, ctx.clone()).await?;
+                    }
+                    record_count
+                }
+            }
+            FlightSqlCommand::PreparedStatementQuery(command) => {
+                let handle = command.prepared_statement_handle;
+                let sql = prepared_handle_to_sql(&handle)?;
+                let mut parameter_sets = read_parameter_sets(flight_data, &mut stream).await?;
+                if parameter_sets.len() > 1 {
+                    return Err(error::NotSupportedSnafu {
+                        feat: "binding multiple parameter sets to Flight SQL prepared queries",
+                    }
+                    .build()
+                    .into());
+                }
+                let parameters = parameter_sets.pop().unwrap_or_default();
+                let _ = bind_parameters(&sql, &parameters)?;
+                self.parameters.insert(handle, parameters);
+                return Ok(Response::new(iter_stream(vec![])));
+            }
+            command => {
+                return Err(error::InvalidFlightSqlCommandSnafu {
+                    reason: format!("unexpected command in DoPut: {command:?}"),
+                }
+                .build()
+                .into())
+            }
+        };
+
+        let result = PutResult {
+            app_metadata: DoPutUpdateResult { record_count }.encode_to_vec().into(),
+        };
+        Ok(Response::new(iter_stream(vec![result])))
+    }
+
+    type DoExchangeStream = TonicStream<FlightData>;
+
+    async fn do_exchange(
+        &self,
+        _: Request<Streaming<FlightData>>,
+    ) -> TonicResult<Response<Self::DoExchangeStream>> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    type DoActionStream = TonicStream<arrow_flight::Result>;
+
+    async fn do_action(
+        &self,
+        request: Request<Action>,
+    ) -> TonicResult<Response<Self::DoActionStream>> {
+        let ctx = self.query_context(&request).await?;
+        let action = request.into_inner();
+        let body = decode_any(&action.body)?;
+
+        let results = match action.r#type.as_str() {
+            CREATE_PREPARED_STATEMENT => {
+                let request = unpack::<ActionCreatePreparedStatementRequest>(&body)?;
+                let placeholders = find_placeholders(&request.query).len();
+                // The result schema of a query with placeholders is known once the parameters
+                // are bound.
+                let schema = if placeholders == 0 {
+                    self.describe(&request.query, ctx).await?
+                } else {
+                    Arc::new(ArrowSchema::empty())
+                };
+                let result = ActionCreatePreparedStatementResult {
+                    prepared_statement_handle: prepared_statement_handle(request.query),
+                    dataset_schema: encode_schema(&schema)?,
+                    parameter_schema: encode_schema(&parameters_schema(placeholders))?,
+                };
+                vec![arrow_flight::Result {
+                    body: result.as_any().encode_to_vec().into(),
+                }]
+            }
+            CLOSE_PREPARED_STATEMENT => {
+                let request = unpack::<ActionClosePreparedStatementRequest>(&body)?;
+                self.parameters.remove(&request.prepared_statement_handle);
+                vec![]
+            }
+            other => {
+                return Err(error::NotSupportedSnafu {
+                    feat: format!("Flight SQL action {other}"),
+                }
+                .build()
+                .into())
+            }
+        };
+        Ok(Response::new(iter_stream(results)))
+    }
+
+    type ListActionsStream = TonicStream<ActionType>;
+
+    async fn list_actions(
+        &self,
+        _: Request<Empty>,
+    ) -> TonicResult<Response<Self::ListActionsStream>> {
+        let actions = vec![
+            ActionType {
+                r#type: CREATE_PREPARED_STATEMENT.to_string(),
+                description: "Creates a reusable prepared statement resource on the server.\n\
+                    Request Message: ActionCreatePreparedStatementRequest\n\
+                    Response Message: ActionCreatePreparedStatementResult"
+                    .to_string(),
+            },
+            ActionType {
+                r#type: CLOSE_PREPARED_STATEMENT.to_string(),
+                description: "Closes a reusable prepared statement resource on the server.\n\
+                    Request Message: ActionClosePreparedStatementRequest\n\
+                    Response Message: N/A"
+                    .to_string(),
+            },
+        ];
+        Ok(Response::new(iter_stream(actions)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_to_regex() {
+        let regex = like_to_regex("my%").unwrap();
+        assert!(regex.is_match("my"));
+        assert!(regex.is_match("my_table"));
+        assert!(!regex.is_match("a_my_table"));
+
+        let regex = like_to_regex("t_\\_1").unwrap();
+        assert!(regex.is_match("ta_1"));
+        assert!(!regex.is_match("tab1"));
+        assert!(!regex.is_match("ta_12"));
+
+        let regex = like_to_regex("a.b(").unwrap();
+        assert!(regex.is_match("a.b("));
+        assert!(!regex.is_match("axb("));
+    }
+
+    #[test]
+    fn test_flight_sql_command() {
+        let command = CommandStatementQuery {
+            query: "SELECT 1".to_string(),
+        };
+        let any = decode_any(&command.as_any().encode_to_vec()).unwrap();
+        let FlightSqlCommand::StatementQuery(decoded) = FlightSqlCommand::try_from_any(any).unwrap()
+            else { unreachable!() };
+        assert_eq!(command, decoded);
+
+        let command = ActionCreatePreparedStatementRequest {
+            query: "SELECT 1".to_string(),
+        };
+        let result = FlightSqlCommand::try_from_any(command.as_any());
+        assert!(matches!(result, Err(error::Error::NotSupported { .. })));
+
+        assert!(decode_any(b"invalid").is_err());
+        assert!(handle_to_sql(b"\xff").is_err());
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let sql = "SELECT * FROM t WHERE a = ? AND b = '?' AND \"c?\" IN (?, 'it''s ?')";
+        assert_eq!(vec![26, 53], find_placeholders(sql));
+        assert_eq!(
+            "SELECT * FROM t WHERE a = 1 AND b = '?' AND \"c?\" IN ('x', 'it''s ?')",
+            bind_parameters(sql, &["1".to_string(), "'x'".to_string()]).unwrap()
+        );
+        assert!(bind_parameters(sql, &["1".to_string()]).is_err());
+        assert_eq!("SELECT 1", bind_parameters("SELECT 1", &[]).unwrap());
+
+        assert_eq!("NULL", to_literal(Value::Null));
+        assert_eq!("-1", to_literal(Value::Int64(-1)));
+        assert_eq!("true", to_literal(Value::Boolean(true)));
+        assert_eq!("'it''s'", to_literal(Value::from("it's")));
+    }
+
+    #[test]
+    fn test_prepared_statement_handle() {
+        let handle = prepared_statement_handle("SELECT 1".to_string());
+        assert_eq!("SELECT 1", prepared_handle_to_sql(&handle).unwrap());
+        assert_ne!(handle, prepared_statement_handle("SELECT 1".to_string()));
+        assert!(prepared_handle_to_sql(b"SELECT 1").is_err());
+    }
+
+    #[test]
+    fn test_expiring_map() {
+        let map = ExpiringMap::new(Duration::from_millis(100));
+        map.insert("a".to_string(), 1);
+        assert_eq!(Some(1), map.get("a"));
+        map.remove("a");
+        assert_eq!(None, map.get("a"));
+
+        map.insert("a".to_string(), 1);
+        std::thread::sleep(Duration::from_millis(150));
+        assert_eq!(None, map.get("a"));
+        // The expired entries are evicted on insertion.
+        map.insert("b".to_string(), 2);
+        assert_eq!(1, map.entries.read().len());
+    }
+
+    #[test]
+    fn test_decode_basic() {
+        // "greptime:secret"
+        assert_eq!(
+            ("greptime".to_string(), "secret".to_string()),
+            decode_basic("Z3JlcHRpbWU6c2VjcmV0").unwrap()
+        );
+        assert!(decode_basic("invalid base64!").is_err());
+        // "greptime"
+        assert!(decode_basic("Z3JlcHRpbWU=").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Servers compatible with the carbon receivers of Graphite, which accept the metrics in the
+//! plaintext protocol (`<metric path> <value> <timestamp>` lines) or the pickle protocol.
+
+pub mod codec;
+mod handler;
+mod pickle;
+pub mod template;
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common_runtime::Runtime;
+use common_telemetry::logging::error;
+use futures::StreamExt;
+use tokio::sync::broadcast;
+
+use crate::error::Result;
+use crate::graphite::handler::Handler;
+use crate::graphite::template::Templates;
+use crate::query_handler::GraphiteProtocolHandlerRef;
+use crate::server::{AbortableStream, BaseTcpServer, Server};
+use crate::shutdown::Shutdown;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphiteProtocol {
+    Plaintext,
+    Pickle,
+}
+
+pub struct GraphiteServer {
+    base_server: BaseTcpServer,
+    query_handler: GraphiteProtocolHandlerRef,
+    templates: Arc<Templates>,
+    protocol: GraphiteProtocol,
+
+    /// Broadcasts a shutdown signal to all active connections.
+    notify_shutdown: Option<broadcast::Sender<()>>,
+}
+
+impl GraphiteServer {
+    pub fn create_server(
+        query_handler: GraphiteProtocolHandlerRef,
+        templates: Arc<Templates>,
+        protocol: GraphiteProtocol,
+        io_runtime: Arc<Runtime>,
+    ) -> Box<dyn Server> {
+        let (notify_shutdown, _) = broadcast::channel(1);
+        let name = match protocol {
+            GraphiteProtocol::Plaintext => "Graphite",
+            GraphiteProtocol::Pickle => "Graphite pickle",
+        };
+
+        Box::new(GraphiteServer {
+            base_server: BaseTcpServer::create_server(name, io_runtime),
+            query_handler,
+            templates,
+            protocol,
+            notify_shutdown: Some(notify_shutdown),
+        })
+    }
+
+    fn accept(
+        &self,
+        io_runtime: Arc<Runtime>,
+        stream: AbortableStream,
+    ) -> impl Future<Output = ()> {
+        let query_handler = self.query_handler.clone();
+        let templates = self.templates.clone();
+        let protocol = self.protocol;
+        let notify_shutdown = self
+            .notify_shutdown
+            .clone()
+            .expect("`notify_shutdown` must be present when accepting connection!");
+        stream.for_each(move |stream| {
+            let io_runtime = io_runtime.clone();
+            let query_handler = query_handler.clone();
+            let templates = templates.clone();
+            let shutdown = Shutdown::new(notify_shutdown.subscribe());
+            async move {
+                match stream {
+                    Ok(stream) => {
+                        let mut handler =
+                            Handler::new(query_handler, templates, protocol, stream, shutdown);
+
+                        io_runtime.spawn(async move {
+                            if let Err(e) = handler.run().await {
+                                error!(e; "Unexpected error when handling Graphite connection");
+                            }
+                        });
+                    }
+                    Err(error) => error!("Broken pipe: {}", error), // IoError doesn't impl ErrorExt.
+                };
+            }
+        })
+    }
+}
+
+pub const GRAPHITE_SERVER: &str = "GRAPHITE_SERVER";
+pub const GRAPHITE_PICKLE_SERVER: &str = "GRAPHITE_PICKLE_SERVER";
+
+#[async_trait]
+impl Server for GraphiteServer {
+    async fn shutdown(&self) -> Result<()> {
+        if let Some(tx) = &self.notify_shutdown {
+            // Err of broadcast sender does not mean that future calls to send will fail, so
+            // its return value is ignored here.
+            let _ = tx.send(());
+        }
+        self.base_server.shutdown().await?;
+        Ok(())
+    }
+
+    async fn start(&self, listening: SocketAddr) -> Result<SocketAddr> {
+        let (stream, addr) = self.base_server.bind(listening).await?;
+
+        let io_runtime = self.base_server.io_runtime();
+        let join_handle = tokio::spawn(self.accept(io_runtime, stream));
+        self.base_server.start_with(join_handle).await?;
+        Ok(addr)
+    }
+
+    fn name(&self) -> &str {
+        match self.protocol {
+            GraphiteProtocol::Plaintext => GRAPHITE_SERVER,
+            GraphiteProtocol::Pickle => GRAPHITE_PICKLE_SERVER,
+        }
+    }
+}
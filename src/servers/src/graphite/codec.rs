@@ -0,0 +1,355 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use api::v1::InsertRequest as GrpcInsertRequest;
+use common_grpc::writer::{LinesWriter, Precision};
+use snafu::{ensure, ResultExt};
+
+use crate::error::{Error, GraphiteLinesWriteSnafu, InvalidGraphiteLineSnafu, Result};
+use crate::graphite::template::Templates;
+
+pub const GRAPHITE_TIMESTAMP_COLUMN_NAME: &str = "greptime_timestamp";
+pub const GRAPHITE_FIELD_COLUMN_NAME: &str = "greptime_value";
+
+/// A Graphite data point mapped by the templates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphitePoint {
+    pub table: String,
+    pub tags: BTreeMap<String, String>,
+    pub field: String,
+    pub value: f64,
+    pub ts_millis: i64,
+}
+
+impl GraphitePoint {
+    /// Creates the point of a metric path, whose tags in the Graphite tagged syntax like
+    /// `cpu.load;host=web01` override the ones mapped by the templates.
+    pub fn try_create(
+        path: &str,
+        value: f64,
+        ts_millis: i64,
+        templates: &Templates,
+    ) -> Result<Self> {
+        let mut parts = path.split(';');
+        let name = parts.next().unwrap_or_default();
+        ensure!(
+            !name.is_empty(),
+            InvalidGraphiteLineSnafu {
+                reason: format!("empty metric path: {path}"),
+            }
+        );
+        let mapping = templates.apply(name);
+
+        let mut tags = mapping.tags;
+        for tag in parts {
+            match tag.split_once('=') {
+                Some((k, v)) if !k.is_empty() && !v.is_empty() => {
+                    let _ = tags.insert(k.to_string(), v.to_string());
+                }
+                _ => {
+                    return InvalidGraphiteLineSnafu {
+                        reason: format!("invalid tag '{tag}' of metric path: {path}"),
+                    }
+                    .fail()
+                }
+            }
+        }
+
+        Ok(Self {
+            table: mapping.table,
+            tags,
+            field: mapping
+                .field
+                .unwrap_or_else(|| GRAPHITE_FIELD_COLUMN_NAME.to_string()),
+            value,
+            ts_millis,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct GraphiteRequest {
+    pub points: Vec<GraphitePoint>,
+}
+
+type TableName = String;
+type SeriesKey<'a> = (&'a BTreeMap<String, String>, i64);
+
+impl TryFrom<&GraphiteRequest> for Vec<GrpcInsertRequest> {
+    type Error = Error;
+
+    /// The points of the same series and timestamp are merged into a row, whose fields are the
+    /// ones of the points, like InfluxDB does.
+    fn try_from(value: &GraphiteRequest) -> Result<Self> {
+        let mut tables: HashMap<&str, Vec<(SeriesKey, BTreeMap<&str, f64>)>> = HashMap::new();
+        let mut row_indexes: HashMap<(&str, SeriesKey), usize> = HashMap::new();
+        for point in &value.points {
+            let key = (&point.tags, point.ts_millis);
+            let rows = tables.entry(point.table.as_str()).or_default();
+            let index = *row_indexes
+                .entry((point.table.as_str(), key))
+                .or_insert_with(|| {
+                    rows.push((key, BTreeMap::new()));
+                    rows.len() - 1
+                });
+            let _ = rows[index].1.insert(&point.field, point.value);
+        }
+
+        tables
+            .into_iter()
+            .map(|(table_name, rows)| {
+                let mut writer = LinesWriter::with_lines(rows.len());
+                for ((tags, ts_millis), fields) in rows {
+                    for (k, v) in tags {
+                        writer.write_tag(k, v).context(GraphiteLinesWriteSnafu)?;
+                    }
+                    writer
+                        .write_ts(
+                            GRAPHITE_TIMESTAMP_COLUMN_NAME,
+                            (ts_millis, Precision::Millisecond),
+                        )
+                        .context(GraphiteLinesWriteSnafu)?;
+                    for (field, value) in fields {
+                        writer
+                            .write_f64(field, value)
+                            .context(GraphiteLinesWriteSnafu)?;
+                    }
+                    writer.commit();
+                }
+
+                let (columns, row_count) = writer.finish();
+                Ok(GrpcInsertRequest {
+                    table_name: table_name.to_string(),
+                    region_number: 0,
+                    columns,
+                    row_count,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Parses a line of the plaintext protocol, `<metric path> <value> [timestamp]`, where the
+/// timestamp is in seconds and a missing or negative one means `now_millis`.
+///
+/// Returns `None` for the empty lines and the NaN values, which carbon drops too.
+pub fn parse_line(
+    line: &str,
+    templates: &Templates,
+    now_millis: i64,
+) -> Result<Option<GraphitePoint>> {
+    let tokens = line.split_whitespace().collect::<Vec<_>>();
+    let (path, value, timestamp) = match tokens[..] {
+        [] => return Ok(None),
+        [path, value] => (path, value, None),
+        [path, value, timestamp] => (path, value, Some(timestamp)),
+        _ => {
+            return InvalidGraphiteLineSnafu {
+                reason: format!("expect '<metric path> <value> [timestamp]', got: {line}"),
+            }
+            .fail()
+        }
+    };
+
+    let value = parse_f64(value, line)?;
+    if value.is_nan() {
+        return Ok(None);
+    }
+    let timestamp = match timestamp {
+        Some(ts) if !ts.eq_ignore_ascii_case("n") => parse_f64(ts, line)?,
+        _ => -1.0,
+    };
+    GraphitePoint::try_create(path, value, to_millis(timestamp, now_millis), templates).map(Some)
+}
+
+/// Converts the timestamp in seconds into milliseconds, where a negative one means now.
+pub(crate) fn to_millis(timestamp: f64, now_millis: i64) -> i64 {
+    if timestamp < 0.0 {
+        now_millis
+    } else {
+        (timestamp * 1000.0) as i64
+    }
+}
+
+fn parse_f64(s: &str, line: &str) -> Result<f64> {
+    s.parse().map_err(|_| {
+        InvalidGraphiteLineSnafu {
+            reason: format!("invalid number '{s}' in line: {line}"),
+        }
+        .build()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use api::v1::column::{SemanticType, Values};
+    use api::v1::{Column, ColumnDataType};
+    use common_base::BitVec;
+
+    use super::*;
+
+    fn point(
+        table: &str,
+        tags: &[(&str, &str)],
+        field: &str,
+        value: f64,
+        ts: i64,
+    ) -> GraphitePoint {
+        GraphitePoint {
+            table: table.to_string(),
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            field: field.to_string(),
+            value,
+            ts_millis: ts,
+        }
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let templates =
+            Templates::try_new(&["servers.* .host.measurement.field*".to_string()], ".").unwrap();
+
+        assert_eq!(
+            Some(point(
+                "cpu",
+                &[("host", "web01")],
+                "load.1m",
+                0.5,
+                1663840496000
+            )),
+            parse_line("servers.web01.cpu.load.1m 0.5 1663840496", &templates, 0).unwrap()
+        );
+        assert_eq!(
+            Some(point(
+                "app.requests",
+                &[("dc", "us"), ("host", "web01")],
+                GRAPHITE_FIELD_COLUMN_NAME,
+                10.0,
+                1663840496500
+            )),
+            parse_line(
+                "app.requests;host=web01;dc=us 10 1663840496.5",
+                &templates,
+                0
+            )
+            .unwrap()
+        );
+        // The tags in the path override the ones of the templates.
+        assert_eq!(
+            Some(point("cpu", &[("host", "web02")], "idle", 1.0, 42)),
+            parse_line("servers.web01.cpu.idle;host=web02 1 -1", &templates, 42).unwrap()
+        );
+        assert_eq!(
+            Some(point("a", &[], GRAPHITE_FIELD_COLUMN_NAME, 1.0, 42)),
+            parse_line("a 1", &templates, 42).unwrap()
+        );
+        assert_eq!(None, parse_line("a nan 1", &templates, 42).unwrap());
+        assert_eq!(None, parse_line("  ", &templates, 42).unwrap());
+
+        for line in [
+            "a",
+            "a 1 2 3",
+            "a x 1",
+            "a 1 x",
+            ";host=web01 1 1",
+            "a;host 1 1",
+        ] {
+            assert!(parse_line(line, &templates, 0).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_to_insert_requests() {
+        let request = GraphiteRequest {
+            points: vec![
+                point("cpu", &[("host", "web01")], "idle", 0.5, 1000),
+                point("cpu", &[("host", "web02")], "user", 1.5, 2000),
+                point("cpu", &[("host", "web01")], "user", 2.5, 1000),
+            ],
+        };
+        let requests: Vec<GrpcInsertRequest> = (&request).try_into().unwrap();
+        assert_eq!(1, requests.len());
+        let request = &requests[0];
+        assert_eq!("cpu", request.table_name);
+        assert_eq!(2, request.row_count);
+
+        let columns = request
+            .columns
+            .iter()
+            .map(|c| (c.column_name.as_str(), c))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(4, columns.len());
+        assert_column(
+            columns["host"],
+            SemanticType::Tag,
+            ColumnDataType::String,
+            Values {
+                string_values: vec!["web01".to_string(), "web02".to_string()],
+                ..Default::default()
+            },
+            vec![false, false],
+        );
+        assert_column(
+            columns[GRAPHITE_TIMESTAMP_COLUMN_NAME],
+            SemanticType::Timestamp,
+            ColumnDataType::TimestampMillisecond,
+            Values {
+                ts_millisecond_values: vec![1000, 2000],
+                ..Default::default()
+            },
+            vec![false, false],
+        );
+        assert_column(
+            columns["idle"],
+            SemanticType::Field,
+            ColumnDataType::Float64,
+            Values {
+                f64_values: vec![0.5],
+                ..Default::default()
+            },
+            vec![false, true],
+        );
+        assert_column(
+            columns["user"],
+            SemanticType::Field,
+            ColumnDataType::Float64,
+            Values {
+                f64_values: vec![2.5, 1.5],
+                ..Default::default()
+            },
+            vec![false, false],
+        );
+    }
+
+    fn assert_column(
+        column: &Column,
+        semantic_type: SemanticType,
+        datatype: ColumnDataType,
+        values: Values,
+        null_mask: Vec<bool>,
+    ) {
+        assert_eq!(semantic_type as i32, column.semantic_type);
+        assert_eq!(datatype as i32, column.datatype);
+        assert_eq!(Some(values), column.values);
+        let bitvec = BitVec::from_slice(&column.null_mask);
+        for (idx, b) in null_mask.iter().enumerate() {
+            assert_eq!(b, bitvec.get(idx).unwrap());
+        }
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use bytes::{Buf, BytesMut};
+use common_telemetry::logging::{error, warn};
+use common_time::util::current_time_millis;
+use session::context::QueryContext;
+use snafu::ensure;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error::{
+    ConnResetByPeerSnafu, InvalidGraphiteLineSnafu, InvalidGraphitePickleSnafu, Result,
+};
+use crate::graphite::codec::{self, GraphitePoint, GraphiteRequest};
+use crate::graphite::template::Templates;
+use crate::graphite::{pickle, GraphiteProtocol};
+use crate::query_handler::GraphiteProtocolHandlerRef;
+use crate::shutdown::Shutdown;
+
+/// The max length of a pickle payload, the same as carbon's.
+const MAX_PICKLE_LENGTH: usize = 1024 * 1024;
+/// The max length of a plaintext line.
+const MAX_LINE_LENGTH: usize = 64 * 1024;
+const PICKLE_LENGTH_PREFIX: usize = 4;
+
+/// Per-connection handler. Reads the metrics from `stream` and writes them by the
+/// [GraphiteProtocolHandler](crate::query_handler::GraphiteProtocolHandler).
+///
+/// Graphite clients never read from the connections, so the invalid metrics are logged and
+/// skipped instead of being replied.
+pub(crate) struct Handler<S: AsyncRead + Unpin> {
+    query_handler: GraphiteProtocolHandlerRef,
+    templates: Arc<Templates>,
+    protocol: GraphiteProtocol,
+    stream: S,
+    buffer: BytesMut,
+    /// The length of the buffer prefix known to contain no newline.
+    scanned: usize,
+    /// Listen for shutdown notifications.
+    shutdown: Shutdown,
+}
+
+impl<S: AsyncRead + Unpin> Handler<S> {
+    pub(crate) fn new(
+        query_handler: GraphiteProtocolHandlerRef,
+        templates: Arc<Templates>,
+        protocol: GraphiteProtocol,
+        stream: S,
+        shutdown: Shutdown,
+    ) -> Self {
+        Self {
+            query_handler,
+            templates,
+            protocol,
+            stream,
+            buffer: BytesMut::with_capacity(4 * 1024),
+            scanned: 0,
+            shutdown,
+        }
+    }
+
+    pub(crate) async fn run(&mut self) -> Result<()> {
+        let ctx = QueryContext::arc();
+        while !self.shutdown.is_shutdown() {
+            let n = tokio::select! {
+                n = self.stream.read_buf(&mut self.buffer) => n?,
+                _ = self.shutdown.recv() => return Ok(()),
+            };
+
+            // Writes all the complete lines or payloads received in a request.
+            let points = match self.protocol {
+                GraphiteProtocol::Plaintext => self.parse_lines(n == 0)?,
+                GraphiteProtocol::Pickle => self.parse_pickles()?,
+            };
+            if !points.is_empty() {
+                let request = GraphiteRequest { points };
+                if let Err(e) = self.query_handler.exec(&request, ctx.clone()).await {
+                    error!(e; "Failed to write {} Graphite metrics", request.points.len());
+                }
+            }
+
+            if n == 0 {
+                // The remote closed the connection, which should not break a payload in half.
+                return if self.buffer.is_empty() {
+                    Ok(())
+                } else {
+                    ConnResetByPeerSnafu {}.fail()
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the complete lines in the buffer, or all the remaining data if the stream ends,
+    /// which may be not terminated by a newline.
+    fn parse_lines(&mut self, eof: bool) -> Result<Vec<GraphitePoint>> {
+        // Only the data received since the last call is scanned for newlines.
+        let newline = self.buffer[self.scanned..]
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map(|i| self.scanned + i);
+        let len = match newline {
+            Some(i) => i + 1,
+            None if eof => self.buffer.len(),
+            None => {
+                // A line too long can't be skipped safely, since its end may never arrive.
+                ensure!(
+                    self.buffer.len() <= MAX_LINE_LENGTH,
+                    InvalidGraphiteLineSnafu {
+                        reason: format!("line exceeds the length limit {MAX_LINE_LENGTH}"),
+                    }
+                );
+                self.scanned = self.buffer.len();
+                return Ok(vec![]);
+            }
+        };
+        let data = self.buffer.split_to(len);
+        // The rest follows the last newline.
+        self.scanned = self.buffer.len();
+        let now = current_time_millis();
+
+        let points = String::from_utf8_lossy(&data)
+            .lines()
+            .filter_map(|line| match codec::parse_line(line, &self.templates, now) {
+                Ok(point) => point,
+                Err(e) => {
+                    warn!("Skipped invalid Graphite line: {}", e);
+                    None
+                }
+            })
+            .collect();
+        Ok(points)
+    }
+
+    /// Parses the complete payloads in the buffer, each of which is prefixed by its length in
+    /// 4 bytes big endian.
+    fn parse_pickles(&mut self) -> Result<Vec<GraphitePoint>> {
+        let mut points = vec![];
+        let now = current_time_millis();
+        while self.buffer.len() >= PICKLE_LENGTH_PREFIX {
+            let len = u32::from_be_bytes([
+                self.buffer[0],
+                self.buffer[1],
+                self.buffer[2],
+                self.buffer[3],
+            ]) as usize;
+            // A payload too large can't be skipped safely, since the length may be corrupted.
+            ensure!(
+                len <= MAX_PICKLE_LENGTH,
+                InvalidGraphitePickleSnafu {
+                    reason: format!("payload of {len} bytes exceeds the limit {MAX_PICKLE_LENGTH}"),
+                }
+            );
+            if self.buffer.len() < PICKLE_LENGTH_PREFIX + len {
+                self.buffer
+                    .reserve(PICKLE_LENGTH_PREFIX + len - self.buffer.len());
+                break;
+            }
+
+            self.buffer.advance(PICKLE_LENGTH_PREFIX);
+            let payload = self.buffer.split_to(len);
+            let metrics = match pickle::decode_metrics(&payload) {
+                Ok(metrics) => metrics,
+                Err(e) => {
+                    warn!("Skipped invalid Graphite pickle payload: {}", e);
+                    continue;
+                }
+            };
+            for (path, timestamp, value) in metrics {
+                if value.is_nan() {
+                    continue;
+                }
+                let ts_millis = codec::to_millis(timestamp, now);
+                match GraphitePoint::try_create(&path, value, ts_millis, &self.templates) {
+                    Ok(point) => points.push(point),
+                    Err(e) => warn!("Skipped invalid Graphite metric: {}", e),
+                }
+            }
+        }
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use session::context::QueryContextRef;
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::{broadcast, mpsc};
+
+    use super::*;
+    use crate::query_handler::GraphiteProtocolHandler;
+
+    struct DummyQueryHandler {
+        tx: mpsc::Sender<Vec<GraphitePoint>>,
+    }
+
+    #[async_trait]
+    impl GraphiteProtocolHandler for DummyQueryHandler {
+        async fn exec(&self, request: &GraphiteRequest, _ctx: QueryContextRef) -> Result<()> {
+            self.tx.send(request.points.clone()).await.unwrap();
+            Ok(())
+        }
+    }
+
+    async fn run_handler(protocol: GraphiteProtocol, chunks: Vec<Vec<u8>>) -> Vec<GraphitePoint> {
+        let (tx, mut rx) = mpsc::channel(100);
+        let query_handler = Arc::new(DummyQueryHandler { tx });
+        let templates = Templates::try_new(&["host.measurement.field".to_string()], ".").unwrap();
+        let (client, server) = tokio::io::duplex(64);
+        let (notify_shutdown, _) = broadcast::channel(1);
+        let mut handler = Handler::new(
+            query_handler,
+            Arc::new(templates),
+            protocol,
+            server,
+            Shutdown::new(notify_shutdown.subscribe()),
+        );
+
+        let writer = tokio::spawn(async move {
+            let mut client = client;
+            for chunk in chunks {
+                client.write_all(&chunk).await.unwrap();
+                client.flush().await.unwrap();
+            }
+        });
+        handler.run().await.unwrap();
+        writer.await.unwrap();
+        drop(handler);
+
+        let mut points = vec![];
+        while let Some(batch) = rx.recv().await {
+            points.extend(batch);
+        }
+        points
+    }
+
+    fn point(host: &str, field: &str, value: f64, ts_millis: i64) -> GraphitePoint {
+        GraphitePoint {
+            table: "cpu".to_string(),
+            tags: [("host".to_string(), host.to_string())].into(),
+            field: field.to_string(),
+            value,
+            ts_millis,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plaintext() {
+        let chunks = vec![
+            b"web01.cpu.idle 0.5 1663840496\nweb01.cpu.us".to_vec(),
+            b"er 1.5 1663840496\ninvalid\n".to_vec(),
+            b"web02.cpu.idle 2 1663840497".to_vec(),
+        ];
+        assert_eq!(
+            vec![
+                point("web01", "idle", 0.5, 1663840496000),
+                point("web01", "user", 1.5, 1663840496000),
+                point("web02", "idle", 2.0, 1663840497000),
+            ],
+            run_handler(GraphiteProtocol::Plaintext, chunks).await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plaintext_line_too_long() {
+        let (tx, _rx) = mpsc::channel(100);
+        let query_handler = Arc::new(DummyQueryHandler { tx });
+        let templates = Templates::try_new(&["host.measurement.field".to_string()], ".").unwrap();
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let (notify_shutdown, _) = broadcast::channel(1);
+        let mut handler = Handler::new(
+            query_handler,
+            Arc::new(templates),
+            GraphiteProtocol::Plaintext,
+            server,
+            Shutdown::new(notify_shutdown.subscribe()),
+        );
+
+        let writer = tokio::spawn(async move {
+            let line = vec![b'a'; 1024];
+            // Stops writing once the handler closes the connection.
+            while client.write_all(&line).await.is_ok() {}
+        });
+        assert!(handler.run().await.is_err());
+        drop(handler);
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pickle() {
+        // pickle.dumps([("web01.cpu.idle", (1663840496, 0.5))], protocol=2)
+        let payload = b"\x80\x02]q\x00X\x0e\x00\x00\x00web01.cpu.idleq\x01J\xf00,cG?\xe0\x00\x00\x00\x00\x00\x00\x86q\x02\x86q\x03a.";
+        let mut data = (payload.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(payload);
+        let invalid = b"\x00\x00\x00\x02K.";
+        data.extend_from_slice(invalid);
+        data.extend_from_slice(&data.clone()[..PICKLE_LENGTH_PREFIX + payload.len()]);
+
+        // Splits the payloads in the middle.
+        let chunks = vec![data[..10].to_vec(), data[10..].to_vec()];
+        assert_eq!(
+            vec![
+                point("web01", "idle", 0.5, 1663840496000),
+                point("web01", "idle", 0.5, 1663840496000),
+            ],
+            run_handler(GraphiteProtocol::Pickle, chunks).await
+        );
+    }
+}
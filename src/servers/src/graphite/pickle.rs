@@ -0,0 +1,559 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A restricted unpickler of the payloads sent to the carbon pickle receiver, which are lists of
+//! `(path, (timestamp, value))` tuples.
+//!
+//! Only the opcodes that build the primitive values, tuples and lists are supported, so the
+//! payloads can't construct any objects. The size and the nesting depth of the values are
+//! limited, since DUP and GET copy the values, which may double them on every opcode.
+
+use std::collections::HashMap;
+
+use snafu::{ensure, OptionExt};
+
+use crate::error::{InvalidGraphitePickleSnafu, Result};
+
+/// The max total size of the values built by a payload, counted in values and string bytes.
+const MAX_UNPICKLED_SIZE: usize = 4 * 1024 * 1024;
+/// The max nesting depth of the tuples and lists.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+enum PickleValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Tuple(Vec<PickleValue>),
+    List(Vec<PickleValue>),
+}
+
+impl PickleValue {
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            PickleValue::Int(v) => Some(*v as f64),
+            PickleValue::Float(v) => Some(*v),
+            PickleValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the metrics in the payload into `(path, timestamp, value)`, where the timestamp is in
+/// seconds.
+pub(crate) fn decode_metrics(payload: &[u8]) -> Result<Vec<(String, f64, f64)>> {
+    let PickleValue::List(items) = Unpickler::new(payload).load()? else {
+        return invalid("the metrics must be a list");
+    };
+
+    items
+        .into_iter()
+        .map(|item| {
+            let PickleValue::Tuple(mut item) = item else {
+                return invalid("a metric must be a tuple of (path, (timestamp, value))");
+            };
+            let (Some(PickleValue::Tuple(point)), Some(PickleValue::String(path)), None) =
+                (item.pop(), item.pop(), item.pop())
+            else {
+                return invalid("a metric must be a tuple of (path, (timestamp, value))");
+            };
+            let [timestamp, value] = &point[..] else {
+                return invalid("a data point must be a tuple of (timestamp, value)");
+            };
+            let timestamp = timestamp.as_f64().context(InvalidGraphitePickleSnafu {
+                reason: format!("invalid timestamp: {timestamp:?}"),
+            })?;
+            let value = value.as_f64().context(InvalidGraphitePickleSnafu {
+                reason: format!("invalid value: {value:?}"),
+            })?;
+            Ok((path, timestamp, value))
+        })
+        .collect()
+}
+
+fn invalid<T>(reason: &str) -> Result<T> {
+    InvalidGraphitePickleSnafu { reason }.fail()
+}
+
+/// A value on the stack or in the memo, with its size and nesting depth.
+#[derive(Debug, Clone)]
+struct Item {
+    value: PickleValue,
+    size: usize,
+    depth: usize,
+}
+
+struct Unpickler<'a> {
+    data: &'a [u8],
+    pos: usize,
+    stack: Vec<Item>,
+    /// The positions in the stack of the marks.
+    marks: Vec<usize>,
+    memo: HashMap<u64, Item>,
+    /// The total size of the values built so far, including the copies.
+    size: usize,
+}
+
+impl<'a> Unpickler<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            stack: Vec::new(),
+            marks: Vec::new(),
+            memo: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    fn load(mut self) -> Result<PickleValue> {
+        loop {
+            let opcode = self.read(1)?[0];
+            match opcode {
+                // PROTO
+                0x80 => {
+                    let _ = self.read(1)?;
+                }
+                // FRAME
+                0x95 => {
+                    let _ = self.read(8)?;
+                }
+                // STOP
+                b'.' => return self.pop().map(|item| item.value),
+                // MARK
+                b'(' => self.marks.push(self.stack.len()),
+                // POP
+                b'0' => {
+                    let _ = self.pop()?;
+                }
+                // POP_MARK
+                b'1' => {
+                    let _ = self.pop_mark()?;
+                }
+                // DUP
+                b'2' => {
+                    let top = self.peek()?.clone();
+                    self.push_item(top)?;
+                }
+                b'N' => self.push(PickleValue::None)?,
+                // NEWTRUE and NEWFALSE
+                0x88 => self.push(PickleValue::Bool(true))?,
+                0x89 => self.push(PickleValue::Bool(false))?,
+                // INT, which is also the bool in protocol 0
+                b'I' => {
+                    let line = self.read_line()?;
+                    let value = match line.as_str() {
+                        "00" => PickleValue::Bool(false),
+                        "01" => PickleValue::Bool(true),
+                        line => PickleValue::Int(parse_number(line)?),
+                    };
+                    self.push(value)?;
+                }
+                // LONG
+                b'L' => {
+                    let line = self.read_line()?;
+                    let value = parse_number(line.trim_end_matches('L'))?;
+                    self.push(PickleValue::Int(value))?;
+                }
+                // BININT
+                b'J' => {
+                    let value = i32::from_le_bytes(self.read_array()?);
+                    self.push(PickleValue::Int(value as i64))?;
+                }
+                // BININT1
+                b'K' => {
+                    let value = self.read(1)?[0];
+                    self.push(PickleValue::Int(value as i64))?;
+                }
+                // BININT2
+                b'M' => {
+                    let value = u16::from_le_bytes(self.read_array()?);
+                    self.push(PickleValue::Int(value as i64))?;
+                }
+                // LONG1
+                0x8a => {
+                    let len = self.read(1)?[0] as usize;
+                    ensure!(
+                        len <= 8,
+                        InvalidGraphitePickleSnafu {
+                            reason: "integer out of range",
+                        }
+                    );
+                    let bytes = self.read(len)?;
+                    let mut buf = if bytes.last().map_or(false, |b| b & 0x80 != 0) {
+                        [0xff; 8]
+                    } else {
+                        [0; 8]
+                    };
+                    buf[..len].copy_from_slice(bytes);
+                    self.push(PickleValue::Int(i64::from_le_bytes(buf)))?;
+                }
+                // FLOAT
+                b'F' => {
+                    let line = self.read_line()?;
+                    let value = line.parse().ok().context(InvalidGraphitePickleSnafu {
+                        reason: format!("invalid float: {line}"),
+                    })?;
+                    self.push(PickleValue::Float(value))?;
+                }
+                // BINFLOAT
+                b'G' => {
+                    let value = f64::from_be_bytes(self.read_array()?);
+                    self.push(PickleValue::Float(value))?;
+                }
+                // STRING and UNICODE, whose escapes are not expected in metric paths
+                b'S' | b'V' => {
+                    let line = self.read_line()?;
+                    let value = if opcode == b'S' {
+                        line.trim_matches(|c| c == '\'' || c == '"').to_string()
+                    } else {
+                        line
+                    };
+                    self.push(PickleValue::String(value))?;
+                }
+                // SHORT_BINSTRING, SHORT_BINBYTES and SHORT_BINUNICODE
+                b'U' | b'C' | 0x8c => {
+                    let len = self.read(1)?[0] as usize;
+                    self.push_string(len)?;
+                }
+                // BINSTRING, BINBYTES and BINUNICODE
+                b'T' | b'B' | b'X' => {
+                    let len = u32::from_le_bytes(self.read_array()?) as usize;
+                    self.push_string(len)?;
+                }
+                // BINUNICODE8 and BINBYTES8
+                0x8d | 0x8e => {
+                    let len = u64::from_le_bytes(self.read_array()?) as usize;
+                    self.push_string(len)?;
+                }
+                b']' => self.push(PickleValue::List(vec![]))?,
+                b')' => self.push(PickleValue::Tuple(vec![]))?,
+                // LIST
+                b'l' => {
+                    let items = self.pop_mark()?;
+                    self.push_container(PickleValue::List, items)?;
+                }
+                // TUPLE
+                b't' => {
+                    let items = self.pop_mark()?;
+                    self.push_container(PickleValue::Tuple, items)?;
+                }
+                // TUPLE1, TUPLE2 and TUPLE3
+                0x85..=0x87 => {
+                    let len = (opcode - 0x84) as usize;
+                    ensure!(
+                        self.stack.len() >= len,
+                        InvalidGraphitePickleSnafu {
+                            reason: "stack underflow",
+                        }
+                    );
+                    let items = self.stack.split_off(self.stack.len() - len);
+                    self.push_container(PickleValue::Tuple, items)?;
+                }
+                // APPEND
+                b'a' => {
+                    let item = self.pop()?;
+                    self.append(vec![item])?;
+                }
+                // APPENDS
+                b'e' => {
+                    let items = self.pop_mark()?;
+                    self.append(items)?;
+                }
+                // PUT
+                b'p' => {
+                    let index = parse_number(&self.read_line()?)? as u64;
+                    self.put(index)?;
+                }
+                // BINPUT
+                b'q' => {
+                    let index = self.read(1)?[0] as u64;
+                    self.put(index)?;
+                }
+                // LONG_BINPUT
+                b'r' => {
+                    let index = u32::from_le_bytes(self.read_array()?) as u64;
+                    self.put(index)?;
+                }
+                // MEMOIZE
+                0x94 => {
+                    let index = self.memo.len() as u64;
+                    self.put(index)?;
+                }
+                // GET
+                b'g' => {
+                    let index = parse_number(&self.read_line()?)? as u64;
+                    self.get(index)?;
+                }
+                // BINGET
+                b'h' => {
+                    let index = self.read(1)?[0] as u64;
+                    self.get(index)?;
+                }
+                // LONG_BINGET
+                b'j' => {
+                    let index = u32::from_le_bytes(self.read_array()?) as u64;
+                    self.get(index)?;
+                }
+                opcode => {
+                    return InvalidGraphitePickleSnafu {
+                        reason: format!("unsupported opcode: {opcode:#04x}"),
+                    }
+                    .fail()
+                }
+            }
+        }
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len());
+        let end = end.context(InvalidGraphitePickleSnafu {
+            reason: "unexpected end of data",
+        })?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0; N];
+        buf.copy_from_slice(self.read(N)?);
+        Ok(buf)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let rest = &self.data[self.pos..];
+        let len = rest
+            .iter()
+            .position(|b| *b == b'\n')
+            .context(InvalidGraphitePickleSnafu {
+                reason: "unexpected end of data",
+            })?;
+        let line = self.read(len + 1)?;
+        Ok(String::from_utf8_lossy(&line[..len]).into_owned())
+    }
+
+    fn push_string(&mut self, len: usize) -> Result<()> {
+        let bytes = self.read(len)?;
+        let value = String::from_utf8_lossy(bytes).into_owned();
+        self.push(PickleValue::String(value))
+    }
+
+    /// Pushes a primitive value, or an empty tuple or list.
+    fn push(&mut self, value: PickleValue) -> Result<()> {
+        let size = match &value {
+            PickleValue::String(s) => 1 + s.len(),
+            _ => 1,
+        };
+        self.push_item(Item {
+            value,
+            size,
+            depth: 0,
+        })
+    }
+
+    /// Pushes a new or copied item, whose size is added to the total size.
+    fn push_item(&mut self, item: Item) -> Result<()> {
+        self.grow(item.size)?;
+        self.stack.push(item);
+        Ok(())
+    }
+
+    fn push_container(
+        &mut self,
+        container: fn(Vec<PickleValue>) -> PickleValue,
+        items: Vec<Item>,
+    ) -> Result<()> {
+        // The items are moved into the container, so only the container itself is new.
+        self.grow(1)?;
+        let size = 1 + items.iter().map(|item| item.size).sum::<usize>();
+        let depth = 1 + items.iter().map(|item| item.depth).max().unwrap_or(0);
+        ensure_depth(depth)?;
+        let values = items.into_iter().map(|item| item.value).collect();
+        self.stack.push(Item {
+            value: container(values),
+            size,
+            depth,
+        });
+        Ok(())
+    }
+
+    fn grow(&mut self, size: usize) -> Result<()> {
+        self.size = self.size.saturating_add(size);
+        ensure!(
+            self.size <= MAX_UNPICKLED_SIZE,
+            InvalidGraphitePickleSnafu {
+                reason: format!("unpickled values exceed the size limit {MAX_UNPICKLED_SIZE}"),
+            }
+        );
+        Ok(())
+    }
+
+    fn peek(&self) -> Result<&Item> {
+        self.stack.last().context(InvalidGraphitePickleSnafu {
+            reason: "stack underflow",
+        })
+    }
+
+    fn pop(&mut self) -> Result<Item> {
+        self.stack.pop().context(InvalidGraphitePickleSnafu {
+            reason: "stack underflow",
+        })
+    }
+
+    fn pop_mark(&mut self) -> Result<Vec<Item>> {
+        let mark = self.marks.pop().context(InvalidGraphitePickleSnafu {
+            reason: "mark not found",
+        })?;
+        ensure!(
+            mark <= self.stack.len(),
+            InvalidGraphitePickleSnafu {
+                reason: "stack underflow",
+            }
+        );
+        Ok(self.stack.split_off(mark))
+    }
+
+    fn append(&mut self, items: Vec<Item>) -> Result<()> {
+        let Some(Item {
+            value: PickleValue::List(list),
+            size,
+            depth,
+        }) = self.stack.last_mut()
+        else {
+            return invalid("can only append to a list");
+        };
+        for item in items {
+            *size += item.size;
+            *depth = (*depth).max(1 + item.depth);
+            list.push(item.value);
+        }
+        ensure_depth(*depth)
+    }
+
+    fn put(&mut self, index: u64) -> Result<()> {
+        let top = self.peek()?.clone();
+        self.grow(top.size)?;
+        let _ = self.memo.insert(index, top);
+        Ok(())
+    }
+
+    fn get(&mut self, index: u64) -> Result<()> {
+        let item = self
+            .memo
+            .get(&index)
+            .cloned()
+            .context(InvalidGraphitePickleSnafu {
+                reason: format!("memo {index} not found"),
+            })?;
+        self.push_item(item)
+    }
+}
+
+fn ensure_depth(depth: usize) -> Result<()> {
+    ensure!(
+        depth <= MAX_DEPTH,
+        InvalidGraphitePickleSnafu {
+            reason: format!("values nested deeper than {MAX_DEPTH}"),
+        }
+    );
+    Ok(())
+}
+
+fn parse_number(s: &str) -> Result<i64> {
+    s.trim().parse().ok().context(InvalidGraphitePickleSnafu {
+        reason: format!("invalid integer: {s}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_metrics() {
+        // pickle.dumps([("a.b.c", (1663840496, 1.5)), ("a.b.d;host=web01", (1663840496.5, 2))],
+        //     protocol=2)
+        let payload = b"\x80\x02]q\x00(X\x05\x00\x00\x00a.b.cq\x01J\xf00,cG?\xf8\x00\x00\x00\x00\x00\x00\x86q\x02\x86q\x03X\x10\x00\x00\x00a.b.d;host=web01q\x04GA\xd8\xcb\x0c< \x00\x00K\x02\x86q\x05\x86q\x06e.";
+        assert_eq!(
+            vec![
+                ("a.b.c".to_string(), 1663840496.0, 1.5),
+                ("a.b.d;host=web01".to_string(), 1663840496.5, 2.0),
+            ],
+            decode_metrics(payload).unwrap()
+        );
+
+        // pickle.dumps([("a", (1, 2))], protocol=0)
+        let payload = b"(lp0\n(Va\np1\n(I1\nI2\ntp2\ntp3\na.";
+        assert_eq!(
+            vec![("a".to_string(), 1.0, 2.0)],
+            decode_metrics(payload).unwrap()
+        );
+
+        // pickle.dumps([("a", (1, 2))], protocol=4)
+        let payload =
+            b"\x80\x04\x95\x10\x00\x00\x00\x00\x00\x00\x00]\x94\x8c\x01a\x94K\x01K\x02\x86\x94\x86\x94a.";
+        assert_eq!(
+            vec![("a".to_string(), 1.0, 2.0)],
+            decode_metrics(payload).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_metrics() {
+        for payload in [
+            // An object built by os.system.
+            &b"cos\nsystem\n(S'ls'\ntR."[..],
+            // Not a list.
+            &b"K\x01."[..],
+            // Not a tuple of (path, (timestamp, value)).
+            &b"]K\x01a."[..],
+            // Truncated.
+            &b"\x80\x02]q\x00(X\x05\x00\x00"[..],
+        ] {
+            assert!(decode_metrics(payload).is_err(), "{payload:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode_oversized_metrics() {
+        // Doubles the list on every DUP and TUPLE2.
+        let mut payload = b"]".to_vec();
+        for _ in 0..40 {
+            payload.extend_from_slice(b"2\x86");
+        }
+        payload.push(b'.');
+        assert!(decode_metrics(&payload).is_err());
+
+        // Doubles a string of 1KB on every PUT, GET and TUPLE2.
+        let mut payload = b"X\x00\x04\x00\x00".to_vec();
+        payload.extend_from_slice(&[b'a'; 1024]);
+        for _ in 0..20 {
+            payload.extend_from_slice(b"q\x00h\x00\x86");
+        }
+        payload.push(b'.');
+        assert!(decode_metrics(&payload).is_err());
+
+        // Nests the tuples deeply.
+        let mut payload = b")".to_vec();
+        payload.extend_from_slice(&[0x85; 10000]);
+        payload.push(b'.');
+        assert!(decode_metrics(&payload).is_err());
+    }
+}
@@ -0,0 +1,367 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Templates that map the dotted paths of Graphite metrics into tables, tags and fields, which
+//! work like the ones of InfluxDB's Graphite input.
+//!
+//! A template is `[filter] template [default_tags]`, for example
+//! `servers.* .host.measurement.field* region=us-west`:
+//! - The filter is matched against the leading parts of the path, and `*` matches any part.
+//!   The most specific filter is used when several match, and a template without filter is the
+//!   default one.
+//! - Every part of the template names the same part of the path: `measurement` and `field`
+//!   are parts of the table and the field, an empty part is skipped, and the others are tags.
+//!   `measurement*` and `field*` take all the remaining parts.
+//! - The parts of the same name are joined by the separator, `.` by default.
+//!
+//! Without a matched template, the whole path is the table.
+
+use std::collections::BTreeMap;
+
+use snafu::ensure;
+
+use crate::error::{InvalidGraphiteTemplateSnafu, Result};
+
+const MEASUREMENT: &str = "measurement";
+const MEASUREMENT_GREEDY: &str = "measurement*";
+const FIELD: &str = "field";
+const FIELD_GREEDY: &str = "field*";
+const WILDCARD: &str = "*";
+
+pub const DEFAULT_SEPARATOR: &str = ".";
+
+/// The table, tags and field of a path, where the field is `None` if the template doesn't
+/// name it.
+#[derive(Debug, PartialEq)]
+pub struct Mapping {
+    pub table: String,
+    pub tags: BTreeMap<String, String>,
+    pub field: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Template {
+    parts: Vec<String>,
+    default_tags: Vec<(String, String)>,
+}
+
+impl Template {
+    fn parse(template: &str, default_tags: Option<&str>) -> Result<Self> {
+        let parts = template
+            .split('.')
+            .map(|part| part.to_string())
+            .collect::<Vec<_>>();
+        ensure!(
+            !(parts.iter().any(|p| p == MEASUREMENT_GREEDY)
+                && parts.iter().any(|p| p == FIELD_GREEDY)),
+            InvalidGraphiteTemplateSnafu {
+                reason: format!(
+                    "either 'field*' or 'measurement*' can be used in a template, but not both: {template}"
+                ),
+            }
+        );
+
+        let default_tags = match default_tags {
+            Some(tags) => tags
+                .split(',')
+                .map(|tag| match tag.split_once('=') {
+                    Some((k, v)) if !k.is_empty() && !v.is_empty() => {
+                        Ok((k.to_string(), v.to_string()))
+                    }
+                    _ => InvalidGraphiteTemplateSnafu {
+                        reason: format!("invalid default tag: {tag}"),
+                    }
+                    .fail(),
+                })
+                .collect::<Result<_>>()?,
+            None => vec![],
+        };
+        Ok(Self {
+            parts,
+            default_tags,
+        })
+    }
+
+    fn apply(&self, path: &str, separator: &str) -> Mapping {
+        let path_parts = path.split('.').collect::<Vec<_>>();
+        let mut measurement = Vec::new();
+        let mut field = Vec::new();
+        let mut tags: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+        for (i, part) in self.parts.iter().enumerate() {
+            let Some(value) = path_parts.get(i) else {
+                break;
+            };
+            match part.as_str() {
+                "" => {}
+                MEASUREMENT => measurement.push(*value),
+                FIELD => field.push(*value),
+                // The greedy parts take all the remaining parts of the path.
+                MEASUREMENT_GREEDY => {
+                    measurement.extend(&path_parts[i..]);
+                    break;
+                }
+                FIELD_GREEDY => {
+                    field.extend(&path_parts[i..]);
+                    break;
+                }
+                tag => tags.entry(tag).or_default().push(*value),
+            }
+        }
+
+        let mut mapped_tags = self
+            .default_tags
+            .iter()
+            .cloned()
+            .collect::<BTreeMap<_, _>>();
+        mapped_tags.extend(
+            tags.into_iter()
+                .map(|(tag, values)| (tag.to_string(), values.join(separator))),
+        );
+        Mapping {
+            table: if measurement.is_empty() {
+                path.to_string()
+            } else {
+                measurement.join(separator)
+            },
+            tags: mapped_tags,
+            field: (!field.is_empty()).then(|| field.join(separator)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Templates {
+    separator: String,
+    default: Option<Template>,
+    filtered: Vec<(Vec<String>, Template)>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            separator: DEFAULT_SEPARATOR.to_string(),
+            default: None,
+            filtered: vec![],
+        }
+    }
+}
+
+impl Templates {
+    pub fn try_new(templates: &[String], separator: &str) -> Result<Self> {
+        let mut default = None;
+        let mut filtered = Vec::with_capacity(templates.len());
+        for template in templates {
+            let parts = template.split_whitespace().collect::<Vec<_>>();
+            let (filter, template_str, tags) = match parts[..] {
+                [template] => (None, template, None),
+                [template, tags] if tags.contains('=') => (None, template, Some(tags)),
+                [filter, template] => (Some(filter), template, None),
+                [filter, template, tags] => (Some(filter), template, Some(tags)),
+                _ => {
+                    return InvalidGraphiteTemplateSnafu {
+                        reason: format!("invalid template: {template}"),
+                    }
+                    .fail()
+                }
+            };
+            let parsed = Template::parse(template_str, tags)?;
+            match filter {
+                Some(filter) => {
+                    let filter = filter.split('.').map(|p| p.to_string()).collect::<Vec<_>>();
+                    ensure!(
+                        !filtered.iter().any(|(f, _)| *f == filter),
+                        InvalidGraphiteTemplateSnafu {
+                            reason: format!("duplicate filter of template: {template}"),
+                        }
+                    );
+                    filtered.push((filter, parsed));
+                }
+                None => {
+                    ensure!(
+                        default.is_none(),
+                        InvalidGraphiteTemplateSnafu {
+                            reason: format!("duplicate default template: {template}"),
+                        }
+                    );
+                    default = Some(parsed);
+                }
+            }
+        }
+        Ok(Self {
+            separator: separator.to_string(),
+            default,
+            filtered,
+        })
+    }
+
+    /// Maps the path by the most specific template that matches it.
+    pub fn apply(&self, path: &str) -> Mapping {
+        let path_parts = path.split('.').collect::<Vec<_>>();
+        // The parts matched exactly are more specific than the ones matched by wildcards, from
+        // the first part, and a longer filter is more specific than its prefixes.
+        let template = self
+            .filtered
+            .iter()
+            .filter_map(|(filter, template)| {
+                if filter.len() > path_parts.len() {
+                    return None;
+                }
+                let mut specificity = Vec::with_capacity(filter.len());
+                for (filter_part, path_part) in filter.iter().zip(&path_parts) {
+                    if filter_part == path_part {
+                        specificity.push(2);
+                    } else if glob_match(filter_part, path_part) {
+                        specificity.push(1);
+                    } else {
+                        return None;
+                    }
+                }
+                Some((specificity, template))
+            })
+            // Keeps the first defined one if they are equally specific.
+            .rev()
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, template)| template)
+            .or(self.default.as_ref());
+
+        match template {
+            Some(template) => template.apply(path, &self.separator),
+            None => Mapping {
+                table: path.to_string(),
+                tags: BTreeMap::new(),
+                field: None,
+            },
+        }
+    }
+}
+
+/// Matches the text with the pattern, where `*` matches any characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    if pattern == WILDCARD {
+        return true;
+    }
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut text) = text.strip_prefix(prefix) else {
+        return false;
+    };
+    let mut segments = rest.split('*').peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_none() {
+            return text.ends_with(segment);
+        }
+        match text.find(segment) {
+            Some(i) => text = &text[i + segment.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates(templates: &[&str]) -> Templates {
+        let templates = templates.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        Templates::try_new(&templates, DEFAULT_SEPARATOR).unwrap()
+    }
+
+    fn mapping(table: &str, tags: &[(&str, &str)], field: Option<&str>) -> Mapping {
+        Mapping {
+            table: table.to_string(),
+            tags: tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            field: field.map(|f| f.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_apply_templates() {
+        let templates = templates(&[
+            "servers.* .host.measurement* region=us-west",
+            "servers.localhost .host.measurement.field*",
+            "stats.*.*.timer .host.host.measurement.field",
+            "measurement.measurement",
+        ]);
+
+        assert_eq!(
+            mapping(
+                "cpu.loadavg.10",
+                &[("host", "web01"), ("region", "us-west")],
+                None
+            ),
+            templates.apply("servers.web01.cpu.loadavg.10")
+        );
+        assert_eq!(
+            mapping("cpu", &[("host", "localhost")], Some("loadavg.10")),
+            templates.apply("servers.localhost.cpu.loadavg.10")
+        );
+        assert_eq!(
+            mapping("timer", &[("host", "web.01")], Some("mean")),
+            templates.apply("stats.web.01.timer.mean")
+        );
+        // The default template ignores the remaining parts.
+        assert_eq!(
+            mapping("app.requests", &[], None),
+            templates.apply("app.requests.count")
+        );
+        assert_eq!(
+            mapping("a.b.c", &[], None),
+            Templates::default().apply("a.b.c")
+        );
+    }
+
+    #[test]
+    fn test_separator() {
+        let templates = Templates::try_new(&["host.host.measurement*".to_string()], "_").unwrap();
+        assert_eq!(
+            mapping("cpu_idle", &[("host", "us_web01")], None),
+            templates.apply("us.web01.cpu.idle")
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        for template in [
+            "measurement*.field*",
+            "a b c d",
+            ".host.measurement region=",
+        ] {
+            assert!(
+                Templates::try_new(&[template.to_string()], DEFAULT_SEPARATOR).is_err(),
+                "{template}"
+            );
+        }
+        assert!(Templates::try_new(
+            &["measurement".to_string(), "host.measurement".to_string()],
+            DEFAULT_SEPARATOR
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "cpu"));
+        assert!(glob_match("cpu*", "cpu0"));
+        assert!(glob_match("*pu", "cpu"));
+        assert!(glob_match("c*u*0", "cpu0"));
+        assert!(!glob_match("cpu*", "mem"));
+        assert!(!glob_match("c*x", "cpu"));
+    }
+}
@@ -41,14 +41,15 @@ use crate::error::{
 use crate::grpc::database::DatabaseService;
 use crate::grpc::flight::FlightHandler;
 use crate::grpc::handler::GreptimeRequestHandler;
-use crate::query_handler::grpc::ServerGrpcQueryHandlerRef;
+use crate::query_handler::grpc::{BulkInsertHandlerRef, ServerGrpcQueryHandlerRef};
 use crate::server::Server;
 
-type TonicResult<T> = std::result::Result<T, Status>;
+pub(crate) type TonicResult<T> = std::result::Result<T, Status>;
 
 pub struct GrpcServer {
     shutdown_tx: Mutex<Option<Sender<()>>>,
     request_handler: Arc<GreptimeRequestHandler>,
+    bulk_insert_handler: Option<BulkInsertHandlerRef>,
 }
 
 impl GrpcServer {
@@ -65,11 +66,22 @@ impl GrpcServer {
         Self {
             shutdown_tx: Mutex::new(None),
             request_handler,
+            bulk_insert_handler: None,
         }
     }
 
+    /// Enables inserting the record batches put by Arrow Flight.
+    pub fn with_bulk_insert_handler(mut self, handler: BulkInsertHandlerRef) -> Self {
+        self.bulk_insert_handler = Some(handler);
+        self
+    }
+
     pub fn create_flight_service(&self) -> FlightServiceServer<impl FlightService> {
-        FlightServiceServer::new(FlightHandler::new(self.request_handler.clone()))
+        let mut flight_handler = FlightHandler::new(self.request_handler.clone());
+        if let Some(handler) = &self.bulk_insert_handler {
+            flight_handler = flight_handler.with_bulk_insert_handler(handler.clone());
+        }
+        FlightServiceServer::new(flight_handler)
     }
 
     pub fn create_database_service(&self) -> GreptimeDatabaseServer<impl GreptimeDatabase> {
@@ -12,39 +12,54 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-mod stream;
+pub(crate) mod stream;
 
 use std::pin::Pin;
 use std::sync::Arc;
 
-use api::v1::GreptimeRequest;
+use api::v1::greptime_request::Request as GreptimeRequestType;
+use api::v1::{AffectedRows, FlightMetadata, GreptimeRequest};
 use arrow_flight::flight_service_server::FlightService;
 use arrow_flight::{
     Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
     HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
 };
 use async_trait::async_trait;
-use common_grpc::flight::{FlightEncoder, FlightMessage};
+use common_grpc::flight::{FlightDecoder, FlightEncoder, FlightMessage};
 use common_query::Output;
-use futures::Stream;
+use common_telemetry::warn;
+use futures::channel::mpsc;
+use futures::{SinkExt, Stream};
 use prost::Message;
-use snafu::ResultExt;
+use session::context::QueryContextRef;
+use snafu::{OptionExt, ResultExt};
 use tonic::{Request, Response, Status, Streaming};
 
 use crate::error;
 use crate::grpc::flight::stream::FlightRecordBatchStream;
 use crate::grpc::handler::GreptimeRequestHandler;
 use crate::grpc::TonicResult;
+use crate::query_handler::grpc::BulkInsertHandlerRef;
 
-type TonicStream<T> = Pin<Box<dyn Stream<Item = TonicResult<T>> + Send + Sync + 'static>>;
+pub(crate) type TonicStream<T> =
+    Pin<Box<dyn Stream<Item = TonicResult<T>> + Send + Sync + 'static>>;
 
 pub struct FlightHandler {
     handler: Arc<GreptimeRequestHandler>,
+    bulk_insert_handler: Option<BulkInsertHandlerRef>,
 }
 
 impl FlightHandler {
     pub fn new(handler: Arc<GreptimeRequestHandler>) -> Self {
-        Self { handler }
+        Self {
+            handler,
+            bulk_insert_handler: None,
+        }
+    }
+
+    pub fn with_bulk_insert_handler(mut self, handler: BulkInsertHandlerRef) -> Self {
+        self.bulk_insert_handler = Some(handler);
+        self
     }
 }
 
@@ -97,11 +112,73 @@ impl FlightService for FlightHandler {
 
     type DoPutStream = TonicStream<PutResult>;
 
+    /// Writes the record batches into the table.
+    ///
+    /// The first message is the schema with a [FlightDescriptor], whose `cmd` is an encoded
+    /// [GreptimeRequest] of an empty `InsertRequest` to name the table, and carries the header.
+    /// Every record batch is inserted into the table as it is, and replied by a [PutResult] of
+    /// the affected rows in [FlightMetadata].
     async fn do_put(
         &self,
-        _: Request<Streaming<FlightData>>,
+        request: Request<Streaming<FlightData>>,
     ) -> TonicResult<Response<Self::DoPutStream>> {
-        Err(Status::unimplemented("Not yet implemented"))
+        let Some(bulk_insert_handler) = self.bulk_insert_handler.clone() else {
+            return Err(Status::unimplemented("Not yet implemented"));
+        };
+
+        let mut stream = request.into_inner();
+        let flight_data = stream
+            .message()
+            .await?
+            .context(error::InvalidFlightPutSnafu {
+                reason: "Expecting the schema to be sent first.",
+            })?;
+        let descriptor =
+            flight_data
+                .flight_descriptor
+                .as_ref()
+                .context(error::InvalidFlightPutSnafu {
+                    reason: "Expecting a FlightDescriptor with the schema.",
+                })?;
+        let request = GreptimeRequest::decode(descriptor.cmd.as_ref())
+            .context(error::InvalidFlightDescriptorSnafu)?;
+        let Some(GreptimeRequestType::Insert(insert)) = request.request else {
+            return Err(error::InvalidFlightPutSnafu {
+                reason: "Expecting an InsertRequest in the FlightDescriptor.",
+            }
+            .build()
+            .into());
+        };
+
+        let mut decoder = FlightDecoder::default();
+        let message = decoder
+            .try_decode(flight_data)
+            .context(error::ConvertFlightMessageSnafu)?;
+        if !matches!(message, FlightMessage::Schema(_)) {
+            return Err(error::InvalidFlightPutSnafu {
+                reason: "Expecting the schema to be sent first.",
+            }
+            .build()
+            .into());
+        }
+
+        let ctx = self.handler.query_context(request.header.as_ref()).await?;
+
+        let (mut tx, rx) = mpsc::channel::<TonicResult<PutResult>>(1);
+        let put = PutRecordBatches {
+            handler: bulk_insert_handler,
+            ctx,
+            table_name: insert.table_name,
+            decoder,
+        };
+        let _handle = common_runtime::spawn_write(async move {
+            if let Err(e) = put.put(stream, &mut tx).await {
+                if let Err(e) = tx.send(Err(e)).await {
+                    warn!("stop sending Flight put result, err: {e}");
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(rx)))
     }
 
     type DoExchangeStream = TonicStream<FlightData>;
@@ -129,6 +206,56 @@ impl FlightService for FlightHandler {
     }
 }
 
+struct PutRecordBatches {
+    handler: BulkInsertHandlerRef,
+    ctx: QueryContextRef,
+    table_name: String,
+    decoder: FlightDecoder,
+}
+
+impl PutRecordBatches {
+    async fn put(
+        mut self,
+        mut stream: Streaming<FlightData>,
+        tx: &mut mpsc::Sender<TonicResult<PutResult>>,
+    ) -> TonicResult<()> {
+        while let Some(flight_data) = stream.message().await? {
+            let message = self
+                .decoder
+                .try_decode(flight_data)
+                .context(error::ConvertFlightMessageSnafu)?;
+            let FlightMessage::Recordbatch(recordbatch) = message else {
+                return Err(error::InvalidFlightPutSnafu {
+                    reason: "Expecting the following messages are all record batches.",
+                }
+                .build()
+                .into());
+            };
+            if recordbatch.num_rows() == 0 {
+                continue;
+            }
+
+            let rows = self
+                .handler
+                .insert_recordbatch(&self.table_name, recordbatch, self.ctx.clone())
+                .await?;
+
+            let result = PutResult {
+                app_metadata: FlightMetadata {
+                    affected_rows: Some(AffectedRows { value: rows as _ }),
+                }
+                .encode_to_vec()
+                .into(),
+            };
+            if let Err(e) = tx.send(Ok(result)).await {
+                warn!("stop sending Flight put result, err: {e}");
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+}
+
 fn to_flight_data_stream(output: Output) -> TonicStream<FlightData> {
     match output {
         Output::Stream(stream) => {
@@ -52,10 +52,7 @@ impl GreptimeRequestHandler {
             reason: "Expecting non-empty GreptimeRequest.",
         })?;
 
-        let header = request.header.as_ref();
-        let query_ctx = create_query_context(header);
-
-        self.auth(header, &query_ctx).await?;
+        let query_ctx = self.query_context(request.header.as_ref()).await?;
 
         let handler = self.handler.clone();
 
@@ -82,6 +79,17 @@ impl GreptimeRequestHandler {
         Ok(output)
     }
 
+    /// Creates the query context from the request header, once the request is authenticated and
+    /// authorized.
+    pub(crate) async fn query_context(
+        &self,
+        header: Option<&RequestHeader>,
+    ) -> TonicResult<QueryContextRef> {
+        let query_ctx = create_query_context(header);
+        self.auth(header, &query_ctx).await?;
+        Ok(query_ctx)
+    }
+
     async fn auth(
         &self,
         header: Option<&RequestHeader>,
@@ -119,7 +127,9 @@ impl GreptimeRequestHandler {
                 &user_info,
             )
             .await
-            .map_err(|e| Status::permission_denied(e.to_string()))
+            .map_err(|e| Status::permission_denied(e.to_string()))?;
+        query_ctx.set_current_user(user_info);
+        Ok(())
     }
 }
 
@@ -16,8 +16,10 @@ pub mod authorize;
 pub mod handler;
 pub mod influxdb;
 pub mod opentsdb;
+pub mod otlp;
 pub mod prometheus;
 pub mod script;
+mod stream;
 
 mod admin;
 #[cfg(feature = "dashboard")]
@@ -25,6 +27,7 @@ mod dashboard;
 #[cfg(feature = "mem-prof")]
 pub mod mem_prof;
 
+use std::io::Read;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -42,11 +45,12 @@ use common_query::Output;
 use common_recordbatch::{util, RecordBatch};
 use common_telemetry::logging::info;
 use datatypes::data_type::DataType;
+use flate2::read::GzDecoder;
 use futures::FutureExt;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use session::context::QueryContext;
+use session::context::{QueryContext, UserInfo};
 use snafu::{ensure, ResultExt};
 use tokio::sync::oneshot::{self, Sender};
 use tokio::sync::Mutex;
@@ -56,42 +60,73 @@ use tower_http::auth::AsyncRequireAuthorizationLayer;
 use tower_http::trace::TraceLayer;
 
 use self::authorize::HttpAuth;
-use self::influxdb::{influxdb_health, influxdb_ping, influxdb_write};
+use self::influxdb::{
+    influxdb_health, influxdb_ping, influxdb_query, influxdb_v2_buckets, influxdb_v2_write,
+    influxdb_write, BucketsState,
+};
 use crate::auth::UserProviderRef;
 use crate::error::{AlreadyStartedSnafu, Result, StartHttpSnafu};
 use crate::http::admin::flush;
 use crate::metrics_handler::MetricsHandler;
+use crate::prom::{self, PromHandlerRef, PROM_API_VERSION};
 use crate::query_handler::grpc::ServerGrpcQueryHandlerRef;
 use crate::query_handler::sql::ServerSqlQueryHandlerRef;
 use crate::query_handler::{
-    InfluxdbLineProtocolHandlerRef, OpentsdbProtocolHandlerRef, PrometheusProtocolHandlerRef,
-    ScriptHandlerRef,
+    InfluxdbLineProtocolHandlerRef, OpenTelemetryProtocolHandlerRef, OpentsdbProtocolHandlerRef,
+    PrometheusProtocolHandlerRef, ScriptHandlerRef,
 };
 use crate::server::Server;
 
-/// create query context from database name information, catalog and schema are
-/// resolved from the name
+/// create query context of the authenticated user from database name information, catalog and
+/// schema are resolved from the name
 pub(crate) fn query_context_from_db(
     query_handler: ServerSqlQueryHandlerRef,
     db: Option<String>,
+    user_info: UserInfo,
 ) -> std::result::Result<Arc<QueryContext>, JsonResponse> {
-    if let Some(db) = &db {
+    let query_ctx = if let Some(db) = &db {
         let (catalog, schema) = super::parse_catalog_and_schema_from_client_database_name(db);
 
         match query_handler.is_valid_schema(catalog, schema) {
-            Ok(true) => Ok(Arc::new(QueryContext::with(catalog, schema))),
-            Ok(false) => Err(JsonResponse::with_error(
-                format!("Database not found: {db}"),
-                StatusCode::DatabaseNotFound,
-            )),
-            Err(e) => Err(JsonResponse::with_error(
-                format!("Error checking database: {db}, {e}"),
-                StatusCode::Internal,
-            )),
+            Ok(true) => Arc::new(QueryContext::with(catalog, schema)),
+            Ok(false) => {
+                return Err(JsonResponse::with_error(
+                    format!("Database not found: {db}"),
+                    StatusCode::DatabaseNotFound,
+                ))
+            }
+            Err(e) => {
+                return Err(JsonResponse::with_error(
+                    format!("Error checking database: {db}, {e}"),
+                    StatusCode::Internal,
+                ))
+            }
         }
     } else {
-        Ok(QueryContext::arc())
-    }
+        QueryContext::arc()
+    };
+    query_ctx.set_current_user(user_info);
+    Ok(query_ctx)
+}
+
+/// Max size of a gzipped request body after it's decompressed.
+const MAX_DECOMPRESSED_BODY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Decompresses the gzipped request `body`, fails if it's larger than
+/// [MAX_DECOMPRESSED_BODY_SIZE] after decompressed, so a small body can't inflate into a huge
+/// buffer.
+pub(crate) fn gunzip_body(body: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let _ = GzDecoder::new(body)
+        .take(MAX_DECOMPRESSED_BODY_SIZE + 1)
+        .read_to_end(&mut buf)?;
+    if buf.len() as u64 > MAX_DECOMPRESSED_BODY_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("decompressed body exceeds the limit of {MAX_DECOMPRESSED_BODY_SIZE} bytes"),
+        ));
+    }
+    Ok(buf)
 }
 
 pub const HTTP_API_VERSION: &str = "v1";
@@ -108,6 +143,8 @@ pub struct HttpServer {
     influxdb_handler: Option<InfluxdbLineProtocolHandlerRef>,
     opentsdb_handler: Option<OpentsdbProtocolHandlerRef>,
     prom_handler: Option<PrometheusProtocolHandlerRef>,
+    prom_query_handler: Option<PromHandlerRef>,
+    otlp_handler: Option<OpenTelemetryProtocolHandlerRef>,
     script_handler: Option<ScriptHandlerRef>,
     shutdown_tx: Mutex<Option<Sender<()>>>,
     user_provider: Option<UserProviderRef>,
@@ -378,6 +415,8 @@ impl HttpServerBuilder {
                 opentsdb_handler: None,
                 influxdb_handler: None,
                 prom_handler: None,
+                prom_query_handler: None,
+                otlp_handler: None,
                 user_provider: None,
                 script_handler: None,
                 metrics_handler: None,
@@ -416,6 +455,16 @@ impl HttpServerBuilder {
         self
     }
 
+    pub fn with_prom_query_handler(&mut self, handler: PromHandlerRef) -> &mut Self {
+        self.inner.prom_query_handler.get_or_insert(handler);
+        self
+    }
+
+    pub fn with_otlp_handler(&mut self, handler: OpenTelemetryProtocolHandlerRef) -> &mut Self {
+        self.inner.otlp_handler.get_or_insert(handler);
+        self
+    }
+
     pub fn with_user_provider(&mut self, user_provider: UserProviderRef) -> &mut Self {
         self.inner.user_provider.get_or_insert(user_provider);
         self
@@ -487,6 +536,20 @@ impl HttpServer {
             );
         }
 
+        if let Some(prom_query_handler) = self.prom_query_handler.clone() {
+            router = router.nest(
+                &format!("/{HTTP_API_VERSION}/prometheus/api/{PROM_API_VERSION}"),
+                prom::api_router(prom_query_handler),
+            );
+        }
+
+        if let Some(otlp_handler) = self.otlp_handler.clone() {
+            router = router.nest(
+                &format!("/{HTTP_API_VERSION}/otlp"),
+                self.route_otlp(otlp_handler),
+            );
+        }
+
         // mem profiler
         #[cfg(feature = "mem-prof")]
         {
@@ -559,18 +622,65 @@ impl HttpServer {
             .with_state(prom_handler)
     }
 
-    fn route_influxdb<S>(&self, influxdb_handler: InfluxdbLineProtocolHandlerRef) -> Router<S> {
+    fn route_otlp<S>(&self, otlp_handler: OpenTelemetryProtocolHandlerRef) -> Router<S> {
         Router::new()
+            .route("/v1/metrics", routing::post(otlp::metrics))
+            .with_state(otlp_handler)
+    }
+
+    fn route_influxdb<S>(&self, influxdb_handler: InfluxdbLineProtocolHandlerRef) -> Router<S> {
+        let router = Router::new()
             .route("/write", routing::post(influxdb_write))
             .route("/ping", routing::get(influxdb_ping))
             .route("/health", routing::get(influxdb_health))
-            .with_state(influxdb_handler)
+            // The v2 API, for clients whose url is configured as `/v1/influxdb`.
+            .route("/api/v2/write", routing::post(influxdb_v2_write))
+            .with_state(influxdb_handler.clone())
+            .merge(
+                Router::new()
+                    .route("/api/v2/buckets", routing::get(influxdb_v2_buckets))
+                    .with_state(BucketsState {
+                        handler: influxdb_handler,
+                        user_provider: self.user_provider.clone(),
+                    }),
+            );
+
+        // InfluxQL queries are translated into SQL, so they are served by the SQL handler.
+        match self.sql_handler.clone() {
+            Some(sql_handler) => router.merge(
+                Router::new()
+                    .route("/query", routing::get(influxdb_query).post(influxdb_query))
+                    .with_state(sql_handler),
+            ),
+            None => router,
+        }
     }
 
     fn route_opentsdb<S>(&self, opentsdb_handler: OpentsdbProtocolHandlerRef) -> Router<S> {
-        Router::new()
+        let router = Router::new()
             .route("/api/put", routing::post(opentsdb::put))
-            .with_state(opentsdb_handler)
+            .with_state(opentsdb_handler);
+
+        // The queries are translated into SQL, so they are served by the SQL handler.
+        match self.sql_handler.clone() {
+            Some(sql_handler) => router.merge(
+                Router::new()
+                    .route(
+                        "/api/query",
+                        routing::get(opentsdb::query).post(opentsdb::query),
+                    )
+                    .route(
+                        "/api/suggest",
+                        routing::get(opentsdb::suggest).post(opentsdb::suggest),
+                    )
+                    .route(
+                        "/api/search/lookup",
+                        routing::get(opentsdb::search_lookup).post(opentsdb::search_lookup),
+                    )
+                    .with_state(sql_handler),
+            ),
+            None => router,
+        }
     }
 
     fn route_admin<S>(&self, grpc_handler: ServerGrpcQueryHandlerRef) -> Router<S> {
@@ -738,6 +848,26 @@ mod test {
         assert_eq!(Duration::from_secs(30), default.timeout)
     }
 
+    #[test]
+    fn test_gunzip_body() {
+        use std::io::Write;
+
+        let gzip = |data: &[u8]| {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let body = gzip(b"cpu,host=a usage=1.0");
+        assert_eq!(b"cpu,host=a usage=1.0".to_vec(), gunzip_body(&body).unwrap());
+
+        // A small body inflating beyond the limit.
+        let body = gzip(&vec![0; MAX_DECOMPRESSED_BODY_SIZE as usize + 1]);
+        let err = gunzip_body(&body).unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"), "{err}");
+    }
+
     #[tokio::test]
     async fn test_http_server_request_timeout() {
         let (tx, _rx) = mpsc::channel(100);
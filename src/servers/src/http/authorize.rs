@@ -31,8 +31,12 @@ use crate::error::{
     self, InvalidAuthorizationHeaderSnafu, InvisibleASCIISnafu, NotFoundInfluxAuthSnafu, Result,
     UnsupportedAuthSchemeSnafu,
 };
+use crate::http::influxdb::v2_write_database;
 use crate::http::HTTP_API_PREFIX;
 
+const INFLUXDB_V2_WRITE_PATH: &str = "/influxdb/api/v2/write";
+const INFLUXDB_V2_BUCKETS_PATH: &str = "/influxdb/api/v2/buckets";
+
 pub struct HttpAuth<RespBody> {
     user_provider: Option<UserProviderRef>,
     _ty: PhantomData<RespBody>,
@@ -103,14 +107,27 @@ async fn authorize<B: Send + Sync + 'static>(
     user_provider: &UserProviderRef,
     request: &Request<B>,
 ) -> crate::auth::Result<()> {
+    let path = request.uri().path();
+    // Listing buckets doesn't access any database, the handler only lists the accessible ones.
+    if path.ends_with(INFLUXDB_V2_BUCKETS_PATH) {
+        return Ok(());
+    }
+
     // try get database name
     let query = request.uri().query().unwrap_or_default();
-    let input_database = extract_db_from_query(query).context(IllegalParamSnafu {
-        msg: "db not provided or corrupted",
-    })?;
+    let input_database = if path.ends_with(INFLUXDB_V2_WRITE_PATH) {
+        // The same database as the one written by the handler.
+        v2_write_database(query).map_err(|e| IllegalParamSnafu { msg: e.to_string() }.build())?
+    } else {
+        extract_db_from_query(query)
+            .context(IllegalParamSnafu {
+                msg: "db not provided or corrupted",
+            })?
+            .to_string()
+    };
 
     let (catalog, database) =
-        crate::parse_catalog_and_schema_from_client_database_name(input_database);
+        crate::parse_catalog_and_schema_from_client_database_name(&input_database);
 
     let user_info = request
         .extensions()
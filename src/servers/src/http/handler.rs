@@ -16,21 +16,91 @@ use std::collections::HashMap;
 use std::time::Instant;
 
 use aide::transform::TransformOperation;
+use aide::OperationOutput;
+use axum::body::StreamBody;
 use axum::extract::{Json, Query, State};
+use axum::http::{header, StatusCode as HttpStatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::{Extension, Form};
 use common_error::status_code::StatusCode;
+use common_query::Output;
+use common_recordbatch::SendableRecordBatchStream;
+use common_telemetry::logging::error;
+use futures::TryStreamExt;
 use query::parser::PromQuery;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use session::context::UserInfo;
 
-use crate::http::{ApiState, JsonResponse};
+use crate::error::Result;
+use crate::http::{stream, ApiState, JsonResponse};
 use crate::metrics_handler::MetricsHandler;
 
 #[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SqlQuery {
     pub db: Option<String>,
     pub sql: Option<String>,
+    /// The format of the results, JSON by default.
+    pub format: Option<ResponseFormat>,
+}
+
+/// The formats of the SQL results. The results in the formats other than JSON are streamed as
+/// they are produced by chunked transfer encoding, instead of being held in memory as a whole.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    Csv,
+    Tsv,
+    Ndjson,
+    /// The Arrow IPC streaming format.
+    Arrow,
+}
+
+impl ResponseFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Csv => "text/csv",
+            ResponseFormat::Tsv => "text/tab-separated-values",
+            ResponseFormat::Ndjson => "application/x-ndjson",
+            ResponseFormat::Arrow => "application/vnd.apache.arrow.stream",
+        }
+    }
+}
+
+pub enum SqlResponse {
+    Json(Json<JsonResponse>),
+    /// The records streamed in a format other than JSON.
+    Stream(ResponseFormat, SendableRecordBatchStream),
+    /// The error of a request for a streaming format, which is replied with the status
+    /// `400 Bad Request`, since its clients are not likely to check the code in the body.
+    Error(Json<JsonResponse>),
+}
+
+impl IntoResponse for SqlResponse {
+    fn into_response(self) -> Response {
+        match self {
+            SqlResponse::Json(json) => json.into_response(),
+            SqlResponse::Stream(format, stream) => {
+                let body = stream::encode_stream(format, stream).inspect_err(|e| {
+                    error!(e; "Failed to stream the SQL results in {:?}", format);
+                });
+                (
+                    [(header::CONTENT_TYPE, format.content_type())],
+                    StreamBody::new(body),
+                )
+                    .into_response()
+            }
+            SqlResponse::Error(json) => (HttpStatusCode::BAD_REQUEST, json).into_response(),
+        }
+    }
+}
+
+// The responses are documented by `sql_docs`.
+impl OperationOutput for SqlResponse {
+    type Inner = Self;
 }
 
 /// Handler to execute sql
@@ -38,20 +108,31 @@ pub struct SqlQuery {
 pub async fn sql(
     State(state): State<ApiState>,
     Query(query_params): Query<SqlQuery>,
-    // TODO(fys): pass _user_info into query context
-    _user_info: Extension<UserInfo>,
+    Extension(user_info): Extension<UserInfo>,
     Form(form_params): Form<SqlQuery>,
-) -> Json<JsonResponse> {
+) -> SqlResponse {
     let sql_handler = &state.sql_handler;
 
     let start = Instant::now();
     let sql = query_params.sql.or(form_params.sql);
     let db = query_params.db.or(form_params.db);
+    let format = query_params
+        .format
+        .or(form_params.format)
+        .unwrap_or_default();
 
     let resp = if let Some(sql) = &sql {
-        match super::query_context_from_db(sql_handler.clone(), db) {
+        match super::query_context_from_db(sql_handler.clone(), db, user_info) {
             Ok(query_ctx) => {
-                JsonResponse::from_output(sql_handler.do_query(sql, query_ctx).await).await
+                let outputs = sql_handler.do_query(sql, query_ctx).await;
+                if format == ResponseFormat::Json {
+                    JsonResponse::from_output(outputs).await
+                } else {
+                    match stream_output(outputs) {
+                        Ok(stream) => return SqlResponse::Stream(format, stream),
+                        Err(outputs) => JsonResponse::from_output(outputs).await,
+                    }
+                }
             }
             Err(resp) => resp,
         }
@@ -62,7 +143,27 @@ pub async fn sql(
         )
     };
 
-    Json(resp.with_execution_time(start.elapsed().as_millis()))
+    let json = Json(resp.with_execution_time(start.elapsed().as_millis()));
+    if format != ResponseFormat::Json && !json.success() {
+        SqlResponse::Error(json)
+    } else {
+        SqlResponse::Json(json)
+    }
+}
+
+/// Returns the records to stream if the SQL is a single query, or gives the outputs back to be
+/// replied in JSON, like the affected rows of the other statements.
+fn stream_output(
+    mut outputs: Vec<Result<Output>>,
+) -> std::result::Result<SendableRecordBatchStream, Vec<Result<Output>>> {
+    if outputs.len() != 1 {
+        return Err(outputs);
+    }
+    match outputs.remove(0) {
+        Ok(Output::Stream(stream)) => Ok(stream),
+        Ok(Output::RecordBatches(recordbatches)) => Ok(recordbatches.as_stream()),
+        output => Err(vec![output]),
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -90,14 +191,13 @@ impl From<PromqlQuery> for PromQuery {
 pub async fn promql(
     State(state): State<ApiState>,
     Query(params): Query<PromqlQuery>,
-    // TODO(fys): pass _user_info into query context
-    _user_info: Extension<UserInfo>,
+    Extension(user_info): Extension<UserInfo>,
 ) -> Json<JsonResponse> {
     let sql_handler = &state.sql_handler;
     let exec_start = Instant::now();
     let db = params.db.clone();
     let prom_query = params.into();
-    let resp = match super::query_context_from_db(sql_handler.clone(), db) {
+    let resp = match super::query_context_from_db(sql_handler.clone(), db, user_info) {
         Ok(query_ctx) => {
             JsonResponse::from_output(sql_handler.do_promql_query(&prom_query, query_ctx).await)
                 .await
@@ -15,16 +15,27 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use axum::extract::{Query, State};
-use axum::http::StatusCode;
+use axum::extract::{Query, RawQuery, State};
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use common_catalog::consts::DEFAULT_SCHEMA_NAME;
+use axum::{Extension, Json};
+use bytes::Bytes;
+use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
 use common_grpc::writer::Precision;
-use session::context::QueryContext;
+use serde_json::{json, Value};
+use session::context::{QueryContext, UserInfo};
+use snafu::{ensure, OptionExt, ResultExt};
 
-use crate::error::{Result, TimePrecisionSnafu};
+use crate::auth::{self, UserProviderRef};
+use crate::error::{
+    AuthSnafu, DecompressInfluxdbRequestSnafu, InvalidInfluxdbLinesSnafu, InvalidInfluxqlSnafu,
+    InvalidQuerySnafu, Result, TimePrecisionSnafu,
+};
+use crate::http::gunzip_body;
+use crate::influxdb::influxql::{self, InfluxqlResponse};
 use crate::influxdb::InfluxdbRequest;
 use crate::parse_catalog_and_schema_from_client_database_name;
+use crate::query_handler::sql::ServerSqlQueryHandlerRef;
 use crate::query_handler::InfluxdbLineProtocolHandlerRef;
 
 // https://docs.influxdata.com/influxdb/v1.8/tools/api/#ping-http-endpoint
@@ -43,24 +54,219 @@ pub async fn influxdb_health() -> Result<impl IntoResponse> {
 pub async fn influxdb_write(
     State(handler): State<InfluxdbLineProtocolHandlerRef>,
     Query(mut params): Query<HashMap<String, String>>,
-    lines: String,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> Result<impl IntoResponse> {
     let db = params
         .remove("db")
         .unwrap_or_else(|| DEFAULT_SCHEMA_NAME.to_string());
-    let (catalog, schema) = parse_catalog_and_schema_from_client_database_name(&db);
-    let ctx = Arc::new(QueryContext::with(catalog, schema));
-
     let precision = params
         .get("precision")
         .map(|val| parse_time_precision(val))
         .transpose()?;
+
+    write_lines(handler, &db, precision, &headers, body).await
+}
+
+// https://docs.influxdata.com/influxdb/v2.7/api/#operation/PostWrite
+#[axum_macros::debug_handler]
+pub async fn influxdb_v2_write(
+    State(handler): State<InfluxdbLineProtocolHandlerRef>,
+    Query(params): Query<HashMap<String, String>>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse> {
+    // The organization is ignored, as the buckets of all organizations are the databases.
+    let db = v2_write_database(query.as_deref().unwrap_or_default())?;
+    let precision = params
+        .get("precision")
+        .map(|val| parse_v2_time_precision(val))
+        .transpose()?;
+
+    write_lines(handler, &db, precision, &headers, body).await
+}
+
+#[derive(Clone)]
+pub struct BucketsState {
+    pub handler: InfluxdbLineProtocolHandlerRef,
+    /// Only the buckets the user may access are listed if there is a user provider.
+    pub user_provider: Option<UserProviderRef>,
+}
+
+impl BucketsState {
+    async fn is_accessible(&self, database: &str, user_info: &UserInfo) -> Result<bool> {
+        let Some(user_provider) = &self.user_provider else {
+            return Ok(true);
+        };
+        match user_provider
+            .authorize(DEFAULT_CATALOG_NAME, database, user_info)
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(auth::Error::AccessDenied { .. }) => Ok(false),
+            Err(e) => Err(e).context(AuthSnafu),
+        }
+    }
+}
+
+// https://docs.influxdata.com/influxdb/v2.7/api/#operation/GetBuckets
+#[axum_macros::debug_handler]
+pub async fn influxdb_v2_buckets(
+    State(state): State<BucketsState>,
+    Query(params): Query<HashMap<String, String>>,
+    Extension(user_info): Extension<UserInfo>,
+) -> Result<Json<Value>> {
+    let org = params.get("org").map(String::as_str).unwrap_or_default();
+    let mut buckets = Vec::new();
+    for name in state.handler.database_names(QueryContext::arc()).await? {
+        if params.get("name").map_or(false, |bucket| *bucket != name)
+            || !state.is_accessible(&name, &user_info).await?
+        {
+            continue;
+        }
+        buckets.push(json!({
+            "id": name,
+            "orgID": org,
+            "name": name,
+            "type": "user",
+            "retentionRules": [],
+        }));
+    }
+    Ok(Json(json!({ "buckets": buckets })))
+}
+
+// https://docs.influxdata.com/influxdb/v1.8/tools/api/#query-http-endpoint
+#[axum_macros::debug_handler]
+pub async fn influxdb_query(
+    State(handler): State<ServerSqlQueryHandlerRef>,
+    Query(mut params): Query<HashMap<String, String>>,
+    Extension(user_info): Extension<UserInfo>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<InfluxqlResponse>> {
+    // Clients like Grafana may send the query in a form by POST.
+    let is_form = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| {
+            value.starts_with("application/x-www-form-urlencoded")
+        });
+    if is_form {
+        let form: Vec<(String, String)> = serde_urlencoded::from_bytes(&body).map_err(|e| {
+            InvalidInfluxqlSnafu {
+                reason: format!("invalid form: {e}"),
+            }
+            .build()
+        })?;
+        params.extend(form);
+    }
+
+    let query = params.get("q").context(InvalidInfluxqlSnafu {
+        reason: "missing required parameter \"q\"",
+    })?;
+    let db = params
+        .get("db")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_SCHEMA_NAME);
+    let epoch = params
+        .get("epoch")
+        .map(|val| parse_epoch(val))
+        .transpose()?;
+
+    let response = influxql::execute(&handler, query, db, epoch, user_info).await?;
+    Ok(Json(response))
+}
+
+async fn write_lines(
+    handler: InfluxdbLineProtocolHandlerRef,
+    db: &str,
+    precision: Option<Precision>,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> Result<(StatusCode, ())> {
+    let (catalog, schema) = parse_catalog_and_schema_from_client_database_name(db);
+    let ctx = Arc::new(QueryContext::with(catalog, schema));
+
+    let lines = decode_lines(headers, body)?;
     let request = InfluxdbRequest { precision, lines };
 
     handler.exec(&request, ctx).await?;
     Ok((StatusCode::NO_CONTENT, ()))
 }
 
+/// Decodes the lines in the body, which may be compressed by gzip.
+fn decode_lines(headers: &HeaderMap, body: Bytes) -> Result<String> {
+    let gzipped = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.eq_ignore_ascii_case("gzip"));
+    if gzipped {
+        let lines = gunzip_body(&body).context(DecompressInfluxdbRequestSnafu)?;
+        String::from_utf8(lines).context(InvalidInfluxdbLinesSnafu)
+    } else {
+        String::from_utf8(body.to_vec()).context(InvalidInfluxdbLinesSnafu)
+    }
+}
+
+/// Returns the database written by a v2 write request of the query string `query`, which is
+/// the database of its `bucket`, or the default database without a bucket. Requests with more
+/// than one bucket are rejected, so the authorized database is always the written one.
+pub(crate) fn v2_write_database(query: &str) -> Result<String> {
+    let params: Vec<(String, String)> = serde_urlencoded::from_str(query).map_err(|e| {
+        InvalidQuerySnafu {
+            reason: format!("invalid query string: {e}"),
+        }
+        .build()
+    })?;
+    let mut buckets = params
+        .into_iter()
+        .filter_map(|(name, value)| (name == "bucket").then_some(value));
+    let bucket = buckets.next();
+    ensure!(
+        buckets.next().is_none(),
+        InvalidQuerySnafu {
+            reason: "more than one bucket is provided",
+        }
+    );
+    Ok(bucket.map_or_else(
+        || DEFAULT_SCHEMA_NAME.to_string(),
+        |bucket| bucket_to_database(&bucket).to_string(),
+    ))
+}
+
+/// Returns the database of a bucket, which is the database itself or the database with a
+/// retention policy, like `db/rp`. Retention policies are ignored.
+fn bucket_to_database(bucket: &str) -> &str {
+    bucket
+        .split_once('/')
+        .map_or(bucket, |(database, _)| database)
+}
+
+/// Parses the precision of the v2 API, which doesn't support minutes and hours.
+fn parse_v2_time_precision(value: &str) -> Result<Precision> {
+    match value {
+        "ns" => Ok(Precision::Nanosecond),
+        "us" => Ok(Precision::Microsecond),
+        "ms" => Ok(Precision::Millisecond),
+        "s" => Ok(Precision::Second),
+        unknown => TimePrecisionSnafu {
+            name: unknown.to_string(),
+        }
+        .fail(),
+    }
+}
+
+/// Parses the precision of the times in the query results, which is in `n`, `ns`, `u`, `µ`,
+/// `ms`, `s`, `m` or `h`.
+fn parse_epoch(value: &str) -> Result<Precision> {
+    match value {
+        "ns" => Ok(Precision::Nanosecond),
+        "µ" => Ok(Precision::Microsecond),
+        value => parse_time_precision(value),
+    }
+}
+
 fn parse_time_precision(value: &str) -> Result<Precision> {
     match value {
         "n" => Ok(Precision::Nanosecond),
@@ -78,9 +284,7 @@ fn parse_time_precision(value: &str) -> Result<Precision> {
 
 #[cfg(test)]
 mod tests {
-    use common_grpc::writer::Precision;
-
-    use crate::http::influxdb::parse_time_precision;
+    use super::*;
 
     #[test]
     fn test_parse_time_precision() {
@@ -92,4 +296,59 @@ mod tests {
         assert_eq!(Precision::Hour, parse_time_precision("h").unwrap());
         assert!(parse_time_precision("unknown").is_err());
     }
+
+    #[test]
+    fn test_parse_v2_time_precision() {
+        assert_eq!(
+            Precision::Nanosecond,
+            parse_v2_time_precision("ns").unwrap()
+        );
+        assert_eq!(
+            Precision::Microsecond,
+            parse_v2_time_precision("us").unwrap()
+        );
+        assert_eq!(
+            Precision::Millisecond,
+            parse_v2_time_precision("ms").unwrap()
+        );
+        assert_eq!(Precision::Second, parse_v2_time_precision("s").unwrap());
+        assert!(parse_v2_time_precision("n").is_err());
+        assert!(parse_v2_time_precision("h").is_err());
+    }
+
+    #[test]
+    fn test_bucket_to_database() {
+        assert_eq!("db", bucket_to_database("db"));
+        assert_eq!("db", bucket_to_database("db/autogen"));
+        assert_eq!("greptime-db", bucket_to_database("greptime-db/autogen"));
+    }
+
+    #[test]
+    fn test_v2_write_database() {
+        assert_eq!("public", v2_write_database("").unwrap());
+        assert_eq!("public", v2_write_database("org=greptime").unwrap());
+        assert_eq!("db", v2_write_database("org=greptime&bucket=db").unwrap());
+        assert_eq!("db", v2_write_database("bucket=db%2Fautogen").unwrap());
+        assert_eq!("db", v2_write_database("bucket=%64b").unwrap());
+        assert!(v2_write_database("bucket=db&bucket=public").is_err());
+    }
+
+    #[test]
+    fn test_decode_lines() {
+        let lines = "monitor,host=host1 cpu=1.2 1664370459457010101";
+        let headers = HeaderMap::new();
+        assert_eq!(
+            lines,
+            decode_lines(&headers, Bytes::from_static(lines.as_bytes())).unwrap()
+        );
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, lines.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(header::CONTENT_ENCODING, "gzip".parse().unwrap());
+        assert_eq!(lines, decode_lines(&headers, Bytes::from(gzipped)).unwrap());
+
+        assert!(decode_lines(&headers, Bytes::from_static(lines.as_bytes())).is_err());
+    }
 }
@@ -15,18 +15,24 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use axum::body::Bytes;
 use axum::extract::{Query, RawBody, State};
 use axum::http::StatusCode as HttpStatusCode;
-use axum::Json;
+use axum::{Extension, Json};
 use common_catalog::consts::DEFAULT_SCHEMA_NAME;
 use hyper::Body;
 use serde::{Deserialize, Serialize};
-use session::context::QueryContext;
-use snafu::ResultExt;
+use session::context::{QueryContext, UserInfo};
+use snafu::{OptionExt, ResultExt};
 
-use crate::error::{self, Error, Result};
+use crate::error::{self, Error, InvalidOpentsdbQuerySnafu, Result};
 use crate::opentsdb::codec::DataPoint;
+use crate::opentsdb::query::{
+    self, LookupRequest, LookupResponse, QueryRequest, QueryResult, SubQuery, SuggestRequest,
+    TimeSpec,
+};
 use crate::parse_catalog_and_schema_from_client_database_name;
+use crate::query_handler::sql::ServerSqlQueryHandlerRef;
 use crate::query_handler::OpentsdbProtocolHandlerRef;
 
 #[derive(Serialize, Deserialize)]
@@ -136,6 +142,138 @@ pub async fn put(
     Ok(response)
 }
 
+// Please refer to the OpenTSDB documents of ["api/query"](http://opentsdb.net/docs/build/html/api_http/query/index.html)
+// for more details.
+#[axum_macros::debug_handler]
+pub async fn query(
+    State(handler): State<ServerSqlQueryHandlerRef>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user_info): Extension<UserInfo>,
+    body: Bytes,
+) -> Result<Json<Vec<QueryResult>>> {
+    let request = if body.is_empty() {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let start = param("start").context(InvalidOpentsdbQuerySnafu {
+            reason: "missing required parameter \"start\"",
+        })?;
+        let queries = params
+            .iter()
+            .filter(|(key, _)| key == "m")
+            .map(|(_, m)| SubQuery::parse(m))
+            .collect::<Result<Vec<_>>>()?;
+        QueryRequest {
+            start: TimeSpec::String(start.to_string()),
+            end: param("end").map(|end| TimeSpec::String(end.to_string())),
+            queries,
+            ms_resolution: param("ms").is_some() || param("msResolution").is_some(),
+        }
+    } else {
+        serde_json::from_slice(&body).context(error::InvalidOpentsdbJsonRequestSnafu)?
+    };
+
+    let results = query::execute_query(&handler, &request, query_context(&params, user_info)).await?;
+    Ok(Json(results))
+}
+
+// Please refer to the OpenTSDB documents of ["api/suggest"](http://opentsdb.net/docs/build/html/api_http/suggest.html)
+// for more details.
+#[axum_macros::debug_handler]
+pub async fn suggest(
+    State(handler): State<ServerSqlQueryHandlerRef>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user_info): Extension<UserInfo>,
+    body: Bytes,
+) -> Result<Json<Vec<String>>> {
+    let request = if body.is_empty() {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let max = param("max")
+            .map(|max| {
+                max.parse().ok().context(InvalidOpentsdbQuerySnafu {
+                    reason: format!("invalid max: {max}"),
+                })
+            })
+            .transpose()?;
+        SuggestRequest {
+            suggest_type: param("type").context(InvalidOpentsdbQuerySnafu {
+                reason: "missing required parameter \"type\"",
+            })?,
+            q: param("q").unwrap_or_default(),
+            max,
+        }
+    } else {
+        serde_json::from_slice(&body).context(error::InvalidOpentsdbJsonRequestSnafu)?
+    };
+
+    let suggestions = query::execute_suggest(&handler, &request, query_context(&params, user_info)).await?;
+    Ok(Json(suggestions))
+}
+
+// Please refer to the OpenTSDB documents of ["api/search/lookup"](http://opentsdb.net/docs/build/html/api_http/search/lookup.html)
+// for more details.
+#[axum_macros::debug_handler]
+pub async fn search_lookup(
+    State(handler): State<ServerSqlQueryHandlerRef>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user_info): Extension<UserInfo>,
+    body: Bytes,
+) -> Result<Json<LookupResponse>> {
+    let request = if body.is_empty() {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let parse_number = |name: &str| {
+            param(name)
+                .map(|value| {
+                    value
+                        .parse::<usize>()
+                        .ok()
+                        .context(InvalidOpentsdbQuerySnafu {
+                            reason: format!("invalid {name}: {value}"),
+                        })
+                })
+                .transpose()
+        };
+        let m = param("m").context(InvalidOpentsdbQuerySnafu {
+            reason: "missing required parameter \"m\"",
+        })?;
+        LookupRequest::parse(
+            m,
+            parse_number("limit")?,
+            parse_number("startIndex")?.unwrap_or_default(),
+        )?
+    } else {
+        serde_json::from_slice(&body).context(error::InvalidOpentsdbJsonRequestSnafu)?
+    };
+
+    let response = query::execute_lookup(&handler, request, query_context(&params, user_info)).await?;
+    Ok(Json(response))
+}
+
+fn query_context(params: &[(String, String)], user_info: UserInfo) -> Arc<QueryContext> {
+    let db = params
+        .iter()
+        .find(|(key, _)| key == "db")
+        .map(|(_, value)| value.as_str())
+        .unwrap_or(DEFAULT_SCHEMA_NAME);
+    let (catalog, schema) = parse_catalog_and_schema_from_client_database_name(db);
+    let ctx = QueryContext::with(catalog, schema);
+    ctx.set_current_user(user_info);
+    Arc::new(ctx)
+}
+
 async fn parse_data_points(body: Body) -> Result<Vec<DataPointRequest>> {
     let body = hyper::body::to_bytes(body)
         .await
@@ -0,0 +1,83 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use opentelemetry_proto::tonic::collector::metrics::v1::{
+    ExportMetricsServiceRequest, ExportMetricsServiceResponse,
+};
+use prost::Message;
+use session::context::QueryContext;
+use snafu::ResultExt;
+
+use crate::error::{DecodeOtlpRequestSnafu, DecompressOtlpRequestSnafu, Result};
+use crate::http::gunzip_body;
+use crate::http::prometheus::DatabaseQuery;
+use crate::otlp::{MetricNaming, OtlpMetricsRequest, OTLP_NAMING_HEADER};
+use crate::parse_catalog_and_schema_from_client_database_name;
+use crate::query_handler::OpenTelemetryProtocolHandlerRef;
+
+const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+// https://opentelemetry.io/docs/specs/otlp/#otlphttp
+#[axum_macros::debug_handler]
+pub async fn metrics(
+    State(handler): State<OpenTelemetryProtocolHandlerRef>,
+    Query(params): Query<DatabaseQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse> {
+    let naming = headers
+        .get(OTLP_NAMING_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.parse::<MetricNaming>())
+        .transpose()?
+        .unwrap_or_default();
+    let request = OtlpMetricsRequest {
+        naming,
+        request: decode_request(&headers, body)?,
+    };
+
+    let ctx = if let Some(db) = params.db {
+        let (catalog, schema) = parse_catalog_and_schema_from_client_database_name(&db);
+        Arc::new(QueryContext::with(catalog, schema))
+    } else {
+        QueryContext::arc()
+    };
+
+    handler.metrics(&request, ctx).await?;
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, PROTOBUF_CONTENT_TYPE)],
+        ExportMetricsServiceResponse::default().encode_to_vec(),
+    ))
+}
+
+/// Decodes the request in the body, which may be compressed by gzip.
+fn decode_request(headers: &HeaderMap, body: Bytes) -> Result<ExportMetricsServiceRequest> {
+    let gzipped = headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.eq_ignore_ascii_case("gzip"));
+    if gzipped {
+        let buf = gunzip_body(&body).context(DecompressOtlpRequestSnafu)?;
+        ExportMetricsServiceRequest::decode(&buf[..]).context(DecodeOtlpRequestSnafu)
+    } else {
+        ExportMetricsServiceRequest::decode(body).context(DecodeOtlpRequestSnafu)
+    }
+}
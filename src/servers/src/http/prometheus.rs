@@ -15,21 +15,25 @@
 use std::sync::Arc;
 
 use api::prometheus::remote::{ReadRequest, WriteRequest};
+use axum::body::StreamBody;
 use axum::extract::{Query, RawBody, State};
-use axum::http::{header, StatusCode};
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::IntoResponse;
+use axum::Extension;
 use common_catalog::consts::DEFAULT_SCHEMA_NAME;
 use hyper::Body;
 use prost::Message;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use session::context::QueryContext;
+use session::context::{QueryContext, UserInfo};
 use snafu::prelude::*;
 
 use crate::error::{self, Result};
 use crate::parse_catalog_and_schema_from_client_database_name;
 use crate::prometheus::snappy_decompress;
-use crate::query_handler::{PrometheusProtocolHandlerRef, PrometheusResponse};
+use crate::query_handler::{
+    PrometheusProtocolHandlerRef, PrometheusResponse, PrometheusResponseBody,
+};
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct DatabaseQuery {
@@ -48,6 +52,7 @@ impl Default for DatabaseQuery {
 pub async fn remote_write(
     State(handler): State<PrometheusProtocolHandlerRef>,
     Query(params): Query<DatabaseQuery>,
+    Extension(user_info): Extension<UserInfo>,
     RawBody(body): RawBody,
 ) -> Result<(StatusCode, ())> {
     let request = decode_remote_write_request(body).await?;
@@ -58,6 +63,7 @@ pub async fn remote_write(
     } else {
         QueryContext::arc()
     };
+    ctx.set_current_user(user_info);
 
     // TODO(shuiyisong): add more error log
     handler.write(request, ctx).await?;
@@ -66,14 +72,20 @@ pub async fn remote_write(
 
 impl IntoResponse for PrometheusResponse {
     fn into_response(self) -> axum::response::Response {
-        (
-            [
-                (header::CONTENT_TYPE, self.content_type),
-                (header::CONTENT_ENCODING, self.content_encoding),
-            ],
-            self.body,
-        )
-            .into_response()
+        let mut response = match self.body {
+            PrometheusResponseBody::Full(body) => body.into_response(),
+            PrometheusResponseBody::Stream(stream) => StreamBody::new(stream).into_response(),
+        };
+        let headers = response.headers_mut();
+        if let Ok(content_type) = HeaderValue::from_str(&self.content_type) {
+            let _ = headers.insert(header::CONTENT_TYPE, content_type);
+        }
+        if !self.content_encoding.is_empty() {
+            if let Ok(content_encoding) = HeaderValue::from_str(&self.content_encoding) {
+                let _ = headers.insert(header::CONTENT_ENCODING, content_encoding);
+            }
+        }
+        response
     }
 }
 
@@ -81,6 +93,7 @@ impl IntoResponse for PrometheusResponse {
 pub async fn remote_read(
     State(handler): State<PrometheusProtocolHandlerRef>,
     Query(params): Query<DatabaseQuery>,
+    Extension(user_info): Extension<UserInfo>,
     RawBody(body): RawBody,
 ) -> Result<PrometheusResponse> {
     let request = decode_remote_read_request(body).await?;
@@ -91,6 +104,7 @@ pub async fn remote_read(
     } else {
         QueryContext::arc()
     };
+    ctx.set_current_user(user_info);
 
     // TODO(shuiyisong): add more error log
     handler.read(request, ctx).await
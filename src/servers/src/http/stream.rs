@@ -0,0 +1,262 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encodes the record batches of the SQL results into the response body as they are produced,
+//! so the large results are never held in memory as a whole.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use common_recordbatch::SendableRecordBatchStream;
+use datatypes::arrow::csv::WriterBuilder;
+use datatypes::arrow::ipc::writer::StreamWriter;
+use datatypes::arrow::record_batch::RecordBatch as ArrowRecordBatch;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde_json::Value;
+use snafu::ResultExt;
+
+use crate::error::{self, Result};
+use crate::http::handler::ResponseFormat;
+
+/// Encodes the record batches in the `format`, which must not be [ResponseFormat::Json].
+pub(crate) fn encode_stream(
+    format: ResponseFormat,
+    stream: SendableRecordBatchStream,
+) -> BoxStream<'static, Result<Bytes>> {
+    match format {
+        ResponseFormat::Csv => encode_csv(stream, b','),
+        ResponseFormat::Tsv => encode_csv(stream, b'\t'),
+        ResponseFormat::Ndjson => encode_ndjson(stream),
+        ResponseFormat::Arrow => encode_arrow(stream),
+        ResponseFormat::Json => unreachable!("JSON results are not streamed"),
+    }
+}
+
+/// Encodes the record batches into the delimiter-separated values with a header line.
+fn encode_csv(
+    mut stream: SendableRecordBatchStream,
+    delimiter: u8,
+) -> BoxStream<'static, Result<Bytes>> {
+    let stream = async_stream::try_stream! {
+        let mut has_headers = true;
+        while let Some(batch) = stream.next().await {
+            let batch = batch.context(error::CollectRecordbatchSnafu)?;
+            yield write_csv(batch.df_record_batch(), delimiter, has_headers)?;
+            has_headers = false;
+        }
+        // Writes the header even if there are no rows.
+        if has_headers {
+            let batch = ArrowRecordBatch::new_empty(stream.schema().arrow_schema().clone());
+            yield write_csv(&batch, delimiter, true)?;
+        }
+    };
+    Box::pin(stream)
+}
+
+fn write_csv(batch: &ArrowRecordBatch, delimiter: u8, has_headers: bool) -> Result<Bytes> {
+    let mut buffer = Vec::new();
+    {
+        // The writer flushes the buffered rows when it's dropped.
+        let mut writer = WriterBuilder::new()
+            .has_headers(has_headers)
+            .with_delimiter(delimiter)
+            .build(&mut buffer);
+        writer
+            .write(batch)
+            .context(error::EncodeHttpOutputSnafu { format: "csv" })?;
+    }
+    Ok(buffer.into())
+}
+
+/// Encodes every row into a JSON object of the column names and the values in a line.
+fn encode_ndjson(mut stream: SendableRecordBatchStream) -> BoxStream<'static, Result<Bytes>> {
+    let stream = async_stream::try_stream! {
+        let schema = stream.schema();
+        while let Some(batch) = stream.next().await {
+            let batch = batch.context(error::CollectRecordbatchSnafu)?;
+            let mut buffer = Vec::new();
+            for row in batch.rows() {
+                // Writes the fields in the order of columns.
+                buffer.push(b'{');
+                for (i, (column_schema, value)) in
+                    schema.column_schemas().iter().zip(row).enumerate()
+                {
+                    if i > 0 {
+                        buffer.push(b',');
+                    }
+                    let value = Value::try_from(value).map_err(|e| {
+                        error::InternalSnafu {
+                            err_msg: e.to_string(),
+                        }
+                        .build()
+                    })?;
+                    write_json(&mut buffer, &column_schema.name)?;
+                    buffer.push(b':');
+                    write_json(&mut buffer, &value)?;
+                }
+                buffer.extend_from_slice(b"}\n");
+            }
+            yield buffer.into();
+        }
+    };
+    Box::pin(stream)
+}
+
+fn write_json<T: serde::Serialize + ?Sized>(buffer: &mut Vec<u8>, value: &T) -> Result<()> {
+    serde_json::to_writer(buffer, value).map_err(|e| {
+        error::InternalSnafu {
+            err_msg: e.to_string(),
+        }
+        .build()
+    })
+}
+
+/// Encodes the record batches in the Arrow IPC streaming format.
+fn encode_arrow(mut stream: SendableRecordBatchStream) -> BoxStream<'static, Result<Bytes>> {
+    let stream = async_stream::try_stream! {
+        let buffer = SharedBuffer::default();
+        let mut writer = StreamWriter::try_new(buffer.clone(), stream.schema().arrow_schema())
+            .context(error::EncodeHttpOutputSnafu { format: "arrow" })?;
+        yield buffer.take();
+
+        while let Some(batch) = stream.next().await {
+            let batch = batch.context(error::CollectRecordbatchSnafu)?;
+            writer
+                .write(batch.df_record_batch())
+                .context(error::EncodeHttpOutputSnafu { format: "arrow" })?;
+            yield buffer.take();
+        }
+
+        writer
+            .finish()
+            .context(error::EncodeHttpOutputSnafu { format: "arrow" })?;
+        yield buffer.take();
+    };
+    Box::pin(stream)
+}
+
+/// A buffer shared with the [StreamWriter], to take the encoded messages after every write.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Bytes {
+        std::mem::take(&mut *self.0.lock()).into()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use common_recordbatch::{RecordBatch, RecordBatches};
+    use datatypes::arrow::ipc::reader::StreamReader;
+    use datatypes::prelude::ConcreteDataType;
+    use datatypes::schema::{ColumnSchema, Schema};
+    use datatypes::vectors::{Float64Vector, StringVector};
+
+    use super::*;
+
+    fn recordbatches() -> RecordBatches {
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new("host", ConcreteDataType::string_datatype(), true),
+            ColumnSchema::new("cpu", ConcreteDataType::float64_datatype(), true),
+        ]));
+        let batch1 = RecordBatch::new(
+            schema.clone(),
+            vec![
+                Arc::new(StringVector::from(vec!["host1", "host,2"])) as _,
+                Arc::new(Float64Vector::from(vec![Some(1.5), None])) as _,
+            ],
+        )
+        .unwrap();
+        let batch2 = RecordBatch::new(
+            schema.clone(),
+            vec![
+                Arc::new(StringVector::from(vec!["host3"])) as _,
+                Arc::new(Float64Vector::from_slice([3.0])) as _,
+            ],
+        )
+        .unwrap();
+        RecordBatches::try_new(schema, vec![batch1, batch2]).unwrap()
+    }
+
+    async fn encode(format: ResponseFormat, recordbatches: &RecordBatches) -> Vec<u8> {
+        let chunks = encode_stream(format, recordbatches.as_stream())
+            .collect::<Vec<_>>()
+            .await;
+        chunks.into_iter().flat_map(|c| c.unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_encode_csv() {
+        let recordbatches = recordbatches();
+        let csv = encode(ResponseFormat::Csv, &recordbatches).await;
+        assert_eq!(
+            "host,cpu\nhost1,1.5\n\"host,2\",\nhost3,3.0\n",
+            String::from_utf8(csv).unwrap()
+        );
+
+        let tsv = encode(ResponseFormat::Tsv, &recordbatches).await;
+        assert_eq!(
+            "host\tcpu\nhost1\t1.5\nhost,2\t\nhost3\t3.0\n",
+            String::from_utf8(tsv).unwrap()
+        );
+
+        let empty = RecordBatches::try_new(recordbatches.schema(), vec![]).unwrap();
+        let csv = encode(ResponseFormat::Csv, &empty).await;
+        assert_eq!("host,cpu\n", String::from_utf8(csv).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_encode_ndjson() {
+        let ndjson = encode(ResponseFormat::Ndjson, &recordbatches()).await;
+        assert_eq!(
+            r#"{"host":"host1","cpu":1.5}
+{"host":"host,2","cpu":null}
+{"host":"host3","cpu":3.0}
+"#,
+            String::from_utf8(ndjson).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_arrow() {
+        let recordbatches = recordbatches();
+        let arrow = encode(ResponseFormat::Arrow, &recordbatches).await;
+
+        let reader = StreamReader::try_new(Cursor::new(arrow), None).unwrap();
+        let batches = reader.collect::<std::result::Result<Vec<_>, _>>().unwrap();
+        let expected = recordbatches
+            .take()
+            .into_iter()
+            .map(|batch| batch.into_df_record_batch())
+            .collect::<Vec<_>>();
+        assert_eq!(expected, batches);
+    }
+}
@@ -0,0 +1,1235 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A subset of InfluxQL, which is translated into SQL against the tables written by the line
+//! protocol. The results are encoded as the JSON of InfluxDB's `/query` endpoint.
+
+mod parser;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use common_grpc::writer::Precision;
+use common_recordbatch::RecordBatch;
+use datatypes::value::Value;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use session::context::{QueryContext, QueryContextRef, UserInfo};
+use snafu::{ensure, OptionExt};
+
+pub use self::parser::{
+    parse, BinaryOp, Expr, Field, Fill, GroupBy, Measurement, SelectStatement, Statement, TagKeys,
+};
+use crate::error::{InvalidInfluxqlSnafu, Result};
+use crate::parse_catalog_and_schema_from_client_database_name;
+use crate::query_handler::sql::{
+    describe_table, query_recordbatches, query_strings, quote_ident, quote_string, value_to_string,
+    ServerSqlQueryHandlerRef, TableColumns,
+};
+
+/// The maximum number of points in a series filled by `fill()`.
+const MAX_FILLED_POINTS: i64 = 100_000;
+
+const TIME_COLUMN: &str = "time";
+
+/// The response of `/influxdb/query`.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct InfluxqlResponse {
+    pub results: Vec<StatementResult>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StatementResult {
+    pub statement_id: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub series: Vec<Series>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Series {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    pub columns: Vec<String>,
+    pub values: Vec<Vec<JsonValue>>,
+}
+
+impl Series {
+    fn new(name: &str, columns: &[&str], values: Vec<Vec<JsonValue>>) -> Self {
+        Self {
+            name: name.to_string(),
+            tags: BTreeMap::new(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            values,
+        }
+    }
+}
+
+/// Executes the InfluxQL `query` against the database `db`. The times in the results are
+/// integers in the `epoch` precision, or RFC3339 strings if it's absent.
+///
+/// Returns an error if the query can't be parsed, while the errors of executing each
+/// statement are reported in the statement's result, like InfluxDB does.
+pub async fn execute(
+    handler: &ServerSqlQueryHandlerRef,
+    query: &str,
+    db: &str,
+    epoch: Option<Precision>,
+    user_info: UserInfo,
+) -> Result<InfluxqlResponse> {
+    let statements = parse(query)?;
+    let executor = Executor {
+        handler,
+        db,
+        epoch,
+        user_info,
+        now: Utc::now().timestamp_nanos(),
+    };
+
+    let mut results = Vec::with_capacity(statements.len());
+    for (statement_id, statement) in statements.into_iter().enumerate() {
+        let result = match executor.execute_statement(statement).await {
+            Ok(series) => StatementResult {
+                statement_id,
+                series,
+                error: None,
+            },
+            Err(e) => StatementResult {
+                statement_id,
+                series: vec![],
+                error: Some(e.to_string()),
+            },
+        };
+        results.push(result);
+    }
+    Ok(InfluxqlResponse { results })
+}
+
+struct Executor<'a> {
+    handler: &'a ServerSqlQueryHandlerRef,
+    db: &'a str,
+    epoch: Option<Precision>,
+    /// The user executing the statements.
+    user_info: UserInfo,
+    /// The value of `now()`, in nanoseconds.
+    now: i64,
+}
+
+impl<'a> Executor<'a> {
+    async fn execute_statement(&self, statement: Statement) -> Result<Vec<Series>> {
+        match statement {
+            Statement::Select(select) => self.select(*select).await,
+            Statement::ShowDatabases => {
+                let names = self
+                    .query_strings("SHOW DATABASES", self.query_context(None))
+                    .await?;
+                Ok(single_column_series("databases", "name", names))
+            }
+            Statement::ShowRetentionPolicies { .. } => {
+                // Retention policies are ignored, so every database has the default one only.
+                Ok(vec![Series::new(
+                    "",
+                    &[
+                        "name",
+                        "duration",
+                        "shardGroupDuration",
+                        "replicaN",
+                        "default",
+                    ],
+                    vec![vec![
+                        "autogen".into(),
+                        "0s".into(),
+                        "168h0m0s".into(),
+                        1.into(),
+                        true.into(),
+                    ]],
+                )])
+            }
+            Statement::ShowMeasurements {
+                database,
+                filter,
+                limit,
+                offset,
+            } => {
+                let ctx = self.query_context(database.as_deref());
+                let mut names = self.measurements(ctx).await?;
+                match filter {
+                    Some(Expr::Regex(regex)) => {
+                        let regex = compile_regex(&regex)?;
+                        names.retain(|name| regex.is_match(name));
+                    }
+                    Some(Expr::String(name)) => names.retain(|n| *n == name),
+                    _ => {}
+                }
+                let names = names
+                    .into_iter()
+                    .skip(offset.unwrap_or(0))
+                    .take(limit.unwrap_or(usize::MAX))
+                    .collect();
+                Ok(single_column_series("measurements", "name", names))
+            }
+            Statement::ShowTagKeys { database, from } => {
+                let database = database.or_else(|| from.as_ref()?.database.clone());
+                let ctx = self.query_context(database.as_deref());
+                let mut series = Vec::new();
+                for measurement in self.measurements_from(from, ctx.clone()).await? {
+                    if let Some(schema) = self.describe(&measurement, ctx.clone()).await? {
+                        let mut tags = schema.tags;
+                        tags.sort();
+                        series.extend(single_column_series(&measurement, "tagKey", tags));
+                    }
+                }
+                Ok(series)
+            }
+            Statement::ShowTagValues {
+                database,
+                from,
+                keys,
+                condition,
+                limit,
+            } => {
+                let database = database.or_else(|| from.as_ref()?.database.clone());
+                let ctx = self.query_context(database.as_deref());
+                let mut series = Vec::new();
+                for measurement in self.measurements_from(from, ctx.clone()).await? {
+                    let values = self
+                        .tag_values(&measurement, &keys, condition.as_ref(), ctx.clone())
+                        .await?;
+                    let values = values
+                        .into_iter()
+                        .take(limit.unwrap_or(usize::MAX))
+                        .map(|(key, value)| vec![key.into(), value.into()])
+                        .collect::<Vec<Vec<JsonValue>>>();
+                    if !values.is_empty() {
+                        series.push(Series::new(&measurement, &["key", "value"], values));
+                    }
+                }
+                Ok(series)
+            }
+            Statement::ShowFieldKeys { database, from } => {
+                let database = database.or_else(|| from.as_ref()?.database.clone());
+                let ctx = self.query_context(database.as_deref());
+                let mut series = Vec::new();
+                for measurement in self.measurements_from(from, ctx.clone()).await? {
+                    let Some(schema) = self.describe(&measurement, ctx.clone()).await? else {
+                        continue;
+                    };
+                    let mut fields = schema.fields;
+                    fields.sort();
+                    let values = fields
+                        .into_iter()
+                        .map(|(name, data_type)| vec![name.into(), field_type(&data_type).into()])
+                        .collect::<Vec<Vec<JsonValue>>>();
+                    if !values.is_empty() {
+                        series.push(Series::new(
+                            &measurement,
+                            &["fieldKey", "fieldType"],
+                            values,
+                        ));
+                    }
+                }
+                Ok(series)
+            }
+        }
+    }
+
+    async fn select(&self, select: SelectStatement) -> Result<Vec<Series>> {
+        let ctx = self.query_context(select.from.database.as_deref());
+        let Some(schema) = self.describe(&select.from.name, ctx.clone()).await? else {
+            return Ok(vec![]);
+        };
+        let translator = Translator {
+            schema: &schema,
+            now: self.now,
+        };
+        let factor = schema.time_unit.factor() as i64;
+
+        let group_tags = if select.group_by.all_tags {
+            schema.tags.clone()
+        } else {
+            select
+                .group_by
+                .tags
+                .iter()
+                .filter(|tag| schema.tags.contains(tag))
+                .cloned()
+                .collect()
+        };
+        let projections = translator.projections(&select.fields, &group_tags)?;
+        let aggregate = projections.iter().any(|p| p.aggregate);
+        ensure!(
+            projections.iter().all(|p| p.aggregate == aggregate),
+            InvalidInfluxqlSnafu {
+                reason: "mixing aggregate and non-aggregate queries is not supported",
+            }
+        );
+        ensure!(
+            aggregate || select.group_by.time.is_none(),
+            InvalidInfluxqlSnafu {
+                reason: "GROUP BY requires at least one aggregate function",
+            }
+        );
+
+        let (lower, upper) = match &select.condition {
+            Some(condition) => translator.time_range(condition)?,
+            None => (None, None),
+        };
+
+        // The interval and the offset of the buckets, in the unit of the time index.
+        let buckets = select.group_by.time.map(|(interval, offset)| {
+            let step = (interval / factor).max(1);
+            (step, (offset / factor).rem_euclid(step))
+        });
+        let time_index = quote_ident(&schema.time_index);
+        let time_expr = match buckets {
+            Some((step, 0)) => Some(format!("CAST({time_index} AS BIGINT) / {step} * {step}")),
+            Some((step, offset)) => Some(format!(
+                "(CAST({time_index} AS BIGINT) - {offset}) / {step} * {step} + {offset}"
+            )),
+            None if aggregate => None,
+            None => Some(time_index.clone()),
+        };
+
+        let mut items = time_expr.iter().cloned().collect::<Vec<_>>();
+        items.extend(group_tags.iter().map(|tag| quote_ident(tag)));
+        items.extend(projections.iter().map(|p| p.sql.clone()));
+        let mut sql = format!(
+            "SELECT {} FROM {}",
+            items.join(", "),
+            quote_ident(&select.from.name)
+        );
+        if let Some(condition) = &select.condition {
+            sql.push_str(&format!(" WHERE {}", translator.expr_to_sql(condition)?));
+        }
+        if aggregate {
+            let mut keys = time_expr.iter().cloned().collect::<Vec<_>>();
+            keys.extend(group_tags.iter().map(|tag| quote_ident(tag)));
+            if !keys.is_empty() {
+                sql.push_str(&format!(" GROUP BY {}", keys.join(", ")));
+            }
+        }
+        let mut order_by = group_tags
+            .iter()
+            .map(|tag| quote_ident(tag))
+            .collect::<Vec<_>>();
+        order_by.extend(time_expr.clone());
+        if !order_by.is_empty() {
+            sql.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+        }
+        // The limits of raw queries without series are pushed down, rather than applied to
+        // each series later.
+        let push_down_limit = !aggregate && group_tags.is_empty();
+        if push_down_limit {
+            if select.descending {
+                sql.push_str(" DESC");
+            }
+            if let Some(limit) = select.limit {
+                sql.push_str(&format!(" LIMIT {limit}"));
+            }
+            if let Some(offset) = select.offset {
+                sql.push_str(&format!(" OFFSET {offset}"));
+            }
+        }
+
+        let batches = self.query(&sql, ctx).await?;
+        let tags_start = usize::from(time_expr.is_some());
+        let values_start = tags_start + group_tags.len();
+        let mut series: BTreeMap<Vec<String>, Vec<(i64, Vec<JsonValue>)>> = BTreeMap::new();
+        for batch in &batches {
+            let columns = batch.columns();
+            for row in 0..batch.num_rows() {
+                let time = if time_expr.is_some() {
+                    match columns[0].get(row) {
+                        Value::Timestamp(ts) => ts.value() * ts.unit().factor() as i64,
+                        Value::Int64(v) => v * factor,
+                        _ => continue,
+                    }
+                } else {
+                    lower.unwrap_or(0)
+                };
+                let tags = columns[tags_start..values_start]
+                    .iter()
+                    .map(|column| value_to_string(column.get(row)).unwrap_or_default())
+                    .collect::<Vec<_>>();
+                let values = columns[values_start..]
+                    .iter()
+                    .map(|column| to_json(column.get(row)))
+                    .collect::<Vec<_>>();
+                // An aggregate over nothing is null, which is dropped like InfluxDB does.
+                if aggregate && buckets.is_none() && values.iter().all(JsonValue::is_null) {
+                    continue;
+                }
+                series.entry(tags).or_default().push((time, values));
+            }
+        }
+
+        let mut columns = vec![TIME_COLUMN.to_string()];
+        columns.extend(projections.into_iter().map(|p| p.name));
+        let mut result = Vec::with_capacity(series.len());
+        for (tags, mut points) in series {
+            if let Some((step, offset)) = buckets {
+                let (interval, offset) = (step * factor, offset * factor);
+                let align = |t: i64| {
+                    t.checked_sub(offset)
+                        .and_then(|t| t.div_euclid(interval).checked_mul(interval))
+                        .and_then(|t| t.checked_add(offset))
+                        .context(InvalidInfluxqlSnafu {
+                            reason: "time out of range",
+                        })
+                };
+                let start = align(lower.unwrap_or(points[0].0))?;
+                let end = align(upper.unwrap_or(self.now).max(points[points.len() - 1].0))?;
+                points = fill_points(points, start, end, interval, &select.fill)?;
+            }
+            if select.descending && !push_down_limit {
+                points.reverse();
+            }
+            if !push_down_limit {
+                points = points
+                    .into_iter()
+                    .skip(select.offset.unwrap_or(0))
+                    .take(select.limit.unwrap_or(usize::MAX))
+                    .collect();
+            }
+            if points.is_empty() {
+                continue;
+            }
+
+            let values = points
+                .into_iter()
+                .map(|(time, values)| {
+                    let mut row = Vec::with_capacity(values.len() + 1);
+                    row.push(self.format_time(time));
+                    row.extend(values);
+                    row
+                })
+                .collect();
+            result.push(Series {
+                name: select.from.name.clone(),
+                tags: group_tags.iter().cloned().zip(tags).collect(),
+                columns: columns.clone(),
+                values,
+            });
+        }
+        Ok(result
+            .into_iter()
+            .skip(select.soffset.unwrap_or(0))
+            .take(select.slimit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Returns the sorted `(key, value)` pairs of the tags matching `keys`.
+    async fn tag_values(
+        &self,
+        measurement: &str,
+        keys: &TagKeys,
+        condition: Option<&Expr>,
+        ctx: QueryContextRef,
+    ) -> Result<Vec<(String, String)>> {
+        let Some(schema) = self.describe(measurement, ctx.clone()).await? else {
+            return Ok(vec![]);
+        };
+        let mut tags: Vec<&String> = match keys {
+            TagKeys::Eq(key) => schema.tags.iter().filter(|tag| *tag == key).collect(),
+            TagKeys::In(keys) => schema
+                .tags
+                .iter()
+                .filter(|tag| keys.contains(tag))
+                .collect(),
+            TagKeys::Regex(regex) => {
+                let regex = compile_regex(regex)?;
+                schema
+                    .tags
+                    .iter()
+                    .filter(|tag| regex.is_match(tag))
+                    .collect()
+            }
+        };
+        tags.sort();
+
+        let translator = Translator {
+            schema: &schema,
+            now: self.now,
+        };
+        let condition = condition
+            .map(|condition| translator.expr_to_sql(condition))
+            .transpose()?
+            .map(|condition| format!(" WHERE {condition}"))
+            .unwrap_or_default();
+        let mut values = Vec::new();
+        for tag in tags {
+            let column = quote_ident(tag);
+            let sql = format!(
+                "SELECT DISTINCT {column} FROM {}{condition} ORDER BY {column}",
+                quote_ident(measurement)
+            );
+            for value in self.query_strings(&sql, ctx.clone()).await? {
+                values.push((tag.clone(), value));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Returns the measurement in `from`, or all measurements if it's absent.
+    async fn measurements_from(
+        &self,
+        from: Option<Measurement>,
+        ctx: QueryContextRef,
+    ) -> Result<Vec<String>> {
+        match from {
+            Some(measurement) => Ok(vec![measurement.name]),
+            None => self.measurements(ctx).await,
+        }
+    }
+
+    async fn measurements(&self, ctx: QueryContextRef) -> Result<Vec<String>> {
+        let mut names = self.query_strings("SHOW TABLES", ctx).await?;
+        names.sort();
+        Ok(names)
+    }
+
+    async fn describe(
+        &self,
+        measurement: &str,
+        ctx: QueryContextRef,
+    ) -> Result<Option<TableColumns>> {
+        describe_table(self.handler, measurement, ctx).await
+    }
+
+    async fn query_strings(&self, sql: &str, ctx: QueryContextRef) -> Result<Vec<String>> {
+        query_strings(self.handler, sql, ctx).await
+    }
+
+    async fn query(&self, sql: &str, ctx: QueryContextRef) -> Result<Vec<RecordBatch>> {
+        query_recordbatches(self.handler, sql, ctx).await
+    }
+
+    fn query_context(&self, database: Option<&str>) -> QueryContextRef {
+        let (catalog, schema) =
+            parse_catalog_and_schema_from_client_database_name(database.unwrap_or(self.db));
+        let ctx = QueryContext::with(catalog, schema);
+        ctx.set_current_user(self.user_info.clone());
+        Arc::new(ctx)
+    }
+
+    fn format_time(&self, nanos: i64) -> JsonValue {
+        match self.epoch {
+            Some(epoch) => (nanos / precision_nanos(epoch)).into(),
+            None => Utc
+                .timestamp_nanos(nanos)
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                .into(),
+        }
+    }
+}
+
+/// A column in the results of `SELECT`.
+#[derive(Debug)]
+struct Projection {
+    name: String,
+    sql: String,
+    aggregate: bool,
+}
+
+/// Translates the InfluxQL expressions into SQL against a measurement.
+struct Translator<'a> {
+    schema: &'a TableColumns,
+    now: i64,
+}
+
+impl<'a> Translator<'a> {
+    /// Translates the fields of `SELECT`, expanding the wildcards and naming the columns like
+    /// InfluxDB does.
+    fn projections(&self, fields: &[Field], group_tags: &[String]) -> Result<Vec<Projection>> {
+        let mut projections = Vec::with_capacity(fields.len());
+        for field in fields {
+            match &field.expr {
+                // All fields and tags that are not grouped, in alphabetical order.
+                Expr::Wildcard => {
+                    let mut columns = self
+                        .schema
+                        .fields
+                        .iter()
+                        .map(|(name, _)| name)
+                        .chain(self.schema.tags.iter())
+                        .filter(|name| !group_tags.contains(name))
+                        .collect::<Vec<_>>();
+                    columns.sort();
+                    projections.extend(columns.into_iter().map(|name| Projection {
+                        name: name.clone(),
+                        sql: quote_ident(name),
+                        aggregate: false,
+                    }));
+                }
+                // The function applied to every field, like `mean(*)`.
+                Expr::Call { name, args } if args.first() == Some(&Expr::Wildcard) => {
+                    for (field, _) in &self.schema.fields {
+                        let mut args = args.clone();
+                        args[0] = Expr::Ident(field.clone());
+                        let expr = Expr::Call {
+                            name: name.clone(),
+                            args,
+                        };
+                        projections.push(Projection {
+                            name: format!("{name}_{field}"),
+                            sql: self.expr_to_sql(&expr)?,
+                            aggregate: is_aggregate(&expr),
+                        });
+                    }
+                }
+                expr => projections.push(Projection {
+                    name: field.alias.clone().unwrap_or_else(|| column_name(expr)),
+                    sql: self.expr_to_sql(expr)?,
+                    aggregate: is_aggregate(expr),
+                }),
+            }
+        }
+        ensure!(
+            !projections.is_empty(),
+            InvalidInfluxqlSnafu {
+                reason: "at least one non-time field must be queried",
+            }
+        );
+
+        // The duplicated names are suffixed by numbers, like `mean` and `mean_1`.
+        let mut names: BTreeMap<String, usize> = BTreeMap::new();
+        for projection in &mut projections {
+            let count = names.entry(projection.name.clone()).or_default();
+            if *count > 0 {
+                projection.name = format!("{}_{count}", projection.name);
+            }
+            *count += 1;
+        }
+        Ok(projections)
+    }
+
+    fn expr_to_sql(&self, expr: &Expr) -> Result<String> {
+        let sql = match expr {
+            Expr::Ident(name) if is_time(name) => quote_ident(&self.schema.time_index),
+            Expr::Ident(name) => quote_ident(name),
+            Expr::String(s) | Expr::Regex(s) => quote_string(s),
+            Expr::Integer(i) => i.to_string(),
+            Expr::Float(f) => format!("{f:?}"),
+            Expr::Boolean(b) => b.to_string(),
+            Expr::Duration(d) => d.to_string(),
+            Expr::Wildcard => {
+                return InvalidInfluxqlSnafu {
+                    reason: "wildcards are only supported in the fields and functions",
+                }
+                .fail()
+            }
+            Expr::Call { name, args } => self.call_to_sql(name, args)?,
+            Expr::Binary { left, op, right } => {
+                if let Some((op, other)) = time_comparison(left, *op, right) {
+                    let time = self.eval_time(other)?;
+                    let time = time.div_euclid(self.schema.time_unit.factor() as i64);
+                    let time_index = quote_ident(&self.schema.time_index);
+                    return Ok(format!("({time_index} {} {time})", op.as_sql()));
+                }
+                if matches!(op, BinaryOp::RegexMatch | BinaryOp::RegexNotMatch) {
+                    ensure!(
+                        matches!(**right, Expr::Regex(_)),
+                        InvalidInfluxqlSnafu {
+                            reason: format!("expected a regex after {}", op.as_sql()),
+                        }
+                    );
+                }
+                format!(
+                    "({} {} {})",
+                    self.expr_to_sql(left)?,
+                    op.as_sql(),
+                    self.expr_to_sql(right)?
+                )
+            }
+        };
+        Ok(sql)
+    }
+
+    fn call_to_sql(&self, name: &str, args: &[Expr]) -> Result<String> {
+        let arg = |expected: usize| -> Result<String> {
+            ensure!(
+                args.len() == expected,
+                InvalidInfluxqlSnafu {
+                    reason: format!("invalid number of arguments for {name}, expected {expected}"),
+                }
+            );
+            self.expr_to_sql(&args[0])
+        };
+
+        let sql = match name {
+            "mean" => format!("avg({})", arg(1)?),
+            "median" | "sum" | "min" | "max" | "stddev" => format!("{name}({})", arg(1)?),
+            "count" => match args {
+                [Expr::Call { name, args }] if name == "distinct" && args.len() == 1 => {
+                    format!("count(DISTINCT {})", self.expr_to_sql(&args[0])?)
+                }
+                _ => format!("count({})", arg(1)?),
+            },
+            "spread" => {
+                let arg = arg(1)?;
+                format!("(max({arg}) - min({arg}))")
+            }
+            "percentile" => {
+                let arg = arg(2)?;
+                let percentile = match &args[1] {
+                    Expr::Integer(i) => *i as f64,
+                    Expr::Float(f) => *f,
+                    _ => {
+                        return InvalidInfluxqlSnafu {
+                            reason: "the percentile must be a number",
+                        }
+                        .fail()
+                    }
+                };
+                ensure!(
+                    (0.0..=100.0).contains(&percentile),
+                    InvalidInfluxqlSnafu {
+                        reason: format!("invalid percentile: {percentile}"),
+                    }
+                );
+                format!(
+                    "approx_percentile_cont(CAST({arg} AS DOUBLE), {:?})",
+                    percentile / 100.0
+                )
+            }
+            // The math functions are shared by SQL.
+            "abs" | "acos" | "asin" | "atan" | "ceil" | "cos" | "exp" | "floor" | "ln" | "log2"
+            | "log10" | "round" | "sin" | "sqrt" | "tan" => format!("{name}({})", arg(1)?),
+            "atan2" | "pow" => {
+                let arg = arg(2)?;
+                format!("{name}({arg}, {})", self.expr_to_sql(&args[1])?)
+            }
+            // The base is the second argument in InfluxQL, but the first one in SQL.
+            "log" => {
+                let arg = arg(2)?;
+                format!("log({}, {arg})", self.expr_to_sql(&args[1])?)
+            }
+            _ => {
+                return InvalidInfluxqlSnafu {
+                    reason: format!("unsupported function: {name}()"),
+                }
+                .fail()
+            }
+        };
+        Ok(sql)
+    }
+
+    /// Returns the inclusive bounds of time in the condition, in nanoseconds.
+    fn time_range(&self, condition: &Expr) -> Result<(Option<i64>, Option<i64>)> {
+        let Expr::Binary { left, op, right } = condition else {
+            return Ok((None, None));
+        };
+        if *op == BinaryOp::And {
+            let (left_lower, left_upper) = self.time_range(left)?;
+            let (right_lower, right_upper) = self.time_range(right)?;
+            let lower = left_lower.max(right_lower);
+            let upper = match (left_upper, right_upper) {
+                (Some(l), Some(r)) => Some(l.min(r)),
+                (l, r) => l.or(r),
+            };
+            return Ok((lower, upper));
+        }
+
+        let Some((op, other)) = time_comparison(left, *op, right) else {
+            return Ok((None, None));
+        };
+        let time = self.eval_time(other)?;
+        let range = match op {
+            BinaryOp::Gt => (Some(time.saturating_add(1)), None),
+            BinaryOp::GtEq => (Some(time), None),
+            BinaryOp::Lt => (None, Some(time.saturating_sub(1))),
+            BinaryOp::LtEq => (None, Some(time)),
+            BinaryOp::Eq => (Some(time), Some(time)),
+            _ => (None, None),
+        };
+        Ok(range)
+    }
+
+    /// Evaluates a time expression like `now() - 1h` into nanoseconds.
+    fn eval_time(&self, expr: &Expr) -> Result<i64> {
+        let nanos = match expr {
+            Expr::Integer(i) | Expr::Duration(i) => *i,
+            Expr::Float(f) => *f as i64,
+            Expr::String(s) => parse_time(s)?,
+            Expr::Call { name, args } if name == "now" && args.is_empty() => self.now,
+            Expr::Binary {
+                left,
+                op: BinaryOp::Add,
+                right,
+            } => self.eval_time(left)?.saturating_add(self.eval_time(right)?),
+            Expr::Binary {
+                left,
+                op: BinaryOp::Sub,
+                right,
+            } => self.eval_time(left)?.saturating_sub(self.eval_time(right)?),
+            expr => {
+                return InvalidInfluxqlSnafu {
+                    reason: format!("invalid time expression: {expr:?}"),
+                }
+                .fail()
+            }
+        };
+        Ok(nanos)
+    }
+}
+
+/// Returns the comparison and the other operand if either operand is `time`.
+fn time_comparison<'e>(
+    left: &'e Expr,
+    op: BinaryOp,
+    right: &'e Expr,
+) -> Option<(BinaryOp, &'e Expr)> {
+    if !op.is_comparison() {
+        return None;
+    }
+    match (left, right) {
+        (Expr::Ident(name), other) if is_time(name) => Some((op, other)),
+        (other, Expr::Ident(name)) if is_time(name) => Some((op.flip(), other)),
+        _ => None,
+    }
+}
+
+fn is_time(name: &str) -> bool {
+    name.eq_ignore_ascii_case(TIME_COLUMN)
+}
+
+fn is_aggregate(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call { name, args } => {
+            matches!(
+                name.as_str(),
+                "mean"
+                    | "median"
+                    | "sum"
+                    | "count"
+                    | "min"
+                    | "max"
+                    | "stddev"
+                    | "spread"
+                    | "percentile"
+            ) || args.iter().any(is_aggregate)
+        }
+        Expr::Binary { left, right, .. } => is_aggregate(left) || is_aggregate(right),
+        _ => false,
+    }
+}
+
+/// Returns the default name of a field, which is the name of the first function or variable.
+fn column_name(expr: &Expr) -> String {
+    fn find_name(expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Call { name, .. } => Some(name.clone()),
+            Expr::Ident(name) => Some(name.clone()),
+            Expr::Binary { left, right, .. } => find_name(left).or_else(|| find_name(right)),
+            _ => None,
+        }
+    }
+    find_name(expr).unwrap_or_else(|| "value".to_string())
+}
+
+/// Inserts the missing buckets between `start` and `end` into the points, which are sorted by
+/// time, and fills their values.
+fn fill_points(
+    points: Vec<(i64, Vec<JsonValue>)>,
+    start: i64,
+    end: i64,
+    interval: i64,
+    fill: &Fill,
+) -> Result<Vec<(i64, Vec<JsonValue>)>> {
+    if *fill == Fill::None {
+        return Ok(points);
+    }
+    ensure!(
+        start > end
+            || end
+                .checked_sub(start)
+                .map_or(false, |range| range / interval < MAX_FILLED_POINTS),
+        InvalidInfluxqlSnafu {
+            reason: "too many points in the GROUP BY interval, maybe the time range is missing",
+        }
+    );
+
+    let width = points.first().map_or(0, |(_, values)| values.len());
+    let fill_value = match fill {
+        Fill::Value(v) if v.fract() == 0.0 => JsonValue::from(*v as i64),
+        Fill::Value(v) => JsonValue::from(*v),
+        _ => JsonValue::Null,
+    };
+    // The points with whether they are filled.
+    let mut buckets: BTreeMap<i64, (Vec<JsonValue>, bool)> = points
+        .into_iter()
+        .map(|(time, values)| (time, (values, false)))
+        .collect();
+    let mut time = Some(start);
+    while let Some(t) = time.filter(|t| *t <= end) {
+        let _ = buckets
+            .entry(t)
+            .or_insert_with(|| (vec![fill_value.clone(); width], true));
+        time = t.checked_add(interval);
+    }
+    let mut points = buckets
+        .into_iter()
+        .map(|(time, (values, filled))| (time, values, filled))
+        .collect::<Vec<_>>();
+
+    match fill {
+        Fill::Previous => {
+            let mut previous = vec![JsonValue::Null; width];
+            for (_, values, filled) in &mut points {
+                if *filled {
+                    values.clone_from(&previous);
+                } else {
+                    for (last, value) in previous.iter_mut().zip(values.iter()) {
+                        if !value.is_null() {
+                            *last = value.clone();
+                        }
+                    }
+                }
+            }
+        }
+        Fill::Linear => {
+            for column in 0..width {
+                let known = points
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, _, filled))| !filled)
+                    .filter_map(|(i, (time, values, _))| Some((i, *time, values[column].as_f64()?)))
+                    .collect::<Vec<_>>();
+                for pair in known.windows(2) {
+                    let (i, start_time, start_value) = pair[0];
+                    let (j, end_time, end_value) = pair[1];
+                    for (time, values, filled) in &mut points[i + 1..j] {
+                        if *filled {
+                            let ratio =
+                                (*time - start_time) as f64 / (end_time - start_time) as f64;
+                            values[column] =
+                                JsonValue::from(start_value + (end_value - start_value) * ratio);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(points
+        .into_iter()
+        .map(|(time, values, _)| (time, values))
+        .collect())
+}
+
+fn single_column_series(name: &str, column: &str, values: Vec<String>) -> Vec<Series> {
+    if values.is_empty() {
+        return vec![];
+    }
+    let values = values.into_iter().map(|v| vec![v.into()]).collect();
+    vec![Series::new(name, &[column], values)]
+}
+
+/// Returns the type of a field in InfluxDB by the name of its data type.
+fn field_type(data_type: &str) -> &'static str {
+    match data_type {
+        "Float32" | "Float64" => "float",
+        "Int8" | "Int16" | "Int32" | "Int64" => "integer",
+        "UInt8" | "UInt16" | "UInt32" | "UInt64" => "unsigned",
+        "Boolean" => "boolean",
+        _ => "string",
+    }
+}
+
+fn precision_nanos(precision: Precision) -> i64 {
+    match precision {
+        Precision::Nanosecond => 1,
+        Precision::Microsecond => 1_000,
+        Precision::Millisecond => 1_000_000,
+        Precision::Second => 1_000_000_000,
+        Precision::Minute => 60_000_000_000,
+        Precision::Hour => 3_600_000_000_000,
+    }
+}
+
+/// Parses a time string in RFC3339, or like `2023-01-01 00:00:00` and `2023-01-01` in UTC.
+fn parse_time(s: &str) -> Result<i64> {
+    let datetime = DateTime::parse_from_rfc3339(s)
+        .map(|datetime| datetime.naive_utc())
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+        .with_context(|| InvalidInfluxqlSnafu {
+            reason: format!("invalid time: {s}"),
+        })?;
+    Ok(datetime.timestamp_nanos())
+}
+
+fn compile_regex(regex: &str) -> Result<Regex> {
+    Regex::new(regex)
+        .ok()
+        .with_context(|| InvalidInfluxqlSnafu {
+            reason: format!("invalid regex: {regex}"),
+        })
+}
+
+fn to_json(value: Value) -> JsonValue {
+    match value {
+        Value::Float32(v) if !v.0.is_finite() => JsonValue::Null,
+        Value::Float64(v) if !v.0.is_finite() => JsonValue::Null,
+        value => JsonValue::try_from(value).unwrap_or(JsonValue::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_time::timestamp::TimeUnit;
+
+    use super::*;
+
+    fn schema() -> TableColumns {
+        TableColumns {
+            tags: vec!["host".to_string(), "region".to_string()],
+            fields: vec![
+                ("value".to_string(), "Float64".to_string()),
+                ("count".to_string(), "Int64".to_string()),
+            ],
+            time_index: "ts".to_string(),
+            time_unit: TimeUnit::Millisecond,
+        }
+    }
+
+    fn parse_select(query: &str) -> SelectStatement {
+        match parse(query).unwrap().remove(0) {
+            Statement::Select(select) => *select,
+            statement => panic!("unexpected statement: {statement:?}"),
+        }
+    }
+
+    #[test]
+    fn test_translate_condition() {
+        let schema = schema();
+        let translator = Translator {
+            schema: &schema,
+            now: 3_600_000_000_000,
+        };
+        let select = parse_select(
+            "SELECT value FROM cpu WHERE host =~ /^a'b/ AND time >= now() - 30m AND time < '1970-01-01T01:00:00Z' OR value <> 1.5",
+        );
+        let condition = select.condition.unwrap();
+        assert_eq!(
+            r#"(((("host" ~ '^a''b') AND ("ts" >= 1800000)) AND ("ts" < 3600000)) OR ("value" <> 1.5))"#,
+            translator.expr_to_sql(&condition).unwrap()
+        );
+
+        let select = parse_select(
+            "SELECT value FROM cpu WHERE time >= 1000ms AND 2000000000 >= time AND host = 'a'",
+        );
+        let condition = select.condition.unwrap();
+        assert_eq!(
+            r#"((("ts" >= 1000) AND ("ts" <= 2000)) AND ("host" = 'a'))"#,
+            translator.expr_to_sql(&condition).unwrap()
+        );
+        assert_eq!(
+            (Some(1_000_000_000), Some(2_000_000_000)),
+            translator.time_range(&condition).unwrap()
+        );
+
+        let select = parse_select(
+            "SELECT value FROM cpu WHERE time > now() + 9223372036854775807 AND time < now() - 9223372036854775807 - 9223372036854775807",
+        );
+        let condition = select.condition.unwrap();
+        assert_eq!(
+            (Some(i64::MAX), Some(i64::MIN)),
+            translator.time_range(&condition).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_projections() {
+        let schema = schema();
+        let translator = Translator {
+            schema: &schema,
+            now: 0,
+        };
+
+        let select = parse_select(
+            "SELECT mean(value), mean(\"value\") * 2, spread(value) AS s, percentile(value, 95) FROM cpu",
+        );
+        let projections = translator.projections(&select.fields, &[]).unwrap();
+        let projections = projections
+            .iter()
+            .map(|p| (p.name.as_str(), p.sql.as_str(), p.aggregate))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("mean", r#"avg("value")"#, true),
+                ("mean_1", r#"(avg("value") * 2)"#, true),
+                ("s", r#"(max("value") - min("value"))"#, true),
+                (
+                    "percentile",
+                    r#"approx_percentile_cont(CAST("value" AS DOUBLE), 0.95)"#,
+                    true
+                ),
+            ],
+            projections
+        );
+
+        let select = parse_select("SELECT * FROM cpu GROUP BY host");
+        let projections = translator
+            .projections(&select.fields, &["host".to_string()])
+            .unwrap();
+        let names = projections
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["count", "region", "value"], names);
+
+        let select = parse_select("SELECT max(*) FROM cpu");
+        let projections = translator.projections(&select.fields, &[]).unwrap();
+        let names = projections
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["max_value", "max_count"], names);
+
+        let select = parse_select("SELECT last(value) FROM cpu");
+        assert!(translator.projections(&select.fields, &[]).is_err());
+
+        let select = parse_select("SELECT log(value, 2), ROUND(value) FROM cpu");
+        let projections = translator.projections(&select.fields, &[]).unwrap();
+        let sqls = projections
+            .iter()
+            .map(|p| p.sql.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![r#"log(2, "value")"#, r#"round("value")"#], sqls);
+
+        // Only the functions of InfluxQL are passed through.
+        for sql in [
+            "SELECT version() FROM cpu",
+            "SELECT to_timestamp(value) FROM cpu",
+            "SELECT abs(value, 1) FROM cpu",
+        ] {
+            let select = parse_select(sql);
+            assert!(translator.projections(&select.fields, &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        let points = vec![(10, vec![1.into()]), (40, vec![4.into()])];
+        let times = |points: &[(i64, Vec<JsonValue>)]| {
+            points
+                .iter()
+                .map(|(time, values)| (*time, values[0].clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let filled = fill_points(points.clone(), 0, 50, 10, &Fill::None).unwrap();
+        assert_eq!(times(&points), times(&filled));
+
+        let filled = fill_points(points.clone(), 0, 50, 10, &Fill::Null).unwrap();
+        assert_eq!(
+            vec![
+                (0, JsonValue::Null),
+                (10, 1.into()),
+                (20, JsonValue::Null),
+                (30, JsonValue::Null),
+                (40, 4.into()),
+                (50, JsonValue::Null),
+            ],
+            times(&filled)
+        );
+
+        let filled = fill_points(points.clone(), 0, 50, 10, &Fill::Value(0.0)).unwrap();
+        assert_eq!(JsonValue::from(0), filled[0].1[0]);
+
+        let filled = fill_points(points.clone(), 0, 50, 10, &Fill::Previous).unwrap();
+        assert_eq!(
+            vec![
+                (0, JsonValue::Null),
+                (10, 1.into()),
+                (20, 1.into()),
+                (30, 1.into()),
+                (40, 4.into()),
+                (50, 4.into()),
+            ],
+            times(&filled)
+        );
+
+        let filled = fill_points(points.clone(), 0, 50, 10, &Fill::Linear).unwrap();
+        assert_eq!(
+            vec![
+                (0, JsonValue::Null),
+                (10, 1.into()),
+                (20, 2.0.into()),
+                (30, 3.0.into()),
+                (40, 4.into()),
+                (50, JsonValue::Null),
+            ],
+            times(&filled)
+        );
+
+        assert!(fill_points(points.clone(), 0, MAX_FILLED_POINTS * 10, 10, &Fill::Null).is_err());
+        assert!(fill_points(points, i64::MIN, i64::MAX, 10, &Fill::Null).is_err());
+        let filled = fill_points(vec![], i64::MAX - 15, i64::MAX, 10, &Fill::Null).unwrap();
+        assert_eq!(
+            vec![i64::MAX - 15, i64::MAX - 5],
+            filled.iter().map(|(time, _)| *time).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(0, parse_time("1970-01-01T00:00:00Z").unwrap());
+        assert_eq!(
+            3_600_000_000_000,
+            parse_time("1970-01-01T02:00:00+01:00").unwrap()
+        );
+        assert_eq!(1_500_000_000, parse_time("1970-01-01 00:00:01.5").unwrap());
+        assert_eq!(86_400_000_000_000, parse_time("1970-01-02").unwrap());
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_serialize_response() {
+        let response = InfluxqlResponse {
+            results: vec![
+                StatementResult {
+                    statement_id: 0,
+                    series: vec![Series {
+                        name: "cpu".to_string(),
+                        tags: BTreeMap::from([("host".to_string(), "a".to_string())]),
+                        columns: vec!["time".to_string(), "mean".to_string()],
+                        values: vec![vec!["1970-01-01T00:00:00Z".into(), 1.5.into()]],
+                    }],
+                    error: None,
+                },
+                StatementResult {
+                    statement_id: 1,
+                    series: vec![],
+                    error: Some("oops".to_string()),
+                },
+            ],
+        };
+        assert_eq!(
+            r#"{"results":[{"statement_id":0,"series":[{"name":"cpu","tags":{"host":"a"},"columns":["time","mean"],"values":[["1970-01-01T00:00:00Z",1.5]]}]},{"statement_id":1,"error":"oops"}]}"#,
+            serde_json::to_string(&response).unwrap()
+        );
+    }
+}
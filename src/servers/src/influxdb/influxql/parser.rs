@@ -0,0 +1,1138 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lexer and parser of the InfluxQL subset served by the `/influxdb/query` endpoint.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use snafu::{ensure, OptionExt};
+
+use crate::error::{InvalidInfluxqlSnafu, Result};
+
+const NANOS_PER_MICRO: i64 = 1_000;
+const NANOS_PER_MILLI: i64 = 1_000_000;
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const NANOS_PER_MINUTE: i64 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_HOUR: i64 = 60 * NANOS_PER_MINUTE;
+const NANOS_PER_DAY: i64 = 24 * NANOS_PER_HOUR;
+const NANOS_PER_WEEK: i64 = 7 * NANOS_PER_DAY;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Select(Box<SelectStatement>),
+    ShowDatabases,
+    ShowRetentionPolicies {
+        database: Option<String>,
+    },
+    ShowMeasurements {
+        database: Option<String>,
+        /// The regex or the exact name in `WITH MEASUREMENT`.
+        filter: Option<Expr>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    },
+    ShowTagKeys {
+        database: Option<String>,
+        from: Option<Measurement>,
+    },
+    ShowTagValues {
+        database: Option<String>,
+        from: Option<Measurement>,
+        keys: TagKeys,
+        condition: Option<Expr>,
+        limit: Option<usize>,
+    },
+    ShowFieldKeys {
+        database: Option<String>,
+        from: Option<Measurement>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectStatement {
+    pub fields: Vec<Field>,
+    pub from: Measurement,
+    pub condition: Option<Expr>,
+    pub group_by: GroupBy,
+    pub fill: Fill,
+    pub descending: bool,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    pub slimit: Option<usize>,
+    pub soffset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub expr: Expr,
+    pub alias: Option<String>,
+}
+
+/// A measurement in `FROM`, which may be qualified as `db.rp.measurement` or `rp.measurement`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub database: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GroupBy {
+    /// The interval and offset of `time(...)`, in nanoseconds.
+    pub time: Option<(i64, i64)>,
+    pub tags: Vec<String>,
+    /// Whether the query is grouped by all tags, via `GROUP BY *`.
+    pub all_tags: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Fill {
+    #[default]
+    Null,
+    None,
+    Value(f64),
+    Previous,
+    Linear,
+}
+
+/// The keys in `SHOW TAG VALUES ... WITH KEY`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagKeys {
+    Eq(String),
+    In(Vec<String>),
+    Regex(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Ident(String),
+    Wildcard,
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    /// A duration literal, in nanoseconds.
+    Duration(i64),
+    Regex(String),
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    Binary {
+        left: Box<Expr>,
+        op: BinaryOp,
+        right: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    RegexMatch,
+    RegexNotMatch,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Eq
+                | BinaryOp::NotEq
+                | BinaryOp::Lt
+                | BinaryOp::LtEq
+                | BinaryOp::Gt
+                | BinaryOp::GtEq
+        )
+    }
+
+    /// Returns the operator with the operands swapped, e.g. `a < b` is `b > a`.
+    pub fn flip(&self) -> BinaryOp {
+        match self {
+            BinaryOp::Lt => BinaryOp::Gt,
+            BinaryOp::LtEq => BinaryOp::GtEq,
+            BinaryOp::Gt => BinaryOp::Lt,
+            BinaryOp::GtEq => BinaryOp::LtEq,
+            op => *op,
+        }
+    }
+
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Eq => "=",
+            BinaryOp::NotEq => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::RegexMatch => "~",
+            BinaryOp::RegexNotMatch => "!~",
+            BinaryOp::And => "AND",
+            BinaryOp::Or => "OR",
+        }
+    }
+}
+
+/// Parses the statements in the query, which are separated by semicolons.
+pub fn parse(query: &str) -> Result<Vec<Statement>> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser { tokens, pos: 0 };
+
+    let mut statements = Vec::new();
+    loop {
+        while parser.consume(&Token::Semicolon) {}
+        if parser.peek().is_none() {
+            break;
+        }
+        statements.push(parser.parse_statement()?);
+        if parser.peek().is_some() {
+            parser.expect(&Token::Semicolon)?;
+        }
+    }
+    ensure!(
+        !statements.is_empty(),
+        InvalidInfluxqlSnafu {
+            reason: "empty query",
+        }
+    );
+    Ok(statements)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    QuotedIdent(String),
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Duration(i64),
+    Regex(String),
+    Comma,
+    Dot,
+    LParen,
+    RParen,
+    Semicolon,
+    DoubleColon,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    RegexMatch,
+    RegexNotMatch,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut chars = query.chars().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                let _ = chars.next();
+                continue;
+            }
+            '-' if lookahead(&chars, 1) == Some('-') => {
+                // A comment till the end of the line.
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '"' => {
+                let _ = chars.next();
+                Token::QuotedIdent(read_quoted(&mut chars, '"')?)
+            }
+            '\'' => {
+                let _ = chars.next();
+                Token::String(read_quoted(&mut chars, '\'')?)
+            }
+            c if c.is_ascii_digit() || (c == '.' && next_is_digit(&chars)) => {
+                read_number(&mut chars)?
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        ident.push(c);
+                        let _ = chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Token::Ident(ident)
+            }
+            _ => {
+                let _ = chars.next();
+                match c {
+                    ',' => Token::Comma,
+                    '.' => Token::Dot,
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    ';' => Token::Semicolon,
+                    '*' => Token::Star,
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '/' => Token::Slash,
+                    ':' if chars.next_if_eq(&':').is_some() => Token::DoubleColon,
+                    '=' if chars.next_if_eq(&'~').is_some() => Token::RegexMatch,
+                    '=' => Token::Eq,
+                    '!' if chars.next_if_eq(&'=').is_some() => Token::NotEq,
+                    '!' if chars.next_if_eq(&'~').is_some() => Token::RegexNotMatch,
+                    '<' if chars.next_if_eq(&'=').is_some() => Token::LtEq,
+                    '<' if chars.next_if_eq(&'>').is_some() => Token::NotEq,
+                    '<' => Token::Lt,
+                    '>' if chars.next_if_eq(&'=').is_some() => Token::GtEq,
+                    '>' => Token::Gt,
+                    c => {
+                        return InvalidInfluxqlSnafu {
+                            reason: format!("unexpected character '{c}'"),
+                        }
+                        .fail()
+                    }
+                }
+            }
+        };
+
+        // Slashes after the regex operators delimit a regex, rather than being divisions.
+        let is_regex_op = matches!(token, Token::RegexMatch | Token::RegexNotMatch);
+        tokens.push(token);
+        if is_regex_op {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.next_if_eq(&'/').is_some() {
+                tokens.push(Token::Regex(read_regex(&mut chars)?));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn lookahead(chars: &Peekable<Chars>, n: usize) -> Option<char> {
+    chars.clone().nth(n)
+}
+
+fn next_is_digit(chars: &Peekable<Chars>) -> bool {
+    lookahead(chars, 1).map_or(false, |c| c.is_ascii_digit())
+}
+
+/// Reads a quoted string or identifier, whose opening quote is consumed.
+fn read_quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String> {
+    let mut s = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some(c) => s.push(c),
+                None => break,
+            },
+            c if c == quote => return Ok(s),
+            c => s.push(c),
+        }
+    }
+    InvalidInfluxqlSnafu {
+        reason: format!("unterminated quoted literal: {quote}{s}"),
+    }
+    .fail()
+}
+
+/// Reads a regex, whose opening slash is consumed. Only the escaped slashes are unescaped.
+fn read_regex(chars: &mut Peekable<Chars>) -> Result<String> {
+    let mut regex = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next_if_eq(&'/').is_some() => regex.push('/'),
+            '/' => return Ok(regex),
+            c => regex.push(c),
+        }
+    }
+    InvalidInfluxqlSnafu {
+        reason: format!("unterminated regex: /{regex}"),
+    }
+    .fail()
+}
+
+/// Reads an integer, a float or a duration like `1h30m`.
+fn read_number(chars: &mut Peekable<Chars>) -> Result<Token> {
+    let mut number = String::new();
+    let mut is_float = false;
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || (c == '.' && !is_float && next_is_digit(chars)) {
+            is_float |= c == '.';
+            number.push(c);
+            let _ = chars.next();
+        } else {
+            break;
+        }
+    }
+
+    let invalid = |number: &str| {
+        InvalidInfluxqlSnafu {
+            reason: format!("invalid number: {number}"),
+        }
+        .build()
+    };
+    if is_float {
+        return number
+            .parse()
+            .map(Token::Float)
+            .map_err(|_| invalid(&number));
+    }
+    let value: i64 = number.parse().map_err(|_| invalid(&number))?;
+    if !chars.peek().map_or(false, |c| c.is_alphabetic()) {
+        return Ok(Token::Integer(value));
+    }
+
+    let mut nanos = 0i64;
+    let mut amount = value;
+    loop {
+        let mut unit = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+            unit.push(c);
+        }
+        let unit_nanos = match unit.as_str() {
+            "ns" => 1,
+            "u" | "µ" | "us" | "µs" => NANOS_PER_MICRO,
+            "ms" => NANOS_PER_MILLI,
+            "s" => NANOS_PER_SECOND,
+            "m" => NANOS_PER_MINUTE,
+            "h" => NANOS_PER_HOUR,
+            "d" => NANOS_PER_DAY,
+            "w" => NANOS_PER_WEEK,
+            _ => {
+                return InvalidInfluxqlSnafu {
+                    reason: format!("invalid duration unit: {number}{unit}"),
+                }
+                .fail()
+            }
+        };
+        nanos = amount
+            .checked_mul(unit_nanos)
+            .and_then(|amount| nanos.checked_add(amount))
+            .with_context(|| InvalidInfluxqlSnafu {
+                reason: "duration overflows",
+            })?;
+
+        // Durations may be compound, like `1h30m`.
+        if !chars.peek().map_or(false, |c| c.is_ascii_digit()) {
+            break;
+        }
+        let mut digits = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+            digits.push(c);
+        }
+        amount = digits.parse().map_err(|_| invalid(&digits))?;
+    }
+    Ok(Token::Duration(nanos))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn consume(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<()> {
+        if self.consume(token) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("{token:?}"))
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(keyword))
+    }
+
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.consume_keyword(keyword) {
+            Ok(())
+        } else {
+            self.unexpected(keyword)
+        }
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        let found = self
+            .peek()
+            .map_or_else(|| "end of query".to_string(), |token| format!("{token:?}"));
+        InvalidInfluxqlSnafu {
+            reason: format!("expected {expected}, found {found}"),
+        }
+        .fail()
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement> {
+        if self.consume_keyword("SELECT") {
+            return Ok(Statement::Select(Box::new(self.parse_select()?)));
+        }
+        if !self.consume_keyword("SHOW") {
+            return self.unexpected("SELECT or SHOW");
+        }
+
+        if self.consume_keyword("DATABASES") {
+            Ok(Statement::ShowDatabases)
+        } else if self.consume_keyword("RETENTION") {
+            self.expect_keyword("POLICIES")?;
+            let database = self.parse_on_database()?;
+            Ok(Statement::ShowRetentionPolicies { database })
+        } else if self.consume_keyword("MEASUREMENTS") {
+            let database = self.parse_on_database()?;
+            let filter = if self.consume_keyword("WITH") {
+                self.expect_keyword("MEASUREMENT")?;
+                match self.next() {
+                    Some(Token::RegexMatch) => Some(Expr::Regex(self.parse_regex()?)),
+                    Some(Token::Eq) => Some(Expr::String(self.parse_ident()?)),
+                    _ => return self.unexpected("=~ or ="),
+                }
+            } else {
+                None
+            };
+            let (limit, offset) = self.parse_limit_offset("LIMIT", "OFFSET")?;
+            Ok(Statement::ShowMeasurements {
+                database,
+                filter,
+                limit,
+                offset,
+            })
+        } else if self.consume_keyword("TAG") {
+            if self.consume_keyword("KEYS") {
+                let database = self.parse_on_database()?;
+                let from = self.parse_optional_from()?;
+                Ok(Statement::ShowTagKeys { database, from })
+            } else {
+                self.expect_keyword("VALUES")?;
+                let database = self.parse_on_database()?;
+                let from = self.parse_optional_from()?;
+                self.expect_keyword("WITH")?;
+                self.expect_keyword("KEY")?;
+                let keys = match self.next() {
+                    Some(Token::Eq) => TagKeys::Eq(self.parse_ident()?),
+                    Some(Token::RegexMatch) => TagKeys::Regex(self.parse_regex()?),
+                    Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("IN") => {
+                        self.expect(&Token::LParen)?;
+                        let mut keys = vec![self.parse_ident()?];
+                        while self.consume(&Token::Comma) {
+                            keys.push(self.parse_ident()?);
+                        }
+                        self.expect(&Token::RParen)?;
+                        TagKeys::In(keys)
+                    }
+                    _ => return self.unexpected("=, =~ or IN"),
+                };
+                let condition = self.parse_condition()?;
+                let (limit, _) = self.parse_limit_offset("LIMIT", "OFFSET")?;
+                Ok(Statement::ShowTagValues {
+                    database,
+                    from,
+                    keys,
+                    condition,
+                    limit,
+                })
+            }
+        } else if self.consume_keyword("FIELD") {
+            self.expect_keyword("KEYS")?;
+            let database = self.parse_on_database()?;
+            let from = self.parse_optional_from()?;
+            Ok(Statement::ShowFieldKeys { database, from })
+        } else {
+            self.unexpected(
+                "DATABASES, RETENTION POLICIES, MEASUREMENTS, TAG KEYS, TAG VALUES or FIELD KEYS",
+            )
+        }
+    }
+
+    fn parse_select(&mut self) -> Result<SelectStatement> {
+        let mut fields = vec![self.parse_field()?];
+        while self.consume(&Token::Comma) {
+            fields.push(self.parse_field()?);
+        }
+
+        self.expect_keyword("FROM")?;
+        let from = self.parse_measurement()?;
+        let condition = self.parse_condition()?;
+
+        let mut group_by = GroupBy::default();
+        if self.consume_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            loop {
+                self.parse_dimension(&mut group_by)?;
+                if !self.consume(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let fill = if self.consume_keyword("fill") {
+            self.expect(&Token::LParen)?;
+            let fill = match self.next() {
+                Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("null") => Fill::Null,
+                Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("none") => Fill::None,
+                Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("previous") => {
+                    Fill::Previous
+                }
+                Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("linear") => Fill::Linear,
+                Some(Token::Integer(i)) => Fill::Value(i as f64),
+                Some(Token::Float(f)) => Fill::Value(f),
+                Some(Token::Minus) => match self.next() {
+                    Some(Token::Integer(i)) => Fill::Value(-i as f64),
+                    Some(Token::Float(f)) => Fill::Value(-f),
+                    _ => return self.unexpected("number"),
+                },
+                _ => return self.unexpected("null, none, previous, linear or number"),
+            };
+            self.expect(&Token::RParen)?;
+            fill
+        } else {
+            Fill::Null
+        };
+
+        let mut descending = false;
+        if self.consume_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            self.expect_keyword("time")?;
+            if self.consume_keyword("DESC") {
+                descending = true;
+            } else {
+                let _ = self.consume_keyword("ASC");
+            }
+        }
+
+        let (limit, offset) = self.parse_limit_offset("LIMIT", "OFFSET")?;
+        let (slimit, soffset) = self.parse_limit_offset("SLIMIT", "SOFFSET")?;
+
+        Ok(SelectStatement {
+            fields,
+            from,
+            condition,
+            group_by,
+            fill,
+            descending,
+            limit,
+            offset,
+            slimit,
+            soffset,
+        })
+    }
+
+    fn parse_field(&mut self) -> Result<Field> {
+        let expr = self.parse_expr()?;
+        let alias = if self.consume_keyword("AS") {
+            Some(self.parse_ident()?)
+        } else {
+            None
+        };
+        Ok(Field { expr, alias })
+    }
+
+    fn parse_dimension(&mut self, group_by: &mut GroupBy) -> Result<()> {
+        if self.consume(&Token::Star) {
+            group_by.all_tags = true;
+            return Ok(());
+        }
+        if self.peek_keyword("time") && self.tokens.get(self.pos + 1) == Some(&Token::LParen) {
+            self.pos += 2;
+            let interval = self.parse_duration()?;
+            let offset = if self.consume(&Token::Comma) {
+                if self.consume(&Token::Minus) {
+                    -self.parse_duration()?
+                } else {
+                    self.parse_duration()?
+                }
+            } else {
+                0
+            };
+            self.expect(&Token::RParen)?;
+            ensure!(
+                interval > 0,
+                InvalidInfluxqlSnafu {
+                    reason: "the interval of GROUP BY time must be positive",
+                }
+            );
+            group_by.time = Some((interval, offset));
+            return Ok(());
+        }
+        let tag = self.parse_ident()?;
+        if self.consume(&Token::DoubleColon) {
+            let _ = self.parse_ident()?;
+        }
+        group_by.tags.push(tag);
+        Ok(())
+    }
+
+    fn parse_duration(&mut self) -> Result<i64> {
+        match self.next() {
+            Some(Token::Duration(nanos)) => Ok(nanos),
+            _ => {
+                self.pos -= 1;
+                self.unexpected("duration")
+            }
+        }
+    }
+
+    fn parse_on_database(&mut self) -> Result<Option<String>> {
+        if self.consume_keyword("ON") {
+            Ok(Some(self.parse_ident()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_optional_from(&mut self) -> Result<Option<Measurement>> {
+        if self.consume_keyword("FROM") {
+            Ok(Some(self.parse_measurement()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_measurement(&mut self) -> Result<Measurement> {
+        let mut parts = vec![self.parse_ident()?];
+        while self.consume(&Token::Dot) {
+            // The retention policy may be omitted, like `db..measurement`.
+            if self.peek() == Some(&Token::Dot) {
+                parts.push(String::new());
+                continue;
+            }
+            parts.push(self.parse_ident()?);
+        }
+        let name = parts.pop().unwrap_or_default();
+        ensure!(
+            parts.len() <= 2,
+            InvalidInfluxqlSnafu {
+                reason: format!("invalid measurement: {name}"),
+            }
+        );
+        let database = (parts.len() == 2).then(|| parts.swap_remove(0));
+        Ok(Measurement { database, name })
+    }
+
+    fn parse_condition(&mut self) -> Result<Option<Expr>> {
+        if self.consume_keyword("WHERE") {
+            Ok(Some(self.parse_expr()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_limit_offset(
+        &mut self,
+        limit_keyword: &str,
+        offset_keyword: &str,
+    ) -> Result<(Option<usize>, Option<usize>)> {
+        let mut limit = None;
+        let mut offset = None;
+        if self.consume_keyword(limit_keyword) {
+            limit = Some(self.parse_unsigned()?);
+        }
+        if self.consume_keyword(offset_keyword) {
+            offset = Some(self.parse_unsigned()?);
+        }
+        Ok((limit, offset))
+    }
+
+    fn parse_unsigned(&mut self) -> Result<usize> {
+        match self.next() {
+            Some(Token::Integer(i)) if i >= 0 => Ok(i as usize),
+            _ => {
+                self.pos -= 1;
+                self.unexpected("non-negative integer")
+            }
+        }
+    }
+
+    fn parse_ident(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Ident(ident)) | Some(Token::QuotedIdent(ident)) => Ok(ident),
+            // Single quoted identifiers are accepted in `SHOW` statements, like influxd does.
+            Some(Token::String(s)) => Ok(s),
+            _ => {
+                self.pos -= 1;
+                self.unexpected("identifier")
+            }
+        }
+    }
+
+    fn parse_regex(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Regex(regex)) => Ok(regex),
+            _ => {
+                self.pos -= 1;
+                self.unexpected("regex")
+            }
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_binary(0)
+    }
+
+    /// Parses binary expressions by precedence climbing, from `OR` of precedence 0 to the
+    /// multiplicative operators of precedence 4.
+    fn parse_binary(&mut self, precedence: u8) -> Result<Expr> {
+        if precedence > 4 {
+            return self.parse_unary();
+        }
+        let mut left = self.parse_binary(precedence + 1)?;
+        while let Some(op) = self.peek_binary_op(precedence) {
+            self.pos += 1;
+            let right = if matches!(op, BinaryOp::RegexMatch | BinaryOp::RegexNotMatch) {
+                Expr::Regex(self.parse_regex()?)
+            } else {
+                self.parse_binary(precedence + 1)?
+            };
+            left = Expr::Binary {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn peek_binary_op(&self, precedence: u8) -> Option<BinaryOp> {
+        let op = match (precedence, self.peek()?) {
+            (0, Token::Ident(ident)) if ident.eq_ignore_ascii_case("OR") => BinaryOp::Or,
+            (1, Token::Ident(ident)) if ident.eq_ignore_ascii_case("AND") => BinaryOp::And,
+            (2, Token::Eq) => BinaryOp::Eq,
+            (2, Token::NotEq) => BinaryOp::NotEq,
+            (2, Token::Lt) => BinaryOp::Lt,
+            (2, Token::LtEq) => BinaryOp::LtEq,
+            (2, Token::Gt) => BinaryOp::Gt,
+            (2, Token::GtEq) => BinaryOp::GtEq,
+            (2, Token::RegexMatch) => BinaryOp::RegexMatch,
+            (2, Token::RegexNotMatch) => BinaryOp::RegexNotMatch,
+            (3, Token::Plus) => BinaryOp::Add,
+            (3, Token::Minus) => BinaryOp::Sub,
+            (4, Token::Star) => BinaryOp::Mul,
+            (4, Token::Slash) => BinaryOp::Div,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.consume(&Token::Minus) {
+            return match self.parse_unary()? {
+                Expr::Integer(i) => Ok(Expr::Integer(-i)),
+                Expr::Float(f) => Ok(Expr::Float(-f)),
+                Expr::Duration(d) => Ok(Expr::Duration(-d)),
+                expr => Ok(Expr::Binary {
+                    left: Box::new(Expr::Integer(0)),
+                    op: BinaryOp::Sub,
+                    right: Box::new(expr),
+                }),
+            };
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let expr = match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.parse_expr()?;
+                self.expect(&Token::RParen)?;
+                expr
+            }
+            Some(Token::Star) => Expr::Wildcard,
+            Some(Token::String(s)) => Expr::String(s),
+            Some(Token::Integer(i)) => Expr::Integer(i),
+            Some(Token::Float(f)) => Expr::Float(f),
+            Some(Token::Duration(d)) => Expr::Duration(d),
+            Some(Token::Regex(regex)) => Expr::Regex(regex),
+            Some(Token::QuotedIdent(ident)) => Expr::Ident(ident),
+            Some(Token::Ident(ident)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if !self.consume(&Token::RParen) {
+                    args.push(self.parse_expr()?);
+                    while self.consume(&Token::Comma) {
+                        args.push(self.parse_expr()?);
+                    }
+                    self.expect(&Token::RParen)?;
+                }
+                Expr::Call {
+                    name: ident.to_lowercase(),
+                    args,
+                }
+            }
+            Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("true") => Expr::Boolean(true),
+            Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case("false") => {
+                Expr::Boolean(false)
+            }
+            Some(Token::Ident(ident)) => Expr::Ident(ident),
+            _ => {
+                self.pos -= 1;
+                return self.unexpected("expression");
+            }
+        };
+
+        // The type casts like `"value"::field` are ignored.
+        if matches!(expr, Expr::Ident(_)) && self.consume(&Token::DoubleColon) {
+            let _ = self.parse_ident()?;
+        }
+        Ok(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Box<Expr> {
+        Box::new(Expr::Ident(name.to_string()))
+    }
+
+    fn parse_select(query: &str) -> SelectStatement {
+        match parse(query).unwrap().remove(0) {
+            Statement::Select(select) => *select,
+            statement => panic!("unexpected statement: {statement:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_select() {
+        let select = parse_select(
+            r#"SELECT mean("value") AS "avg", max("value") * 2 FROM "db"."autogen"."cpu" WHERE ("host" =~ /^a\/b$/ AND "region" = 'us') AND time >= now() - 1h30m GROUP BY time(1m, 10s), "host"::tag fill(previous) ORDER BY time DESC LIMIT 10 SLIMIT 2"#,
+        );
+        assert_eq!(
+            select.fields,
+            vec![
+                Field {
+                    expr: Expr::Call {
+                        name: "mean".to_string(),
+                        args: vec![Expr::Ident("value".to_string())],
+                    },
+                    alias: Some("avg".to_string()),
+                },
+                Field {
+                    expr: Expr::Binary {
+                        left: Box::new(Expr::Call {
+                            name: "max".to_string(),
+                            args: vec![Expr::Ident("value".to_string())],
+                        }),
+                        op: BinaryOp::Mul,
+                        right: Box::new(Expr::Integer(2)),
+                    },
+                    alias: None,
+                },
+            ]
+        );
+        assert_eq!(
+            select.from,
+            Measurement {
+                database: Some("db".to_string()),
+                name: "cpu".to_string(),
+            }
+        );
+        assert_eq!(
+            select.condition,
+            Some(Expr::Binary {
+                left: Box::new(Expr::Binary {
+                    left: Box::new(Expr::Binary {
+                        left: ident("host"),
+                        op: BinaryOp::RegexMatch,
+                        right: Box::new(Expr::Regex("^a/b$".to_string())),
+                    }),
+                    op: BinaryOp::And,
+                    right: Box::new(Expr::Binary {
+                        left: ident("region"),
+                        op: BinaryOp::Eq,
+                        right: Box::new(Expr::String("us".to_string())),
+                    }),
+                }),
+                op: BinaryOp::And,
+                right: Box::new(Expr::Binary {
+                    left: ident("time"),
+                    op: BinaryOp::GtEq,
+                    right: Box::new(Expr::Binary {
+                        left: Box::new(Expr::Call {
+                            name: "now".to_string(),
+                            args: vec![],
+                        }),
+                        op: BinaryOp::Sub,
+                        right: Box::new(Expr::Duration(90 * NANOS_PER_MINUTE)),
+                    }),
+                }),
+            })
+        );
+        assert_eq!(
+            select.group_by,
+            GroupBy {
+                time: Some((NANOS_PER_MINUTE, 10 * NANOS_PER_SECOND)),
+                tags: vec!["host".to_string()],
+                all_tags: false,
+            }
+        );
+        assert_eq!(select.fill, Fill::Previous);
+        assert!(select.descending);
+        assert_eq!(select.limit, Some(10));
+        assert_eq!(select.offset, None);
+        assert_eq!(select.slimit, Some(2));
+    }
+
+    #[test]
+    fn test_parse_grafana_queries() {
+        let select = parse_select(
+            r#"SELECT "value" FROM "cpu" WHERE time >= 1681000000000ms and time <= 1681003600000ms"#,
+        );
+        assert_eq!(select.fields[0].expr, Expr::Ident("value".to_string()));
+        let Some(Expr::Binary { left, op: BinaryOp::And, right }) = select.condition else {
+            panic!("unexpected condition: {:?}", select.condition);
+        };
+        assert_eq!(
+            *left,
+            Expr::Binary {
+                left: ident("time"),
+                op: BinaryOp::GtEq,
+                right: Box::new(Expr::Duration(1681000000000 * NANOS_PER_MILLI)),
+            }
+        );
+        assert_eq!(
+            *right,
+            Expr::Binary {
+                left: ident("time"),
+                op: BinaryOp::LtEq,
+                right: Box::new(Expr::Duration(1681003600000 * NANOS_PER_MILLI)),
+            }
+        );
+
+        let select = parse_select("SELECT count(*) FROM cpu GROUP BY * fill(-1.5)");
+        assert_eq!(
+            select.fields[0].expr,
+            Expr::Call {
+                name: "count".to_string(),
+                args: vec![Expr::Wildcard],
+            }
+        );
+        assert!(select.group_by.all_tags);
+        assert_eq!(select.fill, Fill::Value(-1.5));
+
+        let statements = parse(
+            "SHOW MEASUREMENTS WITH MEASUREMENT =~ /(?i)cpu/ LIMIT 100; SHOW TAG KEYS FROM cpu;\
+             SHOW TAG VALUES ON db FROM \"cpu\" WITH KEY IN (\"host\", region) WHERE a = 'b';\
+             SHOW FIELD KEYS; SHOW RETENTION POLICIES ON \"db\"; SHOW DATABASES",
+        )
+        .unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                Statement::ShowMeasurements {
+                    database: None,
+                    filter: Some(Expr::Regex("(?i)cpu".to_string())),
+                    limit: Some(100),
+                    offset: None,
+                },
+                Statement::ShowTagKeys {
+                    database: None,
+                    from: Some(Measurement {
+                        database: None,
+                        name: "cpu".to_string(),
+                    }),
+                },
+                Statement::ShowTagValues {
+                    database: Some("db".to_string()),
+                    from: Some(Measurement {
+                        database: None,
+                        name: "cpu".to_string(),
+                    }),
+                    keys: TagKeys::In(vec!["host".to_string(), "region".to_string()]),
+                    condition: Some(Expr::Binary {
+                        left: ident("a"),
+                        op: BinaryOp::Eq,
+                        right: Box::new(Expr::String("b".to_string())),
+                    }),
+                    limit: None,
+                },
+                Statement::ShowFieldKeys {
+                    database: None,
+                    from: None,
+                },
+                Statement::ShowRetentionPolicies {
+                    database: Some("db".to_string()),
+                },
+                Statement::ShowDatabases,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for query in [
+            "",
+            ";",
+            "SELECT",
+            "SELECT value FROM",
+            "SELECT value FROM cpu WHERE",
+            "SELECT value FROM cpu GROUP BY time(0s)",
+            "SELECT value FROM cpu LIMIT -1",
+            "SELECT value FROM cpu WHERE time > 1y",
+            "SELECT 'value FROM cpu",
+            "SELECT value FROM cpu WHERE host =~ /a",
+            "SELECT value FROM cpu extra",
+            "DROP MEASUREMENT cpu",
+            "SHOW TAG VALUES FROM cpu",
+        ] {
+            assert!(parse(query).is_err(), "{query}");
+        }
+    }
+}
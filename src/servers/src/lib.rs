@@ -19,7 +19,10 @@ use common_catalog::consts::DEFAULT_CATALOG_NAME;
 use serde::{Deserialize, Serialize};
 
 pub mod auth;
+mod bulk_insert;
 pub mod error;
+pub mod flight_sql;
+pub mod graphite;
 pub mod grpc;
 pub mod http;
 pub mod influxdb;
@@ -28,6 +31,7 @@ pub mod line_writer;
 pub mod metrics_handler;
 pub mod mysql;
 pub mod opentsdb;
+pub mod otlp;
 pub mod postgres;
 pub mod prom;
 pub mod prometheus;
@@ -34,6 +34,7 @@ use session::Session;
 use snafu::ensure;
 use sql::dialect::GenericDialect;
 use sql::parser::ParserContext;
+use sql::statements::load_data::LoadData;
 use sql::statements::statement::Statement;
 use tokio::io::AsyncWrite;
 
@@ -117,6 +118,10 @@ impl MysqlInstanceShim {
         let guard = self.prepared_stmts.read();
         guard.get(&stmt_id).map(|s| s.to_owned())
     }
+
+    pub(crate) fn session(&self) -> Arc<Session> {
+        self.session.clone()
+    }
 }
 
 #[async_trait]
@@ -127,6 +132,10 @@ impl<W: AsyncWrite + Send + Sync + Unpin> AsyncMysqlShim<W> for MysqlInstanceShi
         self.salt
     }
 
+    fn connect_id(&self) -> u32 {
+        self.session.context().conn_id()
+    }
+
     async fn authenticate(
         &self,
         auth_plugin: &str,
@@ -226,6 +235,20 @@ impl<W: AsyncWrite + Send + Sync + Unpin> AsyncMysqlShim<W> for MysqlInstanceShi
         query: &'a str,
         writer: QueryResultWriter<'a, W>,
     ) -> Result<()> {
+        if let Some(load_data) = parse_local_load_data(query) {
+            // The file is requested from the client by the relay in front of opensrv-mysql, which
+            // is only enabled by `local_infile` and can't see the queries on the connections
+            // upgraded to TLS.
+            let msg = format!(
+                "LOAD DATA LOCAL INFILE '{}' is not enabled, or not supported over TLS",
+                load_data.file_name
+            );
+            return writer
+                .error(ErrorKind::ER_NOT_ALLOWED_COMMAND, msg.as_bytes())
+                .await
+                .map_err(|e| e.into());
+        }
+
         let outputs = self.do_query(query).await;
         writer::write_output(writer, query, outputs).await?;
         Ok(())
@@ -287,6 +310,24 @@ fn format_duration(duration: Duration) -> String {
     format!("{}:{}:{}", hours, minutes, seconds)
 }
 
+/// Parses the query if it is `LOAD DATA LOCAL INFILE`, whose file is sent by the client.
+pub(crate) fn parse_local_load_data(query: &str) -> Option<LoadData> {
+    let is_load = query
+        .trim_start()
+        .get(..4)
+        .map_or(false, |s| s.eq_ignore_ascii_case("LOAD"));
+    if !is_load {
+        return None;
+    }
+    let mut statements = ParserContext::create_with_dialect(query, &GenericDialect {}).ok()?;
+    match statements.pop() {
+        Some(Statement::LoadData(load_data)) if statements.is_empty() && load_data.local => {
+            Some(load_data)
+        }
+        _ => None,
+    }
+}
+
 async fn validate_query(query: &str) -> Result<Statement> {
     let statement = ParserContext::create_with_dialect(query, &GenericDialect {});
     let mut statement = statement.map_err(|e| {
@@ -0,0 +1,409 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decodes the file of MySQL `LOAD DATA LOCAL INFILE`, which is sent by the client in packets,
+//! according to the `FIELDS` and `LINES` options of the statement.
+
+use datatypes::prelude::{ConcreteDataType, Value};
+use session::context::QueryContextRef;
+use snafu::ensure;
+use sql::statements::load_data::LoadData;
+
+use crate::bulk_insert::{parse_text_value, BulkInserter, Row};
+use crate::error::{self, Result};
+use crate::query_handler::grpc::BulkInsertHandlerRef;
+use crate::query_handler::sql::ServerSqlQueryHandlerRef;
+
+/// A `LOAD DATA LOCAL INFILE` in progress, which inserts the rows decoded from the file packets
+/// into the table in batches.
+pub(crate) struct LocalInfile {
+    inserter: BulkInserter,
+    decoder: LoadDataDecoder,
+}
+
+impl LocalInfile {
+    pub(crate) async fn try_new(
+        query_handler: ServerSqlQueryHandlerRef,
+        bulk_insert_handler: BulkInsertHandlerRef,
+        query_ctx: QueryContextRef,
+        load_data: &LoadData,
+    ) -> Result<Self> {
+        let inserter = BulkInserter::try_new(
+            query_handler,
+            bulk_insert_handler,
+            query_ctx,
+            &load_data.table_name,
+            &load_data.columns,
+        )
+        .await?;
+        let decoder = LoadDataDecoder::new(load_data, inserter.column_types().to_vec());
+        Ok(LocalInfile { inserter, decoder })
+    }
+
+    pub(crate) async fn push(&mut self, data: &[u8]) -> Result<()> {
+        let rows = self.decoder.decode(data)?;
+        self.inserter.push(rows).await
+    }
+
+    /// Inserts the rest of the rows, and returns the number of inserted rows.
+    pub(crate) async fn finish(mut self) -> Result<usize> {
+        let rows = self.decoder.finish()?;
+        self.inserter.push(rows).await?;
+        self.inserter.finish().await
+    }
+}
+
+/// Decodes the content of the file into rows, which may be split across the packets.
+pub(crate) struct LoadDataDecoder {
+    field_terminator: Vec<u8>,
+    enclosed_by: Option<u8>,
+    escaped_by: Option<u8>,
+    line_prefix: Vec<u8>,
+    line_terminator: Vec<u8>,
+    /// The number of lines to skip yet.
+    ignore_lines: u64,
+    column_types: Vec<ConcreteDataType>,
+    buffer: Vec<u8>,
+}
+
+impl LoadDataDecoder {
+    pub(crate) fn new(load_data: &LoadData, column_types: Vec<ConcreteDataType>) -> Self {
+        // The parser guarantees the characters are ASCII.
+        LoadDataDecoder {
+            field_terminator: load_data.fields.terminated_by.as_bytes().to_vec(),
+            enclosed_by: load_data.fields.enclosed_by.map(|c| c as u8),
+            escaped_by: load_data.fields.escaped_by.map(|c| c as u8),
+            line_prefix: load_data.lines.starting_by.as_bytes().to_vec(),
+            line_terminator: load_data.lines.terminated_by.as_bytes().to_vec(),
+            ignore_lines: load_data.ignore_lines,
+            column_types,
+            buffer: vec![],
+        }
+    }
+
+    /// Decodes the complete lines in the received data, and keeps the rest for the next.
+    pub(crate) fn decode(&mut self, data: &[u8]) -> Result<Vec<Row>> {
+        self.buffer.extend_from_slice(data);
+        self.decode_lines(false)
+    }
+
+    /// Decodes the last line, which may have no line terminator.
+    pub(crate) fn finish(&mut self) -> Result<Vec<Row>> {
+        self.decode_lines(true)
+    }
+
+    fn decode_lines(&mut self, eof: bool) -> Result<Vec<Row>> {
+        let mut rows = vec![];
+        while !self.buffer.is_empty() {
+            let consumed = self.decode_line(&mut rows, eof)?;
+            if consumed == 0 {
+                break;
+            }
+            let _ = self.buffer.drain(..consumed);
+        }
+        Ok(rows)
+    }
+
+    /// Decodes a line, returns the number of consumed bytes, or 0 if the line is incomplete.
+    /// The enclosed fields may contain the terminators.
+    fn decode_line(&mut self, rows: &mut Vec<Row>, eof: bool) -> Result<usize> {
+        let start = if self.line_prefix.is_empty() {
+            0
+        } else {
+            // The text before the prefix is skipped, as well as the lines without the prefix.
+            let prefix = find(&self.buffer, &self.line_prefix);
+            match (prefix, find(&self.buffer, &self.line_terminator)) {
+                (Some(prefix), Some(end)) if end < prefix => {
+                    return Ok(end + self.line_terminator.len())
+                }
+                (Some(prefix), _) => prefix + self.line_prefix.len(),
+                (None, Some(end)) => return Ok(end + self.line_terminator.len()),
+                (None, None) if eof => return Ok(self.buffer.len()),
+                (None, None) => return Ok(0),
+            }
+        };
+
+        let mut fields = vec![];
+        let mut field = vec![];
+        let mut is_null = false;
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut consumed = None;
+
+        let buffer = &self.buffer;
+        let mut i = start;
+        while i < buffer.len() {
+            let b = buffer[i];
+            if Some(b) == self.escaped_by {
+                let Some(next) = buffer.get(i + 1) else {
+                    if !eof {
+                        return Ok(0);
+                    }
+                    field.push(b);
+                    break;
+                };
+                if *next == b'N' && field.is_empty() && !quoted {
+                    is_null = true;
+                } else {
+                    field.push(unescape(*next));
+                }
+                i += 2;
+                continue;
+            }
+            if in_quotes {
+                if Some(b) == self.enclosed_by {
+                    if buffer.get(i + 1) == Some(&b) {
+                        field.push(b);
+                        i += 1;
+                    } else if i + 1 == buffer.len() && !eof {
+                        // Can't tell whether the quote is doubled yet.
+                        return Ok(0);
+                    } else {
+                        in_quotes = false;
+                    }
+                } else {
+                    field.push(b);
+                }
+                i += 1;
+                continue;
+            }
+
+            let rest = &buffer[i..];
+            if rest.starts_with(&self.line_terminator) {
+                consumed = Some(i + self.line_terminator.len());
+                break;
+            }
+            if rest.starts_with(&self.field_terminator) {
+                fields.push(self.to_field(std::mem::take(&mut field), is_null, quoted));
+                is_null = false;
+                quoted = false;
+                i += self.field_terminator.len();
+                continue;
+            }
+            if !eof
+                && (self.line_terminator.starts_with(rest)
+                    || self.field_terminator.starts_with(rest))
+            {
+                // Can't tell whether it is a terminator yet.
+                return Ok(0);
+            }
+            if Some(b) == self.enclosed_by && field.is_empty() && !quoted && !is_null {
+                in_quotes = true;
+                quoted = true;
+            } else {
+                field.push(b);
+            }
+            i += 1;
+        }
+        let consumed = match consumed {
+            Some(consumed) => consumed,
+            None if eof && !in_quotes => buffer.len(),
+            None if eof => {
+                return error::InvalidMysqlLoadDataSnafu {
+                    reason: "Unterminated enclosed field",
+                }
+                .fail()
+            }
+            None => return Ok(0),
+        };
+
+        // Skips the empty lines, like the trailing one of the file.
+        if fields.is_empty() && field.is_empty() && !is_null && !quoted {
+            return Ok(consumed);
+        }
+        fields.push(self.to_field(field, is_null, quoted));
+
+        if self.ignore_lines > 0 {
+            self.ignore_lines -= 1;
+            return Ok(consumed);
+        }
+        rows.push(self.to_row(fields)?);
+        Ok(consumed)
+    }
+
+    fn to_field(&self, field: Vec<u8>, is_null: bool, quoted: bool) -> Option<Vec<u8>> {
+        // An unquoted `NULL` is also NULL if the fields may be enclosed.
+        if is_null || (self.enclosed_by.is_some() && !quoted && field == b"NULL") {
+            None
+        } else {
+            Some(field)
+        }
+    }
+
+    fn to_row(&self, fields: Vec<Option<Vec<u8>>>) -> Result<Row> {
+        ensure!(
+            fields.len() == self.column_types.len(),
+            error::InvalidMysqlLoadDataSnafu {
+                reason: format!(
+                    "Expect {} columns in a line, found {}",
+                    self.column_types.len(),
+                    fields.len()
+                ),
+            }
+        );
+        fields
+            .into_iter()
+            .zip(&self.column_types)
+            .map(|(field, data_type)| match field {
+                Some(field) => {
+                    let field = String::from_utf8(field).map_err(|e| {
+                        error::InvalidMysqlLoadDataSnafu {
+                            reason: e.to_string(),
+                        }
+                        .build()
+                    })?;
+                    parse_text_value(&field, data_type)
+                }
+                None => Ok(Value::Null),
+            })
+            .collect()
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Maps the character following the escape character, like `n` of `\n`.
+fn unescape(b: u8) -> u8 {
+    match b {
+        b'0' => b'\0',
+        b'b' => b'\x08',
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'Z' => b'\x1a',
+        _ => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common_time::timestamp::Timestamp;
+    use sql::dialect::GenericDialect;
+    use sql::parser::ParserContext;
+    use sql::statements::statement::Statement;
+
+    use super::*;
+
+    fn new_decoder(sql: &str) -> LoadDataDecoder {
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        let Statement::LoadData(load_data) = stmts.remove(0) else {
+            unreachable!()
+        };
+        LoadDataDecoder::new(
+            &load_data,
+            vec![
+                ConcreteDataType::string_datatype(),
+                ConcreteDataType::float64_datatype(),
+                ConcreteDataType::timestamp_millisecond_datatype(),
+            ],
+        )
+    }
+
+    /// Decodes the data in chunks of every size, which should all make the same rows.
+    fn decode_in_chunks(sql: &str, data: &[u8]) -> Vec<Row> {
+        let expected = {
+            let mut decoder = new_decoder(sql);
+            let mut rows = decoder.decode(data).unwrap();
+            rows.extend(decoder.finish().unwrap());
+            rows
+        };
+        for chunk_size in 1..data.len() {
+            let mut decoder = new_decoder(sql);
+            let mut rows = vec![];
+            for chunk in data.chunks(chunk_size) {
+                rows.extend(decoder.decode(chunk).unwrap());
+            }
+            rows.extend(decoder.finish().unwrap());
+            assert_eq!(expected, rows, "chunk size: {chunk_size}");
+        }
+        expected
+    }
+
+    fn row(host: Option<&str>, cpu: Option<f64>, ts: i64) -> Row {
+        vec![
+            host.map(|h| Value::String(h.into())).unwrap_or(Value::Null),
+            cpu.map(|c| Value::Float64(c.into())).unwrap_or(Value::Null),
+            Value::Timestamp(Timestamp::new_millisecond(ts)),
+        ]
+    }
+
+    #[test]
+    fn test_decode_tsv() {
+        let sql = "LOAD DATA LOCAL INFILE 'demo.tsv' INTO TABLE demo";
+        let data = b"host1\t1.5\t1970-01-01T00:00:01Z\n\
+            host\\t2\t\\N\t1970-01-01T00:00:02Z\n\
+            \\N\t2.5\t1970-01-01T00:00:03Z";
+        assert_eq!(
+            vec![
+                row(Some("host1"), Some(1.5), 1000),
+                row(Some("host\t2"), None, 2000),
+                row(None, Some(2.5), 3000),
+            ],
+            decode_in_chunks(sql, data)
+        );
+    }
+
+    #[test]
+    fn test_decode_csv() {
+        let sql = r#"LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo
+            FIELDS TERMINATED BY ',' ENCLOSED BY '"' LINES TERMINATED BY '\r\n' IGNORE 1 LINES"#;
+        let data = b"host,cpu,ts\r\n\
+            \"host,1\",1.5,1970-01-01T00:00:01Z\r\n\
+            \"say \"\"hi\"\"\r\n\",NULL,1970-01-01T00:00:02Z\r\n\
+            \"NULL\",2.5,1970-01-01T00:00:03Z\r\n\r\n";
+        assert_eq!(
+            vec![
+                row(Some("host,1"), Some(1.5), 1000),
+                row(Some("say \"hi\"\r\n"), None, 2000),
+                row(Some("NULL"), Some(2.5), 3000),
+            ],
+            decode_in_chunks(sql, data)
+        );
+    }
+
+    #[test]
+    fn test_decode_lines_starting_by() {
+        let sql = "LOAD DATA LOCAL INFILE 'demo.txt' INTO TABLE demo
+            FIELDS TERMINATED BY '||' ESCAPED BY '' LINES STARTING BY 'xxx'";
+        let data = b"xxxhost\\1||1.5||1970-01-01T00:00:01Z\n\
+            no prefix||2.5||1970-01-01T00:00:02Z\n\
+            ...xxxhost2||3.5||1970-01-01T00:00:03Z\n";
+        assert_eq!(
+            vec![
+                row(Some("host\\1"), Some(1.5), 1000),
+                row(Some("host2"), Some(3.5), 3000),
+            ],
+            decode_in_chunks(sql, data)
+        );
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let sql = r#"LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo
+            FIELDS TERMINATED BY ',' ENCLOSED BY '"'"#;
+        let mut decoder = new_decoder(sql);
+        assert!(decoder.decode(b"host1,1.5\n").is_err());
+
+        let mut decoder = new_decoder(sql);
+        assert!(decoder.decode(b"host1,cpu,1970-01-01T00:00:01Z\n").is_err());
+
+        let mut decoder = new_decoder(sql);
+        assert!(decoder.decode(b"\"host1,1.5,0\n").unwrap().is_empty());
+        assert!(decoder.finish().is_err());
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runs the packet exchange of `LOAD DATA LOCAL INFILE` in front of opensrv-mysql, which can't
+//! request the file from the client or read the file packets back.
+//!
+//! The relay is only used when `local_infile` is enabled in the MySQL options. opensrv-mysql
+//! then serves the connection on the other end of an in-memory pipe, and the packets are relayed
+//! between it and the client. Once opensrv-mysql has authenticated the client,
+//! `LOAD DATA LOCAL INFILE` queries are run by the relay instead of being passed on. The
+//! connections that are not authenticated, or upgraded to TLS, are relayed as they are, so their
+//! `LOAD DATA LOCAL INFILE` queries still reach, and are rejected by, the shim.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use common_telemetry::error;
+use session::Session;
+use sql::statements::load_data::LoadData;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::error::Error;
+use crate::mysql::handler::parse_local_load_data;
+use crate::mysql::load_data::LocalInfile;
+use crate::mysql::writer::error_kind;
+use crate::query_handler::grpc::BulkInsertHandlerRef;
+use crate::query_handler::sql::ServerSqlQueryHandlerRef;
+
+/// The payloads of this length are continued in the next packet.
+const MAX_PAYLOAD_LEN: usize = 0xff_ffff;
+const COM_QUERY: u8 = 0x03;
+const LOCAL_INFILE_REQUEST: u8 = 0xfb;
+const OK_PACKET: u8 = 0x00;
+const ERR_PACKET: u8 = 0xff;
+const CLIENT_LOCAL_FILES: u8 = 0x80;
+const CLIENT_SSL: u32 = 0x800;
+const SERVER_STATUS_AUTOCOMMIT: u16 = 0x0002;
+const RELAY_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Relays the packets between the client and opensrv-mysql until either of them closes the
+/// connection.
+pub(crate) async fn relay<S>(
+    client: TcpStream,
+    server: S,
+    query_handler: ServerSqlQueryHandlerRef,
+    bulk_insert_handler: BulkInsertHandlerRef,
+    session: Arc<Session>,
+) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Send,
+{
+    let (client_reader, client_writer) = client.into_split();
+    let (server_reader, server_writer) = tokio::io::split(server);
+    let client_writer = Arc::new(Mutex::new(client_writer));
+    let authenticated = Arc::new(AtomicBool::new(false));
+    let (tls_sender, tls_receiver) = oneshot::channel();
+
+    let mut upstream = Upstream {
+        client_reader,
+        client_writer: client_writer.clone(),
+        server_writer,
+        query_handler,
+        bulk_insert_handler,
+        session,
+        authenticated: authenticated.clone(),
+    };
+    let downstream = relay_to_client(server_reader, client_writer, tls_receiver, authenticated);
+    tokio::select! {
+        result = upstream.run(tls_sender) => result,
+        result = downstream => result,
+    }
+}
+
+/// Relays the packets from opensrv-mysql to the client, announcing `CLIENT_LOCAL_FILES` in the
+/// handshake, without which the clients refuse to send the files.
+///
+/// The packets of the authentication phase are read one by one, and `authenticated` is set
+/// before the OK packet that ends the authentication is passed on to the client.
+async fn relay_to_client<S: AsyncRead>(
+    mut server_reader: ReadHalf<S>,
+    client_writer: Arc<Mutex<OwnedWriteHalf>>,
+    tls: oneshot::Receiver<bool>,
+    authenticated: Arc<AtomicBool>,
+) -> io::Result<()> {
+    let Some((seq, mut handshake)) = read_packet(&mut server_reader).await? else {
+        return Ok(());
+    };
+    set_local_files_capability(&mut handshake);
+    write_packet(&mut *client_writer.lock().await, seq, &handshake).await?;
+
+    // The packets of the connections upgraded to TLS can't be read.
+    let Ok(false) = tls.await else {
+        return relay_bytes(server_reader, client_writer).await;
+    };
+    // The server replies to the handshake response with OK or ERR, possibly after switching the
+    // auth plugin or exchanging more auth data.
+    while let Some((seq, payload)) = read_packet(&mut server_reader).await? {
+        let first = payload.first().copied();
+        if first == Some(OK_PACKET) {
+            authenticated.store(true, Ordering::Release);
+        }
+        write_packet(&mut *client_writer.lock().await, seq, &payload).await?;
+        if matches!(first, Some(OK_PACKET | ERR_PACKET)) {
+            return relay_bytes(server_reader, client_writer).await;
+        }
+    }
+    Ok(())
+}
+
+async fn relay_bytes<S: AsyncRead>(
+    mut server_reader: ReadHalf<S>,
+    client_writer: Arc<Mutex<OwnedWriteHalf>>,
+) -> io::Result<()> {
+    let mut buf = vec![0; RELAY_BUFFER_SIZE];
+    loop {
+        let n = server_reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        let mut client_writer = client_writer.lock().await;
+        client_writer.write_all(&buf[..n]).await?;
+        client_writer.flush().await?;
+    }
+}
+
+/// Sets `CLIENT_LOCAL_FILES` in the lower capability flags of the initial handshake packet.
+fn set_local_files_capability(handshake: &mut [u8]) {
+    // Protocol version 10, followed by the null terminated server version.
+    if handshake.first() != Some(&10) {
+        return;
+    }
+    let Some(version_len) = handshake[1..].iter().position(|b| *b == 0) else {
+        return;
+    };
+    // The connection id, the first 8 bytes of the auth plugin data and a filler.
+    let offset = 1 + version_len + 1 + 4 + 8 + 1;
+    if let Some(flags) = handshake.get_mut(offset) {
+        *flags |= CLIENT_LOCAL_FILES;
+    }
+}
+
+/// Relays the packets from the client to opensrv-mysql, except for `LOAD DATA LOCAL INFILE`.
+struct Upstream<S> {
+    client_reader: OwnedReadHalf,
+    client_writer: Arc<Mutex<OwnedWriteHalf>>,
+    server_writer: WriteHalf<S>,
+    query_handler: ServerSqlQueryHandlerRef,
+    bulk_insert_handler: BulkInsertHandlerRef,
+    session: Arc<Session>,
+    /// Set once opensrv-mysql has authenticated the client, before which the queries are passed
+    /// on as they are.
+    authenticated: Arc<AtomicBool>,
+}
+
+impl<S: AsyncWrite> Upstream<S> {
+    async fn run(&mut self, tls: oneshot::Sender<bool>) -> io::Result<()> {
+        // The handshake response, or the SSLRequest before the TLS handshake.
+        let Some((seq, response)) = read_packet(&mut self.client_reader).await? else {
+            return Ok(());
+        };
+        let capabilities = response
+            .get(..4)
+            .map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        let client_tls = capabilities & CLIENT_SSL != 0;
+        // Sent before the response is passed on, which the server replies to.
+        let _ = tls.send(client_tls);
+        write_packet(&mut self.server_writer, seq, &response).await?;
+        if client_tls {
+            let _ = tokio::io::copy(&mut self.client_reader, &mut self.server_writer).await?;
+            return Ok(());
+        }
+
+        while let Some((seq, payload)) = read_packet(&mut self.client_reader).await? {
+            // The commands start with sequence id 0. The queries pipelined before the end of the
+            // authentication are passed on, and rejected by the shim.
+            let load_data = match payload.split_first() {
+                Some((&COM_QUERY, query))
+                    if seq == 0 && self.authenticated.load(Ordering::Acquire) =>
+                {
+                    std::str::from_utf8(query)
+                        .ok()
+                        .and_then(parse_local_load_data)
+                }
+                _ => None,
+            };
+            match load_data {
+                Some(load_data) => self.load_data(load_data).await?,
+                None => write_packet(&mut self.server_writer, seq, &payload).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests the file from the client, loads the file packets into the table, and replies OK
+    /// with the number of loaded rows.
+    async fn load_data(&mut self, load_data: LoadData) -> io::Result<()> {
+        let local_infile = LocalInfile::try_new(
+            self.query_handler.clone(),
+            self.bulk_insert_handler.clone(),
+            self.session.context(),
+            &load_data,
+        )
+        .await;
+        let mut local_infile = match local_infile {
+            Ok(local_infile) => local_infile,
+            Err(e) => return self.write_error(1, e).await,
+        };
+
+        let mut request = vec![LOCAL_INFILE_REQUEST];
+        request.extend_from_slice(load_data.file_name.as_bytes());
+        self.write_to_client(1, &request).await?;
+
+        // The file ends with an empty packet, which is not the continuation of a full one.
+        let mut result = Ok(());
+        let mut continued = false;
+        let seq = loop {
+            let Some((seq, payload)) = read_packet(&mut self.client_reader).await? else {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            };
+            if payload.is_empty() && !continued {
+                break seq;
+            }
+            continued = payload.len() == MAX_PAYLOAD_LEN;
+            // Keeps reading the file after an error, whose packets are already on the way.
+            if result.is_ok() {
+                result = local_infile.push(&payload).await;
+            }
+        };
+        let result = match result {
+            Ok(()) => local_infile.finish().await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(rows) => {
+                let mut ok = vec![0];
+                write_lenenc_int(&mut ok, rows as u64);
+                // The last insert id, the status flags and the number of warnings.
+                write_lenenc_int(&mut ok, 0);
+                ok.extend_from_slice(&SERVER_STATUS_AUTOCOMMIT.to_le_bytes());
+                ok.extend_from_slice(&0u16.to_le_bytes());
+                self.write_to_client(seq.wrapping_add(1), &ok).await
+            }
+            Err(e) => self.write_error(seq.wrapping_add(1), e).await,
+        }
+    }
+
+    async fn write_error(&mut self, seq: u8, error: Error) -> io::Result<()> {
+        error!(error; "Failed to load data from the local file");
+
+        let kind = error_kind(&error);
+        let sqlstate = kind.sqlstate();
+        let mut packet = vec![0xff];
+        packet.extend_from_slice(&(kind as u16).to_le_bytes());
+        packet.push(b'#');
+        packet.extend_from_slice(sqlstate);
+        packet.extend_from_slice(error.to_string().as_bytes());
+        self.write_to_client(seq, &packet).await
+    }
+
+    async fn write_to_client(&mut self, seq: u8, payload: &[u8]) -> io::Result<()> {
+        let mut client_writer = self.client_writer.lock().await;
+        write_packet(&mut *client_writer, seq, payload).await
+    }
+}
+
+/// Reads a packet, and returns its sequence id and payload, or `None` at the end of stream.
+async fn read_packet<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0; 4];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    let mut payload = vec![0; len];
+    let _ = reader.read_exact(&mut payload).await?;
+    Ok(Some((header[3], payload)))
+}
+
+async fn write_packet<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    seq: u8,
+    payload: &[u8],
+) -> io::Result<()> {
+    let len = (payload.len() as u32).to_le_bytes();
+    writer.write_all(&[len[0], len[1], len[2], seq]).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await
+}
+
+fn write_lenenc_int(buf: &mut Vec<u8>, n: u64) {
+    if n < 251 {
+        buf.push(n as u8);
+    } else if n < 1 << 16 {
+        buf.push(0xfc);
+        buf.extend_from_slice(&n.to_le_bytes()[..2]);
+    } else if n < 1 << 24 {
+        buf.push(0xfd);
+        buf.extend_from_slice(&n.to_le_bytes()[..3]);
+    } else {
+        buf.push(0xfe);
+        buf.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_local_files_capability() {
+        let mut handshake = vec![10];
+        handshake.extend_from_slice(b"8.0.26\0");
+        handshake.extend_from_slice(&42u32.to_le_bytes());
+        handshake.extend_from_slice(&[1; 8]);
+        handshake.push(0);
+        handshake.extend_from_slice(&0x0200u16.to_le_bytes());
+        set_local_files_capability(&mut handshake);
+        assert_eq!(&[0x80, 0x02], &handshake[handshake.len() - 2..]);
+
+        // Error packets are left as they are.
+        let mut error = vec![0xff, 0x10, 0x04];
+        set_local_files_capability(&mut error);
+        assert_eq!(vec![0xff, 0x10, 0x04], error);
+    }
+
+    #[test]
+    fn test_write_lenenc_int() {
+        let encode = |n| {
+            let mut buf = vec![];
+            write_lenenc_int(&mut buf, n);
+            buf
+        };
+        assert_eq!(vec![250], encode(250));
+        assert_eq!(vec![0xfc, 251, 0], encode(251));
+        assert_eq!(vec![0xfd, 0, 0, 1], encode(1 << 16));
+        assert_eq!(vec![0xfe, 0, 0, 0, 1, 0, 0, 0, 0], encode(1 << 24));
+    }
+}
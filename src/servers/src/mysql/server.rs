@@ -24,24 +24,29 @@ use opensrv_mysql::{
     plain_run_with_options, secure_run_with_options, AsyncMysqlIntermediary, IntermediaryOptions,
 };
 use tokio;
-use tokio::io::BufWriter;
+use tokio::io::{AsyncRead, AsyncWrite, BufWriter};
 use tokio::net::TcpStream;
 use tokio_rustls::rustls::ServerConfig;
 
 use crate::auth::UserProviderRef;
 use crate::error::{Error, Result};
 use crate::mysql::handler::MysqlInstanceShim;
+use crate::mysql::local_infile;
+use crate::query_handler::grpc::BulkInsertHandlerRef;
 use crate::query_handler::sql::ServerSqlQueryHandlerRef;
 use crate::server::{AbortableStream, BaseTcpServer, Server};
 
 // Default size of ResultSet write buffer: 100KB
 const DEFAULT_RESULT_SET_WRITE_BUFFER_SIZE: usize = 100 * 1024;
+// Size of the in-memory pipe between opensrv-mysql and the relay: 64KB
+const RELAY_PIPE_SIZE: usize = 64 * 1024;
 
 /// [`MysqlSpawnRef`] stores arc refs
 /// that should be passed to new [`MysqlInstanceShim`]s.
 pub struct MysqlSpawnRef {
     query_handler: ServerSqlQueryHandlerRef,
     user_provider: Option<UserProviderRef>,
+    bulk_insert_handler: Option<BulkInsertHandlerRef>,
 }
 
 impl MysqlSpawnRef {
@@ -52,15 +57,26 @@ impl MysqlSpawnRef {
         MysqlSpawnRef {
             query_handler,
             user_provider,
+            bulk_insert_handler: None,
         }
     }
 
+    /// Enables inserting the files of `LOAD DATA LOCAL INFILE`, along with `local_infile` of
+    /// [MysqlSpawnConfig].
+    pub fn with_bulk_insert_handler(mut self, handler: BulkInsertHandlerRef) -> Self {
+        self.bulk_insert_handler = Some(handler);
+        self
+    }
+
     fn query_handler(&self) -> ServerSqlQueryHandlerRef {
         self.query_handler.clone()
     }
     fn user_provider(&self) -> Option<UserProviderRef> {
         self.user_provider.clone()
     }
+    fn bulk_insert_handler(&self) -> Option<BulkInsertHandlerRef> {
+        self.bulk_insert_handler.clone()
+    }
 }
 
 /// [`MysqlSpawnConfig`] stores config values
@@ -71,6 +87,8 @@ pub struct MysqlSpawnConfig {
     tls: Option<Arc<ServerConfig>>,
     // other shim config
     reject_no_database: bool,
+    // serve the connections through the relay of `LOAD DATA LOCAL INFILE`
+    local_infile: bool,
 }
 
 impl MysqlSpawnConfig {
@@ -78,11 +96,13 @@ impl MysqlSpawnConfig {
         force_tls: bool,
         tls: Option<Arc<ServerConfig>>,
         reject_no_database: bool,
+        local_infile: bool,
     ) -> MysqlSpawnConfig {
         MysqlSpawnConfig {
             force_tls,
             tls,
             reject_no_database,
+            local_infile,
         }
     }
 
@@ -171,12 +191,43 @@ impl MysqlServer {
         spawn_ref: Arc<MysqlSpawnRef>,
         spawn_config: Arc<MysqlSpawnConfig>,
     ) -> Result<()> {
-        let mut shim = MysqlInstanceShim::create(
+        let shim = MysqlInstanceShim::create(
             spawn_ref.query_handler(),
             spawn_ref.user_provider(),
             stream.peer_addr()?,
         );
-        let (mut r, w) = stream.into_split();
+        let bulk_insert_handler = match spawn_ref.bulk_insert_handler() {
+            Some(handler) if spawn_config.local_infile => handler,
+            _ => return Self::serve(stream, shim, spawn_config).await,
+        };
+
+        // opensrv-mysql serves the connection through the relay, which runs the packet exchange
+        // of `LOAD DATA LOCAL INFILE`.
+        let (server, relayed) = tokio::io::duplex(RELAY_PIPE_SIZE);
+        let relay = local_infile::relay(
+            stream,
+            relayed,
+            spawn_ref.query_handler(),
+            bulk_insert_handler,
+            shim.session(),
+        );
+
+        let (result, relay_result) = tokio::join!(Self::serve(server, shim, spawn_config), relay);
+        if let Err(e) = relay_result {
+            error!("Failed to relay MySQL packets, error: {}", e);
+        }
+        result
+    }
+
+    async fn serve<S>(
+        stream: S,
+        mut shim: MysqlInstanceShim,
+        spawn_config: Arc<MysqlSpawnConfig>,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Send + Sync + Unpin + 'static,
+    {
+        let (mut r, w) = tokio::io::split(stream);
         let mut w = BufWriter::with_capacity(DEFAULT_RESULT_SET_WRITE_BUFFER_SIZE, w);
 
         let ops = spawn_config.as_ref().into();
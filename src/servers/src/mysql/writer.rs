@@ -14,6 +14,7 @@
 
 use std::ops::Deref;
 
+use common_error::prelude::{ErrorExt, StatusCode};
 use common_query::Output;
 use common_recordbatch::{util, RecordBatch};
 use common_telemetry::error;
@@ -73,14 +74,21 @@ impl<'a, W: AsyncWrite + Unpin> MysqlResultWriter<'a, W> {
             Ok(output) => match output {
                 Output::Stream(stream) => {
                     let schema = stream.schema().clone();
-                    let recordbatches = util::collect(stream)
+                    // The statement may fail after it starts, e.g. killed by `KILL QUERY`, which
+                    // should be told to the client rather than closing the connection.
+                    match util::collect(stream)
                         .await
-                        .context(error::CollectRecordbatchSnafu)?;
-                    let query_result = QueryResult {
-                        recordbatches,
-                        schema,
-                    };
-                    Self::write_query_result(query, query_result, self.writer).await?;
+                        .context(error::CollectRecordbatchSnafu)
+                    {
+                        Ok(recordbatches) => {
+                            let query_result = QueryResult {
+                                recordbatches,
+                                schema,
+                            };
+                            Self::write_query_result(query, query_result, self.writer).await?;
+                        }
+                        Err(error) => Self::write_query_error(query, error, self.writer).await?,
+                    }
                 }
                 Output::RecordBatches(recordbatches) => {
                     let query_result = QueryResult {
@@ -184,12 +192,21 @@ impl<'a, W: AsyncWrite + Unpin> MysqlResultWriter<'a, W> {
     ) -> Result<()> {
         error!(error; "Failed to execute query '{}'", query);
 
-        let kind = ErrorKind::ER_INTERNAL_ERROR;
-        w.error(kind, error.to_string().as_bytes()).await?;
+        w.error(error_kind(&error), error.to_string().as_bytes())
+            .await?;
         Ok(())
     }
 }
 
+/// Maps the status code of an error to the MySQL error.
+pub(crate) fn error_kind(error: &Error) -> ErrorKind {
+    match error.status_code() {
+        StatusCode::Cancelled | StatusCode::QueryTimeout => ErrorKind::ER_QUERY_INTERRUPTED,
+        StatusCode::ExceedMemoryLimit => ErrorKind::ER_OUT_OF_RESOURCES,
+        _ => ErrorKind::ER_INTERNAL_ERROR,
+    }
+}
+
 fn create_mysql_column(column_schema: &ColumnSchema) -> Result<Column> {
     let column_type = match column_schema.data_type {
         ConcreteDataType::Null(_) => Ok(ColumnType::MYSQL_TYPE_NULL),
@@ -0,0 +1,1349 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The read path of OpenTSDB's HTTP API, which queries the tables written by
+//! [DataPoint::as_grpc_insert](crate::opentsdb::codec::DataPoint::as_grpc_insert).
+//!
+//! Each distinct combination of tags in a metric's table is a time series. A query downsamples
+//! the series in SQL, then computes the rates and aggregates the series of each group.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use common_time::timestamp::TimeUnit;
+use common_time::util::current_time_millis;
+use datatypes::value::Value;
+use serde::{Deserialize, Serialize};
+use session::context::QueryContextRef;
+use snafu::{ensure, OptionExt};
+
+use crate::error::{InvalidOpentsdbQuerySnafu, Result};
+use crate::opentsdb::codec::{DataPoint, OPENTSDB_FIELD_COLUMN_NAME};
+use crate::query_handler::sql::{
+    describe_table, query_recordbatches, query_strings, quote_ident, quote_string, value_to_string,
+    ServerSqlQueryHandlerRef, TableColumns,
+};
+
+const MILLIS_PER_SECOND: i64 = 1_000;
+const MILLIS_PER_MINUTE: i64 = 60 * MILLIS_PER_SECOND;
+const MILLIS_PER_HOUR: i64 = 60 * MILLIS_PER_MINUTE;
+const MILLIS_PER_DAY: i64 = 24 * MILLIS_PER_HOUR;
+
+/// The maximum number of points in a series filled by the downsampling fill policy.
+const MAX_FILLED_POINTS: i64 = 100_000;
+const DEFAULT_SUGGEST_MAX: usize = 25;
+const DEFAULT_LOOKUP_LIMIT: usize = 25;
+
+/// An absolute time in seconds or milliseconds, or a time string like `1h-ago` and
+/// `2023/01/01-00:00:00`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum TimeSpec {
+    Number(i64),
+    String(String),
+}
+
+// http://opentsdb.net/docs/build/html/api_http/query/index.html
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+    pub start: TimeSpec,
+    pub end: Option<TimeSpec>,
+    pub queries: Vec<SubQuery>,
+    #[serde(default)]
+    pub ms_resolution: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubQuery {
+    pub aggregator: String,
+    pub metric: String,
+    #[serde(default)]
+    pub rate: bool,
+    pub rate_options: Option<RateOptions>,
+    pub downsample: Option<String>,
+    /// The tags in the form before OpenTSDB 2.2, whose values may be filters like `*`,
+    /// `a|b` and `wildcard(a*)`. They are all grouped by.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateOptions {
+    #[serde(default)]
+    pub counter: bool,
+    pub counter_max: Option<f64>,
+    pub reset_value: Option<f64>,
+    #[serde(default)]
+    pub drop_resets: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Filter {
+    #[serde(rename = "type")]
+    pub filter_type: String,
+    pub tagk: String,
+    pub filter: String,
+    #[serde(default)]
+    pub group_by: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub metric: String,
+    /// The tags that have the same value in all the aggregated series.
+    pub tags: BTreeMap<String, String>,
+    /// The tags whose values differ in the aggregated series.
+    pub aggregate_tags: Vec<String>,
+    /// The data points keyed by seconds, or by milliseconds if `msResolution` is set.
+    pub dps: BTreeMap<i64, f64>,
+}
+
+// http://opentsdb.net/docs/build/html/api_http/suggest.html
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SuggestRequest {
+    #[serde(rename = "type")]
+    pub suggest_type: String,
+    #[serde(default)]
+    pub q: String,
+    pub max: Option<usize>,
+}
+
+// http://opentsdb.net/docs/build/html/api_http/search/lookup.html
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupRequest {
+    pub metric: String,
+    #[serde(default)]
+    pub tags: Vec<LookupTag>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub start_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LookupTag {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupResponse {
+    #[serde(rename = "type")]
+    pub lookup_type: String,
+    pub metric: String,
+    pub tags: Vec<LookupTag>,
+    pub limit: usize,
+    /// The elapsed time of the lookup, in milliseconds.
+    pub time: u128,
+    pub results: Vec<LookupResult>,
+    pub start_index: usize,
+    pub total_results: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LookupResult {
+    pub metric: String,
+    pub tags: BTreeMap<String, String>,
+    /// The UIDs of series are not assigned, so it's always empty.
+    pub tsuid: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregator {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+    Dev,
+    /// The percentile in `[0, 1]`, which includes the median.
+    Percentile(f64),
+    First,
+    Last,
+    /// Every series is returned without aggregation.
+    None,
+}
+
+impl Aggregator {
+    pub fn parse(name: &str) -> Result<Self> {
+        let aggregator = match name {
+            "sum" | "zimsum" => Aggregator::Sum,
+            "avg" => Aggregator::Avg,
+            "min" | "mimmin" => Aggregator::Min,
+            "max" | "mimmax" => Aggregator::Max,
+            "count" => Aggregator::Count,
+            "dev" => Aggregator::Dev,
+            "median" | "p50" => Aggregator::Percentile(0.5),
+            "p75" => Aggregator::Percentile(0.75),
+            "p90" => Aggregator::Percentile(0.9),
+            "p95" => Aggregator::Percentile(0.95),
+            "p99" => Aggregator::Percentile(0.99),
+            "p999" => Aggregator::Percentile(0.999),
+            "first" => Aggregator::First,
+            "last" => Aggregator::Last,
+            "none" => Aggregator::None,
+            _ => {
+                return InvalidOpentsdbQuerySnafu {
+                    reason: format!("unsupported aggregator: {name}"),
+                }
+                .fail()
+            }
+        };
+        Ok(aggregator)
+    }
+
+    /// Returns the SQL aggregation of the column, which is used by downsampling.
+    fn to_sql(self, column: &str) -> Result<String> {
+        let sql = match self {
+            Aggregator::Sum => format!("sum({column})"),
+            Aggregator::Avg => format!("avg({column})"),
+            Aggregator::Min => format!("min({column})"),
+            Aggregator::Max => format!("max({column})"),
+            Aggregator::Count => format!("count({column})"),
+            Aggregator::Dev => format!("stddev_pop({column})"),
+            Aggregator::Percentile(p) if p == 0.5 => format!("median({column})"),
+            Aggregator::Percentile(p) => {
+                format!("approx_percentile_cont(CAST({column} AS DOUBLE), {p:?})")
+            }
+            Aggregator::First | Aggregator::Last | Aggregator::None => {
+                return InvalidOpentsdbQuerySnafu {
+                    reason: format!("unsupported downsampling aggregator: {self:?}"),
+                }
+                .fail()
+            }
+        };
+        Ok(sql)
+    }
+
+    /// Aggregates the values of the series at the same timestamp, which are in the order of
+    /// the series.
+    fn aggregate(self, values: &mut [f64]) -> f64 {
+        if values.is_empty() {
+            return f64::NAN;
+        }
+        let n = values.len() as f64;
+        match self {
+            Aggregator::Sum => values.iter().sum(),
+            Aggregator::Avg => values.iter().sum::<f64>() / n,
+            Aggregator::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregator::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregator::Count => n,
+            Aggregator::Dev => {
+                let mean = values.iter().sum::<f64>() / n;
+                (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt()
+            }
+            Aggregator::Percentile(p) => {
+                values.sort_by(|a, b| a.total_cmp(b));
+                let rank = p * (n - 1.0);
+                let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+                values[lower] + (values[upper] - values[lower]) * (rank - rank.floor())
+            }
+            Aggregator::First | Aggregator::None => values[0],
+            Aggregator::Last => values[values.len() - 1],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillPolicy {
+    None,
+    /// Fills NaN, which is serialized as null like the `null` policy.
+    Nan,
+    Null,
+    Zero,
+}
+
+/// A downsampling specification like `1m-avg` or `1h-sum-zero`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Downsample {
+    /// The interval in milliseconds, or `None` for `0all`, which downsamples all the points
+    /// into one.
+    pub interval: Option<i64>,
+    pub aggregator: Aggregator,
+    pub fill: FillPolicy,
+}
+
+impl Downsample {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || {
+            InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid downsample specifier: {spec}"),
+            }
+            .build()
+        };
+        let mut parts = spec.split('-');
+        let interval = parts.next().ok_or_else(invalid)?;
+        let interval = if interval.ends_with("all") {
+            None
+        } else {
+            Some(parse_duration(interval)?)
+        };
+        ensure!(
+            interval.map_or(true, |i| i > 0),
+            InvalidOpentsdbQuerySnafu {
+                reason: format!("the downsample interval must be positive: {spec}"),
+            }
+        );
+        let aggregator = Aggregator::parse(parts.next().ok_or_else(invalid)?)?;
+        let fill = match parts.next() {
+            None | Some("none") => FillPolicy::None,
+            Some("nan") => FillPolicy::Nan,
+            Some("null") => FillPolicy::Null,
+            Some("zero") => FillPolicy::Zero,
+            Some(_) => return Err(invalid()),
+        };
+        ensure!(
+            parts.next().is_none(),
+            InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid downsample specifier: {spec}"),
+            }
+        );
+        Ok(Downsample {
+            interval,
+            aggregator,
+            fill,
+        })
+    }
+}
+
+impl SubQuery {
+    /// Parses the metric query in the `m` parameter of `GET /api/query`, which is like
+    /// `sum:rate{counter,,1000}:1m-avg:sys.cpu{host=*}{dc=literal_or(lga|lgb)}`.
+    pub fn parse(m: &str) -> Result<Self> {
+        let segments = split_outside_brackets(m, ':');
+        ensure!(
+            segments.len() >= 2,
+            InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid metric query: {m}"),
+            }
+        );
+
+        let mut query = SubQuery {
+            aggregator: segments[0].to_string(),
+            ..Default::default()
+        };
+        for segment in &segments[1..segments.len() - 1] {
+            if let Some(options) = segment.strip_prefix("rate") {
+                query.rate = true;
+                query.rate_options = parse_rate_options(options)?;
+            } else if segment.contains('-') {
+                query.downsample = Some(segment.to_string());
+            } else if *segment != "explicit_tags" {
+                return InvalidOpentsdbQuerySnafu {
+                    reason: format!("invalid metric query: {m}"),
+                }
+                .fail();
+            }
+        }
+
+        let metric = segments[segments.len() - 1];
+        let (name, mut groups) = match metric.find('{') {
+            Some(i) => (&metric[..i], &metric[i..]),
+            None => (metric, ""),
+        };
+        query.metric = name.to_string();
+        // The first braces are the grouped filters, and the second are the others.
+        let mut group_by = true;
+        while let Some(rest) = groups.strip_prefix('{') {
+            let end = rest.find('}').with_context(|| InvalidOpentsdbQuerySnafu {
+                reason: format!("unclosed braces in metric query: {m}"),
+            })?;
+            for pair in split_outside_brackets(&rest[..end], ',') {
+                if pair.is_empty() {
+                    continue;
+                }
+                let (tagk, value) =
+                    pair.split_once('=')
+                        .with_context(|| InvalidOpentsdbQuerySnafu {
+                            reason: format!("invalid tag filter: {pair}"),
+                        })?;
+                query.filters.push(parse_tag_filter(tagk, value, group_by));
+            }
+            groups = &rest[end + 1..];
+            group_by = false;
+        }
+        ensure!(
+            groups.is_empty(),
+            InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid metric query: {m}"),
+            }
+        );
+        Ok(query)
+    }
+
+    /// Returns the filters, including the ones in the tags.
+    fn all_filters(&self) -> Vec<Filter> {
+        self.tags
+            .iter()
+            .map(|(tagk, value)| parse_tag_filter(tagk, value, true))
+            .chain(self.filters.iter().cloned())
+            .collect()
+    }
+}
+
+/// Parses the rate options like `{counter,1000,0}` in a metric query.
+fn parse_rate_options(options: &str) -> Result<Option<RateOptions>> {
+    if options.is_empty() {
+        return Ok(None);
+    }
+    let options = options
+        .strip_prefix('{')
+        .and_then(|options| options.strip_suffix('}'))
+        .with_context(|| InvalidOpentsdbQuerySnafu {
+            reason: format!("invalid rate options: {options}"),
+        })?;
+    let parse_number = |s: &str| -> Result<Option<f64>> {
+        if s.is_empty() {
+            return Ok(None);
+        }
+        s.parse()
+            .map(Some)
+            .ok()
+            .with_context(|| InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid number in rate options: {s}"),
+            })
+    };
+
+    let parts = options.split(',').collect::<Vec<_>>();
+    let mut rate_options = RateOptions::default();
+    match parts[0] {
+        "counter" => rate_options.counter = true,
+        "dropcounter" => {
+            rate_options.counter = true;
+            rate_options.drop_resets = true;
+        }
+        "" => {}
+        option => {
+            return InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid rate option: {option}"),
+            }
+            .fail()
+        }
+    }
+    if let Some(counter_max) = parts.get(1) {
+        rate_options.counter_max = parse_number(counter_max)?;
+    }
+    if let Some(reset_value) = parts.get(2) {
+        rate_options.reset_value = parse_number(reset_value)?;
+    }
+    Ok(Some(rate_options))
+}
+
+/// Parses a tag value like `*`, `a|b`, `web*` or `regexp(web.*)` into a filter.
+fn parse_tag_filter(tagk: &str, value: &str, group_by: bool) -> Filter {
+    let explicit = value.split_once('(').and_then(|(filter_type, rest)| {
+        let is_type = !filter_type.is_empty()
+            && filter_type
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let filter = rest.strip_suffix(')')?;
+        is_type.then(|| (filter_type.to_string(), filter.to_string()))
+    });
+    let (filter_type, filter) = match explicit {
+        Some(explicit) => explicit,
+        None if value.contains('*') => ("wildcard".to_string(), value.to_string()),
+        None => ("literal_or".to_string(), value.to_string()),
+    };
+    Filter {
+        filter_type,
+        tagk: tagk.to_string(),
+        filter,
+        group_by,
+    }
+}
+
+/// Splits the string by the separator, except the ones inside brackets.
+fn split_outside_brackets(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Translates the filter into a SQL condition. All filters require the tag to be present.
+fn filter_to_sql(filter: &Filter) -> Result<String> {
+    let column = quote_ident(&filter.tagk);
+    let literals = |lowercase: bool| {
+        filter
+            .filter
+            .split('|')
+            .map(|v| {
+                if lowercase {
+                    quote_string(&v.to_lowercase())
+                } else {
+                    quote_string(v)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let pattern = |lowercase: bool| {
+        let pattern = filter.filter.replace('*', "%");
+        if lowercase {
+            quote_string(&pattern.to_lowercase())
+        } else {
+            quote_string(&pattern)
+        }
+    };
+
+    let sql = match filter.filter_type.as_str() {
+        "literal_or" => format!("{column} IN ({})", literals(false)),
+        "iliteral_or" => format!("lower({column}) IN ({})", literals(true)),
+        "not_literal_or" => format!("{column} NOT IN ({})", literals(false)),
+        "not_iliteral_or" => format!("lower({column}) NOT IN ({})", literals(true)),
+        "wildcard" | "iwildcard" if filter.filter == "*" => format!("{column} IS NOT NULL"),
+        "wildcard" => format!("{column} LIKE {}", pattern(false)),
+        "iwildcard" => format!("lower({column}) LIKE {}", pattern(true)),
+        "regexp" => format!("{column} ~ {}", quote_string(&filter.filter)),
+        filter_type => {
+            return InvalidOpentsdbQuerySnafu {
+                reason: format!("unsupported filter type: {filter_type}"),
+            }
+            .fail()
+        }
+    };
+    Ok(format!("({column} IS NOT NULL AND {sql})"))
+}
+
+/// Parses a time like `1h-ago`, `2023/01/01-00:00:00` or a timestamp into milliseconds.
+pub fn parse_time(spec: &TimeSpec, now: i64) -> Result<i64> {
+    let s = match spec {
+        TimeSpec::Number(t) => return Ok(DataPoint::timestamp_to_millis(*t)),
+        TimeSpec::String(s) => s.trim(),
+    };
+    if let Some(duration) = s.strip_suffix("-ago") {
+        return Ok(now - parse_duration(duration)?);
+    }
+    if let Ok(t) = s.parse::<i64>() {
+        return Ok(DataPoint::timestamp_to_millis(t));
+    }
+    // Seconds with milliseconds, like `1356998400.123`.
+    if let Some((secs, millis)) = s.split_once('.') {
+        if let (Ok(secs), Ok(millis)) = (secs.parse::<i64>(), millis.parse::<i64>()) {
+            return Ok(secs * MILLIS_PER_SECOND + millis);
+        }
+    }
+
+    // The absolute times are in UTC.
+    const FORMATS: [&str; 4] = [
+        "%Y/%m/%d-%H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y/%m/%d-%H:%M",
+        "%Y/%m/%d %H:%M",
+    ];
+    FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y/%m/%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+        .map(|datetime| datetime.timestamp_millis())
+        .with_context(|| InvalidOpentsdbQuerySnafu {
+            reason: format!("invalid time: {s}"),
+        })
+}
+
+/// Parses a duration like `5m` into milliseconds. The months and years are 30 and 365 days.
+fn parse_duration(s: &str) -> Result<i64> {
+    let invalid = || {
+        InvalidOpentsdbQuerySnafu {
+            reason: format!("invalid duration: {s}"),
+        }
+        .build()
+    };
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = s.split_at(unit_start);
+    let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+    let unit_millis = match unit {
+        "ms" => 1,
+        "s" => MILLIS_PER_SECOND,
+        "m" => MILLIS_PER_MINUTE,
+        "h" => MILLIS_PER_HOUR,
+        "d" => MILLIS_PER_DAY,
+        "w" => 7 * MILLIS_PER_DAY,
+        "n" => 30 * MILLIS_PER_DAY,
+        "y" => 365 * MILLIS_PER_DAY,
+        _ => return Err(invalid()),
+    };
+    amount.checked_mul(unit_millis).ok_or_else(invalid)
+}
+
+/// A time series, which is identified by its tag values in the order of the table's tags.
+struct TimeSeries {
+    tags: Vec<Option<String>>,
+    /// The points in milliseconds, sorted by time.
+    points: Vec<(i64, f64)>,
+}
+
+/// Executes the queries in the request.
+pub async fn execute_query(
+    handler: &ServerSqlQueryHandlerRef,
+    request: &QueryRequest,
+    ctx: QueryContextRef,
+) -> Result<Vec<QueryResult>> {
+    let now = current_time_millis();
+    let start = parse_time(&request.start, now)?;
+    let end = match &request.end {
+        Some(end) => parse_time(end, now)?,
+        None => now,
+    };
+    ensure!(
+        start <= end,
+        InvalidOpentsdbQuerySnafu {
+            reason: "the start time must not be after the end time",
+        }
+    );
+
+    let mut results = Vec::new();
+    for query in &request.queries {
+        let series = query_series(handler, query, start, end, ctx.clone()).await?;
+        results.extend(aggregate_series(query, series, request.ms_resolution)?);
+    }
+    Ok(results)
+}
+
+/// Queries the downsampled series of a metric, with the rates computed if required.
+async fn query_series(
+    handler: &ServerSqlQueryHandlerRef,
+    query: &SubQuery,
+    start: i64,
+    end: i64,
+    ctx: QueryContextRef,
+) -> Result<(Vec<String>, Vec<TimeSeries>)> {
+    // Validates the query before finding the table.
+    let downsample = query
+        .downsample
+        .as_deref()
+        .map(Downsample::parse)
+        .transpose()?;
+    let _ = Aggregator::parse(&query.aggregator)?;
+    let filters = query
+        .all_filters()
+        .iter()
+        .map(|filter| Ok((filter.tagk.clone(), filter_to_sql(filter)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let Some(table) = describe_table(handler, &query.metric, ctx.clone()).await? else {
+        return Ok((vec![], vec![]));
+    };
+    let Some(value_column) = value_column(&table) else {
+        return Ok((vec![], vec![]));
+    };
+    // No series has the tags that are not in the table.
+    if filters.iter().any(|(tagk, _)| !table.tags.contains(tagk)) {
+        return Ok((table.tags, vec![]));
+    }
+
+    let time_index = quote_ident(&table.time_index);
+    let value_column = quote_ident(value_column);
+    let tags = table
+        .tags
+        .iter()
+        .map(|tag| quote_ident(tag))
+        .collect::<Vec<_>>();
+    let mut conditions = vec![
+        format!("{time_index} >= {}", millis_to_unit(start, table.time_unit)),
+        format!("{time_index} <= {}", millis_to_unit(end, table.time_unit)),
+    ];
+    conditions.extend(filters.into_iter().map(|(_, sql)| sql));
+
+    let (time_expr, value_expr) = match &downsample {
+        Some(downsample) => {
+            let value_expr = downsample.aggregator.to_sql(&value_column)?;
+            let time_expr = downsample.interval.map(|interval| {
+                let step = millis_to_unit(interval, table.time_unit).max(1);
+                format!("CAST({time_index} AS BIGINT) / {step} * {step}")
+            });
+            (time_expr, value_expr)
+        }
+        None => (Some(time_index.clone()), value_column),
+    };
+
+    let mut items = time_expr.iter().cloned().collect::<Vec<_>>();
+    items.extend(tags.iter().cloned());
+    items.push(value_expr);
+    let mut sql = format!(
+        "SELECT {} FROM {} WHERE {}",
+        items.join(", "),
+        quote_ident(&query.metric),
+        conditions.join(" AND ")
+    );
+    let mut keys = time_expr.iter().cloned().collect::<Vec<_>>();
+    keys.extend(tags.iter().cloned());
+    if downsample.is_some() && !keys.is_empty() {
+        sql.push_str(&format!(" GROUP BY {}", keys.join(", ")));
+    }
+    let mut order_by = tags;
+    order_by.extend(time_expr.clone());
+    if !order_by.is_empty() {
+        sql.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+    }
+
+    let mut series: Vec<TimeSeries> = Vec::new();
+    for batch in query_recordbatches(handler, &sql, ctx).await? {
+        let columns = batch.columns();
+        let tags_start = usize::from(time_expr.is_some());
+        let value_index = columns.len() - 1;
+        for row in 0..batch.num_rows() {
+            let Some(value) = value_to_f64(columns[value_index].get(row)) else {
+                continue;
+            };
+            let time = if time_expr.is_some() {
+                match columns[0].get(row) {
+                    Value::Timestamp(ts) => {
+                        ts.convert_to(TimeUnit::Millisecond).map(|ts| ts.value())
+                    }
+                    Value::Int64(v) => Some(unit_to_millis(v, table.time_unit)),
+                    _ => None,
+                }
+            } else {
+                Some(start)
+            };
+            let Some(time) = time else {
+                continue;
+            };
+            let tags = columns[tags_start..value_index]
+                .iter()
+                .map(|column| value_to_string(column.get(row)))
+                .collect::<Vec<_>>();
+            match series.last_mut() {
+                Some(last) if last.tags == tags => last.points.push((time, value)),
+                _ => series.push(TimeSeries {
+                    tags,
+                    points: vec![(time, value)],
+                }),
+            }
+        }
+    }
+
+    if let Some(Downsample {
+        interval: Some(interval),
+        fill,
+        ..
+    }) = downsample
+    {
+        if fill != FillPolicy::None {
+            for series in &mut series {
+                series.points = fill_points(&series.points, start, end, interval, fill)?;
+            }
+        }
+    }
+    if query.rate {
+        let options = query.rate_options.clone().unwrap_or_default();
+        for series in &mut series {
+            series.points = rate(&series.points, &options);
+        }
+    }
+    Ok((table.tags, series))
+}
+
+/// Groups the series by the tags of the grouped filters, and aggregates each group.
+fn aggregate_series(
+    query: &SubQuery,
+    (tag_names, series): (Vec<String>, Vec<TimeSeries>),
+    ms_resolution: bool,
+) -> Result<Vec<QueryResult>> {
+    let aggregator = Aggregator::parse(&query.aggregator)?;
+    let group_by = query
+        .all_filters()
+        .into_iter()
+        .filter(|filter| filter.group_by)
+        .filter_map(|filter| tag_names.iter().position(|tag| *tag == filter.tagk))
+        .collect::<BTreeSet<_>>();
+
+    // The series are keyed by their indexes too if they are not aggregated.
+    let mut groups: BTreeMap<(usize, Vec<Option<&str>>), Vec<&TimeSeries>> = BTreeMap::new();
+    for (i, series) in series.iter().enumerate() {
+        let index = if aggregator == Aggregator::None { i } else { 0 };
+        let tags = group_by
+            .iter()
+            .map(|i| series.tags[*i].as_deref())
+            .collect();
+        groups.entry((index, tags)).or_default().push(series);
+    }
+
+    let mut results = Vec::with_capacity(groups.len());
+    for group in groups.into_values() {
+        let mut points: BTreeMap<i64, Vec<f64>> = BTreeMap::new();
+        for series in &group {
+            for (time, value) in &series.points {
+                let values = points.entry(*time).or_default();
+                // The missing values filled by NaN are skipped.
+                if !value.is_nan() {
+                    values.push(*value);
+                }
+            }
+        }
+        if points.is_empty() {
+            continue;
+        }
+        let dps = points
+            .into_iter()
+            .map(|(time, mut values)| {
+                let time = if ms_resolution {
+                    time
+                } else {
+                    time.div_euclid(MILLIS_PER_SECOND)
+                };
+                (time, aggregator.aggregate(&mut values))
+            })
+            .collect();
+
+        let mut tags = BTreeMap::new();
+        let mut aggregate_tags = Vec::new();
+        for (i, name) in tag_names.iter().enumerate() {
+            let values = group
+                .iter()
+                .map(|series| series.tags[i].as_deref())
+                .collect::<BTreeSet<_>>();
+            match values.into_iter().collect::<Vec<_>>()[..] {
+                [Some(value)] => {
+                    let _ = tags.insert(name.clone(), value.to_string());
+                }
+                [None] => {}
+                _ => aggregate_tags.push(name.clone()),
+            }
+        }
+        results.push(QueryResult {
+            metric: query.metric.clone(),
+            tags,
+            aggregate_tags,
+            dps,
+        });
+    }
+    Ok(results)
+}
+
+/// Fills the missing buckets of a downsampled series between `start` and `end`.
+fn fill_points(
+    points: &[(i64, f64)],
+    start: i64,
+    end: i64,
+    interval: i64,
+    fill: FillPolicy,
+) -> Result<Vec<(i64, f64)>> {
+    let start = start.div_euclid(interval) * interval;
+    let end = end.div_euclid(interval) * interval;
+    ensure!(
+        (end - start) / interval < MAX_FILLED_POINTS,
+        InvalidOpentsdbQuerySnafu {
+            reason: "too many points to fill, maybe the downsample interval is too small",
+        }
+    );
+
+    let fill_value = if fill == FillPolicy::Zero {
+        0.0
+    } else {
+        f64::NAN
+    };
+    let mut filled: BTreeMap<i64, f64> = points.iter().copied().collect();
+    let mut time = start;
+    while time <= end {
+        let _ = filled.entry(time).or_insert(fill_value);
+        time += interval;
+    }
+    Ok(filled.into_iter().collect())
+}
+
+/// Computes the rates per second of the points.
+fn rate(points: &[(i64, f64)], options: &RateOptions) -> Vec<(i64, f64)> {
+    let mut rates = Vec::with_capacity(points.len().saturating_sub(1));
+    let mut previous: Option<(i64, f64)> = None;
+    for &(time, value) in points {
+        if value.is_nan() {
+            rates.push((time, value));
+            continue;
+        }
+        let Some((previous_time, previous_value)) = previous.replace((time, value)) else {
+            continue;
+        };
+        let seconds = (time - previous_time) as f64 / MILLIS_PER_SECOND as f64;
+        let mut delta = value - previous_value;
+        if options.counter && delta < 0.0 {
+            if options.drop_resets {
+                continue;
+            }
+            delta += options.counter_max.unwrap_or(u64::MAX as f64);
+        }
+        let mut rate = delta / seconds;
+        if options.counter && options.reset_value.map_or(false, |r| r > 0.0 && rate > r) {
+            rate = 0.0;
+        }
+        rates.push((time, rate));
+    }
+    rates
+}
+
+/// Returns the column of the values, which is the only field of the tables written by
+/// OpenTSDB.
+fn value_column(table: &TableColumns) -> Option<&str> {
+    table
+        .fields
+        .iter()
+        .find(|(name, _)| name == OPENTSDB_FIELD_COLUMN_NAME)
+        .or_else(|| table.fields.first())
+        .map(|(name, _)| name.as_str())
+}
+
+fn value_to_f64(value: Value) -> Option<f64> {
+    let value = match value {
+        Value::Float64(v) => v.0,
+        Value::Float32(v) => v.0 as f64,
+        Value::Int64(v) => v as f64,
+        Value::Int32(v) => v as f64,
+        Value::UInt64(v) => v as f64,
+        Value::UInt32(v) => v as f64,
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn millis_to_unit(millis: i64, unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => millis.div_euclid(MILLIS_PER_SECOND),
+        TimeUnit::Millisecond => millis,
+        TimeUnit::Microsecond => millis.saturating_mul(1_000),
+        TimeUnit::Nanosecond => millis.saturating_mul(1_000_000),
+    }
+}
+
+fn unit_to_millis(value: i64, unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => value.saturating_mul(MILLIS_PER_SECOND),
+        TimeUnit::Millisecond => value,
+        TimeUnit::Microsecond => value.div_euclid(1_000),
+        TimeUnit::Nanosecond => value.div_euclid(1_000_000),
+    }
+}
+
+/// Suggests the names of metrics, tag keys or tag values with the prefix.
+pub async fn execute_suggest(
+    handler: &ServerSqlQueryHandlerRef,
+    request: &SuggestRequest,
+    ctx: QueryContextRef,
+) -> Result<Vec<String>> {
+    let max = request.max.unwrap_or(DEFAULT_SUGGEST_MAX);
+    let prefix = request.q.as_str();
+    let metrics = query_strings(handler, "SHOW TABLES", ctx.clone()).await?;
+
+    let mut suggestions = BTreeSet::new();
+    match request.suggest_type.as_str() {
+        "metrics" => suggestions.extend(metrics.into_iter().filter(|m| m.starts_with(prefix))),
+        "tagk" => {
+            for metric in metrics {
+                if let Some(table) = describe_table(handler, &metric, ctx.clone()).await? {
+                    suggestions.extend(table.tags.into_iter().filter(|t| t.starts_with(prefix)));
+                }
+            }
+        }
+        "tagv" => {
+            let pattern = quote_string(&format!("{prefix}%"));
+            for metric in metrics {
+                let Some(table) = describe_table(handler, &metric, ctx.clone()).await? else {
+                    continue;
+                };
+                for tag in table.tags {
+                    let column = quote_ident(&tag);
+                    let sql = format!(
+                        "SELECT DISTINCT {column} FROM {} WHERE {column} LIKE {pattern}",
+                        quote_ident(&metric)
+                    );
+                    suggestions.extend(query_strings(handler, &sql, ctx.clone()).await?);
+                }
+            }
+        }
+        suggest_type => {
+            return InvalidOpentsdbQuerySnafu {
+                reason: format!("invalid suggest type: {suggest_type}"),
+            }
+            .fail()
+        }
+    }
+    Ok(suggestions.into_iter().take(max).collect())
+}
+
+impl LookupRequest {
+    /// Parses the metric query in the `m` parameter of `GET /api/search/lookup`, which is like
+    /// `sys.cpu{host=*}`.
+    pub fn parse(m: &str, limit: Option<usize>, start_index: usize) -> Result<Self> {
+        let (metric, tags) = match m.split_once('{') {
+            Some((metric, tags)) => {
+                let tags = tags
+                    .strip_suffix('}')
+                    .with_context(|| InvalidOpentsdbQuerySnafu {
+                        reason: format!("unclosed braces in lookup query: {m}"),
+                    })?;
+                (metric, tags)
+            }
+            None => (m, ""),
+        };
+        let tags = tags
+            .split(',')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) =
+                    pair.split_once('=')
+                        .with_context(|| InvalidOpentsdbQuerySnafu {
+                            reason: format!("invalid tag in lookup query: {pair}"),
+                        })?;
+                Ok(LookupTag {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(LookupRequest {
+            metric: metric.to_string(),
+            tags,
+            limit,
+            start_index,
+        })
+    }
+}
+
+/// Looks up the series of the metric, or all metrics if it's `*`, matching the tags.
+pub async fn execute_lookup(
+    handler: &ServerSqlQueryHandlerRef,
+    request: LookupRequest,
+    ctx: QueryContextRef,
+) -> Result<LookupResponse> {
+    let timer = Instant::now();
+    let metrics = if request.metric == "*" {
+        query_strings(handler, "SHOW TABLES", ctx.clone()).await?
+    } else {
+        vec![request.metric.clone()]
+    };
+
+    let mut results = Vec::new();
+    for metric in metrics {
+        let Some(table) = describe_table(handler, &metric, ctx.clone()).await? else {
+            continue;
+        };
+        let mut conditions = Vec::with_capacity(request.tags.len());
+        for tag in &request.tags {
+            // A key of `*` matches the value in any tag.
+            let keys = if tag.key == "*" {
+                table.tags.iter().collect::<Vec<_>>()
+            } else {
+                table.tags.iter().filter(|t| **t == tag.key).collect()
+            };
+            let condition = keys
+                .iter()
+                .map(|key| {
+                    let column = quote_ident(key);
+                    if tag.value == "*" {
+                        format!("{column} IS NOT NULL")
+                    } else {
+                        format!("{column} = {}", quote_string(&tag.value))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            conditions.push(if condition.is_empty() {
+                "false".to_string()
+            } else {
+                format!("({condition})")
+            });
+        }
+        if table.tags.is_empty() {
+            continue;
+        }
+
+        let columns = table
+            .tags
+            .iter()
+            .map(|tag| quote_ident(tag))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut sql = format!("SELECT DISTINCT {columns} FROM {}", quote_ident(&metric));
+        if !conditions.is_empty() {
+            sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        sql.push_str(&format!(" ORDER BY {columns}"));
+        for batch in query_recordbatches(handler, &sql, ctx.clone()).await? {
+            for row in 0..batch.num_rows() {
+                let tags = table
+                    .tags
+                    .iter()
+                    .zip(batch.columns())
+                    .filter_map(|(name, column)| {
+                        Some((name.clone(), value_to_string(column.get(row))?))
+                    })
+                    .collect();
+                results.push(LookupResult {
+                    metric: metric.clone(),
+                    tags,
+                    tsuid: String::new(),
+                });
+            }
+        }
+    }
+
+    let limit = request.limit.unwrap_or(DEFAULT_LOOKUP_LIMIT);
+    let total_results = results.len();
+    let results = results
+        .into_iter()
+        .skip(request.start_index)
+        .take(limit)
+        .collect();
+    Ok(LookupResponse {
+        lookup_type: "LOOKUP".to_string(),
+        metric: request.metric,
+        tags: request.tags,
+        limit,
+        time: timer.elapsed().as_millis(),
+        results,
+        start_index: request.start_index,
+        total_results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metric_query() {
+        let query = SubQuery::parse(
+            "sum:rate{counter,,1000}:1m-avg-zero:sys.cpu{host=*}{dc=literal_or(a|b),env=prod}",
+        )
+        .unwrap();
+        assert_eq!(
+            SubQuery {
+                aggregator: "sum".to_string(),
+                metric: "sys.cpu".to_string(),
+                rate: true,
+                rate_options: Some(RateOptions {
+                    counter: true,
+                    counter_max: None,
+                    reset_value: Some(1000.0),
+                    drop_resets: false,
+                }),
+                downsample: Some("1m-avg-zero".to_string()),
+                tags: BTreeMap::new(),
+                filters: vec![
+                    Filter {
+                        filter_type: "wildcard".to_string(),
+                        tagk: "host".to_string(),
+                        filter: "*".to_string(),
+                        group_by: true,
+                    },
+                    Filter {
+                        filter_type: "literal_or".to_string(),
+                        tagk: "dc".to_string(),
+                        filter: "a|b".to_string(),
+                        group_by: false,
+                    },
+                    Filter {
+                        filter_type: "literal_or".to_string(),
+                        tagk: "env".to_string(),
+                        filter: "prod".to_string(),
+                        group_by: false,
+                    },
+                ],
+            },
+            query
+        );
+
+        let query = SubQuery::parse("avg:sys.cpu").unwrap();
+        assert_eq!("avg", query.aggregator);
+        assert_eq!("sys.cpu", query.metric);
+        assert!(!query.rate);
+        assert!(query.filters.is_empty());
+
+        for m in [
+            "sys.cpu",
+            "sum:foo:sys.cpu",
+            "sum:sys.cpu{host=*",
+            "sum:sys.cpu{host}",
+        ] {
+            assert!(SubQuery::parse(m).is_err(), "{m}");
+        }
+    }
+
+    #[test]
+    fn test_filter_to_sql() {
+        let sql = |tagk: &str, value: &str| filter_to_sql(&parse_tag_filter(tagk, value, true));
+        assert_eq!(
+            r#"("host" IS NOT NULL AND "host" IS NOT NULL)"#,
+            sql("host", "*").unwrap()
+        );
+        assert_eq!(
+            r#"("host" IS NOT NULL AND "host" IN ('a', 'b''c'))"#,
+            sql("host", "a|b'c").unwrap()
+        );
+        assert_eq!(
+            r#"("host" IS NOT NULL AND "host" LIKE 'web%')"#,
+            sql("host", "web*").unwrap()
+        );
+        assert_eq!(
+            r#"("host" IS NOT NULL AND lower("host") LIKE 'web%')"#,
+            sql("host", "iwildcard(WEB*)").unwrap()
+        );
+        assert_eq!(
+            r#"("host" IS NOT NULL AND "host" NOT IN ('a'))"#,
+            sql("host", "not_literal_or(a)").unwrap()
+        );
+        assert_eq!(
+            r#"("host" IS NOT NULL AND "host" ~ 'web\d+')"#,
+            sql("host", r"regexp(web\d+)").unwrap()
+        );
+        assert!(sql("host", "unknown(a)").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = 1_700_000_000_000;
+        let parse = |s: &str| parse_time(&TimeSpec::String(s.to_string()), now);
+        assert_eq!(now - MILLIS_PER_HOUR, parse("1h-ago").unwrap());
+        assert_eq!(1_356_998_400_000, parse("1356998400").unwrap());
+        assert_eq!(1_356_998_400_123, parse("1356998400123").unwrap());
+        assert_eq!(1_356_998_400_123, parse("1356998400.123").unwrap());
+        assert_eq!(1_356_998_400_000, parse("2013/01/01-00:00:00").unwrap());
+        assert_eq!(1_356_998_400_000, parse("2013/01/01 00:00").unwrap());
+        assert_eq!(1_356_998_400_000, parse("2013/01/01").unwrap());
+        assert_eq!(
+            1_356_998_400_000,
+            parse_time(&TimeSpec::Number(1356998400), now).unwrap()
+        );
+        assert!(parse("yesterday").is_err());
+        assert!(parse("1x-ago").is_err());
+    }
+
+    #[test]
+    fn test_downsample() {
+        assert_eq!(
+            Downsample {
+                interval: Some(MILLIS_PER_MINUTE),
+                aggregator: Aggregator::Avg,
+                fill: FillPolicy::None,
+            },
+            Downsample::parse("1m-avg").unwrap()
+        );
+        assert_eq!(
+            Downsample {
+                interval: None,
+                aggregator: Aggregator::Sum,
+                fill: FillPolicy::Zero,
+            },
+            Downsample::parse("0all-zimsum-zero").unwrap()
+        );
+        for spec in [
+            "1m",
+            "0s-avg",
+            "1m-unknown",
+            "1m-avg-unknown",
+            "1m-avg-zero-x",
+        ] {
+            assert!(Downsample::parse(spec).is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let aggregate =
+            |aggregator: Aggregator, values: &[f64]| aggregator.aggregate(&mut values.to_vec());
+        let values = [4.0, 1.0, 3.0, 2.0];
+        assert_eq!(10.0, aggregate(Aggregator::Sum, &values));
+        assert_eq!(2.5, aggregate(Aggregator::Avg, &values));
+        assert_eq!(1.0, aggregate(Aggregator::Min, &values));
+        assert_eq!(4.0, aggregate(Aggregator::Max, &values));
+        assert_eq!(4.0, aggregate(Aggregator::Count, &values));
+        assert_eq!(2.5, aggregate(Aggregator::Percentile(0.5), &values));
+        assert_eq!(4.0, aggregate(Aggregator::Percentile(1.0), &values));
+        assert_eq!(4.0, aggregate(Aggregator::First, &values));
+        assert_eq!(2.0, aggregate(Aggregator::Last, &values));
+        assert_eq!(1.25f64.sqrt(), aggregate(Aggregator::Dev, &values));
+        assert!(aggregate(Aggregator::Sum, &[]).is_nan());
+    }
+
+    #[test]
+    fn test_rate_and_fill() {
+        let points = vec![(0, 1.0), (1000, 3.0), (3000, 1.0)];
+        assert_eq!(
+            vec![(1000, 2.0), (3000, -1.0)],
+            rate(&points, &RateOptions::default())
+        );
+
+        let counter = RateOptions {
+            counter: true,
+            counter_max: Some(100.0),
+            ..Default::default()
+        };
+        assert_eq!(vec![(1000, 2.0), (3000, 49.0)], rate(&points, &counter));
+
+        let drop_resets = RateOptions {
+            counter: true,
+            drop_resets: true,
+            ..Default::default()
+        };
+        assert_eq!(vec![(1000, 2.0)], rate(&points, &drop_resets));
+
+        let filled = fill_points(&[(1000, 1.0)], 0, 3500, 1000, FillPolicy::Zero).unwrap();
+        assert_eq!(
+            vec![(0, 0.0), (1000, 1.0), (2000, 0.0), (3000, 0.0)],
+            filled
+        );
+        let filled = fill_points(&[(1000, 1.0)], 0, 1000, 1000, FillPolicy::Null).unwrap();
+        assert!(filled[0].1.is_nan());
+        assert!(fill_points(&[], 0, MAX_FILLED_POINTS * 1000, 1, FillPolicy::Zero).is_err());
+    }
+
+    #[test]
+    fn test_parse_lookup() {
+        let request = LookupRequest::parse("sys.cpu{host=*,dc=lga}", Some(10), 0).unwrap();
+        assert_eq!(
+            LookupRequest {
+                metric: "sys.cpu".to_string(),
+                tags: vec![
+                    LookupTag {
+                        key: "host".to_string(),
+                        value: "*".to_string(),
+                    },
+                    LookupTag {
+                        key: "dc".to_string(),
+                        value: "lga".to_string(),
+                    },
+                ],
+                limit: Some(10),
+                start_index: 0,
+            },
+            request
+        );
+        assert!(LookupRequest::parse("sys.cpu{host}", None, 0).is_err());
+    }
+}
@@ -0,0 +1,908 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! OpenTelemetry protocol (OTLP) supports, which converts the exported metrics into inserts.
+//!
+//! Every metric is written into the tables named by it, with the same columns as the Prometheus
+//! remote write: the attributes of the resource, the scope and the data point are tags, and the
+//! samples are in `greptime_timestamp` and `greptime_value`. The histograms and the summaries
+//! are split into the `_bucket`, `_sum` and `_count` tables like Prometheus does, so they can
+//! be queried by PromQL.
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use api::v1::InsertRequest as GrpcInsertRequest;
+use common_grpc::writer::{LinesWriter, Precision};
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{any_value, AnyValue, KeyValue};
+use opentelemetry_proto::tonic::metrics::v1::{
+    metric, number_data_point, AggregationTemporality, ExponentialHistogramDataPoint,
+    HistogramDataPoint, Metric, NumberDataPoint, SummaryDataPoint,
+};
+use snafu::ResultExt;
+
+use crate::error::{Error, InvalidOtlpRequestSnafu, OtlpMetricsWriteSnafu, Result};
+
+pub const OTLP_TIMESTAMP_COLUMN_NAME: &str = "greptime_timestamp";
+pub const OTLP_FIELD_COLUMN_NAME: &str = "greptime_value";
+/// The header to choose the [MetricNaming] of a request.
+pub const OTLP_NAMING_HEADER: &str = "x-greptime-otlp-naming";
+
+const SCOPE_NAME_TAG: &str = "otel_scope_name";
+const SCOPE_VERSION_TAG: &str = "otel_scope_version";
+const BUCKET_TAG: &str = "le";
+const QUANTILE_TAG: &str = "quantile";
+
+/// The data point flag that marks a point without value.
+const FLAG_NO_RECORDED_VALUE: u32 = 1;
+
+/// How the names of the metrics and the attributes are converted into tables and columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetricNaming {
+    /// The names are kept as is, like `http.server.duration`.
+    #[default]
+    Otel,
+    /// The names are converted as the OpenTelemetry to Prometheus compatibility specification,
+    /// like `http_server_duration_milliseconds`: the invalid characters are replaced by `_`,
+    /// the units are appended, and the monotonic cumulative sums end with `_total`.
+    Prometheus,
+}
+
+impl FromStr for MetricNaming {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "otel" => Ok(MetricNaming::Otel),
+            "prometheus" => Ok(MetricNaming::Prometheus),
+            _ => InvalidOtlpRequestSnafu {
+                reason: format!("unknown metric naming: {s}"),
+            }
+            .fail(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OtlpMetricsRequest {
+    pub naming: MetricNaming,
+    pub request: ExportMetricsServiceRequest,
+}
+
+type TableName = String;
+
+impl TryFrom<&OtlpMetricsRequest> for Vec<GrpcInsertRequest> {
+    type Error = Error;
+
+    fn try_from(value: &OtlpMetricsRequest) -> Result<Self> {
+        let mut writer = MetricsWriter {
+            naming: value.naming,
+            writers: HashMap::new(),
+        };
+
+        for resource_metrics in &value.request.resource_metrics {
+            let mut resource_tags = BTreeMap::new();
+            if let Some(resource) = &resource_metrics.resource {
+                writer.add_tags(&mut resource_tags, &resource.attributes);
+            }
+
+            for scope_metrics in &resource_metrics.scope_metrics {
+                let mut scope_tags = resource_tags.clone();
+                if let Some(scope) = &scope_metrics.scope {
+                    writer.add_tags(&mut scope_tags, &scope.attributes);
+                    if !scope.name.is_empty() {
+                        let _ = scope_tags.insert(SCOPE_NAME_TAG.to_string(), scope.name.clone());
+                    }
+                    if !scope.version.is_empty() {
+                        let _ =
+                            scope_tags.insert(SCOPE_VERSION_TAG.to_string(), scope.version.clone());
+                    }
+                }
+
+                for metric in &scope_metrics.metrics {
+                    writer.write_metric(metric, &scope_tags)?;
+                }
+            }
+        }
+
+        Ok(writer
+            .writers
+            .into_iter()
+            .map(|(table_name, writer)| {
+                let (columns, row_count) = writer.finish();
+                GrpcInsertRequest {
+                    table_name,
+                    region_number: 0,
+                    columns,
+                    row_count,
+                }
+            })
+            .collect())
+    }
+}
+
+struct MetricsWriter {
+    naming: MetricNaming,
+    writers: HashMap<TableName, LinesWriter>,
+}
+
+impl MetricsWriter {
+    fn write_metric(&mut self, metric: &Metric, tags: &BTreeMap<String, String>) -> Result<()> {
+        let Some(data) = &metric.data else {
+            return Ok(());
+        };
+        match data {
+            metric::Data::Gauge(gauge) => {
+                let table = self.metric_name(metric, "");
+                for point in &gauge.data_points {
+                    self.write_number_point(&table, tags, point)?;
+                }
+            }
+            metric::Data::Sum(sum) => {
+                // Only the monotonic cumulative sums are counters in Prometheus, the others
+                // are stored as reported, like gauges.
+                let is_counter = sum.is_monotonic
+                    && sum.aggregation_temporality == AggregationTemporality::Cumulative as i32;
+                let table = self.metric_name(metric, if is_counter { "_total" } else { "" });
+                for point in &sum.data_points {
+                    self.write_number_point(&table, tags, point)?;
+                }
+            }
+            metric::Data::Histogram(histogram) => {
+                let name = self.metric_name(metric, "");
+                for point in &histogram.data_points {
+                    self.write_histogram_point(&name, tags, point)?;
+                }
+            }
+            metric::Data::ExponentialHistogram(histogram) => {
+                let name = self.metric_name(metric, "");
+                for point in &histogram.data_points {
+                    self.write_exponential_histogram_point(&name, tags, point)?;
+                }
+            }
+            metric::Data::Summary(summary) => {
+                let name = self.metric_name(metric, "");
+                for point in &summary.data_points {
+                    self.write_summary_point(&name, tags, point)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_number_point(
+        &mut self,
+        table: &str,
+        tags: &BTreeMap<String, String>,
+        point: &NumberDataPoint,
+    ) -> Result<()> {
+        let value = match point.value {
+            Some(number_data_point::Value::AsDouble(v)) => v,
+            Some(number_data_point::Value::AsInt(v)) => v as f64,
+            None => return Ok(()),
+        };
+        if point.flags & FLAG_NO_RECORDED_VALUE != 0 {
+            return Ok(());
+        }
+        let tags = self.point_tags(tags, &point.attributes);
+        self.write_row(table, &tags, point.time_unix_nano, value)
+    }
+
+    fn write_histogram_point(
+        &mut self,
+        name: &str,
+        tags: &BTreeMap<String, String>,
+        point: &HistogramDataPoint,
+    ) -> Result<()> {
+        if point.flags & FLAG_NO_RECORDED_VALUE != 0 {
+            return Ok(());
+        }
+        let tags = self.point_tags(tags, &point.attributes);
+
+        // The last bucket is unbounded, so there is one more count than the bounds.
+        let mut buckets = Vec::with_capacity(point.bucket_counts.len());
+        let mut cumulative_count = 0;
+        for (i, count) in point.bucket_counts.iter().enumerate() {
+            cumulative_count += count;
+            let upper_bound = point
+                .explicit_bounds
+                .get(i)
+                .copied()
+                .unwrap_or(f64::INFINITY);
+            buckets.push((upper_bound, cumulative_count));
+        }
+        self.write_buckets(name, &tags, point.time_unix_nano, buckets, point.count)?;
+        self.write_sum_and_count(name, &tags, point.time_unix_nano, point.sum, point.count)
+    }
+
+    fn write_exponential_histogram_point(
+        &mut self,
+        name: &str,
+        tags: &BTreeMap<String, String>,
+        point: &ExponentialHistogramDataPoint,
+    ) -> Result<()> {
+        if point.flags & FLAG_NO_RECORDED_VALUE != 0 {
+            return Ok(());
+        }
+        let tags = self.point_tags(tags, &point.attributes);
+
+        // The bucket of index `i` is `(base^i, base^(i+1)]`, where `base = 2^(2^-scale)`.
+        let upper_bound = |index: i64| 2f64.powf(index as f64 * 2f64.powi(-point.scale));
+        let mut buckets = Vec::new();
+        let mut cumulative_count = 0;
+        // The negative buckets are mirrored, so they are accumulated from the largest index.
+        if let Some(negative) = &point.negative {
+            for (i, count) in negative.bucket_counts.iter().enumerate().rev() {
+                cumulative_count += count;
+                let index = negative.offset as i64 + i as i64;
+                buckets.push((-upper_bound(index), cumulative_count));
+            }
+        }
+        cumulative_count += point.zero_count;
+        buckets.push((0.0, cumulative_count));
+        if let Some(positive) = &point.positive {
+            for (i, count) in positive.bucket_counts.iter().enumerate() {
+                cumulative_count += count;
+                let index = positive.offset as i64 + i as i64;
+                buckets.push((upper_bound(index + 1), cumulative_count));
+            }
+        }
+        self.write_buckets(name, &tags, point.time_unix_nano, buckets, point.count)?;
+        self.write_sum_and_count(name, &tags, point.time_unix_nano, point.sum, point.count)
+    }
+
+    fn write_summary_point(
+        &mut self,
+        name: &str,
+        tags: &BTreeMap<String, String>,
+        point: &SummaryDataPoint,
+    ) -> Result<()> {
+        if point.flags & FLAG_NO_RECORDED_VALUE != 0 {
+            return Ok(());
+        }
+        let tags = self.point_tags(tags, &point.attributes);
+        for quantile in &point.quantile_values {
+            let mut tags = tags.clone();
+            let _ = tags.insert(QUANTILE_TAG.to_string(), quantile.quantile.to_string());
+            self.write_row(name, &tags, point.time_unix_nano, quantile.value)?;
+        }
+        self.write_sum_and_count(
+            name,
+            &tags,
+            point.time_unix_nano,
+            Some(point.sum),
+            point.count,
+        )
+    }
+
+    /// Writes the cumulative counts of the buckets, which are sorted by their upper bounds.
+    /// The `+Inf` bucket is always written with the total count.
+    fn write_buckets(
+        &mut self,
+        name: &str,
+        tags: &BTreeMap<String, String>,
+        time_unix_nano: u64,
+        buckets: Vec<(f64, u64)>,
+        count: u64,
+    ) -> Result<()> {
+        let table = format!("{name}_bucket");
+        for (upper_bound, cumulative_count) in buckets {
+            if upper_bound.is_infinite() {
+                continue;
+            }
+            let mut tags = tags.clone();
+            let _ = tags.insert(BUCKET_TAG.to_string(), upper_bound.to_string());
+            self.write_row(&table, &tags, time_unix_nano, cumulative_count as f64)?;
+        }
+        let mut tags = tags.clone();
+        let _ = tags.insert(BUCKET_TAG.to_string(), "+Inf".to_string());
+        self.write_row(&table, &tags, time_unix_nano, count as f64)
+    }
+
+    fn write_sum_and_count(
+        &mut self,
+        name: &str,
+        tags: &BTreeMap<String, String>,
+        time_unix_nano: u64,
+        sum: Option<f64>,
+        count: u64,
+    ) -> Result<()> {
+        if let Some(sum) = sum {
+            self.write_row(&format!("{name}_sum"), tags, time_unix_nano, sum)?;
+        }
+        self.write_row(&format!("{name}_count"), tags, time_unix_nano, count as f64)
+    }
+
+    fn write_row(
+        &mut self,
+        table: &str,
+        tags: &BTreeMap<String, String>,
+        time_unix_nano: u64,
+        value: f64,
+    ) -> Result<()> {
+        let writer = self.writers.entry(table.to_string()).or_default();
+        for (k, v) in tags {
+            writer.write_tag(k, v).context(OtlpMetricsWriteSnafu)?;
+        }
+        writer
+            .write_ts(
+                OTLP_TIMESTAMP_COLUMN_NAME,
+                (time_unix_nano as i64, Precision::Nanosecond),
+            )
+            .context(OtlpMetricsWriteSnafu)?;
+        writer
+            .write_f64(OTLP_FIELD_COLUMN_NAME, value)
+            .context(OtlpMetricsWriteSnafu)?;
+        writer.commit();
+        Ok(())
+    }
+
+    fn point_tags(
+        &self,
+        tags: &BTreeMap<String, String>,
+        attributes: &[KeyValue],
+    ) -> BTreeMap<String, String> {
+        let mut tags = tags.clone();
+        self.add_tags(&mut tags, attributes);
+        tags
+    }
+
+    /// Adds the attributes into the tags, overriding the ones of the same names.
+    fn add_tags(&self, tags: &mut BTreeMap<String, String>, attributes: &[KeyValue]) {
+        // The attributes of different keys may be sanitized into the same name in Prometheus
+        // naming, whose values are joined by `;` as the specification says.
+        let mut added: BTreeMap<String, String> = BTreeMap::new();
+        for attribute in attributes {
+            let Some(value) = attribute.value.as_ref().and_then(any_value_to_string) else {
+                continue;
+            };
+            let key = match self.naming {
+                MetricNaming::Otel => attribute.key.clone(),
+                MetricNaming::Prometheus => sanitize_label_name(&attribute.key),
+            };
+            added
+                .entry(key)
+                .and_modify(|joined| {
+                    joined.push(';');
+                    joined.push_str(&value);
+                })
+                .or_insert(value);
+        }
+        tags.extend(added);
+    }
+
+    /// Returns the name of the metric's table, with the suffix in Prometheus naming.
+    fn metric_name(&self, metric: &Metric, suffix: &str) -> String {
+        match self.naming {
+            MetricNaming::Otel => metric.name.clone(),
+            MetricNaming::Prometheus => prometheus_metric_name(metric, suffix),
+        }
+    }
+}
+
+fn any_value_to_string(value: &AnyValue) -> Option<String> {
+    match value.value.as_ref()? {
+        any_value::Value::StringValue(s) => Some(s.clone()),
+        _ => Some(any_value_to_json(value).to_string()),
+    }
+}
+
+fn any_value_to_json(value: &AnyValue) -> serde_json::Value {
+    use serde_json::Value as JsonValue;
+
+    let Some(value) = &value.value else {
+        return JsonValue::Null;
+    };
+    match value {
+        any_value::Value::StringValue(s) => JsonValue::String(s.clone()),
+        any_value::Value::BoolValue(b) => JsonValue::Bool(*b),
+        any_value::Value::IntValue(i) => JsonValue::from(*i),
+        any_value::Value::DoubleValue(d) => JsonValue::from(*d),
+        any_value::Value::BytesValue(bytes) => JsonValue::String(hex::encode(bytes)),
+        any_value::Value::ArrayValue(array) => {
+            JsonValue::Array(array.values.iter().map(any_value_to_json).collect())
+        }
+        any_value::Value::KvlistValue(list) => JsonValue::Object(
+            list.values
+                .iter()
+                .map(|kv| {
+                    let value = kv.value.as_ref().map_or(JsonValue::Null, any_value_to_json);
+                    (kv.key.clone(), value)
+                })
+                .collect(),
+        ),
+    }
+}
+
+// https://github.com/open-telemetry/opentelemetry-specification/blob/main/specification/compatibility/prometheus_and_openmetrics.md#metric-metadata-1
+fn prometheus_metric_name(metric: &Metric, suffix: &str) -> String {
+    let mut name = metric.name.clone();
+    if let Some(unit) = prometheus_unit(&metric.unit) {
+        if !name.contains(&unit) {
+            name.push('_');
+            name.push_str(&unit);
+        }
+    }
+    let is_gauge = matches!(metric.data, Some(metric::Data::Gauge(_)));
+    if is_gauge && metric.unit == "1" && !name.ends_with("_ratio") {
+        name.push_str("_ratio");
+    }
+    if !name.ends_with(suffix) {
+        name.push_str(suffix);
+    }
+    sanitize_metric_name(&name)
+}
+
+/// Converts the UCUM unit into the full words, like `bytes_per_second` for `By/s`. The
+/// annotations in braces like `{requests}` are dropped.
+fn prometheus_unit(unit: &str) -> Option<String> {
+    let mut stripped = String::with_capacity(unit.len());
+    let mut depth = 0;
+    for c in unit.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            c if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    let unit = stripped.trim();
+    if unit.is_empty() || unit == "1" {
+        return None;
+    }
+
+    let unit = match unit.split_once('/') {
+        Some((main, per)) => {
+            let main = unit_name(main.trim());
+            let per = per_unit_name(per.trim());
+            if main.is_empty() {
+                format!("per_{per}")
+            } else {
+                format!("{main}_per_{per}")
+            }
+        }
+        None => unit_name(unit).to_string(),
+    };
+    Some(unit)
+}
+
+fn unit_name(unit: &str) -> &str {
+    match unit {
+        "d" => "days",
+        "h" => "hours",
+        "min" => "minutes",
+        "s" => "seconds",
+        "ms" => "milliseconds",
+        "us" => "microseconds",
+        "ns" => "nanoseconds",
+        "By" => "bytes",
+        "KiBy" => "kibibytes",
+        "MiBy" => "mebibytes",
+        "GiBy" => "gibibytes",
+        "TiBy" => "tibibytes",
+        "KBy" => "kilobytes",
+        "MBy" => "megabytes",
+        "GBy" => "gigabytes",
+        "TBy" => "terabytes",
+        "m" => "meters",
+        "V" => "volts",
+        "A" => "amperes",
+        "J" => "joules",
+        "W" => "watts",
+        "g" => "grams",
+        "Cel" => "celsius",
+        "Hz" => "hertz",
+        "%" => "percent",
+        "1" => "",
+        unit => unit,
+    }
+}
+
+fn per_unit_name(unit: &str) -> &str {
+    match unit {
+        "s" => "second",
+        "m" => "minute",
+        "h" => "hour",
+        "d" => "day",
+        "w" => "week",
+        "mo" => "month",
+        "y" => "year",
+        unit => unit,
+    }
+}
+
+/// Replaces the characters not allowed in Prometheus metric names by `_`, and merges the
+/// consecutive `_`.
+fn sanitize_metric_name(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.chars() {
+        let c = if c.is_ascii_alphanumeric() || c == ':' {
+            c
+        } else {
+            '_'
+        };
+        if c == '_' && sanitized.ends_with('_') {
+            continue;
+        }
+        sanitized.push(c);
+    }
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Replaces the characters not allowed in Prometheus label names by `_`.
+fn sanitize_label_name(name: &str) -> String {
+    let sanitized = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("key_{sanitized}")
+    } else {
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use api::v1::column::Values;
+    use api::v1::Column;
+    use opentelemetry_proto::tonic::common::v1::InstrumentationScope;
+    use opentelemetry_proto::tonic::metrics::v1::summary_data_point::ValueAtQuantile;
+    use opentelemetry_proto::tonic::metrics::v1::{
+        exponential_histogram_data_point, ExponentialHistogram, Gauge, Histogram, ResourceMetrics,
+        ScopeMetrics, Sum, Summary,
+    };
+    use opentelemetry_proto::tonic::resource::v1::Resource;
+
+    use super::*;
+
+    const TIME: u64 = 1_663_840_496_100_023_100;
+
+    fn key_value(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.to_string(),
+            value: Some(AnyValue {
+                value: Some(any_value::Value::StringValue(value.to_string())),
+            }),
+        }
+    }
+
+    fn number_point(value: f64, host: &str) -> NumberDataPoint {
+        NumberDataPoint {
+            attributes: vec![key_value("host.name", host)],
+            time_unix_nano: TIME,
+            value: Some(number_data_point::Value::AsDouble(value)),
+            ..Default::default()
+        }
+    }
+
+    fn metric(name: &str, unit: &str, data: metric::Data) -> Metric {
+        Metric {
+            name: name.to_string(),
+            unit: unit.to_string(),
+            data: Some(data),
+            ..Default::default()
+        }
+    }
+
+    fn request(metrics: Vec<Metric>, naming: MetricNaming) -> Vec<GrpcInsertRequest> {
+        let request = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(Resource {
+                    attributes: vec![key_value("service.name", "api")],
+                    ..Default::default()
+                }),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(InstrumentationScope {
+                        name: "meter".to_string(),
+                        ..Default::default()
+                    }),
+                    metrics,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        let mut requests: Vec<GrpcInsertRequest> = (&OtlpMetricsRequest { naming, request })
+            .try_into()
+            .unwrap();
+        requests.sort_by(|a, b| a.table_name.cmp(&b.table_name));
+        requests
+    }
+
+    fn column<'a>(request: &'a GrpcInsertRequest, name: &str) -> &'a Column {
+        request
+            .columns
+            .iter()
+            .find(|c| c.column_name == name)
+            .unwrap()
+    }
+
+    fn values(request: &GrpcInsertRequest, name: &str) -> Values {
+        column(request, name).values.clone().unwrap()
+    }
+
+    #[test]
+    fn test_convert_gauge_and_sum() {
+        let requests = request(
+            vec![
+                metric(
+                    "system.memory.usage",
+                    "By",
+                    metric::Data::Gauge(Gauge {
+                        data_points: vec![
+                            number_point(1024.0, "host1"),
+                            number_point(2048.0, "host2"),
+                        ],
+                    }),
+                ),
+                metric(
+                    "http.server.requests",
+                    "{requests}",
+                    metric::Data::Sum(Sum {
+                        data_points: vec![number_point(10.0, "host1")],
+                        aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                        is_monotonic: true,
+                    }),
+                ),
+            ],
+            MetricNaming::Otel,
+        );
+        assert_eq!(
+            vec!["http.server.requests", "system.memory.usage"],
+            requests
+                .iter()
+                .map(|r| r.table_name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let gauge = &requests[1];
+        assert_eq!(2, gauge.row_count);
+        assert_eq!(
+            vec![1024.0, 2048.0],
+            values(gauge, "greptime_value").f64_values
+        );
+        assert_eq!(
+            vec![1663840496100, 1663840496100],
+            values(gauge, "greptime_timestamp").ts_millisecond_values
+        );
+        assert_eq!(
+            vec!["host1", "host2"],
+            values(gauge, "host.name").string_values
+        );
+        assert_eq!(
+            vec!["api", "api"],
+            values(gauge, "service.name").string_values
+        );
+        assert_eq!(
+            vec!["meter", "meter"],
+            values(gauge, "otel_scope_name").string_values
+        );
+
+        let requests = request(
+            vec![
+                metric(
+                    "system.memory.usage",
+                    "By",
+                    metric::Data::Gauge(Gauge {
+                        data_points: vec![number_point(1024.0, "host1")],
+                    }),
+                ),
+                metric(
+                    "system.cpu.utilization",
+                    "1",
+                    metric::Data::Gauge(Gauge {
+                        data_points: vec![number_point(0.5, "host1")],
+                    }),
+                ),
+                metric(
+                    "http.server.requests",
+                    "{requests}",
+                    metric::Data::Sum(Sum {
+                        data_points: vec![number_point(10.0, "host1")],
+                        aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                        is_monotonic: true,
+                    }),
+                ),
+                metric(
+                    "http.server.active_requests",
+                    "{requests}",
+                    metric::Data::Sum(Sum {
+                        data_points: vec![number_point(3.0, "host1")],
+                        aggregation_temporality: AggregationTemporality::Delta as i32,
+                        is_monotonic: true,
+                    }),
+                ),
+            ],
+            MetricNaming::Prometheus,
+        );
+        assert_eq!(
+            vec![
+                "http_server_active_requests",
+                "http_server_requests_total",
+                "system_cpu_utilization_ratio",
+                "system_memory_usage_bytes",
+            ],
+            requests
+                .iter()
+                .map(|r| r.table_name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["host1"],
+            values(&requests[0], "host_name").string_values
+        );
+        assert_eq!(
+            vec!["api"],
+            values(&requests[0], "service_name").string_values
+        );
+    }
+
+    #[test]
+    fn test_convert_histograms_and_summary() {
+        let requests = request(
+            vec![
+                metric(
+                    "http.server.duration",
+                    "ms",
+                    metric::Data::Histogram(Histogram {
+                        data_points: vec![HistogramDataPoint {
+                            time_unix_nano: TIME,
+                            count: 6,
+                            sum: Some(60.0),
+                            bucket_counts: vec![1, 2, 3],
+                            explicit_bounds: vec![5.0, 10.0],
+                            ..Default::default()
+                        }],
+                        aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                    }),
+                ),
+                metric(
+                    "rpc.duration",
+                    "s",
+                    metric::Data::ExponentialHistogram(ExponentialHistogram {
+                        data_points: vec![ExponentialHistogramDataPoint {
+                            time_unix_nano: TIME,
+                            count: 7,
+                            sum: Some(10.0),
+                            scale: 0,
+                            zero_count: 1,
+                            positive: Some(exponential_histogram_data_point::Buckets {
+                                offset: 1,
+                                bucket_counts: vec![2, 3],
+                            }),
+                            negative: Some(exponential_histogram_data_point::Buckets {
+                                offset: 0,
+                                bucket_counts: vec![1],
+                            }),
+                            ..Default::default()
+                        }],
+                        aggregation_temporality: AggregationTemporality::Cumulative as i32,
+                    }),
+                ),
+                metric(
+                    "rpc.latency",
+                    "",
+                    metric::Data::Summary(Summary {
+                        data_points: vec![SummaryDataPoint {
+                            time_unix_nano: TIME,
+                            count: 4,
+                            sum: 8.0,
+                            quantile_values: vec![
+                                ValueAtQuantile {
+                                    quantile: 0.5,
+                                    value: 1.5,
+                                },
+                                ValueAtQuantile {
+                                    quantile: 0.99,
+                                    value: 3.0,
+                                },
+                            ],
+                            ..Default::default()
+                        }],
+                    }),
+                ),
+            ],
+            MetricNaming::Prometheus,
+        );
+        assert_eq!(
+            vec![
+                "http_server_duration_milliseconds_bucket",
+                "http_server_duration_milliseconds_count",
+                "http_server_duration_milliseconds_sum",
+                "rpc_duration_seconds_bucket",
+                "rpc_duration_seconds_count",
+                "rpc_duration_seconds_sum",
+                "rpc_latency",
+                "rpc_latency_count",
+                "rpc_latency_sum",
+            ],
+            requests
+                .iter()
+                .map(|r| r.table_name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let buckets = &requests[0];
+        assert_eq!(vec!["5", "10", "+Inf"], values(buckets, "le").string_values);
+        assert_eq!(
+            vec![1.0, 3.0, 6.0],
+            values(buckets, "greptime_value").f64_values
+        );
+        assert_eq!(vec![6.0], values(&requests[1], "greptime_value").f64_values);
+        assert_eq!(
+            vec![60.0],
+            values(&requests[2], "greptime_value").f64_values
+        );
+
+        // The upper bounds of the buckets [-2, -1), [0, 0], (2, 4] and (4, 8].
+        let buckets = &requests[3];
+        assert_eq!(
+            vec!["-1", "0", "4", "8", "+Inf"],
+            values(buckets, "le").string_values
+        );
+        assert_eq!(
+            vec![1.0, 2.0, 4.0, 7.0, 7.0],
+            values(buckets, "greptime_value").f64_values
+        );
+
+        let quantiles = &requests[6];
+        assert_eq!(
+            vec!["0.5", "0.99"],
+            values(quantiles, "quantile").string_values
+        );
+        assert_eq!(
+            vec![1.5, 3.0],
+            values(quantiles, "greptime_value").f64_values
+        );
+        assert_eq!(vec![4.0], values(&requests[7], "greptime_value").f64_values);
+        assert_eq!(vec![8.0], values(&requests[8], "greptime_value").f64_values);
+    }
+
+    #[test]
+    fn test_prometheus_naming() {
+        assert_eq!(
+            Some("bytes_per_second".to_string()),
+            prometheus_unit("By/s")
+        );
+        assert_eq!(Some("per_second".to_string()), prometheus_unit("1/s"));
+        assert_eq!(Some("seconds".to_string()), prometheus_unit("s{cpu}"));
+        assert_eq!(None, prometheus_unit("{requests}"));
+        assert_eq!(None, prometheus_unit("1"));
+
+        assert_eq!(
+            "http_server_duration",
+            sanitize_metric_name("http.server..duration")
+        );
+        assert_eq!("_9p:rate", sanitize_metric_name("9p:rate"));
+        assert_eq!("service_name", sanitize_label_name("service.name"));
+        assert_eq!("key_0_id", sanitize_label_name("0.id"));
+
+        let mut tags = BTreeMap::new();
+        let writer = MetricsWriter {
+            naming: MetricNaming::Prometheus,
+            writers: HashMap::new(),
+        };
+        writer.add_tags(&mut tags, &[key_value("a.b", "1"), key_value("a_b", "2")]);
+        assert_eq!("1;2", tags["a_b"]);
+
+        assert_eq!(MetricNaming::Otel, "otel".parse().unwrap());
+        assert_eq!(MetricNaming::Prometheus, "Prometheus".parse().unwrap());
+        assert!("unknown".parse::<MetricNaming>().is_err());
+    }
+}
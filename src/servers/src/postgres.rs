@@ -13,8 +13,10 @@
 // limitations under the License.
 
 mod auth_handler;
+pub mod copy;
 mod handler;
 mod server;
+mod socket;
 
 pub(crate) const METADATA_USER: &str = "user";
 pub(crate) const METADATA_DATABASE: &str = "database";
@@ -37,6 +39,7 @@ use sql::statements::statement::Statement;
 use self::auth_handler::PgLoginVerifier;
 use self::handler::POCQueryParser;
 use crate::auth::UserProviderRef;
+use crate::query_handler::grpc::BulkInsertHandlerRef;
 use crate::query_handler::sql::ServerSqlQueryHandlerRef;
 
 pub(crate) struct GreptimeDBStartupParameters {
@@ -69,6 +72,8 @@ impl ServerParameterProvider for GreptimeDBStartupParameters {
 
 pub struct PostgresServerHandler {
     query_handler: ServerSqlQueryHandlerRef,
+    /// Inserts the rows of `COPY FROM STDIN`, which is not supported without it.
+    bulk_insert_handler: Option<BulkInsertHandlerRef>,
     login_verifier: PgLoginVerifier,
     force_tls: bool,
     param_provider: Arc<GreptimeDBStartupParameters>,
@@ -78,9 +83,11 @@ pub struct PostgresServerHandler {
     query_parser: Arc<POCQueryParser>,
 }
 
-#[derive(Builder)]
+#[derive(Builder, Clone)]
 pub(crate) struct MakePostgresServerHandler {
     query_handler: ServerSqlQueryHandlerRef,
+    #[builder(default)]
+    bulk_insert_handler: Option<BulkInsertHandlerRef>,
     user_provider: Option<UserProviderRef>,
     #[builder(default = "Arc::new(GreptimeDBStartupParameters::new())")]
     param_provider: Arc<GreptimeDBStartupParameters>,
@@ -95,6 +102,7 @@ impl MakeHandler for MakePostgresServerHandler {
     fn make(&self) -> Self::Handler {
         Arc::new(PostgresServerHandler {
             query_handler: self.query_handler.clone(),
+            bulk_insert_handler: self.bulk_insert_handler.clone(),
             login_verifier: PgLoginVerifier::new(self.user_provider.clone()),
             force_tls: self.force_tls,
             param_provider: self.param_provider.clone(),
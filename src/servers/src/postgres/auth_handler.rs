@@ -70,33 +70,28 @@ impl LoginInfo {
 }
 
 impl PgLoginVerifier {
-    async fn verify_pwd(&self, password: &str, login: &LoginInfo) -> Result<bool> {
-        if let Some(user_provider) = &self.user_provider {
-            let user_name = match &login.user {
-                Some(name) => name,
-                None => return Ok(false),
-            };
+    /// Returns the authenticated user, or `None` if the login has no user name.
+    async fn verify_pwd(&self, password: &str, login: &LoginInfo) -> Result<Option<UserInfo>> {
+        let Some(user_provider) = &self.user_provider else {
+            return Ok(Some(UserInfo::default()));
+        };
+        let user_name = match &login.user {
+            Some(name) => name,
+            None => return Ok(None),
+        };
 
-            // TODO(fys): pass user_info to context
-            let _user_info = user_provider
-                .authenticate(
-                    Identity::UserId(user_name, None),
-                    Password::PlainText(password),
-                )
-                .await
-                .context(error::AuthSnafu)?;
-        }
-        Ok(true)
+        let user_info = user_provider
+            .authenticate(
+                Identity::UserId(user_name, None),
+                Password::PlainText(password),
+            )
+            .await
+            .context(error::AuthSnafu)?;
+        Ok(Some(user_info))
     }
 
-    async fn authorize(&self, login: &LoginInfo) -> Result<bool> {
-        // at this time, username in login info should be valid
-        // TODO(shuiyisong): change to use actually user_info from session
+    async fn authorize(&self, login: &LoginInfo, user_info: &UserInfo) -> Result<bool> {
         if let Some(user_provider) = &self.user_provider {
-            let user_name = match &login.user {
-                Some(name) => name,
-                None => return Ok(false),
-            };
             let catalog = match &login.catalog {
                 Some(name) => name,
                 None => return Ok(false),
@@ -105,9 +100,7 @@ impl PgLoginVerifier {
                 Some(name) => name,
                 None => return Ok(false),
             };
-            user_provider
-                .authorize(catalog, schema, &UserInfo::new(user_name))
-                .await?;
+            user_provider.authorize(catalog, schema, user_info).await?;
         }
         Ok(true)
     }
@@ -187,7 +180,7 @@ impl StartupHandler for PostgresServerHandler {
                     .login_verifier
                     .verify_pwd(pwd.password(), &login_info)
                     .await;
-                if !matches!(authenticate_result, Ok(true)) {
+                let Ok(Some(user_info)) = authenticate_result else {
                     return send_error(
                         client,
                         "FATAL",
@@ -195,9 +188,9 @@ impl StartupHandler for PostgresServerHandler {
                         "password authentication failed".to_owned(),
                     )
                     .await;
-                }
+                };
                 // do authorize
-                let authorize_result = self.login_verifier.authorize(&login_info).await;
+                let authorize_result = self.login_verifier.authorize(&login_info, &user_info).await;
                 if !matches!(authorize_result, Ok(true)) {
                     return send_error(
                         client,
@@ -207,6 +200,7 @@ impl StartupHandler for PostgresServerHandler {
                     )
                     .await;
                 }
+                self.query_ctx.set_current_user(user_info);
                 set_query_context_from_client_info(client, self.query_ctx.clone());
                 auth::finish_authentication(client, self.param_provider.as_ref()).await;
             }
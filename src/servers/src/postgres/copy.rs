@@ -0,0 +1,932 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The data path of the COPY sub-protocol, that is `COPY table FROM STDIN` and
+//! `COPY {table | (query)} TO STDOUT` in the `text`, `csv` and `binary` formats of PostgreSQL.
+//!
+//! The payloads of the CopyData messages are decoded into rows and inserted into the table
+//! in batches, and the query results are encoded into the payloads the other way around.
+
+use bytes::Bytes;
+use common_query::Output;
+use common_recordbatch::RecordBatch;
+use common_time::date::Date;
+use common_time::datetime::DateTime;
+use common_time::timestamp::{TimeUnit, Timestamp};
+use datatypes::prelude::{ConcreteDataType, Value};
+use datatypes::schema::Schema;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use snafu::{ensure, OptionExt, ResultExt};
+use sql::ast::{Ident, ObjectName};
+use sql::dialect::GenericDialect;
+use sql::parser::ParserContext;
+use sql::statements::copy::{CopyStdio, CopyStdioFormat, CopyStdioOptions};
+use sql::statements::statement::Statement;
+
+use super::PostgresServerHandler;
+use crate::bulk_insert::{parse_text_value, BulkInserter, Row};
+use crate::error::{self, Result};
+
+/// The signature at the beginning of the `binary` format.
+const BINARY_SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Days between 1970-01-01 and 2000-01-01, the epoch of the `binary` format.
+const PG_EPOCH_DAYS: i32 = 10_957;
+/// Seconds between 1970-01-01 and 2000-01-01.
+const PG_EPOCH_SECONDS: i64 = 946_684_800;
+
+/// Parses the query if it is `COPY ... {FROM STDIN | TO STDOUT}`, whose data is transferred in
+/// the COPY sub-protocol. The malformed ones are left to the query engine, which reports them.
+pub(crate) fn parse_copy_stdio(query: &str) -> Option<CopyStdio> {
+    let is_copy = query
+        .trim_start()
+        .get(..4)
+        .map_or(false, |s| s.eq_ignore_ascii_case("COPY"));
+    if !is_copy {
+        return None;
+    }
+    let mut statements = ParserContext::create_with_dialect(query, &GenericDialect {}).ok()?;
+    match statements.pop() {
+        Some(Statement::CopyStdio(copy)) if statements.is_empty() => Some(copy),
+        _ => None,
+    }
+}
+
+/// A `COPY table FROM STDIN` in progress, which inserts the rows decoded from the payloads of
+/// the CopyData messages into the table in batches.
+pub(crate) struct CopyIn {
+    inserter: BulkInserter,
+    decoder: CopyInDecoder,
+}
+
+impl CopyIn {
+    pub(crate) fn columns(&self) -> usize {
+        self.inserter.column_types().len()
+    }
+
+    pub(crate) async fn push(&mut self, data: &[u8]) -> Result<()> {
+        let rows = self.decoder.decode(data)?;
+        self.inserter.push(rows).await
+    }
+
+    /// Inserts the rest of the rows, and returns the number of inserted rows.
+    pub(crate) async fn finish(mut self) -> Result<usize> {
+        let rows = self.decoder.finish()?;
+        self.inserter.push(rows).await?;
+        self.inserter.finish().await
+    }
+}
+
+/// The results of a `COPY ... TO STDOUT` query.
+pub(crate) struct CopyOut {
+    pub(crate) columns: usize,
+    /// The payloads of the CopyData messages, and the numbers of rows in them, which are
+    /// encoded as the query results are produced.
+    pub(crate) data: BoxStream<'static, Result<(Bytes, usize)>>,
+}
+
+impl PostgresServerHandler {
+    /// Starts `COPY table FROM STDIN` once the columns to insert are resolved.
+    pub(crate) async fn copy_in(
+        &self,
+        table_name: &ObjectName,
+        columns: &[Ident],
+        options: CopyStdioOptions,
+    ) -> Result<CopyIn> {
+        let bulk_insert_handler =
+            self.bulk_insert_handler
+                .clone()
+                .context(error::NotSupportedSnafu {
+                    feat: "COPY FROM STDIN",
+                })?;
+        let inserter = BulkInserter::try_new(
+            self.query_handler.clone(),
+            bulk_insert_handler,
+            self.query_ctx.clone(),
+            table_name,
+            columns,
+        )
+        .await?;
+        let decoder = CopyInDecoder::new(options, inserter.column_types().to_vec());
+        Ok(CopyIn { inserter, decoder })
+    }
+
+    /// Executes the query of `COPY ... TO STDOUT`.
+    pub(crate) async fn copy_out(&self, query: &str, options: CopyStdioOptions) -> Result<CopyOut> {
+        let mut outputs = self
+            .query_handler
+            .do_query(query, self.query_ctx.clone())
+            .await;
+        ensure!(
+            outputs.len() == 1,
+            error::InvalidPostgresCopySnafu {
+                reason: "COPY expects exactly one query",
+            }
+        );
+        let mut stream = match outputs.remove(0)? {
+            Output::Stream(stream) => stream,
+            Output::RecordBatches(recordbatches) => recordbatches.as_stream(),
+            Output::AffectedRows(_) => {
+                return error::InvalidPostgresCopySnafu {
+                    reason: "COPY query must have a result",
+                }
+                .fail()
+            }
+        };
+
+        let schema = stream.schema();
+        let encoder = CopyOutEncoder { options };
+        let data = async_stream::try_stream! {
+            yield (encoder.encode_header(&schema), 0);
+            while let Some(batch) = stream.next().await {
+                let batch = batch.context(error::CollectRecordbatchSnafu)?;
+                yield (encoder.encode_batch(&batch)?, batch.num_rows());
+            }
+            if let Some(trailer) = encoder.encode_trailer() {
+                yield (trailer, 0);
+            }
+        };
+        Ok(CopyOut {
+            columns: schema.num_columns(),
+            data: Box::pin(data),
+        })
+    }
+}
+
+/// Decodes the payloads of CopyData messages into rows, which may be split across the
+/// messages.
+struct CopyInDecoder {
+    options: CopyStdioOptions,
+    column_types: Vec<ConcreteDataType>,
+    buffer: Vec<u8>,
+    /// Whether the CSV header or the binary header is skipped.
+    header_skipped: bool,
+    /// Whether the end-of-data marker is received.
+    finished: bool,
+}
+
+impl CopyInDecoder {
+    fn new(options: CopyStdioOptions, column_types: Vec<ConcreteDataType>) -> Self {
+        let header_skipped = !options.header && options.format != CopyStdioFormat::Binary;
+        CopyInDecoder {
+            options,
+            column_types,
+            buffer: vec![],
+            header_skipped,
+            finished: false,
+        }
+    }
+
+    /// Decodes the complete rows in the received data, and keeps the rest for the next.
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<Row>> {
+        self.buffer.extend_from_slice(data);
+        let mut rows = vec![];
+        while !self.finished {
+            let consumed = match self.options.format {
+                CopyStdioFormat::Text => self.decode_text_line(&mut rows, false)?,
+                CopyStdioFormat::Csv => self.decode_csv_record(&mut rows, false)?,
+                CopyStdioFormat::Binary => self.decode_binary_tuple(&mut rows)?,
+            };
+            if consumed == 0 {
+                break;
+            }
+            let _ = self.buffer.drain(..consumed);
+        }
+        Ok(rows)
+    }
+
+    /// Decodes the last row without the line ending, and checks no data is truncated.
+    fn finish(&mut self) -> Result<Vec<Row>> {
+        let mut rows = vec![];
+        if !self.finished && !self.buffer.is_empty() {
+            let consumed = match self.options.format {
+                CopyStdioFormat::Text => self.decode_text_line(&mut rows, true)?,
+                CopyStdioFormat::Csv => self.decode_csv_record(&mut rows, true)?,
+                CopyStdioFormat::Binary => 0,
+            };
+            let _ = self.buffer.drain(..consumed);
+        }
+        ensure!(
+            self.buffer.is_empty() || self.finished,
+            error::InvalidPostgresCopySnafu {
+                reason: "Unexpected end of the COPY data",
+            }
+        );
+        Ok(rows)
+    }
+
+    /// Decodes a line of the `text` format, returns the number of consumed bytes, or 0 if the
+    /// line is incomplete.
+    fn decode_text_line(&mut self, rows: &mut Vec<Row>, eof: bool) -> Result<usize> {
+        let (line, consumed) = match self.buffer.iter().position(|b| *b == b'\n') {
+            Some(end) => (&self.buffer[..end], end + 1),
+            None if eof => (&self.buffer[..], self.buffer.len()),
+            None => return Ok(0),
+        };
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line == b"\\." {
+            self.finished = true;
+            return Ok(consumed);
+        }
+
+        let mut fields = vec![];
+        let mut start = 0;
+        let mut escaped = false;
+        for (i, b) in line.iter().enumerate() {
+            if escaped {
+                escaped = false;
+            } else if *b == b'\\' {
+                escaped = true;
+            } else if *b == self.options.delimiter {
+                fields.push(&line[start..i]);
+                start = i + 1;
+            }
+        }
+        fields.push(&line[start..]);
+
+        let row = self.to_row(fields.into_iter().map(|field| {
+            if field == self.options.null.as_bytes() {
+                Ok(None)
+            } else {
+                unescape_text(field).map(Some)
+            }
+        }))?;
+        rows.push(row);
+        Ok(consumed)
+    }
+
+    /// Decodes a record of the `csv` format, returns the number of consumed bytes, or 0 if the
+    /// record is incomplete. The quoted fields may contain line endings.
+    fn decode_csv_record(&mut self, rows: &mut Vec<Row>, eof: bool) -> Result<usize> {
+        let mut fields = vec![];
+        let mut field = vec![];
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut consumed = None;
+
+        let mut i = 0;
+        while i < self.buffer.len() {
+            let b = self.buffer[i];
+            if in_quotes {
+                if b == b'"' {
+                    if self.buffer.get(i + 1) == Some(&b'"') {
+                        field.push(b'"');
+                        i += 1;
+                    } else if i + 1 == self.buffer.len() && !eof {
+                        // Can't tell whether the quote is escaped yet.
+                        return Ok(0);
+                    } else {
+                        in_quotes = false;
+                    }
+                } else {
+                    field.push(b);
+                }
+            } else if b == b'"' {
+                in_quotes = true;
+                quoted = true;
+            } else if b == self.options.delimiter {
+                fields.push((std::mem::take(&mut field), quoted));
+                quoted = false;
+            } else if b == b'\n' {
+                consumed = Some(i + 1);
+                break;
+            } else {
+                field.push(b);
+            }
+            i += 1;
+        }
+        let consumed = match consumed {
+            Some(consumed) => consumed,
+            None if eof && !in_quotes => self.buffer.len(),
+            None if eof => {
+                return error::InvalidPostgresCopySnafu {
+                    reason: "Unterminated CSV quoted field",
+                }
+                .fail()
+            }
+            None => return Ok(0),
+        };
+        if !quoted && field.last() == Some(&b'\r') {
+            let _ = field.pop();
+        }
+        fields.push((field, quoted));
+
+        if !self.header_skipped {
+            self.header_skipped = true;
+            return Ok(consumed);
+        }
+        if fields.len() == 1 && !fields[0].1 && fields[0].0 == b"\\." {
+            self.finished = true;
+            return Ok(consumed);
+        }
+
+        let row = self.to_row(fields.into_iter().map(|(field, quoted)| {
+            if !quoted && field == self.options.null.as_bytes() {
+                Ok(None)
+            } else {
+                String::from_utf8(field).map(Some).map_err(|e| {
+                    error::InvalidPostgresCopySnafu {
+                        reason: e.to_string(),
+                    }
+                    .build()
+                })
+            }
+        }))?;
+        rows.push(row);
+        Ok(consumed)
+    }
+
+    fn to_row(&self, fields: impl ExactSizeIterator<Item = Result<Option<String>>>) -> Result<Row> {
+        ensure!(
+            fields.len() == self.column_types.len(),
+            error::InvalidPostgresCopySnafu {
+                reason: format!(
+                    "Expect {} columns in a row, found {}",
+                    self.column_types.len(),
+                    fields.len()
+                ),
+            }
+        );
+        fields
+            .zip(&self.column_types)
+            .map(|(field, data_type)| match field? {
+                Some(field) => parse_text_value(&field, data_type),
+                None => Ok(Value::Null),
+            })
+            .collect()
+    }
+
+    /// Decodes the header or a tuple of the `binary` format, returns the number of consumed
+    /// bytes, or 0 if the data is incomplete.
+    fn decode_binary_tuple(&mut self, rows: &mut Vec<Row>) -> Result<usize> {
+        let mut reader = BinaryReader::new(&self.buffer);
+        if !self.header_skipped {
+            let Some(signature) = reader.read(BINARY_SIGNATURE.len()) else {
+                return Ok(0);
+            };
+            ensure!(
+                signature == BINARY_SIGNATURE,
+                error::InvalidPostgresCopySnafu {
+                    reason: "COPY file signature not recognized",
+                }
+            );
+            let Some(_flags) = reader.read_i32() else {
+                return Ok(0);
+            };
+            let Some(extension) = reader.read_i32() else {
+                return Ok(0);
+            };
+            if reader.read(extension.max(0) as usize).is_none() {
+                return Ok(0);
+            }
+            self.header_skipped = true;
+            return Ok(reader.position);
+        }
+
+        let Some(count) = reader.read_i16() else {
+            return Ok(0);
+        };
+        if count == -1 {
+            self.finished = true;
+            return Ok(reader.position);
+        }
+        ensure!(
+            count as usize == self.column_types.len(),
+            error::InvalidPostgresCopySnafu {
+                reason: format!(
+                    "Expect {} columns in a row, found {}",
+                    self.column_types.len(),
+                    count
+                ),
+            }
+        );
+
+        let mut row = Vec::with_capacity(self.column_types.len());
+        for data_type in &self.column_types {
+            let Some(len) = reader.read_i32() else {
+                return Ok(0);
+            };
+            if len < 0 {
+                row.push(Value::Null);
+                continue;
+            }
+            let Some(field) = reader.read(len as usize) else {
+                return Ok(0);
+            };
+            row.push(decode_binary_value(field, data_type)?);
+        }
+        rows.push(row);
+        Ok(reader.position)
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BinaryReader { data, position: 0 }
+    }
+
+    fn read(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + len)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    fn read_i16(&mut self) -> Option<i16> {
+        self.read(2).map(|b| i16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn read_i32(&mut self) -> Option<i32> {
+        self.read(4)
+            .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+/// Removes the backslash escapes of the `text` format.
+fn unescape_text(field: &[u8]) -> Result<String> {
+    let mut unescaped = Vec::with_capacity(field.len());
+    let mut iter = field.iter();
+    while let Some(b) = iter.next() {
+        if *b != b'\\' {
+            unescaped.push(*b);
+            continue;
+        }
+        match iter.next() {
+            Some(b'b') => unescaped.push(0x08),
+            Some(b'f') => unescaped.push(0x0c),
+            Some(b'n') => unescaped.push(b'\n'),
+            Some(b'r') => unescaped.push(b'\r'),
+            Some(b't') => unescaped.push(b'\t'),
+            Some(b'v') => unescaped.push(0x0b),
+            Some(b) => unescaped.push(*b),
+            None => unescaped.push(b'\\'),
+        }
+    }
+    String::from_utf8(unescaped).map_err(|e| {
+        error::InvalidPostgresCopySnafu {
+            reason: e.to_string(),
+        }
+        .build()
+    })
+}
+
+/// Decodes a field of the `binary` format, in the PostgreSQL types mapped from the column
+/// types like the results of queries.
+fn decode_binary_value(field: &[u8], data_type: &ConcreteDataType) -> Result<Value> {
+    let invalid = || {
+        error::InvalidPostgresCopySnafu {
+            reason: format!("Invalid binary input of type {data_type:?}: {field:?}"),
+        }
+        .build()
+    };
+    macro_rules! decode {
+        ($ty: ty) => {
+            <$ty>::from_be_bytes(field.try_into().map_err(|_| invalid())?)
+        };
+    }
+
+    let value = match data_type {
+        ConcreteDataType::Boolean(_) => Value::Boolean(decode!(u8) != 0),
+        ConcreteDataType::Int8(_) => Value::Int8(decode!(i8)),
+        ConcreteDataType::UInt8(_) => Value::UInt8(decode!(i8) as u8),
+        ConcreteDataType::Int16(_) => Value::Int16(decode!(i16)),
+        ConcreteDataType::UInt16(_) => Value::UInt16(decode!(i16) as u16),
+        ConcreteDataType::Int32(_) => Value::Int32(decode!(i32)),
+        ConcreteDataType::UInt32(_) => Value::UInt32(decode!(i32) as u32),
+        ConcreteDataType::Int64(_) => Value::Int64(decode!(i64)),
+        ConcreteDataType::UInt64(_) => Value::UInt64(decode!(i64) as u64),
+        ConcreteDataType::Float32(_) => Value::Float32(decode!(f32).into()),
+        ConcreteDataType::Float64(_) => Value::Float64(decode!(f64).into()),
+        ConcreteDataType::String(_) => {
+            Value::String(std::str::from_utf8(field).map_err(|_| invalid())?.into())
+        }
+        ConcreteDataType::Date(_) => Value::Date(Date::new(decode!(i32) + PG_EPOCH_DAYS)),
+        ConcreteDataType::DateTime(_) => {
+            Value::DateTime(DateTime::new(decode!(i64) / 1_000_000 + PG_EPOCH_SECONDS))
+        }
+        ConcreteDataType::Timestamp(t) => {
+            let micros = decode!(i64) + PG_EPOCH_SECONDS * 1_000_000;
+            Value::Timestamp(
+                Timestamp::new_microsecond(micros)
+                    .convert_to(t.unit())
+                    .with_context(|| error::InvalidPostgresCopySnafu {
+                        reason: format!("Timestamp overflow: {micros}us"),
+                    })?,
+            )
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(value)
+}
+
+/// Encodes the query results into the payloads of CopyData messages.
+struct CopyOutEncoder {
+    options: CopyStdioOptions,
+}
+
+impl CopyOutEncoder {
+    fn encode_header(&self, schema: &Schema) -> Bytes {
+        match self.options.format {
+            CopyStdioFormat::Binary => {
+                let mut buffer = BINARY_SIGNATURE.to_vec();
+                // The flags and the length of the header extension.
+                buffer.extend_from_slice(&0i32.to_be_bytes());
+                buffer.extend_from_slice(&0i32.to_be_bytes());
+                buffer.into()
+            }
+            CopyStdioFormat::Csv if self.options.header => {
+                let header = schema
+                    .column_schemas()
+                    .iter()
+                    .map(|c| self.quote_csv(&c.name))
+                    .collect::<Vec<_>>()
+                    .join(&(self.options.delimiter as char).to_string());
+                format!("{header}\n").into()
+            }
+            _ => Bytes::new(),
+        }
+    }
+
+    fn encode_batch(&self, batch: &RecordBatch) -> Result<Bytes> {
+        let mut buffer = vec![];
+        for row in batch.rows() {
+            match self.options.format {
+                CopyStdioFormat::Binary => {
+                    buffer.extend_from_slice(&(row.len() as i16).to_be_bytes());
+                    for value in &row {
+                        encode_binary_value(value, &mut buffer)?;
+                    }
+                }
+                CopyStdioFormat::Text | CopyStdioFormat::Csv => {
+                    for (i, value) in row.iter().enumerate() {
+                        if i > 0 {
+                            buffer.push(self.options.delimiter);
+                        }
+                        let field = match value {
+                            Value::Null => self.options.null.clone(),
+                            _ if self.options.format == CopyStdioFormat::Csv => {
+                                self.quote_csv(&text_value(value)?)
+                            }
+                            _ => self.escape_text(&text_value(value)?),
+                        };
+                        buffer.extend_from_slice(field.as_bytes());
+                    }
+                    buffer.push(b'\n');
+                }
+            }
+        }
+        Ok(buffer.into())
+    }
+
+    fn encode_trailer(&self) -> Option<Bytes> {
+        (self.options.format == CopyStdioFormat::Binary)
+            .then(|| Bytes::copy_from_slice(&(-1i16).to_be_bytes()))
+    }
+
+    fn escape_text(&self, s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c as u32 == self.options.delimiter as u32 => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Quotes the field if it contains special characters, or it's the same as the null string.
+    fn quote_csv(&self, s: &str) -> String {
+        let needs_quote = s == self.options.null
+            || s.bytes()
+                .any(|b| matches!(b, b'"' | b'\n' | b'\r') || b == self.options.delimiter);
+        if needs_quote {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_string()
+        }
+    }
+}
+
+/// Formats the value like the results of queries in the text format of PostgreSQL.
+fn text_value(value: &Value) -> Result<String> {
+    let s = match value {
+        Value::Boolean(v) => String::from(if *v { "t" } else { "f" }),
+        Value::String(v) => v.as_utf8().to_string(),
+        Value::Binary(v) => {
+            let hex = v.iter().map(|b| format!("{b:02x}")).collect::<String>();
+            format!("\\x{hex}")
+        }
+        Value::Timestamp(v) => match v.to_chrono_datetime() {
+            chrono::LocalResult::Single(datetime) => datetime.naive_utc().to_string(),
+            _ => {
+                return error::InvalidPostgresCopySnafu {
+                    reason: format!("Failed to convert timestamp {v:?}"),
+                }
+                .fail()
+            }
+        },
+        Value::List(_) => {
+            return error::InvalidPostgresCopySnafu {
+                reason: format!("Cannot copy value {value:?}: unimplemented"),
+            }
+            .fail()
+        }
+        _ => value.to_string(),
+    };
+    Ok(s)
+}
+
+fn encode_binary_value(value: &Value, buffer: &mut Vec<u8>) -> Result<()> {
+    let field = match value {
+        Value::Null => {
+            buffer.extend_from_slice(&(-1i32).to_be_bytes());
+            return Ok(());
+        }
+        Value::Boolean(v) => vec![*v as u8],
+        Value::UInt8(v) => vec![*v],
+        Value::Int8(v) => v.to_be_bytes().to_vec(),
+        Value::UInt16(v) => (*v as i16).to_be_bytes().to_vec(),
+        Value::Int16(v) => v.to_be_bytes().to_vec(),
+        Value::UInt32(v) => (*v as i32).to_be_bytes().to_vec(),
+        Value::Int32(v) => v.to_be_bytes().to_vec(),
+        Value::UInt64(v) => (*v as i64).to_be_bytes().to_vec(),
+        Value::Int64(v) => v.to_be_bytes().to_vec(),
+        Value::Float32(v) => v.0.to_be_bytes().to_vec(),
+        Value::Float64(v) => v.0.to_be_bytes().to_vec(),
+        Value::String(v) => v.as_utf8().as_bytes().to_vec(),
+        Value::Binary(v) => v.to_vec(),
+        Value::Date(v) => (v.val() - PG_EPOCH_DAYS).to_be_bytes().to_vec(),
+        Value::DateTime(v) => ((v.val() - PG_EPOCH_SECONDS) * 1_000_000)
+            .to_be_bytes()
+            .to_vec(),
+        Value::Timestamp(v) => {
+            let micros = v
+                .convert_to(TimeUnit::Microsecond)
+                .with_context(|| error::InvalidPostgresCopySnafu {
+                    reason: format!("Timestamp overflow: {v:?}"),
+                })?
+                .value();
+            (micros - PG_EPOCH_SECONDS * 1_000_000)
+                .to_be_bytes()
+                .to_vec()
+        }
+        Value::List(_) => {
+            return error::InvalidPostgresCopySnafu {
+                reason: format!("Cannot copy value {value:?}: unimplemented"),
+            }
+            .fail()
+        }
+    };
+    buffer.extend_from_slice(&(field.len() as i32).to_be_bytes());
+    buffer.extend_from_slice(&field);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+    use std::sync::Arc;
+
+    use common_recordbatch::{RecordBatches, SendableRecordBatchStream};
+    use datatypes::schema::ColumnSchema;
+    use datatypes::vectors::{Float64Vector, StringVector, TimestampMillisecondVector};
+
+    use super::*;
+
+    async fn collect(stream: SendableRecordBatchStream, options: CopyStdioOptions) -> Vec<u8> {
+        let encoder = CopyOutEncoder { options };
+        let mut data = encoder.encode_header(&stream.schema()).to_vec();
+        let batches = common_recordbatch::util::collect(stream).await.unwrap();
+        for batch in batches {
+            data.extend_from_slice(&encoder.encode_batch(&batch).unwrap());
+        }
+        if let Some(trailer) = encoder.encode_trailer() {
+            data.extend_from_slice(&trailer);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_copy_stdio() {
+        assert_matches!(
+            parse_copy_stdio(" copy demo (host) FROM STDIN WITH CSV"),
+            Some(CopyStdio::From { .. })
+        );
+        assert_matches!(
+            parse_copy_stdio("COPY (SELECT 1) TO STDOUT;"),
+            Some(CopyStdio::To { .. })
+        );
+
+        assert!(parse_copy_stdio("SELECT 1").is_none());
+        assert!(parse_copy_stdio("COPY demo TO 'demo.parquet'").is_none());
+        // Left to the query engine to report the errors.
+        assert!(parse_copy_stdio("COPY demo FROM STDIN (FORMAT json)").is_none());
+        assert!(parse_copy_stdio("COPY demo FROM STDIN; SELECT 1").is_none());
+    }
+
+    fn column_types() -> Vec<ConcreteDataType> {
+        vec![
+            ConcreteDataType::string_datatype(),
+            ConcreteDataType::float64_datatype(),
+            ConcreteDataType::timestamp_millisecond_datatype(),
+        ]
+    }
+
+    fn expected_rows() -> Vec<Row> {
+        vec![
+            vec![
+                Value::String("host1".into()),
+                Value::Float64(1.5.into()),
+                Value::Timestamp(Timestamp::new_millisecond(1000)),
+            ],
+            vec![
+                Value::String("host\t2\n".into()),
+                Value::Null,
+                Value::Timestamp(Timestamp::new_millisecond(2000)),
+            ],
+        ]
+    }
+
+    /// Decodes the data in chunks of the size, to test the rows split across the messages.
+    fn decode(options: CopyStdioOptions, data: &[u8], chunk_size: usize) -> Result<Vec<Row>> {
+        let mut decoder = CopyInDecoder::new(options, column_types());
+        let mut rows = vec![];
+        for chunk in data.chunks(chunk_size) {
+            rows.extend(decoder.decode(chunk)?);
+        }
+        rows.extend(decoder.finish()?);
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_decode_text() {
+        let data =
+            b"host1\t1.5\t1970-01-01T00:00:01Z\nhost\\t2\\n\t\\N\t1970-01-01T00:00:02Z\n\\.\n";
+        for chunk_size in [1, 7, data.len()] {
+            let rows = decode(
+                CopyStdioOptions::new(CopyStdioFormat::Text),
+                data,
+                chunk_size,
+            )
+            .unwrap();
+            assert_eq!(expected_rows(), rows);
+        }
+
+        // The last line may have no line ending.
+        let data = b"host1\t1.5\t1970-01-01T00:00:01Z";
+        let rows = decode(
+            CopyStdioOptions::new(CopyStdioFormat::Text),
+            data,
+            data.len(),
+        )
+        .unwrap();
+        assert_eq!(expected_rows()[..1], rows);
+
+        let data = b"host1\t1.5\n";
+        assert!(decode(
+            CopyStdioOptions::new(CopyStdioFormat::Text),
+            data,
+            data.len()
+        )
+        .is_err());
+        let data = b"host1\tnot a number\t1970-01-01T00:00:01Z\n";
+        assert!(decode(
+            CopyStdioOptions::new(CopyStdioFormat::Text),
+            data,
+            data.len()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decode_csv() {
+        let data = b"host,cpu,ts\r\n\
+            host1,1.5,1970-01-01T00:00:01Z\r\n\
+            \"host\t2\n\",,1970-01-01T00:00:02Z\r\n";
+        let mut options = CopyStdioOptions::new(CopyStdioFormat::Csv);
+        options.header = true;
+        for chunk_size in [1, 5, data.len()] {
+            let rows = decode(options.clone(), data, chunk_size).unwrap();
+            assert_eq!(expected_rows(), rows);
+        }
+
+        // Quoted empty strings are not null.
+        let data = b"\"\",\"2\",1970-01-01T00:00:01Z\n\"a\"\"b\",,1970-01-01T00:00:01Z";
+        for chunk_size in [1, data.len()] {
+            let rows = decode(
+                CopyStdioOptions::new(CopyStdioFormat::Csv),
+                data,
+                chunk_size,
+            )
+            .unwrap();
+            assert_eq!(Value::String("".into()), rows[0][0]);
+            assert_eq!(Value::Float64(2.0.into()), rows[0][1]);
+            assert_eq!(Value::String("a\"b".into()), rows[1][0]);
+            assert_eq!(Value::Null, rows[1][1]);
+        }
+
+        let data = b"\"host1,1.5,1970-01-01T00:00:01Z\n";
+        assert!(decode(
+            CopyStdioOptions::new(CopyStdioFormat::Csv),
+            data,
+            data.len()
+        )
+        .is_err());
+    }
+
+    fn recordbatches() -> RecordBatches {
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new("host", ConcreteDataType::string_datatype(), true),
+            ColumnSchema::new("cpu", ConcreteDataType::float64_datatype(), true),
+            ColumnSchema::new(
+                "ts",
+                ConcreteDataType::timestamp_millisecond_datatype(),
+                true,
+            ),
+        ]));
+        let batch = RecordBatch::new(
+            schema.clone(),
+            vec![
+                Arc::new(StringVector::from(vec!["host1", "host\t2\n"])) as _,
+                Arc::new(Float64Vector::from(vec![Some(1.5), None])) as _,
+                Arc::new(TimestampMillisecondVector::from_vec(vec![1000, 2000])) as _,
+            ],
+        )
+        .unwrap();
+        RecordBatches::try_new(schema, vec![batch]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encode() {
+        let text = collect(
+            recordbatches().as_stream(),
+            CopyStdioOptions::new(CopyStdioFormat::Text),
+        )
+        .await;
+        assert_eq!(
+            "host1\t1.5\t1970-01-01 00:00:01\nhost\\t2\\n\t\\N\t1970-01-01 00:00:02\n",
+            String::from_utf8(text).unwrap()
+        );
+
+        let mut options = CopyStdioOptions::new(CopyStdioFormat::Csv);
+        options.header = true;
+        let csv = collect(recordbatches().as_stream(), options).await;
+        assert_eq!(
+            "host,cpu,ts\nhost1,1.5,1970-01-01 00:00:01\n\"host\t2\n\",,1970-01-01 00:00:02\n",
+            String::from_utf8(csv).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_binary_round_trip() {
+        let data = collect(
+            recordbatches().as_stream(),
+            CopyStdioOptions::new(CopyStdioFormat::Binary),
+        )
+        .await;
+        assert!(data.starts_with(BINARY_SIGNATURE));
+        assert!(data.ends_with(&(-1i16).to_be_bytes()));
+
+        for chunk_size in [1, 3, data.len()] {
+            let rows = decode(
+                CopyStdioOptions::new(CopyStdioFormat::Binary),
+                &data,
+                chunk_size,
+            )
+            .unwrap();
+            assert_eq!(expected_rows(), rows);
+        }
+
+        // Truncated data.
+        let data = &data[..data.len() - 4];
+        assert!(decode(
+            CopyStdioOptions::new(CopyStdioFormat::Binary),
+            data,
+            data.len()
+        )
+        .is_err());
+    }
+}
@@ -17,6 +17,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use chrono::LocalResult;
+use common_error::prelude::{ErrorExt, StatusCode};
 use common_query::Output;
 use common_recordbatch::error::Result as RecordBatchResult;
 use common_recordbatch::RecordBatch;
@@ -80,12 +81,24 @@ fn output_to_query_response<'a>(
         }
         Err(e) => Ok(Response::Error(Box::new(ErrorInfo::new(
             "ERROR".to_string(),
-            "XX000".to_string(),
+            sqlstate(e.status_code()).to_string(),
             e.to_string(),
         )))),
     }
 }
 
+/// Maps the status code of an error to the PostgreSQL SQLSTATE.
+pub(super) fn sqlstate(status_code: StatusCode) -> &'static str {
+    match status_code {
+        // PostgreSQL reports both cancelled and timed out statements as `query_canceled`.
+        StatusCode::Cancelled | StatusCode::QueryTimeout => "57014",
+        // out_of_memory
+        StatusCode::ExceedMemoryLimit => "53200",
+        // internal_error
+        _ => "XX000",
+    }
+}
+
 fn recordbatches_to_query_response<'a, S>(
     recordbatches_stream: S,
     schema: SchemaRef,
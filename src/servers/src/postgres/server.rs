@@ -22,13 +22,14 @@ use common_telemetry::logging::error;
 use common_telemetry::{debug, warn};
 use futures::StreamExt;
 use pgwire::api::MakeHandler;
-use pgwire::tokio::process_socket;
 use tokio;
 use tokio_rustls::TlsAcceptor;
 
+use super::socket::process_socket;
 use super::{MakePostgresServerHandler, MakePostgresServerHandlerBuilder};
 use crate::auth::UserProviderRef;
 use crate::error::Result;
+use crate::query_handler::grpc::BulkInsertHandlerRef;
 use crate::query_handler::sql::ServerSqlQueryHandlerRef;
 use crate::server::{AbortableStream, BaseTcpServer, Server};
 use crate::tls::TlsOption;
@@ -62,6 +63,12 @@ impl PostgresServer {
         }
     }
 
+    /// Enables inserting the rows of `COPY FROM STDIN`.
+    pub fn with_bulk_insert_handler(mut self, handler: BulkInsertHandlerRef) -> Self {
+        Arc::make_mut(&mut self.make_handler).bulk_insert_handler = Some(handler);
+        self
+    }
+
     fn accept(
         &self,
         io_runtime: Arc<Runtime>,
@@ -83,13 +90,7 @@ impl PostgresServer {
                             Err(e) => warn!("Failed to get PostgreSQL client addr, err: {}", e),
                         }
 
-                        io_runtime.spawn(process_socket(
-                            io_stream,
-                            tls_acceptor.clone(),
-                            handler.clone(),
-                            handler.clone(),
-                            handler,
-                        ));
+                        io_runtime.spawn(process_socket(io_stream, tls_acceptor.clone(), handler));
                     }
                 };
             }
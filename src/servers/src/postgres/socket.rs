@@ -0,0 +1,425 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The connection loop of the PostgreSQL server, forked from `process_socket` in `src/tokio.rs`
+//! of pgwire 0.12.0, which is pinned in `Cargo.toml`. Port the upstream changes to this loop
+//! when upgrading pgwire, until pgwire lets the query handlers take over the connection.
+//!
+//! pgwire doesn't let the query handlers read from the client, so the COPY sub-protocol is run
+//! here: the messages of COPY statements are handled by this loop, and all the other messages
+//! are delegated to the handlers of pgwire.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use common_error::prelude::ErrorExt;
+use futures::{SinkExt, StreamExt};
+use pgwire::api::auth::StartupHandler;
+use pgwire::api::query::{ExtendedQueryHandler, SimpleQueryHandler};
+use pgwire::api::{ClientInfo, DefaultClient, PgWireConnectionState};
+use pgwire::error::{ErrorInfo, PgWireError, PgWireResult};
+use pgwire::messages::copy::{CopyData, CopyDone, CopyInResponse, CopyOutResponse};
+use pgwire::messages::data::{NoData, ParameterDescription};
+use pgwire::messages::extendedquery::{
+    BindComplete, CloseComplete, ParseComplete, TARGET_TYPE_BYTE_PORTAL, TARGET_TYPE_BYTE_STATEMENT,
+};
+use pgwire::messages::response::{CommandComplete, ReadyForQuery, READY_STATUS_IDLE};
+use pgwire::messages::{PgWireBackendMessage, PgWireFrontendMessage};
+use pgwire::tokio::PgWireMessageServerCodec;
+use sql::ast::{Ident, ObjectName};
+use sql::statements::copy::{CopyStdio, CopyStdioFormat, CopyStdioOptions};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::codec::Framed;
+
+use super::copy::{parse_copy_stdio, CopyOut};
+use super::handler::sqlstate;
+use super::PostgresServerHandler;
+use crate::error::Error;
+
+/// The length and the code of SSLRequest.
+const SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 4, 210, 22, 47];
+
+pub(crate) async fn process_socket(
+    mut tcp_socket: TcpStream,
+    tls_acceptor: Option<Arc<TlsAcceptor>>,
+    handler: Arc<PostgresServerHandler>,
+) -> PgWireResult<()> {
+    let addr = tcp_socket.peer_addr()?;
+    tcp_socket.set_nodelay(true)?;
+
+    let ssl = peek_for_sslrequest(&mut tcp_socket, tls_acceptor.is_some()).await?;
+    let client_info = DefaultClient::new(addr, ssl);
+    match tls_acceptor {
+        Some(tls_acceptor) if ssl => {
+            let tls_socket = tls_acceptor.accept(tcp_socket).await?;
+            let socket = Framed::new(tls_socket, PgWireMessageServerCodec::new(client_info));
+            Connection::new(socket, handler).run().await
+        }
+        _ => {
+            let socket = Framed::new(tcp_socket, PgWireMessageServerCodec::new(client_info));
+            Connection::new(socket, handler).run().await
+        }
+    }
+}
+
+/// Replies to the SSLRequest at the beginning of the connection, if any. Returns whether to
+/// accept TLS on the connection.
+async fn peek_for_sslrequest(tcp_socket: &mut TcpStream, ssl_supported: bool) -> io::Result<bool> {
+    let mut buf = [0u8; SSL_REQUEST.len()];
+    loop {
+        let size = tcp_socket.peek(&mut buf).await?;
+        if size == 0 {
+            return Ok(false);
+        }
+        if size == buf.len() {
+            break;
+        }
+    }
+    if buf != SSL_REQUEST {
+        return Ok(false);
+    }
+
+    tcp_socket.read_exact(&mut buf).await?;
+    if ssl_supported {
+        tcp_socket.write_all(b"S").await?;
+    } else {
+        tcp_socket.write_all(b"N").await?;
+    }
+    Ok(ssl_supported)
+}
+
+struct Connection<S> {
+    socket: Framed<S, PgWireMessageServerCodec>,
+    handler: Arc<PostgresServerHandler>,
+    /// The COPY statements prepared in the extended query protocol, by the statement names.
+    statements: HashMap<String, CopyStdio>,
+    /// The portals bound to the prepared COPY statements, by the portal names.
+    portals: HashMap<String, CopyStdio>,
+    /// Whether the messages are discarded until Sync, after an error in the extended query
+    /// protocol.
+    discarding: bool,
+}
+
+impl<S> Connection<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync,
+{
+    fn new(
+        socket: Framed<S, PgWireMessageServerCodec>,
+        handler: Arc<PostgresServerHandler>,
+    ) -> Self {
+        Self {
+            socket,
+            handler,
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+            discarding: false,
+        }
+    }
+
+    async fn run(mut self) -> PgWireResult<()> {
+        while let Some(message) = self.socket.next().await {
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => return self.process_error(e.into(), false).await,
+            };
+            let extended = matches!(
+                message,
+                PgWireFrontendMessage::Parse(_)
+                    | PgWireFrontendMessage::Bind(_)
+                    | PgWireFrontendMessage::Describe(_)
+                    | PgWireFrontendMessage::Execute(_)
+                    | PgWireFrontendMessage::Close(_)
+            );
+            if let Err(e) = self.process_message(message).await {
+                self.process_error(e, extended).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_message(&mut self, message: PgWireFrontendMessage) -> PgWireResult<()> {
+        if matches!(
+            self.socket.state(),
+            PgWireConnectionState::AwaitingStartup
+                | PgWireConnectionState::AuthenticationInProgress
+        ) {
+            return self.handler.on_startup(&mut self.socket, message).await;
+        }
+
+        if self.discarding {
+            if !matches!(message, PgWireFrontendMessage::Sync(_)) {
+                return Ok(());
+            }
+            self.discarding = false;
+        }
+
+        let handler = self.handler.clone();
+        match message {
+            PgWireFrontendMessage::Query(query) => match parse_copy_stdio(query.query()) {
+                Some(statement) => {
+                    self.copy(statement).await?;
+                    self.socket
+                        .send(PgWireBackendMessage::ReadyForQuery(ReadyForQuery::new(
+                            READY_STATUS_IDLE,
+                        )))
+                        .await?;
+                    Ok(())
+                }
+                None => handler.on_query(&mut self.socket, query).await,
+            },
+            PgWireFrontendMessage::Parse(parse) => {
+                let name = parse.name().clone().unwrap_or_default();
+                match parse_copy_stdio(parse.query()) {
+                    Some(statement) => {
+                        let _ = self.statements.insert(name, statement);
+                        self.socket
+                            .feed(PgWireBackendMessage::ParseComplete(ParseComplete::new()))
+                            .await?;
+                        Ok(())
+                    }
+                    None => {
+                        let _ = self.statements.remove(&name);
+                        handler.on_parse(&mut self.socket, parse).await
+                    }
+                }
+            }
+            PgWireFrontendMessage::Bind(bind) => {
+                let portal_name = bind.portal_name().clone().unwrap_or_default();
+                let statement_name = bind.statement_name().clone().unwrap_or_default();
+                match self.statements.get(&statement_name) {
+                    Some(statement) => {
+                        let _ = self.portals.insert(portal_name, statement.clone());
+                        self.socket
+                            .feed(PgWireBackendMessage::BindComplete(BindComplete::new()))
+                            .await?;
+                        Ok(())
+                    }
+                    None => {
+                        let _ = self.portals.remove(&portal_name);
+                        handler.on_bind(&mut self.socket, bind).await
+                    }
+                }
+            }
+            PgWireFrontendMessage::Describe(describe) => {
+                let name = describe.name().clone().unwrap_or_default();
+                match describe.target_type() {
+                    TARGET_TYPE_BYTE_STATEMENT if self.statements.contains_key(&name) => {
+                        self.socket
+                            .feed(PgWireBackendMessage::ParameterDescription(
+                                ParameterDescription::new(vec![]),
+                            ))
+                            .await?;
+                        self.socket
+                            .feed(PgWireBackendMessage::NoData(NoData::new()))
+                            .await?;
+                        Ok(())
+                    }
+                    TARGET_TYPE_BYTE_PORTAL if self.portals.contains_key(&name) => {
+                        self.socket
+                            .feed(PgWireBackendMessage::NoData(NoData::new()))
+                            .await?;
+                        Ok(())
+                    }
+                    _ => handler.on_describe(&mut self.socket, describe).await,
+                }
+            }
+            PgWireFrontendMessage::Execute(execute) => {
+                let name = execute.name().clone().unwrap_or_default();
+                match self.portals.get(&name).cloned() {
+                    Some(statement) => self.copy(statement).await,
+                    None => handler.on_execute(&mut self.socket, execute).await,
+                }
+            }
+            PgWireFrontendMessage::Close(close) => {
+                let name = close.name().clone().unwrap_or_default();
+                let closed = match close.target_type() {
+                    TARGET_TYPE_BYTE_STATEMENT => self.statements.remove(&name).is_some(),
+                    TARGET_TYPE_BYTE_PORTAL => self.portals.remove(&name).is_some(),
+                    _ => false,
+                };
+                if closed {
+                    self.socket
+                        .feed(PgWireBackendMessage::CloseComplete(CloseComplete::new()))
+                        .await?;
+                    Ok(())
+                } else {
+                    handler.on_close(&mut self.socket, close).await
+                }
+            }
+            PgWireFrontendMessage::Sync(sync) => handler.on_sync(&mut self.socket, sync).await,
+            PgWireFrontendMessage::Flush(_) => {
+                self.socket.flush().await?;
+                Ok(())
+            }
+            PgWireFrontendMessage::Terminate(_) => {
+                self.socket.close().await?;
+                Ok(())
+            }
+            // The rest of an aborted COPY FROM STDIN.
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs a COPY statement, and completes it with the number of copied rows.
+    async fn copy(&mut self, statement: CopyStdio) -> PgWireResult<()> {
+        let rows = match statement {
+            CopyStdio::From {
+                table_name,
+                columns,
+                options,
+            } => self.copy_in(&table_name, &columns, options).await?,
+            CopyStdio::To { source, options } => self.copy_out(&source.to_query(), options).await?,
+        };
+        self.socket
+            .feed(PgWireBackendMessage::CommandComplete(CommandComplete::new(
+                format!("COPY {rows}"),
+            )))
+            .await?;
+        Ok(())
+    }
+
+    async fn copy_in(
+        &mut self,
+        table_name: &ObjectName,
+        columns: &[Ident],
+        options: CopyStdioOptions,
+    ) -> PgWireResult<usize> {
+        let format = format_code(&options);
+        let mut copy_in = self
+            .handler
+            .copy_in(table_name, columns, options)
+            .await
+            .map_err(user_error)?;
+        let columns = copy_in.columns();
+        self.socket
+            .send(PgWireBackendMessage::CopyInResponse(CopyInResponse::new(
+                format,
+                columns as i16,
+                vec![format as i16; columns],
+            )))
+            .await?;
+
+        loop {
+            match self.socket.next().await.transpose()? {
+                Some(PgWireFrontendMessage::CopyData(data)) => {
+                    copy_in.push(data.data()).await.map_err(user_error)?
+                }
+                Some(PgWireFrontendMessage::CopyDone(_)) => {
+                    return copy_in.finish().await.map_err(user_error)
+                }
+                Some(PgWireFrontendMessage::CopyFail(fail)) => {
+                    return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                        "ERROR".to_string(),
+                        "57014".to_string(),
+                        format!("COPY from stdin failed: {}", fail.message()),
+                    ))))
+                }
+                // Sync and Flush are ignored during COPY FROM STDIN.
+                Some(PgWireFrontendMessage::Sync(_)) | Some(PgWireFrontendMessage::Flush(_)) => {}
+                Some(_) => {
+                    return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                        "ERROR".to_string(),
+                        "08P01".to_string(),
+                        "Unexpected message during COPY from stdin".to_string(),
+                    ))))
+                }
+                None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            }
+        }
+    }
+
+    async fn copy_out(&mut self, query: &str, options: CopyStdioOptions) -> PgWireResult<usize> {
+        let format = format_code(&options);
+        let CopyOut { columns, mut data } = self
+            .handler
+            .copy_out(query, options)
+            .await
+            .map_err(user_error)?;
+        self.socket
+            .feed(PgWireBackendMessage::CopyOutResponse(CopyOutResponse::new(
+                format,
+                columns as i16,
+                vec![format as i16; columns],
+            )))
+            .await?;
+
+        let mut rows = 0;
+        while let Some(chunk) = data.next().await {
+            let (chunk, n) = chunk.map_err(user_error)?;
+            rows += n;
+            self.socket
+                .feed(PgWireBackendMessage::CopyData(CopyData::new(chunk)))
+                .await?;
+        }
+        self.socket
+            .feed(PgWireBackendMessage::CopyDone(CopyDone::new()))
+            .await?;
+        Ok(rows)
+    }
+
+    /// Reports the error to the client. In the extended query protocol, the messages are then
+    /// discarded until Sync, which is answered with ReadyForQuery.
+    async fn process_error(&mut self, error: PgWireError, extended: bool) -> PgWireResult<()> {
+        let error_info = match error {
+            PgWireError::UserError(error_info) => *error_info,
+            PgWireError::ApiError(e) => {
+                ErrorInfo::new("ERROR".to_string(), "XX000".to_string(), e.to_string())
+            }
+            _ => {
+                let error_info =
+                    ErrorInfo::new("FATAL".to_string(), "XX000".to_string(), error.to_string());
+                self.socket
+                    .send(PgWireBackendMessage::ErrorResponse(error_info.into()))
+                    .await?;
+                self.socket.close().await?;
+                return Ok(());
+            }
+        };
+
+        self.socket
+            .feed(PgWireBackendMessage::ErrorResponse(error_info.into()))
+            .await?;
+        if extended {
+            self.discarding = true;
+        } else {
+            self.socket
+                .feed(PgWireBackendMessage::ReadyForQuery(ReadyForQuery::new(
+                    READY_STATUS_IDLE,
+                )))
+                .await?;
+        }
+        self.socket.flush().await?;
+        Ok(())
+    }
+}
+
+/// The format code of the CopyInResponse and CopyOutResponse messages.
+fn format_code(options: &CopyStdioOptions) -> i8 {
+    match options.format {
+        CopyStdioFormat::Binary => 1,
+        CopyStdioFormat::Text | CopyStdioFormat::Csv => 0,
+    }
+}
+
+fn user_error(e: Error) -> PgWireError {
+    PgWireError::UserError(Box::new(ErrorInfo::new(
+        "ERROR".to_string(),
+        sqlstate(e.status_code()).to_string(),
+        e.to_string(),
+    )))
+}
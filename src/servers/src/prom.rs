@@ -13,14 +13,21 @@
 // limitations under the License.
 
 //! prom supply the prometheus HTTP API Server compliance
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use api::prometheus::remote::label_matcher::Type as LabelMatcherType;
+use api::prometheus::remote::{
+    Label, LabelMatcher, MetricMetadata, Query as RemoteQuery, TimeSeries,
+};
 use async_trait::async_trait;
 use axum::body::BoxBody;
-use axum::extract::{Query, State};
-use axum::{routing, Form, Json, Router};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode as HttpStatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{routing, Extension, Form, Json, Router};
 use common_error::prelude::ErrorExt;
 use common_error::status_code::StatusCode;
 use common_query::Output;
@@ -30,14 +37,15 @@ use datatypes::prelude::ConcreteDataType;
 use datatypes::scalars::ScalarVector;
 use datatypes::vectors::{Float64Vector, StringVector, TimestampMillisecondVector};
 use futures::FutureExt;
-use promql_parser::label::METRIC_NAME;
+use promql_parser::label::{MatchOp, Matcher, METRIC_NAME};
 use promql_parser::parser::{
-    AggregateExpr, BinaryExpr, Call, Expr as PromqlExpr, MatrixSelector, ParenExpr, SubqueryExpr,
-    UnaryExpr, VectorSelector,
+    AggregateExpr, BinaryExpr, Call, Expr as PromqlExpr, MatrixSelector, ParenExpr, StringLiteral,
+    SubqueryExpr, UnaryExpr, VectorSelector,
 };
-use query::parser::PromQuery;
+use query::parser::{PromQuery, QueryLanguageParser};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use session::context::{QueryContext, QueryContextRef, UserInfo};
 use snafu::{ensure, OptionExt, ResultExt};
 use tokio::sync::oneshot::Sender;
 use tokio::sync::{oneshot, Mutex};
@@ -48,10 +56,12 @@ use tower_http::trace::TraceLayer;
 
 use crate::auth::UserProviderRef;
 use crate::error::{
-    AlreadyStartedSnafu, CollectRecordbatchSnafu, InternalSnafu, Result, StartHttpSnafu,
+    AlreadyStartedSnafu, CollectRecordbatchSnafu, InternalSnafu, InvalidQuerySnafu,
+    QueryTimeoutSnafu, Result, StartHttpSnafu,
 };
 use crate::http::authorize::HttpAuth;
 use crate::server::Server;
+use crate::{parse_catalog_and_schema_from_client_database_name, prometheus};
 
 pub const PROM_API_VERSION: &str = "v1";
 
@@ -59,7 +69,60 @@ pub type PromHandlerRef = Arc<dyn PromHandler + Send + Sync>;
 
 #[async_trait]
 pub trait PromHandler {
-    async fn do_query(&self, query: &PromQuery) -> Result<Output>;
+    /// Executes the query in the context of the user sending it.
+    async fn do_query(&self, query: &PromQuery, query_ctx: QueryContextRef) -> Result<Output>;
+
+    /// Returns the names of all metrics, i.e. the tables that can be queried by PromQL, in the
+    /// database of the query context.
+    async fn metric_names(&self, query_ctx: QueryContextRef) -> Result<Vec<String>>;
+
+    /// Returns the label names of the metric, i.e. the tag columns of its table, or `None` if
+    /// the metric doesn't exist.
+    async fn label_names(
+        &self,
+        metric: &str,
+        query_ctx: QueryContextRef,
+    ) -> Result<Option<Vec<String>>>;
+
+    /// Returns the distinct values of the label `label_name` of the series selected by the
+    /// matchers of `query` that have samples in its time range. The values of `__name__` are
+    /// the name of the selected metric.
+    async fn label_values(
+        &self,
+        query: &RemoteQuery,
+        label_name: &str,
+        query_ctx: QueryContextRef,
+    ) -> Result<Vec<String>>;
+
+    /// Returns the loaded recording and alerting rule groups.
+    async fn rule_groups(&self) -> Result<Vec<PromRuleGroup>> {
+        Ok(vec![])
+    }
+
+    /// Returns the pending and firing alerts.
+    async fn alerts(&self) -> Result<Vec<PromAlert>> {
+        Ok(vec![])
+    }
+
+    /// Returns the metadata of the metric family `metric`, or of all metric families if it's
+    /// `None`.
+    async fn metric_metadata(
+        &self,
+        _metric: Option<&str>,
+        _query_ctx: QueryContextRef,
+    ) -> Result<Vec<MetricMetadata>> {
+        Ok(vec![])
+    }
+
+    /// Returns the series selected by the matchers of `query`, with their exemplars in its time
+    /// range. The series without exemplars in the range are not returned.
+    async fn query_exemplars(
+        &self,
+        _query: &RemoteQuery,
+        _query_ctx: QueryContextRef,
+    ) -> Result<Vec<TimeSeries>> {
+        Ok(vec![])
+    }
 }
 
 /// PromServer represents PrometheusServer which handles the compliance with prometheus HTTP API
@@ -84,15 +147,11 @@ impl PromServer {
     }
 
     pub fn make_app(&self) -> Router {
-        // TODO(ruihang): implement format_query, series, labels, values, query_examplars and targets methods
-
-        let router = Router::new()
-            .route("/query", routing::post(instant_query).get(instant_query))
-            .route("/query_range", routing::post(range_query).get(range_query))
-            .with_state(self.query_handler.clone());
-
         Router::new()
-            .nest(&format!("/api/{PROM_API_VERSION}"), router)
+            .nest(
+                &format!("/api/{PROM_API_VERSION}"),
+                api_router(self.query_handler.clone()),
+            )
             // middlewares
             .layer(
                 ServiceBuilder::new()
@@ -106,6 +165,29 @@ impl PromServer {
     }
 }
 
+/// Routes of the [Prometheus HTTP API](https://prometheus.io/docs/prometheus/latest/querying/api/),
+/// which are nested under `/api/v1`.
+pub fn api_router<S>(query_handler: PromHandlerRef) -> Router<S> {
+    // TODO(ruihang): implement format_query and targets methods
+    Router::new()
+        .route("/query", routing::post(instant_query).get(instant_query))
+        .route("/query_range", routing::post(range_query).get(range_query))
+        .route("/series", routing::post(series_query).get(series_query))
+        .route("/labels", routing::post(labels_query).get(labels_query))
+        .route(
+            "/label/:label_name/values",
+            routing::post(label_values_query).get(label_values_query),
+        )
+        .route("/rules", routing::get(rules_query))
+        .route("/alerts", routing::get(alerts_query))
+        .route("/metadata", routing::get(metric_metadata_query))
+        .route(
+            "/query_exemplars",
+            routing::post(exemplars_query).get(exemplars_query),
+        )
+        .with_state(query_handler)
+}
+
 pub const PROM_SERVER: &str = "PROM_SERVER";
 
 #[async_trait]
@@ -154,23 +236,188 @@ impl Server for PromServer {
     }
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+/// Error types in the responses of Prometheus HTTP API.
+const ERROR_BAD_DATA: &str = "bad_data";
+const ERROR_EXECUTION: &str = "execution";
+const ERROR_TIMEOUT: &str = "timeout";
+const ERROR_CANCELED: &str = "canceled";
+const ERROR_INTERNAL: &str = "internal";
+const ERROR_UNAVAILABLE: &str = "unavailable";
+
+/// Step of instant queries, which are evaluated as range queries that start and end at the same time.
+const INSTANT_QUERY_STEP: &str = "1s";
+
+pub type PromLabels = BTreeMap<String, String>;
+
+/// A sample, which is a timestamp in seconds and a value formatted like Prometheus does.
+pub type PromValue = (f64, String);
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct PromSeries {
-    metric: HashMap<String, String>,
-    values: Vec<(f64, String)>,
+    metric: PromLabels,
+    values: Vec<PromValue>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromSample {
+    metric: PromLabels,
+    value: PromValue,
+}
+
+/// Result of an expression query, in one of the
+/// [formats](https://prometheus.io/docs/prometheus/latest/querying/api/#expression-query-result-formats)
+/// determined by the type of the expression.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum PromQueryResult {
+    Matrix(Vec<PromSeries>),
+    Vector(Vec<PromSample>),
+    Scalar(PromValue),
+    String(PromValue),
+}
+
+impl Default for PromQueryResult {
+    fn default() -> Self {
+        PromQueryResult::Matrix(vec![])
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct PromData {
     #[serde(rename = "resultType")]
     result_type: String,
-    result: Vec<PromSeries>,
+    result: PromQueryResult,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+impl PromData {
+    pub fn new(result: PromQueryResult) -> Self {
+        let result_type = match &result {
+            PromQueryResult::Matrix(_) => "matrix",
+            PromQueryResult::Vector(_) => "vector",
+            PromQueryResult::Scalar(_) => "scalar",
+            PromQueryResult::String(_) => "string",
+        };
+        PromData {
+            result_type: result_type.to_string(),
+            result,
+        }
+    }
+}
+
+/// An alert of an alerting rule.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromAlert {
+    pub labels: PromLabels,
+    pub annotations: PromLabels,
+    /// "pending" or "firing".
+    pub state: String,
+    /// Time when the alert became pending, in RFC3339 format.
+    #[serde(rename = "activeAt")]
+    pub active_at: String,
+    pub value: String,
+}
+
+/// Health of a rule in its last evaluation.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromRuleHealth {
+    /// "ok", "err" or "unknown" if the rule has not been evaluated.
+    pub health: String,
+    #[serde(rename = "lastError", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Time of the last evaluation in RFC3339 format.
+    #[serde(rename = "lastEvaluation", skip_serializing_if = "Option::is_none")]
+    pub last_evaluation: Option<String>,
+    /// Duration of the last evaluation in seconds.
+    #[serde(rename = "evaluationTime")]
+    pub evaluation_time: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PromRule {
+    Alerting {
+        name: String,
+        query: String,
+        /// The `for` duration in seconds.
+        duration: f64,
+        labels: PromLabels,
+        annotations: PromLabels,
+        alerts: Vec<PromAlert>,
+        /// "inactive", "pending" or "firing", the most severe state of the alerts.
+        state: String,
+        #[serde(flatten)]
+        health: PromRuleHealth,
+    },
+    Recording {
+        name: String,
+        query: String,
+        labels: PromLabels,
+        #[serde(flatten)]
+        health: PromRuleHealth,
+    },
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromRuleGroup {
+    pub name: String,
+    pub file: String,
+    pub rules: Vec<PromRule>,
+    /// Evaluation interval in seconds.
+    pub interval: f64,
+    #[serde(rename = "lastEvaluation", skip_serializing_if = "Option::is_none")]
+    pub last_evaluation: Option<String>,
+    #[serde(rename = "evaluationTime")]
+    pub evaluation_time: f64,
+}
+
+/// Metadata of a metric family.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromMetadata {
+    #[serde(rename = "type")]
+    pub metric_type: String,
+    pub help: String,
+    pub unit: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromExemplar {
+    pub labels: PromLabels,
+    pub value: String,
+    /// Timestamp in seconds.
+    pub timestamp: f64,
+}
+
+/// Exemplars of a series.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct PromExemplars {
+    #[serde(rename = "seriesLabels")]
+    pub series_labels: PromLabels,
+    pub exemplars: Vec<PromExemplar>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum PromResponse {
+    PromData(PromData),
+    /// Label names or label values.
+    Labels(Vec<String>),
+    Series(Vec<PromLabels>),
+    RuleGroups {
+        groups: Vec<PromRuleGroup>,
+    },
+    Alerts {
+        alerts: Vec<PromAlert>,
+    },
+    /// Metadata of the metric families, keyed by their names.
+    Metadata(BTreeMap<String, Vec<PromMetadata>>),
+    Exemplars(Vec<PromExemplars>),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct PromJsonResponse {
     status: String,
-    data: PromData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<PromResponse>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -181,232 +428,1071 @@ pub struct PromJsonResponse {
 }
 
 impl PromJsonResponse {
-    pub fn error<S1, S2>(error_type: S1, reason: S2) -> Json<Self>
+    pub fn error<S1, S2>(error_type: S1, reason: S2) -> Self
     where
         S1: Into<String>,
         S2: Into<String>,
     {
-        Json(PromJsonResponse {
+        PromJsonResponse {
             status: "error".to_string(),
-            data: PromData::default(),
+            data: None,
             error: Some(reason.into()),
             error_type: Some(error_type.into()),
             warnings: None,
-        })
+        }
     }
 
-    pub fn success(data: PromData) -> Json<Self> {
-        Json(PromJsonResponse {
+    pub fn success(data: PromResponse) -> Self {
+        PromJsonResponse {
             status: "success".to_string(),
-            data,
+            data: Some(data),
             error: None,
             error_type: None,
             warnings: None,
-        })
+        }
     }
 
-    /// Convert from `Result<Output>`
-    pub async fn from_query_result(result: Result<Output>, metric_name: String) -> Json<Self> {
-        let response: Result<Json<Self>> = try {
-            let json = match result? {
-                Output::RecordBatches(batches) => {
-                    Self::success(Self::record_batches_to_data(batches, metric_name)?)
-                }
-                Output::Stream(stream) => {
-                    let record_batches = RecordBatches::try_collect(stream)
-                        .await
-                        .context(CollectRecordbatchSnafu)?;
-                    Self::success(Self::record_batches_to_data(record_batches, metric_name)?)
-                }
-                Output::AffectedRows(_) => Self::error(
-                    "unexpected result",
-                    "expected data result, but got affected rows",
-                ),
-            };
+    pub fn from_result(result: Result<PromResponse>) -> Self {
+        match result {
+            Ok(data) => Self::success(data),
+            Err(err) => Self::error(error_type(err.status_code()), err.to_string()),
+        }
+    }
+
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings
+            .get_or_insert_with(Vec::new)
+            .push(warning.into());
+        self
+    }
+
+    pub fn data(&self) -> Option<&PromResponse> {
+        self.data.as_ref()
+    }
+
+    pub fn error_type(&self) -> Option<&str> {
+        self.error_type.as_deref()
+    }
+}
 
-            json
+impl IntoResponse for PromJsonResponse {
+    fn into_response(self) -> Response {
+        // The same status codes as Prometheus.
+        let status = match self.error_type.as_deref() {
+            None => HttpStatusCode::OK,
+            Some(ERROR_BAD_DATA) => HttpStatusCode::BAD_REQUEST,
+            Some(ERROR_EXECUTION) => HttpStatusCode::UNPROCESSABLE_ENTITY,
+            Some(ERROR_TIMEOUT) | Some(ERROR_UNAVAILABLE) => HttpStatusCode::SERVICE_UNAVAILABLE,
+            // 499 Client Closed Request
+            Some(ERROR_CANCELED) => HttpStatusCode::from_u16(499).unwrap(),
+            Some(_) => HttpStatusCode::INTERNAL_SERVER_ERROR,
         };
+        (status, Json(self)).into_response()
+    }
+}
 
-        match response {
-            Ok(resp) => resp,
-            Err(err) => {
-                // Prometheus won't report error if querying nonexist label and metric
-                if err.status_code() == StatusCode::TableNotFound
-                    || err.status_code() == StatusCode::TableColumnNotFound
-                {
-                    Self::success(PromData {
-                        result_type: "matrix".to_string(),
-                        ..Default::default()
-                    })
-                } else {
-                    Self::error(err.status_code().to_string(), err.to_string())
-                }
-            }
-        }
+fn error_type(status_code: StatusCode) -> &'static str {
+    match status_code {
+        StatusCode::InvalidArguments
+        | StatusCode::InvalidSyntax
+        | StatusCode::Unsupported
+        | StatusCode::PlanQuery => ERROR_BAD_DATA,
+        StatusCode::QueryTimeout => ERROR_TIMEOUT,
+        StatusCode::Cancelled => ERROR_CANCELED,
+        StatusCode::StorageUnavailable => ERROR_UNAVAILABLE,
+        StatusCode::Unknown | StatusCode::Unexpected | StatusCode::Internal => ERROR_INTERNAL,
+        _ => ERROR_EXECUTION,
     }
+}
 
-    fn record_batches_to_data(batches: RecordBatches, metric_name: String) -> Result<PromData> {
-        // infer semantic type of each column from schema.
-        // TODO(ruihang): wish there is a better way to do this.
-        let mut timestamp_column_index = None;
-        let mut tag_column_indices = Vec::new();
-        let mut first_field_column_index = None;
+/// Creates the query context of the user sending the request, in the database `db` if present.
+/// It's only taken from the URL, the same as the database the request is authorized for.
+fn query_context(db: Option<&str>, user_info: UserInfo) -> QueryContextRef {
+    let query_ctx = match db {
+        Some(db) => {
+            let (catalog, schema) = parse_catalog_and_schema_from_client_database_name(db);
+            Arc::new(QueryContext::with(catalog, schema))
+        }
+        None => QueryContext::arc(),
+    };
+    query_ctx.set_current_user(user_info);
+    query_ctx
+}
 
-        for (i, column) in batches.schema().column_schemas().iter().enumerate() {
-            match column.data_type {
-                ConcreteDataType::Timestamp(datatypes::types::TimestampType::Millisecond(_)) => {
-                    if timestamp_column_index.is_none() {
-                        timestamp_column_index = Some(i);
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct InstantQuery {
+    db: Option<String>,
+    query: Option<String>,
+    time: Option<String>,
+    timeout: Option<String>,
+}
+
+#[axum_macros::debug_handler]
+pub async fn instant_query(
+    State(handler): State<PromHandlerRef>,
+    Query(params): Query<InstantQuery>,
+    Extension(user_info): Extension<UserInfo>,
+    form_params: Option<Form<InstantQuery>>,
+) -> PromJsonResponse {
+    let form_params = form_params.map(|Form(params)| params).unwrap_or_default();
+    let query = params.query.or(form_params.query).unwrap_or_default();
+    let time = params
+        .time
+        .or(form_params.time)
+        .unwrap_or_else(current_time);
+    let timeout = params.timeout.or(form_params.timeout);
+
+    let response: Result<PromResponse> = try {
+        let expr = parse_expr(&query)?;
+        let time_ms = parse_time(&time)?;
+        let timeout = timeout.as_deref().map(parse_timeout).transpose()?;
+        let time_secs = time_ms as f64 / 1000.0;
+
+        let result = match value_type(&expr) {
+            // A string is returned as is, it is not a series to query.
+            ValueType::String => {
+                let PromqlExpr::StringLiteral(StringLiteral { val }) = &expr else {
+                    unreachable!()
+                };
+                PromQueryResult::String((time_secs, val.clone()))
+            }
+            value_type => {
+                let prom_query = PromQuery {
+                    query,
+                    start: time.clone(),
+                    end: time,
+                    step: INSTANT_QUERY_STEP.to_string(),
+                };
+                let metric_name = promql_expr_to_metric_name(expr).unwrap_or_default();
+                let series = query_series_within(
+                    &handler,
+                    &prom_query,
+                    query_context(params.db.as_deref(), user_info),
+                    &metric_name,
+                    timeout,
+                )
+                .await?;
+                match value_type {
+                    ValueType::Scalar => {
+                        let value = series
+                            .into_values()
+                            .next()
+                            .and_then(|mut values| values.pop())
+                            .unwrap_or_else(|| (time_secs, format_value(f64::NAN)));
+                        PromQueryResult::Scalar(value)
                     }
+                    ValueType::Matrix => PromQueryResult::Matrix(
+                        series
+                            .into_iter()
+                            .map(|(metric, values)| PromSeries { metric, values })
+                            .collect(),
+                    ),
+                    _ => PromQueryResult::Vector(
+                        series
+                            .into_iter()
+                            .filter_map(|(metric, mut values)| {
+                                values.pop().map(|value| PromSample { metric, value })
+                            })
+                            .collect(),
+                    ),
                 }
-                ConcreteDataType::Float64(_) => {
-                    if first_field_column_index.is_none() {
-                        first_field_column_index = Some(i);
+            }
+        };
+        PromResponse::PromData(PromData::new(result))
+    };
+    PromJsonResponse::from_result(response)
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RangeQuery {
+    db: Option<String>,
+    query: Option<String>,
+    start: Option<String>,
+    end: Option<String>,
+    step: Option<String>,
+    timeout: Option<String>,
+}
+
+#[axum_macros::debug_handler]
+pub async fn range_query(
+    State(handler): State<PromHandlerRef>,
+    Query(params): Query<RangeQuery>,
+    Extension(user_info): Extension<UserInfo>,
+    form_params: Option<Form<RangeQuery>>,
+) -> PromJsonResponse {
+    let form_params = form_params.map(|Form(params)| params).unwrap_or_default();
+    let prom_query = PromQuery {
+        query: params.query.or(form_params.query).unwrap_or_default(),
+        start: params.start.or(form_params.start).unwrap_or_default(),
+        end: params.end.or(form_params.end).unwrap_or_default(),
+        step: params.step.or(form_params.step).unwrap_or_default(),
+    };
+    let timeout = params.timeout.or(form_params.timeout);
+
+    let response: Result<PromResponse> = try {
+        let expr = parse_expr(&prom_query.query)?;
+        let timeout = timeout.as_deref().map(parse_timeout).transpose()?;
+        let value_type = value_type(&expr);
+        ensure!(
+            matches!(value_type, ValueType::Scalar | ValueType::Vector),
+            InvalidQuerySnafu {
+                reason: format!(
+                    "invalid expression type \"{}\" for range query, must be Scalar or instant Vector",
+                    value_type.name()
+                ),
+            }
+        );
+        let metric_name = promql_expr_to_metric_name(expr).unwrap_or_default();
+        let series = query_series_within(
+            &handler,
+            &prom_query,
+            query_context(params.db.as_deref(), user_info),
+            &metric_name,
+            timeout,
+        )
+        .await?;
+        PromResponse::PromData(PromData::new(PromQueryResult::Matrix(
+            series
+                .into_iter()
+                .map(|(metric, values)| PromSeries { metric, values })
+                .collect(),
+        )))
+    };
+    PromJsonResponse::from_result(response)
+}
+
+/// How long before the end the APIs querying metadata look for series if the start is absent,
+/// same as the default lookback delta of Prometheus.
+const DEFAULT_METADATA_LOOKBACK_MS: i64 = 5 * 60 * 1000;
+
+/// How many metrics the label names are looked up from at most if there is no `match[]`, so a
+/// request without it doesn't go through the schemas of all tables.
+const MAX_LABELS_LOOKUP_METRICS: usize = 1000;
+
+/// Parameters of the APIs querying metadata, i.e. series, label names and label values.
+#[derive(Debug, Default, PartialEq)]
+struct MetadataQuery {
+    /// The database in the URL.
+    db: Option<String>,
+    /// Series selectors in `match[]`, which are repeatable.
+    matches: Vec<String>,
+    start: Option<String>,
+    end: Option<String>,
+}
+
+impl MetadataQuery {
+    fn new(
+        params: Vec<(String, String)>,
+        form_params: Option<Form<Vec<(String, String)>>>,
+    ) -> Self {
+        let mut query = MetadataQuery {
+            db: params
+                .iter()
+                .find(|(key, _)| key == "db")
+                .map(|(_, value)| value.clone()),
+            ..Default::default()
+        };
+        // Parameters of GET requests are also extracted as forms, so they are deduplicated.
+        let form_params = form_params.map(|Form(params)| params).unwrap_or_default();
+        for (key, value) in params.into_iter().chain(form_params) {
+            match key.as_str() {
+                "match[]" => {
+                    if !query.matches.contains(&value) {
+                        query.matches.push(value);
                     }
                 }
-                ConcreteDataType::String(_) => {
-                    tag_column_indices.push(i);
+                "start" => {
+                    let _ = query.start.get_or_insert(value);
+                }
+                "end" => {
+                    let _ = query.end.get_or_insert(value);
                 }
                 _ => {}
             }
         }
+        query
+    }
 
-        let timestamp_column_index = timestamp_column_index.context(InternalSnafu {
-            err_msg: "no timestamp column found".to_string(),
-        })?;
-        let first_field_column_index = first_field_column_index.context(InternalSnafu {
-            err_msg: "no value column found".to_string(),
-        })?;
+    /// Returns the end of the time range, and the time range in unix milliseconds.
+    fn time_range(&self) -> Result<(String, i64, i64)> {
+        let end = self.end.clone().unwrap_or_else(current_time);
+        let end_ms = parse_time(&end)?;
+        let start_ms = match &self.start {
+            Some(start) => parse_time(start)?,
+            None => end_ms - DEFAULT_METADATA_LOOKBACK_MS,
+        };
+        Ok((end, start_ms, end_ms))
+    }
 
-        let metric_name = (METRIC_NAME.to_string(), metric_name);
-        let mut buffer = HashMap::<Vec<(String, String)>, Vec<(f64, String)>>::new();
+    /// Returns the matchers of the metric names and the other labels of each selector in
+    /// `match[]`. There is one selector selecting all metrics if there is no `match[]`.
+    fn selectors(&self) -> Result<Vec<(Vec<Matcher>, Vec<Matcher>)>> {
+        let mut selectors = Vec::with_capacity(self.matches.len().max(1));
+        for selector in &self.matches {
+            selectors.push(parse_selector(selector)?);
+        }
+        if selectors.is_empty() {
+            selectors.push(vec![]);
+        }
+        Ok(selectors
+            .into_iter()
+            .map(|matchers| {
+                matchers
+                    .into_iter()
+                    .partition(|matcher| matcher.name == METRIC_NAME)
+            })
+            .collect())
+    }
+}
 
-        for batch in batches.iter() {
-            // prepare things...
-            let tag_columns = tag_column_indices
-                .iter()
-                .map(|i| {
-                    batch
-                        .column(*i)
-                        .as_any()
-                        .downcast_ref::<StringVector>()
-                        .unwrap()
-                })
-                .collect::<Vec<_>>();
-            let tag_names = tag_column_indices
-                .iter()
-                .map(|c| batches.schema().column_name_by_index(*c).to_string())
-                .collect::<Vec<_>>();
-            let timestamp_column = batch
-                .column(timestamp_column_index)
-                .as_any()
-                .downcast_ref::<TimestampMillisecondVector>()
-                .unwrap();
-            let field_column = batch
-                .column(first_field_column_index)
-                .as_any()
-                .downcast_ref::<Float64Vector>()
-                .unwrap();
-
-            // assemble rows
-            for row_index in 0..batch.num_rows() {
-                // retrieve tags
-                // TODO(ruihang): push table name `__metric__`
-                let mut tags = vec![metric_name.clone()];
-                for (tag_column, tag_name) in tag_columns.iter().zip(tag_names.iter()) {
-                    let tag_value = tag_column.get_data(row_index).unwrap().to_string();
-                    tags.push((tag_name.to_string(), tag_value));
-                }
+#[axum_macros::debug_handler]
+pub async fn series_query(
+    State(handler): State<PromHandlerRef>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user_info): Extension<UserInfo>,
+    form_params: Option<Form<Vec<(String, String)>>>,
+) -> PromJsonResponse {
+    let query = MetadataQuery::new(params, form_params);
+    if query.matches.is_empty() {
+        return PromJsonResponse::error(ERROR_BAD_DATA, "no match[] parameter provided");
+    }
 
-                // retrieve timestamp
-                let timestamp_millis: i64 = timestamp_column.get_data(row_index).unwrap().into();
-                let timestamp = timestamp_millis as f64 / 1000.0;
+    let query_ctx = query_context(query.db.as_deref(), user_info);
+    let response = find_series(&handler, &query, query_ctx)
+        .await
+        .map(|series| PromResponse::Series(series.into_iter().collect()));
+    PromJsonResponse::from_result(response)
+}
 
-                // retrieve value
-                let value =
-                    Into::<f64>::into(field_column.get_data(row_index).unwrap()).to_string();
+#[axum_macros::debug_handler]
+pub async fn labels_query(
+    State(handler): State<PromHandlerRef>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user_info): Extension<UserInfo>,
+    form_params: Option<Form<Vec<(String, String)>>>,
+) -> PromJsonResponse {
+    let query = MetadataQuery::new(params, form_params);
+    let query_ctx = query_context(query.db.as_deref(), user_info);
 
-                buffer.entry(tags).or_default().push((timestamp, value));
+    // Label names are the tag columns of the selected metrics, regardless of the label
+    // matchers and the time range.
+    let mut truncated = false;
+    let response: Result<PromResponse> = try {
+        let mut metric_names = None;
+        let mut names = BTreeSet::new();
+        for (name_matchers, _) in query.selectors()? {
+            let mut metrics =
+                select_metric_names(&handler, &name_matchers, &mut metric_names, &query_ctx)
+                    .await?;
+            if query.matches.is_empty() && metrics.len() > MAX_LABELS_LOOKUP_METRICS {
+                metrics.truncate(MAX_LABELS_LOOKUP_METRICS);
+                truncated = true;
+            }
+            for metric in metrics {
+                if let Some(label_names) = handler.label_names(&metric, query_ctx.clone()).await? {
+                    let _ = names.insert(METRIC_NAME.to_string());
+                    names.extend(label_names);
+                }
             }
         }
+        PromResponse::Labels(names.into_iter().collect())
+    };
+    let response = PromJsonResponse::from_result(response);
+    if truncated {
+        response.with_warning(format!(
+            "label names are only looked up from {MAX_LABELS_LOOKUP_METRICS} metrics without match[]"
+        ))
+    } else {
+        response
+    }
+}
 
-        let result = buffer
-            .into_iter()
-            .map(|(tags, values)| PromSeries {
-                metric: tags.into_iter().collect(),
-                values,
-            })
-            .collect();
+#[axum_macros::debug_handler]
+pub async fn label_values_query(
+    State(handler): State<PromHandlerRef>,
+    Path(label_name): Path<String>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user_info): Extension<UserInfo>,
+    form_params: Option<Form<Vec<(String, String)>>>,
+) -> PromJsonResponse {
+    let query = MetadataQuery::new(params, form_params);
+    let query_ctx = query_context(query.db.as_deref(), user_info);
 
-        let data = PromData {
-            result_type: "matrix".to_string(),
-            result,
+    let response: Result<PromResponse> = try {
+        let values = if label_name == METRIC_NAME && query.matches.is_empty() {
+            handler
+                .metric_names(query_ctx)
+                .await?
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+        } else {
+            find_label_values(&handler, &query, &label_name, query_ctx).await?
         };
+        PromResponse::Labels(values.into_iter().collect())
+    };
+    PromJsonResponse::from_result(response)
+}
 
-        Ok(data)
-    }
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RulesQuery {
+    /// "alert" or "record" to return only the alerting or recording rules.
+    #[serde(rename = "type")]
+    rule_type: Option<String>,
+}
+
+#[axum_macros::debug_handler]
+pub async fn rules_query(
+    State(handler): State<PromHandlerRef>,
+    Query(params): Query<RulesQuery>,
+) -> PromJsonResponse {
+    let response: Result<PromResponse> = try {
+        let keep_alerting = match params.rule_type.as_deref() {
+            None => None,
+            Some("alert") => Some(true),
+            Some("record") => Some(false),
+            Some(other) => InvalidQuerySnafu {
+                reason: format!("invalid parameter \"type\": {other:?}"),
+            }
+            .fail()?,
+        };
+        let mut groups = handler.rule_groups().await?;
+        if let Some(keep_alerting) = keep_alerting {
+            for group in &mut groups {
+                group
+                    .rules
+                    .retain(|rule| matches!(rule, PromRule::Alerting { .. }) == keep_alerting);
+            }
+        }
+        PromResponse::RuleGroups { groups }
+    };
+    PromJsonResponse::from_result(response)
+}
+
+#[axum_macros::debug_handler]
+pub async fn alerts_query(State(handler): State<PromHandlerRef>) -> PromJsonResponse {
+    let response = handler
+        .alerts()
+        .await
+        .map(|alerts| PromResponse::Alerts { alerts });
+    PromJsonResponse::from_result(response)
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
-pub struct InstantQuery {
-    query: String,
-    time: Option<String>,
-    timeout: Option<String>,
+pub struct MetricMetadataQuery {
+    db: Option<String>,
+    metric: Option<String>,
+    /// Max number of metric families to return.
+    limit: Option<String>,
 }
 
 #[axum_macros::debug_handler]
-pub async fn instant_query(
-    State(_handler): State<PromHandlerRef>,
-    Query(_params): Query<InstantQuery>,
-) -> Json<PromJsonResponse> {
-    PromJsonResponse::error(
-        "not implemented",
-        "instant query api `/query` is not implemented. Use `/query_range` instead.",
-    )
+pub async fn metric_metadata_query(
+    State(handler): State<PromHandlerRef>,
+    Query(params): Query<MetricMetadataQuery>,
+    Extension(user_info): Extension<UserInfo>,
+) -> PromJsonResponse {
+    let query_ctx = query_context(params.db.as_deref(), user_info);
+    let response: Result<PromResponse> = try {
+        // A negative limit means no limit, like Prometheus.
+        let limit = match &params.limit {
+            Some(limit) => limit
+                .parse::<i64>()
+                .ok()
+                .with_context(|| InvalidQuerySnafu {
+                    reason: format!("invalid parameter \"limit\": {limit:?}"),
+                })?,
+            None => -1,
+        };
+        let mut metadata = BTreeMap::<String, Vec<PromMetadata>>::new();
+        for m in handler
+            .metric_metadata(params.metric.as_deref(), query_ctx.clone())
+            .await?
+        {
+            if limit >= 0
+                && metadata.len() as i64 >= limit
+                && !metadata.contains_key(&m.metric_family_name)
+            {
+                continue;
+            }
+            metadata
+                .entry(m.metric_family_name.clone())
+                .or_default()
+                .push(PromMetadata {
+                    metric_type: prometheus::metric_type_name(m.r#type).to_string(),
+                    help: m.help,
+                    unit: m.unit,
+                });
+        }
+        PromResponse::Metadata(metadata)
+    };
+    PromJsonResponse::from_result(response)
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
-pub struct RangeQuery {
+pub struct ExemplarsQuery {
+    db: Option<String>,
     query: Option<String>,
     start: Option<String>,
     end: Option<String>,
-    step: Option<String>,
-    timeout: Option<String>,
 }
 
 #[axum_macros::debug_handler]
-pub async fn range_query(
+pub async fn exemplars_query(
     State(handler): State<PromHandlerRef>,
-    Query(params): Query<RangeQuery>,
-    Form(form_params): Form<RangeQuery>,
-) -> Json<PromJsonResponse> {
-    let prom_query = PromQuery {
-        query: params.query.or(form_params.query).unwrap_or_default(),
-        start: params.start.or(form_params.start).unwrap_or_default(),
-        end: params.end.or(form_params.end).unwrap_or_default(),
-        step: params.step.or(form_params.step).unwrap_or_default(),
+    Query(params): Query<ExemplarsQuery>,
+    Extension(user_info): Extension<UserInfo>,
+    form_params: Option<Form<ExemplarsQuery>>,
+) -> PromJsonResponse {
+    let query_ctx = query_context(params.db.as_deref(), user_info);
+    let form_params = form_params.map(|Form(params)| params).unwrap_or_default();
+    let query = params.query.or(form_params.query).unwrap_or_default();
+    let start = params.start.or(form_params.start);
+    let end = params.end.or(form_params.end).unwrap_or_else(current_time);
+
+    let response: Result<PromResponse> = try {
+        let expr = parse_expr(&query)?;
+        let start_ms = match &start {
+            Some(start) => parse_time(start)?,
+            None => 0,
+        };
+        let end_ms = parse_time(&end)?;
+
+        let mut selectors = Vec::new();
+        collect_selectors(&expr, &mut selectors);
+        let mut metric_names = None;
+        let mut series = BTreeMap::new();
+        for matchers in selectors {
+            let (name_matchers, label_matchers): (Vec<_>, Vec<_>) = matchers
+                .into_iter()
+                .partition(|matcher| matcher.name == METRIC_NAME);
+            for name in
+                select_metric_names(&handler, &name_matchers, &mut metric_names, &query_ctx).await?
+            {
+                let remote_query = RemoteQuery {
+                    start_timestamp_ms: start_ms,
+                    end_timestamp_ms: end_ms,
+                    matchers: label_matchers
+                        .iter()
+                        .map(to_label_matcher)
+                        .chain(Some(LabelMatcher {
+                            name: METRIC_NAME.to_string(),
+                            value: name,
+                            r#type: LabelMatcherType::Eq as i32,
+                        }))
+                        .collect(),
+                    ..Default::default()
+                };
+                for timeseries in handler
+                    .query_exemplars(&remote_query, query_ctx.clone())
+                    .await?
+                {
+                    let series_labels = to_prom_labels(timeseries.labels);
+                    let exemplars = timeseries
+                        .exemplars
+                        .into_iter()
+                        .map(|exemplar| PromExemplar {
+                            labels: to_prom_labels(exemplar.labels),
+                            value: format_value(exemplar.value),
+                            timestamp: exemplar.timestamp as f64 / 1000.0,
+                        })
+                        .collect();
+                    // A series selected by several selectors is returned once.
+                    let _ = series.insert(series_labels, exemplars);
+                }
+            }
+        }
+        PromResponse::Exemplars(
+            series
+                .into_iter()
+                .map(|(series_labels, exemplars)| PromExemplars {
+                    series_labels,
+                    exemplars,
+                })
+                .collect(),
+        )
     };
-    let result = handler.do_query(&prom_query).await;
-    let metric_name = retrieve_metric_name(&prom_query.query).unwrap_or_default();
-    PromJsonResponse::from_query_result(result, metric_name).await
+    PromJsonResponse::from_result(response)
 }
 
-fn retrieve_metric_name(promql: &str) -> Option<String> {
-    let promql_expr = promql_parser::parser::parse(promql).ok()?;
-    promql_expr_to_metric_name(promql_expr)
+/// Returns the names of the metrics matching all the matchers of the metric name.
+/// `metric_names` caches the names of all metrics, which are fetched only if the matchers don't
+/// name a metric.
+async fn select_metric_names(
+    handler: &PromHandlerRef,
+    name_matchers: &[Matcher],
+    metric_names: &mut Option<Vec<String>>,
+    query_ctx: &QueryContextRef,
+) -> Result<Vec<String>> {
+    let names = match name_matchers
+        .iter()
+        .find(|matcher| matches!(matcher.op, MatchOp::Equal))
+    {
+        Some(matcher) => vec![matcher.value.clone()],
+        None => {
+            if metric_names.is_none() {
+                *metric_names = Some(handler.metric_names(query_ctx.clone()).await?);
+            }
+            metric_names.clone().unwrap_or_default()
+        }
+    };
+    Ok(names
+        .into_iter()
+        .filter(|name| name_matchers.iter().all(|m| is_match(m, name)))
+        .collect())
 }
 
-fn promql_expr_to_metric_name(expr: PromqlExpr) -> Option<String> {
+/// Finds the label sets of the series selected by `match[]` that have samples in the time range.
+/// All series are selected if there is no `match[]`.
+async fn find_series(
+    handler: &PromHandlerRef,
+    query: &MetadataQuery,
+    query_ctx: QueryContextRef,
+) -> Result<BTreeSet<PromLabels>> {
+    let (end, start_ms, end_ms) = query.time_range()?;
+    // `last_over_time` over the whole time range evaluates to one sample for each series.
+    let range_ms = (end_ms - start_ms).max(0) + 1;
+
+    let mut metric_names = None;
+    let mut series = BTreeSet::new();
+    for (name_matchers, label_matchers) in query.selectors()? {
+        for name in
+            select_metric_names(handler, &name_matchers, &mut metric_names, &query_ctx).await?
+        {
+            let selector = label_matchers
+                .iter()
+                .map(format_matcher)
+                .chain(Some(format!("{METRIC_NAME}=\"{}\"", escape(&name))))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let prom_query = PromQuery {
+                query: format!("last_over_time({{{selector}}}[{range_ms}ms])"),
+                start: end.clone(),
+                end: end.clone(),
+                step: INSTANT_QUERY_STEP.to_string(),
+            };
+            let output = handler.do_query(&prom_query, query_ctx.clone()).await;
+            let result = query_series(output, &name).await?;
+            series.extend(result.into_keys());
+        }
+    }
+    Ok(series)
+}
+
+/// Finds the values of the label of the series selected by `match[]` that have samples in the
+/// time range. All series are selected if there is no `match[]`.
+async fn find_label_values(
+    handler: &PromHandlerRef,
+    query: &MetadataQuery,
+    label_name: &str,
+    query_ctx: QueryContextRef,
+) -> Result<BTreeSet<String>> {
+    let (_, start_ms, end_ms) = query.time_range()?;
+
+    let mut metric_names = None;
+    let mut values = BTreeSet::new();
+    for (name_matchers, label_matchers) in query.selectors()? {
+        for name in
+            select_metric_names(handler, &name_matchers, &mut metric_names, &query_ctx).await?
+        {
+            let Some(label_names) = handler.label_names(&name, query_ctx.clone()).await? else {
+                continue;
+            };
+            if label_name != METRIC_NAME && !label_names.iter().any(|x| x == label_name) {
+                continue;
+            }
+            // A label the metric doesn't have is empty, only the matchers matching the empty
+            // string select its series.
+            let (matchers, absent): (Vec<_>, Vec<_>) = label_matchers
+                .iter()
+                .partition(|matcher| label_names.contains(&matcher.name));
+            if !absent.iter().all(|matcher| is_match(matcher, "")) {
+                continue;
+            }
+
+            let remote_query = RemoteQuery {
+                start_timestamp_ms: start_ms,
+                end_timestamp_ms: end_ms,
+                matchers: matchers
+                    .into_iter()
+                    .map(to_label_matcher)
+                    .chain(Some(LabelMatcher {
+                        name: METRIC_NAME.to_string(),
+                        value: name,
+                        r#type: LabelMatcherType::Eq as i32,
+                    }))
+                    .collect(),
+                ..Default::default()
+            };
+            values.extend(
+                handler
+                    .label_values(&remote_query, label_name, query_ctx.clone())
+                    .await?,
+            );
+        }
+    }
+    Ok(values)
+}
+
+/// Parses a series selector like `up{job="prometheus"}` into its label matchers.
+fn parse_selector(selector: &str) -> Result<Vec<Matcher>> {
+    match parse_expr(selector)? {
+        PromqlExpr::VectorSelector(VectorSelector { matchers, .. }) => {
+            Ok(matchers.matchers.into_iter().collect())
+        }
+        _ => InvalidQuerySnafu {
+            reason: format!("invalid parameter \"match[]\": {selector} is not a series selector"),
+        }
+        .fail(),
+    }
+}
+
+fn is_match(matcher: &Matcher, value: &str) -> bool {
+    match &matcher.op {
+        MatchOp::Equal => matcher.value == value,
+        MatchOp::NotEqual => matcher.value != value,
+        MatchOp::Re(regex) => regex.is_match(value),
+        MatchOp::NotRe(regex) => !regex.is_match(value),
+    }
+}
+
+/// Collects the label matchers of the series selectors in the expression.
+fn collect_selectors(expr: &PromqlExpr, selectors: &mut Vec<Vec<Matcher>>) {
+    match expr {
+        PromqlExpr::Aggregate(AggregateExpr { expr, .. })
+        | PromqlExpr::Unary(UnaryExpr { expr })
+        | PromqlExpr::Paren(ParenExpr { expr })
+        | PromqlExpr::Subquery(SubqueryExpr { expr, .. }) => collect_selectors(expr, selectors),
+        PromqlExpr::Binary(BinaryExpr { lhs, rhs, .. }) => {
+            collect_selectors(lhs, selectors);
+            collect_selectors(rhs, selectors);
+        }
+        PromqlExpr::NumberLiteral(_) | PromqlExpr::StringLiteral(_) => {}
+        PromqlExpr::VectorSelector(VectorSelector { matchers, .. })
+        | PromqlExpr::MatrixSelector(MatrixSelector {
+            vector_selector: VectorSelector { matchers, .. },
+            ..
+        }) => selectors.push(matchers.matchers.iter().cloned().collect()),
+        PromqlExpr::Call(Call { args, .. }) => {
+            for arg in &args.args {
+                collect_selectors(arg, selectors);
+            }
+        }
+    }
+}
+
+fn to_label_matcher(matcher: &Matcher) -> LabelMatcher {
+    let matcher_type = match matcher.op {
+        MatchOp::Equal => LabelMatcherType::Eq,
+        MatchOp::NotEqual => LabelMatcherType::Neq,
+        MatchOp::Re(_) => LabelMatcherType::Re,
+        MatchOp::NotRe(_) => LabelMatcherType::Nre,
+    };
+    LabelMatcher {
+        name: matcher.name.clone(),
+        value: matcher.value.clone(),
+        r#type: matcher_type as i32,
+    }
+}
+
+fn to_prom_labels(labels: Vec<Label>) -> PromLabels {
+    labels
+        .into_iter()
+        .map(|label| (label.name, label.value))
+        .collect()
+}
+
+fn format_matcher(matcher: &Matcher) -> String {
+    let op = match matcher.op {
+        MatchOp::Equal => "=",
+        MatchOp::NotEqual => "!=",
+        MatchOp::Re(_) => "=~",
+        MatchOp::NotRe(_) => "!~",
+    };
+    format!("{}{op}\"{}\"", matcher.name, escape(&matcher.value))
+}
+
+/// Escapes a string to be quoted in PromQL.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    Matrix,
+    Vector,
+    Scalar,
+    String,
+}
+
+impl ValueType {
+    fn name(&self) -> &'static str {
+        match self {
+            ValueType::Matrix => "range vector",
+            ValueType::Vector => "instant vector",
+            ValueType::Scalar => "scalar",
+            ValueType::String => "string",
+        }
+    }
+}
+
+/// Infers the type of the value that a PromQL expression evaluates to.
+fn value_type(expr: &PromqlExpr) -> ValueType {
     match expr {
-        PromqlExpr::Aggregate(AggregateExpr { expr, .. }) => promql_expr_to_metric_name(*expr),
-        PromqlExpr::Unary(UnaryExpr { expr }) => promql_expr_to_metric_name(*expr),
+        PromqlExpr::NumberLiteral(_) => ValueType::Scalar,
+        PromqlExpr::StringLiteral(_) => ValueType::String,
+        PromqlExpr::MatrixSelector(_) | PromqlExpr::Subquery(_) => ValueType::Matrix,
+        PromqlExpr::Paren(ParenExpr { expr }) | PromqlExpr::Unary(UnaryExpr { expr }) => {
+            value_type(expr)
+        }
         PromqlExpr::Binary(BinaryExpr { lhs, rhs, .. }) => {
-            promql_expr_to_metric_name(*lhs).or(promql_expr_to_metric_name(*rhs))
+            if value_type(lhs) == ValueType::Scalar && value_type(rhs) == ValueType::Scalar {
+                ValueType::Scalar
+            } else {
+                ValueType::Vector
+            }
+        }
+        PromqlExpr::Call(Call { func, .. }) if func.name == "scalar" || func.name == "time" => {
+            ValueType::Scalar
         }
+        PromqlExpr::Aggregate(_) | PromqlExpr::VectorSelector(_) | PromqlExpr::Call(_) => {
+            ValueType::Vector
+        }
+    }
+}
+
+fn parse_expr(query: &str) -> Result<PromqlExpr> {
+    promql_parser::parser::parse(query).map_err(|reason| {
+        InvalidQuerySnafu {
+            reason: format!("invalid parameter \"query\": {reason}"),
+        }
+        .build()
+    })
+}
+
+/// Parses a timestamp in RFC3339 format or in unix seconds into unix milliseconds.
+fn parse_time(time: &str) -> Result<i64> {
+    QueryLanguageParser::parse_promql_timestamp(time)
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+        .with_context(|| InvalidQuerySnafu {
+            reason: format!("cannot parse {time:?} to a valid timestamp"),
+        })
+}
+
+fn current_time() -> String {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+        .to_string()
+}
+
+/// Parses a duration like `30s` or in seconds, which is how Prometheus accepts `timeout`.
+fn parse_timeout(timeout: &str) -> Result<Duration> {
+    timeout
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+        .map(Duration::from_secs_f64)
+        .or_else(|| promql_parser::util::parse_duration(timeout).ok())
+        .with_context(|| InvalidQuerySnafu {
+            reason: format!("cannot parse {timeout:?} to a valid duration"),
+        })
+}
+
+/// Executes the query and collects its series like [query_series], failing if it doesn't finish
+/// within `timeout`.
+async fn query_series_within(
+    handler: &PromHandlerRef,
+    query: &PromQuery,
+    query_ctx: QueryContextRef,
+    metric_name: &str,
+    timeout: Option<Duration>,
+) -> Result<BTreeMap<PromLabels, Vec<PromValue>>> {
+    let series = async {
+        let output = handler.do_query(query, query_ctx).await;
+        query_series(output, metric_name).await
+    };
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, series)
+            .await
+            .map_err(|_| QueryTimeoutSnafu { timeout }.build())?,
+        None => series.await,
+    }
+}
+
+/// Formats a value like Prometheus does.
+pub fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Collects the samples in the output of a PromQL query, grouped by their series.
+async fn query_series(
+    result: Result<Output>,
+    metric_name: &str,
+) -> Result<BTreeMap<PromLabels, Vec<PromValue>>> {
+    Ok(query_samples(result, metric_name)
+        .await?
+        .into_iter()
+        .map(|(labels, samples)| {
+            let values = samples
+                .into_iter()
+                .map(|(timestamp_millis, value)| {
+                    (timestamp_millis as f64 / 1000.0, format_value(value))
+                })
+                .collect();
+            (labels, values)
+        })
+        .collect())
+}
+
+/// Collects the samples in the output of a PromQL query as pairs of the timestamp in
+/// milliseconds and the value, grouped by their series. The series are labeled with
+/// `metric_name` unless it's empty.
+pub async fn query_samples(
+    result: Result<Output>,
+    metric_name: &str,
+) -> Result<BTreeMap<PromLabels, Vec<(i64, f64)>>> {
+    let output = match result {
+        Ok(output) => output,
+        // Prometheus won't report error if querying nonexist label and metric
+        Err(err)
+            if err.status_code() == StatusCode::TableNotFound
+                || err.status_code() == StatusCode::TableColumnNotFound =>
+        {
+            return Ok(BTreeMap::new())
+        }
+        Err(err) => return Err(err),
+    };
+    let batches = match output {
+        Output::RecordBatches(batches) => batches,
+        Output::Stream(stream) => RecordBatches::try_collect(stream)
+            .await
+            .context(CollectRecordbatchSnafu)?,
+        Output::AffectedRows(_) => {
+            return InternalSnafu {
+                err_msg: "expected data result, but got affected rows",
+            }
+            .fail()
+        }
+    };
+    record_batches_to_samples(&batches, metric_name)
+}
+
+fn record_batches_to_samples(
+    batches: &RecordBatches,
+    metric_name: &str,
+) -> Result<BTreeMap<PromLabels, Vec<(i64, f64)>>> {
+    // infer semantic type of each column from schema.
+    // TODO(ruihang): wish there is a better way to do this.
+    let mut timestamp_column_index = None;
+    let mut tag_column_indices = Vec::new();
+    let mut first_field_column_index = None;
+
+    for (i, column) in batches.schema().column_schemas().iter().enumerate() {
+        match column.data_type {
+            ConcreteDataType::Timestamp(datatypes::types::TimestampType::Millisecond(_)) => {
+                if timestamp_column_index.is_none() {
+                    timestamp_column_index = Some(i);
+                }
+            }
+            ConcreteDataType::Float64(_) => {
+                if first_field_column_index.is_none() {
+                    first_field_column_index = Some(i);
+                }
+            }
+            ConcreteDataType::String(_) => {
+                tag_column_indices.push(i);
+            }
+            _ => {}
+        }
+    }
+
+    let timestamp_column_index = timestamp_column_index.context(InternalSnafu {
+        err_msg: "no timestamp column found".to_string(),
+    })?;
+    let first_field_column_index = first_field_column_index.context(InternalSnafu {
+        err_msg: "no value column found".to_string(),
+    })?;
+
+    let tag_names = tag_column_indices
+        .iter()
+        .map(|c| batches.schema().column_name_by_index(*c).to_string())
+        .collect::<Vec<_>>();
+    let mut buffer = BTreeMap::<PromLabels, Vec<(i64, f64)>>::new();
+
+    for batch in batches.iter() {
+        // prepare things...
+        let tag_columns = tag_column_indices
+            .iter()
+            .map(|i| {
+                batch
+                    .column(*i)
+                    .as_any()
+                    .downcast_ref::<StringVector>()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let timestamp_column = batch
+            .column(timestamp_column_index)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondVector>()
+            .unwrap();
+        let field_column = batch
+            .column(first_field_column_index)
+            .as_any()
+            .downcast_ref::<Float64Vector>()
+            .unwrap();
+
+        // assemble rows
+        for row_index in 0..batch.num_rows() {
+            let (Some(timestamp), Some(value)) = (
+                timestamp_column.get_data(row_index),
+                field_column.get_data(row_index),
+            ) else {
+                continue;
+            };
+
+            // retrieve tags, a null tag is the same as an absent one
+            let mut tags = PromLabels::new();
+            if !metric_name.is_empty() {
+                let _ = tags.insert(METRIC_NAME.to_string(), metric_name.to_string());
+            }
+            for (tag_column, tag_name) in tag_columns.iter().zip(tag_names.iter()) {
+                if let Some(tag_value) = tag_column.get_data(row_index) {
+                    let _ = tags.insert(tag_name.clone(), tag_value.to_string());
+                }
+            }
+
+            // retrieve timestamp
+            let timestamp_millis: i64 = timestamp.into();
+
+            buffer
+                .entry(tags)
+                .or_default()
+                .push((timestamp_millis, value));
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Returns the metric name of the series the expression evaluates to. Like Prometheus, only the
+/// series of selectors keep their metric name, aggregations, functions and arithmetic drop it.
+fn promql_expr_to_metric_name(expr: PromqlExpr) -> Option<String> {
+    match expr {
         PromqlExpr::Paren(ParenExpr { expr }) => promql_expr_to_metric_name(*expr),
-        PromqlExpr::Subquery(SubqueryExpr { expr, .. }) => promql_expr_to_metric_name(*expr),
-        PromqlExpr::NumberLiteral(_) => None,
-        PromqlExpr::StringLiteral(_) => None,
         PromqlExpr::VectorSelector(VectorSelector { matchers, .. }) => {
             matchers.find_matchers(METRIC_NAME).pop().cloned()
         }
@@ -416,9 +1502,177 @@ fn promql_expr_to_metric_name(expr: PromqlExpr) -> Option<String> {
             let VectorSelector { matchers, .. } = vector_selector;
             matchers.find_matchers(METRIC_NAME).pop().cloned()
         }
-        PromqlExpr::Call(Call { args, .. }) => args
-            .args
-            .into_iter()
-            .find_map(|e| promql_expr_to_metric_name(*e)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_type() {
+        let cases = [
+            ("1", ValueType::Scalar),
+            ("\"foo\"", ValueType::String),
+            ("(1 + 2) * -3", ValueType::Scalar),
+            ("time()", ValueType::Scalar),
+            ("up", ValueType::Vector),
+            ("1 + up", ValueType::Vector),
+            ("sum(rate(up[5m]))", ValueType::Vector),
+            ("up[5m]", ValueType::Matrix),
+            ("rate(up[5m])[30m:1m]", ValueType::Matrix),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(expected, value_type(&parse_expr(query).unwrap()), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!("1", format_value(1.0));
+        assert_eq!("0.25", format_value(0.25));
+        assert_eq!("-3", format_value(-3.0));
+        assert_eq!("NaN", format_value(f64::NAN));
+        assert_eq!("+Inf", format_value(f64::INFINITY));
+        assert_eq!("-Inf", format_value(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_serialize_rule() {
+        let rule = PromRule::Recording {
+            name: "job:up:sum".to_string(),
+            query: "sum by (job) (up)".to_string(),
+            labels: PromLabels::new(),
+            health: PromRuleHealth {
+                health: "ok".to_string(),
+                last_error: None,
+                last_evaluation: Some("2023-04-01T00:00:00Z".to_string()),
+                evaluation_time: 0.5,
+            },
+        };
+        assert_eq!(
+            r#"{"type":"recording","name":"job:up:sum","query":"sum by (job) (up)","labels":{},"health":"ok","lastEvaluation":"2023-04-01T00:00:00Z","evaluationTime":0.5}"#,
+            serde_json::to_string(&rule).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_collect_selectors() {
+        let expr = parse_expr(r#"sum(rate(http_requests{job="api"}[5m])) / up"#).unwrap();
+        let mut selectors = Vec::new();
+        collect_selectors(&expr, &mut selectors);
+        let selectors = selectors
+            .iter()
+            .map(|matchers| {
+                let mut matchers = matchers.iter().map(format_matcher).collect::<Vec<_>>();
+                matchers.sort();
+                matchers
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                vec![
+                    r#"__name__="http_requests""#.to_string(),
+                    r#"job="api""#.to_string()
+                ],
+                vec![r#"__name__="up""#.to_string()],
+            ],
+            selectors
+        );
+
+        let matcher = to_label_matcher(&parse_selector(r#"{job=~"a.*"}"#).unwrap()[0]);
+        assert_eq!("job", matcher.name);
+        assert_eq!("a.*", matcher.value);
+        assert_eq!(LabelMatcherType::Re as i32, matcher.r#type);
+    }
+
+    #[test]
+    fn test_metadata_query() {
+        let params = vec![
+            ("match[]".to_string(), "up".to_string()),
+            ("start".to_string(), "1".to_string()),
+            (
+                "match[]".to_string(),
+                "process_start_time_seconds".to_string(),
+            ),
+            ("db".to_string(), "metrics".to_string()),
+        ];
+        // The database is only taken from the URL, which is the one authorized.
+        let form_params = vec![
+            ("db".to_string(), "public".to_string()),
+            ("match[]".to_string(), "up".to_string()),
+            ("start".to_string(), "2".to_string()),
+            ("end".to_string(), "3".to_string()),
+        ];
+        let query = MetadataQuery::new(params, Some(Form(form_params)));
+        assert_eq!(
+            MetadataQuery {
+                db: Some("metrics".to_string()),
+                matches: vec!["up".to_string(), "process_start_time_seconds".to_string()],
+                start: Some("1".to_string()),
+                end: Some("3".to_string()),
+            },
+            query
+        );
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let mut matchers = parse_selector(r#"up{job=~"prom.*", instance!="a\"b"}"#)
+            .unwrap()
+            .iter()
+            .map(format_matcher)
+            .collect::<Vec<_>>();
+        matchers.sort();
+        assert_eq!(
+            vec![
+                r#"__name__="up""#.to_string(),
+                r#"instance!="a\"b""#.to_string(),
+                r#"job=~"prom.*""#.to_string(),
+            ],
+            matchers
+        );
+
+        assert!(parse_selector("up[5m]").is_err());
+        assert!(parse_selector("sum(up)").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(1_500, parse_time("1.5").unwrap());
+        assert_eq!(60_000, parse_time("1970-01-01T00:01:00Z").unwrap());
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_promql_expr_to_metric_name() {
+        let cases = [
+            ("up", Some("up")),
+            (r#"{__name__="up", job="a"}"#, Some("up")),
+            ("(up[5m])", Some("up")),
+            ("sum(rate(up[5m]))", None),
+            ("rate(up[5m])", None),
+            ("up / other", None),
+            ("-up", None),
+            ("1", None),
+        ];
+        for (query, expected) in cases {
+            let expr = parse_expr(query).unwrap();
+            assert_eq!(
+                expected.map(str::to_string),
+                promql_expr_to_metric_name(expr),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(Duration::from_millis(1_500), parse_timeout("1.5").unwrap());
+        assert_eq!(Duration::from_secs(30), parse_timeout("30s").unwrap());
+        assert_eq!(Duration::from_secs(90), parse_timeout("1m30s").unwrap());
+        assert!(parse_timeout("-1").is_err());
+        assert!(parse_timeout("soon").is_err());
     }
 }
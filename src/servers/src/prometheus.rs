@@ -14,27 +14,66 @@
 
 //! prometheus protocol supportings
 //! handles prometheus remote_write, remote_read logic
+pub mod chunked;
+
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 
 use api::prometheus::remote::label_matcher::Type as MatcherType;
-use api::prometheus::remote::{Label, Query, Sample, TimeSeries, WriteRequest};
+use api::prometheus::remote::metric_metadata::MetricType;
+use api::prometheus::remote::{
+    Exemplar, Label, LabelMatcher, MetricMetadata, Query, Sample, TimeSeries, WriteRequest,
+};
 use api::v1::column::SemanticType;
 use api::v1::{column, Column, ColumnDataType, InsertRequest as GrpcInsertRequest};
+use common_catalog::consts::{
+    DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, MITO_ENGINE, PROMETHEUS_EXEMPLARS_TABLE_ID,
+    PROMETHEUS_EXEMPLARS_TABLE_NAME, PROMETHEUS_METRIC_METADATA_TABLE_ID,
+    PROMETHEUS_METRIC_METADATA_TABLE_NAME,
+};
+use common_catalog::format_full_table_name;
+use common_query::Output;
 use common_recordbatch::{RecordBatch, RecordBatches};
 use common_time::timestamp::TimeUnit;
 use datatypes::prelude::{ConcreteDataType, Value};
+use datatypes::schema::{ColumnSchema, RawSchema};
+use datatypes::vectors::VectorRef;
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use openmetrics_parser::{MetricsExposition, PrometheusType, PrometheusValue};
+use regex::Regex;
 use snafu::{ensure, OptionExt, ResultExt};
 use snap::raw::{Decoder, Encoder};
+use table::requests::{CreateTableRequest, TableOptions};
 
 use crate::error::{self, Result};
+use crate::prometheus::chunked::ChunkedResponseEncoder;
+use crate::query_handler::sql::{quote_ident, quote_string};
 
 const TIMESTAMP_COLUMN_NAME: &str = "greptime_timestamp";
 const FIELD_COLUMN_NAME: &str = "greptime_value";
 pub const METRIC_NAME_LABEL: &str = "__name__";
 
+/// System table of the metadata of the metrics written by remote write requests, one row for
+/// each metric family of a schema.
+pub const METRIC_METADATA_TABLE_NAME: &str = PROMETHEUS_METRIC_METADATA_TABLE_NAME;
+/// System table of the exemplars written by remote write requests, keyed by the metric name and
+/// the labels of their series.
+pub const EXEMPLAR_TABLE_NAME: &str = PROMETHEUS_EXEMPLARS_TABLE_NAME;
+// Columns of the catalog and the schema the metadata and the exemplars are written to.
+const CATALOG_NAME_COLUMN_NAME: &str = "catalog_name";
+const SCHEMA_NAME_COLUMN_NAME: &str = "schema_name";
+const METRIC_FAMILY_NAME_COLUMN_NAME: &str = "metric_family_name";
+const METRIC_TYPE_COLUMN_NAME: &str = "metric_type";
+const METRIC_HELP_COLUMN_NAME: &str = "help";
+const METRIC_UNIT_COLUMN_NAME: &str = "unit";
+const EXEMPLAR_METRIC_NAME_COLUMN_NAME: &str = "metric_name";
+/// Column of the labels of the series of the exemplars except `__name__`, in a JSON object.
+const EXEMPLAR_SERIES_LABELS_COLUMN_NAME: &str = "series_labels";
+/// Column of the labels of the exemplars, in a JSON object.
+const EXEMPLAR_LABELS_COLUMN_NAME: &str = "exemplar_labels";
+
 /// Metrics for push gateway protocol
 pub struct Metrics {
     pub exposition: MetricsExposition<PrometheusType, PrometheusValue>,
@@ -43,11 +82,47 @@ pub struct Metrics {
 /// Generate a sql from a remote request query
 /// TODO(dennis): maybe use logical plan in future to prevent sql injection
 pub fn query_to_sql(q: &Query) -> Result<(String, String)> {
-    let start_timestamp_ms = q.start_timestamp_ms;
-    let end_timestamp_ms = q.end_timestamp_ms;
+    let table_name = query_table_name(q)?;
+    let conditions = query_conditions(q, TIMESTAMP_COLUMN_NAME)?;
 
-    let label_matches = &q.matchers;
-    let table_name = label_matches
+    Ok((
+        table_name.clone(),
+        format!("select * from {table_name} where {conditions} order by {TIMESTAMP_COLUMN_NAME}",),
+    ))
+}
+
+/// Generate a sql selecting the samples of a remote request query sorted by their series, then
+/// by timestamp, so the samples of each series are consecutive and the result can be streamed,
+/// see [chunked_read_response_stream]. `tag_columns` are the tag columns of the queried table.
+pub fn series_ordered_query_sql(q: &Query, tag_columns: &[String]) -> Result<(String, String)> {
+    let table_name = query_table_name(q)?;
+    let conditions = query_conditions(q, TIMESTAMP_COLUMN_NAME)?;
+    let order_by = sorted_tag_columns(tag_columns)
+        .into_iter()
+        .map(quote_ident)
+        .chain(std::iter::once(TIMESTAMP_COLUMN_NAME.to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok((
+        table_name.clone(),
+        format!(
+            "select * from {} where {conditions} order by {order_by}",
+            quote_ident(&table_name)
+        ),
+    ))
+}
+
+/// Series are sorted by their labels, which are sorted by name.
+fn sorted_tag_columns(tag_columns: &[String]) -> Vec<&str> {
+    let mut tag_columns = tag_columns.iter().map(String::as_str).collect::<Vec<_>>();
+    tag_columns.sort_unstable();
+    tag_columns
+}
+
+/// Returns the name of the table queried by a remote request query.
+pub fn query_table_name(q: &Query) -> Result<String> {
+    q.matchers
         .iter()
         .find_map(|m| {
             if m.name == METRIC_NAME_LABEL {
@@ -58,12 +133,45 @@ pub fn query_to_sql(q: &Query) -> Result<(String, String)> {
         })
         .context(error::InvalidPromRemoteRequestSnafu {
             msg: "missing '__name__' label in timeseries",
-        })?;
+        })
+}
+
+/// Generate a sql selecting the distinct values of the label `label_name` of the series selected
+/// by a remote request query. The values of `__name__` are the name of the queried table, whose
+/// time index is `timestamp_column`.
+pub fn label_values_sql(
+    q: &Query,
+    label_name: &str,
+    timestamp_column: &str,
+) -> Result<(String, String)> {
+    let table_name = query_table_name(q)?;
+    let conditions = query_conditions(q, timestamp_column)?;
+    let column = if label_name == METRIC_NAME_LABEL {
+        quote_string(&table_name)
+    } else {
+        quote_ident(label_name)
+    };
+
+    Ok((
+        table_name.clone(),
+        format!(
+            "select distinct {column} from {} where {conditions}",
+            quote_ident(&table_name)
+        ),
+    ))
+}
+
+/// Generate the conditions of the time range and the label matchers of a remote request query.
+fn query_conditions(q: &Query, timestamp_column: &str) -> Result<String> {
+    let start_timestamp_ms = q.start_timestamp_ms;
+    let end_timestamp_ms = q.end_timestamp_ms;
+
+    let label_matches = &q.matchers;
 
     let mut conditions: Vec<String> = Vec::with_capacity(label_matches.len());
 
     conditions.push(format!(
-        "{TIMESTAMP_COLUMN_NAME}>={start_timestamp_ms} AND {TIMESTAMP_COLUMN_NAME}<={end_timestamp_ms}",
+        "{timestamp_column}>={start_timestamp_ms} AND {timestamp_column}<={end_timestamp_ms}",
     ));
 
     for m in label_matches {
@@ -97,12 +205,7 @@ pub fn query_to_sql(q: &Query) -> Result<(String, String)> {
         }
     }
 
-    let conditions = conditions.join(" AND ");
-
-    Ok((
-        table_name.to_string(),
-        format!("select * from {table_name} where {conditions} order by {TIMESTAMP_COLUMN_NAME}",),
-    ))
+    Ok(conditions.join(" AND "))
 }
 
 #[inline]
@@ -219,7 +322,9 @@ pub fn recordbatches_to_timeseries(
         .collect())
 }
 
-fn recordbatch_to_timeseries(table: &str, recordbatch: RecordBatch) -> Result<Vec<TimeSeries>> {
+/// Returns the timestamp column and the value column of the query result, checking their
+/// datatypes.
+fn sample_columns(recordbatch: &RecordBatch) -> Result<(&VectorRef, &VectorRef)> {
     let ts_column = recordbatch.column_by_name(TIMESTAMP_COLUMN_NAME).context(
         error::InvalidPromRemoteReadQueryResultSnafu {
             msg: "missing greptime_timestamp column in query result",
@@ -250,6 +355,30 @@ fn recordbatch_to_timeseries(table: &str, recordbatch: RecordBatch) -> Result<Ve
         }
     );
 
+    Ok((ts_column, field_column))
+}
+
+/// Returns the sample of a row, or `None` if its timestamp or value is null. The columns must be
+/// checked by [sample_columns].
+fn sample_at(ts_column: &VectorRef, field_column: &VectorRef, row: usize) -> Option<Sample> {
+    if ts_column.is_null(row) || field_column.is_null(row) {
+        return None;
+    }
+
+    let value: f64 = match field_column.get(row) {
+        Value::Float64(value) => value.into(),
+        _ => unreachable!("checked by the \"ensure\" in sample_columns"),
+    };
+    let timestamp = match ts_column.get(row) {
+        Value::Timestamp(t) if t.unit() == TimeUnit::Millisecond => t.value(),
+        _ => unreachable!("checked by the \"ensure\" in sample_columns"),
+    };
+    Some(Sample { value, timestamp })
+}
+
+fn recordbatch_to_timeseries(table: &str, recordbatch: RecordBatch) -> Result<Vec<TimeSeries>> {
+    let (ts_column, field_column) = sample_columns(&recordbatch)?;
+
     // First, collect each row's timeseries id
     let timeseries_ids = collect_timeseries_ids(table, &recordbatch);
     // Then, group timeseries by it's id.
@@ -263,33 +392,193 @@ fn recordbatch_to_timeseries(table: &str, recordbatch: RecordBatch) -> Result<Ve
                 ..Default::default()
             });
 
-        if ts_column.is_null(row) || field_column.is_null(row) {
-            continue;
+        if let Some(sample) = sample_at(ts_column, field_column, row) {
+            timeseries.samples.push(sample);
         }
+    }
 
-        let value: f64 = match field_column.get(row) {
-            Value::Float64(value) => value.into(),
-            _ => unreachable!("checked by the \"ensure\" above"),
-        };
-        let timestamp = match ts_column.get(row) {
-            Value::Timestamp(t) if t.unit() == TimeUnit::Millisecond => t.value(),
-            _ => unreachable!("checked by the \"ensure\" above"),
-        };
-        let sample = Sample { value, timestamp };
+    Ok(timeseries_map.into_values().collect())
+}
+
+/// Encodes the results of the remote read queries into a stream of `ChunkedReadResponse` frames.
+/// Each item of `results` is the index of a query, the queried table and the samples of the
+/// query sorted by their series, see [series_ordered_query_sql]. The items of a query must be
+/// consecutive.
+pub fn chunked_read_response_stream(
+    mut results: BoxStream<'static, Result<(usize, String, Output)>>,
+) -> BoxStream<'static, Result<Vec<u8>>> {
+    let stream = async_stream::try_stream! {
+        let mut encoder: Option<ChunkedResponseEncoder> = None;
+        let mut current_query = None;
+        while let Some(result) = results.next().await {
+            let (query_index, table_name, output) = result?;
+            if current_query != Some(query_index) {
+                if let Some(frame) = encoder.take().and_then(ChunkedResponseEncoder::finish) {
+                    yield frame;
+                }
+                current_query = Some(query_index);
+            }
+            let query_encoder =
+                encoder.get_or_insert_with(|| ChunkedResponseEncoder::new(query_index as i64));
+
+            let mut recordbatches = match output {
+                Output::Stream(stream) => stream,
+                Output::RecordBatches(recordbatches) => recordbatches.as_stream(),
+                Output::AffectedRows(_) => error::InvalidPromRemoteReadQueryResultSnafu {
+                    msg: "Expect recordbatches result, but got AffectedRows",
+                }
+                .fail()?,
+            };
+            while let Some(recordbatch) = recordbatches.next().await {
+                let recordbatch = recordbatch.context(error::CollectRecordbatchSnafu)?;
+                let (ts_column, field_column) = sample_columns(&recordbatch)?;
+                let timeseries_ids = collect_timeseries_ids(&table_name, &recordbatch);
+                for (row, timeseries_id) in timeseries_ids.into_iter().enumerate() {
+                    let Some(sample) = sample_at(ts_column, field_column, row) else {
+                        continue;
+                    };
+                    let mut labels = timeseries_id.labels;
+                    labels.sort_unstable_by(|l, r| l.name.cmp(&r.name));
+                    if let Some(frame) =
+                        query_encoder.append(labels, sample.timestamp, sample.value)
+                    {
+                        yield frame;
+                    }
+                }
+            }
+        }
+        if let Some(frame) = encoder.and_then(ChunkedResponseEncoder::finish) {
+            yield frame;
+        }
+    };
+    Box::pin(stream)
+}
+
+/// Returns the request creating the system table [METRIC_METADATA_TABLE_NAME].
+pub fn metric_metadata_table_request() -> CreateTableRequest {
+    let column_schemas = vec![
+        string_column_schema(CATALOG_NAME_COLUMN_NAME, false),
+        string_column_schema(SCHEMA_NAME_COLUMN_NAME, false),
+        string_column_schema(METRIC_FAMILY_NAME_COLUMN_NAME, false),
+        timestamp_column_schema(),
+        string_column_schema(METRIC_TYPE_COLUMN_NAME, true),
+        string_column_schema(METRIC_HELP_COLUMN_NAME, true),
+        string_column_schema(METRIC_UNIT_COLUMN_NAME, true),
+    ];
+    system_table_request(
+        PROMETHEUS_METRIC_METADATA_TABLE_ID,
+        METRIC_METADATA_TABLE_NAME,
+        "Prometheus metric metadata table",
+        column_schemas,
+        // catalog, schema and metric family name as primary key
+        vec![0, 1, 2],
+    )
+}
+
+/// Returns the request creating the system table [EXEMPLAR_TABLE_NAME].
+pub fn exemplar_table_request() -> CreateTableRequest {
+    let column_schemas = vec![
+        string_column_schema(CATALOG_NAME_COLUMN_NAME, false),
+        string_column_schema(SCHEMA_NAME_COLUMN_NAME, false),
+        string_column_schema(EXEMPLAR_METRIC_NAME_COLUMN_NAME, false),
+        string_column_schema(EXEMPLAR_SERIES_LABELS_COLUMN_NAME, false),
+        timestamp_column_schema(),
+        ColumnSchema::new(
+            FIELD_COLUMN_NAME,
+            ConcreteDataType::float64_datatype(),
+            true,
+        ),
+        string_column_schema(EXEMPLAR_LABELS_COLUMN_NAME, true),
+    ];
+    system_table_request(
+        PROMETHEUS_EXEMPLARS_TABLE_ID,
+        EXEMPLAR_TABLE_NAME,
+        "Prometheus exemplars table",
+        column_schemas,
+        // catalog, schema, metric name and series labels as primary key
+        vec![0, 1, 2, 3],
+    )
+}
+
+fn string_column_schema(name: &str, nullable: bool) -> ColumnSchema {
+    ColumnSchema::new(name, ConcreteDataType::string_datatype(), nullable)
+}
+
+fn timestamp_column_schema() -> ColumnSchema {
+    ColumnSchema::new(
+        TIMESTAMP_COLUMN_NAME,
+        ConcreteDataType::timestamp_millisecond_datatype(),
+        false,
+    )
+    .with_time_index(true)
+}
 
-        timeseries.samples.push(sample);
+/// System tables are put into the default catalog and schema, like the scripts table.
+fn system_table_request(
+    id: u32,
+    table_name: &str,
+    desc: &str,
+    column_schemas: Vec<ColumnSchema>,
+    primary_key_indices: Vec<usize>,
+) -> CreateTableRequest {
+    CreateTableRequest {
+        id,
+        catalog_name: DEFAULT_CATALOG_NAME.to_string(),
+        schema_name: DEFAULT_SCHEMA_NAME.to_string(),
+        table_name: table_name.to_string(),
+        desc: Some(desc.to_string()),
+        schema: RawSchema::new(column_schemas),
+        region_numbers: vec![0],
+        primary_key_indices,
+        create_if_not_exists: true,
+        table_options: TableOptions::default(),
+        engine: MITO_ENGINE.to_string(),
     }
+}
 
-    Ok(timeseries_map.into_values().collect())
+/// Converts the samples of the series in a remote write request into insert requests of the
+/// tables of their metrics, see [to_system_insert_requests] for their exemplars and metadata.
+pub fn to_grpc_insert_requests(request: WriteRequest) -> Result<Vec<GrpcInsertRequest>> {
+    request
+        .timeseries
+        .into_iter()
+        // A series may only carry exemplars.
+        .filter(|timeseries| !timeseries.samples.is_empty())
+        .map(to_grpc_insert_request)
+        .collect()
 }
 
-pub fn to_grpc_insert_requests(mut request: WriteRequest) -> Result<Vec<GrpcInsertRequest>> {
-    let timeseries = std::mem::take(&mut request.timeseries);
-    timeseries.into_iter().map(to_grpc_insert_request).collect()
+/// Converts the exemplars and the metadata of the metrics in a remote write request to the
+/// schema `schema` of the catalog `catalog` into insert requests of the system tables
+/// [EXEMPLAR_TABLE_NAME] and [METRIC_METADATA_TABLE_NAME].
+pub fn to_system_insert_requests(
+    request: &WriteRequest,
+    catalog: &str,
+    schema: &str,
+) -> Result<Vec<GrpcInsertRequest>> {
+    let mut requests = Vec::with_capacity(2);
+    if request
+        .timeseries
+        .iter()
+        .any(|timeseries| !timeseries.exemplars.is_empty())
+    {
+        requests.push(to_exemplar_insert_request(
+            &request.timeseries,
+            catalog,
+            schema,
+        )?);
+    }
+    if !request.metadata.is_empty() {
+        requests.push(to_metadata_insert_request(
+            &request.metadata,
+            catalog,
+            schema,
+        ));
+    }
+    Ok(requests)
 }
 
 fn to_grpc_insert_request(mut timeseries: TimeSeries) -> Result<GrpcInsertRequest> {
-    // TODO(dennis): save exemplars into a column
     let labels = std::mem::take(&mut timeseries.labels);
     let samples = std::mem::take(&mut timeseries.samples);
 
@@ -354,6 +643,440 @@ fn to_grpc_insert_request(mut timeseries: TimeSeries) -> Result<GrpcInsertReques
     })
 }
 
+fn string_column(name: &str, semantic_type: SemanticType, values: Vec<String>) -> Column {
+    Column {
+        column_name: name.to_string(),
+        values: Some(column::Values {
+            string_values: values,
+            ..Default::default()
+        }),
+        semantic_type: semantic_type as i32,
+        datatype: ColumnDataType::String as i32,
+        ..Default::default()
+    }
+}
+
+/// Returns the labels in a JSON object, whose keys are sorted.
+fn labels_to_json<'a>(labels: impl Iterator<Item = &'a Label>) -> String {
+    let labels = labels
+        .map(|label| {
+            (
+                label.name.clone(),
+                serde_json::Value::String(label.value.clone()),
+            )
+        })
+        .collect();
+    serde_json::Value::Object(labels).to_string()
+}
+
+/// Converts the exemplars of the series into an insert request of the system table
+/// [EXEMPLAR_TABLE_NAME].
+fn to_exemplar_insert_request(
+    timeseries: &[TimeSeries],
+    catalog: &str,
+    schema: &str,
+) -> Result<GrpcInsertRequest> {
+    let mut metric_names = Vec::new();
+    let mut series_labels = Vec::new();
+    let mut timestamps = Vec::new();
+    let mut values = Vec::new();
+    let mut exemplar_labels = Vec::new();
+    for timeseries in timeseries {
+        if timeseries.exemplars.is_empty() {
+            continue;
+        }
+        let metric_name = timeseries
+            .labels
+            .iter()
+            .find(|label| label.name == METRIC_NAME_LABEL)
+            .context(error::InvalidPromRemoteRequestSnafu {
+                msg: "missing '__name__' label in timeseries",
+            })?;
+        let labels = labels_to_json(
+            timeseries
+                .labels
+                .iter()
+                .filter(|label| label.name != METRIC_NAME_LABEL),
+        );
+        for exemplar in &timeseries.exemplars {
+            metric_names.push(metric_name.value.clone());
+            series_labels.push(labels.clone());
+            timestamps.push(exemplar.timestamp);
+            values.push(exemplar.value);
+            exemplar_labels.push(labels_to_json(exemplar.labels.iter()));
+        }
+    }
+
+    let row_count = timestamps.len();
+    let columns = vec![
+        string_column(
+            CATALOG_NAME_COLUMN_NAME,
+            SemanticType::Tag,
+            vec![catalog.to_string(); row_count],
+        ),
+        string_column(
+            SCHEMA_NAME_COLUMN_NAME,
+            SemanticType::Tag,
+            vec![schema.to_string(); row_count],
+        ),
+        string_column(
+            EXEMPLAR_METRIC_NAME_COLUMN_NAME,
+            SemanticType::Tag,
+            metric_names,
+        ),
+        string_column(
+            EXEMPLAR_SERIES_LABELS_COLUMN_NAME,
+            SemanticType::Tag,
+            series_labels,
+        ),
+        Column {
+            column_name: TIMESTAMP_COLUMN_NAME.to_string(),
+            values: Some(column::Values {
+                ts_millisecond_values: timestamps,
+                ..Default::default()
+            }),
+            semantic_type: SemanticType::Timestamp as i32,
+            datatype: ColumnDataType::TimestampMillisecond as i32,
+            ..Default::default()
+        },
+        Column {
+            column_name: FIELD_COLUMN_NAME.to_string(),
+            values: Some(column::Values {
+                f64_values: values,
+                ..Default::default()
+            }),
+            semantic_type: SemanticType::Field as i32,
+            datatype: ColumnDataType::Float64 as i32,
+            ..Default::default()
+        },
+        string_column(
+            EXEMPLAR_LABELS_COLUMN_NAME,
+            SemanticType::Field,
+            exemplar_labels,
+        ),
+    ];
+
+    Ok(GrpcInsertRequest {
+        table_name: EXEMPLAR_TABLE_NAME.to_string(),
+        region_number: 0,
+        columns,
+        row_count: row_count as u32,
+    })
+}
+
+/// Converts the metadata of the metrics into an insert request of the system table
+/// [METRIC_METADATA_TABLE_NAME]. All rows have the timestamp 0, so the latest metadata of a
+/// metric family replaces the former one.
+fn to_metadata_insert_request(
+    metadata: &[MetricMetadata],
+    catalog: &str,
+    schema: &str,
+) -> GrpcInsertRequest {
+    let row_count = metadata.len();
+    let columns = vec![
+        string_column(
+            CATALOG_NAME_COLUMN_NAME,
+            SemanticType::Tag,
+            vec![catalog.to_string(); row_count],
+        ),
+        string_column(
+            SCHEMA_NAME_COLUMN_NAME,
+            SemanticType::Tag,
+            vec![schema.to_string(); row_count],
+        ),
+        string_column(
+            METRIC_FAMILY_NAME_COLUMN_NAME,
+            SemanticType::Tag,
+            metadata
+                .iter()
+                .map(|m| m.metric_family_name.clone())
+                .collect(),
+        ),
+        Column {
+            column_name: TIMESTAMP_COLUMN_NAME.to_string(),
+            values: Some(column::Values {
+                ts_millisecond_values: vec![0; row_count],
+                ..Default::default()
+            }),
+            semantic_type: SemanticType::Timestamp as i32,
+            datatype: ColumnDataType::TimestampMillisecond as i32,
+            ..Default::default()
+        },
+        string_column(
+            METRIC_TYPE_COLUMN_NAME,
+            SemanticType::Field,
+            metadata
+                .iter()
+                .map(|m| metric_type_name(m.r#type).to_string())
+                .collect(),
+        ),
+        string_column(
+            METRIC_HELP_COLUMN_NAME,
+            SemanticType::Field,
+            metadata.iter().map(|m| m.help.clone()).collect(),
+        ),
+        string_column(
+            METRIC_UNIT_COLUMN_NAME,
+            SemanticType::Field,
+            metadata.iter().map(|m| m.unit.clone()).collect(),
+        ),
+    ];
+
+    GrpcInsertRequest {
+        table_name: METRIC_METADATA_TABLE_NAME.to_string(),
+        region_number: 0,
+        columns,
+        row_count: row_count as u32,
+    }
+}
+
+/// Returns the name of the metric type in the Prometheus HTTP API.
+pub fn metric_type_name(metric_type: i32) -> &'static str {
+    match MetricType::from_i32(metric_type) {
+        Some(MetricType::Counter) => "counter",
+        Some(MetricType::Gauge) => "gauge",
+        Some(MetricType::Histogram) => "histogram",
+        Some(MetricType::Gaugehistogram) => "gaugehistogram",
+        Some(MetricType::Summary) => "summary",
+        Some(MetricType::Info) => "info",
+        Some(MetricType::Stateset) => "stateset",
+        Some(MetricType::Unknown) | None => "unknown",
+    }
+}
+
+fn parse_metric_type(name: &str) -> MetricType {
+    match name {
+        "counter" => MetricType::Counter,
+        "gauge" => MetricType::Gauge,
+        "histogram" => MetricType::Histogram,
+        "gaugehistogram" => MetricType::Gaugehistogram,
+        "summary" => MetricType::Summary,
+        "info" => MetricType::Info,
+        "stateset" => MetricType::Stateset,
+        _ => MetricType::Unknown,
+    }
+}
+
+/// Generate a sql querying the metadata of the metric family `metric`, or of all metric
+/// families if it's `None`, written to the schema `schema` of the catalog `catalog`.
+pub fn metadata_query_sql(catalog: &str, schema: &str, metric: Option<&str>) -> String {
+    let mut conditions = vec![
+        format!("{CATALOG_NAME_COLUMN_NAME}={}", quote_string(catalog)),
+        format!("{SCHEMA_NAME_COLUMN_NAME}={}", quote_string(schema)),
+    ];
+    if let Some(metric) = metric {
+        conditions.push(format!(
+            "{METRIC_FAMILY_NAME_COLUMN_NAME}={}",
+            quote_string(metric)
+        ));
+    }
+    format!(
+        "select * from {} where {} order by {METRIC_FAMILY_NAME_COLUMN_NAME}",
+        format_full_table_name(
+            DEFAULT_CATALOG_NAME,
+            DEFAULT_SCHEMA_NAME,
+            METRIC_METADATA_TABLE_NAME
+        ),
+        conditions.join(" AND ")
+    )
+}
+
+/// Generate a sql querying the exemplars of the metric of a remote request query in its time
+/// range, written to the schema `schema` of the catalog `catalog`. The label matchers are
+/// applied by [recordbatches_to_exemplars], as the labels of the series are in a JSON object.
+pub fn exemplars_query_sql(q: &Query, catalog: &str, schema: &str) -> Result<String> {
+    let metric = query_table_name(q)?;
+    Ok(format!(
+        "select * from {} where {CATALOG_NAME_COLUMN_NAME}={} AND {SCHEMA_NAME_COLUMN_NAME}={} AND {EXEMPLAR_METRIC_NAME_COLUMN_NAME}={} AND {TIMESTAMP_COLUMN_NAME}>={} AND {TIMESTAMP_COLUMN_NAME}<={} order by {EXEMPLAR_SERIES_LABELS_COLUMN_NAME}, {TIMESTAMP_COLUMN_NAME}",
+        format_full_table_name(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, EXEMPLAR_TABLE_NAME),
+        quote_string(catalog),
+        quote_string(schema),
+        quote_string(&metric),
+        q.start_timestamp_ms,
+        q.end_timestamp_ms,
+    ))
+}
+
+pub fn recordbatches_to_metadata(recordbatches: RecordBatches) -> Result<Vec<MetricMetadata>> {
+    let mut metadata = Vec::new();
+    for recordbatch in recordbatches.take() {
+        let column = |name: &str| {
+            recordbatch.column_by_name(name).with_context(|| {
+                error::InvalidPromRemoteReadQueryResultSnafu {
+                    msg: format!("missing {name} column in metadata query result"),
+                }
+            })
+        };
+        let name_column = column(METRIC_FAMILY_NAME_COLUMN_NAME)?;
+        let type_column = column(METRIC_TYPE_COLUMN_NAME)?;
+        let help_column = column(METRIC_HELP_COLUMN_NAME)?;
+        let unit_column = column(METRIC_UNIT_COLUMN_NAME)?;
+        for row in 0..recordbatch.num_rows() {
+            metadata.push(MetricMetadata {
+                r#type: parse_metric_type(&string_at(type_column, row)) as i32,
+                metric_family_name: string_at(name_column, row),
+                help: string_at(help_column, row),
+                unit: string_at(unit_column, row),
+            });
+        }
+    }
+    Ok(metadata)
+}
+
+/// Converts the result of [label_values_sql] into the label values, nulls are skipped.
+pub fn recordbatches_to_label_values(recordbatches: RecordBatches) -> Vec<String> {
+    let mut values = Vec::new();
+    for recordbatch in recordbatches.take() {
+        let column = recordbatch.column(0);
+        for row in 0..recordbatch.num_rows() {
+            if let Value::String(s) = column.get(row) {
+                values.push(s.as_utf8().to_string());
+            }
+        }
+    }
+    values
+}
+
+/// A label matcher of a remote request query, whose regular expression is compiled.
+enum LabelMatch {
+    Eq(String),
+    Neq(String),
+    Re(Regex),
+    Nre(Regex),
+}
+
+impl LabelMatch {
+    fn new(matcher: &LabelMatcher) -> Result<Self> {
+        let m_type = MatcherType::from_i32(matcher.r#type).context(
+            error::InvalidPromRemoteRequestSnafu {
+                msg: format!("invalid LabelMatcher type: {}", matcher.r#type),
+            },
+        )?;
+        let regex = || {
+            // Regular expressions of Prometheus are fully anchored.
+            Regex::new(&format!("^(?:{})$", matcher.value)).map_err(|e| {
+                error::InvalidPromRemoteRequestSnafu {
+                    msg: format!("invalid regex of label {}: {e}", matcher.name),
+                }
+                .build()
+            })
+        };
+        Ok(match m_type {
+            MatcherType::Eq => LabelMatch::Eq(matcher.value.clone()),
+            MatcherType::Neq => LabelMatch::Neq(matcher.value.clone()),
+            MatcherType::Re => LabelMatch::Re(regex()?),
+            MatcherType::Nre => LabelMatch::Nre(regex()?),
+        })
+    }
+
+    /// A missing label matches as an empty label value.
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            LabelMatch::Eq(expected) => value == expected,
+            LabelMatch::Neq(expected) => value != expected,
+            LabelMatch::Re(regex) => regex.is_match(value),
+            LabelMatch::Nre(regex) => !regex.is_match(value),
+        }
+    }
+}
+
+/// Parses the labels in a JSON object, values that are not strings are kept in JSON.
+fn json_to_labels(json: &str) -> Vec<Label> {
+    match serde_json::from_str(json) {
+        Ok(serde_json::Value::Object(labels)) => labels
+            .into_iter()
+            .map(|(name, value)| match value {
+                serde_json::Value::String(value) => new_label(name, value),
+                value => new_label(name, value.to_string()),
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Converts the result of [exemplars_query_sql] into the series of the metric of the remote
+/// request query with their exemplars, the series not matching the label matchers of the query
+/// are skipped.
+pub fn recordbatches_to_exemplars(
+    q: &Query,
+    recordbatches: RecordBatches,
+) -> Result<Vec<TimeSeries>> {
+    let metric = query_table_name(q)?;
+    let matchers = q
+        .matchers
+        .iter()
+        .filter(|m| m.name != METRIC_NAME_LABEL)
+        .map(|m| Ok((m.name.as_str(), LabelMatch::new(m)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Series labels in JSON -> the labels if the series matches, and its exemplars.
+    let mut timeseries_map: BTreeMap<String, Option<(Vec<Label>, Vec<Exemplar>)>> =
+        BTreeMap::default();
+    for recordbatch in recordbatches.take() {
+        let (ts_column, field_column) = sample_columns(&recordbatch)?;
+        let column = |name: &str| {
+            recordbatch.column_by_name(name).with_context(|| {
+                error::InvalidPromRemoteReadQueryResultSnafu {
+                    msg: format!("missing {name} column in exemplars query result"),
+                }
+            })
+        };
+        let series_labels_column = column(EXEMPLAR_SERIES_LABELS_COLUMN_NAME)?;
+        let labels_column = column(EXEMPLAR_LABELS_COLUMN_NAME)?;
+
+        for row in 0..recordbatch.num_rows() {
+            let Some(sample) = sample_at(ts_column, field_column, row) else {
+                continue;
+            };
+            let series_labels = string_at(series_labels_column, row);
+            let entry = timeseries_map
+                .entry(series_labels)
+                .or_insert_with_key(|series_labels| {
+                    let labels = json_to_labels(series_labels);
+                    let is_match = matchers.iter().all(|(name, matcher)| {
+                        let value = labels
+                            .iter()
+                            .find(|label| label.name == *name)
+                            .map(|label| label.value.as_str())
+                            .unwrap_or_default();
+                        matcher.matches(value)
+                    });
+                    is_match.then(|| {
+                        let mut series_labels = Vec::with_capacity(labels.len() + 1);
+                        series_labels
+                            .push(new_label(METRIC_NAME_LABEL.to_string(), metric.clone()));
+                        series_labels.extend(labels);
+                        (series_labels, Vec::new())
+                    })
+                });
+            if let Some((_, exemplars)) = entry {
+                exemplars.push(Exemplar {
+                    labels: json_to_labels(&string_at(labels_column, row)),
+                    value: sample.value,
+                    timestamp: sample.timestamp,
+                });
+            }
+        }
+    }
+    Ok(timeseries_map
+        .into_values()
+        .flatten()
+        .map(|(labels, exemplars)| TimeSeries {
+            labels,
+            exemplars,
+            ..Default::default()
+        })
+        .collect())
+}
+
+/// Returns the string in the row of a string column, or an empty string if it's null.
+fn string_at(column: &VectorRef, row: usize) -> String {
+    match column.get(row) {
+        Value::String(s) => s.as_utf8().to_string(),
+        _ => String::new(),
+    }
+}
+
 #[inline]
 pub fn snappy_decompress(buf: &[u8]) -> Result<Vec<u8>> {
     let mut decoder = Decoder::new();
@@ -498,6 +1221,36 @@ mod tests {
         let (table, sql) = query_to_sql(&q).unwrap();
         assert_eq!("test", table);
         assert_eq!("select * from test where greptime_timestamp>=1000 AND greptime_timestamp<=2000 AND job~'*prom*' AND instance!='localhost' order by greptime_timestamp", sql);
+
+        let (table, sql) =
+            series_ordered_query_sql(&q, &["job".to_string(), "instance".to_string()]).unwrap();
+        assert_eq!("test", table);
+        assert_eq!("select * from \"test\" where greptime_timestamp>=1000 AND greptime_timestamp<=2000 AND job~'*prom*' AND instance!='localhost' order by \"instance\", \"job\", greptime_timestamp", sql);
+        let (_, sql) = series_ordered_query_sql(&q, &[]).unwrap();
+        assert!(sql.ends_with(" order by greptime_timestamp"));
+
+        let (table, sql) = label_values_sql(&q, "job", "ts").unwrap();
+        assert_eq!("test", table);
+        assert_eq!("select distinct \"job\" from \"test\" where ts>=1000 AND ts<=2000 AND job~'*prom*' AND instance!='localhost'", sql);
+        let (_, sql) = label_values_sql(&q, METRIC_NAME_LABEL, "ts").unwrap();
+        assert!(sql.starts_with("select distinct 'test' from \"test\" where "));
+
+        let q = Query {
+            start_timestamp_ms: 1000,
+            end_timestamp_ms: 2000,
+            matchers: vec![LabelMatcher {
+                name: METRIC_NAME_LABEL.to_string(),
+                value: "Test's \"metric\"".to_string(),
+                r#type: EQ_TYPE,
+            }],
+            ..Default::default()
+        };
+        let (table, sql) = label_values_sql(&q, METRIC_NAME_LABEL, "ts").unwrap();
+        assert_eq!("Test's \"metric\"", table);
+        assert_eq!(
+            "select distinct 'Test''s \"metric\"' from \"Test's \"\"metric\"\"\" where ts>=1000 AND ts<=2000",
+            sql
+        );
     }
 
     #[test]
@@ -602,6 +1355,222 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_metadata_and_exemplars() {
+        let write_request = WriteRequest {
+            timeseries: vec![
+                TimeSeries {
+                    labels: vec![
+                        new_label(METRIC_NAME_LABEL.to_string(), "requests".to_string()),
+                        new_label("job".to_string(), "api".to_string()),
+                    ],
+                    samples: vec![Sample {
+                        value: 1.0,
+                        timestamp: 1000,
+                    }],
+                    exemplars: vec![Exemplar {
+                        labels: vec![new_label("trace_id".to_string(), "abc".to_string())],
+                        value: 0.5,
+                        timestamp: 900,
+                    }],
+                    ..Default::default()
+                },
+                TimeSeries {
+                    labels: vec![new_label(
+                        METRIC_NAME_LABEL.to_string(),
+                        "latency".to_string(),
+                    )],
+                    exemplars: vec![Exemplar {
+                        labels: vec![],
+                        value: 2.0,
+                        timestamp: 2000,
+                    }],
+                    ..Default::default()
+                },
+            ],
+            metadata: vec![MetricMetadata {
+                r#type: MetricType::Counter as i32,
+                metric_family_name: "requests".to_string(),
+                help: "Total requests.".to_string(),
+                unit: "".to_string(),
+            }],
+        };
+
+        let exprs = to_system_insert_requests(&write_request, "greptime", "db").unwrap();
+        let table_names = exprs
+            .iter()
+            .map(|expr| expr.table_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![EXEMPLAR_TABLE_NAME, METRIC_METADATA_TABLE_NAME],
+            table_names
+        );
+
+        let columns = &exprs[0].columns;
+        assert_eq!(2, exprs[0].row_count);
+        let column_names = columns
+            .iter()
+            .map(|column| column.column_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                CATALOG_NAME_COLUMN_NAME,
+                SCHEMA_NAME_COLUMN_NAME,
+                EXEMPLAR_METRIC_NAME_COLUMN_NAME,
+                EXEMPLAR_SERIES_LABELS_COLUMN_NAME,
+                TIMESTAMP_COLUMN_NAME,
+                FIELD_COLUMN_NAME,
+                EXEMPLAR_LABELS_COLUMN_NAME
+            ],
+            column_names
+        );
+        assert_eq!(
+            vec!["db", "db"],
+            columns[1].values.as_ref().unwrap().string_values
+        );
+        assert_eq!(
+            vec!["requests", "latency"],
+            columns[2].values.as_ref().unwrap().string_values
+        );
+        assert_eq!(
+            vec![r#"{"job":"api"}"#, "{}"],
+            columns[3].values.as_ref().unwrap().string_values
+        );
+        assert_eq!(
+            vec![900, 2000],
+            columns[4].values.as_ref().unwrap().ts_millisecond_values
+        );
+        assert_eq!(
+            vec![r#"{"trace_id":"abc"}"#, "{}"],
+            columns[6].values.as_ref().unwrap().string_values
+        );
+
+        let columns = &exprs[1].columns;
+        assert_eq!(1, exprs[1].row_count);
+        let values = columns
+            .iter()
+            .filter(|column| column.column_name != TIMESTAMP_COLUMN_NAME)
+            .map(|column| column.values.as_ref().unwrap().string_values[0].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "greptime",
+                "db",
+                "requests",
+                "counter",
+                "Total requests.",
+                ""
+            ],
+            values
+        );
+
+        // The series only carrying exemplars has no samples to insert.
+        let exprs = to_grpc_insert_requests(write_request).unwrap();
+        assert_eq!(1, exprs.len());
+        assert_eq!("requests", exprs[0].table_name);
+    }
+
+    #[test]
+    fn test_recordbatches_to_exemplars() {
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new(
+                EXEMPLAR_SERIES_LABELS_COLUMN_NAME,
+                ConcreteDataType::string_datatype(),
+                false,
+            ),
+            ColumnSchema::new(
+                TIMESTAMP_COLUMN_NAME,
+                ConcreteDataType::timestamp_millisecond_datatype(),
+                false,
+            ),
+            ColumnSchema::new(
+                FIELD_COLUMN_NAME,
+                ConcreteDataType::float64_datatype(),
+                true,
+            ),
+            ColumnSchema::new(
+                EXEMPLAR_LABELS_COLUMN_NAME,
+                ConcreteDataType::string_datatype(),
+                true,
+            ),
+        ]));
+        let recordbatches = || {
+            RecordBatches::try_new(
+                schema.clone(),
+                vec![RecordBatch::new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(StringVector::from(vec![
+                            r#"{"job":"api"}"#,
+                            r#"{"job":"api"}"#,
+                            r#"{"job":"db"}"#,
+                        ])) as _,
+                        Arc::new(TimestampMillisecondVector::from_vec(vec![1000, 2000, 1000])) as _,
+                        Arc::new(Float64Vector::from_vec(vec![1.0, 2.0, 3.0])) as _,
+                        Arc::new(StringVector::from(vec![r#"{"trace_id":"a"}"#, "{}", "{}"])) as _,
+                    ],
+                )
+                .unwrap()],
+            )
+            .unwrap()
+        };
+
+        let q = Query {
+            start_timestamp_ms: 0,
+            end_timestamp_ms: 3000,
+            matchers: vec![
+                LabelMatcher {
+                    name: METRIC_NAME_LABEL.to_string(),
+                    value: "requests".to_string(),
+                    r#type: EQ_TYPE,
+                },
+                LabelMatcher {
+                    name: "job".to_string(),
+                    value: "a.*".to_string(),
+                    r#type: RE_TYPE,
+                },
+            ],
+            ..Default::default()
+        };
+        let sql = exemplars_query_sql(&q, "greptime", "db").unwrap();
+        assert_eq!("select * from greptime.public.prometheus_exemplars where catalog_name='greptime' AND schema_name='db' AND metric_name='requests' AND greptime_timestamp>=0 AND greptime_timestamp<=3000 order by series_labels, greptime_timestamp", sql);
+
+        let timeseries = recordbatches_to_exemplars(&q, recordbatches()).unwrap();
+        assert_eq!(1, timeseries.len());
+        assert_eq!(
+            vec![
+                new_label(METRIC_NAME_LABEL.to_string(), "requests".to_string()),
+                new_label("job".to_string(), "api".to_string()),
+            ],
+            timeseries[0].labels
+        );
+        assert_eq!(
+            vec![
+                Exemplar {
+                    labels: vec![new_label("trace_id".to_string(), "a".to_string())],
+                    value: 1.0,
+                    timestamp: 1000,
+                },
+                Exemplar {
+                    labels: vec![],
+                    value: 2.0,
+                    timestamp: 2000,
+                },
+            ],
+            timeseries[0].exemplars
+        );
+
+        // Regular expressions are fully anchored.
+        let mut q = q;
+        q.matchers[1].value = "ap".to_string();
+        assert!(recordbatches_to_exemplars(&q, recordbatches())
+            .unwrap()
+            .is_empty());
+        q.matchers[1].value = "(".to_string();
+        let err = recordbatches_to_exemplars(&q, RecordBatches::empty()).unwrap_err();
+        assert!(matches!(err, error::Error::InvalidPromRemoteRequest { .. }));
+    }
+
     #[test]
     fn test_recordbatches_to_timeseries() {
         let schema = Arc::new(Schema::new(vec![
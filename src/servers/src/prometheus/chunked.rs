@@ -0,0 +1,624 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoding of the `STREAMED_XOR_CHUNKS` remote read responses, which are a stream of framed
+//! `ChunkedReadResponse` messages holding the samples of the series in XOR (Gorilla) encoded
+//! chunks, compatible with Prometheus.
+
+use api::prometheus::remote::chunk::Encoding;
+use api::prometheus::remote::{Chunk, ChunkedReadResponse, ChunkedSeries, Label};
+use crc::{Crc, CRC_32_ISCSI};
+use prost::Message;
+use snafu::{ensure, OptionExt};
+
+use crate::error::{self, Result};
+
+pub const CHUNKED_CONTENT_TYPE: &str =
+    "application/x-streamed-protobuf; proto=prometheus.ChunkedReadResponse";
+
+/// A frame is flushed once its chunks exceed this size, the same as Prometheus.
+const MAX_BYTES_IN_FRAME: usize = 1024 * 1024;
+/// Max number of samples in a chunk, the same as Prometheus.
+const MAX_SAMPLES_IN_CHUNK: u16 = 120;
+
+/// Frames are checksummed with CRC32 in the Castagnoli polynomial.
+const CASTAGNOLI: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+
+/// Encodes the samples of a query into frames of `ChunkedReadResponse`.
+///
+/// The samples of a series must be appended together in time order. A series whose chunks
+/// exceed the frame size is split into several frames, like Prometheus does.
+pub struct ChunkedResponseEncoder {
+    query_index: i64,
+    /// Series in the current frame.
+    series: Vec<ChunkedSeries>,
+    frame_bytes: usize,
+    labels: Vec<Label>,
+    /// Finished chunks of the current series that are not in a frame yet.
+    chunks: Vec<Chunk>,
+    chunk: Option<XorChunkEncoder>,
+}
+
+impl ChunkedResponseEncoder {
+    pub fn new(query_index: i64) -> Self {
+        Self {
+            query_index,
+            series: Vec::new(),
+            frame_bytes: 0,
+            labels: Vec::new(),
+            chunks: Vec::new(),
+            chunk: None,
+        }
+    }
+
+    /// Appends a sample of the series with `labels`, which must be sorted by name. Returns a
+    /// frame if there are enough chunks.
+    pub fn append(&mut self, labels: Vec<Label>, timestamp: i64, value: f64) -> Option<Vec<u8>> {
+        if labels != self.labels {
+            self.finish_series();
+            self.labels = labels;
+        }
+
+        let chunk = self.chunk.get_or_insert_with(XorChunkEncoder::new);
+        chunk.append(timestamp, value);
+        if chunk.num_samples >= MAX_SAMPLES_IN_CHUNK {
+            self.finish_chunk();
+        }
+
+        if self.frame_bytes >= MAX_BYTES_IN_FRAME {
+            Some(self.flush())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the last frame, if there are samples not in a frame.
+    pub fn finish(mut self) -> Option<Vec<u8>> {
+        self.finish_series();
+        if self.series.is_empty() {
+            None
+        } else {
+            Some(self.flush())
+        }
+    }
+
+    fn finish_chunk(&mut self) {
+        if let Some(chunk) = self.chunk.take() {
+            let chunk = chunk.finish();
+            self.frame_bytes += chunk.data.len();
+            self.chunks.push(chunk);
+        }
+    }
+
+    fn finish_series(&mut self) {
+        self.finish_chunk();
+        if !self.chunks.is_empty() {
+            self.series.push(ChunkedSeries {
+                labels: std::mem::take(&mut self.labels),
+                chunks: std::mem::take(&mut self.chunks),
+            });
+        }
+    }
+
+    /// Frames the series so far, including the finished chunks of the current series.
+    fn flush(&mut self) -> Vec<u8> {
+        if !self.chunks.is_empty() {
+            self.series.push(ChunkedSeries {
+                labels: self.labels.clone(),
+                chunks: std::mem::take(&mut self.chunks),
+            });
+        }
+        self.frame_bytes = 0;
+        let response = ChunkedReadResponse {
+            chunked_series: std::mem::take(&mut self.series),
+            query_index: self.query_index,
+        };
+        encode_frame(&response.encode_to_vec())
+    }
+}
+
+/// Frames a message as its length in uvarint, its checksum in big endian and itself.
+fn encode_frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(message.len() + 14);
+    prost::encoding::encode_varint(message.len() as u64, &mut frame);
+    frame.extend_from_slice(&CASTAGNOLI.checksum(message).to_be_bytes());
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// Decodes the frame at the beginning of `buf`, returns the message and the rest of `buf`.
+pub fn decode_frame(mut buf: &[u8]) -> Result<(ChunkedReadResponse, &[u8])> {
+    let invalid = |msg: &str| {
+        error::InvalidPromRemoteReadQueryResultSnafu {
+            msg: format!("invalid chunked read response frame: {msg}"),
+        }
+        .build()
+    };
+
+    let len = prost::encoding::decode_varint(&mut buf).map_err(|_| invalid("bad length"))? as usize;
+    ensure!(
+        buf.len() >= len + 4,
+        error::InvalidPromRemoteReadQueryResultSnafu {
+            msg: "invalid chunked read response frame: truncated",
+        }
+    );
+    let (checksum, rest) = buf.split_at(4);
+    let (message, rest) = rest.split_at(len);
+    if checksum != CASTAGNOLI.checksum(message).to_be_bytes().as_slice() {
+        return Err(invalid("checksum mismatch"));
+    }
+    let response = ChunkedReadResponse::decode(message).map_err(|_| invalid("bad message"))?;
+    Ok((response, rest))
+}
+
+/// Decodes the samples in a XOR encoded chunk.
+pub fn decode_xor_chunk(chunk: &Chunk) -> Result<Vec<(i64, f64)>> {
+    ensure!(
+        chunk.r#type == Encoding::Xor as i32,
+        error::InvalidPromRemoteReadQueryResultSnafu {
+            msg: format!("unsupported chunk encoding {}", chunk.r#type),
+        }
+    );
+    XorChunkDecoder::new(&chunk.data).decode().context(
+        error::InvalidPromRemoteReadQueryResultSnafu {
+            msg: "invalid XOR chunk",
+        },
+    )
+}
+
+/// Writes bits from the most significant one.
+struct BitWriter {
+    bytes: Vec<u8>,
+    /// Number of unwritten bits in the last byte.
+    free_bits: u8,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.free_bits == 0 {
+            self.bytes.push(0);
+            self.free_bits = 8;
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << (self.free_bits - 1);
+        }
+        self.free_bits -= 1;
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        if self.free_bits == 0 {
+            self.bytes.push(byte);
+            return;
+        }
+        *self.bytes.last_mut().unwrap() |= byte >> (8 - self.free_bits);
+        self.bytes.push(byte << self.free_bits);
+    }
+
+    /// Writes the lowest `nbits` bits of `bits`.
+    fn write_bits(&mut self, bits: u64, nbits: u8) {
+        if nbits == 0 {
+            return;
+        }
+        let mut bits = bits << (64 - nbits);
+        let mut nbits = nbits;
+        while nbits >= 8 {
+            self.write_byte((bits >> 56) as u8);
+            bits <<= 8;
+            nbits -= 8;
+        }
+        while nbits > 0 {
+            self.write_bit(bits >> 63 == 1);
+            bits <<= 1;
+            nbits -= 1;
+        }
+    }
+}
+
+/// Encodes samples in the XOR chunk format of Prometheus: the number of samples in 2 bytes,
+/// then the first timestamp in varint and value in raw bits, the second timestamp as the delta
+/// in uvarint, then the delta of deltas for the other timestamps, and the values XORed with
+/// the previous ones.
+struct XorChunkEncoder {
+    writer: BitWriter,
+    num_samples: u16,
+    min_time: i64,
+    timestamp: i64,
+    value: f64,
+    timestamp_delta: u64,
+    /// Leading and trailing zeros of the last XORed value written with them, 0xff if none.
+    leading: u8,
+    trailing: u8,
+}
+
+impl XorChunkEncoder {
+    fn new() -> Self {
+        Self {
+            writer: BitWriter {
+                bytes: vec![0, 0],
+                free_bits: 0,
+            },
+            num_samples: 0,
+            min_time: 0,
+            timestamp: 0,
+            value: 0.0,
+            timestamp_delta: 0,
+            leading: 0xff,
+            trailing: 0,
+        }
+    }
+
+    fn append(&mut self, timestamp: i64, value: f64) {
+        let mut timestamp_delta = 0;
+        match self.num_samples {
+            0 => {
+                self.min_time = timestamp;
+                let mut buf = Vec::with_capacity(10);
+                prost::encoding::encode_varint(zigzag(timestamp), &mut buf);
+                buf.into_iter()
+                    .for_each(|byte| self.writer.write_byte(byte));
+                self.writer.write_bits(value.to_bits(), 64);
+            }
+            1 => {
+                timestamp_delta = timestamp.wrapping_sub(self.timestamp) as u64;
+                let mut buf = Vec::with_capacity(10);
+                prost::encoding::encode_varint(timestamp_delta, &mut buf);
+                buf.into_iter()
+                    .for_each(|byte| self.writer.write_byte(byte));
+                self.write_value(value);
+            }
+            _ => {
+                timestamp_delta = timestamp.wrapping_sub(self.timestamp) as u64;
+                let dod = timestamp_delta.wrapping_sub(self.timestamp_delta) as i64;
+                if dod == 0 {
+                    self.writer.write_bit(false);
+                } else if bit_range(dod, 14) {
+                    self.writer.write_bits(0b10, 2);
+                    self.writer.write_bits(dod as u64, 14);
+                } else if bit_range(dod, 17) {
+                    self.writer.write_bits(0b110, 3);
+                    self.writer.write_bits(dod as u64, 17);
+                } else if bit_range(dod, 20) {
+                    self.writer.write_bits(0b1110, 4);
+                    self.writer.write_bits(dod as u64, 20);
+                } else {
+                    self.writer.write_bits(0b1111, 4);
+                    self.writer.write_bits(dod as u64, 64);
+                }
+                self.write_value(value);
+            }
+        }
+        self.timestamp = timestamp;
+        self.value = value;
+        self.timestamp_delta = timestamp_delta;
+        self.num_samples += 1;
+    }
+
+    fn write_value(&mut self, value: f64) {
+        let delta = value.to_bits() ^ self.value.to_bits();
+        if delta == 0 {
+            self.writer.write_bit(false);
+            return;
+        }
+        self.writer.write_bit(true);
+
+        // Clamps the leading zeros to fit in 5 bits.
+        let leading = (delta.leading_zeros() as u8).min(31);
+        let trailing = delta.trailing_zeros() as u8;
+        if self.leading != 0xff && leading >= self.leading && trailing >= self.trailing {
+            self.writer.write_bit(false);
+            self.writer
+                .write_bits(delta >> self.trailing, 64 - self.leading - self.trailing);
+            return;
+        }
+
+        self.leading = leading;
+        self.trailing = trailing;
+        self.writer.write_bit(true);
+        self.writer.write_bits(leading as u64, 5);
+        // 64 significant bits overflows to 0, which is decoded as 64.
+        let significant_bits = 64 - leading - trailing;
+        self.writer.write_bits(significant_bits as u64, 6);
+        self.writer.write_bits(delta >> trailing, significant_bits);
+    }
+
+    fn finish(mut self) -> Chunk {
+        self.writer.bytes[..2].copy_from_slice(&self.num_samples.to_be_bytes());
+        Chunk {
+            min_time_ms: self.min_time,
+            max_time_ms: self.timestamp,
+            r#type: Encoding::Xor as i32,
+            data: self.writer.bytes,
+        }
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Whether `value` can be encoded in `nbits` bits, with the range Prometheus uses.
+fn bit_range(value: i64, nbits: u8) -> bool {
+    -((1 << (nbits - 1)) - 1) <= value && value <= 1 << (nbits - 1)
+}
+
+/// Reads bits from the most significant one.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// Position of the next bit.
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.position / 8)?;
+        let bit = (byte >> (7 - self.position % 8)) & 1 == 1;
+        self.position += 1;
+        Some(bit)
+    }
+
+    fn read_bits(&mut self, nbits: u8) -> Option<u64> {
+        let mut bits = 0;
+        for _ in 0..nbits {
+            bits = (bits << 1) | self.read_bit()? as u64;
+        }
+        Some(bits)
+    }
+
+    fn read_uvarint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_bits(8)?;
+            value |= (byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+struct XorChunkDecoder<'a> {
+    reader: BitReader<'a>,
+    num_samples: u16,
+}
+
+impl<'a> XorChunkDecoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let num_samples = data
+            .get(..2)
+            .map(|header| u16::from_be_bytes([header[0], header[1]]))
+            .unwrap_or_default();
+        Self {
+            reader: BitReader {
+                bytes: data,
+                position: 16,
+            },
+            num_samples,
+        }
+    }
+
+    fn decode(mut self) -> Option<Vec<(i64, f64)>> {
+        let mut samples = Vec::with_capacity(self.num_samples as usize);
+        let (mut timestamp, mut value_bits, mut timestamp_delta) = (0i64, 0u64, 0i64);
+        let (mut leading, mut trailing) = (0u8, 0u8);
+        for i in 0..self.num_samples {
+            match i {
+                0 => {
+                    let raw = self.reader.read_uvarint()?;
+                    timestamp = (raw >> 1) as i64 ^ -((raw & 1) as i64);
+                    value_bits = self.reader.read_bits(64)?;
+                }
+                _ => {
+                    if i == 1 {
+                        timestamp_delta = self.reader.read_uvarint()? as i64;
+                    } else {
+                        let mut prefix = 0;
+                        while prefix < 4 && self.reader.read_bit()? {
+                            prefix += 1;
+                        }
+                        let dod = match prefix {
+                            0 => 0,
+                            1 => sign_extend(self.reader.read_bits(14)?, 14),
+                            2 => sign_extend(self.reader.read_bits(17)?, 17),
+                            3 => sign_extend(self.reader.read_bits(20)?, 20),
+                            _ => self.reader.read_bits(64)? as i64,
+                        };
+                        timestamp_delta += dod;
+                    }
+                    timestamp += timestamp_delta;
+
+                    if self.reader.read_bit()? {
+                        if self.reader.read_bit()? {
+                            leading = self.reader.read_bits(5)? as u8;
+                            let significant_bits = match self.reader.read_bits(6)? as u8 {
+                                0 => 64,
+                                n => n,
+                            };
+                            trailing = 64 - leading - significant_bits;
+                        }
+                        let significant_bits = 64 - leading - trailing;
+                        value_bits ^= self.reader.read_bits(significant_bits)? << trailing;
+                    }
+                }
+            }
+            samples.push((timestamp, f64::from_bits(value_bits)));
+        }
+        Some(samples)
+    }
+}
+
+/// Interprets the lowest `nbits` bits as a signed integer, the inverse of Prometheus' encoding
+/// where `1 << (nbits - 1)` is positive.
+fn sign_extend(bits: u64, nbits: u8) -> i64 {
+    if bits > 1 << (nbits - 1) {
+        bits as i64 - (1 << nbits)
+    } else {
+        bits as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str, value: &str) -> Label {
+        Label {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn encode_chunk(samples: &[(i64, f64)]) -> Chunk {
+        let mut encoder = XorChunkEncoder::new();
+        for (timestamp, value) in samples {
+            encoder.append(*timestamp, *value);
+        }
+        encoder.finish()
+    }
+
+    #[test]
+    fn test_xor_chunk() {
+        // Deltas of deltas in every range, and values sharing or not sharing the window of
+        // significant bits.
+        let samples = vec![
+            (-1000, 1.0),
+            (0, 1.0),
+            (1000, 2.0),
+            (2000, 2.5),
+            (3005, -3.25),
+            (10000, f64::MAX),
+            (100000, f64::MIN_POSITIVE),
+            (1000000, f64::INFINITY),
+            (1000001, 0.0),
+            (100000000000, 123456.789),
+            (100000000000, 123456.789),
+        ];
+        let chunk = encode_chunk(&samples);
+        assert_eq!(-1000, chunk.min_time_ms);
+        assert_eq!(100000000000, chunk.max_time_ms);
+        assert_eq!(
+            samples.len() as u16,
+            u16::from_be_bytes([chunk.data[0], chunk.data[1]])
+        );
+        assert_eq!(samples, decode_xor_chunk(&chunk).unwrap());
+
+        let samples = (0..120)
+            .map(|i| (i * 15000, (i % 7) as f64))
+            .collect::<Vec<_>>();
+        assert_eq!(samples, decode_xor_chunk(&encode_chunk(&samples)).unwrap());
+    }
+
+    #[test]
+    fn test_xor_chunk_bytes() {
+        // The first sample is the timestamp 1 in zigzag varint and the value 1.0 in raw bits,
+        // the second is the delta 1 in uvarint and the bit 0 for the same value.
+        let chunk = encode_chunk(&[(1, 1.0), (2, 1.0)]);
+        assert_eq!(
+            vec![0, 2, 2, 0x3f, 0xf0, 0, 0, 0, 0, 0, 0, 1, 0],
+            chunk.data
+        );
+    }
+
+    #[test]
+    fn test_frame() {
+        let response = ChunkedReadResponse {
+            chunked_series: vec![],
+            query_index: 3,
+        };
+        let mut buf = encode_frame(&response.encode_to_vec());
+        buf.extend(encode_frame(&response.encode_to_vec()));
+
+        let (decoded, rest) = decode_frame(&buf).unwrap();
+        assert_eq!(response, decoded);
+        let (decoded, rest) = decode_frame(rest).unwrap();
+        assert_eq!(response, decoded);
+        assert!(rest.is_empty());
+
+        let mut corrupted = encode_frame(&response.encode_to_vec());
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(decode_frame(&corrupted).is_err());
+    }
+
+    #[test]
+    fn test_chunked_response_encoder() {
+        let series_a = vec![label("__name__", "m"), label("host", "a")];
+        let series_b = vec![label("__name__", "m"), label("host", "b")];
+        let mut encoder = ChunkedResponseEncoder::new(1);
+        for i in 0..150 {
+            assert!(encoder
+                .append(series_a.clone(), i * 1000, i as f64)
+                .is_none());
+        }
+        assert!(encoder.append(series_b.clone(), 0, 1.0).is_none());
+        let frame = encoder.finish().unwrap();
+
+        let (response, rest) = decode_frame(&frame).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(1, response.query_index);
+        assert_eq!(2, response.chunked_series.len());
+
+        let series = &response.chunked_series[0];
+        assert_eq!(series_a, series.labels);
+        assert_eq!(2, series.chunks.len());
+        assert_eq!(
+            (0, 119000),
+            (series.chunks[0].min_time_ms, series.chunks[0].max_time_ms)
+        );
+        let samples = series
+            .chunks
+            .iter()
+            .flat_map(|chunk| decode_xor_chunk(chunk).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            (0..150).map(|i| (i * 1000, i as f64)).collect::<Vec<_>>(),
+            samples
+        );
+
+        let series = &response.chunked_series[1];
+        assert_eq!(series_b, series.labels);
+        assert_eq!(vec![(0, 1.0)], decode_xor_chunk(&series.chunks[0]).unwrap());
+
+        assert!(ChunkedResponseEncoder::new(0).finish().is_none());
+    }
+
+    #[test]
+    fn test_split_series_into_frames() {
+        let labels = vec![label("__name__", "m")];
+        let mut encoder = ChunkedResponseEncoder::new(0);
+        let mut frames = vec![];
+        // Random values don't compress, so the chunks exceed a frame.
+        let mut value = 0u64;
+        for i in 0..300_000 {
+            value = value
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            if let Some(frame) = encoder.append(labels.clone(), i, f64::from_bits(value >> 2)) {
+                frames.push(frame);
+            }
+        }
+        frames.extend(encoder.finish());
+        assert!(frames.len() > 1);
+
+        let mut num_samples = 0;
+        for frame in frames {
+            let (response, _) = decode_frame(&frame).unwrap();
+            assert_eq!(1, response.chunked_series.len());
+            assert_eq!(labels, response.chunked_series[0].labels);
+            for chunk in &response.chunked_series[0].chunks {
+                num_samples += decode_xor_chunk(chunk).unwrap().len();
+            }
+        }
+        assert_eq!(300_000, num_samples);
+    }
+}
@@ -31,16 +31,21 @@ use std::sync::Arc;
 use api::prometheus::remote::{ReadRequest, WriteRequest};
 use async_trait::async_trait;
 use common_query::Output;
+use futures::stream::BoxStream;
 use session::context::QueryContextRef;
 
 use crate::error::Result;
+use crate::graphite::codec::GraphiteRequest;
 use crate::influxdb::InfluxdbRequest;
 use crate::opentsdb::codec::DataPoint;
+use crate::otlp::OtlpMetricsRequest;
 use crate::prometheus::Metrics;
 
 pub type OpentsdbProtocolHandlerRef = Arc<dyn OpentsdbProtocolHandler + Send + Sync>;
 pub type InfluxdbLineProtocolHandlerRef = Arc<dyn InfluxdbLineProtocolHandler + Send + Sync>;
 pub type PrometheusProtocolHandlerRef = Arc<dyn PrometheusProtocolHandler + Send + Sync>;
+pub type OpenTelemetryProtocolHandlerRef = Arc<dyn OpenTelemetryProtocolHandler + Send + Sync>;
+pub type GraphiteProtocolHandlerRef = Arc<dyn GraphiteProtocolHandler + Send + Sync>;
 pub type ScriptHandlerRef = Arc<dyn ScriptHandler + Send + Sync>;
 
 #[async_trait]
@@ -59,6 +64,10 @@ pub trait InfluxdbLineProtocolHandler {
     /// A successful request will not return a response.
     /// Only on error will the socket return a line of data.
     async fn exec(&self, request: &InfluxdbRequest, ctx: QueryContextRef) -> Result<()>;
+
+    /// Returns the names of the databases in the catalog of `ctx`, which are listed as the
+    /// buckets by the InfluxDB v2 API.
+    async fn database_names(&self, ctx: QueryContextRef) -> Result<Vec<String>>;
 }
 
 #[async_trait]
@@ -70,8 +79,15 @@ pub trait OpentsdbProtocolHandler {
 
 pub struct PrometheusResponse {
     pub content_type: String,
+    /// Encoding of the body, empty if it's not encoded.
     pub content_encoding: String,
-    pub body: Vec<u8>,
+    pub body: PrometheusResponseBody,
+}
+
+pub enum PrometheusResponseBody {
+    Full(Vec<u8>),
+    /// A body sent in chunks as they are produced, like the streamed remote read responses.
+    Stream(BoxStream<'static, Result<Vec<u8>>>),
 }
 
 #[async_trait]
@@ -83,3 +99,15 @@ pub trait PrometheusProtocolHandler {
     /// Handling push gateway requests
     async fn ingest_metrics(&self, metrics: Metrics) -> Result<()>;
 }
+
+#[async_trait]
+pub trait OpenTelemetryProtocolHandler {
+    /// Handling OTLP metrics export requests
+    async fn metrics(&self, request: &OtlpMetricsRequest, ctx: QueryContextRef) -> Result<()>;
+}
+
+#[async_trait]
+pub trait GraphiteProtocolHandler {
+    /// Handling the metrics received by the Graphite servers, which never reply to the clients.
+    async fn exec(&self, request: &GraphiteRequest, ctx: QueryContextRef) -> Result<()>;
+}
@@ -18,12 +18,14 @@ use api::v1::greptime_request::Request as GreptimeRequest;
 use async_trait::async_trait;
 use common_error::prelude::*;
 use common_query::Output;
+use common_recordbatch::RecordBatch;
 use session::context::QueryContextRef;
 
 use crate::error::{self, Result};
 
 pub type GrpcQueryHandlerRef<E> = Arc<dyn GrpcQueryHandler<Error = E> + Send + Sync>;
 pub type ServerGrpcQueryHandlerRef = GrpcQueryHandlerRef<error::Error>;
+pub type BulkInsertHandlerRef = Arc<dyn BulkInsertHandler + Send + Sync>;
 
 #[async_trait]
 pub trait GrpcQueryHandler {
@@ -59,3 +61,16 @@ where
             .context(error::ExecuteGrpcQuerySnafu)
     }
 }
+
+/// Inserts the record batches put by Arrow Flight into tables as they are.
+#[async_trait]
+pub trait BulkInsertHandler {
+    /// Inserts the record batch into the table, creating or altering the table on demand.
+    /// Returns the affected rows.
+    async fn insert_recordbatch(
+        &self,
+        table_name: &str,
+        recordbatch: RecordBatch,
+        ctx: QueryContextRef,
+    ) -> Result<usize>;
+}
@@ -16,10 +16,15 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use common_error::prelude::*;
+use common_error::status_code::StatusCode;
 use common_query::Output;
+use common_recordbatch::{util, RecordBatch};
+use common_time::timestamp::TimeUnit;
 use datatypes::schema::Schema;
+use datatypes::value::Value;
 use query::parser::PromQuery;
 use session::context::QueryContextRef;
+use snafu::{OptionExt, ResultExt};
 use sql::statements::statement::Statement;
 
 use crate::error::{self, Result};
@@ -123,3 +128,119 @@ where
             .context(error::CheckDatabaseValiditySnafu)
     }
 }
+
+/// The columns of a table grouped by their semantic types, which are found by `DESCRIBE TABLE`.
+#[derive(Debug)]
+pub(crate) struct TableColumns {
+    pub(crate) tags: Vec<String>,
+    /// The names and the types of the fields.
+    pub(crate) fields: Vec<(String, String)>,
+    pub(crate) time_index: String,
+    pub(crate) time_unit: TimeUnit,
+}
+
+/// Describes the table, or returns `None` if it doesn't exist.
+pub(crate) async fn describe_table(
+    handler: &ServerSqlQueryHandlerRef,
+    table: &str,
+    ctx: QueryContextRef,
+) -> Result<Option<TableColumns>> {
+    let sql = format!("DESCRIBE TABLE {}", quote_ident(table));
+    let batches = match query_recordbatches(handler, &sql, ctx).await {
+        Ok(batches) => batches,
+        Err(e) if e.status_code() == StatusCode::TableNotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut columns = TableColumns {
+        tags: vec![],
+        fields: vec![],
+        time_index: String::new(),
+        time_unit: TimeUnit::Millisecond,
+    };
+    for batch in &batches {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .with_context(|| error::InternalSnafu {
+                    err_msg: format!("missing {name} column in the description of table"),
+                })
+        };
+        let names = column("Field")?;
+        let types = column("Type")?;
+        let semantic_types = column("Semantic Type")?;
+        for row in 0..batch.num_rows() {
+            let name = value_to_string(names.get(row)).unwrap_or_default();
+            let data_type = value_to_string(types.get(row)).unwrap_or_default();
+            match value_to_string(semantic_types.get(row)).as_deref() {
+                Some("PRIMARY KEY") => columns.tags.push(name),
+                Some("TIME INDEX") => {
+                    columns.time_unit = match data_type.as_str() {
+                        "TimestampSecond" => TimeUnit::Second,
+                        "TimestampMicrosecond" => TimeUnit::Microsecond,
+                        "TimestampNanosecond" => TimeUnit::Nanosecond,
+                        _ => TimeUnit::Millisecond,
+                    };
+                    columns.time_index = name;
+                }
+                _ => columns.fields.push((name, data_type)),
+            }
+        }
+    }
+    Ok(Some(columns))
+}
+
+/// Executes a single SQL statement and collects its results.
+pub(crate) async fn query_recordbatches(
+    handler: &ServerSqlQueryHandlerRef,
+    sql: &str,
+    ctx: QueryContextRef,
+) -> Result<Vec<RecordBatch>> {
+    let output = handler
+        .do_query(sql, ctx)
+        .await
+        .into_iter()
+        .next()
+        .unwrap_or(Ok(Output::AffectedRows(0)))?;
+    match output {
+        Output::Stream(stream) => util::collect(stream)
+            .await
+            .context(error::CollectRecordbatchSnafu),
+        Output::RecordBatches(recordbatches) => Ok(recordbatches.take()),
+        Output::AffectedRows(_) => Ok(vec![]),
+    }
+}
+
+/// Executes a single SQL statement and returns the non-null values of its first column as
+/// strings.
+pub(crate) async fn query_strings(
+    handler: &ServerSqlQueryHandlerRef,
+    sql: &str,
+    ctx: QueryContextRef,
+) -> Result<Vec<String>> {
+    let mut strings = Vec::new();
+    for batch in query_recordbatches(handler, sql, ctx).await? {
+        if batch.num_columns() == 0 {
+            continue;
+        }
+        let column = batch.column(0);
+        strings.extend((0..batch.num_rows()).filter_map(|row| value_to_string(column.get(row))));
+    }
+    Ok(strings)
+}
+
+pub(crate) fn value_to_string(value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.as_utf8().to_string()),
+        value => Some(value.to_string()),
+    }
+}
+
+pub(crate) fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+pub(crate) fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
@@ -16,9 +16,12 @@ use std::collections::HashMap;
 
 use axum::body::Body;
 use axum::extract::{Json, Query, RawBody, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
 use axum::Form;
 use common_telemetry::metric;
 use metrics::counter;
+use servers::http::handler::{ResponseFormat, SqlResponse};
 use servers::http::{handler as http_handler, script as script_handler, ApiState, JsonOutput};
 use servers::metrics_handler::MetricsHandler;
 use session::context::UserInfo;
@@ -32,7 +35,7 @@ use crate::{
 #[tokio::test]
 async fn test_sql_not_provided() {
     let sql_handler = create_testing_sql_query_handler(MemTable::default_numbers_table());
-    let Json(json) = http_handler::sql(
+    let SqlResponse::Json(Json(json)) = http_handler::sql(
         State(ApiState {
             sql_handler,
             script_handler: None,
@@ -41,7 +44,7 @@ async fn test_sql_not_provided() {
         axum::Extension(UserInfo::default()),
         Form(http_handler::SqlQuery::default()),
     )
-    .await;
+    .await else { unreachable!() };
     assert!(!json.success());
     assert_eq!(
         Some(&"sql parameter is required.".to_string()),
@@ -57,7 +60,7 @@ async fn test_sql_output_rows() {
     let query = create_query();
     let sql_handler = create_testing_sql_query_handler(MemTable::default_numbers_table());
 
-    let Json(json) = http_handler::sql(
+    let SqlResponse::Json(Json(json)) = http_handler::sql(
         State(ApiState {
             sql_handler,
             script_handler: None,
@@ -66,7 +69,7 @@ async fn test_sql_output_rows() {
         axum::Extension(UserInfo::default()),
         Form(http_handler::SqlQuery::default()),
     )
-    .await;
+    .await else { unreachable!() };
     assert!(json.success(), "{json:?}");
     assert!(json.error().is_none());
     match &json.output().expect("assertion failed")[0] {
@@ -96,6 +99,33 @@ async fn test_sql_output_rows() {
     }
 }
 
+#[tokio::test]
+async fn test_sql_output_csv() {
+    let sql_handler = create_testing_sql_query_handler(MemTable::default_numbers_table());
+    let response = http_handler::sql(
+        State(ApiState {
+            sql_handler,
+            script_handler: None,
+        }),
+        Query(http_handler::SqlQuery {
+            sql: Some("select uint32s from numbers order by uint32s limit 3".to_string()),
+            db: None,
+            format: Some(ResponseFormat::Csv),
+        }),
+        axum::Extension(UserInfo::default()),
+        Form(http_handler::SqlQuery::default()),
+    )
+    .await
+    .into_response();
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!(
+        "text/csv",
+        response.headers().get(header::CONTENT_TYPE).unwrap()
+    );
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!("uint32s\n0\n1\n2\n", String::from_utf8_lossy(&body));
+}
+
 #[tokio::test]
 async fn test_sql_form() {
     common_telemetry::init_default_ut_logging();
@@ -103,7 +133,7 @@ async fn test_sql_form() {
     let form = create_form();
     let sql_handler = create_testing_sql_query_handler(MemTable::default_numbers_table());
 
-    let Json(json) = http_handler::sql(
+    let SqlResponse::Json(Json(json)) = http_handler::sql(
         State(ApiState {
             sql_handler,
             script_handler: None,
@@ -112,7 +142,7 @@ async fn test_sql_form() {
         axum::Extension(UserInfo::default()),
         form,
     )
-    .await;
+    .await else { unreachable!() };
     assert!(json.success(), "{json:?}");
     assert!(json.error().is_none());
     match &json.output().expect("assertion failed")[0] {
@@ -341,6 +371,7 @@ fn create_query() -> Query<http_handler::SqlQuery> {
     Query(http_handler::SqlQuery {
         sql: Some("select sum(uint32s) from numbers limit 20".to_string()),
         db: None,
+        format: None,
     })
 }
 
@@ -348,6 +379,7 @@ fn create_form() -> Form<http_handler::SqlQuery> {
     Form(http_handler::SqlQuery {
         sql: Some("select sum(uint32s) from numbers limit 20".to_string()),
         db: None,
+        format: None,
     })
 }
 
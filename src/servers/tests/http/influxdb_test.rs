@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write;
 use std::sync::Arc;
 
 use api::v1::greptime_request::Request;
@@ -21,6 +22,8 @@ use axum::{http, Router};
 use axum_test_helper::TestClient;
 use common_query::Output;
 use datatypes::schema::Schema;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use query::parser::PromQuery;
 use servers::error::{Error, Result};
 use servers::http::{HttpOptions, HttpServerBuilder};
@@ -61,6 +64,10 @@ impl InfluxdbLineProtocolHandler for DummyInstance {
 
         Ok(())
     }
+
+    async fn database_names(&self, _ctx: QueryContextRef) -> Result<Vec<String>> {
+        Ok(vec!["influxdb".to_string(), "public".to_string()])
+    }
 }
 
 #[async_trait]
@@ -199,3 +206,114 @@ async fn test_influxdb_write() {
         ]
     );
 }
+
+#[tokio::test]
+async fn test_influxdb_v2_write() {
+    let (tx, mut rx) = mpsc::channel(100);
+    let tx = Arc::new(tx);
+
+    let app = make_test_app(tx.clone(), Some("influxdb"));
+    let client = TestClient::new(app);
+
+    // bucket with a retention policy
+    let result = client
+        .post("/v1/influxdb/api/v2/write?org=greptime&bucket=influxdb%2Fautogen&precision=ns")
+        .body("monitor,host=host1 cpu=1.2 1664370459457010101")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 204);
+    assert!(result.text().await.is_empty());
+
+    // gzipped body
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(b"monitor,host=host1 cpu=1.2 1664370459457")
+        .unwrap();
+    let result = client
+        .post("/v1/influxdb/api/v2/write?org=greptime&bucket=influxdb&precision=ms")
+        .body(encoder.finish().unwrap())
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .header(http::header::CONTENT_ENCODING, "gzip")
+        .send()
+        .await;
+    assert_eq!(result.status(), 204);
+
+    // unauthorized bucket
+    let result = client
+        .post("/v1/influxdb/api/v2/write?org=greptime&bucket=public")
+        .body("monitor,host=host1 cpu=1.2 1664370459457010101")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 401);
+
+    // the bucket is authorized after being decoded, like it's written
+    let result = client
+        .post("/v1/influxdb/api/v2/write?org=greptime&bucket=%70ublic")
+        .body("monitor,host=host1 cpu=1.2 1664370459457010101")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 401);
+
+    // more than one bucket
+    let result = client
+        .post("/v1/influxdb/api/v2/write?org=greptime&bucket=influxdb&bucket=public")
+        .body("monitor,host=host1 cpu=1.2 1664370459457010101")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 401);
+
+    // v1 precision is invalid in v2
+    let result = client
+        .post("/v1/influxdb/api/v2/write?org=greptime&bucket=influxdb&precision=n")
+        .body("monitor,host=host1 cpu=1.2 1664370459457010101")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 400);
+
+    // only the accessible buckets are listed
+    let result = client
+        .get("/v1/influxdb/api/v2/buckets?org=greptime")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 200);
+    let buckets: serde_json::Value = serde_json::from_str(&result.text().await).unwrap();
+    assert_eq!(
+        serde_json::json!({
+            "buckets": [{
+                "id": "influxdb",
+                "orgID": "greptime",
+                "name": "influxdb",
+                "type": "user",
+                "retentionRules": [],
+            }]
+        }),
+        buckets
+    );
+
+    let result = client
+        .get("/v1/influxdb/api/v2/buckets?org=greptime&name=public")
+        .header(http::header::AUTHORIZATION, "Token greptime:greptime")
+        .send()
+        .await;
+    assert_eq!(result.status(), 200);
+    let buckets: serde_json::Value = serde_json::from_str(&result.text().await).unwrap();
+    assert_eq!(serde_json::json!({ "buckets": [] }), buckets);
+
+    let mut metrics = vec![];
+    while let Ok(s) = rx.try_recv() {
+        metrics.push(s);
+    }
+    assert_eq!(
+        metrics,
+        vec![
+            ("influxdb".to_string(), "monitor".to_string()),
+            ("influxdb".to_string(), "monitor".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,365 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use api::prometheus::remote::Query as RemoteQuery;
+use async_trait::async_trait;
+use axum::Router;
+use axum_test_helper::TestClient;
+use common_query::Output;
+use common_recordbatch::{RecordBatch, RecordBatches};
+use datatypes::prelude::ConcreteDataType;
+use datatypes::schema::{ColumnSchema, Schema};
+use datatypes::vectors::{Float64Vector, StringVector, TimestampMillisecondVector};
+use query::parser::PromQuery;
+use serde_json::{json, Value};
+use servers::error::Result;
+use servers::http::{HttpOptions, HttpServerBuilder};
+use servers::prom::{PromHandler, PromServer};
+use session::context::QueryContextRef;
+use tokio::sync::mpsc;
+
+/// Answers every query with the same two series: `host="a"` and `host="b"`, of the metrics `cpu`
+/// and `mem`. The query `slow` takes a second to answer. The database `empty` has no metrics.
+struct DummyInstance {
+    tx: mpsc::Sender<PromQuery>,
+}
+
+#[async_trait]
+impl PromHandler for DummyInstance {
+    async fn do_query(&self, query: &PromQuery, _query_ctx: QueryContextRef) -> Result<Output> {
+        let _ = self.tx.send(query.clone()).await;
+        if query.query == "slow" {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        let schema = Arc::new(Schema::new(vec![
+            ColumnSchema::new("host", ConcreteDataType::string_datatype(), true),
+            ColumnSchema::new(
+                "ts",
+                ConcreteDataType::timestamp_millisecond_datatype(),
+                false,
+            ),
+            ColumnSchema::new("value", ConcreteDataType::float64_datatype(), true),
+        ]));
+        let batch = RecordBatch::new(
+            schema.clone(),
+            vec![
+                Arc::new(StringVector::from(vec!["a", "a", "b"])) as _,
+                Arc::new(TimestampMillisecondVector::from_vec(vec![0, 5000, 5000])) as _,
+                Arc::new(Float64Vector::from_vec(vec![1.0, 0.5, f64::INFINITY])) as _,
+            ],
+        )
+        .unwrap();
+        Ok(Output::RecordBatches(
+            RecordBatches::try_new(schema, vec![batch]).unwrap(),
+        ))
+    }
+
+    async fn metric_names(&self, query_ctx: QueryContextRef) -> Result<Vec<String>> {
+        if query_ctx.current_schema() == "empty" {
+            return Ok(vec![]);
+        }
+        Ok(vec!["mem".to_string(), "cpu".to_string()])
+    }
+
+    async fn label_names(
+        &self,
+        metric: &str,
+        query_ctx: QueryContextRef,
+    ) -> Result<Option<Vec<String>>> {
+        let exists = query_ctx.current_schema() != "empty" && (metric == "cpu" || metric == "mem");
+        Ok(exists.then(|| vec!["host".to_string()]))
+    }
+
+    async fn label_values(
+        &self,
+        query: &RemoteQuery,
+        label_name: &str,
+        _query_ctx: QueryContextRef,
+    ) -> Result<Vec<String>> {
+        let metric = &query.matchers.last().unwrap().value;
+        match label_name {
+            "__name__" => Ok(vec![metric.clone()]),
+            _ => Ok(vec!["a".to_string(), "b".to_string()]),
+        }
+    }
+}
+
+fn make_test_app(tx: mpsc::Sender<PromQuery>) -> Router {
+    PromServer::create_server(Arc::new(DummyInstance { tx })).make_app()
+}
+
+async fn get(client: &TestClient, url: &str) -> (u16, Value) {
+    let res = client.get(url).send().await;
+    let status = res.status().as_u16();
+    (status, serde_json::from_str(&res.text().await).unwrap())
+}
+
+fn received(rx: &mut mpsc::Receiver<PromQuery>) -> Vec<PromQuery> {
+    let mut queries = vec![];
+    while let Ok(query) = rx.try_recv() {
+        queries.push(query);
+    }
+    queries
+}
+
+#[tokio::test]
+async fn test_prom_query_api() {
+    let (tx, mut rx) = mpsc::channel(100);
+    let client = TestClient::new(make_test_app(tx));
+
+    let (status, body) = get(&client, "/api/v1/query?query=cpu&time=5").await;
+    assert_eq!(200, status);
+    assert_eq!(
+        json!({
+            "status": "success",
+            "data": {
+                "resultType": "vector",
+                "result": [
+                    {"metric": {"__name__": "cpu", "host": "a"}, "value": [5.0, "0.5"]},
+                    {"metric": {"__name__": "cpu", "host": "b"}, "value": [5.0, "+Inf"]},
+                ],
+            },
+        }),
+        body
+    );
+    assert_eq!(
+        vec![PromQuery {
+            query: "cpu".to_string(),
+            start: "5".to_string(),
+            end: "5".to_string(),
+            step: "1s".to_string(),
+        }],
+        received(&mut rx)
+    );
+
+    let (status, body) = get(
+        &client,
+        "/api/v1/query_range?query=cpu&start=0&end=5&step=5",
+    )
+    .await;
+    assert_eq!(200, status);
+    assert_eq!(
+        json!({
+            "status": "success",
+            "data": {
+                "resultType": "matrix",
+                "result": [
+                    {"metric": {"__name__": "cpu", "host": "a"}, "values": [[0.0, "1"], [5.0, "0.5"]]},
+                    {"metric": {"__name__": "cpu", "host": "b"}, "values": [[5.0, "+Inf"]]},
+                ],
+            },
+        }),
+        body
+    );
+
+    // A string is returned without querying.
+    let (status, body) = get(&client, "/api/v1/query?query=%22foo%22&time=1.5").await;
+    assert_eq!(200, status);
+    assert_eq!(
+        json!({
+            "status": "success",
+            "data": {"resultType": "string", "result": [1.5, "foo"]},
+        }),
+        body
+    );
+
+    let (status, body) = get(
+        &client,
+        "/api/v1/query_range?query=cpu%5B5m%5D&start=0&end=5&step=5",
+    )
+    .await;
+    assert_eq!(400, status);
+    assert_eq!("error", body["status"]);
+    assert_eq!("bad_data", body["errorType"]);
+    assert!(body.get("data").is_none());
+
+    let (status, body) = get(&client, "/api/v1/query?query=cpu%7B").await;
+    assert_eq!(400, status);
+    assert_eq!("bad_data", body["errorType"]);
+
+    let (status, body) = get(&client, "/api/v1/query?query=cpu&time=yesterday").await;
+    assert_eq!(400, status);
+    assert_eq!("bad_data", body["errorType"]);
+
+    assert_eq!(1, received(&mut rx).len());
+}
+
+#[tokio::test]
+async fn test_prom_query_timeout() {
+    let (tx, mut rx) = mpsc::channel(100);
+    let client = TestClient::new(make_test_app(tx));
+
+    for url in [
+        "/api/v1/query?query=slow&time=5&timeout=10ms",
+        "/api/v1/query_range?query=slow&start=0&end=5&step=5&timeout=0.01",
+    ] {
+        let (status, body) = get(&client, url).await;
+        assert_eq!(503, status);
+        assert_eq!("timeout", body["errorType"]);
+    }
+    assert_eq!(2, received(&mut rx).len());
+
+    let (status, body) = get(&client, "/api/v1/query?query=cpu&time=5&timeout=1m").await;
+    assert_eq!(200, status);
+    assert_eq!("success", body["status"]);
+
+    let (status, body) = get(&client, "/api/v1/query?query=cpu&time=5&timeout=soon").await;
+    assert_eq!(400, status);
+    assert_eq!("bad_data", body["errorType"]);
+
+    // The parameters in the URL of a POST request without a form are also accepted.
+    let res = client
+        .post("/api/v1/query_range?query=cpu&start=0&end=5&step=5&timeout=1m")
+        .send()
+        .await;
+    assert_eq!(200, res.status().as_u16());
+    let body: Value = serde_json::from_str(&res.text().await).unwrap();
+    assert_eq!("matrix", body["data"]["resultType"]);
+}
+
+#[tokio::test]
+async fn test_prom_metadata_api() {
+    let (tx, mut rx) = mpsc::channel(100);
+    let client = TestClient::new(make_test_app(tx));
+
+    let (status, body) = get(
+        &client,
+        "/api/v1/series?match%5B%5D=cpu%7Bhost%3D~%22a%7Cb%22%7D&start=10&end=20",
+    )
+    .await;
+    assert_eq!(200, status);
+    assert_eq!(
+        json!({
+            "status": "success",
+            "data": [
+                {"__name__": "cpu", "host": "a"},
+                {"__name__": "cpu", "host": "b"},
+            ],
+        }),
+        body
+    );
+    assert_eq!(
+        vec![PromQuery {
+            query: r#"last_over_time({host=~"a|b", __name__="cpu"}[10001ms])"#.to_string(),
+            start: "20".to_string(),
+            end: "20".to_string(),
+            step: "1s".to_string(),
+        }],
+        received(&mut rx)
+    );
+
+    // Series are looked for in the 5 minutes before the end without the start.
+    let (status, _) = get(&client, "/api/v1/series?match%5B%5D=cpu&end=400").await;
+    assert_eq!(200, status);
+    let queries = received(&mut rx);
+    assert_eq!(1, queries.len());
+    assert_eq!(
+        r#"last_over_time({__name__="cpu"}[300001ms])"#,
+        queries[0].query
+    );
+
+    let (status, body) = get(&client, "/api/v1/series").await;
+    assert_eq!(400, status);
+    assert_eq!("bad_data", body["errorType"]);
+
+    // Label names come from the metrics without querying.
+    let (status, body) = get(&client, "/api/v1/labels?start=0&end=20").await;
+    assert_eq!(200, status);
+    assert_eq!(
+        json!({"status": "success", "data": ["__name__", "host"]}),
+        body
+    );
+    let (status, body) = get(&client, "/api/v1/labels?match%5B%5D=disk").await;
+    assert_eq!(200, status);
+    assert_eq!(json!({"status": "success", "data": []}), body);
+    assert!(received(&mut rx).is_empty());
+
+    let (status, body) = get(&client, "/api/v1/label/__name__/values").await;
+    assert_eq!(200, status);
+    assert_eq!(json!({"status": "success", "data": ["cpu", "mem"]}), body);
+    assert!(received(&mut rx).is_empty());
+
+    // Metrics and labels are looked up in the database of the URL.
+    for url in [
+        "/api/v1/labels?db=empty",
+        "/api/v1/label/__name__/values?db=empty",
+        "/api/v1/label/host/values?db=greptime-empty&match%5B%5D=cpu",
+    ] {
+        let (status, body) = get(&client, url).await;
+        assert_eq!(200, status);
+        assert_eq!(json!({"status": "success", "data": []}), body);
+    }
+
+    // Metric names are matched against the regex.
+    let (status, body) = get(
+        &client,
+        "/api/v1/label/host/values?match%5B%5D=%7B__name__%3D~%22c.%2A%22%7D&end=20",
+    )
+    .await;
+    assert_eq!(200, status);
+    assert_eq!(json!({"status": "success", "data": ["a", "b"]}), body);
+
+    let (status, body) = get(&client, "/api/v1/label/job/values?match%5B%5D=cpu&end=20").await;
+    assert_eq!(200, status);
+    assert_eq!(json!({"status": "success", "data": []}), body);
+
+    let (status, body) = get(
+        &client,
+        "/api/v1/label/__name__/values?match%5B%5D=%7Bhost%3D%22a%22%7D",
+    )
+    .await;
+    assert_eq!(200, status);
+    assert_eq!(json!({"status": "success", "data": ["cpu", "mem"]}), body);
+
+    // The series without the label only match the matchers matching the empty string.
+    for (selector, expected) in [
+        (r#"cpu{job="x"}"#, json!([])),
+        (r#"cpu{job=""}"#, json!(["a", "b"])),
+    ] {
+        let res = client
+            .post("/api/v1/label/host/values")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(format!(
+                "match[]={}",
+                selector
+                    .replace('{', "%7B")
+                    .replace('}', "%7D")
+                    .replace('"', "%22")
+                    .replace('=', "%3D")
+            ))
+            .send()
+            .await;
+        assert_eq!(200, res.status().as_u16());
+        let body: Value = serde_json::from_str(&res.text().await).unwrap();
+        assert_eq!(json!({"status": "success", "data": expected}), body);
+    }
+    assert!(received(&mut rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_prom_api_in_http_server() {
+    let (tx, _rx) = mpsc::channel(100);
+    let server = HttpServerBuilder::new(HttpOptions::default())
+        .with_prom_query_handler(Arc::new(DummyInstance { tx }))
+        .build();
+    let client = TestClient::new(server.make_app());
+
+    let (status, body) = get(&client, "/v1/prometheus/api/v1/label/__name__/values").await;
+    assert_eq!(200, status);
+    assert_eq!(json!({"status": "success", "data": ["cpu", "mem"]}), body);
+}
@@ -31,7 +31,9 @@ use servers::prometheus;
 use servers::prometheus::{snappy_compress, Metrics};
 use servers::query_handler::grpc::GrpcQueryHandler;
 use servers::query_handler::sql::SqlQueryHandler;
-use servers::query_handler::{PrometheusProtocolHandler, PrometheusResponse};
+use servers::query_handler::{
+    PrometheusProtocolHandler, PrometheusResponse, PrometheusResponseBody,
+};
 use session::context::QueryContextRef;
 use tokio::sync::mpsc;
 
@@ -77,7 +79,7 @@ impl PrometheusProtocolHandler for DummyInstance {
         Ok(PrometheusResponse {
             content_type: "application/x-protobuf".to_string(),
             content_encoding: "snappy".to_string(),
-            body: response.encode_to_vec(),
+            body: PrometheusResponseBody::Full(response.encode_to_vec()),
         })
     }
 
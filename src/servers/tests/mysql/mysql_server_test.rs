@@ -65,6 +65,7 @@ fn create_mysql_server(table: MemTable, opts: MysqlOpts<'_>) -> Result<Box<dyn S
             opts.tls.should_force_tls(),
             opts.tls.setup()?.map(Arc::new),
             opts.reject_no_database,
+            false,
         )),
     ))
 }
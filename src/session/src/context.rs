@@ -14,7 +14,9 @@
 
 use std::fmt::{Display, Formatter};
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use arc_swap::ArcSwap;
 use common_catalog::consts::{DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
@@ -23,10 +25,20 @@ use common_telemetry::debug;
 pub type QueryContextRef = Arc<QueryContext>;
 pub type ConnInfoRef = Arc<ConnInfo>;
 
+/// The id generator for connections, shared by all protocols in this server process.
+static NEXT_CONN_ID: AtomicU32 = AtomicU32::new(1);
+
 #[derive(Debug)]
 pub struct QueryContext {
     current_catalog: ArcSwap<String>,
     current_schema: ArcSwap<String>,
+    current_user: ArcSwap<UserInfo>,
+    /// Identifies the connection this context belongs to. Queries are listed and killed by it.
+    conn_id: u32,
+    conn_info: Option<ConnInfoRef>,
+    /// Limits of the statements in this context, initialized from the server defaults
+    /// by the first statement if not set explicitly.
+    query_limits: RwLock<Option<QueryLimits>>,
 }
 
 impl Default for QueryContext {
@@ -52,19 +64,58 @@ impl QueryContext {
     }
 
     pub fn new() -> Self {
-        Self {
-            current_catalog: ArcSwap::new(Arc::new(DEFAULT_CATALOG_NAME.to_string())),
-            current_schema: ArcSwap::new(Arc::new(DEFAULT_SCHEMA_NAME.to_string())),
-        }
+        Self::with(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME)
     }
 
     pub fn with(catalog: &str, schema: &str) -> Self {
         Self {
             current_catalog: ArcSwap::new(Arc::new(catalog.to_string())),
             current_schema: ArcSwap::new(Arc::new(schema.to_string())),
+            current_user: ArcSwap::new(Arc::new(UserInfo::default())),
+            conn_id: NEXT_CONN_ID.fetch_add(1, Ordering::Relaxed),
+            conn_info: None,
+            query_limits: RwLock::new(None),
+        }
+    }
+
+    /// Creates a context for a client connection, which lives as long as the connection.
+    pub fn with_conn_info(conn_info: ConnInfoRef) -> Self {
+        Self {
+            conn_info: Some(conn_info),
+            ..Self::new()
         }
     }
 
+    pub fn conn_id(&self) -> u32 {
+        self.conn_id
+    }
+
+    pub fn conn_info(&self) -> Option<ConnInfoRef> {
+        self.conn_info.clone()
+    }
+
+    pub fn current_user(&self) -> Arc<UserInfo> {
+        self.current_user.load().clone()
+    }
+
+    pub fn set_current_user(&self, user_info: UserInfo) {
+        self.current_user.store(Arc::new(user_info));
+    }
+
+    /// Returns the limits of the statements in this context, unlimited if not set.
+    pub fn query_limits(&self) -> QueryLimits {
+        self.query_limits.read().unwrap().unwrap_or_default()
+    }
+
+    pub fn set_query_limits(&self, query_limits: QueryLimits) {
+        *self.query_limits.write().unwrap() = Some(query_limits);
+    }
+
+    /// Sets the limits to `defaults` if they are not set yet, and returns the limits in effect.
+    pub fn init_query_limits(&self, defaults: QueryLimits) -> QueryLimits {
+        *self.query_limits.write().unwrap().get_or_insert(defaults)
+    }
+
     pub fn current_schema(&self) -> String {
         self.current_schema.load().as_ref().clone()
     }
@@ -90,18 +141,32 @@ impl QueryContext {
     }
 }
 
+/// Resource limits of a statement. `None` means unlimited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// Statements running longer than this are cancelled.
+    pub statement_timeout: Option<Duration>,
+    /// Max bytes of memory the query engine can use to execute a statement. In distributed mode,
+    /// it only limits the operators executed in the frontend, but not the ones executed in the
+    /// datanodes, like the scans and the pushed down aggregations.
+    pub memory_limit: Option<usize>,
+}
+
 pub const DEFAULT_USERNAME: &str = "greptime";
 
 #[derive(Clone, Debug)]
 pub struct UserInfo {
     username: String,
+    /// Whether the user can see and kill the statements of other users.
+    is_admin: bool,
 }
 
 impl Default for UserInfo {
     fn default() -> Self {
-        Self {
-            username: DEFAULT_USERNAME.to_string(),
-        }
+        // Without authentication, all connections act as this user, so they can access the
+        // statements of each other. With authentication, the connections are given the
+        // authenticated users, and the ones that are not can't access the others' statements.
+        Self::new(DEFAULT_USERNAME)
     }
 }
 
@@ -110,13 +175,26 @@ impl UserInfo {
         self.username.as_str()
     }
 
+    pub fn is_admin(&self) -> bool {
+        self.is_admin
+    }
+
     pub fn new(username: impl Into<String>) -> Self {
         Self {
             username: username.into(),
+            is_admin: false,
+        }
+    }
+
+    pub fn new_admin(username: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            is_admin: true,
         }
     }
 }
 
+#[derive(Debug)]
 pub struct ConnInfo {
     pub client_host: SocketAddr,
     pub channel: Channel,
@@ -131,7 +209,7 @@ impl ConnInfo {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Channel {
     Grpc,
     Http,
@@ -142,9 +220,26 @@ pub enum Channel {
     Prometheus,
 }
 
+impl Display for Channel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Channel::Grpc => "grpc",
+            Channel::Http => "http",
+            Channel::Mysql => "mysql",
+            Channel::Postgres => "postgres",
+            Channel::Opentsdb => "opentsdb",
+            Channel::Influxdb => "influxdb",
+            Channel::Prometheus => "prometheus",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::context::{Channel, UserInfo};
+    use std::time::Duration;
+
+    use crate::context::{Channel, QueryContext, QueryLimits, UserInfo};
     use crate::Session;
 
     #[test]
@@ -162,5 +257,36 @@ mod test {
             "127.0.0.1"
         );
         assert_eq!(session.conn_info().client_host.port(), 9000);
+
+        // test query context shares the connection
+        let query_ctx = session.context();
+        assert_eq!(query_ctx.current_user().username(), "root");
+        assert_eq!(
+            query_ctx.conn_info().unwrap().client_host,
+            session.conn_info().client_host
+        );
+        assert_ne!(query_ctx.conn_id(), QueryContext::new().conn_id());
+    }
+
+    #[test]
+    fn test_query_limits() {
+        let defaults = QueryLimits {
+            statement_timeout: Some(Duration::from_secs(30)),
+            memory_limit: Some(1024),
+        };
+
+        let query_ctx = QueryContext::new();
+        assert_eq!(query_ctx.query_limits(), QueryLimits::default());
+        assert_eq!(query_ctx.init_query_limits(defaults), defaults);
+
+        // Limits set explicitly are not overwritten by defaults.
+        let query_ctx = QueryContext::new();
+        let limits = QueryLimits {
+            statement_timeout: Some(Duration::from_secs(1)),
+            memory_limit: None,
+        };
+        query_ctx.set_query_limits(limits);
+        assert_eq!(query_ctx.init_query_limits(defaults), limits);
+        assert_eq!(query_ctx.query_limits(), limits);
     }
 }
@@ -0,0 +1,301 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The list of statements running in this server process. It backs `SHOW PROCESSLIST`,
+//! the `information_schema.processlist` table and `KILL QUERY`.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::task::AtomicWaker;
+use once_cell::sync::Lazy;
+
+use crate::context::{Channel, QueryContextRef, UserInfo};
+
+pub type ProcessManagerRef = Arc<ProcessManager>;
+pub type ProcessRef = Arc<Process>;
+
+static GLOBAL_PROCESS_MANAGER: Lazy<ProcessManagerRef> =
+    Lazy::new(|| Arc::new(ProcessManager::default()));
+
+/// Keeps track of the running statements, keyed by the id of the connection that issues them.
+#[derive(Default)]
+pub struct ProcessManager {
+    processes: RwLock<HashMap<u32, ProcessRef>>,
+}
+
+impl ProcessManager {
+    /// Returns the process manager shared by all protocol servers of this process.
+    pub fn global() -> ProcessManagerRef {
+        GLOBAL_PROCESS_MANAGER.clone()
+    }
+
+    /// Registers a statement that starts running on the connection of `query_ctx`. The statement
+    /// is removed from the list when the returned guard is dropped.
+    pub fn register(self: &Arc<Self>, query: &str, query_ctx: &QueryContextRef) -> ProcessGuard {
+        let conn_info = query_ctx.conn_info();
+        let process = Arc::new(Process {
+            id: query_ctx.conn_id(),
+            user: query_ctx.current_user().username().to_string(),
+            client: conn_info.as_ref().map(|x| x.client_host),
+            channel: conn_info.as_ref().map(|x| x.channel),
+            catalog: query_ctx.current_catalog(),
+            schema: query_ctx.current_schema(),
+            query: query.to_string(),
+            start_time: SystemTime::now(),
+            cancelled: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+
+        let _ = self
+            .processes
+            .write()
+            .unwrap()
+            .insert(process.id, process.clone());
+
+        ProcessGuard {
+            manager: self.clone(),
+            process,
+        }
+    }
+
+    /// Returns all running statements ordered by their ids.
+    pub fn processes(&self) -> Vec<ProcessRef> {
+        let mut processes = self
+            .processes
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        processes.sort_by_key(|x| x.id);
+        processes
+    }
+
+    /// Returns the statements that `user` can access, ordered by their ids.
+    pub fn processes_of(&self, user: &UserInfo) -> Vec<ProcessRef> {
+        let mut processes = self.processes();
+        processes.retain(|x| x.is_accessible_by(user));
+        processes
+    }
+
+    /// Returns the statement running on connection `id`.
+    pub fn get(&self, id: u32) -> Option<ProcessRef> {
+        self.processes.read().unwrap().get(&id).cloned()
+    }
+
+    /// Cancels the statement running on connection `id`. Returns `false` if there is none.
+    pub fn kill(&self, id: u32) -> bool {
+        match self.get(id) {
+            Some(process) => {
+                process.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn deregister(&self, process: &ProcessRef) {
+        let mut processes = self.processes.write().unwrap();
+        // The connection may have started another statement since, which must be kept.
+        if matches!(processes.get(&process.id), Some(x) if Arc::ptr_eq(x, process)) {
+            let _ = processes.remove(&process.id);
+        }
+    }
+}
+
+/// A running statement.
+#[derive(Debug)]
+pub struct Process {
+    id: u32,
+    user: String,
+    client: Option<SocketAddr>,
+    channel: Option<Channel>,
+    catalog: String,
+    schema: String,
+    query: String,
+    start_time: SystemTime,
+    cancelled: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Process {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn client(&self) -> Option<SocketAddr> {
+        self.client
+    }
+
+    pub fn channel(&self) -> Option<Channel> {
+        self.channel
+    }
+
+    pub fn catalog(&self) -> &str {
+        &self.catalog
+    }
+
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Milliseconds since UNIX epoch when the statement started.
+    pub fn start_timestamp_millis(&self) -> i64 {
+        self.start_time
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_millis() as i64)
+            .unwrap_or_default()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.elapsed().unwrap_or_default()
+    }
+
+    /// Administrators can access all statements, other users only their own.
+    pub fn is_accessible_by(&self, user: &UserInfo) -> bool {
+        user.is_admin() || user.username() == self.user
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+        self.waker.wake();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Polls whether the statement is cancelled. The waker of `cx` is notified on cancellation
+    /// if it's not cancelled yet.
+    pub fn poll_cancelled(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_cancelled() {
+            return Poll::Ready(());
+        }
+        self.waker.register(cx.waker());
+        // Check again in case the statement is cancelled before the waker is registered.
+        if self.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Removes the statement from the process list when dropped.
+pub struct ProcessGuard {
+    manager: ProcessManagerRef,
+    process: ProcessRef,
+}
+
+impl ProcessGuard {
+    pub fn process(&self) -> &ProcessRef {
+        &self.process
+    }
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        self.manager.deregister(&self.process);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ConnInfo, QueryContext, UserInfo};
+
+    #[test]
+    fn test_register_and_kill() {
+        let manager = Arc::new(ProcessManager::default());
+
+        let conn_info = Arc::new(ConnInfo::new(
+            "127.0.0.1:4002".parse().unwrap(),
+            Channel::Mysql,
+        ));
+        let query_ctx = Arc::new(QueryContext::with_conn_info(conn_info));
+        query_ctx.set_current_user(UserInfo::new("root"));
+
+        let guard = manager.register("SELECT 1", &query_ctx);
+        let processes = manager.processes();
+        assert_eq!(processes.len(), 1);
+        let process = &processes[0];
+        assert_eq!(process.id(), query_ctx.conn_id());
+        assert_eq!(process.user(), "root");
+        assert_eq!(process.channel(), Some(Channel::Mysql));
+        assert_eq!(process.client().unwrap().port(), 4002);
+        assert_eq!(process.query(), "SELECT 1");
+        assert!(!process.is_cancelled());
+
+        assert!(!manager.kill(query_ctx.conn_id() + 1));
+        assert!(manager.kill(query_ctx.conn_id()));
+        assert!(guard.process().is_cancelled());
+
+        drop(guard);
+        assert!(manager.processes().is_empty());
+    }
+
+    #[test]
+    fn test_processes_of_user() {
+        let manager = Arc::new(ProcessManager::default());
+        let new_query_ctx = |user_info| {
+            let query_ctx = QueryContext::arc();
+            query_ctx.set_current_user(user_info);
+            query_ctx
+        };
+        let alice = new_query_ctx(UserInfo::new("alice"));
+        let bob = new_query_ctx(UserInfo::new("bob"));
+
+        let _alice_guard = manager.register("SELECT 1", &alice);
+        let _bob_guard = manager.register("SELECT 2", &bob);
+
+        let processes = manager.processes_of(&alice.current_user());
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].id(), alice.conn_id());
+        assert!(!processes[0].is_accessible_by(&bob.current_user()));
+
+        let admin = UserInfo::new_admin("root");
+        assert_eq!(manager.processes_of(&admin).len(), 2);
+        assert!(manager.get(bob.conn_id()).unwrap().is_accessible_by(&admin));
+    }
+
+    #[test]
+    fn test_deregister_keeps_newer_statement() {
+        let manager = Arc::new(ProcessManager::default());
+        let query_ctx = QueryContext::arc();
+
+        let first = manager.register("SELECT 1", &query_ctx);
+        let second = manager.register("SELECT 2", &query_ctx);
+        drop(first);
+
+        let processes = manager.processes();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].query(), "SELECT 2");
+
+        drop(second);
+        assert!(manager.processes().is_empty());
+    }
+}
@@ -19,11 +19,13 @@ use sqlparser::parser::{Parser, ParserError};
 use sqlparser::tokenizer::{Token, TokenWithLocation};
 
 use crate::error::{self, InvalidDatabaseNameSnafu, InvalidTableNameSnafu, Result, SyntaxSnafu};
-use crate::parsers::tql_parser;
+use crate::parsers::{kill_parser, load_data_parser, tql_parser};
 use crate::statements::describe::DescribeTable;
-use crate::statements::drop::DropTable;
+use crate::statements::drop::{DropMaterializedView, DropTable, DropView};
 use crate::statements::explain::Explain;
-use crate::statements::show::{ShowCreateTable, ShowDatabases, ShowKind, ShowTables};
+use crate::statements::show::{
+    ShowCreateTable, ShowCreateView, ShowDatabases, ShowKind, ShowProcesslist, ShowTables,
+};
 use crate::statements::statement::Statement;
 
 /// GrepTime SQL parser context, a simple wrapper for Datafusion SQL parser.
@@ -115,12 +117,24 @@ impl<'a> ParserContext<'a> {
 
                     Keyword::COPY => self.parse_copy(),
 
+                    Keyword::SET => self.parse_set_variables(),
+
                     Keyword::NoKeyword
                         if w.value.to_uppercase() == tql_parser::TQL && w.quote_style.is_none() =>
                     {
                         self.parse_tql()
                     }
 
+                    _ if w.value.to_uppercase() == kill_parser::KILL && w.quote_style.is_none() => {
+                        self.parse_kill()
+                    }
+
+                    _ if w.value.to_uppercase() == load_data_parser::LOAD
+                        && w.quote_style.is_none() =>
+                    {
+                        self.parse_load_data()
+                    }
+
                     // todo(hl) support more statements.
                     _ => self.unsupported(self.peek_token_as_string()),
                 }
@@ -150,6 +164,16 @@ impl<'a> ParserContext<'a> {
         } else if self.consume_token("CREATE") {
             if self.consume_token("TABLE") {
                 self.parse_show_create_table()
+            } else if self.consume_token("VIEW") {
+                self.parse_show_create_view()
+            } else {
+                self.unsupported(self.peek_token_as_string())
+            }
+        } else if self.consume_token("PROCESSLIST") {
+            Ok(Statement::ShowProcesslist(ShowProcesslist { full: false }))
+        } else if self.consume_token("FULL") {
+            if self.consume_token("PROCESSLIST") {
+                Ok(Statement::ShowProcesslist(ShowProcesslist { full: true }))
             } else {
                 self.unsupported(self.peek_token_as_string())
             }
@@ -179,6 +203,25 @@ impl<'a> ParserContext<'a> {
         }))
     }
 
+    /// Parse SHOW CREATE VIEW statement
+    fn parse_show_create_view(&mut self) -> Result<Statement> {
+        let view_name =
+            self.parser
+                .parse_object_name()
+                .with_context(|_| error::UnexpectedSnafu {
+                    sql: self.sql,
+                    expected: "a view name",
+                    actual: self.peek_token_as_string(),
+                })?;
+        ensure!(
+            !view_name.0.is_empty(),
+            InvalidTableNameSnafu {
+                name: view_name.to_string(),
+            }
+        );
+        Ok(Statement::ShowCreateView(ShowCreateView { view_name }))
+    }
+
     fn parse_show_tables(&mut self) -> Result<Statement> {
         let database = match self.parser.peek_token().token {
             Token::EOF | Token::SemiColon => {
@@ -290,6 +333,17 @@ impl<'a> ParserContext<'a> {
 
     fn parse_drop(&mut self) -> Result<Statement> {
         self.parser.next_token();
+        if self.matches_keyword(Keyword::VIEW) {
+            self.parser.next_token();
+            return self.parse_drop_view();
+        }
+        if self.matches_keyword(Keyword::MATERIALIZED) {
+            self.parser.next_token();
+            self.parser
+                .expect_keyword(Keyword::VIEW)
+                .context(SyntaxSnafu { sql: self.sql })?;
+            return self.parse_drop_materialized_view();
+        }
         if !self.matches_keyword(Keyword::TABLE) {
             return self.unsupported(self.peek_token_as_string());
         }
@@ -313,6 +367,50 @@ impl<'a> ParserContext<'a> {
         Ok(Statement::DropTable(DropTable::new(table_ident)))
     }
 
+    /// Parses `DROP VIEW [IF EXISTS] <view_name>` after `DROP VIEW`.
+    fn parse_drop_view(&mut self) -> Result<Statement> {
+        let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let view_ident =
+            self.parser
+                .parse_object_name()
+                .with_context(|_| error::UnexpectedSnafu {
+                    sql: self.sql,
+                    expected: "a view name",
+                    actual: self.peek_token_as_string(),
+                })?;
+        ensure!(
+            !view_ident.0.is_empty(),
+            InvalidTableNameSnafu {
+                name: view_ident.to_string()
+            }
+        );
+
+        Ok(Statement::DropView(DropView::new(view_ident, if_exists)))
+    }
+
+    /// Parses `DROP MATERIALIZED VIEW [IF EXISTS] <view_name>` after `DROP MATERIALIZED VIEW`.
+    fn parse_drop_materialized_view(&mut self) -> Result<Statement> {
+        let if_exists = self.parser.parse_keywords(&[Keyword::IF, Keyword::EXISTS]);
+        let view_ident =
+            self.parser
+                .parse_object_name()
+                .with_context(|_| error::UnexpectedSnafu {
+                    sql: self.sql,
+                    expected: "a view name",
+                    actual: self.peek_token_as_string(),
+                })?;
+        ensure!(
+            !view_ident.0.is_empty(),
+            InvalidTableNameSnafu {
+                name: view_ident.to_string()
+            }
+        );
+
+        Ok(Statement::DropMaterializedView(DropMaterializedView::new(
+            view_ident, if_exists,
+        )))
+    }
+
     // Report unexpected token
     pub(crate) fn expected<T>(&self, expected: &str, found: TokenWithLocation) -> Result<T> {
         Err(ParserError::ParserError(format!(
@@ -337,6 +435,14 @@ impl<'a> ParserContext<'a> {
         }
     }
 
+    pub(crate) fn expect_token(&mut self, expected: &str) -> Result<()> {
+        if self.consume_token(expected) {
+            Ok(())
+        } else {
+            self.expected(expected, self.parser.peek_token())
+        }
+    }
+
     #[inline]
     pub(crate) fn peek_token_as_string(&self) -> String {
         self.parser.peek_token().to_string()
@@ -610,6 +716,45 @@ mod tests {
         )
     }
 
+    #[test]
+    pub fn test_drop_view() {
+        let sql = "DROP VIEW foo";
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(
+            stmts.pop().unwrap(),
+            Statement::DropView(DropView::new(ObjectName(vec![Ident::new("foo")]), false))
+        );
+
+        let sql = "DROP VIEW IF EXISTS my_schema.foo";
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(
+            stmts.pop().unwrap(),
+            Statement::DropView(DropView::new(
+                ObjectName(vec![Ident::new("my_schema"), Ident::new("foo")]),
+                true
+            ))
+        );
+
+        let sql = "DROP VIEW";
+        assert!(ParserContext::create_with_dialect(sql, &GenericDialect {}).is_err());
+    }
+
+    #[test]
+    pub fn test_drop_materialized_view() {
+        let sql = "DROP MATERIALIZED VIEW IF EXISTS my_schema.foo";
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(
+            stmts.pop().unwrap(),
+            Statement::DropMaterializedView(DropMaterializedView::new(
+                ObjectName(vec![Ident::new("my_schema"), Ident::new("foo")]),
+                true
+            ))
+        );
+
+        let sql = "DROP MATERIALIZED foo";
+        assert!(ParserContext::create_with_dialect(sql, &GenericDialect {}).is_err());
+    }
+
     fn test_timestamp_precision(sql: &str, expected_type: ConcreteDataType) {
         match ParserContext::create_with_dialect(sql, &GenericDialect {})
             .unwrap()
@@ -17,5 +17,8 @@ pub(crate) mod copy_parser;
 pub(crate) mod create_parser;
 pub(crate) mod delete_parser;
 pub(crate) mod insert_parser;
+pub(crate) mod kill_parser;
+pub(crate) mod load_data_parser;
 pub(crate) mod query_parser;
+pub(crate) mod set_var_parser;
 pub(crate) mod tql_parser;
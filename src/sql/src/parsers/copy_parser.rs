@@ -12,25 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use snafu::ResultExt;
+use snafu::{ensure, ResultExt};
 use sqlparser::ast::ObjectName;
 use sqlparser::keywords::Keyword;
+use sqlparser::parser::IsOptional::Optional;
+use sqlparser::tokenizer::Token;
 
-use crate::error::{self, Result};
+use crate::error::{self, InvalidSqlSnafu, Result};
 use crate::parser::ParserContext;
-use crate::statements::copy::{CopyTable, CopyTableArgument, Format};
+use crate::statements::copy::{
+    CopyStdio, CopyStdioFormat, CopyStdioOptions, CopyStdioSource, CopyTable, CopyTableArgument,
+    Format,
+};
 use crate::statements::statement::Statement;
 use crate::util::parse_option_string;
 
 // COPY tbl TO 'output.parquet';
+// COPY tbl [(col, ...)] {FROM STDIN | TO STDOUT} [WITH (...)];
+// COPY (query) TO STDOUT [WITH (...)];
 impl<'a> ParserContext<'a> {
     pub(crate) fn parse_copy(&mut self) -> Result<Statement> {
         self.parser.next_token();
-        let copy_table = self.parse_copy_table()?;
-        Ok(Statement::Copy(copy_table))
-    }
+        if self.parser.consume_token(&Token::LParen) {
+            return self.parse_copy_query_to_stdout();
+        }
 
-    fn parse_copy_table(&mut self) -> Result<CopyTable> {
         let table_name =
             self.parser
                 .parse_object_name()
@@ -39,17 +45,175 @@ impl<'a> ParserContext<'a> {
                     expected: "a table name",
                     actual: self.peek_token_as_string(),
                 })?;
+        let columns = self
+            .parser
+            .parse_parenthesized_column_list(Optional, false)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
 
         if self.parser.parse_keyword(Keyword::TO) {
-            Ok(CopyTable::To(self.parse_copy_table_to(table_name)?))
+            if self.consume_token("STDOUT") {
+                let options = self.parse_copy_stdio_options()?;
+                return Ok(Statement::CopyStdio(CopyStdio::To {
+                    source: CopyStdioSource::Table {
+                        table_name,
+                        columns,
+                    },
+                    options,
+                }));
+            }
+            if !columns.is_empty() {
+                return self.expected("STDOUT", self.parser.peek_token());
+            }
+            Ok(Statement::Copy(CopyTable::To(
+                self.parse_copy_table_to(table_name)?,
+            )))
         } else {
             self.parser
                 .expect_keyword(Keyword::FROM)
                 .context(error::SyntaxSnafu { sql: self.sql })?;
-            Ok(CopyTable::From(self.parse_copy_table_from(table_name)?))
+            if self.consume_token("STDIN") {
+                let options = self.parse_copy_stdio_options()?;
+                return Ok(Statement::CopyStdio(CopyStdio::From {
+                    table_name,
+                    columns,
+                    options,
+                }));
+            }
+            if !columns.is_empty() {
+                return self.expected("STDIN", self.parser.peek_token());
+            }
+            Ok(Statement::Copy(CopyTable::From(
+                self.parse_copy_table_from(table_name)?,
+            )))
         }
     }
 
+    /// Parses `COPY (query) TO STDOUT`, of which the left parenthesis is consumed.
+    fn parse_copy_query_to_stdout(&mut self) -> Result<Statement> {
+        let query = self
+            .parser
+            .parse_query()
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+        self.parser
+            .expect_token(&Token::RParen)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+        self.parser
+            .expect_keyword(Keyword::TO)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+        self.expect_token("STDOUT")?;
+        let options = self.parse_copy_stdio_options()?;
+        Ok(Statement::CopyStdio(CopyStdio::To {
+            source: CopyStdioSource::Query(Box::new(query)),
+            options,
+        }))
+    }
+
+    /// Parses both the options in parentheses, like `WITH (FORMAT csv, HEADER)`, and the
+    /// legacy ones, like `WITH CSV HEADER`.
+    fn parse_copy_stdio_options(&mut self) -> Result<CopyStdioOptions> {
+        let _ = self.parser.parse_keyword(Keyword::WITH);
+        let mut format = None;
+        let mut delimiter = None;
+        let mut null = None;
+        let mut header = false;
+
+        if self.parser.consume_token(&Token::LParen) {
+            loop {
+                let name = self
+                    .parser
+                    .parse_identifier()
+                    .context(error::SyntaxSnafu { sql: self.sql })?
+                    .value
+                    .to_ascii_uppercase();
+                match name.as_str() {
+                    "FORMAT" => {
+                        let value = self.parse_copy_option_value()?;
+                        format = Some(parse_copy_stdio_format(&value)?);
+                    }
+                    "HEADER" => {
+                        header = if matches!(
+                            self.parser.peek_token().token,
+                            Token::Comma | Token::RParen
+                        ) {
+                            true
+                        } else {
+                            parse_copy_option_bool(&self.parse_copy_option_value()?)?
+                        };
+                    }
+                    "DELIMITER" => delimiter = Some(self.parse_copy_delimiter()?),
+                    "NULL" => null = Some(self.parse_copy_option_value()?),
+                    _ => {
+                        return InvalidSqlSnafu {
+                            msg: format!("Unsupported COPY option: {name}"),
+                        }
+                        .fail()
+                    }
+                }
+                if self.parser.consume_token(&Token::RParen) {
+                    break;
+                }
+                self.parser
+                    .expect_token(&Token::Comma)
+                    .context(error::SyntaxSnafu { sql: self.sql })?;
+            }
+        } else {
+            loop {
+                if self.consume_token("BINARY") {
+                    format = Some(CopyStdioFormat::Binary);
+                } else if self.consume_token("CSV") {
+                    format = Some(CopyStdioFormat::Csv);
+                } else if self.consume_token("HEADER") {
+                    header = true;
+                } else if self.consume_token("DELIMITER") {
+                    let _ = self.parser.parse_keyword(Keyword::AS);
+                    delimiter = Some(self.parse_copy_delimiter()?);
+                } else if self.consume_token("NULL") {
+                    let _ = self.parser.parse_keyword(Keyword::AS);
+                    null = Some(self.parse_copy_option_value()?);
+                } else {
+                    break;
+                }
+            }
+        }
+
+        let mut options = CopyStdioOptions::new(format.unwrap_or(CopyStdioFormat::Text));
+        ensure!(
+            !header || options.format == CopyStdioFormat::Csv,
+            InvalidSqlSnafu {
+                msg: "COPY HEADER available only in CSV mode",
+            }
+        );
+        options.header = header;
+        if let Some(delimiter) = delimiter {
+            options.delimiter = delimiter;
+        }
+        if let Some(null) = null {
+            options.null = null;
+        }
+        Ok(options)
+    }
+
+    fn parse_copy_option_value(&mut self) -> Result<String> {
+        let token = self.parser.next_token();
+        match &token.token {
+            Token::Word(word) => Ok(word.value.clone()),
+            Token::SingleQuotedString(s) | Token::EscapedStringLiteral(s) => Ok(s.clone()),
+            Token::Number(n, _) => Ok(n.clone()),
+            _ => self.expected("a value of the option", token),
+        }
+    }
+
+    fn parse_copy_delimiter(&mut self) -> Result<u8> {
+        let value = self.parse_copy_option_value()?;
+        ensure!(
+            value.len() == 1,
+            InvalidSqlSnafu {
+                msg: "COPY delimiter must be a single one-byte character",
+            }
+        );
+        Ok(value.as_bytes()[0])
+    }
+
     fn parse_copy_table_from(&mut self, table_name: ObjectName) -> Result<CopyTableArgument> {
         let location =
             self.parser
@@ -160,6 +324,29 @@ impl<'a> ParserContext<'a> {
     }
 }
 
+fn parse_copy_stdio_format(name: &str) -> Result<CopyStdioFormat> {
+    match name.to_ascii_lowercase().as_str() {
+        "text" => Ok(CopyStdioFormat::Text),
+        "csv" => Ok(CopyStdioFormat::Csv),
+        "binary" => Ok(CopyStdioFormat::Binary),
+        _ => InvalidSqlSnafu {
+            msg: format!("COPY format \"{name}\" not recognized"),
+        }
+        .fail(),
+    }
+}
+
+fn parse_copy_option_bool(value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "on" | "1" => Ok(true),
+        "false" | "off" | "0" => Ok(false),
+        _ => InvalidSqlSnafu {
+            msg: format!("Invalid boolean value: {value}"),
+        }
+        .fail(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_matches::assert_matches;
@@ -348,4 +535,83 @@ mod tests {
             );
         }
     }
+
+    fn parse_copy_stdio(sql: &str) -> Result<CopyStdio> {
+        let mut result = ParserContext::create_with_dialect(sql, &GenericDialect {})?;
+        assert_eq!(1, result.len());
+        match result.remove(0) {
+            Statement::CopyStdio(copy) => Ok(copy),
+            other => unreachable!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_copy_from_stdin() {
+        let copy = parse_copy_stdio("COPY demo (host, \"cpu\") FROM STDIN").unwrap();
+        let CopyStdio::From {
+            table_name,
+            columns,
+            options,
+        } = copy
+        else {
+            unreachable!()
+        };
+        assert_eq!("demo", table_name.to_string());
+        assert_eq!(
+            vec!["host", "\"cpu\""],
+            columns.iter().map(|c| c.to_string()).collect::<Vec<_>>()
+        );
+        assert_eq!(CopyStdioOptions::new(CopyStdioFormat::Text), options);
+
+        let copy = parse_copy_stdio("copy public.demo from stdin with (format csv, header true);")
+            .unwrap();
+        let mut expected = CopyStdioOptions::new(CopyStdioFormat::Csv);
+        expected.header = true;
+        assert_matches!(copy, CopyStdio::From { options, .. } if options == expected);
+
+        let copy =
+            parse_copy_stdio("COPY demo FROM STDIN WITH DELIMITER AS '|' NULL AS 'null' CSV")
+                .unwrap();
+        let mut expected = CopyStdioOptions::new(CopyStdioFormat::Csv);
+        expected.delimiter = b'|';
+        expected.null = "null".to_string();
+        assert_matches!(copy, CopyStdio::From { options, .. } if options == expected);
+    }
+
+    #[test]
+    fn test_parse_copy_to_stdout() {
+        let copy =
+            parse_copy_stdio("COPY (SELECT * FROM demo WHERE (cpu > 1)) TO STDOUT (FORMAT binary)")
+                .unwrap();
+        let CopyStdio::To { source, options } = copy else {
+            unreachable!()
+        };
+        assert_eq!("SELECT * FROM demo WHERE (cpu > 1)", source.to_query());
+        assert_eq!(CopyStdioOptions::new(CopyStdioFormat::Binary), options);
+
+        let copy = parse_copy_stdio("COPY demo (host) TO STDOUT").unwrap();
+        let CopyStdio::To { source, options } = copy else {
+            unreachable!()
+        };
+        assert_eq!("SELECT host FROM demo", source.to_query());
+        assert_eq!(CopyStdioOptions::new(CopyStdioFormat::Text), options);
+
+        let copy = parse_copy_stdio("COPY demo TO STDOUT").unwrap();
+        assert_matches!(copy, CopyStdio::To { source, .. } if source.to_query() == "SELECT * FROM demo");
+    }
+
+    #[test]
+    fn test_parse_copy_stdio_with_invalid_options() {
+        for sql in [
+            "COPY demo FROM STDIN (FORMAT json)",
+            "COPY demo FROM STDIN (HEADER)",
+            "COPY demo FROM STDIN (DELIMITER '||')",
+            "COPY demo FROM STDIN (FORCE_QUOTE *)",
+            "COPY demo FROM STDIN WITH CSV HEADER foo",
+            "COPY demo (host) TO 'demo.parquet'",
+            "COPY (SELECT 1) TO 'demo.parquet'",
+        ] {
+            assert!(parse_copy_stdio(sql).is_err(), "{sql}");
+        }
+    }
 }
@@ -32,8 +32,10 @@ use crate::error::{
 };
 use crate::parser::ParserContext;
 use crate::statements::create::{
-    CreateDatabase, CreateExternalTable, CreateTable, PartitionEntry, Partitions, TIME_INDEX,
+    CreateDatabase, CreateExternalTable, CreateMaterializedView, CreateTable, CreateView,
+    PartitionEntry, Partitions, TIME_INDEX,
 };
+use crate::statements::query::Query;
 use crate::statements::statement::Statement;
 use crate::statements::{sql_data_type_to_concrete_data_type, sql_value_to_value};
 use crate::util::parse_option_string;
@@ -55,6 +57,10 @@ impl<'a> ParserContext<'a> {
 
                 Keyword::EXTERNAL => self.parse_create_external_table(),
 
+                Keyword::MATERIALIZED => self.parse_create_materialized_view(),
+
+                Keyword::OR | Keyword::VIEW => self.parse_create_view(),
+
                 _ => self.unsupported(w.to_string()),
             },
             unexpected => self.unsupported(unexpected.to_string()),
@@ -100,6 +106,83 @@ impl<'a> ParserContext<'a> {
         }))
     }
 
+    fn parse_create_materialized_view(&mut self) -> Result<Statement> {
+        self.parser.next_token();
+        self.parser
+            .expect_keyword(Keyword::VIEW)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+
+        let view_name = self
+            .parser
+            .parse_object_name()
+            .context(error::UnexpectedSnafu {
+                sql: self.sql,
+                expected: "a view name",
+                actual: self.peek_token_as_string(),
+            })?;
+
+        let options = self
+            .parser
+            .parse_options(Keyword::WITH)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        self.parser
+            .expect_keyword(Keyword::AS)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        let query = self
+            .parser
+            .parse_query()
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        Ok(Statement::CreateMaterializedView(CreateMaterializedView {
+            name: view_name,
+            if_not_exists,
+            options,
+            query: Box::new(Query::try_from(query)?),
+        }))
+    }
+
+    fn parse_create_view(&mut self) -> Result<Statement> {
+        let or_replace = self.parser.parse_keywords(&[Keyword::OR, Keyword::REPLACE]);
+        self.parser
+            .expect_keyword(Keyword::VIEW)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+
+        let view_name = self
+            .parser
+            .parse_object_name()
+            .context(error::UnexpectedSnafu {
+                sql: self.sql,
+                expected: "a view name",
+                actual: self.peek_token_as_string(),
+            })?;
+
+        self.parser
+            .expect_keyword(Keyword::AS)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        let query = self
+            .parser
+            .parse_query()
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        Ok(Statement::CreateView(CreateView {
+            name: view_name,
+            or_replace,
+            if_not_exists,
+            query: Box::new(Query::try_from(query)?),
+        }))
+    }
+
     fn parse_create_database(&mut self) -> Result<Statement> {
         self.parser.next_token();
 
@@ -864,6 +947,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_create_materialized_view() {
+        let sql = "CREATE MATERIALIZED VIEW IF NOT EXISTS cpu_5m AS \
+                   SELECT host, date_bin(INTERVAL '5 minutes', ts) AS ts, avg(usage) AS usage \
+                   FROM cpu GROUP BY host, date_bin(INTERVAL '5 minutes', ts)";
+        let stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(1, stmts.len());
+        match &stmts[0] {
+            Statement::CreateMaterializedView(c) => {
+                assert_eq!(c.name.to_string(), "cpu_5m");
+                assert!(c.if_not_exists);
+                assert!(c.options.is_empty());
+                assert!(c.query.inner.to_string().starts_with("SELECT host"));
+            }
+            _ => unreachable!(),
+        }
+
+        let sql = "CREATE MATERIALIZED VIEW cpu_5m WITH (allowed_lateness = '1h') AS \
+                   SELECT date_bin('5m', ts) AS ts, avg(usage) AS usage FROM cpu GROUP BY ts";
+        let stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        match &stmts[0] {
+            Statement::CreateMaterializedView(c) => {
+                assert_eq!(c.options.len(), 1);
+                assert_eq!(c.options[0].to_string(), "allowed_lateness = '1h'");
+            }
+            _ => unreachable!(),
+        }
+
+        let sql = "CREATE MATERIALIZED VIEW cpu_5m SELECT * FROM cpu";
+        assert!(ParserContext::create_with_dialect(sql, &GenericDialect {}).is_err());
+    }
+
+    #[test]
+    fn test_parse_create_view() {
+        let sql = "CREATE VIEW IF NOT EXISTS busy_hosts AS SELECT host FROM cpu WHERE usage > 90";
+        let stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(1, stmts.len());
+        match &stmts[0] {
+            Statement::CreateView(c) => {
+                assert_eq!(c.name.to_string(), "busy_hosts");
+                assert!(!c.or_replace);
+                assert!(c.if_not_exists);
+                assert_eq!(
+                    c.query.inner.to_string(),
+                    "SELECT host FROM cpu WHERE usage > 90"
+                );
+            }
+            _ => unreachable!(),
+        }
+
+        let sql = "CREATE OR REPLACE VIEW public.busy_hosts AS SELECT host FROM cpu";
+        let stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        match &stmts[0] {
+            Statement::CreateView(c) => {
+                assert_eq!(c.name.to_string(), "public.busy_hosts");
+                assert!(c.or_replace);
+                assert!(!c.if_not_exists);
+            }
+            _ => unreachable!(),
+        }
+
+        let sql = "CREATE OR VIEW busy_hosts AS SELECT host FROM cpu";
+        assert!(ParserContext::create_with_dialect(sql, &GenericDialect {}).is_err());
+        let sql = "CREATE VIEW busy_hosts SELECT host FROM cpu";
+        assert!(ParserContext::create_with_dialect(sql, &GenericDialect {}).is_err());
+    }
+
     #[test]
     fn test_parse_create_database() {
         let sql = "create database";
@@ -0,0 +1,110 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snafu::{ensure, ResultExt};
+
+use crate::error::{self, InvalidSqlSnafu, Result};
+use crate::parser::ParserContext;
+use crate::statements::kill::{Kill, KillKind};
+use crate::statements::statement::Statement;
+
+pub const KILL: &str = "KILL";
+
+/// KILL statement parser implementation
+impl<'a> ParserContext<'a> {
+    pub(crate) fn parse_kill(&mut self) -> Result<Statement> {
+        self.parser.next_token();
+
+        let kind = if self.consume_token("QUERY") {
+            KillKind::Query
+        } else {
+            let _ = self.consume_token("CONNECTION");
+            KillKind::Connection
+        };
+
+        let id = self
+            .parser
+            .parse_literal_uint()
+            .with_context(|_| error::UnexpectedSnafu {
+                sql: self.sql,
+                expected: "a connection id",
+                actual: self.peek_token_as_string(),
+            })?;
+        ensure!(
+            id <= u32::MAX as u64,
+            InvalidSqlSnafu {
+                msg: format!("invalid connection id: {id}"),
+            }
+        );
+
+        Ok(Statement::Kill(Kill {
+            kind,
+            id: id as u32,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::dialect::GenericDialect;
+
+    use super::*;
+
+    fn parse_kill(sql: &str) -> Kill {
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(1, stmts.len());
+        match stmts.remove(0) {
+            Statement::Kill(kill) => kill,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_kill() {
+        assert_eq!(
+            Kill {
+                kind: KillKind::Query,
+                id: 42
+            },
+            parse_kill("KILL QUERY 42")
+        );
+        assert_eq!(
+            Kill {
+                kind: KillKind::Connection,
+                id: 42
+            },
+            parse_kill("kill connection 42;")
+        );
+        assert_eq!(
+            Kill {
+                kind: KillKind::Connection,
+                id: 7
+            },
+            parse_kill("KILL 7")
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_kill() {
+        for sql in [
+            "KILL",
+            "KILL QUERY",
+            "KILL QUERY abc",
+            "KILL QUERY 99999999999",
+        ] {
+            let result = ParserContext::create_with_dialect(sql, &GenericDialect {});
+            assert!(result.is_err(), "sql: {sql}, result: {result:?}");
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snafu::{ensure, ResultExt};
+use sqlparser::keywords::Keyword;
+use sqlparser::parser::IsOptional::Optional;
+
+use crate::error::{self, InvalidSqlSnafu, Result};
+use crate::parser::ParserContext;
+use crate::statements::load_data::{FieldsOptions, LinesOptions, LoadData};
+use crate::statements::statement::Statement;
+
+pub const LOAD: &str = "LOAD";
+
+/// LOAD DATA statement parser implementation
+impl<'a> ParserContext<'a> {
+    pub(crate) fn parse_load_data(&mut self) -> Result<Statement> {
+        self.parser.next_token();
+        self.expect_token("DATA")?;
+        let local = self.consume_token("LOCAL");
+        self.expect_token("INFILE")?;
+
+        let file_name = self.parse_option_string("a file name")?;
+
+        self.parser
+            .expect_keywords(&[Keyword::INTO, Keyword::TABLE])
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+        let table_name =
+            self.parser
+                .parse_object_name()
+                .with_context(|_| error::UnexpectedSnafu {
+                    sql: self.sql,
+                    expected: "a table name",
+                    actual: self.peek_token_as_string(),
+                })?;
+
+        let fields = self.parse_fields_options()?;
+        let lines = self.parse_lines_options()?;
+
+        let ignore_lines = if self.consume_token("IGNORE") {
+            let n = self
+                .parser
+                .parse_literal_uint()
+                .with_context(|_| error::UnexpectedSnafu {
+                    sql: self.sql,
+                    expected: "the number of lines to ignore",
+                    actual: self.peek_token_as_string(),
+                })?;
+            if !self.consume_token("LINES") {
+                self.expect_token("ROWS")?;
+            }
+            n
+        } else {
+            0
+        };
+
+        let columns = self
+            .parser
+            .parse_parenthesized_column_list(Optional, false)
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+
+        Ok(Statement::LoadData(LoadData {
+            local,
+            file_name,
+            table_name,
+            fields,
+            lines,
+            ignore_lines,
+            columns,
+        }))
+    }
+
+    fn parse_fields_options(&mut self) -> Result<FieldsOptions> {
+        let mut options = FieldsOptions::default();
+        if !(self.consume_token("FIELDS") || self.consume_token("COLUMNS")) {
+            return Ok(options);
+        }
+
+        let mut parsed = false;
+        loop {
+            if self.consume_token("TERMINATED") {
+                self.expect_token("BY")?;
+                options.terminated_by = self.parse_option_string("a field terminator")?;
+                ensure!(
+                    !options.terminated_by.is_empty(),
+                    InvalidSqlSnafu {
+                        msg: "FIELDS TERMINATED BY must not be empty",
+                    }
+                );
+            } else if self.consume_token("ENCLOSED") {
+                self.expect_token("BY")?;
+                options.enclosed_by = self.parse_option_char("ENCLOSED BY")?;
+            } else if self.consume_token("OPTIONALLY") {
+                // OPTIONALLY only matters to the output, which is the same as ENCLOSED here.
+                self.expect_token("ENCLOSED")?;
+                self.expect_token("BY")?;
+                options.enclosed_by = self.parse_option_char("ENCLOSED BY")?;
+            } else if self.consume_token("ESCAPED") {
+                self.expect_token("BY")?;
+                options.escaped_by = self.parse_option_char("ESCAPED BY")?;
+            } else {
+                break;
+            }
+            parsed = true;
+        }
+        ensure!(
+            parsed,
+            InvalidSqlSnafu {
+                msg: "FIELDS requires at least one of TERMINATED BY, ENCLOSED BY or ESCAPED BY",
+            }
+        );
+        Ok(options)
+    }
+
+    fn parse_lines_options(&mut self) -> Result<LinesOptions> {
+        let mut options = LinesOptions::default();
+        if !self.consume_token("LINES") {
+            return Ok(options);
+        }
+
+        let mut parsed = false;
+        loop {
+            if self.consume_token("STARTING") {
+                self.expect_token("BY")?;
+                options.starting_by = self.parse_option_string("a line prefix")?;
+            } else if self.consume_token("TERMINATED") {
+                self.expect_token("BY")?;
+                options.terminated_by = self.parse_option_string("a line terminator")?;
+                ensure!(
+                    !options.terminated_by.is_empty(),
+                    InvalidSqlSnafu {
+                        msg: "LINES TERMINATED BY must not be empty",
+                    }
+                );
+            } else {
+                break;
+            }
+            parsed = true;
+        }
+        ensure!(
+            parsed,
+            InvalidSqlSnafu {
+                msg: "LINES requires at least one of STARTING BY or TERMINATED BY",
+            }
+        );
+        Ok(options)
+    }
+
+    /// Parses a string literal with the backslash escapes of MySQL, like `'\t'`.
+    fn parse_option_string(&mut self, expected: &str) -> Result<String> {
+        let s = self
+            .parser
+            .parse_literal_string()
+            .with_context(|_| error::UnexpectedSnafu {
+                sql: self.sql,
+                expected,
+                actual: self.peek_token_as_string(),
+            })?;
+        Ok(unescape(&s))
+    }
+
+    /// Parses a single ASCII character, or none for an empty string.
+    fn parse_option_char(&mut self, option: &str) -> Result<Option<char>> {
+        let s = self.parse_option_string("a character")?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Ok(None),
+            (Some(c), None) if c.is_ascii() => Ok(Some(c)),
+            _ => InvalidSqlSnafu {
+                msg: format!("{option} must be a single ASCII character, found: '{s}'"),
+            }
+            .fail(),
+        }
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('0') => unescaped.push('\0'),
+            Some('b') => unescaped.push('\x08'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('Z') => unescaped.push('\x1a'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::ast::{Ident, ObjectName};
+    use sqlparser::dialect::GenericDialect;
+
+    use super::*;
+
+    fn parse_load_data(sql: &str) -> LoadData {
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(1, stmts.len());
+        match stmts.remove(0) {
+            Statement::LoadData(load_data) => load_data,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_load_data() {
+        assert_eq!(
+            LoadData {
+                local: false,
+                file_name: "/tmp/demo.tsv".to_string(),
+                table_name: ObjectName(vec![Ident::new("demo")]),
+                fields: FieldsOptions::default(),
+                lines: LinesOptions::default(),
+                ignore_lines: 0,
+                columns: vec![],
+            },
+            parse_load_data("LOAD DATA INFILE '/tmp/demo.tsv' INTO TABLE demo")
+        );
+
+        let sql = r#"load data local infile 'demo.csv' into table public.demo
+            fields terminated by ',' optionally enclosed by '"' escaped by ''
+            lines starting by 'xxx' terminated by '\r\n'
+            ignore 1 lines (host, cpu, ts)"#;
+        assert_eq!(
+            LoadData {
+                local: true,
+                file_name: "demo.csv".to_string(),
+                table_name: ObjectName(vec![Ident::new("public"), Ident::new("demo")]),
+                fields: FieldsOptions {
+                    terminated_by: ",".to_string(),
+                    enclosed_by: Some('"'),
+                    escaped_by: None,
+                },
+                lines: LinesOptions {
+                    starting_by: "xxx".to_string(),
+                    terminated_by: "\r\n".to_string(),
+                },
+                ignore_lines: 1,
+                columns: vec![Ident::new("host"), Ident::new("cpu"), Ident::new("ts")],
+            },
+            parse_load_data(sql)
+        );
+
+        let load_data = parse_load_data(
+            r"LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo
+            COLUMNS ESCAPED BY '\\' IGNORE 2 ROWS",
+        );
+        assert_eq!(Some('\\'), load_data.fields.escaped_by);
+        assert_eq!(2, load_data.ignore_lines);
+    }
+
+    #[test]
+    fn test_parse_load_data_error() {
+        for sql in [
+            "LOAD INFILE 'demo.csv' INTO TABLE demo",
+            "LOAD DATA LOCAL INFILE demo INTO TABLE demo",
+            "LOAD DATA LOCAL INFILE 'demo.csv' INTO demo",
+            "LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo FIELDS",
+            "LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo FIELDS TERMINATED BY ''",
+            "LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo FIELDS ENCLOSED BY '||'",
+            "LOAD DATA LOCAL INFILE 'demo.csv' INTO TABLE demo IGNORE 1",
+        ] {
+            assert!(
+                ParserContext::create_with_dialect(sql, &GenericDialect {}).is_err(),
+                "{sql}"
+            );
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use snafu::ResultExt;
+use sqlparser::ast::Statement as SpStatement;
+
+use crate::error::{self, Result};
+use crate::parser::ParserContext;
+use crate::statements::set_variables::SetVariables;
+use crate::statements::statement::Statement;
+
+/// SET variables statement parser implementation
+impl<'a> ParserContext<'a> {
+    pub(crate) fn parse_set_variables(&mut self) -> Result<Statement> {
+        self.parser.next_token();
+        let spstatement = self
+            .parser
+            .parse_set()
+            .context(error::SyntaxSnafu { sql: self.sql })?;
+        match spstatement {
+            SpStatement::SetVariable {
+                variable, value, ..
+            } => Ok(Statement::SetVariables(SetVariables { variable, value })),
+            unexp => error::UnsupportedSnafu {
+                sql: self.sql.to_string(),
+                keyword: unexp.to_string(),
+            }
+            .fail(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlparser::ast::{Expr, Ident, ObjectName, Value};
+    use sqlparser::dialect::GenericDialect;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_set_variables() {
+        let sql = "SET statement_timeout = 1000";
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(1, stmts.len());
+        assert_eq!(
+            Statement::SetVariables(SetVariables {
+                variable: ObjectName(vec![Ident::new("statement_timeout")]),
+                value: vec![Expr::Value(Value::Number("1000".to_string(), false))],
+            }),
+            stmts.remove(0)
+        );
+
+        let sql = "SET query_memory_limit TO '1GB'";
+        let mut stmts = ParserContext::create_with_dialect(sql, &GenericDialect {}).unwrap();
+        assert_eq!(
+            Statement::SetVariables(SetVariables {
+                variable: ObjectName(vec![Ident::new("query_memory_limit")]),
+                value: vec![Expr::Value(Value::SingleQuotedString("1GB".to_string()))],
+            }),
+            stmts.remove(0)
+        );
+    }
+}
@@ -20,7 +20,10 @@ pub mod describe;
 pub mod drop;
 pub mod explain;
 pub mod insert;
+pub mod kill;
+pub mod load_data;
 pub mod query;
+pub mod set_variables;
 pub mod show;
 pub mod statement;
 pub mod tql;
@@ -14,7 +14,7 @@
 
 use std::collections::HashMap;
 
-use sqlparser::ast::ObjectName;
+use sqlparser::ast::{Ident, ObjectName, Query as SpQuery};
 
 use crate::error::{self, Result};
 
@@ -49,3 +49,90 @@ impl TryFrom<String> for Format {
         error::UnsupportedCopyFormatOptionSnafu { name }.fail()
     }
 }
+
+/// `COPY <table> [(<column>, ...)] FROM STDIN`, `COPY <table> [(<column>, ...)] TO STDOUT` or
+/// `COPY (<query>) TO STDOUT` of PostgreSQL, whose data is transferred in the COPY
+/// sub-protocol instead of files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyStdio {
+    From {
+        table_name: ObjectName,
+        /// The columns to insert, or all columns of the table if empty.
+        columns: Vec<Ident>,
+        options: CopyStdioOptions,
+    },
+    To {
+        source: CopyStdioSource,
+        options: CopyStdioOptions,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyStdioSource {
+    Table {
+        table_name: ObjectName,
+        /// The columns to copy, or all columns of the table if empty.
+        columns: Vec<Ident>,
+    },
+    Query(Box<SpQuery>),
+}
+
+impl CopyStdioSource {
+    /// The query that produces the data to copy.
+    pub fn to_query(&self) -> String {
+        match self {
+            CopyStdioSource::Table {
+                table_name,
+                columns,
+            } if columns.is_empty() => format!("SELECT * FROM {table_name}"),
+            CopyStdioSource::Table {
+                table_name,
+                columns,
+            } => format!(
+                "SELECT {} FROM {table_name}",
+                columns
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            CopyStdioSource::Query(query) => query.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyStdioFormat {
+    Text,
+    Csv,
+    Binary,
+}
+
+/// `WITH (FORMAT <format>, DELIMITER '<char>', NULL '<string>', HEADER [<bool>])`, or the
+/// legacy `[WITH] [BINARY] [DELIMITER [AS] '<char>'] [NULL [AS] '<string>'] [CSV [HEADER]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyStdioOptions {
+    pub format: CopyStdioFormat,
+    pub delimiter: u8,
+    /// The string that represents a null value, `\N` in `text` and an unquoted empty string
+    /// in `csv` by default.
+    pub null: String,
+    /// Whether the first line of `csv` is the header.
+    pub header: bool,
+}
+
+impl CopyStdioOptions {
+    /// The default options of the format.
+    pub fn new(format: CopyStdioFormat) -> Self {
+        let (delimiter, null) = match format {
+            CopyStdioFormat::Csv => (b',', ""),
+            CopyStdioFormat::Text | CopyStdioFormat::Binary => (b'\t', "\\N"),
+        };
+        CopyStdioOptions {
+            format,
+            delimiter,
+            null: null.to_string(),
+            header: false,
+        }
+    }
+}
@@ -15,6 +15,7 @@
 use std::collections::HashMap;
 
 use crate::ast::{ColumnDef, Ident, ObjectName, SqlOption, TableConstraint, Value as SqlValue};
+use crate::statements::query::Query;
 
 /// Time index name, used in table constraints.
 pub const TIME_INDEX: &str = "__time_index";
@@ -53,6 +54,31 @@ pub struct CreateDatabase {
     pub if_not_exists: bool,
 }
 
+/// `CREATE MATERIALIZED VIEW`, whose result is kept in a table of the same name and
+/// maintained from the data newly written to the source table.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CreateMaterializedView {
+    /// View name
+    pub name: ObjectName,
+    /// Create if not exists
+    pub if_not_exists: bool,
+    /// Options in `WITH (...)`, e.g. `allowed_lateness`
+    pub options: Vec<SqlOption>,
+    pub query: Box<Query>,
+}
+
+/// `CREATE [OR REPLACE] VIEW`, the view is expanded into its query when it's queried.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CreateView {
+    /// View name
+    pub name: ObjectName,
+    /// Replace the view if it exists
+    pub or_replace: bool,
+    /// Create if not exists
+    pub if_not_exists: bool,
+    pub query: Box<Query>,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CreateExternalTable {
     /// Table name
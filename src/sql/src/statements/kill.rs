@@ -0,0 +1,32 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// What a `KILL` statement terminates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillKind {
+    /// `KILL QUERY <id>`, terminates the statement the connection is executing, including its
+    /// parts on the datanodes.
+    Query,
+    /// `KILL [CONNECTION] <id>`, terminates the connection in MySQL. Only its statement is
+    /// terminated like `KILL QUERY`, the connection stays open.
+    Connection,
+}
+
+/// SQL structure for `KILL [CONNECTION | QUERY] <id>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kill {
+    pub kind: KillKind,
+    /// The connection id as listed by `SHOW PROCESSLIST`.
+    pub id: u32,
+}
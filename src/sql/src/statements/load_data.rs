@@ -0,0 +1,68 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sqlparser::ast::{Ident, ObjectName};
+
+/// SQL structure for MySQL
+/// `LOAD DATA [LOCAL] INFILE 'file' INTO TABLE <table> [FIELDS ...] [LINES ...]
+/// [IGNORE <n> {LINES | ROWS}] [(<column>, ...)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadData {
+    /// Whether the file is read by the client and sent to the server.
+    pub local: bool,
+    pub file_name: String,
+    pub table_name: ObjectName,
+    pub fields: FieldsOptions,
+    pub lines: LinesOptions,
+    /// The number of lines skipped at the beginning of the file, like the header.
+    pub ignore_lines: u64,
+    /// The columns to load, or all columns of the table if empty.
+    pub columns: Vec<Ident>,
+}
+
+/// `{FIELDS | COLUMNS} [TERMINATED BY 'string'] [[OPTIONALLY] ENCLOSED BY 'char']
+/// [ESCAPED BY 'char']`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldsOptions {
+    pub terminated_by: String,
+    pub enclosed_by: Option<char>,
+    /// The escape character, which is absent if `ESCAPED BY ''`.
+    pub escaped_by: Option<char>,
+}
+
+impl Default for FieldsOptions {
+    fn default() -> Self {
+        FieldsOptions {
+            terminated_by: "\t".to_string(),
+            enclosed_by: None,
+            escaped_by: Some('\\'),
+        }
+    }
+}
+
+/// `LINES [STARTING BY 'string'] [TERMINATED BY 'string']`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinesOptions {
+    pub starting_by: String,
+    pub terminated_by: String,
+}
+
+impl Default for LinesOptions {
+    fn default() -> Self {
+        LinesOptions {
+            starting_by: String::new(),
+            terminated_by: "\n".to_string(),
+        }
+    }
+}
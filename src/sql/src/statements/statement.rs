@@ -17,15 +17,22 @@ use sqlparser::ast::Statement as SpStatement;
 
 use crate::error::{ConvertToDfStatementSnafu, Error};
 use crate::statements::alter::AlterTable;
-use crate::statements::copy::CopyTable;
-use crate::statements::create::{CreateDatabase, CreateExternalTable, CreateTable};
+use crate::statements::copy::{CopyStdio, CopyTable};
+use crate::statements::create::{
+    CreateDatabase, CreateExternalTable, CreateMaterializedView, CreateTable, CreateView,
+};
 use crate::statements::delete::Delete;
 use crate::statements::describe::DescribeTable;
-use crate::statements::drop::DropTable;
+use crate::statements::drop::{DropMaterializedView, DropTable, DropView};
 use crate::statements::explain::Explain;
 use crate::statements::insert::Insert;
+use crate::statements::kill::Kill;
+use crate::statements::load_data::LoadData;
 use crate::statements::query::Query;
-use crate::statements::show::{ShowCreateTable, ShowDatabases, ShowTables};
+use crate::statements::set_variables::SetVariables;
+use crate::statements::show::{
+    ShowCreateTable, ShowCreateView, ShowDatabases, ShowProcesslist, ShowTables,
+};
 use crate::statements::tql::Tql;
 
 /// Tokens parsed by `DFParser` are converted into these values.
@@ -42,8 +49,16 @@ pub enum Statement {
     CreateTable(CreateTable),
     // CREATE EXTERNAL TABLE
     CreateExternalTable(CreateExternalTable),
+    // CREATE MATERIALIZED VIEW
+    CreateMaterializedView(CreateMaterializedView),
+    // CREATE [OR REPLACE] VIEW
+    CreateView(CreateView),
     // DROP TABLE
     DropTable(DropTable),
+    // DROP MATERIALIZED VIEW
+    DropMaterializedView(DropMaterializedView),
+    // DROP VIEW
+    DropView(DropView),
     // CREATE DATABASE
     CreateDatabase(CreateDatabase),
     /// ALTER TABLE
@@ -54,6 +69,10 @@ pub enum Statement {
     ShowTables(ShowTables),
     // SHOW CREATE TABLE
     ShowCreateTable(ShowCreateTable),
+    // SHOW CREATE VIEW
+    ShowCreateView(ShowCreateView),
+    // SHOW [FULL] PROCESSLIST
+    ShowProcesslist(ShowProcesslist),
     // DESCRIBE TABLE
     DescribeTable(DescribeTable),
     // EXPLAIN QUERY
@@ -61,7 +80,15 @@ pub enum Statement {
     Use(String),
     // COPY
     Copy(CopyTable),
+    // COPY ... {FROM STDIN | TO STDOUT}
+    CopyStdio(CopyStdio),
     Tql(Tql),
+    // KILL [CONNECTION | QUERY] <id>
+    Kill(Kill),
+    // SET VARIABLE
+    SetVariables(SetVariables),
+    // LOAD DATA [LOCAL] INFILE
+    LoadData(LoadData),
 }
 
 /// Comment hints from SQL.
@@ -39,13 +39,17 @@ use object_store::test_util::TempFolder;
 use object_store::ObjectStore;
 use once_cell::sync::OnceCell;
 use rand::Rng;
+use servers::flight_sql::FlightSqlServer;
 use servers::grpc::GrpcServer;
 use servers::http::{HttpOptions, HttpServerBuilder};
 use servers::metrics_handler::MetricsHandler;
+use servers::mysql::server::{MysqlServer, MysqlSpawnConfig, MysqlSpawnRef};
+use servers::postgres::PostgresServer;
 use servers::prom::PromServer;
 use servers::query_handler::grpc::ServerGrpcQueryHandlerAdaptor;
 use servers::query_handler::sql::ServerSqlQueryHandlerAdaptor;
 use servers::server::Server;
+use servers::tls::TlsOption;
 use servers::Mode;
 use snafu::ResultExt;
 use table::engine::{EngineContext, TableEngineRef};
@@ -358,11 +362,14 @@ pub async fn setup_grpc_server(
         .unwrap();
     instance.start().await.unwrap();
     let fe_instance_ref = Arc::new(fe_instance);
-    let fe_grpc_server = Arc::new(GrpcServer::new(
-        ServerGrpcQueryHandlerAdaptor::arc(fe_instance_ref),
-        None,
-        runtime,
-    ));
+    let fe_grpc_server = Arc::new(
+        GrpcServer::new(
+            ServerGrpcQueryHandlerAdaptor::arc(fe_instance_ref.clone()),
+            None,
+            runtime,
+        )
+        .with_bulk_insert_handler(fe_instance_ref),
+    );
     let grpc_server_clone = fe_grpc_server.clone();
 
     let fe_grpc_addr_clone = fe_grpc_addr.clone();
@@ -376,3 +383,141 @@ pub async fn setup_grpc_server(
 
     (fe_grpc_addr, guard, fe_grpc_server)
 }
+
+pub async fn setup_flight_sql_server(
+    store_type: StorageType,
+    name: &str,
+) -> (String, TestGuard, Arc<FlightSqlServer>) {
+    common_telemetry::init_default_ut_logging();
+
+    let (opts, guard) = create_tmp_dir_and_datanode_opts(store_type, name);
+    let instance = Arc::new(Instance::with_mock_meta_client(&opts).await.unwrap());
+
+    let runtime = Arc::new(
+        RuntimeBuilder::default()
+            .worker_threads(2)
+            .thread_name("flight-sql-handlers")
+            .build()
+            .unwrap(),
+    );
+
+    let fe_flight_sql_addr = format!("127.0.0.1:{}", get_port());
+
+    let fe_instance = FeInstance::try_new_standalone(instance.clone())
+        .await
+        .unwrap();
+    instance.start().await.unwrap();
+    let fe_flight_sql_server = Arc::new(FlightSqlServer::new(
+        ServerSqlQueryHandlerAdaptor::arc(Arc::new(fe_instance)),
+        None,
+        runtime,
+    ));
+    let flight_sql_server_clone = fe_flight_sql_server.clone();
+
+    let fe_flight_sql_addr_clone = fe_flight_sql_addr.clone();
+    tokio::spawn(async move {
+        let addr = fe_flight_sql_addr_clone.parse::<SocketAddr>().unwrap();
+        flight_sql_server_clone.start(addr).await.unwrap()
+    });
+
+    // wait for Flight SQL server to start
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    (fe_flight_sql_addr, guard, fe_flight_sql_server)
+}
+
+pub async fn setup_pg_server(
+    store_type: StorageType,
+    name: &str,
+) -> (String, TestGuard, Arc<PostgresServer>) {
+    common_telemetry::init_default_ut_logging();
+
+    let (opts, guard) = create_tmp_dir_and_datanode_opts(store_type, name);
+    let instance = Arc::new(Instance::with_mock_meta_client(&opts).await.unwrap());
+
+    let runtime = Arc::new(
+        RuntimeBuilder::default()
+            .worker_threads(2)
+            .thread_name("pg-handlers")
+            .build()
+            .unwrap(),
+    );
+
+    let fe_pg_addr = format!("127.0.0.1:{}", get_port());
+
+    let fe_instance = FeInstance::try_new_standalone(instance.clone())
+        .await
+        .unwrap();
+    instance.start().await.unwrap();
+    let fe_instance_ref = Arc::new(fe_instance);
+    let fe_pg_server = Arc::new(
+        PostgresServer::new(
+            ServerSqlQueryHandlerAdaptor::arc(fe_instance_ref.clone()),
+            TlsOption::default(),
+            runtime,
+            None,
+        )
+        .with_bulk_insert_handler(fe_instance_ref),
+    );
+    let fe_pg_server_clone = fe_pg_server.clone();
+
+    let fe_pg_addr_clone = fe_pg_addr.clone();
+    tokio::spawn(async move {
+        let addr = fe_pg_addr_clone.parse::<SocketAddr>().unwrap();
+        fe_pg_server_clone.start(addr).await.unwrap()
+    });
+
+    // wait for PostgreSQL server to start
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    (fe_pg_addr, guard, fe_pg_server)
+}
+
+pub async fn setup_mysql_server(
+    store_type: StorageType,
+    name: &str,
+) -> (String, TestGuard, Arc<Box<dyn Server>>) {
+    common_telemetry::init_default_ut_logging();
+
+    let (opts, guard) = create_tmp_dir_and_datanode_opts(store_type, name);
+    let instance = Arc::new(Instance::with_mock_meta_client(&opts).await.unwrap());
+
+    let runtime = Arc::new(
+        RuntimeBuilder::default()
+            .worker_threads(2)
+            .thread_name("mysql-handlers")
+            .build()
+            .unwrap(),
+    );
+
+    let fe_mysql_addr = format!("127.0.0.1:{}", get_port());
+
+    let fe_instance = FeInstance::try_new_standalone(instance.clone())
+        .await
+        .unwrap();
+    instance.start().await.unwrap();
+    let fe_instance_ref = Arc::new(fe_instance);
+    let fe_mysql_server = Arc::new(MysqlServer::create_server(
+        runtime,
+        Arc::new(
+            MysqlSpawnRef::new(
+                ServerSqlQueryHandlerAdaptor::arc(fe_instance_ref.clone()),
+                None,
+            )
+            .with_bulk_insert_handler(fe_instance_ref),
+        ),
+        Arc::new(MysqlSpawnConfig::new(false, None, false, true)),
+    ));
+    let fe_mysql_server_clone = fe_mysql_server.clone();
+
+    let fe_mysql_addr_clone = fe_mysql_addr.clone();
+    tokio::spawn(async move {
+        let addr = fe_mysql_addr_clone.parse::<SocketAddr>().unwrap();
+        fe_mysql_server_clone.start(addr).await.unwrap()
+    });
+
+    // wait for MySQL server to start
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    (fe_mysql_addr, guard, fe_mysql_server)
+}
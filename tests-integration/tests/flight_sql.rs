@@ -0,0 +1,386 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::sql::{
+    ActionCreatePreparedStatementRequest, ActionCreatePreparedStatementResult, Any,
+    CommandGetDbSchemas, CommandGetTables, CommandPreparedStatementQuery,
+    CommandPreparedStatementUpdate, CommandStatementQuery, CommandStatementUpdate,
+    DoPutUpdateResult, ProstMessageExt, TicketStatementQuery,
+};
+use arrow_flight::{Action, FlightData, FlightDescriptor, FlightInfo, Ticket};
+use common_grpc::flight::{FlightDecoder, FlightEncoder, FlightMessage};
+use common_recordbatch::{RecordBatch, RecordBatches};
+use datatypes::prelude::ConcreteDataType;
+use datatypes::schema::{ColumnSchema, Schema};
+use datatypes::vectors::{Float64Vector, Int64Vector, StringVector};
+use prost::Message;
+use servers::server::Server;
+use tests_integration::test_util::{setup_flight_sql_server, StorageType};
+use tonic::transport::Channel;
+
+#[macro_export]
+macro_rules! flight_sql_test {
+    ($service:ident, $($(#[$meta:meta])* $test:ident),*,) => {
+        paste::item! {
+            mod [<integration_flight_sql_ $service:lower _test>] {
+                $(
+                    #[tokio::test(flavor = "multi_thread")]
+                    $(
+                        #[$meta]
+                    )*
+                    async fn [< $test >]() {
+                        let store_type = tests_integration::test_util::StorageType::$service;
+                        if store_type.test_on() {
+                            let _ = $crate::flight_sql::$test(store_type).await;
+                        }
+
+                    }
+                )*
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! flight_sql_tests {
+    ($($service:ident),*) => {
+        $(
+            flight_sql_test!(
+                $service,
+
+                test_flight_sql_query,
+                test_flight_sql_multiple_statements,
+                test_flight_sql_prepared_parameters,
+                test_flight_sql_metadata,
+            );
+        )*
+    };
+}
+
+type Client = FlightServiceClient<Channel>;
+
+pub async fn test_flight_sql_query(store_type: StorageType) {
+    let (addr, mut guard, fe_flight_sql_server) =
+        setup_flight_sql_server(store_type, "flight_sql_query").await;
+    let mut client = Client::connect(format!("http://{addr}")).await.unwrap();
+
+    create_and_insert(&mut client).await;
+
+    let expected = "\
++-------+-----+---------------------+
+| host  | cpu | ts                  |
++-------+-----+---------------------+
+| host1 | 1.1 | 1970-01-01T00:00:01 |
+| host2 | 2.2 | 1970-01-01T00:00:02 |
++-------+-----+---------------------+";
+    let query = "SELECT * FROM demo ORDER BY host".to_string();
+
+    let command = CommandStatementQuery {
+        query: query.clone(),
+    };
+    let info = get_flight_info(&mut client, command.as_any()).await;
+    assert_eq!(expected, do_get(&mut client, info).await);
+
+    let result = create_prepared_statement(&mut client, query).await;
+    let command = CommandPreparedStatementQuery {
+        prepared_statement_handle: result.prepared_statement_handle,
+    };
+    let info = get_flight_info(&mut client, command.as_any()).await;
+    // Prepared statements are reusable.
+    assert_eq!(expected, do_get(&mut client, info.clone()).await);
+    assert_eq!(expected, do_get(&mut client, info).await);
+
+    let command = CommandStatementQuery {
+        query: "SELECT * FROM not_exist".to_string(),
+    };
+    let descriptor = FlightDescriptor::new_cmd(command.as_any().encode_to_vec());
+    assert!(client.get_flight_info(descriptor).await.is_err());
+
+    let _ = fe_flight_sql_server.shutdown().await;
+    guard.remove_all().await;
+}
+
+pub async fn test_flight_sql_multiple_statements(store_type: StorageType) {
+    let (addr, mut guard, fe_flight_sql_server) =
+        setup_flight_sql_server(store_type, "flight_sql_multiple_statements").await;
+    let mut client = Client::connect(format!("http://{addr}")).await.unwrap();
+
+    create_and_insert(&mut client).await;
+
+    let sql = "INSERT INTO demo VALUES ('host3', 3.3, 3000); DELETE FROM demo WHERE host = 'host1'";
+    let command = CommandStatementUpdate {
+        query: sql.to_string(),
+    };
+    let flight_data = FlightData {
+        flight_descriptor: Some(FlightDescriptor::new_cmd(command.as_any().encode_to_vec())),
+        ..Default::default()
+    };
+    assert!(client
+        .do_put(futures::stream::iter(vec![flight_data]))
+        .await
+        .is_err());
+
+    // The statement handle of a ticket is not trusted to be a single statement either.
+    let ticket = TicketStatementQuery {
+        statement_handle: format!("SELECT * FROM demo; {sql}").into_bytes().into(),
+    };
+    let ticket = Ticket {
+        ticket: ticket.as_any().encode_to_vec().into(),
+    };
+    assert!(client.do_get(ticket).await.is_err());
+
+    // None of the statements is executed.
+    let command = CommandStatementQuery {
+        query: "SELECT * FROM demo ORDER BY host".to_string(),
+    };
+    let info = get_flight_info(&mut client, command.as_any()).await;
+    let expected = "\
++-------+-----+---------------------+
+| host  | cpu | ts                  |
++-------+-----+---------------------+
+| host1 | 1.1 | 1970-01-01T00:00:01 |
+| host2 | 2.2 | 1970-01-01T00:00:02 |
++-------+-----+---------------------+";
+    assert_eq!(expected, do_get(&mut client, info).await);
+
+    let _ = fe_flight_sql_server.shutdown().await;
+    guard.remove_all().await;
+}
+
+pub async fn test_flight_sql_prepared_parameters(store_type: StorageType) {
+    let (addr, mut guard, fe_flight_sql_server) =
+        setup_flight_sql_server(store_type, "flight_sql_prepared_parameters").await;
+    let mut client = Client::connect(format!("http://{addr}")).await.unwrap();
+
+    create_and_insert(&mut client).await;
+
+    // Executes the prepared update once for every row of the parameters.
+    let sql = "INSERT INTO demo VALUES (?, ?, ?)".to_string();
+    let result = create_prepared_statement(&mut client, sql).await;
+    let schema = Arc::new(Schema::new(vec![
+        ColumnSchema::new("host", ConcreteDataType::string_datatype(), true),
+        ColumnSchema::new("cpu", ConcreteDataType::float64_datatype(), true),
+        ColumnSchema::new("ts", ConcreteDataType::int64_datatype(), true),
+    ]));
+    let parameters = RecordBatch::new(
+        schema,
+        vec![
+            Arc::new(StringVector::from(vec!["host3", "host'4"])) as _,
+            Arc::new(Float64Vector::from(vec![Some(3.3), None])) as _,
+            Arc::new(Int64Vector::from_vec(vec![3000, 4000])) as _,
+        ],
+    )
+    .unwrap();
+    let command = CommandPreparedStatementUpdate {
+        prepared_statement_handle: result.prepared_statement_handle,
+    };
+    let result = do_put_parameters(&mut client, command.as_any(), parameters)
+        .await
+        .message()
+        .await
+        .unwrap()
+        .unwrap();
+    let record_count = DoPutUpdateResult::decode(result.app_metadata.as_ref())
+        .unwrap()
+        .record_count;
+    assert_eq!(2, record_count);
+
+    // Binds the parameters to the prepared query before executing it.
+    let sql = "SELECT * FROM demo WHERE host = ? OR host = ? ORDER BY host".to_string();
+    let result = create_prepared_statement(&mut client, sql).await;
+    let schema = Arc::new(Schema::new(vec![
+        ColumnSchema::new("host1", ConcreteDataType::string_datatype(), true),
+        ColumnSchema::new("host2", ConcreteDataType::string_datatype(), true),
+    ]));
+    let parameters = RecordBatch::new(
+        schema,
+        vec![
+            Arc::new(StringVector::from(vec!["host1"])) as _,
+            Arc::new(StringVector::from(vec!["host'4"])) as _,
+        ],
+    )
+    .unwrap();
+    let command = CommandPreparedStatementQuery {
+        prepared_statement_handle: result.prepared_statement_handle,
+    };
+    let _ = do_put_parameters(&mut client, command.as_any(), parameters).await;
+    let info = get_flight_info(&mut client, command.as_any()).await;
+    let expected = "\
++--------+-----+---------------------+
+| host   | cpu | ts                  |
++--------+-----+---------------------+
+| host'4 |     | 1970-01-01T00:00:04 |
+| host1  | 1.1 | 1970-01-01T00:00:01 |
++--------+-----+---------------------+";
+    assert_eq!(expected, do_get(&mut client, info).await);
+
+    // The prepared query can't be executed without its parameters.
+    let command = CommandPreparedStatementQuery {
+        prepared_statement_handle: create_prepared_statement(
+            &mut client,
+            "SELECT * FROM demo WHERE host = ?".to_string(),
+        )
+        .await
+        .prepared_statement_handle,
+    };
+    let descriptor = FlightDescriptor::new_cmd(command.as_any().encode_to_vec());
+    assert!(client.get_flight_info(descriptor).await.is_err());
+
+    let _ = fe_flight_sql_server.shutdown().await;
+    guard.remove_all().await;
+}
+
+pub async fn test_flight_sql_metadata(store_type: StorageType) {
+    let (addr, mut guard, fe_flight_sql_server) =
+        setup_flight_sql_server(store_type, "flight_sql_metadata").await;
+    let mut client = Client::connect(format!("http://{addr}")).await.unwrap();
+
+    create_and_insert(&mut client).await;
+
+    let command = CommandGetDbSchemas {
+        catalog: None,
+        db_schema_filter_pattern: Some("pub%".to_string()),
+    };
+    let info = get_flight_info(&mut client, command.as_any()).await;
+    let expected = "\
++--------------+----------------+
+| catalog_name | db_schema_name |
++--------------+----------------+
+| greptime     | public         |
++--------------+----------------+";
+    assert_eq!(expected, do_get(&mut client, info).await);
+
+    let command = CommandGetTables {
+        catalog: None,
+        db_schema_filter_pattern: Some("public".to_string()),
+        table_name_filter_pattern: Some("dem_".to_string()),
+        table_types: vec![],
+        include_schema: false,
+    };
+    let info = get_flight_info(&mut client, command.as_any()).await;
+    let expected = "\
++--------------+----------------+------------+------------+
+| catalog_name | db_schema_name | table_name | table_type |
++--------------+----------------+------------+------------+
+| greptime     | public         | demo       | TABLE      |
++--------------+----------------+------------+------------+";
+    assert_eq!(expected, do_get(&mut client, info).await);
+
+    let _ = fe_flight_sql_server.shutdown().await;
+    guard.remove_all().await;
+}
+
+async fn create_and_insert(client: &mut Client) {
+    let sql = "CREATE TABLE demo(host STRING, cpu DOUBLE, ts TIMESTAMP TIME INDEX, \
+        PRIMARY KEY(host))";
+    assert_eq!(0, execute_update(client, sql).await);
+
+    let sql = "INSERT INTO demo VALUES ('host1', 1.1, 1000), ('host2', 2.2, 2000)";
+    assert_eq!(2, execute_update(client, sql).await);
+}
+
+async fn create_prepared_statement(
+    client: &mut Client,
+    query: String,
+) -> ActionCreatePreparedStatementResult {
+    let request = ActionCreatePreparedStatementRequest { query };
+    let action = Action {
+        r#type: "CreatePreparedStatement".to_string(),
+        body: request.as_any().encode_to_vec().into(),
+    };
+    let result = client
+        .do_action(action)
+        .await
+        .unwrap()
+        .into_inner()
+        .message()
+        .await
+        .unwrap()
+        .unwrap();
+    Any::decode(result.body.as_ref())
+        .unwrap()
+        .unpack::<ActionCreatePreparedStatementResult>()
+        .unwrap()
+        .unwrap()
+}
+
+async fn do_put_parameters(
+    client: &mut Client,
+    command: Any,
+    parameters: RecordBatch,
+) -> tonic::Streaming<arrow_flight::PutResult> {
+    let mut encoder = FlightEncoder::default();
+    let mut schema = encoder.encode(FlightMessage::Schema(parameters.schema.clone()));
+    schema.flight_descriptor = Some(FlightDescriptor::new_cmd(command.encode_to_vec()));
+    let batch = encoder.encode(FlightMessage::Recordbatch(parameters));
+    client
+        .do_put(futures::stream::iter(vec![schema, batch]))
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn execute_update(client: &mut Client, sql: &str) -> i64 {
+    let command = CommandStatementUpdate {
+        query: sql.to_string(),
+    };
+    let flight_data = FlightData {
+        flight_descriptor: Some(FlightDescriptor::new_cmd(command.as_any().encode_to_vec())),
+        ..Default::default()
+    };
+    let result = client
+        .do_put(futures::stream::iter(vec![flight_data]))
+        .await
+        .unwrap()
+        .into_inner()
+        .message()
+        .await
+        .unwrap()
+        .unwrap();
+    DoPutUpdateResult::decode(result.app_metadata.as_ref())
+        .unwrap()
+        .record_count
+}
+
+async fn get_flight_info(client: &mut Client, command: Any) -> FlightInfo {
+    let descriptor = FlightDescriptor::new_cmd(command.encode_to_vec());
+    client
+        .get_flight_info(descriptor)
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+async fn do_get(client: &mut Client, info: FlightInfo) -> String {
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+    let mut stream = client.do_get(ticket).await.unwrap().into_inner();
+
+    let mut decoder = FlightDecoder::default();
+    let mut schema = None;
+    let mut batches = vec![];
+    while let Some(flight_data) = stream.message().await.unwrap() {
+        match decoder.try_decode(flight_data).unwrap() {
+            FlightMessage::Schema(s) => schema = Some(s),
+            FlightMessage::Recordbatch(batch) => batches.push(batch),
+            FlightMessage::AffectedRows(_) => unreachable!(),
+        }
+    }
+    RecordBatches::try_new(schema.unwrap(), batches)
+        .unwrap()
+        .pretty_print()
+        .unwrap()
+}
@@ -11,6 +11,8 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::sync::Arc;
+
 use api::v1::alter_expr::Kind;
 use api::v1::column::SemanticType;
 use api::v1::{
@@ -19,7 +21,12 @@ use api::v1::{
 };
 use client::{Client, Database, DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME};
 use common_catalog::consts::{MIN_USER_TABLE_ID, MITO_ENGINE};
+use common_grpc::flight::SEMANTIC_TYPE_KEY;
 use common_query::Output;
+use common_recordbatch::{RecordBatch, RecordBatches};
+use datatypes::prelude::ConcreteDataType;
+use datatypes::schema::{ColumnSchema, Schema};
+use datatypes::vectors::{Float64Vector, StringVector, TimestampMillisecondVector};
 use servers::server::Server;
 use tests_integration::test_util::{setup_grpc_server, StorageType};
 
@@ -56,6 +63,7 @@ macro_rules! grpc_tests {
                 test_auto_create_table,
                 test_insert_and_select,
                 test_dbname,
+                test_bulk_insert,
             );
         )*
     };
@@ -263,6 +271,89 @@ async fn insert_and_assert(db: &Database) {
     }
 }
 
+pub async fn test_bulk_insert(store_type: StorageType) {
+    let (addr, mut guard, fe_grpc_server) = setup_grpc_server(store_type, "bulk_insert").await;
+
+    let grpc_client = Client::with_urls(vec![addr]);
+    let db = Database::new(DEFAULT_CATALOG_NAME, DEFAULT_SCHEMA_NAME, grpc_client);
+
+    let host = ColumnSchema::new("host", ConcreteDataType::string_datatype(), false)
+        .with_metadata([(SEMANTIC_TYPE_KEY.to_string(), "TAG".to_string())].into());
+    let cpu = ColumnSchema::new("cpu", ConcreteDataType::float64_datatype(), true);
+    let memory = ColumnSchema::new("memory", ConcreteDataType::float64_datatype(), true);
+    let ts = ColumnSchema::new(
+        "ts",
+        ConcreteDataType::timestamp_millisecond_datatype(),
+        false,
+    );
+
+    // The table is created by the first put.
+    let schema = Arc::new(Schema::new(vec![host.clone(), cpu, ts.clone()]));
+    let recordbatches = RecordBatches::try_new(
+        schema.clone(),
+        vec![
+            RecordBatch::new(
+                schema.clone(),
+                vec![
+                    Arc::new(StringVector::from(vec!["host1", "host2"])) as _,
+                    Arc::new(Float64Vector::from(vec![Some(0.31), None])) as _,
+                    Arc::new(TimestampMillisecondVector::from_vec(vec![100, 101])) as _,
+                ],
+            )
+            .unwrap(),
+            RecordBatch::new(
+                schema,
+                vec![
+                    Arc::new(StringVector::from(vec!["host3"])) as _,
+                    Arc::new(Float64Vector::from(vec![Some(0.41)])) as _,
+                    Arc::new(TimestampMillisecondVector::from_vec(vec![102])) as _,
+                ],
+            )
+            .unwrap(),
+        ],
+    )
+    .unwrap();
+    let result = db.bulk_insert("demo", recordbatches.as_stream()).await;
+    assert_eq!(result.unwrap(), 3);
+
+    // The new column is added by the second put.
+    let schema = Arc::new(Schema::new(vec![host, memory, ts]));
+    let recordbatches = RecordBatches::try_new(
+        schema.clone(),
+        vec![RecordBatch::new(
+            schema.clone(),
+            vec![
+                Arc::new(StringVector::from(vec!["host4"])) as _,
+                Arc::new(Float64Vector::from(vec![Some(0.3)])) as _,
+                Arc::new(TimestampMillisecondVector::from_vec(vec![103])) as _,
+            ],
+        )
+        .unwrap()],
+    )
+    .unwrap();
+    let result = db.bulk_insert("demo", recordbatches.as_stream()).await;
+    assert_eq!(result.unwrap(), 1);
+
+    let result = db
+        .sql("SELECT host, cpu, memory, ts FROM demo ORDER BY ts")
+        .await
+        .unwrap();
+    let Output::RecordBatches(recordbatches) = result else { unreachable!() };
+    let expected = "\
++-------+------+--------+-------------------------+
+| host  | cpu  | memory | ts                      |
++-------+------+--------+-------------------------+
+| host1 | 0.31 |        | 1970-01-01T00:00:00.100 |
+| host2 |      |        | 1970-01-01T00:00:00.101 |
+| host3 | 0.41 |        | 1970-01-01T00:00:00.102 |
+| host4 |      | 0.3    | 1970-01-01T00:00:00.103 |
++-------+------+--------+-------------------------+";
+    assert_eq!(recordbatches.pretty_print().unwrap(), expected);
+
+    let _ = fe_grpc_server.shutdown().await;
+    guard.remove_all().await;
+}
+
 fn testing_create_expr() -> CreateTableExpr {
     let column_defs = vec![
         ColumnDef {
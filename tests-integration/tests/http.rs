@@ -53,6 +53,7 @@ macro_rules! http_tests {
                 $service,
 
                 test_sql_api,
+                test_sql_api_formats,
                 test_prometheus_promql_api,
                 test_prom_http_api,
                 test_metrics_api,
@@ -266,6 +267,53 @@ pub async fn test_sql_api(store_type: StorageType) {
     guard.remove_all().await;
 }
 
+pub async fn test_sql_api_formats(store_type: StorageType) {
+    common_telemetry::init_default_ut_logging();
+    let (app, mut guard) = setup_test_http_app_with_frontend(store_type, "sql_api_formats").await;
+    let client = TestClient::new(app);
+
+    let res = client
+        .get("/v1/sql?sql=select * from numbers limit 3&format=csv")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("content-type").unwrap(), "text/csv");
+    assert_eq!(res.text().await, "number\n0\n1\n2\n");
+
+    let res = client
+        .get("/v1/sql?sql=select number, number + 1 as next from numbers limit 2&format=tsv")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "number\tnext\n0\t1\n1\t2\n");
+
+    let res = client
+        .get("/v1/sql?sql=select * from numbers limit 2&format=ndjson")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text().await, "{\"number\":0}\n{\"number\":1}\n");
+
+    // statements without record batches fall back to the JSON output
+    let res = client
+        .get("/v1/sql?sql=insert into demo values('host', 66.6, 1024, 0)&format=csv")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = serde_json::from_str::<JsonResponse>(&res.text().await).unwrap();
+    assert!(body.success());
+
+    let res = client
+        .get("/v1/sql?sql=select * from not_exist&format=csv")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let body = serde_json::from_str::<JsonResponse>(&res.text().await).unwrap();
+    assert!(!body.success());
+
+    guard.remove_all().await;
+}
+
 pub async fn test_prometheus_promql_api(store_type: StorageType) {
     common_telemetry::init_default_ut_logging();
     let (app, mut guard) = setup_test_http_app_with_frontend(store_type, "sql_api").await;
@@ -307,6 +355,45 @@ pub async fn test_prom_http_api(store_type: StorageType) {
         .await;
     assert_eq!(res.status(), StatusCode::OK);
 
+    // metadata
+    let res = client
+        .get("/api/v1/series?match%5B%5D=demo&start=0&end=100")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = serde_json::from_str::<serde_json::Value>(&res.text().await).unwrap();
+    assert_eq!(body, json!({"status": "success", "data": []}));
+    let res = client
+        .get("/api/v1/labels?match%5B%5D=demo&start=0&end=100")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = serde_json::from_str::<serde_json::Value>(&res.text().await).unwrap();
+    assert_eq!(
+        body,
+        json!({"status": "success", "data": ["__name__", "host"]})
+    );
+    let res = client
+        .post("/api/v1/label/host/values")
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body("match[]=demo%7Bhost%3D~%22.%2B%22%7D&start=0&end=100")
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = serde_json::from_str::<serde_json::Value>(&res.text().await).unwrap();
+    assert_eq!(body, json!({"status": "success", "data": []}));
+    let res = client.get("/api/v1/label/__name__/values").send().await;
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = serde_json::from_str::<serde_json::Value>(&res.text().await).unwrap();
+    let metric_names = body["data"].as_array().unwrap();
+    assert!(metric_names.contains(&json!("demo")));
+    assert!(!metric_names.contains(&json!("prometheus_metric_metadata")));
+    assert!(!metric_names.contains(&json!("prometheus_exemplars")));
+
+    // bad query
+    let res = client.get("/api/v1/query?query=demo%7B").send().await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
     guard.remove_all().await;
 }
 
@@ -12,10 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[macro_use]
+mod flight_sql;
 #[macro_use]
 mod grpc;
 #[macro_use]
 mod http;
+#[macro_use]
+mod sql;
 
+flight_sql_tests!(File, S3, Oss);
 grpc_tests!(File, S3, Oss);
 http_tests!(File, S3, Oss);
+sql_tests!(File, S3, Oss);
@@ -0,0 +1,194 @@
+// Copyright 2023 Greptime Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+use futures::{pin_mut, SinkExt, TryStreamExt};
+use mysql_async::prelude::Queryable;
+use mysql_async::{OptsBuilder, WhiteListFsHandler};
+use servers::server::Server;
+use tests_integration::test_util::{setup_mysql_server, setup_pg_server, StorageType};
+use tokio_postgres::{Client, NoTls, SimpleQueryMessage};
+
+#[macro_export]
+macro_rules! sql_test {
+    ($service:ident, $($(#[$meta:meta])* $test:ident),*,) => {
+        paste::item! {
+            mod [<integration_sql_ $service:lower _test>] {
+                $(
+                    #[tokio::test(flavor = "multi_thread")]
+                    $(
+                        #[$meta]
+                    )*
+                    async fn [< $test >]() {
+                        let store_type = tests_integration::test_util::StorageType::$service;
+                        if store_type.test_on() {
+                            let _ = $crate::sql::$test(store_type).await;
+                        }
+
+                    }
+                )*
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! sql_tests {
+    ($($service:ident),*) => {
+        $(
+            sql_test!(
+                $service,
+
+                test_postgres_copy,
+                test_mysql_load_data,
+            );
+        )*
+    };
+}
+
+pub async fn test_postgres_copy(store_type: StorageType) {
+    let (addr, mut guard, fe_pg_server) = setup_pg_server(store_type, "postgres_copy").await;
+    let client = connect_postgres(&addr).await;
+
+    client
+        .simple_query(
+            "CREATE TABLE demo(host STRING, cpu DOUBLE, ts TIMESTAMP TIME INDEX, \
+             PRIMARY KEY(host))",
+        )
+        .await
+        .unwrap();
+
+    let sink = client
+        .copy_in::<_, Bytes>("COPY demo (host, cpu, ts) FROM STDIN")
+        .await
+        .unwrap();
+    pin_mut!(sink);
+    sink.send(Bytes::from_static(
+        b"host1\t1.1\t1970-01-01T00:00:01Z\nhost2\t",
+    ))
+    .await
+    .unwrap();
+    sink.send(Bytes::from_static(b"\\N\t1970-01-01T00:00:02Z\n"))
+        .await
+        .unwrap();
+    assert_eq!(2, sink.finish().await.unwrap());
+
+    let rows = client
+        .query("SELECT host, cpu FROM demo ORDER BY host", &[])
+        .await
+        .unwrap();
+    let rows = rows
+        .iter()
+        .map(|row| (row.get::<_, String>(0), row.get::<_, Option<f64>>(1)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            ("host1".to_string(), Some(1.1)),
+            ("host2".to_string(), None)
+        ],
+        rows
+    );
+
+    let stream = client
+        .copy_out("COPY (SELECT host, cpu FROM demo ORDER BY host) TO STDOUT WITH (FORMAT csv)")
+        .await
+        .unwrap();
+    pin_mut!(stream);
+    let chunks = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(b"host1,1.1\nhost2,\n".to_vec(), chunks.concat());
+
+    // The failed COPY leaves the connection ready for the next query.
+    assert!(client
+        .copy_in::<_, Bytes>("COPY not_exist FROM STDIN")
+        .await
+        .is_err());
+    let messages = client.simple_query("SELECT * FROM demo").await.unwrap();
+    let rows = messages
+        .iter()
+        .filter(|message| matches!(message, SimpleQueryMessage::Row(_)))
+        .count();
+    assert_eq!(2, rows);
+
+    let _ = fe_pg_server.shutdown().await;
+    guard.remove_all().await;
+}
+
+pub async fn test_mysql_load_data(store_type: StorageType) {
+    let (addr, mut guard, fe_mysql_server) =
+        setup_mysql_server(store_type, "mysql_load_data").await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("demo.tsv");
+    std::fs::write(
+        &path,
+        "host1\t1.1\t1970-01-01T00:00:01Z\nhost2\t\\N\t1970-01-01T00:00:02Z\n",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+
+    let (host, port) = addr.split_once(':').unwrap();
+    let opts = OptsBuilder::default()
+        .ip_or_hostname(host)
+        .tcp_port(port.parse().unwrap())
+        .prefer_socket(false)
+        .db_name(Some("public"))
+        .local_infile_handler(Some(WhiteListFsHandler::new(&[path][..])));
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+
+    conn.query_drop(
+        "CREATE TABLE demo(host STRING, cpu DOUBLE, ts TIMESTAMP TIME INDEX, PRIMARY KEY(host))",
+    )
+    .await
+    .unwrap();
+
+    conn.query_drop(format!(
+        "LOAD DATA LOCAL INFILE '{path}' INTO TABLE demo (host, cpu, ts)"
+    ))
+    .await
+    .unwrap();
+    assert_eq!(2, conn.affected_rows());
+
+    let rows: Vec<(String, Option<f64>)> = conn
+        .query("SELECT host, cpu FROM demo ORDER BY host")
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![
+            ("host1".to_string(), Some(1.1)),
+            ("host2".to_string(), None)
+        ],
+        rows
+    );
+
+    // The failed LOAD DATA leaves the connection ready for the next query.
+    assert!(conn
+        .query_drop(format!(
+            "LOAD DATA LOCAL INFILE '{path}' INTO TABLE not_exist"
+        ))
+        .await
+        .is_err());
+    let count: Option<i64> = conn.query_first("SELECT COUNT(*) FROM demo").await.unwrap();
+    assert_eq!(Some(2), count);
+
+    let _ = fe_mysql_server.shutdown().await;
+    guard.remove_all().await;
+}
+
+async fn connect_postgres(addr: &str) -> Client {
+    let (host, port) = addr.split_once(':').unwrap();
+    let url = format!("host={host} port={port} connect_timeout=2 dbname=public");
+    let (client, conn) = tokio_postgres::connect(&url, NoTls).await.unwrap();
+    tokio::spawn(conn);
+    client
+}